@@ -0,0 +1,141 @@
+#ifndef NEXLIB_H
+#define NEXLIB_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of every fallible FFI call.
+ */
+typedef enum NexStatus {
+  NEX_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null.
+   */
+  NEX_STATUS_NULL_POINTER = 1,
+  /**
+   * No mount was found.
+   */
+  NEX_STATUS_NOT_FOUND = 2,
+  /**
+   * The mount sent a response that could not be understood.
+   */
+  NEX_STATUS_INVALID_DATA = 3,
+  /**
+   * The mount did not respond in time.
+   */
+  NEX_STATUS_TIMEOUT = 4,
+  /**
+   * The addressed device is unavailable or the command is not supported.
+   */
+  NEX_STATUS_NOT_CONNECTED = 5,
+  /**
+   * An argument was out of range.
+   */
+  NEX_STATUS_INVALID_INPUT = 6,
+  /**
+   * Any other I/O failure.
+   */
+  NEX_STATUS_IO = 7,
+  /**
+   * The library panicked; the handle should be destroyed.
+   */
+  NEX_STATUS_PANIC = 8,
+} NexStatus;
+
+/**
+ * Opaque handle to a connected mount.
+ */
+typedef struct NexMount NexMount;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns a static, human-readable description of `status`.
+ */
+const char *nex_status_message(enum NexStatus status);
+
+/**
+ * Connects to the first detected mount, storing a new handle in `out`.
+ *
+ * # Safety
+ *
+ * `out` must be null or valid for writes.
+ */
+enum NexStatus nex_mount_create(struct NexMount **out);
+
+/**
+ * Closes the connection and frees the handle. Passing null is a no-op.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a pointer returned by `nex_mount_create` which has not already been destroyed.
+ */
+void nex_mount_destroy(struct NexMount *handle);
+
+/**
+ * Gets the current position in right ascension and declination, in degrees.
+ *
+ * # Safety
+ *
+ * `handle` must be a live handle and `ra` and `dec` must be null or valid for writes.
+ */
+enum NexStatus nex_mount_get_position_ra_dec(struct NexMount *handle, double *ra, double *dec);
+
+/**
+ * Gets the current position in azimuth and elevation, in degrees.
+ *
+ * # Safety
+ *
+ * `handle` must be a live handle and `az` and `el` must be null or valid for writes.
+ */
+enum NexStatus nex_mount_get_position_az_el(struct NexMount *handle, double *az, double *el);
+
+/**
+ * Starts a goto to a right ascension and declination, in degrees.
+ *
+ * # Safety
+ *
+ * `handle` must be a live handle.
+ */
+enum NexStatus nex_mount_goto_ra_dec(struct NexMount *handle, double ra, double dec);
+
+/**
+ * Starts a goto to an azimuth and elevation, in degrees.
+ *
+ * # Safety
+ *
+ * `handle` must be a live handle.
+ */
+enum NexStatus nex_mount_goto_az_el(struct NexMount *handle, double az, double el);
+
+/**
+ * Writes whether a goto is currently in progress to `in_progress`.
+ *
+ * # Safety
+ *
+ * `handle` must be a live handle and `in_progress` must be null or valid for writes.
+ */
+enum NexStatus nex_mount_goto_in_progress(struct NexMount *handle, bool *in_progress);
+
+/**
+ * Cancels the goto in progress.
+ *
+ * # Safety
+ *
+ * `handle` must be a live handle.
+ */
+enum NexStatus nex_mount_cancel_goto(struct NexMount *handle);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NEXLIB_H */
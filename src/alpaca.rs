@@ -0,0 +1,734 @@
+//! ASCOM Alpaca server exposing a mount as an `ITelescope` device over HTTP.
+//!
+//! Alpaca is the network counterpart of the ASCOM COM interface: imaging suites such as NINA and SGP find the server
+//! by UDP discovery and drive it with plain HTTP requests, so they can use any [`Mount`] nexlib talks to, on any
+//! platform. The server presents a single telescope, device number 0, at `/api/v1/telescope/0/<member>` alongside the
+//! management API at `/management/...`.
+//!
+//! Like a COM driver, the device starts out disconnected and refuses to touch the mount until a client sets
+//! `Connected`. Every response is a JSON object carrying the client's `ClientTransactionID`, a server transaction ID
+//! counting up from 1, and an ASCOM error number and message, which are 0 and empty on success:
+//!
+//! ```json
+//! {"Value":5.5,"ClientTransactionID":12,"ServerTransactionID":34,"ErrorNumber":0,"ErrorMessage":""}
+//! ```
+
+use crate::mount::error::MountError;
+use crate::mount::tracking::TrackingMemory;
+use crate::mount::{Mount, SlewAxis, SlewDir, TrackingMode};
+use crate::{AzEl, RADec};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+/// Port Alpaca servers conventionally listen on.
+pub const DEFAULT_PORT: u16 = 11111;
+
+/// UDP port clients broadcast discovery requests to.
+pub const DISCOVERY_PORT: u16 = 32227;
+
+const DISCOVERY_REQUEST: &[u8] = b"alpacadiscovery1";
+
+/// Identifies the telescope to clients across restarts, so they remember its settings.
+const UNIQUE_ID: &str = "3f1c6f0e-8b5a-4d8e-9c41-6e2b7d0a5f93";
+
+const DESCRIPTION: &str = "nexlib telescope";
+
+const SLEW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Bodies longer than this are rejected rather than buffered.
+const MAX_BODY: usize = 64 * 1024;
+
+/// Longest request or header line accepted, in bytes.
+const MAX_LINE: usize = 8 * 1024;
+
+// ASCOM error numbers.
+pub const NOT_IMPLEMENTED: i32 = 0x400;
+pub const INVALID_VALUE: i32 = 0x401;
+pub const NOT_CONNECTED: i32 = 0x407;
+pub const DRIVER_ERROR: i32 = 0x500;
+
+/// An ASCOM error, reported to the client in the `ErrorNumber` and `ErrorMessage` fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlpacaError {
+    pub number: i32,
+    pub message: String,
+}
+
+impl AlpacaError {
+    fn new(number: i32, message: impl Into<String>) -> AlpacaError {
+        AlpacaError {
+            number,
+            message: message.into(),
+        }
+    }
+
+    fn not_implemented(member: &str) -> AlpacaError {
+        AlpacaError::new(NOT_IMPLEMENTED, format!("{member} is not implemented."))
+    }
+}
+
+impl From<MountError> for AlpacaError {
+    fn from(e: MountError) -> Self {
+        let number = match e.kind() {
+            io::ErrorKind::NotConnected => NOT_CONNECTED,
+            io::ErrorKind::Unsupported => NOT_IMPLEMENTED,
+            io::ErrorKind::InvalidInput => INVALID_VALUE,
+            _ => DRIVER_ERROR,
+        };
+        AlpacaError::new(number, e.to_string())
+    }
+}
+
+/// Why a request failed.
+#[derive(Debug)]
+enum Failure {
+    /// A parameter is missing or malformed, which Alpaca reports with HTTP 400 rather than an ASCOM error.
+    BadRequest(String),
+    Alpaca(AlpacaError),
+}
+
+impl<E: Into<AlpacaError>> From<E> for Failure {
+    fn from(e: E) -> Self {
+        Failure::Alpaca(e.into())
+    }
+}
+
+type Reply = Result<Value, Failure>;
+
+/// Request parameters, whose names Alpaca matches case-insensitively.
+#[derive(Debug, Default)]
+struct Params(HashMap<String, String>);
+
+impl Params {
+    /// Parses `application/x-www-form-urlencoded` pairs, as found in query strings and `PUT` bodies.
+    fn parse(&mut self, form: &str) {
+        for pair in form.split('&').filter(|p| !p.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            self.0
+                .insert(decode(name).to_ascii_lowercase(), decode(value));
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    fn require<T: std::str::FromStr>(&self, name: &str) -> Result<T, Failure> {
+        let value = self
+            .get(name)
+            .ok_or_else(|| Failure::BadRequest(format!("Missing parameter {name}.")))?;
+        value
+            .trim()
+            .parse()
+            .map_err(|_| Failure::BadRequest(format!("Invalid {name}: {value:?}.")))
+    }
+
+    /// Booleans are sent as `True` and `False`.
+    fn require_bool(&self, name: &str) -> Result<bool, Failure> {
+        self.require::<String>(name)
+            .and_then(|value| match value.to_ascii_lowercase().as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(Failure::BadRequest(format!("Invalid {name}: {value:?}."))),
+            })
+    }
+
+    /// Transaction IDs are optional, and a malformed one is treated as absent.
+    fn client_transaction_id(&self) -> u32 {
+        self.get("ClientTransactionID")
+            .and_then(|id| id.trim().parse().ok())
+            .unwrap_or(0)
+    }
+}
+
+/// Decodes `+` and `%XX` escapes.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => match bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(b) => {
+                    out.push(b);
+                    i += 2;
+                }
+                None => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Right ascension in hours and declination in degrees from the parameters of a slew or sync.
+fn ra_dec(params: &Params) -> Result<RADec, Failure> {
+    let ra: f64 = params.require("RightAscension")?;
+    let dec: f64 = params.require("Declination")?;
+    if !(0.0..24.0).contains(&ra) || !(-90.0..=90.0).contains(&dec) {
+        return Err(AlpacaError::new(
+            INVALID_VALUE,
+            format!("Coordinates out of range: RA {ra} h, Dec {dec}°."),
+        )
+        .into());
+    }
+    Ok(RADec::new(ra * 15.0, dec))
+}
+
+fn az_el(params: &Params) -> Result<AzEl, Failure> {
+    let az: f64 = params.require("Azimuth")?;
+    let alt: f64 = params.require("Altitude")?;
+    if !(0.0..360.0).contains(&az) || !(-90.0..=90.0).contains(&alt) {
+        return Err(AlpacaError::new(
+            INVALID_VALUE,
+            format!("Coordinates out of range: Az {az}°, Alt {alt}°."),
+        )
+        .into());
+    }
+    Ok(AzEl::new(az, alt))
+}
+
+fn axis(params: &Params) -> Result<Option<SlewAxis>, Failure> {
+    Ok(match params.require::<i32>("Axis")? {
+        0 => Some(SlewAxis::RAAz),
+        1 => Some(SlewAxis::DecEl),
+        _ => None,
+    })
+}
+
+/// Fastest rate accepted by `MoveAxis`, in degrees per second.
+fn max_axis_rate() -> f64 {
+    (u16::MAX / 4) as f64 / 3600.0
+}
+
+/// The telescope device and the state the server keeps for it.
+struct Device<M> {
+    mount: Arc<Mutex<M>>,
+    connected: AtomicBool,
+    server_transaction_id: AtomicU32,
+    tracking: Mutex<TrackingMemory>,
+}
+
+impl<M: Mount> Device<M> {
+    fn new(mount: Arc<Mutex<M>>) -> Device<M> {
+        Device {
+            mount,
+            connected: AtomicBool::new(false),
+            server_transaction_id: AtomicU32::new(0),
+            tracking: Mutex::default(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, M> {
+        self.mount.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn tracking_memory(&self) -> MutexGuard<'_, TrackingMemory> {
+        self.tracking.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn with_mount<T, F>(&self, f: F) -> Result<T, Failure>
+    where
+        F: FnOnce(&mut M) -> Result<T, MountError>,
+    {
+        if !self.connected.load(Ordering::SeqCst) {
+            return Err(AlpacaError::new(NOT_CONNECTED, "The telescope is not connected.").into());
+        }
+        Ok(f(&mut self.lock())?)
+    }
+
+    /// Waits for the goto in progress to finish, releasing the mount between polls.
+    fn wait_for_slew(&self) -> Result<(), Failure> {
+        while self.with_mount(|m| m.goto_in_progress())? {
+            thread::sleep(SLEW_POLL_INTERVAL);
+        }
+        Ok(())
+    }
+
+    fn get(&self, member: &str, params: &Params) -> Reply {
+        match member {
+            "connected" => Ok(self.connected.load(Ordering::SeqCst).into()),
+            "name" => Ok("nexlib".into()),
+            "description" => Ok(DESCRIPTION.into()),
+            "driverinfo" => {
+                Ok(format!("{DESCRIPTION} (nexlib {})", env!("CARGO_PKG_VERSION")).into())
+            }
+            "driverversion" => Ok(env!("CARGO_PKG_VERSION")
+                .rsplit_once('.')
+                .map_or(env!("CARGO_PKG_VERSION"), |(v, _)| v)
+                .into()),
+            "interfaceversion" => Ok(3.into()),
+            "supportedactions" => Ok(json!([])),
+            "rightascension" => {
+                Ok((self.with_mount(|m| m.get_position_ra_dec())?.ra / 15.0).into())
+            }
+            "declination" => Ok(self.with_mount(|m| m.get_position_ra_dec())?.dec.into()),
+            "azimuth" => Ok(self.with_mount(|m| m.get_position_az_el())?.az.into()),
+            "altitude" => Ok(self.with_mount(|m| m.get_position_az_el())?.el.into()),
+            "tracking" => {
+                let mode = self.with_mount(|m| m.get_tracking_mode())?;
+                self.tracking_memory().observe(mode);
+                Ok((mode != TrackingMode::Off).into())
+            }
+            "slewing" => Ok(self.with_mount(|m| m.goto_in_progress())?.into()),
+            // algAltAz = 0, algPolar = 1, algGermanPolar = 2.
+            "alignmentmode" => Ok(match self.with_mount(|m| m.get_tracking_mode())? {
+                TrackingMode::AzEl => 0,
+                _ => 2,
+            }
+            .into()),
+            // equTopocentric: the mount works in coordinates of date.
+            "equatorialsystem" => Ok(1.into()),
+            // driveSidereal.
+            "trackingrate" => Ok(0.into()),
+            "trackingrates" => Ok(json!([0])),
+            "utcdate" => Ok(self
+                .with_mount(|m| m.get_time())?
+                .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+                .to_string()
+                .into()),
+            "athome" | "atpark" | "ispulseguiding" => Ok(false.into()),
+            "canslew" | "canslewasync" | "cansync" | "cansettracking" | "canslewaltaz"
+            | "canslewaltazasync" => Ok(true.into()),
+            "canpark"
+            | "canunpark"
+            | "canfindhome"
+            | "canpulseguide"
+            | "cansetpark"
+            | "cansetpierside"
+            | "cansetguiderates"
+            | "cansetrightascensionrate"
+            | "cansetdeclinationrate"
+            | "cansyncaltaz" => Ok(false.into()),
+            "canmoveaxis" => Ok(axis(params)?.is_some().into()),
+            "axisrates" => Ok(match axis(params)? {
+                Some(_) => json!([{"Minimum": 0.0, "Maximum": max_axis_rate()}]),
+                None => json!([]),
+            }),
+            _ => Err(AlpacaError::not_implemented(member).into()),
+        }
+    }
+
+    fn put(&self, member: &str, params: &Params) -> Result<(), Failure> {
+        match member {
+            "connected" => {
+                let connected = params.require_bool("Connected")?;
+                self.connected.store(connected, Ordering::SeqCst);
+                Ok(())
+            }
+            "tracking" => {
+                let on = params.require_bool("Tracking")?;
+                self.with_mount(|m| self.tracking_memory().set_tracking(m, on))
+            }
+            "utcdate" => {
+                let date: String = params.require("UTCDate")?;
+                let time = chrono::DateTime::parse_from_rfc3339(date.trim())
+                    .map_err(|_| Failure::BadRequest(format!("Invalid UTCDate: {date:?}.")))?;
+                self.with_mount(|m| m.set_time(time.with_timezone(&chrono::Utc)))
+            }
+            "slewtocoordinates" | "slewtocoordinatesasync" => {
+                let coord = ra_dec(params)?;
+                self.with_mount(|m| m.goto_ra_dec(coord))?;
+                if member == "slewtocoordinates" {
+                    self.wait_for_slew()?;
+                }
+                Ok(())
+            }
+            "synctocoordinates" => {
+                let coord = ra_dec(params)?;
+                self.with_mount(|m| m.sync(coord))
+            }
+            "slewtoaltaz" | "slewtoaltazasync" => {
+                let coord = az_el(params)?;
+                self.with_mount(|m| m.goto_az_el(coord))?;
+                if member == "slewtoaltaz" {
+                    self.wait_for_slew()?;
+                }
+                Ok(())
+            }
+            "abortslew" => self.with_mount(|m| {
+                m.cancel_goto()?;
+                m.stop_slew(SlewAxis::RAAz)?;
+                m.stop_slew(SlewAxis::DecEl)
+            }),
+            // Rate in degrees per second; zero stops the axis.
+            "moveaxis" => {
+                let Some(axis) = axis(params)? else {
+                    return Err(AlpacaError::new(INVALID_VALUE, "Invalid axis.").into());
+                };
+                let rate: f64 = params.require("Rate")?;
+                if !rate.is_finite() || rate.abs() > max_axis_rate() {
+                    return Err(AlpacaError::new(
+                        INVALID_VALUE,
+                        format!("Rate {rate}°/s is too fast."),
+                    )
+                    .into());
+                }
+                let arcsec = (rate.abs() * 3600.0).round() as u16;
+
+                self.with_mount(|m| {
+                    if arcsec == 0 {
+                        m.stop_slew(axis)
+                    } else {
+                        let dir = if rate > 0.0 {
+                            SlewDir::Positive
+                        } else {
+                            SlewDir::Negative
+                        };
+                        m.slew_variable(axis, dir, arcsec)
+                    }
+                })
+            }
+            _ => Err(AlpacaError::not_implemented(member).into()),
+        }
+    }
+
+    /// Answers a request for `path`, returning the HTTP status and body.
+    fn respond(&self, method: &str, path: &str, params: &Params) -> (u16, String) {
+        let path = path.to_ascii_lowercase();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        let reply = match (method, segments.as_slice()) {
+            ("GET", ["management", "apiversions"]) => Ok(json!([1])),
+            ("GET", ["management", "v1", "description"]) => Ok(json!({
+                "ServerName": "nexlib",
+                "Manufacturer": "nexlib",
+                "ManufacturerVersion": env!("CARGO_PKG_VERSION"),
+                "Location": "",
+            })),
+            ("GET", ["management", "v1", "configureddevices"]) => Ok(json!([{
+                "DeviceName": DESCRIPTION,
+                "DeviceType": "Telescope",
+                "DeviceNumber": 0,
+                "UniqueID": UNIQUE_ID,
+            }])),
+            ("GET", ["api", "v1", "telescope", "0", member]) => self.get(member, params),
+            ("PUT", ["api", "v1", "telescope", "0", member]) => {
+                self.put(member, params).map(|()| Value::Null)
+            }
+            _ => return (404, format!("No such endpoint: {method} {path}")),
+        };
+
+        let mut body = json!({
+            "ClientTransactionID": params.client_transaction_id(),
+            "ServerTransactionID": self.server_transaction_id.fetch_add(1, Ordering::SeqCst) + 1,
+            "ErrorNumber": 0,
+            "ErrorMessage": "",
+        });
+        match reply {
+            Ok(Value::Null) if method == "PUT" => (),
+            Ok(value) => body["Value"] = value,
+            Err(Failure::BadRequest(message)) => return (400, message),
+            Err(Failure::Alpaca(e)) => {
+                body["ErrorNumber"] = e.number.into();
+                body["ErrorMessage"] = e.message.into();
+            }
+        }
+        (200, body.to_string())
+    }
+}
+
+/// Reads a line of at most [`MAX_LINE`] bytes, so a client cannot exhaust memory with an endless one.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> Result<usize, io::Error> {
+    let n = reader.by_ref().take(MAX_LINE as u64 + 1).read_line(line)?;
+    if n > MAX_LINE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Request line too long.",
+        ));
+    }
+    Ok(n)
+}
+
+/// Serves HTTP/1.1 requests on one connection until the client closes it.
+fn serve_client<M: Mount>(stream: TcpStream, device: &Device<M>) -> Result<(), io::Error> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    loop {
+        let mut line = String::new();
+        if read_line(&mut reader, &mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Malformed request line {line:?}."),
+            ));
+        };
+        let method = method.to_ascii_uppercase();
+        let mut keep_alive = !parts
+            .next()
+            .is_some_and(|v| v.eq_ignore_ascii_case("HTTP/1.0"));
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            if read_line(&mut reader, &mut header)? == 0 {
+                return Ok(());
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                let value = value.trim();
+                if name.eq_ignore_ascii_case("Content-Length") {
+                    content_length = value.parse().map_err(|_| {
+                        io::Error::new(io::ErrorKind::InvalidData, "Invalid Content-Length.")
+                    })?;
+                } else if name.eq_ignore_ascii_case("Connection") {
+                    keep_alive = !value.eq_ignore_ascii_case("close");
+                }
+            }
+        }
+        if content_length > MAX_BODY {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Request body too long.",
+            ));
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let mut params = Params::default();
+        params.parse(query);
+        params.parse(&String::from_utf8_lossy(&body));
+
+        let (status, body) = device.respond(&method, path, &params);
+        let (reason, content_type) = match status {
+            200 => ("OK", "application/json"),
+            400 => ("Bad Request", "text/plain"),
+            _ => ("Not Found", "text/plain"),
+        };
+        write!(
+            writer,
+            "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}; charset=utf-8\r\nContent-Length: {}\r\nConnection: {}\r\n\r\n{body}",
+            body.len(),
+            if keep_alive { "keep-alive" } else { "close" },
+        )?;
+        writer.flush()?;
+
+        if !keep_alive {
+            return Ok(());
+        }
+    }
+}
+
+/// Answers discovery broadcasts with the port of the HTTP server.
+fn answer_discovery(port: u16) -> Result<(), io::Error> {
+    let socket = UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT))?;
+    let response = json!({ "AlpacaPort": port }).to_string();
+    let mut buf = [0; 64];
+    loop {
+        let (n, from) = socket.recv_from(&mut buf)?;
+        if buf[..n].starts_with(DISCOVERY_REQUEST) {
+            socket.send_to(response.as_bytes(), from)?;
+        }
+    }
+}
+
+/// Serves a mount to Alpaca clients.
+pub struct AlpacaServer {
+    listener: TcpListener,
+    discovery: bool,
+}
+
+impl AlpacaServer {
+    /// Listens for Alpaca requests on `addr`, conventionally port [`DEFAULT_PORT`].
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<AlpacaServer, io::Error> {
+        Ok(AlpacaServer {
+            listener: TcpListener::bind(addr)?,
+            discovery: true,
+        })
+    }
+
+    /// Sets whether to answer discovery broadcasts on [`DISCOVERY_PORT`]. On by default.
+    pub fn discovery(mut self, discovery: bool) -> AlpacaServer {
+        self.discovery = discovery;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
+
+    /// Serves clients, each on its own thread, until the listener fails.
+    pub fn run<M: Mount + Send + 'static>(self, mount: Arc<Mutex<M>>) -> Result<(), io::Error> {
+        let device = Arc::new(Device::new(mount));
+
+        if self.discovery {
+            let port = self.local_addr()?.port();
+            thread::spawn(move || {
+                if let Err(e) = answer_discovery(port) {
+                    log::warn!(
+                        "[{}:{}] Alpaca discovery stopped: {:?}",
+                        file!(),
+                        line!(),
+                        e
+                    );
+                }
+            });
+        }
+
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let device = Arc::clone(&device);
+            thread::spawn(move || {
+                if let Err(e) = serve_client(stream, &device) {
+                    log::debug!("[{}:{}] Alpaca client dropped: {:?}", file!(), line!(), e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::SimMount;
+    use chrono::TimeZone;
+
+    fn request(device: &Device<SimMount>, method: &str, target: &str, body: &str) -> (u16, Value) {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let mut params = Params::default();
+        params.parse(query);
+        params.parse(body);
+        let (status, body) = device.respond(method, path, &params);
+        (
+            status,
+            serde_json::from_str(&body).unwrap_or(Value::String(body)),
+        )
+    }
+
+    #[test]
+    fn serves_telescope() {
+        let mount = SimMount::new()
+            .manual_clock(chrono::Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap());
+        let device = Device::new(Arc::new(Mutex::new(mount)));
+
+        let (status, reply) = request(
+            &device,
+            "GET",
+            "/management/apiversions?ClientTransactionID=7",
+            "",
+        );
+        assert_eq!(status, 200);
+        assert_eq!(reply["Value"], json!([1]));
+        assert_eq!(reply["ClientTransactionID"], 7);
+        assert_eq!(reply["ServerTransactionID"], 1);
+
+        let (_, reply) = request(&device, "GET", "/api/v1/telescope/0/declination", "");
+        assert_eq!(reply["ErrorNumber"], NOT_CONNECTED);
+        assert_eq!(reply["ServerTransactionID"], 2);
+
+        let (status, reply) = request(
+            &device,
+            "PUT",
+            "/api/v1/telescope/0/connected",
+            "connected=True",
+        );
+        assert_eq!(status, 200);
+        assert_eq!(reply["ErrorNumber"], 0);
+        assert!(reply.get("Value").is_none());
+
+        request(
+            &device,
+            "PUT",
+            "/api/v1/telescope/0/tracking",
+            "Tracking=true",
+        );
+        let (_, reply) = request(&device, "GET", "/api/v1/telescope/0/tracking", "");
+        assert_eq!(reply["Value"], true);
+        device.lock().set_tracking_mode(TrackingMode::AzEl).unwrap();
+        request(
+            &device,
+            "PUT",
+            "/api/v1/telescope/0/tracking",
+            "Tracking=false",
+        );
+        request(
+            &device,
+            "PUT",
+            "/api/v1/telescope/0/tracking",
+            "Tracking=true",
+        );
+        assert_eq!(
+            device.lock().get_tracking_mode().unwrap(),
+            TrackingMode::AzEl
+        );
+
+        let (_, reply) = request(
+            &device,
+            "PUT",
+            "/api/v1/telescope/0/synctocoordinates",
+            "RightAscension=5.5&Declination=%2D20.25&ClientTransactionID=9",
+        );
+        assert_eq!(reply["ErrorNumber"], 0);
+        assert_eq!(reply["ClientTransactionID"], 9);
+        let (_, reply) = request(&device, "GET", "/api/v1/telescope/0/rightascension", "");
+        assert!((reply["Value"].as_f64().unwrap() - 5.5).abs() < 1e-3);
+
+        let (_, reply) = request(
+            &device,
+            "PUT",
+            "/api/v1/telescope/0/slewtocoordinatesasync",
+            "RightAscension=25&Declination=0",
+        );
+        assert_eq!(reply["ErrorNumber"], INVALID_VALUE);
+
+        let (status, _) = request(
+            &device,
+            "PUT",
+            "/api/v1/telescope/0/slewtocoordinatesasync",
+            "Declination=0",
+        );
+        assert_eq!(status, 400);
+
+        let (_, reply) = request(&device, "GET", "/api/v1/telescope/0/utcdate", "");
+        assert_eq!(reply["Value"], "2024-03-20T22:00:00.000Z");
+
+        let (_, reply) = request(
+            &device,
+            "GET",
+            "/api/v1/telescope/0/destinationsideofpier",
+            "",
+        );
+        assert_eq!(reply["ErrorNumber"], NOT_IMPLEMENTED);
+
+        let (status, _) = request(&device, "GET", "/api/v1/telescope/1/name", "");
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn caps_line_length() {
+        let mut line = String::new();
+        let mut reader = io::Cursor::new("GET / HTTP/1.1\r\n");
+        assert_eq!(read_line(&mut reader, &mut line).unwrap(), 16);
+
+        let mut reader = io::Cursor::new("X".repeat(MAX_LINE * 2));
+        let e = read_line(&mut reader, &mut String::new()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,751 @@
+//! Classic (COM) ASCOM Telescope driver for Windows.
+//!
+//! Built with the `ascom` feature, the nexlib DLL becomes an in-process COM server for the `nexlib.Telescope` ProgID,
+//! implementing the late-bound `ITelescopeV3` members used by imaging and planetarium applications. Register it from
+//! an elevated prompt with `regsvr32 nexlib.dll`; this also adds the driver to the ASCOM Chooser. The DLL's bitness
+//! must match the client application, so 32-bit applications need a 32-bit build
+//! (`cargo build --release --lib --features ascom --target i686-pc-windows-msvc`).
+//!
+//! Connecting opens the mount named in the nexlib configuration file, or the first detected one.
+
+#![allow(non_snake_case)]
+
+use crate::config::{Config, ConfiguredMount};
+use crate::mount::error::MountError;
+use crate::mount::tracking::TrackingMemory;
+use crate::mount::{Mount, SlewAxis, SlewDir, TrackingMode};
+use crate::{AzEl, RADec};
+use std::ffi::c_void;
+use std::io;
+use std::mem::ManuallyDrop;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use windows::core::{implement, IUnknown, Interface, BSTR, GUID, HRESULT, PCWSTR, VARIANT};
+use windows::Win32::Foundation::{
+    BOOL, CLASS_E_CLASSNOTAVAILABLE, CLASS_E_NOAGGREGATION, DISP_E_BADPARAMCOUNT, DISP_E_EXCEPTION,
+    DISP_E_MEMBERNOTFOUND, DISP_E_UNKNOWNNAME, ERROR_SUCCESS, E_NOTIMPL, E_POINTER, HMODULE,
+    S_FALSE, S_OK,
+};
+use windows::Win32::System::Com::{
+    IClassFactory, IClassFactory_Impl, IDispatch, IDispatch_Impl, ITypeInfo, DISPATCH_FLAGS,
+    DISPATCH_METHOD, DISPATCH_PROPERTYGET, DISPATCH_PROPERTYPUT, DISPPARAMS, EXCEPINFO,
+};
+use windows::Win32::System::LibraryLoader::{
+    GetModuleFileNameW, GetModuleHandleExW, GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS,
+    GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+};
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegCreateKeyExW, RegDeleteTreeW, RegSetValueExW, HKEY, HKEY_CLASSES_ROOT,
+    HKEY_LOCAL_MACHINE, KEY_WOW64_32KEY, KEY_WRITE, REG_OPTION_NON_VOLATILE, REG_SAM_FLAGS, REG_SZ,
+};
+use windows::Win32::System::Variant::{VariantChangeType, VAR_CHANGE_FLAGS, VT_DATE};
+
+/// Class ID of the `nexlib.Telescope` driver.
+pub const CLSID_TELESCOPE: GUID = GUID::from_u128(0x51ce3529_c436_4d6e_910f_bf1f486a91e2);
+
+/// ProgID under which the driver is registered with COM and the ASCOM Chooser.
+pub const PROG_ID: &str = "nexlib.Telescope";
+
+const DESCRIPTION: &str = "nexlib Celestron Telescope";
+
+/// Time between polls while waiting for a synchronous slew to finish.
+const SLEW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Live COM objects and server locks, consulted by `DllCanUnloadNow`.
+static OBJECTS: AtomicUsize = AtomicUsize::new(0);
+
+// ASCOM error numbers (ASCOM.ErrorCodes).
+const ASCOM_NOT_IMPLEMENTED: i32 = 0x80040400_u32 as i32;
+const ASCOM_INVALID_VALUE: i32 = 0x80040401_u32 as i32;
+const ASCOM_NOT_CONNECTED: i32 = 0x80040407_u32 as i32;
+const ASCOM_DRIVER_ERROR: i32 = 0x80040500_u32 as i32;
+
+/// `ITelescopeV3` members supported by the driver. The DISPID of each member is its index plus one.
+const MEMBERS: &[&str] = &[
+    "Connected",
+    "Name",
+    "Description",
+    "DriverInfo",
+    "DriverVersion",
+    "InterfaceVersion",
+    "RightAscension",
+    "Declination",
+    "Azimuth",
+    "Altitude",
+    "Tracking",
+    "Slewing",
+    "AlignmentMode",
+    "EquatorialSystem",
+    "TrackingRate",
+    "UTCDate",
+    "AtHome",
+    "AtPark",
+    "CanSlew",
+    "CanSlewAsync",
+    "CanSync",
+    "CanSetTracking",
+    "CanSlewAltAz",
+    "CanSlewAltAzAsync",
+    "CanMoveAxis",
+    "CanPark",
+    "CanUnpark",
+    "CanFindHome",
+    "CanPulseGuide",
+    "CanSetPark",
+    "CanSetPierSide",
+    "CanSetGuideRates",
+    "CanSetRightAscensionRate",
+    "CanSetDeclinationRate",
+    "CanSyncAltAz",
+    "SlewToCoordinates",
+    "SlewToCoordinatesAsync",
+    "SyncToCoordinates",
+    "SlewToAltAz",
+    "SlewToAltAzAsync",
+    "AbortSlew",
+    "MoveAxis",
+    "SetupDialog",
+    "Dispose",
+];
+
+/// An ASCOM error raised to the client as a COM exception.
+struct Fault {
+    code: i32,
+    message: String,
+}
+
+impl Fault {
+    fn new(code: i32, message: impl Into<String>) -> Fault {
+        Fault {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn not_implemented(member: &str) -> Fault {
+        Fault::new(
+            ASCOM_NOT_IMPLEMENTED,
+            format!("{member} is not implemented."),
+        )
+    }
+}
+
+impl From<io::Error> for Fault {
+    fn from(e: io::Error) -> Self {
+        let code = match e.kind() {
+            io::ErrorKind::NotConnected => ASCOM_NOT_CONNECTED,
+            io::ErrorKind::Unsupported => ASCOM_NOT_IMPLEMENTED,
+            io::ErrorKind::InvalidInput => ASCOM_INVALID_VALUE,
+            _ => ASCOM_DRIVER_ERROR,
+        };
+        Fault::new(code, e.to_string())
+    }
+}
+
+impl From<MountError> for Fault {
+    fn from(e: MountError) -> Self {
+        io::Error::from(e).into()
+    }
+}
+
+impl From<windows::core::Error> for Fault {
+    fn from(e: windows::core::Error) -> Self {
+        Fault::new(ASCOM_INVALID_VALUE, e.message())
+    }
+}
+
+type Reply = Result<VARIANT, Fault>;
+
+/// Converts days since 1899-12-30 (an OLE automation date) into a `VT_DATE` variant.
+fn ole_date(time: chrono::DateTime<chrono::Utc>) -> Reply {
+    let epoch = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    let days = (time - epoch).num_milliseconds() as f64 / 86_400_000.0;
+
+    let mut date = VARIANT::new();
+    unsafe {
+        VariantChangeType(
+            &mut date,
+            &VARIANT::from(days),
+            VAR_CHANGE_FLAGS(0),
+            VT_DATE,
+        )?
+    };
+    Ok(date)
+}
+
+#[implement(IDispatch)]
+struct Telescope {
+    mount: Mutex<Option<ConfiguredMount>>,
+    tracking: Mutex<TrackingMemory>,
+}
+
+impl Telescope {
+    fn new() -> Telescope {
+        OBJECTS.fetch_add(1, Ordering::SeqCst);
+        Telescope {
+            mount: Mutex::new(None),
+            tracking: Mutex::default(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<ConfiguredMount>> {
+        self.mount.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn tracking_memory(&self) -> MutexGuard<'_, TrackingMemory> {
+        self.tracking.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn with_mount<T, F>(&self, f: F) -> Result<T, Fault>
+    where
+        F: FnOnce(&mut ConfiguredMount) -> Result<T, MountError>,
+    {
+        match self.lock().as_mut() {
+            Some(mount) => Ok(f(mount)?),
+            None => Err(Fault::new(
+                ASCOM_NOT_CONNECTED,
+                "The telescope is not connected.",
+            )),
+        }
+    }
+
+    fn connect(&self, connect: bool) -> Result<(), Fault> {
+        let mut mount = self.lock();
+        if !connect {
+            *mount = None;
+        } else if mount.is_none() {
+            *mount = Some(Config::load()?.connect()?);
+        }
+        Ok(())
+    }
+
+    /// Waits for the goto in progress to finish, releasing the mount between polls.
+    fn wait_for_slew(&self) -> Result<(), Fault> {
+        while self.with_mount(|m| m.goto_in_progress())? {
+            thread::sleep(SLEW_POLL_INTERVAL);
+        }
+        Ok(())
+    }
+
+    /// Right ascension in hours and declination in degrees from the arguments of a slew or sync.
+    fn ra_dec(args: &[&VARIANT]) -> Result<RADec, Fault> {
+        let ra = f64::try_from(args[0])?;
+        let dec = f64::try_from(args[1])?;
+        if !(0.0..24.0).contains(&ra) || !(-90.0..=90.0).contains(&dec) {
+            return Err(Fault::new(
+                ASCOM_INVALID_VALUE,
+                format!("Coordinates out of range: RA {ra} h, Dec {dec}°."),
+            ));
+        }
+        Ok(RADec::new(ra * 15.0, dec))
+    }
+
+    fn az_el(args: &[&VARIANT]) -> Result<AzEl, Fault> {
+        let az = f64::try_from(args[0])?;
+        let alt = f64::try_from(args[1])?;
+        if !(0.0..360.0).contains(&az) || !(-90.0..=90.0).contains(&alt) {
+            return Err(Fault::new(
+                ASCOM_INVALID_VALUE,
+                format!("Coordinates out of range: Az {az}°, Alt {alt}°."),
+            ));
+        }
+        Ok(AzEl::new(az, alt))
+    }
+
+    fn get(&self, member: &str) -> Reply {
+        let connected = self.lock().is_some();
+        match member {
+            "Connected" => Ok(connected.into()),
+            "Name" => Ok("nexlib".into()),
+            "Description" => Ok(DESCRIPTION.into()),
+            "DriverInfo" => Ok(
+                format!("{DESCRIPTION} (nexlib {})", env!("CARGO_PKG_VERSION"))
+                    .as_str()
+                    .into(),
+            ),
+            "DriverVersion" => Ok(env!("CARGO_PKG_VERSION")
+                .rsplit_once('.')
+                .map_or(env!("CARGO_PKG_VERSION"), |(v, _)| v)
+                .into()),
+            "InterfaceVersion" => Ok(3i16.into()),
+            "RightAscension" => {
+                Ok((self.with_mount(|m| m.get_position_ra_dec())?.ra / 15.0).into())
+            }
+            "Declination" => Ok(self.with_mount(|m| m.get_position_ra_dec())?.dec.into()),
+            "Azimuth" => Ok(self.with_mount(|m| m.get_position_az_el())?.az.into()),
+            "Altitude" => Ok(self.with_mount(|m| m.get_position_az_el())?.el.into()),
+            "Tracking" => {
+                let mode = self.with_mount(|m| m.get_tracking_mode())?;
+                self.tracking_memory().observe(mode);
+                Ok((mode != TrackingMode::Off).into())
+            }
+            "Slewing" => Ok(self.with_mount(|m| m.goto_in_progress())?.into()),
+            // algAltAz = 0, algPolar = 1, algGermanPolar = 2.
+            "AlignmentMode" => Ok(match self.with_mount(|m| m.get_tracking_mode())? {
+                TrackingMode::AzEl => 0i32,
+                _ => 2i32,
+            }
+            .into()),
+            // equTopocentric: the mount works in coordinates of date.
+            "EquatorialSystem" => Ok(1i32.into()),
+            // driveSidereal.
+            "TrackingRate" => Ok(0i32.into()),
+            "UTCDate" => ole_date(self.with_mount(|m| m.get_time())?),
+            "AtHome" | "AtPark" => Ok(false.into()),
+            "CanSlew" | "CanSlewAsync" | "CanSync" | "CanSetTracking" | "CanSlewAltAz"
+            | "CanSlewAltAzAsync" => Ok(true.into()),
+            "CanPark"
+            | "CanUnpark"
+            | "CanFindHome"
+            | "CanPulseGuide"
+            | "CanSetPark"
+            | "CanSetPierSide"
+            | "CanSetGuideRates"
+            | "CanSetRightAscensionRate"
+            | "CanSetDeclinationRate"
+            | "CanSyncAltAz" => Ok(false.into()),
+            _ => Err(Fault::not_implemented(member)),
+        }
+    }
+
+    fn put(&self, member: &str, value: &VARIANT) -> Result<(), Fault> {
+        match member {
+            "Connected" => self.connect(bool::try_from(value)?),
+            "Tracking" => {
+                let on = bool::try_from(value)?;
+                self.with_mount(|m| self.tracking_memory().set_tracking(m, on))
+            }
+            _ => Err(Fault::not_implemented(member)),
+        }
+    }
+
+    fn call(&self, member: &str, args: &[&VARIANT]) -> Reply {
+        let expected = match member {
+            "SlewToCoordinates"
+            | "SlewToCoordinatesAsync"
+            | "SyncToCoordinates"
+            | "SlewToAltAz"
+            | "SlewToAltAzAsync"
+            | "MoveAxis" => 2,
+            "CanMoveAxis" => 1,
+            _ => 0,
+        };
+        if args.len() != expected {
+            return Err(Fault::new(
+                ASCOM_INVALID_VALUE,
+                format!("{member} takes {expected} arguments."),
+            ));
+        }
+
+        match member {
+            "CanMoveAxis" => Ok(matches!(i32::try_from(args[0])?, 0 | 1).into()),
+            "SlewToCoordinates" | "SlewToCoordinatesAsync" => {
+                let coord = Self::ra_dec(args)?;
+                self.with_mount(|m| m.goto_ra_dec(coord))?;
+                if member == "SlewToCoordinates" {
+                    self.wait_for_slew()?;
+                }
+                Ok(VARIANT::new())
+            }
+            "SyncToCoordinates" => {
+                let coord = Self::ra_dec(args)?;
+                self.with_mount(|m| m.sync(coord))?;
+                Ok(VARIANT::new())
+            }
+            "SlewToAltAz" | "SlewToAltAzAsync" => {
+                let coord = Self::az_el(args)?;
+                self.with_mount(|m| m.goto_az_el(coord))?;
+                if member == "SlewToAltAz" {
+                    self.wait_for_slew()?;
+                }
+                Ok(VARIANT::new())
+            }
+            "AbortSlew" => {
+                self.with_mount(|m| {
+                    m.cancel_goto()?;
+                    m.stop_slew(SlewAxis::RAAz)?;
+                    m.stop_slew(SlewAxis::DecEl)
+                })?;
+                Ok(VARIANT::new())
+            }
+            // Rate in degrees per second; zero stops the axis.
+            "MoveAxis" => {
+                let axis = match i32::try_from(args[0])? {
+                    0 => SlewAxis::RAAz,
+                    1 => SlewAxis::DecEl,
+                    a => {
+                        return Err(Fault::new(
+                            ASCOM_INVALID_VALUE,
+                            format!("Invalid axis {a}."),
+                        ))
+                    }
+                };
+                let rate = f64::try_from(args[1])?;
+                let arcsec = (rate.abs() * 3600.0).round();
+                if arcsec > (u16::MAX / 4) as f64 {
+                    return Err(Fault::new(
+                        ASCOM_INVALID_VALUE,
+                        format!("Rate {rate}°/s is too fast."),
+                    ));
+                }
+
+                self.with_mount(|m| {
+                    if arcsec == 0.0 {
+                        m.stop_slew(axis)
+                    } else {
+                        let dir = if rate > 0.0 {
+                            SlewDir::Positive
+                        } else {
+                            SlewDir::Negative
+                        };
+                        m.slew_variable(axis, dir, arcsec as u16)
+                    }
+                })?;
+                Ok(VARIANT::new())
+            }
+            "SetupDialog" => Ok(VARIANT::new()),
+            "Dispose" => {
+                self.connect(false)?;
+                Ok(VARIANT::new())
+            }
+            _ => self.get(member),
+        }
+    }
+}
+
+impl Drop for Telescope {
+    fn drop(&mut self) {
+        OBJECTS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl IDispatch_Impl for Telescope_Impl {
+    fn GetTypeInfoCount(&self) -> windows::core::Result<u32> {
+        Ok(0)
+    }
+
+    fn GetTypeInfo(&self, _itinfo: u32, _lcid: u32) -> windows::core::Result<ITypeInfo> {
+        Err(E_NOTIMPL.into())
+    }
+
+    fn GetIDsOfNames(
+        &self,
+        _riid: *const GUID,
+        rgsznames: *const PCWSTR,
+        cnames: u32,
+        _lcid: u32,
+        rgdispid: *mut i32,
+    ) -> windows::core::Result<()> {
+        if rgsznames.is_null() || rgdispid.is_null() || cnames == 0 {
+            return Err(E_POINTER.into());
+        }
+
+        let names = unsafe { std::slice::from_raw_parts(rgsznames, cnames as usize) };
+        let ids = unsafe { std::slice::from_raw_parts_mut(rgdispid, cnames as usize) };
+
+        // Members take no named arguments, so only the member name itself can be resolved.
+        ids.fill(-1);
+        let name = unsafe { names[0].to_string() }.unwrap_or_default();
+        match MEMBERS.iter().position(|m| m.eq_ignore_ascii_case(&name)) {
+            Some(i) if cnames == 1 => {
+                ids[0] = i as i32 + 1;
+                Ok(())
+            }
+            Some(i) => {
+                ids[0] = i as i32 + 1;
+                Err(DISP_E_UNKNOWNNAME.into())
+            }
+            None => Err(DISP_E_UNKNOWNNAME.into()),
+        }
+    }
+
+    fn Invoke(
+        &self,
+        dispidmember: i32,
+        _riid: *const GUID,
+        _lcid: u32,
+        wflags: DISPATCH_FLAGS,
+        pdispparams: *const DISPPARAMS,
+        pvarresult: *mut VARIANT,
+        pexcepinfo: *mut EXCEPINFO,
+        _puargerr: *mut u32,
+    ) -> windows::core::Result<()> {
+        let Some(member) = usize::try_from(dispidmember - 1)
+            .ok()
+            .and_then(|i| MEMBERS.get(i))
+        else {
+            return Err(DISP_E_MEMBERNOTFOUND.into());
+        };
+
+        // Arguments arrive in reverse order.
+        let args: Vec<&VARIANT> = match unsafe { pdispparams.as_ref() } {
+            Some(p) if p.cArgs > 0 => {
+                unsafe { std::slice::from_raw_parts(p.rgvarg, p.cArgs as usize) }
+                    .iter()
+                    .rev()
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let res = if wflags.contains(DISPATCH_PROPERTYPUT) {
+            match args.as_slice() {
+                [value] => self.put(member, value).map(|_| VARIANT::new()),
+                _ => return Err(DISP_E_BADPARAMCOUNT.into()),
+            }
+        } else if wflags.contains(DISPATCH_METHOD) {
+            self.call(member, &args)
+        } else if wflags.contains(DISPATCH_PROPERTYGET) {
+            self.get(member)
+        } else {
+            return Err(DISP_E_MEMBERNOTFOUND.into());
+        };
+
+        match res {
+            Ok(value) => {
+                if let Some(result) = unsafe { pvarresult.as_mut() } {
+                    *result = value;
+                }
+                Ok(())
+            }
+            Err(fault) => {
+                log::error!(
+                    "[{}:{}] {} failed: {}",
+                    file!(),
+                    line!(),
+                    member,
+                    fault.message
+                );
+                if let Some(info) = unsafe { pexcepinfo.as_mut() } {
+                    info.bstrSource = ManuallyDrop::new(BSTR::from(PROG_ID));
+                    info.bstrDescription = ManuallyDrop::new(BSTR::from(fault.message.as_str()));
+                    info.scode = fault.code;
+                }
+                Err(DISP_E_EXCEPTION.into())
+            }
+        }
+    }
+}
+
+#[implement(IClassFactory)]
+struct TelescopeFactory;
+
+impl IClassFactory_Impl for TelescopeFactory_Impl {
+    fn CreateInstance(
+        &self,
+        punkouter: Option<&IUnknown>,
+        riid: *const GUID,
+        ppvobject: *mut *mut c_void,
+    ) -> windows::core::Result<()> {
+        if ppvobject.is_null() {
+            return Err(E_POINTER.into());
+        }
+        unsafe { *ppvobject = std::ptr::null_mut() };
+        if punkouter.is_some() {
+            return Err(CLASS_E_NOAGGREGATION.into());
+        }
+
+        let telescope: IDispatch = Telescope::new().into();
+        unsafe { telescope.query(riid, ppvobject).ok() }
+    }
+
+    fn LockServer(&self, flock: BOOL) -> windows::core::Result<()> {
+        if flock.as_bool() {
+            OBJECTS.fetch_add(1, Ordering::SeqCst);
+        } else {
+            OBJECTS.fetch_sub(1, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+}
+
+/// COM entry point returning the class factory for [`CLSID_TELESCOPE`].
+///
+/// # Safety
+///
+/// Called by COM with valid pointers.
+#[no_mangle]
+pub unsafe extern "system" fn DllGetClassObject(
+    rclsid: *const GUID,
+    riid: *const GUID,
+    ppv: *mut *mut c_void,
+) -> HRESULT {
+    if rclsid.is_null() || ppv.is_null() {
+        return E_POINTER;
+    }
+    *ppv = std::ptr::null_mut();
+    if *rclsid != CLSID_TELESCOPE {
+        return CLASS_E_CLASSNOTAVAILABLE;
+    }
+
+    let factory: IClassFactory = TelescopeFactory.into();
+    factory.query(riid, ppv)
+}
+
+/// COM entry point reporting whether the DLL can be unloaded.
+#[no_mangle]
+pub extern "system" fn DllCanUnloadNow() -> HRESULT {
+    if OBJECTS.load(Ordering::SeqCst) == 0 {
+        S_OK
+    } else {
+        S_FALSE
+    }
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+/// Creates `root\path` and sets its values; an empty name sets the default value.
+fn set_key(
+    root: HKEY,
+    path: &str,
+    sam: REG_SAM_FLAGS,
+    values: &[(&str, &str)],
+) -> windows::core::Result<()> {
+    let mut key = HKEY::default();
+    unsafe {
+        RegCreateKeyExW(
+            root,
+            PCWSTR(wide(path).as_ptr()),
+            0,
+            PCWSTR::null(),
+            REG_OPTION_NON_VOLATILE,
+            KEY_WRITE | sam,
+            None,
+            &mut key,
+            None,
+        )
+        .ok()?;
+    }
+
+    let res = values.iter().try_for_each(|(name, value)| {
+        let data = wide(value);
+        let bytes =
+            unsafe { std::slice::from_raw_parts(data.as_ptr().cast::<u8>(), data.len() * 2) };
+        let name = if name.is_empty() {
+            None
+        } else {
+            Some(wide(name))
+        };
+        let name = name.as_ref().map_or(PCWSTR::null(), |n| PCWSTR(n.as_ptr()));
+        unsafe { RegSetValueExW(key, name, 0, REG_SZ, Some(bytes)).ok() }
+    });
+
+    unsafe {
+        let _ = RegCloseKey(key);
+    }
+    res
+}
+
+fn delete_key(root: HKEY, path: &str) {
+    let err = unsafe { RegDeleteTreeW(root, PCWSTR(wide(path).as_ptr())) };
+    if err != ERROR_SUCCESS {
+        log::warn!(
+            "[{}:{}] Failed to delete {}: {:?}",
+            file!(),
+            line!(),
+            path,
+            err
+        );
+    }
+}
+
+/// Path of this DLL, as registered for `InprocServer32`.
+fn module_path() -> windows::core::Result<String> {
+    let mut module = HMODULE::default();
+    unsafe {
+        GetModuleHandleExW(
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            PCWSTR(DllRegisterServer as *const u16),
+            &mut module,
+        )?;
+    }
+
+    let mut buf = vec![0u16; 32768];
+    let len = unsafe { GetModuleFileNameW(module, &mut buf) } as usize;
+    Ok(String::from_utf16_lossy(&buf[..len]))
+}
+
+fn clsid_key() -> String {
+    format!("CLSID\\{{{:?}}}", CLSID_TELESCOPE)
+}
+
+/// ASCOM Profile entry listing the driver in the Chooser. The Profile lives in the 32-bit registry view.
+const ASCOM_PROFILE_KEY: &str = "SOFTWARE\\ASCOM\\Telescope Drivers\\nexlib.Telescope";
+
+/// Registers the COM class and adds the driver to the ASCOM Profile. Called by `regsvr32`.
+#[no_mangle]
+pub extern "system" fn DllRegisterServer() -> HRESULT {
+    let res = (|| {
+        let path = module_path()?;
+        let clsid = format!("{{{:?}}}", CLSID_TELESCOPE);
+        let none = REG_SAM_FLAGS(0);
+
+        set_key(HKEY_CLASSES_ROOT, PROG_ID, none, &[("", DESCRIPTION)])?;
+        set_key(
+            HKEY_CLASSES_ROOT,
+            &format!("{PROG_ID}\\CLSID"),
+            none,
+            &[("", &clsid)],
+        )?;
+        set_key(HKEY_CLASSES_ROOT, &clsid_key(), none, &[("", DESCRIPTION)])?;
+        set_key(
+            HKEY_CLASSES_ROOT,
+            &format!("{}\\InprocServer32", clsid_key()),
+            none,
+            &[("", &path), ("ThreadingModel", "Both")],
+        )?;
+        set_key(
+            HKEY_CLASSES_ROOT,
+            &format!("{}\\ProgID", clsid_key()),
+            none,
+            &[("", PROG_ID)],
+        )?;
+        set_key(
+            HKEY_LOCAL_MACHINE,
+            ASCOM_PROFILE_KEY,
+            KEY_WOW64_32KEY,
+            &[("", DESCRIPTION)],
+        )
+    })();
+
+    match res {
+        Ok(()) => S_OK,
+        Err(e) => e.code(),
+    }
+}
+
+/// Removes everything added by [`DllRegisterServer`]. Called by `regsvr32 /u`.
+#[no_mangle]
+pub extern "system" fn DllUnregisterServer() -> HRESULT {
+    delete_key(HKEY_CLASSES_ROOT, PROG_ID);
+    delete_key(HKEY_CLASSES_ROOT, &clsid_key());
+
+    let mut key = HKEY::default();
+    unsafe {
+        if RegCreateKeyExW(
+            HKEY_LOCAL_MACHINE,
+            PCWSTR(wide("SOFTWARE\\ASCOM\\Telescope Drivers").as_ptr()),
+            0,
+            PCWSTR::null(),
+            REG_OPTION_NON_VOLATILE,
+            KEY_WRITE | KEY_WOW64_32KEY,
+            None,
+            &mut key,
+            None,
+        ) == ERROR_SUCCESS
+        {
+            delete_key(key, PROG_ID);
+            let _ = RegCloseKey(key);
+        }
+    }
+
+    S_OK
+}
@@ -0,0 +1,26 @@
+//! Conversions between right ascension and declination and azimuth and elevation for a site and time.
+//!
+//! Pointing an unaligned mount at a catalog target means converting its right ascension and declination to azimuth and
+//! elevation, which [`RADec::to_az_el`] does for a [`GeoLocation`] and a time, with [`AzEl::to_ra_dec`] as its
+//! inverse:
+//!
+//! ```
+//! use chrono::{TimeZone, Utc};
+//! use nexlib::astro::GeoLocation;
+//! use nexlib::RADec;
+//!
+//! let site = GeoLocation { latitude: 40.0, longitude: -75.0 };
+//! let time = Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap();
+//! let betelgeuse = RADec::new(88.79, 7.41);
+//! let az_el = betelgeuse.to_az_el(&site, time);
+//! assert!((az_el.to_ra_dec(&site, time).ra - betelgeuse.ra).abs() < 1e-6);
+//! ```
+//!
+//! The conversions are geometric; see [`transform`](crate::mount::transform), which also provides the local sidereal
+//! time and the conversions to and from hour angle.
+
+pub use crate::mount::transform::{az_el_to_ha_dec, ha_dec_to_az_el, local_sidereal_time};
+pub use crate::mount::{AzEl, RADec};
+
+/// Geographic location of an observer, in degrees; the same type as a mount's site, [`Location`](crate::Location).
+pub type GeoLocation = crate::mount::Location;
@@ -0,0 +1,21 @@
+//! INDI driver for the configured mount, or the first detected one.
+//!
+//! Start it under `indiserver`, e.g. `indiserver -v indi_nexlib`, and connect from KStars/Ekos or any other INDI
+//! client. The device is named `nexlib`, or the first argument if given. Log output goes to stderr, which
+//! `indiserver` forwards to its own log.
+
+use nexlib::config::Config;
+use nexlib::mount::indi::driver::IndiDriver;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+
+    let device = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "nexlib".to_owned());
+
+    let mut driver = IndiDriver::new(&device, || Config::load()?.connect());
+    driver.run(std::io::stdin(), std::io::stdout().lock())?;
+
+    Ok(())
+}
@@ -0,0 +1,649 @@
+//! Command-line control of a NexStar-compatible mount.
+//!
+//! Usage: `nexctl <COMMAND>`
+//!
+//! Commands:
+//! - `status` - Print the mount's position, tracking mode, and clock.
+//! - `goto --ra RA --dec DEC [--wait]` - Go to a position, in degrees or sexagesimal such as `5h35m` and `-5:23`.
+//! - `slew --axis raaz|decel --rate RATE` - Move an axis at a rate in arcseconds per second, negative for the other
+//!   direction, until stopped with rate 0 or `stop`.
+//! - `stop` - Stop any goto and slew.
+//! - `time [sync]` - Print the mount's clock against the computer's, or with `sync` set it to the computer's.
+//! - `gps [--timeout DURATION] [--sync]` - Wait for a GPS fix and print it, with `--sync` also setting the mount's
+//!   site and clock from it.
+//! - `tui` - Interactive terminal dashboard with live position and an arrow-key slew pad.
+//! - `daemon [NAME] [--estop TRIGGER]...` - Own the mount connection and serve clients over a local socket, optionally
+//!   stopping the mount when a hardware emergency-stop input fires.
+//! - `limits [PROFILE|none] [--daemon NAME]` - List the running daemon's limit profiles, or select one for all its
+//!   clients. Other commands use the profile in the configuration, or `NEXLIB_LIMITS_PROFILE`.
+//! - `stdio` - Serve JSON-RPC requests on stdin, one per line, answering on stdout, for embedding as a subprocess.
+//! - `watch [--interval DURATION] [--format json|text]` - Print the mount status to stdout at an interval, one line
+//!   per sample, for shell pipelines and logging scripts.
+//! - `alpaca [ADDR]` - Serve the mount to ASCOM Alpaca clients such as NINA and SGP over HTTP, by default on port
+//!   11111 of every interface.
+//! - `guideport [ADDR]` - Take pulse guide commands from autoguiding programs such as PHD2 as an LX200 mount on TCP,
+//!   by default on port 4030 of localhost.
+//! - `server [ADDR] [--token TOKEN]` - Serve the mount API and a position stream to remote clients over WebSocket,
+//!   by default on port 11880 of every interface. The token may instead be given in `NEXLIB_SERVER_TOKEN`.
+//! - `script [--check] FILE` - Run the observing session described in a TOML or JSON file, or only validate it.
+
+use nexlib::mount::Mount;
+use std::io;
+use std::process::ExitCode;
+
+#[cfg(feature = "tui")]
+mod tui;
+
+const USAGE: &str = "Usage: nexctl <COMMAND>
+
+Commands:
+  status         Print the mount's position, tracking mode, and clock
+  goto --ra RA --dec DEC
+                 Go to a position, in degrees or sexagesimal such as 5h35m and -5:23
+                 --wait  Return when the goto has finished
+  slew --axis raaz|decel --rate RATE
+                 Move an axis at RATE arcseconds per second, negative for the other direction, until rate 0 or stop
+  stop           Stop any goto and slew
+  time [sync]    Print the mount's clock against the computer's, or with sync set it to the computer's
+  gps            Wait for a GPS fix and print it
+                 --timeout DURATION  Longest wait, e.g. 30s or 5m (default 2m)
+                 --sync              Set the mount's site and clock from the fix
+  tui            Interactive terminal dashboard with live position and an arrow-key slew pad
+  daemon [NAME]  Own the mount connection and serve clients over a local socket
+                 --estop TRIGGER  Stop the mount when gpio:<PIN>[:active-low] or key:<DEVICE>:<CODE> fires
+  limits [PROFILE|none]
+                 List the running daemon's limit profiles, or select one for all its clients
+                 --daemon NAME  Daemon socket name (default nexlib.sock)
+  stdio          Serve JSON-RPC requests on stdin, answering on stdout
+  watch          Print the mount status to stdout, one line per sample
+                 --interval DURATION  Time between samples, e.g. 500ms, 1s, or 2m (default 1s)
+                 --format json|text   Output format (default json)
+  alpaca [ADDR]  Serve the mount to ASCOM Alpaca clients over HTTP (default 0.0.0.0:11111)
+  guideport [ADDR]
+                 Take pulse guide commands from autoguiding programs over TCP (default 127.0.0.1:4030)
+  server [ADDR]  Serve the mount API and a position stream over WebSocket (default 0.0.0.0:11880)
+                 --token TOKEN  Require clients to present TOKEN (default $NEXLIB_SERVER_TOKEN)
+  script [--check] FILE
+                 Run the observing session described in a TOML or JSON file, or with --check only validate it";
+
+#[cfg(not(all(
+    feature = "tui",
+    feature = "daemon",
+    feature = "rpc",
+    feature = "watch",
+    feature = "alpaca",
+    feature = "guideport",
+    feature = "server",
+    feature = "script"
+)))]
+fn not_built(feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("nexctl was built without the `{feature}` feature."),
+    )
+}
+
+fn unknown_option(arg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Unknown option {arg:?}."),
+    )
+}
+
+/// Takes the value of option `arg` from `args`.
+fn option_value<'a>(
+    arg: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<&'a str, io::Error> {
+    args.next()
+        .map(String::as_str)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{arg} needs a value.")))
+}
+
+fn status(args: &[String]) -> Result<(), io::Error> {
+    if let Some(arg) = args.first() {
+        return Err(unknown_option(arg));
+    }
+
+    let config = nexlib::config::Config::load()?;
+    nexlib::units::set(config.display);
+    let mut mount = config.connect()?;
+    let units = nexlib::units::current();
+
+    let status = nexlib::mount::status::MountStatus::read(&mut mount)?;
+    let clock = mount.get_time()?.with_timezone(&chrono::Local);
+    println!("RA        {}", units.ra(status.ra_dec.ra));
+    println!("Dec       {}", units.dec(status.ra_dec.dec));
+    println!("Az        {}", units.az(status.az_el.az));
+    println!("El        {}", units.dec(status.az_el.el));
+    println!("Tracking  {:?}", status.tracking_mode);
+    println!(
+        "Goto      {}",
+        if status.goto_in_progress {
+            "in progress"
+        } else {
+            "none"
+        }
+    );
+    println!(
+        "Clock     {} {}",
+        clock.format("%Y-%m-%d"),
+        units.time(&clock)
+    );
+    Ok(())
+}
+
+fn goto(args: &[String]) -> Result<(), io::Error> {
+    let units = nexlib::units::current();
+    let (mut ra, mut dec, mut wait) = (None, None, false);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ra" => {
+                let value = option_value(arg, &mut args)?;
+                ra = Some(units.parse_ra(value).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Invalid right ascension {value:?}."),
+                    )
+                })?);
+            }
+            "--dec" => {
+                let value = option_value(arg, &mut args)?;
+                dec = Some(units.parse_dec(value).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Invalid declination {value:?}."),
+                    )
+                })?);
+            }
+            "--wait" => wait = true,
+            arg => return Err(unknown_option(arg)),
+        }
+    }
+    let (Some(ra), Some(dec)) = (ra, dec) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "goto needs --ra and --dec.",
+        ));
+    };
+
+    let mut mount = nexlib::config::Config::load()?.connect()?;
+    mount.goto_ra_dec(nexlib::RADec::new(ra, dec))?;
+    if wait {
+        mount.wait_for_goto(None)?;
+    }
+    Ok(())
+}
+
+fn slew(args: &[String]) -> Result<(), io::Error> {
+    use nexlib::mount::{SlewAxis, SlewDir};
+
+    let (mut axis, mut rate) = (None, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--axis" => {
+                axis = Some(
+                    match option_value(arg, &mut args)?.to_ascii_lowercase().as_str() {
+                        "raaz" => SlewAxis::RAAz,
+                        "decel" => SlewAxis::DecEl,
+                        value => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!("Unknown axis {value:?}; expected raaz or decel."),
+                            ))
+                        }
+                    },
+                );
+            }
+            "--rate" => {
+                let value = option_value(arg, &mut args)?;
+                rate = Some(value.parse::<i32>().ok().filter(|r| r.unsigned_abs() <= u16::MAX as u32).ok_or_else(
+                    || {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Invalid rate {value:?}; expected whole arcseconds per second up to 65535."),
+                        )
+                    },
+                )?);
+            }
+            arg => return Err(unknown_option(arg)),
+        }
+    }
+    let (Some(axis), Some(rate)) = (axis, rate) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "slew needs --axis and --rate.",
+        ));
+    };
+
+    let mut mount = nexlib::config::Config::load()?.connect()?;
+    if rate == 0 {
+        return Ok(mount.stop_slew(axis)?);
+    }
+    let dir = if rate > 0 {
+        SlewDir::Positive
+    } else {
+        SlewDir::Negative
+    };
+    Ok(mount.slew_variable(axis, dir, rate.unsigned_abs() as u16)?)
+}
+
+fn stop(args: &[String]) -> Result<(), io::Error> {
+    if let Some(arg) = args.first() {
+        return Err(unknown_option(arg));
+    }
+    Ok(nexlib::config::Config::load()?.connect()?.stop_all()?)
+}
+
+fn time(args: &[String]) -> Result<(), io::Error> {
+    let sync = match args {
+        [] => false,
+        [arg] if arg == "sync" => true,
+        [arg, ..] => return Err(unknown_option(arg)),
+    };
+
+    let mut mount = nexlib::config::Config::load()?.connect()?;
+    if sync {
+        mount.set_time_now()?;
+    }
+    let clock = mount.get_time()?;
+    let offset = clock - chrono::Utc::now();
+    println!(
+        "{} ({:+.1} s from the computer)",
+        clock
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S %:z"),
+        offset.num_milliseconds() as f64 / 1000.0
+    );
+    Ok(())
+}
+
+fn gps(args: &[String]) -> Result<(), io::Error> {
+    use nexlib::mount::Gps;
+
+    let mut timeout = std::time::Duration::from_secs(120);
+    let mut sync = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--timeout" => timeout = parse_duration(option_value(arg, &mut args)?)?,
+            "--sync" => sync = true,
+            arg => return Err(unknown_option(arg)),
+        }
+    }
+
+    let mut mount = nexlib::config::Config::load()?.connect()?;
+    let fix = mount.get_gps()?.wait_for_fix_with(timeout, |progress| {
+        eprint!(
+            "\rWaiting for a fix: {} s, {}",
+            progress.elapsed.as_secs(),
+            if progress.linked {
+                "linked"
+            } else {
+                "not linked"
+            }
+        );
+    })?;
+    eprintln!();
+    println!("Latitude   {:.5}", fix.location.latitude);
+    println!("Longitude  {:.5}", fix.location.longitude);
+    println!("Time       {}", fix.time.format("%Y-%m-%d %H:%M:%S UTC"));
+
+    if sync {
+        let synced = mount.inner().inner().sync_from_gps()?;
+        match synced.clock_offset {
+            Some(offset) => println!(
+                "Set the site and clock; the clock was {:+.1} s off.",
+                offset.num_milliseconds() as f64 / 1000.0
+            ),
+            None => println!("Set the site and clock."),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tui")]
+fn tui(_args: &[String]) -> Result<(), io::Error> {
+    let config = nexlib::config::Config::load()?;
+    nexlib::units::set(config.display);
+    let mut mount = config.connect()?;
+    tui::run(&mut mount)
+}
+
+#[cfg(not(feature = "tui"))]
+fn tui(_args: &[String]) -> Result<(), io::Error> {
+    Err(not_built("tui"))
+}
+
+#[cfg(feature = "daemon")]
+fn daemon(args: &[String]) -> Result<(), io::Error> {
+    env_logger::init();
+
+    let mut name = nexlib::daemon::DEFAULT_SOCKET_NAME;
+    let mut estops = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--estop" => estops.push(args.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "--estop needs a trigger.")
+            })?),
+            arg => name = arg,
+        }
+    }
+
+    let mount = nexlib::config::Config::load()?.connect()?;
+    let queue = std::sync::Arc::new(nexlib::mount::queue::CommandQueue::spawn(mount));
+    for trigger in estops {
+        bind_estop(trigger, &queue)?;
+    }
+    nexlib::daemon::serve(queue, name)
+}
+
+#[cfg(all(feature = "daemon", target_os = "linux", feature = "estop"))]
+fn bind_estop(
+    trigger: &str,
+    queue: &std::sync::Arc<nexlib::mount::queue::CommandQueue<nexlib::config::ConfiguredMount>>,
+) -> Result<(), io::Error> {
+    nexlib::estop::bind(trigger.parse()?, std::sync::Arc::clone(queue)).map(drop)
+}
+
+#[cfg(all(feature = "daemon", not(all(target_os = "linux", feature = "estop"))))]
+fn bind_estop(
+    _trigger: &str,
+    _queue: &std::sync::Arc<nexlib::mount::queue::CommandQueue<nexlib::config::ConfiguredMount>>,
+) -> Result<(), io::Error> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "nexctl was built without the `estop` feature, which needs Linux.",
+    ))
+}
+
+#[cfg(feature = "daemon")]
+fn limits(args: &[String]) -> Result<(), io::Error> {
+    let mut name = nexlib::daemon::DEFAULT_SOCKET_NAME;
+    let mut profile = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--daemon" => name = option_value(arg, &mut args)?,
+            arg if arg.starts_with("--") => return Err(unknown_option(arg)),
+            arg => profile = Some(arg),
+        }
+    }
+
+    let mut client = nexlib::daemon::DaemonClient::connect(name)?;
+    if let Some(profile) = profile {
+        client.select_limit_profile(Some(profile).filter(|p| *p != "none"))?;
+    }
+    let selection = client.limit_profiles()?;
+    for profile in &selection.profiles {
+        let marker = if selection.selected.as_ref() == Some(profile) {
+            "*"
+        } else {
+            " "
+        };
+        println!("{marker} {profile}");
+    }
+    if selection.selected.is_none() {
+        println!("No limits are enforced.");
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "daemon"))]
+fn daemon(_args: &[String]) -> Result<(), io::Error> {
+    Err(not_built("daemon"))
+}
+
+#[cfg(not(feature = "daemon"))]
+fn limits(_args: &[String]) -> Result<(), io::Error> {
+    Err(not_built("daemon"))
+}
+
+#[cfg(feature = "rpc")]
+fn stdio(_args: &[String]) -> Result<(), io::Error> {
+    // Logs go to stderr, keeping stdout for responses.
+    env_logger::init();
+
+    let mut mount = nexlib::config::Config::load()?.connect()?;
+    nexlib::rpc::serve_stdio(&mut mount)
+}
+
+#[cfg(not(feature = "rpc"))]
+fn stdio(_args: &[String]) -> Result<(), io::Error> {
+    Err(not_built("rpc"))
+}
+
+/// Parses a duration such as `500ms`, `1s`, `1.5s`, or `2m`. A bare number is in seconds.
+fn parse_duration(s: &str) -> Result<std::time::Duration, io::Error> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid duration {s:?}; expected e.g. 500ms, 1s, or 2m."),
+        )
+    };
+
+    let split = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+    let value: f64 = s[..split].parse().map_err(|_| invalid())?;
+    let secs = match &s[split..] {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        _ => return Err(invalid()),
+    };
+    std::time::Duration::try_from_secs_f64(secs).map_err(|_| invalid())
+}
+
+#[cfg(feature = "watch")]
+fn watch(args: &[String]) -> Result<(), io::Error> {
+    use nexlib::mount::status::MountStatus;
+    use std::io::Write;
+
+    let mut interval = std::time::Duration::from_secs(1);
+    let mut json = true;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("{arg} needs a value."))
+            })
+        };
+        match arg.as_str() {
+            "--interval" => interval = parse_duration(value()?)?,
+            "--format" => {
+                json = match value()?.as_str() {
+                    "json" => true,
+                    "text" => false,
+                    format => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Unknown format {format:?}; expected json or text."),
+                        ))
+                    }
+                }
+            }
+            arg => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown option {arg:?}."),
+                ))
+            }
+        }
+    }
+
+    let config = nexlib::config::Config::load()?;
+    nexlib::units::set(config.display);
+    let mut mount = config.connect()?;
+    let units = nexlib::units::current();
+    let mut stdout = io::stdout();
+
+    loop {
+        // A failed sample is reported on stderr and the next one tried, since the mount may recover.
+        let line = match MountStatus::read(&mut mount) {
+            Ok(status) if json => serde_json::to_string(&status)?,
+            Ok(status) => format!(
+                "{} RA {} Dec {} Az {} El {} {:?}{}",
+                units.time(&status.time.with_timezone(&chrono::Local)),
+                units.ra(status.ra_dec.ra),
+                units.dec(status.ra_dec.dec),
+                units.az(status.az_el.az),
+                units.dec(status.az_el.el),
+                status.tracking_mode,
+                if status.goto_in_progress { " goto" } else { "" },
+            ),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::thread::sleep(interval);
+                continue;
+            }
+        };
+
+        match writeln!(stdout, "{line}").and_then(|()| stdout.flush()) {
+            // The reader of the pipe has exited, e.g. `nexctl watch | head`.
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            res => res?,
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(not(feature = "watch"))]
+fn watch(_args: &[String]) -> Result<(), io::Error> {
+    Err(not_built("watch"))
+}
+
+#[cfg(feature = "alpaca")]
+fn alpaca(args: &[String]) -> Result<(), io::Error> {
+    env_logger::init();
+
+    let addr = match args.first() {
+        Some(addr) => addr.clone(),
+        None => format!("0.0.0.0:{}", nexlib::alpaca::DEFAULT_PORT),
+    };
+    let mount = nexlib::config::Config::load()?.connect()?;
+    let server = nexlib::alpaca::AlpacaServer::bind(addr)?;
+    eprintln!("Serving Alpaca on {}", server.local_addr()?);
+    server.run(std::sync::Arc::new(std::sync::Mutex::new(mount)))
+}
+
+#[cfg(not(feature = "alpaca"))]
+fn alpaca(_args: &[String]) -> Result<(), io::Error> {
+    Err(not_built("alpaca"))
+}
+
+#[cfg(feature = "guideport")]
+fn guideport(args: &[String]) -> Result<(), io::Error> {
+    env_logger::init();
+
+    let addr = match args.first() {
+        Some(addr) => addr.clone(),
+        None => format!("127.0.0.1:{}", nexlib::guideport::DEFAULT_PORT),
+    };
+    let mount = nexlib::config::Config::load()?.connect()?;
+    let server = nexlib::guideport::GuidePortServer::bind(addr)?;
+    eprintln!("Serving the guide port on {}", server.local_addr()?);
+    server.run(std::sync::Arc::new(std::sync::Mutex::new(mount)))
+}
+
+#[cfg(not(feature = "guideport"))]
+fn guideport(_args: &[String]) -> Result<(), io::Error> {
+    Err(not_built("guideport"))
+}
+
+#[cfg(feature = "server")]
+fn server(args: &[String]) -> Result<(), io::Error> {
+    env_logger::init();
+
+    let mut addr = format!("0.0.0.0:{}", nexlib::server::DEFAULT_PORT);
+    let mut token = std::env::var("NEXLIB_SERVER_TOKEN").ok();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--token" => token = Some(option_value(arg, &mut args)?.to_owned()),
+            arg if arg.starts_with("--") => return Err(unknown_option(arg)),
+            arg => addr = arg.to_owned(),
+        }
+    }
+
+    let mount = nexlib::config::Config::load()?.connect()?;
+    let mut server = nexlib::server::RemoteServer::bind(addr)?;
+    if let Some(token) = token.filter(|token| !token.is_empty()) {
+        server = server.token(token);
+    }
+    eprintln!("Serving the mount on ws://{}", server.local_addr()?);
+    server.run(std::sync::Arc::new(std::sync::Mutex::new(mount)))
+}
+
+#[cfg(not(feature = "server"))]
+fn server(_args: &[String]) -> Result<(), io::Error> {
+    Err(not_built("server"))
+}
+
+#[cfg(feature = "script")]
+fn script(args: &[String]) -> Result<(), io::Error> {
+    env_logger::init();
+
+    let (check, path) = match args {
+        [flag, path] if flag == "--check" => (true, path),
+        [path] => (false, path),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "script needs a file, optionally after --check.",
+            ))
+        }
+    };
+    let path = std::path::Path::new(path);
+    if check {
+        let script = nexlib::script::validate(path)?;
+        eprintln!(
+            "{}: {} targets, valid from now.",
+            path.display(),
+            script.targets.len()
+        );
+        return Ok(());
+    }
+    let mut mount = nexlib::config::Config::load()?.connect()?;
+    nexlib::script::run(&mut mount, path)
+}
+
+#[cfg(not(feature = "script"))]
+fn script(_args: &[String]) -> Result<(), io::Error> {
+    Err(not_built("script"))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let res = match args.first().map(String::as_str) {
+        Some("status") => status(&args[1..]),
+        Some("goto") => goto(&args[1..]),
+        Some("slew") => slew(&args[1..]),
+        Some("stop") => stop(&args[1..]),
+        Some("time") => time(&args[1..]),
+        Some("gps") => gps(&args[1..]),
+        Some("tui") => tui(&args[1..]),
+        Some("daemon") => daemon(&args[1..]),
+        Some("limits") => limits(&args[1..]),
+        Some("stdio") => stdio(&args[1..]),
+        Some("watch") => watch(&args[1..]),
+        Some("alpaca") => alpaca(&args[1..]),
+        Some("guideport") => guideport(&args[1..]),
+        Some("server") => server(&args[1..]),
+        Some("script") => script(&args[1..]),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Terminal dashboard showing live mount state with an arrow-key slew pad.
+//!
+//! Works over SSH without a display server. Arrow keys start a fixed-rate slew on the matching axis, `+`/`-` change
+//! the rate, space stops both axes, Escape also cancels any goto, and `q` stops the mount and exits.
+
+use nexlib::mount::error::MountError;
+use nexlib::mount::{Mount, SlewAxis, SlewDir, SlewRate, TrackingMode};
+use nexlib::{AzEl, RADec};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Time between status refreshes.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+fn slew_rate(rate: u8) -> SlewRate {
+    match rate {
+        1 => SlewRate::Rate1,
+        2 => SlewRate::Rate2,
+        3 => SlewRate::Rate3,
+        4 => SlewRate::Rate4,
+        5 => SlewRate::Rate5,
+        6 => SlewRate::Rate6,
+        7 => SlewRate::Rate7,
+        8 => SlewRate::Rate8,
+        _ => SlewRate::Rate9,
+    }
+}
+
+#[derive(Default)]
+struct Dashboard {
+    ra_dec: Option<RADec>,
+    az_el: Option<AzEl>,
+    tracking_mode: Option<TrackingMode>,
+    goto_in_progress: Option<bool>,
+    aligned: Option<bool>,
+    /// Local time of the last successful refresh.
+    refreshed: Option<chrono::DateTime<chrono::Local>>,
+    /// Active slew direction of the RA/Az and Dec/El axes, if any.
+    slewing: [Option<SlewDir>; 2],
+    rate: u8,
+    error: Option<String>,
+    quit: bool,
+}
+
+impl Dashboard {
+    fn refresh<M: Mount>(&mut self, mount: &mut M) {
+        let res = (|| -> Result<(), MountError> {
+            self.ra_dec = Some(mount.get_position_ra_dec()?);
+            self.az_el = Some(mount.get_position_az_el()?);
+            self.tracking_mode = Some(mount.get_tracking_mode()?);
+            self.goto_in_progress = Some(mount.goto_in_progress()?);
+            self.aligned = Some(mount.is_aligned()?);
+            self.refreshed = Some(chrono::Local::now());
+            Ok(())
+        })();
+
+        self.record(res);
+    }
+
+    fn record(&mut self, res: Result<(), MountError>) {
+        if let Err(e) = res {
+            self.error = Some(e.to_string());
+        }
+    }
+
+    fn slew<M: Mount>(&mut self, mount: &mut M, axis: SlewAxis, dir: SlewDir) {
+        let res = mount.slew_fixed(axis, dir, slew_rate(self.rate));
+        if res.is_ok() {
+            self.slewing[axis as usize] = Some(dir);
+        }
+        self.record(res);
+    }
+
+    fn stop<M: Mount>(&mut self, mount: &mut M) {
+        let res = mount
+            .stop_slew(SlewAxis::RAAz)
+            .and_then(|_| mount.stop_slew(SlewAxis::DecEl));
+        if res.is_ok() {
+            self.slewing = [None, None];
+        }
+        self.record(res);
+    }
+
+    fn handle_key<M: Mount>(&mut self, mount: &mut M, key: KeyCode) {
+        self.error = None;
+
+        match key {
+            KeyCode::Left => self.slew(mount, SlewAxis::RAAz, SlewDir::Negative),
+            KeyCode::Right => self.slew(mount, SlewAxis::RAAz, SlewDir::Positive),
+            KeyCode::Up => self.slew(mount, SlewAxis::DecEl, SlewDir::Positive),
+            KeyCode::Down => self.slew(mount, SlewAxis::DecEl, SlewDir::Negative),
+            KeyCode::Char('+') | KeyCode::Char('=') => self.rate = (self.rate + 1).min(9),
+            KeyCode::Char('-') => self.rate = (self.rate - 1).max(1),
+            KeyCode::Char(' ') => self.stop(mount),
+            KeyCode::Esc => {
+                self.stop(mount);
+                let res = mount.cancel_goto();
+                self.record(res);
+            }
+            KeyCode::Char('q') => {
+                self.stop(mount);
+                self.quit = true;
+            }
+            _ => (),
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        fn value<T: ToString>(v: Option<T>) -> String {
+            v.map_or_else(|| "-".to_owned(), |v| v.to_string())
+        }
+
+        let [top, pad, footer] = Layout::vertical([
+            Constraint::Length(6),
+            Constraint::Min(6),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [position, status] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(top);
+
+        let units = nexlib::units::current();
+        let position_lines = vec![
+            Line::from(format!(
+                "RA:  {:>13}",
+                value(self.ra_dec.map(|p| units.ra(p.ra)))
+            )),
+            Line::from(format!(
+                "Dec: {:>13}",
+                value(self.ra_dec.map(|p| units.dec(p.dec)))
+            )),
+            Line::from(format!(
+                "Az:  {:>13}",
+                value(self.az_el.map(|p| units.az(p.az)))
+            )),
+            Line::from(format!(
+                "El:  {:>13}",
+                value(self.az_el.map(|p| units.dec(p.el)))
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(position_lines).block(Block::bordered().title(" Position ")),
+            position,
+        );
+
+        let status_lines = vec![
+            Line::from(format!(
+                "Tracking: {}",
+                value(self.tracking_mode.map(|m| format!("{:?}", m)))
+            )),
+            Line::from(format!(
+                "Goto:     {}",
+                value(
+                    self.goto_in_progress
+                        .map(|g| if g { "slewing" } else { "idle" })
+                )
+            )),
+            Line::from(format!("Aligned:  {}", value(self.aligned))),
+            Line::from(format!(
+                "Updated:  {}",
+                value(self.refreshed.map(|t| units.time(&t)))
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(status_lines).block(Block::bordered().title(" Status ")),
+            status,
+        );
+
+        let arrow = |axis: usize, dir: SlewDir, label: &'static str| {
+            if self.slewing[axis] == Some(dir) {
+                label.bold().fg(Color::Green)
+            } else {
+                label.into()
+            }
+        };
+        let pad_lines = vec![
+            Line::from(vec!["      ".into(), arrow(1, SlewDir::Positive, "▲")]),
+            Line::from(vec![
+                "   ".into(),
+                arrow(0, SlewDir::Negative, "◀"),
+                "     ".into(),
+                arrow(0, SlewDir::Positive, "▶"),
+                format!("     Rate: {}", self.rate).into(),
+            ]),
+            Line::from(vec!["      ".into(), arrow(1, SlewDir::Negative, "▼")]),
+            Line::from(""),
+            Line::from(
+                "Arrows: slew   +/-: rate   Space: stop   Esc: stop + cancel goto   q: quit",
+            ),
+        ];
+        frame.render_widget(
+            Paragraph::new(pad_lines).block(Block::bordered().title(" Slew Pad ")),
+            pad,
+        );
+
+        if let Some(e) = &self.error {
+            frame.render_widget(
+                Paragraph::new(e.as_str()).style(Style::new().fg(Color::Red)),
+                footer,
+            );
+        }
+    }
+}
+
+fn run_loop<M: Mount>(terminal: &mut DefaultTerminal, mount: &mut M) -> Result<(), io::Error> {
+    let mut dashboard = Dashboard {
+        rate: 5,
+        ..Default::default()
+    };
+    let mut last_refresh: Option<Instant> = None;
+
+    while !dashboard.quit {
+        if last_refresh.is_none_or(|t| t.elapsed() >= REFRESH_INTERVAL) {
+            dashboard.refresh(mount);
+            last_refresh = Some(Instant::now());
+        }
+
+        terminal.draw(|frame| dashboard.draw(frame))?;
+
+        if event::poll(REFRESH_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    dashboard.handle_key(mount, key.code);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the dashboard until the user quits, restoring the terminal afterwards.
+pub fn run<M: Mount>(mount: &mut M) -> Result<(), io::Error> {
+    let mut terminal = ratatui::init();
+    let res = run_loop(&mut terminal, mount);
+    ratatui::restore();
+    res
+}
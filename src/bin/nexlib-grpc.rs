@@ -0,0 +1,23 @@
+//! Serves the configured mount, or the first detected one, over gRPC.
+//!
+//! Usage: `nexlib-grpc [ADDRESS]`, where `ADDRESS` defaults to `0.0.0.0:50051`.
+
+use nexlib::config::Config;
+use std::net::SocketAddr;
+
+const DEFAULT_ADDR: &str = "0.0.0.0:50051";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let addr: SocketAddr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDR.to_owned())
+        .parse()?;
+
+    let mount = Config::load()?.connect()?;
+
+    println!("Serving mount on {}", addr);
+    nexlib::grpc::serve(mount, addr).await?;
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+//! Observing targets and importers for external observing lists.
+//!
+//! The [`messier`] catalog and the [`stars`] are built in, so named objects can be found without a database or a
+//! network connection:
+//!
+//! ```
+//! let m31 = nexlib::catalog::lookup("M31").unwrap();
+//! assert_eq!(nexlib::catalog::lookup("NGC 224"), Some(m31));
+//! assert_eq!(nexlib::catalog::search("andromda")[0].name, "Andromeda Galaxy");
+//! ```
+
+pub mod ephemeris;
+mod import;
+pub use import::{parse_csv, parse_skylist, read_list};
+
+pub mod messier;
+#[cfg(feature = "sesame")]
+pub mod sesame;
+pub mod stars;
+
+use crate::RADec;
+
+/// An object to observe.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Target {
+    /// Display name, e.g. `Andromeda Galaxy` or `M 31`.
+    pub name: String,
+    /// Catalog designations such as `M 31` and `NGC 224`.
+    pub designations: Vec<String>,
+    /// J2000 position in degrees, if the source list provided one.
+    pub coord: Option<RADec>,
+    /// Visual magnitude.
+    pub magnitude: Option<f64>,
+    /// Object type, e.g. `Galaxy`.
+    pub kind: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl Target {
+    pub fn new(name: &str) -> Target {
+        Target {
+            name: name.to_owned(),
+            designations: Vec::new(),
+            coord: None,
+            magnitude: None,
+            kind: None,
+            notes: None,
+        }
+    }
+
+    /// Whether the target still needs its coordinates looked up by name before it can be pointed at.
+    pub fn needs_resolution(&self) -> bool {
+        self.coord.is_none()
+    }
+
+    /// Names to try when resolving the target, most specific first.
+    pub fn lookup_names(&self) -> impl Iterator<Item = &str> {
+        self.designations
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(self.name.as_str()))
+    }
+}
+
+/// Every object built into the crate: the [`messier`] catalog, then the [`stars`].
+pub fn builtin() -> Vec<Target> {
+    messier::MESSIER
+        .iter()
+        .map(messier::MessierObject::to_target)
+        .chain(stars::BRIGHT_STARS.iter().map(stars::BrightStar::to_target))
+        .collect()
+}
+
+/// Finds a built-in object by name or designation, returning its J2000 position. Case, spaces, and punctuation are
+/// ignored, so `M31`, `m 31`, `Messier 31`, `NGC 224`, and `andromeda galaxy` all find the same object.
+pub fn lookup(name: &str) -> Option<RADec> {
+    let key = search_key(name);
+    builtin()
+        .into_iter()
+        .find(|target| target.lookup_names().any(|n| search_key(n) == key))
+        .and_then(|target| target.coord)
+}
+
+/// Built-in objects loosely matching `query`, best first: those with a name equal to it, then starting with it, then
+/// containing it, then starting with it but for a typo or two. Equally good matches are brightest first.
+pub fn search(query: &str) -> Vec<Target> {
+    let key = search_key(query);
+    if key.is_empty() {
+        return Vec::new();
+    }
+    let typos = key.len() / 4;
+    let score = |name: &str| {
+        let name = search_key(name);
+        if name == key {
+            Some(0)
+        } else if name.starts_with(&key) {
+            Some(1)
+        } else if name.contains(&key) {
+            Some(2)
+        } else {
+            let len = key.len();
+            let distance = (len.saturating_sub(typos)..=(len + typos).min(name.len()))
+                .map(|end| edit_distance(&name[..end], &key))
+                .min()?;
+            (distance <= typos).then_some(2 + distance)
+        }
+    };
+
+    let mut matches: Vec<_> = builtin()
+        .into_iter()
+        .filter_map(|target| Some((target.lookup_names().filter_map(score).min()?, target)))
+        .collect();
+    matches.sort_by(|(a, x), (b, y)| {
+        a.cmp(b)
+            .then(x.magnitude.unwrap_or(f64::MAX).total_cmp(&y.magnitude.unwrap_or(f64::MAX)))
+    });
+    matches.into_iter().map(|(_, target)| target).collect()
+}
+
+/// ASCII lower case letters and digits only, with `Messier` shortened to `M`.
+fn search_key(name: &str) -> String {
+    let key: String = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    match key.strip_prefix("messier") {
+        Some(number) if number.starts_with(|c: char| c.is_ascii_digit()) => format!("m{number}"),
+        _ => key,
+    }
+}
+
+/// Levenshtein distance: the fewest inserted, deleted, or changed bytes turning `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.bytes().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let next = (diagonal + usize::from(ca != cb))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_named_objects() {
+        let m31 = lookup("M31").unwrap();
+        for name in ["m 31", "Messier 31", "NGC 224", "andromeda galaxy"] {
+            assert_eq!(lookup(name), Some(m31), "{name}");
+        }
+        assert_eq!(lookup("vega"), Some(stars::find("Vega").unwrap().coord()));
+        assert_eq!(lookup("M 111"), None);
+        assert_eq!(messier::find(110).unwrap().ngc, Some(205));
+        assert!(messier::MESSIER.iter().enumerate().all(|(i, m)| m.number as usize == i + 1));
+
+        assert_eq!(search("orion")[0].name, "Orion Nebula");
+        // Exact, then the brightest starting with it.
+        let names: Vec<_> = search("M7").into_iter().map(|t| t.designations[0].clone()).collect();
+        assert_eq!(names[..2], ["M 7", "M 79"]);
+        assert_eq!(search("Betelguese")[0].name, "Betelgeuse");
+        assert!(search("xyzzy").is_empty());
+        assert!(search(" ").is_empty());
+    }
+}
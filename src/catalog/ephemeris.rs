@@ -0,0 +1,511 @@
+//! Positions of the Sun, the Moon, and the planets, for gotos without an observing list.
+//!
+//! The planets come from the mean orbital elements of JPL's "Approximate Positions of the Planets" (Standish), valid
+//! from 1800 to 2050, corrected for light time; the Moon from a Keplerian orbit with the largest perturbations
+//! (Schlyter), corrected for parallax at the observing site. Both are good to a few arcminutes, well inside the field
+//! of a finder, which is what a goto needs; aberration and the planets' tiny parallax are ignored.
+//!
+//! Pointing at the Sun can destroy the optics and blind whoever looks through them, so [`goto_body`] refuses it, and
+//! any body close to it, unless the caller passes [`SunSafety::Allow`]:
+//!
+//! ```
+//! use nexlib::catalog::ephemeris::{goto_body, Body, SunSafety};
+//! use nexlib::mount::SimMount;
+//! use nexlib::Location;
+//!
+//! let mut mount = SimMount::new();
+//! let site = Location { latitude: 45.0, longitude: 0.0 };
+//! goto_body(&mut mount, Body::Jupiter, &site, SunSafety::Refuse).ok();
+//! assert!(goto_body(&mut mount, Body::Sun, &site, SunSafety::Refuse).is_err());
+//! ```
+
+use crate::mount::transform::{angular_separation, local_sidereal_time};
+use crate::{Location, Mount, MountError, RADec};
+use chrono::{DateTime, Utc};
+use std::{fmt, io, str::FromStr};
+
+/// Least angle in degrees between the Sun and a body [`goto_body`] points at without [`SunSafety::Allow`].
+pub const SUN_AVOIDANCE: f64 = 10.0;
+
+/// Obliquity of the ecliptic at J2000, in degrees.
+const OBLIQUITY_J2000: f64 = 23.439_28;
+
+/// Speed of light in astronomical units per day.
+const LIGHT_AU_PER_DAY: f64 = 173.144_6;
+
+/// A solar system body [`Body::position`] can find.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Body {
+    Sun,
+    Moon,
+    Mercury,
+    Venus,
+    Mars,
+    Jupiter,
+    Saturn,
+    Uranus,
+    Neptune,
+}
+
+impl Body {
+    pub const ALL: [Body; 9] = [
+        Body::Sun,
+        Body::Moon,
+        Body::Mercury,
+        Body::Venus,
+        Body::Mars,
+        Body::Jupiter,
+        Body::Saturn,
+        Body::Uranus,
+        Body::Neptune,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Body::Sun => "Sun",
+            Body::Moon => "Moon",
+            Body::Mercury => "Mercury",
+            Body::Venus => "Venus",
+            Body::Mars => "Mars",
+            Body::Jupiter => "Jupiter",
+            Body::Saturn => "Saturn",
+            Body::Uranus => "Uranus",
+            Body::Neptune => "Neptune",
+        }
+    }
+
+    /// Position of date at `time` as seen from the center of the Earth.
+    pub fn geocentric(&self, time: DateTime<Utc>) -> RADec {
+        match self {
+            Body::Moon => moon(time).0,
+            Body::Sun => {
+                let earth = Elements::EARTH.heliocentric(centuries(time));
+                to_ra_dec([-earth[0], -earth[1], -earth[2]]).j2000_to_jnow(time)
+            }
+            planet => {
+                let t = centuries(time);
+                let earth = Elements::EARTH.heliocentric(t);
+                let elements = Elements::of(*planet);
+                let mut geo = sub(elements.heliocentric(t), earth);
+                // Seen where the planet was when the light left it.
+                let light_time = norm(geo) / LIGHT_AU_PER_DAY / 36_525.0;
+                geo = sub(elements.heliocentric(t - light_time), earth);
+                to_ra_dec(geo).j2000_to_jnow(time)
+            }
+        }
+    }
+
+    /// Position of date at `time` as seen from `site`, which differs from [`geocentric`](Self::geocentric) by up to
+    /// a degree for the Moon.
+    pub fn position(&self, site: &Location, time: DateTime<Utc>) -> RADec {
+        if *self != Body::Moon {
+            return self.geocentric(time);
+        }
+
+        let (coord, distance) = moon(time);
+        let (ra, dec) = (coord.ra.to_radians(), coord.dec.to_radians());
+        let lst = local_sidereal_time(time, site.longitude).to_radians();
+        let lat = site.latitude.to_radians();
+        // In Earth radii, from the observer, ignoring the flattening of the Earth.
+        let topo = [
+            distance * dec.cos() * ra.cos() - lat.cos() * lst.cos(),
+            distance * dec.cos() * ra.sin() - lat.cos() * lst.sin(),
+            distance * dec.sin() - lat.sin(),
+        ];
+        let (x, y, z) = (topo[0], topo[1], topo[2]);
+        RADec::new(
+            y.atan2(x).to_degrees().rem_euclid(360.0),
+            z.atan2(x.hypot(y)).to_degrees(),
+        )
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Body {
+    type Err = io::Error;
+
+    /// Parses a name in any case, e.g. `jupiter`.
+    fn from_str(s: &str) -> Result<Body, io::Error> {
+        Body::ALL
+            .into_iter()
+            .find(|body| body.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No solar system body named {s}."),
+                )
+            })
+    }
+}
+
+/// Whether [`goto_body`] may point at or near the Sun.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SunSafety {
+    /// Refuse the Sun and bodies within [`SUN_AVOIDANCE`] of it.
+    #[default]
+    Refuse,
+    /// Allow it, e.g. with a full aperture solar filter fitted and the finder covered.
+    Allow,
+}
+
+/// Starts a goto to `body` as seen from `site` at the mount's time.
+///
+/// Fails with `PermissionDenied` for the Sun, or a body within [`SUN_AVOIDANCE`] of it, unless `sun` is
+/// [`SunSafety::Allow`].
+pub fn goto_body<M: Mount + ?Sized>(
+    mount: &mut M,
+    body: Body,
+    site: &Location,
+    sun: SunSafety,
+) -> Result<(), MountError> {
+    let time = mount.get_time()?;
+    let coord = body.position(site, time);
+    if sun == SunSafety::Refuse {
+        let sun = Body::Sun.geocentric(time);
+        let separation = angular_separation(coord.ra, coord.dec, sun.ra, sun.dec);
+        if separation < SUN_AVOIDANCE {
+            return Err(MountError::other(
+                io::ErrorKind::PermissionDenied,
+                format!("{body} is {separation:.1}° from the Sun; pointing there needs explicit permission."),
+            ));
+        }
+    }
+    mount.goto_ra_dec(coord)
+}
+
+/// Julian centuries since J2000.0, close enough to terrestrial time for these approximations.
+fn centuries(time: DateTime<Utc>) -> f64 {
+    days(time) / 36_525.0
+}
+
+/// Days since J2000.0.
+fn days(time: DateTime<Utc>) -> f64 {
+    time.timestamp_millis() as f64 / 86_400_000.0 + 2_440_587.5 - 2_451_545.0
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn norm(v: [f64; 3]) -> f64 {
+    (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+}
+
+/// Converts a vector in J2000 ecliptic coordinates to J2000 right ascension and declination.
+fn to_ra_dec(v: [f64; 3]) -> RADec {
+    let eps = OBLIQUITY_J2000.to_radians();
+    let (x, y, z) = (
+        v[0],
+        v[1] * eps.cos() - v[2] * eps.sin(),
+        v[1] * eps.sin() + v[2] * eps.cos(),
+    );
+    RADec::new(
+        y.atan2(x).to_degrees().rem_euclid(360.0),
+        z.atan2(x.hypot(y)).to_degrees(),
+    )
+}
+
+/// Solves Kepler's equation for the eccentric anomaly, in radians, from the mean anomaly `m` in radians.
+fn eccentric_anomaly(m: f64, e: f64) -> f64 {
+    let mut ecc = m + e * m.sin();
+    for _ in 0..10 {
+        ecc -= (ecc - e * ecc.sin() - m) / (1.0 - e * ecc.cos());
+    }
+    ecc
+}
+
+/// Mean orbital elements at J2000 and their rates per century: semi-major axis in AU, eccentricity, inclination,
+/// mean longitude, longitude of perihelion, and longitude of the ascending node, in degrees.
+struct Elements([f64; 6], [f64; 6]);
+
+impl Elements {
+    /// The Earth-Moon barycenter, standing in for the Earth.
+    const EARTH: Elements = Elements(
+        [
+            1.000_002_61,
+            0.016_711_23,
+            -0.000_015_31,
+            100.464_571_66,
+            102.937_681_93,
+            0.0,
+        ],
+        [
+            0.000_005_62,
+            -0.000_043_92,
+            -0.012_946_68,
+            35_999.372_449_81,
+            0.323_273_64,
+            0.0,
+        ],
+    );
+
+    fn of(body: Body) -> Elements {
+        match body {
+            Body::Mercury => Elements(
+                [
+                    0.387_099_27,
+                    0.205_635_93,
+                    7.004_979_02,
+                    252.250_323_50,
+                    77.457_796_28,
+                    48.330_765_93,
+                ],
+                [
+                    0.000_000_37,
+                    0.000_019_06,
+                    -0.005_947_49,
+                    149_472.674_111_75,
+                    0.160_476_89,
+                    -0.125_340_81,
+                ],
+            ),
+            Body::Venus => Elements(
+                [
+                    0.723_335_66,
+                    0.006_776_72,
+                    3.394_676_05,
+                    181.979_099_50,
+                    131.602_467_18,
+                    76.679_842_55,
+                ],
+                [
+                    0.000_003_90,
+                    -0.000_041_07,
+                    -0.000_788_90,
+                    58_517.815_387_29,
+                    0.002_683_29,
+                    -0.277_694_18,
+                ],
+            ),
+            Body::Mars => Elements(
+                [
+                    1.523_710_34,
+                    0.093_394_10,
+                    1.849_691_42,
+                    -4.553_432_05,
+                    -23.943_629_59,
+                    49.559_538_91,
+                ],
+                [
+                    0.000_018_47,
+                    0.000_078_82,
+                    -0.008_131_31,
+                    19_140.302_684_99,
+                    0.444_410_88,
+                    -0.292_573_43,
+                ],
+            ),
+            Body::Jupiter => Elements(
+                [
+                    5.202_887_00,
+                    0.048_386_24,
+                    1.304_396_95,
+                    34.396_440_51,
+                    14.728_479_83,
+                    100.473_909_09,
+                ],
+                [
+                    -0.000_116_07,
+                    -0.000_132_53,
+                    -0.001_837_14,
+                    3_034.746_127_75,
+                    0.212_526_68,
+                    0.204_691_06,
+                ],
+            ),
+            Body::Saturn => Elements(
+                [
+                    9.536_675_94,
+                    0.053_861_79,
+                    2.485_991_87,
+                    49.954_244_23,
+                    92.598_878_31,
+                    113.662_424_48,
+                ],
+                [
+                    -0.001_250_60,
+                    -0.000_509_91,
+                    0.001_936_09,
+                    1_222.493_622_01,
+                    -0.418_972_16,
+                    -0.288_677_94,
+                ],
+            ),
+            Body::Uranus => Elements(
+                [
+                    19.189_164_64,
+                    0.047_257_44,
+                    0.772_637_83,
+                    313.238_104_51,
+                    170.954_276_30,
+                    74.016_925_03,
+                ],
+                [
+                    -0.001_961_76,
+                    -0.000_043_97,
+                    -0.002_429_39,
+                    428.482_027_85,
+                    0.408_052_81,
+                    0.042_405_89,
+                ],
+            ),
+            Body::Neptune => Elements(
+                [
+                    30.069_922_76,
+                    0.008_590_48,
+                    1.770_043_47,
+                    -55.120_029_69,
+                    44.964_762_27,
+                    131.784_225_74,
+                ],
+                [
+                    0.000_262_91,
+                    0.000_051_05,
+                    0.000_353_72,
+                    218.459_453_25,
+                    -0.322_414_64,
+                    -0.005_086_64,
+                ],
+            ),
+            Body::Sun | Body::Moon => unreachable!("{body} has no heliocentric orbit"),
+        }
+    }
+
+    /// Heliocentric position in AU in J2000 ecliptic coordinates, `t` centuries after J2000.
+    fn heliocentric(&self, t: f64) -> [f64; 3] {
+        let [a, e, i, l, peri, node] = std::array::from_fn(|k| self.0[k] + self.1[k] * t);
+        let (i, node) = (i.to_radians(), node.to_radians());
+        let w = (peri.to_radians()) - node;
+        let ecc = eccentric_anomaly((l - peri).to_radians(), e);
+        let (x, y) = (a * (ecc.cos() - e), a * (1.0 - e * e).sqrt() * ecc.sin());
+        [
+            (w.cos() * node.cos() - w.sin() * node.sin() * i.cos()) * x
+                - (w.sin() * node.cos() + w.cos() * node.sin() * i.cos()) * y,
+            (w.cos() * node.sin() + w.sin() * node.cos() * i.cos()) * x
+                + (w.cos() * node.cos() * i.cos() - w.sin() * node.sin()) * y,
+            w.sin() * i.sin() * x + w.cos() * i.sin() * y,
+        ]
+    }
+}
+
+/// Geocentric position of date of the Moon, and its distance in Earth radii.
+fn moon(time: DateTime<Utc>) -> (RADec, f64) {
+    // Schlyter counts days from 2000 January 0.0.
+    let d = days(time) + 1.5;
+    let node = 125.1228 - 0.052_953_808_3 * d;
+    let incl = 5.1454_f64.to_radians();
+    let peri = 318.0634 + 0.164_357_322_3 * d;
+    let (a, e) = (60.2666, 0.0549);
+    let m = 115.3654 + 13.064_992_950_9 * d;
+
+    let ecc = eccentric_anomaly(m.to_radians(), e);
+    let (xv, yv) = (a * (ecc.cos() - e), a * (1.0 - e * e).sqrt() * ecc.sin());
+    let (v, r) = (yv.atan2(xv), xv.hypot(yv));
+    let (n, u) = (node.to_radians(), v + peri.to_radians());
+    let x = r * (n.cos() * u.cos() - n.sin() * u.sin() * incl.cos());
+    let y = r * (n.sin() * u.cos() + n.cos() * u.sin() * incl.cos());
+    let z = r * u.sin() * incl.sin();
+    let mut lon = y.atan2(x).to_degrees();
+    let mut lat = z.atan2(x.hypot(y)).to_degrees();
+
+    // The largest perturbations, by the Sun.
+    let sun_m = 356.0470 + 0.985_600_258_5 * d;
+    let sun_l = sun_m + 282.9404 + 0.000_047_093_5 * d;
+    let moon_l = m + peri + node;
+    let elong = moon_l - sun_l;
+    let f = moon_l - node;
+    let sin = |deg: f64| deg.to_radians().sin();
+    let cos = |deg: f64| deg.to_radians().cos();
+    lon += -1.274 * sin(m - 2.0 * elong) + 0.658 * sin(2.0 * elong)
+        - 0.186 * sin(sun_m)
+        - 0.059 * sin(2.0 * m - 2.0 * elong)
+        - 0.057 * sin(m - 2.0 * elong + sun_m)
+        + 0.053 * sin(m + 2.0 * elong)
+        + 0.046 * sin(2.0 * elong - sun_m)
+        + 0.041 * sin(m - sun_m)
+        - 0.035 * sin(elong)
+        - 0.031 * sin(m + sun_m)
+        - 0.015 * sin(2.0 * f - 2.0 * elong)
+        + 0.011 * sin(m - 4.0 * elong);
+    lat += -0.173 * sin(f - 2.0 * elong)
+        - 0.055 * sin(m - f - 2.0 * elong)
+        - 0.046 * sin(m + f - 2.0 * elong)
+        + 0.033 * sin(f + 2.0 * elong)
+        + 0.017 * sin(2.0 * m + f);
+    let distance = r - 0.58 * cos(m - 2.0 * elong) - 0.46 * cos(2.0 * elong);
+
+    // From the ecliptic of date to the equator of date.
+    let eps = (23.4393 - 0.000_000_356_3 * d).to_radians();
+    let (lon, lat) = (lon.to_radians(), lat.to_radians());
+    let (x, y, z) = (
+        lat.cos() * lon.cos(),
+        lat.cos() * lon.sin() * eps.cos() - lat.sin() * eps.sin(),
+        lat.cos() * lon.sin() * eps.sin() + lat.sin() * eps.cos(),
+    );
+    let coord = RADec::new(
+        y.atan2(x).to_degrees().rem_euclid(360.0),
+        z.atan2(x.hypot(y)).to_degrees(),
+    );
+    (coord, distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use chrono::TimeZone;
+
+    #[test]
+    fn matches_meeus() {
+        // Apparent positions from Meeus, Astronomical Algorithms, examples 25.a, 33.a, and 47.a.
+        let cases = [
+            (
+                Body::Sun,
+                Utc.with_ymd_and_hms(1992, 10, 13, 0, 0, 0),
+                198.3808,
+                -7.7836,
+            ),
+            (
+                Body::Venus,
+                Utc.with_ymd_and_hms(1992, 12, 20, 0, 0, 0),
+                316.1727,
+                -18.8880,
+            ),
+            (
+                Body::Moon,
+                Utc.with_ymd_and_hms(1992, 4, 12, 0, 0, 0),
+                134.6885,
+                13.7684,
+            ),
+        ];
+        for (body, time, ra, dec) in cases {
+            let coord = body.geocentric(time.unwrap());
+            let error = angular_separation(coord.ra, coord.dec, ra, dec);
+            assert!(error < 0.05, "{body}: {coord} is {error}° off");
+        }
+
+        // The Moon seen from mid-northern latitudes is south of its geocentric position.
+        let time = Utc.with_ymd_and_hms(1992, 4, 12, 0, 0, 0).unwrap();
+        let site = Location {
+            latitude: 45.0,
+            longitude: 0.0,
+        };
+        let topocentric = Body::Moon.position(&site, time);
+        assert!(topocentric.dec < Body::Moon.geocentric(time).dec - 0.3);
+        assert_eq!("jupiter".parse::<Body>().unwrap(), Body::Jupiter);
+        assert!("Pluto".parse::<Body>().is_err());
+
+        let mut mount = SimMount::new().manual_clock(time);
+        let err = goto_body(&mut mount, Body::Sun, &site, SunSafety::Refuse).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        goto_body(&mut mount, Body::Sun, &site, SunSafety::Allow).unwrap();
+        goto_body(&mut mount, Body::Moon, &site, SunSafety::Refuse).unwrap();
+        assert!(mount.goto_in_progress().unwrap());
+    }
+}
@@ -0,0 +1,330 @@
+//! Importers for SkySafari `.skylist` files and generic CSV observing lists.
+//!
+//! SkySafari lists identify objects by name and catalog number only, so imported targets generally need their
+//! coordinates resolved before use (see [`Target::needs_resolution`]).
+//!
+//! CSV lists need a header row. Recognized columns, in any order and case:
+//!
+//! - `name` (or `object`) - required.
+//! - `ra` - right ascension in decimal degrees, or sexagesimal hours (`05:35:17.3` or `05h35m17.3s`).
+//! - `ra_hours` - right ascension in decimal hours.
+//! - `dec` - declination in decimal degrees or sexagesimal degrees (`-05:23:28` or `-05d23m28s`).
+//! - `magnitude` (or `mag`), `type`, and `notes` (or `comment`).
+//!
+//! Other columns are ignored.
+
+use super::Target;
+use crate::RADec;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+fn invalid(line: usize, msg: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Line {}: {}", line, msg),
+    )
+}
+
+/// Reads an observing list, choosing the format from the file extension.
+pub fn read_list(path: &Path) -> Result<Vec<Target>, io::Error> {
+    let text = fs::read_to_string(path)?;
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("skylist") => parse_skylist(&text),
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => parse_csv(&text),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unsupported observing list format: {}", path.display()),
+        )),
+    }
+}
+
+/// Parses a SkySafari `.skylist` observing list.
+pub fn parse_skylist(text: &str) -> Result<Vec<Target>, io::Error> {
+    let mut targets = Vec::new();
+    let mut common_name: Option<String> = None;
+    let mut current: Option<Target> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+
+        match (key.trim(), current.as_mut()) {
+            ("SkyObject", None) if value == "BeginObject" => {
+                current = Some(Target::new(""));
+                common_name = None;
+            }
+            ("SkyObject", Some(_)) => return Err(invalid(i + 1, "Nested SkyObject.")),
+            ("EndObject", Some(_)) if value == "SkyObject" => {
+                let mut target = current.take().unwrap();
+                target.name = match common_name.take() {
+                    Some(name) => name,
+                    None => match target.designations.first() {
+                        Some(designation) => designation.clone(),
+                        None => return Err(invalid(i + 1, "SkyObject has no name.")),
+                    },
+                };
+                targets.push(target);
+            }
+            ("EndObject", None) => return Err(invalid(i + 1, "EndObject outside SkyObject.")),
+            ("CommonName", Some(_)) if !value.is_empty() => common_name = Some(value.to_owned()),
+            ("CatalogNumber", Some(target)) if !value.is_empty() => {
+                target.designations.push(value.to_owned())
+            }
+            ("Comment", Some(target)) if !value.is_empty() => target.notes = Some(value.to_owned()),
+            _ => (),
+        }
+    }
+
+    if current.is_some() {
+        return Err(invalid(text.lines().count(), "Unterminated SkyObject."));
+    }
+
+    Ok(targets)
+}
+
+/// Splits one CSV record, honoring double-quoted fields.
+fn split_record(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_owned()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_owned());
+
+    fields
+}
+
+/// Parses a sexagesimal angle such as `-05:23:28`, `05h35m17.3s`, or `-5 23 28` into decimal units.
+fn parse_sexagesimal(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let parts: Vec<f64> = s
+        .split([':', ' ', 'h', 'd', 'm', 's', '°', '\'', '"'])
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse::<f64>().ok())
+        .collect::<Option<_>>()?;
+
+    if parts.is_empty() || parts.len() > 3 {
+        return None;
+    }
+
+    Some(
+        sign * parts
+            .iter()
+            .zip([1.0, 60.0, 3600.0])
+            .map(|(v, div)| v / div)
+            .sum::<f64>(),
+    )
+}
+
+fn is_sexagesimal(s: &str) -> bool {
+    s.contains([':', ' ', 'h', 'd', 'm', '°'])
+}
+
+/// Parses right ascension in the `ra` column: decimal degrees or sexagesimal hours.
+fn parse_ra(s: &str) -> Option<f64> {
+    if is_sexagesimal(s) {
+        parse_sexagesimal(s).map(|h| h * 15.0)
+    } else {
+        s.parse().ok()
+    }
+}
+
+#[derive(Default)]
+struct Columns {
+    name: Option<usize>,
+    ra: Option<usize>,
+    ra_hours: Option<usize>,
+    dec: Option<usize>,
+    magnitude: Option<usize>,
+    kind: Option<usize>,
+    notes: Option<usize>,
+}
+
+impl Columns {
+    fn from_header(header: &[String]) -> Columns {
+        let mut columns = Columns::default();
+        for (i, col) in header.iter().enumerate() {
+            let slot = match col.to_lowercase().as_str() {
+                "name" | "object" => &mut columns.name,
+                "ra" => &mut columns.ra,
+                "ra_hours" => &mut columns.ra_hours,
+                "dec" => &mut columns.dec,
+                "magnitude" | "mag" => &mut columns.magnitude,
+                "type" => &mut columns.kind,
+                "notes" | "comment" => &mut columns.notes,
+                _ => continue,
+            };
+            slot.get_or_insert(i);
+        }
+        columns
+    }
+}
+
+/// Parses a CSV observing list with a header row.
+pub fn parse_csv(text: &str) -> Result<Vec<Target>, io::Error> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty());
+
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let columns = Columns::from_header(&split_record(header.trim_start_matches('\u{feff}')));
+    let Some(name_col) = columns.name else {
+        return Err(invalid(1, "Missing name column."));
+    };
+
+    let mut targets = Vec::new();
+    for (i, line) in lines {
+        let fields = split_record(line);
+        let field = |col: Option<usize>| {
+            col.and_then(|c| fields.get(c))
+                .map(String::as_str)
+                .filter(|f| !f.is_empty())
+        };
+
+        let Some(name) = field(Some(name_col)) else {
+            return Err(invalid(i + 1, "Missing name."));
+        };
+        let mut target = Target::new(name);
+
+        let ra = match (field(columns.ra_hours), field(columns.ra)) {
+            (Some(h), _) => Some(h.parse::<f64>().ok().map(|h| h * 15.0)),
+            (None, Some(ra)) => Some(parse_ra(ra)),
+            (None, None) => None,
+        };
+        let dec = field(columns.dec).map(|d| {
+            if is_sexagesimal(d) {
+                parse_sexagesimal(d)
+            } else {
+                d.parse().ok()
+            }
+        });
+
+        target.coord = match (ra, dec) {
+            (Some(Some(ra)), Some(Some(dec))) if (-90.0..=90.0).contains(&dec) => {
+                Some(RADec::new(ra.rem_euclid(360.0), dec))
+            }
+            (None, None) => None,
+            _ => return Err(invalid(i + 1, "Invalid or incomplete coordinates.")),
+        };
+
+        if let Some(mag) = field(columns.magnitude) {
+            target.magnitude = Some(
+                mag.parse()
+                    .map_err(|_| invalid(i + 1, format!("Invalid magnitude {mag}.")))?,
+            );
+        }
+        target.kind = field(columns.kind).map(str::to_owned);
+        target.notes = field(columns.notes).map(str::to_owned);
+
+        targets.push(target);
+    }
+
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SKYLIST: &str = "SkySafariObservingListVersion=3.0
+SortedBy=Default Order
+SkyObject=BeginObject
+\tObjectID=4,-1,-1
+\tCommonName=Andromeda Galaxy
+\tCatalogNumber=M 31
+\tCatalogNumber=NGC 224
+\tDefaultIndex=0
+EndObject=SkyObject
+SkyObject=BeginObject
+\tObjectID=4,-1,-1
+\tCatalogNumber=NGC 6946
+\tComment=Fireworks Galaxy
+EndObject=SkyObject
+";
+
+    #[test]
+    fn skylist() {
+        let targets = parse_skylist(SKYLIST).unwrap();
+        assert_eq!(targets.len(), 2);
+
+        assert_eq!(targets[0].name, "Andromeda Galaxy");
+        assert_eq!(targets[0].designations, ["M 31", "NGC 224"]);
+        assert!(targets[0].needs_resolution());
+        assert_eq!(
+            targets[0].lookup_names().collect::<Vec<_>>(),
+            ["M 31", "NGC 224", "Andromeda Galaxy"]
+        );
+
+        assert_eq!(targets[1].name, "NGC 6946");
+        assert_eq!(targets[1].notes.as_deref(), Some("Fireworks Galaxy"));
+    }
+
+    #[test]
+    fn skylist_unterminated() {
+        let e = parse_skylist("SkyObject=BeginObject\nCommonName=Vega\n").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn csv() {
+        let text = "Name,RA,Dec,Mag,Notes\n\
+                    \"Orion Nebula, M 42\",05:35:17.3,-05:23:28,4.0,\"Trapezium \"\"cluster\"\"\"\n\
+                    Vega,279.2347,38.7837,,\n\
+                    Barnard's Star,,,9.5,\n";
+        let targets = parse_csv(text).unwrap();
+        assert_eq!(targets.len(), 3);
+
+        let m42 = &targets[0];
+        assert_eq!(m42.name, "Orion Nebula, M 42");
+        let coord = m42.coord.unwrap();
+        assert!((coord.ra - 83.822083).abs() < 1e-5);
+        assert!((coord.dec + 5.391111).abs() < 1e-5);
+        assert_eq!(m42.magnitude, Some(4.0));
+        assert_eq!(m42.notes.as_deref(), Some("Trapezium \"cluster\""));
+
+        assert_eq!(targets[1].coord.unwrap().ra, 279.2347);
+        assert!(targets[2].needs_resolution());
+    }
+
+    #[test]
+    fn csv_errors() {
+        assert!(parse_csv("RA,Dec\n1,2\n").is_err());
+        assert!(parse_csv("name,ra,dec\nX,10,\n").is_err());
+        assert!(parse_csv("name,ra,dec\nX,10,95\n").is_err());
+        assert!(parse_csv("name,ra_hours,dec\nX,12.5,-30\n")
+            .map(|t| t[0].coord.unwrap().ra == 187.5)
+            .unwrap());
+    }
+
+    #[test]
+    fn sexagesimal() {
+        assert_eq!(parse_sexagesimal("-00:30:00"), Some(-0.5));
+        assert_eq!(parse_sexagesimal("12h30m"), Some(12.5));
+        assert_eq!(parse_sexagesimal("+10°30'"), Some(10.5));
+        assert_eq!(parse_sexagesimal("1:2:3:4"), None);
+    }
+}
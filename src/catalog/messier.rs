@@ -0,0 +1,658 @@
+//! The Messier catalog, for gotos to the best known deep sky objects without an observing list.
+//!
+//! Positions are J2000 in degrees, to about 0.01 degrees, like [`stars`](super::stars); convert them with
+//! [`RADec::j2000_to_jnow`] before sending them to a mount.
+
+use super::Target;
+use crate::RADec;
+
+/// An object in [`MESSIER`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MessierObject {
+    /// Messier number, e.g. 31 for M 31.
+    pub number: u8,
+    /// New General Catalogue number, for the objects which have one.
+    pub ngc: Option<u16>,
+    /// Common name, e.g. `Andromeda Galaxy`.
+    pub name: Option<&'static str>,
+    /// Object type, e.g. `Galaxy`.
+    pub kind: &'static str,
+    /// J2000 right ascension in degrees.
+    pub ra: f64,
+    /// J2000 declination in degrees.
+    pub dec: f64,
+    /// Visual magnitude.
+    pub magnitude: f64,
+}
+
+impl MessierObject {
+    /// J2000 position.
+    pub fn coord(&self) -> RADec {
+        RADec::new(self.ra, self.dec)
+    }
+
+    /// Designations, e.g. `M 31` and `NGC 224`.
+    pub fn designations(&self) -> Vec<String> {
+        std::iter::once(format!("M {}", self.number))
+            .chain(self.ngc.map(|ngc| format!("NGC {ngc}")))
+            .collect()
+    }
+
+    /// A target named by the common name if there is one, else by the Messier designation.
+    pub fn to_target(&self) -> Target {
+        let designations = self.designations();
+        let name = self
+            .name
+            .map_or_else(|| designations[0].clone(), str::to_owned);
+        Target {
+            designations,
+            coord: Some(self.coord()),
+            magnitude: Some(self.magnitude),
+            kind: Some(self.kind.to_owned()),
+            ..Target::new(&name)
+        }
+    }
+}
+
+const fn messier(
+    number: u8,
+    ngc: Option<u16>,
+    name: Option<&'static str>,
+    kind: &'static str,
+    ra: f64,
+    dec: f64,
+    magnitude: f64,
+) -> MessierObject {
+    MessierObject {
+        number,
+        ngc,
+        name,
+        kind,
+        ra,
+        dec,
+        magnitude,
+    }
+}
+
+/// All 110 Messier objects, in catalog order, so `MESSIER[n - 1]` is M n.
+pub const MESSIER: &[MessierObject] = &[
+    messier(
+        1,
+        Some(1952),
+        Some("Crab Nebula"),
+        "Supernova remnant",
+        83.625,
+        22.017,
+        8.4,
+    ),
+    messier(
+        2,
+        Some(7089),
+        None,
+        "Globular cluster",
+        323.375,
+        -0.817,
+        6.5,
+    ),
+    messier(
+        3,
+        Some(5272),
+        None,
+        "Globular cluster",
+        205.550,
+        28.383,
+        6.2,
+    ),
+    messier(
+        4,
+        Some(6121),
+        None,
+        "Globular cluster",
+        245.900,
+        -26.533,
+        5.6,
+    ),
+    messier(5, Some(5904), None, "Globular cluster", 229.650, 2.083, 5.6),
+    messier(
+        6,
+        Some(6405),
+        Some("Butterfly Cluster"),
+        "Open cluster",
+        265.025,
+        -32.217,
+        4.2,
+    ),
+    messier(
+        7,
+        Some(6475),
+        Some("Ptolemy Cluster"),
+        "Open cluster",
+        268.475,
+        -34.817,
+        3.3,
+    ),
+    messier(
+        8,
+        Some(6523),
+        Some("Lagoon Nebula"),
+        "Nebula",
+        270.950,
+        -24.383,
+        6.0,
+    ),
+    messier(
+        9,
+        Some(6333),
+        None,
+        "Globular cluster",
+        259.800,
+        -18.517,
+        7.7,
+    ),
+    messier(
+        10,
+        Some(6254),
+        None,
+        "Globular cluster",
+        254.275,
+        -4.100,
+        6.6,
+    ),
+    messier(
+        11,
+        Some(6705),
+        Some("Wild Duck Cluster"),
+        "Open cluster",
+        282.775,
+        -6.267,
+        5.8,
+    ),
+    messier(
+        12,
+        Some(6218),
+        None,
+        "Globular cluster",
+        251.800,
+        -1.950,
+        6.7,
+    ),
+    messier(
+        13,
+        Some(6205),
+        Some("Hercules Cluster"),
+        "Globular cluster",
+        250.425,
+        36.467,
+        5.8,
+    ),
+    messier(
+        14,
+        Some(6402),
+        None,
+        "Globular cluster",
+        264.400,
+        -3.250,
+        7.6,
+    ),
+    messier(
+        15,
+        Some(7078),
+        None,
+        "Globular cluster",
+        322.500,
+        12.167,
+        6.2,
+    ),
+    messier(
+        16,
+        Some(6611),
+        Some("Eagle Nebula"),
+        "Nebula",
+        274.700,
+        -13.783,
+        6.0,
+    ),
+    messier(
+        17,
+        Some(6618),
+        Some("Omega Nebula"),
+        "Nebula",
+        275.200,
+        -16.183,
+        6.0,
+    ),
+    messier(18, Some(6613), None, "Open cluster", 274.975, -17.133, 6.9),
+    messier(
+        19,
+        Some(6273),
+        None,
+        "Globular cluster",
+        255.650,
+        -26.267,
+        6.8,
+    ),
+    messier(
+        20,
+        Some(6514),
+        Some("Trifid Nebula"),
+        "Nebula",
+        270.650,
+        -23.033,
+        6.3,
+    ),
+    messier(21, Some(6531), None, "Open cluster", 271.150, -22.500, 5.9),
+    messier(
+        22,
+        Some(6656),
+        None,
+        "Globular cluster",
+        279.100,
+        -23.900,
+        5.1,
+    ),
+    messier(23, Some(6494), None, "Open cluster", 269.200, -19.017, 5.5),
+    messier(
+        24,
+        None,
+        Some("Sagittarius Star Cloud"),
+        "Star cloud",
+        274.225,
+        -18.483,
+        4.6,
+    ),
+    messier(25, None, None, "Open cluster", 277.900, -19.250, 4.6),
+    messier(26, Some(6694), None, "Open cluster", 281.300, -9.400, 8.0),
+    messier(
+        27,
+        Some(6853),
+        Some("Dumbbell Nebula"),
+        "Planetary nebula",
+        299.900,
+        22.717,
+        7.4,
+    ),
+    messier(
+        28,
+        Some(6626),
+        None,
+        "Globular cluster",
+        276.125,
+        -24.867,
+        6.8,
+    ),
+    messier(29, Some(6913), None, "Open cluster", 305.975, 38.533, 6.6),
+    messier(
+        30,
+        Some(7099),
+        None,
+        "Globular cluster",
+        325.100,
+        -23.183,
+        7.2,
+    ),
+    messier(
+        31,
+        Some(224),
+        Some("Andromeda Galaxy"),
+        "Galaxy",
+        10.675,
+        41.267,
+        3.4,
+    ),
+    messier(32, Some(221), None, "Galaxy", 10.675, 40.867, 8.1),
+    messier(
+        33,
+        Some(598),
+        Some("Triangulum Galaxy"),
+        "Galaxy",
+        23.475,
+        30.650,
+        5.7,
+    ),
+    messier(34, Some(1039), None, "Open cluster", 40.500, 42.783, 5.5),
+    messier(35, Some(2168), None, "Open cluster", 92.225, 24.333, 5.3),
+    messier(36, Some(1960), None, "Open cluster", 84.025, 34.133, 6.3),
+    messier(37, Some(2099), None, "Open cluster", 88.100, 32.550, 6.2),
+    messier(38, Some(1912), None, "Open cluster", 82.100, 35.833, 7.4),
+    messier(39, Some(7092), None, "Open cluster", 323.050, 48.433, 4.6),
+    messier(
+        40,
+        None,
+        Some("Winnecke 4"),
+        "Double star",
+        185.600,
+        58.083,
+        8.4,
+    ),
+    messier(41, Some(2287), None, "Open cluster", 101.500, -20.733, 4.6),
+    messier(
+        42,
+        Some(1976),
+        Some("Orion Nebula"),
+        "Nebula",
+        83.850,
+        -5.450,
+        4.0,
+    ),
+    messier(
+        43,
+        Some(1982),
+        Some("De Mairan's Nebula"),
+        "Nebula",
+        83.900,
+        -5.267,
+        9.0,
+    ),
+    messier(
+        44,
+        Some(2632),
+        Some("Beehive Cluster"),
+        "Open cluster",
+        130.025,
+        19.983,
+        3.7,
+    ),
+    messier(
+        45,
+        None,
+        Some("Pleiades"),
+        "Open cluster",
+        56.750,
+        24.117,
+        1.6,
+    ),
+    messier(46, Some(2437), None, "Open cluster", 115.450, -14.817, 6.0),
+    messier(47, Some(2422), None, "Open cluster", 114.150, -14.500, 5.2),
+    messier(48, Some(2548), None, "Open cluster", 123.450, -5.800, 5.5),
+    messier(49, Some(4472), None, "Galaxy", 187.450, 8.000, 8.4),
+    messier(50, Some(2323), None, "Open cluster", 105.800, -8.333, 6.3),
+    messier(
+        51,
+        Some(5194),
+        Some("Whirlpool Galaxy"),
+        "Galaxy",
+        202.475,
+        47.200,
+        8.4,
+    ),
+    messier(52, Some(7654), None, "Open cluster", 351.050, 61.583, 7.3),
+    messier(
+        53,
+        Some(5024),
+        None,
+        "Globular cluster",
+        198.225,
+        18.167,
+        7.6,
+    ),
+    messier(
+        54,
+        Some(6715),
+        None,
+        "Globular cluster",
+        283.775,
+        -30.483,
+        7.6,
+    ),
+    messier(
+        55,
+        Some(6809),
+        None,
+        "Globular cluster",
+        295.000,
+        -30.967,
+        6.3,
+    ),
+    messier(
+        56,
+        Some(6779),
+        None,
+        "Globular cluster",
+        289.150,
+        30.183,
+        8.3,
+    ),
+    messier(
+        57,
+        Some(6720),
+        Some("Ring Nebula"),
+        "Planetary nebula",
+        283.400,
+        33.033,
+        8.8,
+    ),
+    messier(58, Some(4579), None, "Galaxy", 189.425, 11.817, 9.7),
+    messier(59, Some(4621), None, "Galaxy", 190.500, 11.650, 9.6),
+    messier(60, Some(4649), None, "Galaxy", 190.925, 11.550, 8.8),
+    messier(61, Some(4303), None, "Galaxy", 185.475, 4.467, 9.7),
+    messier(
+        62,
+        Some(6266),
+        None,
+        "Globular cluster",
+        255.300,
+        -30.117,
+        6.5,
+    ),
+    messier(
+        63,
+        Some(5055),
+        Some("Sunflower Galaxy"),
+        "Galaxy",
+        198.950,
+        42.033,
+        8.6,
+    ),
+    messier(
+        64,
+        Some(4826),
+        Some("Black Eye Galaxy"),
+        "Galaxy",
+        194.175,
+        21.683,
+        8.5,
+    ),
+    messier(65, Some(3623), None, "Galaxy", 169.725, 13.083, 9.3),
+    messier(66, Some(3627), None, "Galaxy", 170.050, 12.983, 8.9),
+    messier(67, Some(2682), None, "Open cluster", 132.600, 11.817, 6.1),
+    messier(
+        68,
+        Some(4590),
+        None,
+        "Globular cluster",
+        189.875,
+        -26.750,
+        7.8,
+    ),
+    messier(
+        69,
+        Some(6637),
+        None,
+        "Globular cluster",
+        277.850,
+        -32.350,
+        7.6,
+    ),
+    messier(
+        70,
+        Some(6681),
+        None,
+        "Globular cluster",
+        280.800,
+        -32.300,
+        7.9,
+    ),
+    messier(
+        71,
+        Some(6838),
+        None,
+        "Globular cluster",
+        298.450,
+        18.783,
+        8.2,
+    ),
+    messier(
+        72,
+        Some(6981),
+        None,
+        "Globular cluster",
+        313.375,
+        -12.533,
+        9.3,
+    ),
+    messier(73, Some(6994), None, "Asterism", 314.725, -12.633, 9.0),
+    messier(74, Some(628), None, "Galaxy", 24.175, 15.783, 9.4),
+    messier(
+        75,
+        Some(6864),
+        None,
+        "Globular cluster",
+        301.525,
+        -21.917,
+        8.5,
+    ),
+    messier(
+        76,
+        Some(650),
+        Some("Little Dumbbell Nebula"),
+        "Planetary nebula",
+        25.600,
+        51.567,
+        10.1,
+    ),
+    messier(77, Some(1068), None, "Galaxy", 40.675, -0.017, 8.9),
+    messier(78, Some(2068), None, "Nebula", 86.675, 0.050, 8.3),
+    messier(
+        79,
+        Some(1904),
+        None,
+        "Globular cluster",
+        81.125,
+        -24.550,
+        7.7,
+    ),
+    messier(
+        80,
+        Some(6093),
+        None,
+        "Globular cluster",
+        244.250,
+        -22.983,
+        7.3,
+    ),
+    messier(
+        81,
+        Some(3031),
+        Some("Bode's Galaxy"),
+        "Galaxy",
+        148.900,
+        69.067,
+        6.9,
+    ),
+    messier(
+        82,
+        Some(3034),
+        Some("Cigar Galaxy"),
+        "Galaxy",
+        148.950,
+        69.683,
+        8.4,
+    ),
+    messier(
+        83,
+        Some(5236),
+        Some("Southern Pinwheel Galaxy"),
+        "Galaxy",
+        204.250,
+        -29.867,
+        7.6,
+    ),
+    messier(84, Some(4374), None, "Galaxy", 186.275, 12.883, 9.1),
+    messier(85, Some(4382), None, "Galaxy", 186.350, 18.183, 9.1),
+    messier(86, Some(4406), None, "Galaxy", 186.550, 12.950, 8.9),
+    messier(
+        87,
+        Some(4486),
+        Some("Virgo A"),
+        "Galaxy",
+        187.700,
+        12.383,
+        8.6,
+    ),
+    messier(88, Some(4501), None, "Galaxy", 188.000, 14.417, 9.6),
+    messier(89, Some(4552), None, "Galaxy", 188.925, 12.550, 9.8),
+    messier(90, Some(4569), None, "Galaxy", 189.200, 13.167, 9.5),
+    messier(91, Some(4548), None, "Galaxy", 188.850, 14.500, 10.2),
+    messier(
+        92,
+        Some(6341),
+        None,
+        "Globular cluster",
+        259.275,
+        43.133,
+        6.4,
+    ),
+    messier(93, Some(2447), None, "Open cluster", 116.150, -23.867, 6.0),
+    messier(94, Some(4736), None, "Galaxy", 192.725, 41.117, 8.2),
+    messier(95, Some(3351), None, "Galaxy", 161.000, 11.700, 9.7),
+    messier(96, Some(3368), None, "Galaxy", 161.700, 11.817, 9.2),
+    messier(
+        97,
+        Some(3587),
+        Some("Owl Nebula"),
+        "Planetary nebula",
+        168.700,
+        55.017,
+        9.9,
+    ),
+    messier(98, Some(4192), None, "Galaxy", 183.450, 14.900, 10.1),
+    messier(99, Some(4254), None, "Galaxy", 184.700, 14.417, 9.9),
+    messier(100, Some(4321), None, "Galaxy", 185.725, 15.817, 9.3),
+    messier(
+        101,
+        Some(5457),
+        Some("Pinwheel Galaxy"),
+        "Galaxy",
+        210.800,
+        54.350,
+        7.9,
+    ),
+    messier(
+        102,
+        Some(5866),
+        Some("Spindle Galaxy"),
+        "Galaxy",
+        226.625,
+        55.767,
+        9.9,
+    ),
+    messier(103, Some(581), None, "Open cluster", 23.300, 60.700, 7.4),
+    messier(
+        104,
+        Some(4594),
+        Some("Sombrero Galaxy"),
+        "Galaxy",
+        190.000,
+        -11.617,
+        8.0,
+    ),
+    messier(105, Some(3379), None, "Galaxy", 161.950, 12.583, 9.3),
+    messier(106, Some(4258), None, "Galaxy", 184.750, 47.300, 8.4),
+    messier(
+        107,
+        Some(6171),
+        None,
+        "Globular cluster",
+        248.125,
+        -13.050,
+        7.9,
+    ),
+    messier(108, Some(3556), None, "Galaxy", 167.875, 55.667, 10.0),
+    messier(109, Some(3992), None, "Galaxy", 179.400, 53.383, 9.8),
+    messier(110, Some(205), None, "Galaxy", 10.100, 41.683, 8.5),
+];
+
+/// Finds a Messier object by number.
+pub fn find(number: u8) -> Option<&'static MessierObject> {
+    MESSIER.get((number as usize).checked_sub(1)?)
+}
@@ -0,0 +1,220 @@
+//! Online name resolution through the CDS Sesame service.
+//!
+//! Sesame queries SIMBAD, NED, and VizieR in turn, so it can resolve nearly any designation or common name
+//! ("Barnard's Star", "NGC 6946", "HD 209458") to J2000 coordinates. Successful lookups are cached in memory and, if
+//! a cache file is configured, on disk, so that each name is only looked up once. Use it as a fallback for targets the
+//! local catalog cannot place:
+//!
+//! ```ignore
+//! let resolver = SesameResolver::new().cache_file("sesame.tsv")?;
+//! for target in targets.iter_mut().filter(|t| t.needs_resolution()) {
+//!     resolver.resolve_target(target).await?;
+//! }
+//! ```
+
+use super::Target;
+use crate::RADec;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Sesame endpoint returning plain text, querying SIMBAD, NED, then VizieR.
+pub const DEFAULT_URL: &str = "https://cds.unistra.fr/cgi-bin/nph-sesame/-o/SNV";
+
+/// Time to wait for Sesame to answer.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Normalizes a name for use as a cache key, so `ngc  6946` and `NGC 6946` share an entry.
+fn cache_key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+/// Extracts the J2000 position from a Sesame plain-text response, e.g. `%J 10.68470833 +41.26875000 = 00:42:44.33`.
+fn parse_response(text: &str) -> Option<RADec> {
+    text.lines().find_map(|line| {
+        let mut fields = line.strip_prefix("%J ")?.split_whitespace();
+        let ra = fields.next()?.parse().ok()?;
+        let dec = fields.next()?.parse().ok()?;
+        Some(RADec::new(ra, dec))
+    })
+}
+
+fn parse_cache_line(line: &str) -> Option<(String, RADec)> {
+    let mut fields = line.split('\t');
+    let name = fields.next()?;
+    let ra = fields.next()?.parse().ok()?;
+    let dec = fields.next()?.parse().ok()?;
+    Some((name.to_owned(), RADec::new(ra, dec)))
+}
+
+/// Resolves object names to J2000 coordinates using CDS Sesame.
+pub struct SesameResolver {
+    client: reqwest::Client,
+    url: String,
+    cache: Mutex<HashMap<String, RADec>>,
+    cache_file: Option<PathBuf>,
+}
+
+impl Default for SesameResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SesameResolver {
+    pub fn new() -> SesameResolver {
+        SesameResolver {
+            client: reqwest::Client::builder()
+                .timeout(DEFAULT_TIMEOUT)
+                .build()
+                .expect("Default HTTP client configuration is valid."),
+            url: DEFAULT_URL.to_owned(),
+            cache: Mutex::new(HashMap::new()),
+            cache_file: None,
+        }
+    }
+
+    /// Uses a different Sesame mirror, e.g. `https://vizier.cfa.harvard.edu/viz-bin/nph-sesame/-o/SNV`.
+    pub fn url(mut self, url: &str) -> SesameResolver {
+        self.url = url.to_owned();
+        self
+    }
+
+    /// Persists resolved names to `path`, loading any entries it already holds.
+    pub fn cache_file<P: AsRef<Path>>(mut self, path: P) -> Result<SesameResolver, io::Error> {
+        let path = path.as_ref();
+
+        match fs::read_to_string(path) {
+            Ok(text) => self
+                .cache
+                .get_mut()
+                .unwrap()
+                .extend(text.lines().filter_map(parse_cache_line)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+
+        self.cache_file = Some(path.to_owned());
+        Ok(self)
+    }
+
+    fn remember(&self, key: String, coord: RADec) -> Result<(), io::Error> {
+        if let Some(path) = &self.cache_file {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}\t{}\t{}", key, coord.ra, coord.dec)?;
+        }
+
+        self.cache.lock().unwrap().insert(key, coord);
+        Ok(())
+    }
+
+    /// Looks up `name`, returning `None` if Sesame does not know it.
+    pub async fn resolve(&self, name: &str) -> Result<Option<RADec>, io::Error> {
+        let key = cache_key(name);
+        if let Some(coord) = self.cache.lock().unwrap().get(&key) {
+            return Ok(Some(*coord));
+        }
+
+        let mut url = reqwest::Url::parse(&self.url)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        url.set_query(Some(name.trim()));
+
+        log::debug!("Resolving {:?} via {}", name, url);
+
+        let text = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(io::Error::other)?
+            .text()
+            .await
+            .map_err(io::Error::other)?;
+
+        match parse_response(&text) {
+            Some(coord) => {
+                self.remember(key, coord)?;
+                Ok(Some(coord))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Fills in the coordinates of `target` from the first of its names Sesame recognizes.
+    ///
+    /// Returns whether the target now has coordinates. Targets which already have them are left unchanged.
+    pub async fn resolve_target(&self, target: &mut Target) -> Result<bool, io::Error> {
+        if target.coord.is_some() {
+            return Ok(true);
+        }
+
+        let names: Vec<String> = target.lookup_names().map(str::to_owned).collect();
+        for name in &names {
+            if let Some(coord) = self.resolve(name).await? {
+                target.coord = Some(coord);
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = "# M 31\t#Q123
+#=S=Simbad (via url):    1
+%@ 10.68470833 +41.26875000
+%J 10.68470833 +41.26875000 = 00:42:44.33 +41:16:07.5
+%V z 1 -0.001001 [0.000010] D 2002LEDA.........0P
+";
+
+    #[test]
+    fn parses_response() {
+        let coord = parse_response(RESPONSE).unwrap();
+        assert_eq!(coord.ra, 10.68470833);
+        assert_eq!(coord.dec, 41.26875);
+
+        assert!(parse_response("# Nothing\n#! *** Nothing found *** \n").is_none());
+    }
+
+    #[test]
+    fn cache_keys_are_normalized() {
+        assert_eq!(cache_key("  ngc   6946 "), "NGC 6946");
+        assert_eq!(cache_key("Barnard's Star"), "BARNARD'S STAR");
+    }
+
+    #[tokio::test]
+    async fn cache_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("nexlib-sesame-{}.tsv", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let resolver = SesameResolver::new().cache_file(&path).unwrap();
+        resolver
+            .remember(cache_key("Vega"), RADec::new(279.2347, 38.7837))
+            .unwrap();
+
+        // An unreachable URL proves the second resolver answers from the file.
+        let resolver = SesameResolver::new()
+            .url("http://127.0.0.1:9/")
+            .cache_file(&path)
+            .unwrap();
+        let coord = resolver.resolve("vega").await.unwrap().unwrap();
+        assert_eq!(coord.ra, 279.2347);
+
+        let mut target = Target::new("Vega");
+        assert!(resolver.resolve_target(&mut target).await.unwrap());
+        assert!(!target.needs_resolution());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,106 @@
+//! The brightest stars, for alignment and quick gotos without an observing list.
+//!
+//! Positions are J2000 in degrees, to about 0.01 degrees, which is plenty for choosing and finding a star; convert
+//! them with [`RADec::j2000_to_jnow`] before sending them to a mount.
+
+use super::Target;
+use crate::RADec;
+
+/// A star in [`BRIGHT_STARS`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrightStar {
+    /// Proper name, e.g. `Vega`.
+    pub name: &'static str,
+    /// J2000 right ascension in degrees.
+    pub ra: f64,
+    /// J2000 declination in degrees.
+    pub dec: f64,
+    /// Visual magnitude.
+    pub magnitude: f64,
+}
+
+impl BrightStar {
+    /// J2000 position.
+    pub fn coord(&self) -> RADec {
+        RADec::new(self.ra, self.dec)
+    }
+
+    pub fn to_target(&self) -> Target {
+        Target {
+            coord: Some(self.coord()),
+            magnitude: Some(self.magnitude),
+            kind: Some("Star".to_owned()),
+            ..Target::new(self.name)
+        }
+    }
+}
+
+const fn star(name: &'static str, ra: f64, dec: f64, magnitude: f64) -> BrightStar {
+    BrightStar {
+        name,
+        ra,
+        dec,
+        magnitude,
+    }
+}
+
+/// Stars brighter than about magnitude 2.5 spread over both hemispheres, brightest first.
+pub const BRIGHT_STARS: &[BrightStar] = &[
+    star("Sirius", 101.287, -16.716, -1.46),
+    star("Canopus", 95.988, -52.696, -0.74),
+    star("Arcturus", 213.915, 19.182, -0.05),
+    star("Vega", 279.235, 38.784, 0.03),
+    star("Capella", 79.172, 45.998, 0.08),
+    star("Rigel", 78.634, -8.202, 0.13),
+    star("Procyon", 114.825, 5.225, 0.34),
+    star("Achernar", 24.429, -57.237, 0.46),
+    star("Betelgeuse", 88.793, 7.407, 0.50),
+    star("Hadar", 210.956, -60.373, 0.61),
+    star("Acrux", 186.650, -63.099, 0.76),
+    star("Altair", 297.696, 8.868, 0.77),
+    star("Aldebaran", 68.980, 16.509, 0.86),
+    star("Antares", 247.352, -26.432, 0.96),
+    star("Spica", 201.298, -11.161, 0.97),
+    star("Pollux", 116.329, 28.026, 1.14),
+    star("Fomalhaut", 344.413, -29.622, 1.16),
+    star("Deneb", 310.358, 45.280, 1.25),
+    star("Mimosa", 191.930, -59.689, 1.25),
+    star("Regulus", 152.093, 11.967, 1.35),
+    star("Adhara", 104.656, -28.972, 1.50),
+    star("Castor", 113.650, 31.888, 1.58),
+    star("Shaula", 263.402, -37.104, 1.62),
+    star("Bellatrix", 81.283, 6.350, 1.64),
+    star("Elnath", 81.573, 28.608, 1.65),
+    star("Alnilam", 84.053, -1.202, 1.69),
+    star("Alioth", 193.507, 55.960, 1.76),
+    star("Dubhe", 165.932, 61.751, 1.79),
+    star("Mirfak", 51.081, 49.861, 1.79),
+    star("Alkaid", 206.885, 49.313, 1.86),
+    star("Menkalinan", 89.882, 44.947, 1.90),
+    star("Alhena", 99.428, 16.399, 1.93),
+    star("Peacock", 306.412, -56.735, 1.94),
+    star("Polaris", 37.955, 89.264, 1.98),
+    star("Mirzam", 95.675, -17.956, 1.98),
+    star("Alphard", 141.897, -8.659, 1.99),
+    star("Hamal", 31.793, 23.462, 2.00),
+    star("Diphda", 10.897, -17.987, 2.02),
+    star("Nunki", 283.816, -26.297, 2.05),
+    star("Alpheratz", 2.097, 29.090, 2.06),
+    star("Rasalhague", 263.734, 12.560, 2.08),
+    star("Kochab", 222.676, 74.156, 2.08),
+    star("Algol", 47.042, 40.956, 2.12),
+    star("Denebola", 177.265, 14.572, 2.13),
+    star("Eltanin", 269.152, 51.489, 2.23),
+    star("Schedar", 10.127, 56.537, 2.24),
+    star("Mizar", 200.981, 54.925, 2.27),
+    star("Caph", 2.295, 59.150, 2.27),
+    star("Enif", 326.046, 9.875, 2.39),
+    star("Markab", 346.190, 15.205, 2.48),
+];
+
+/// Finds a star in [`BRIGHT_STARS`] by name, ignoring case.
+pub fn find(name: &str) -> Option<&'static BrightStar> {
+    BRIGHT_STARS
+        .iter()
+        .find(|star| star.name.eq_ignore_ascii_case(name.trim()))
+}
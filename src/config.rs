@@ -0,0 +1,522 @@
+//! TOML configuration shared by the GUI, `nexctl`, and the servers.
+//!
+//! [`Config::load`] reads the first file found among:
+//!
+//! 1. The path in `NEXLIB_CONFIG`.
+//! 2. `nexlib.toml` in the working directory.
+//! 3. `nexlib/config.toml` in the user configuration directory (`$XDG_CONFIG_HOME` or `~/.config` on Unix,
+//!    `%APPDATA%` on Windows).
+//! 4. `/etc/nexlib/config.toml` on Unix.
+//!
+//! If none exist the defaults are used. Any value can then be overridden with an environment variable named
+//! `NEXLIB_<SECTION>_<KEY>`, e.g. `NEXLIB_SERIAL_PORT=/dev/ttyUSB1` or `NEXLIB_LIMITS_MIN_ELEVATION=15`. Relative
+//! paths in the file are resolved against the directory containing it.
+//!
+//! `nexlib.example.toml` in the repository root documents every key.
+
+use crate::mount::limits::{LimitProfile, LimitedMount};
+use crate::mount::offsets::{OffsetMount, PointingOffset};
+use crate::mount::session::Recorder;
+use crate::mount::transport::{self, Failover, DEFAULT_FAILOVER_AFTER};
+use crate::mount::{CommsConfig, Location, Mount, DEFAULT_BAUD, DEFAULT_TIMEOUT};
+use crate::units::Units;
+use crate::CelestronMount;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable naming an explicit configuration file.
+pub const CONFIG_ENV: &str = "NEXLIB_CONFIG";
+
+/// Prefix of environment variables overriding individual values.
+const ENV_PREFIX: &str = "NEXLIB_";
+
+/// Sections which may be overridden from the environment.
+const SECTIONS: [&str; 6] = ["site", "limits", "optics", "serial", "solver", "display"];
+
+/// Observing site.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Site {
+    /// Geodetic latitude in degrees, north positive.
+    pub latitude: f64,
+    /// Longitude in degrees, east positive.
+    pub longitude: f64,
+    /// Height above sea level in meters.
+    #[serde(default)]
+    pub elevation: f64,
+    /// File describing the local horizon as azimuth/elevation pairs in degrees.
+    #[serde(default)]
+    pub horizon_file: Option<PathBuf>,
+}
+
+/// Pointing limits enforced before commanding a slew.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Lowest elevation the mount may point to, in degrees.
+    pub min_elevation: f64,
+    /// Highest elevation the mount may point to, in degrees.
+    pub max_elevation: f64,
+    /// The profile in use, one of `profiles`. The elevations above apply if unset.
+    pub profile: Option<String>,
+    /// Limits for each way of setting up the mount, e.g. `tripod`, `wedge`, or `pier`.
+    pub profiles: BTreeMap<String, LimitProfile>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            min_elevation: 0.0,
+            max_elevation: 90.0,
+            profile: None,
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+/// Name of the profile made from the top-level elevation limits.
+const DEFAULT_PROFILE: &str = "default";
+
+impl Limits {
+    /// Wraps `mount` at `site` to enforce the selected profile, or the top-level elevation limits as the `default`
+    /// profile; see [`limits`](crate::mount::limits).
+    pub fn limited_mount<M: Mount>(
+        &self,
+        mount: M,
+        site: &Site,
+    ) -> Result<LimitedMount<M>, io::Error> {
+        let mut profiles = self.profiles.clone();
+        profiles
+            .entry(DEFAULT_PROFILE.to_string())
+            .or_insert_with(|| LimitProfile {
+                min_elevation: self.min_elevation,
+                max_elevation: self.max_elevation,
+                ..LimitProfile::default()
+            });
+
+        let mut mount = LimitedMount::new(mount, profiles, site.latitude, site.longitude);
+        mount.select(Some(self.profile.as_deref().unwrap_or(DEFAULT_PROFILE)))?;
+        Ok(mount)
+    }
+}
+
+/// Telescope and camera optics, used for plate scale and field of view.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Optics {
+    /// Aperture in millimeters.
+    pub aperture_mm: Option<f64>,
+    /// Effective focal length in millimeters.
+    pub focal_length_mm: Option<f64>,
+    /// Camera pixel size in micrometers.
+    pub pixel_size_um: Option<f64>,
+    /// The optical configuration in use, one of `offsets`.
+    pub selected: Option<String>,
+    /// Pointing offset of each optical configuration, e.g. `camera`, `eyepiece`, or `guide`.
+    pub offsets: BTreeMap<String, PointingOffset>,
+}
+
+impl Optics {
+    /// Wraps `mount` to apply the offset of the selected configuration; see [`offsets`](crate::mount::offsets).
+    pub fn offset_mount<M: Mount>(&self, mount: M) -> Result<OffsetMount<M>, io::Error> {
+        let mut mount = OffsetMount::new(mount, self.offsets.clone());
+        mount.select(self.selected.as_deref())?;
+        Ok(mount)
+    }
+}
+
+/// Serial connection to the hand control.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Serial {
+    /// Serial port to open, or the `host:port` address of a WiFi module. The port is detected automatically if unset.
+    pub port: Option<String>,
+    /// Baud rate of a serial port, 9600 for the hand control's own port. Ignored for a WiFi module.
+    pub baud: u32,
+    /// Time to wait for a response, in milliseconds.
+    pub timeout_ms: u64,
+    /// Times a query is sent again after it timed out or its response was malformed.
+    pub retries: u32,
+    /// Least time between commands, in milliseconds.
+    pub inter_command_delay_ms: u64,
+    /// Records the serial traffic to this file, to reproduce problems later with a
+    /// [`ReplayPort`](crate::mount::session::ReplayPort).
+    pub record: Option<PathBuf>,
+    /// Checks the link before a command after this many milliseconds idle, or after the host slept; see
+    /// [`CelestronMount::set_revalidate_after`].
+    pub revalidate_after_ms: Option<u64>,
+    /// Serial port or `host:port` address to switch to if `port` fails persistently; see
+    /// [`transport`](crate::mount::transport).
+    pub backup: Option<String>,
+    /// Consecutive failed commands after which the backup is used.
+    pub failover_after: u32,
+}
+
+impl Default for Serial {
+    fn default() -> Self {
+        Serial {
+            port: None,
+            baud: DEFAULT_BAUD,
+            timeout_ms: DEFAULT_TIMEOUT.as_millis() as u64,
+            retries: 0,
+            inter_command_delay_ms: 0,
+            record: None,
+            revalidate_after_ms: None,
+            backup: None,
+            failover_after: DEFAULT_FAILOVER_AFTER,
+        }
+    }
+}
+
+impl Serial {
+    /// Connects to the mount on the configured port, or the first detected one.
+    pub fn connect(&self) -> Result<CelestronMount, io::Error> {
+        let name = match &self.port {
+            Some(port) => port.clone(),
+            None => CelestronMount::detect_port()?,
+        };
+        let timeout = Duration::from_millis(self.timeout_ms);
+        let port = transport::open_at(&name, self.baud, timeout)?;
+
+        let mut mount = match &self.record {
+            Some(path) => {
+                log::info!("Recording serial session to {}", path.display());
+                CelestronMount::from_port(Box::new(Recorder::create(port, path)?))
+            }
+            None => CelestronMount::from_port(port),
+        };
+        mount.set_comms(CommsConfig {
+            read_timeout: timeout,
+            write_timeout: timeout,
+            retries: self.retries,
+            inter_command_delay: Duration::from_millis(self.inter_command_delay_ms),
+        })?;
+        mount.set_revalidate_after(self.revalidate_after_ms.map(Duration::from_millis));
+        if let Some(backup) = self.backup.clone() {
+            let baud = self.baud;
+            mount.set_failover(Some(Failover::new(
+                move || transport::open_at(&backup, baud, timeout),
+                self.failover_after,
+            )));
+        }
+        Ok(mount)
+    }
+}
+
+/// External plate solvers.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Solver {
+    /// Path to the ASTAP executable.
+    pub astap: Option<PathBuf>,
+    /// Path to astrometry.net's `solve-field`.
+    pub astrometry_net: Option<PathBuf>,
+    /// Directory containing the solver star databases or index files.
+    pub index_dir: Option<PathBuf>,
+}
+
+/// The mount [`Config::connect`] returns: the configured connection with the pointing offset of the selected optics
+/// applied and the limits enforced.
+pub type ConfiguredMount = LimitedMount<OffsetMount<CelestronMount>>;
+
+/// Settings shared by every nexlib application. Missing sections take their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub site: Option<Site>,
+    pub limits: Limits,
+    pub optics: Optics,
+    pub serial: Serial,
+    pub solver: Solver,
+    /// Units shown by the GUI and `nexctl`; pass to [`units::set`](crate::units::set) at startup.
+    pub display: Units,
+}
+
+fn invalid(path: &Path, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid configuration {}: {}", path.display(), e),
+    )
+}
+
+/// Parses an environment value as a TOML scalar, falling back to a plain string.
+fn env_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("v = {raw}"))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_owned()))
+}
+
+/// Applies `NEXLIB_<SECTION>_<KEY>` overrides from `vars` to `table`.
+fn apply_overrides(table: &mut toml::Table, vars: impl Iterator<Item = (String, String)>) {
+    for (name, raw) in vars {
+        let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let rest = rest.to_lowercase();
+        let Some((section, key)) = rest.split_once('_') else {
+            continue;
+        };
+        if !SECTIONS.contains(&section) {
+            continue;
+        }
+
+        let section = table
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let Some(section) = section.as_table_mut() {
+            section.insert(key.to_owned(), env_value(&raw));
+        }
+    }
+}
+
+fn resolve(base: &Path, path: &mut Option<PathBuf>) {
+    if let Some(p) = path {
+        if p.is_relative() {
+            *p = base.join(&*p);
+        }
+    }
+}
+
+impl Config {
+    /// Connects to the configured mount, applying the pointing offset of the selected optics and enforcing the
+    /// limits for the site, or for the site set in the hand control if the file has none. Every nexlib front end
+    /// connects through this, so none can drive the mount outside the limits.
+    pub fn connect(&self) -> Result<ConfiguredMount, io::Error> {
+        let mut mount = self.serial.connect()?;
+        let site = match &self.site {
+            Some(site) => site.clone(),
+            None => {
+                let Location {
+                    latitude,
+                    longitude,
+                } = mount.get_site()?;
+                Site {
+                    latitude,
+                    longitude,
+                    elevation: 0.0,
+                    horizon_file: None,
+                }
+            }
+        };
+        let mount = self.optics.offset_mount(mount)?;
+        self.limits.limited_mount(mount, &site)
+    }
+
+    /// Candidate configuration files, in search order.
+    pub fn search_paths() -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        if let Some(path) = env::var_os(CONFIG_ENV) {
+            paths.push(PathBuf::from(path));
+        }
+        paths.push(PathBuf::from("nexlib.toml"));
+
+        let user_dir = if cfg!(windows) {
+            env::var_os("APPDATA").map(PathBuf::from)
+        } else {
+            env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|h| Path::new(&h).join(".config")))
+        };
+        if let Some(dir) = user_dir {
+            paths.push(dir.join("nexlib").join("config.toml"));
+        }
+
+        if cfg!(unix) {
+            paths.push(PathBuf::from("/etc/nexlib/config.toml"));
+        }
+
+        paths
+    }
+
+    /// Loads the first configuration file found on the search path and applies environment overrides.
+    pub fn load() -> Result<Config, io::Error> {
+        let explicit = env::var_os(CONFIG_ENV).is_some();
+        let path = Self::search_paths()
+            .into_iter()
+            .enumerate()
+            .find_map(|(i, p)| {
+                // A file named by `NEXLIB_CONFIG` must exist; the rest are optional.
+                (p.is_file() || (i == 0 && explicit)).then_some(p)
+            });
+
+        match path {
+            Some(path) => {
+                log::debug!("Loading configuration from {}", path.display());
+                Self::load_from(&path)
+            }
+            None => Self::parse(toml::Table::new(), env::vars(), Path::new(".")),
+        }
+    }
+
+    /// Loads the configuration file at `path` and applies environment overrides.
+    pub fn load_from(path: &Path) -> Result<Config, io::Error> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        let table: toml::Table = toml::from_str(&text).map_err(|e| invalid(path, e))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+
+        Self::parse(table, env::vars(), base).map_err(|e| invalid(path, e))
+    }
+
+    /// Writes the configuration to `path`, e.g. after calibrating pointing offsets.
+    ///
+    /// The file is rewritten from the values, without the comments of the original, and includes any environment
+    /// overrides in effect.
+    pub fn save_to(&self, path: &Path) -> Result<(), io::Error> {
+        let text = toml::to_string_pretty(self).map_err(|e| invalid(path, e))?;
+        fs::write(path, text)
+    }
+
+    fn parse(
+        mut table: toml::Table,
+        vars: impl Iterator<Item = (String, String)>,
+        base: &Path,
+    ) -> Result<Config, io::Error> {
+        apply_overrides(&mut table, vars);
+
+        let mut config: Config = table
+            .try_into()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        if let Some(site) = &mut config.site {
+            resolve(base, &mut site.horizon_file);
+        }
+        resolve(base, &mut config.serial.record);
+        resolve(base, &mut config.solver.astap);
+        resolve(base, &mut config.solver.astrometry_net);
+        resolve(base, &mut config.solver.index_dir);
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn example_is_valid() {
+        let table = toml::from_str(include_str!("../nexlib.example.toml")).unwrap();
+        let config = Config::parse(table, vars(&[]), Path::new("/srv/nexlib")).unwrap();
+
+        let site = config.site.unwrap();
+        assert_eq!(site.latitude, 42.36);
+        assert_eq!(
+            site.horizon_file.as_deref(),
+            Some(Path::new("/srv/nexlib/horizon.txt"))
+        );
+        assert_eq!(config.serial.timeout_ms, 3500);
+        assert_eq!(
+            config.display.clock,
+            crate::units::ClockFormat::TwentyFourHour
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let config = Config::parse(toml::Table::new(), vars(&[]), Path::new(".")).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.limits.max_elevation, 90.0);
+    }
+
+    #[test]
+    fn environment_overrides() {
+        let table = toml::from_str("[serial]\nport = \"COM3\"\ntimeout_ms = 1000").unwrap();
+        let config = Config::parse(
+            table,
+            vars(&[
+                ("NEXLIB_SERIAL_PORT", "/dev/ttyUSB1"),
+                ("NEXLIB_SERIAL_BAUD", "115200"),
+                ("NEXLIB_LIMITS_MIN_ELEVATION", "15"),
+                ("NEXLIB_SITE_LATITUDE", "-33.9"),
+                ("NEXLIB_SITE_LONGITUDE", "18.4"),
+                ("NEXLIB_DISPLAY_ANGLES", "sexagesimal"),
+                ("NEXLIB_CONFIG", "ignored.toml"),
+                ("NEXLIB_UNKNOWN_KEY", "ignored"),
+            ]),
+            Path::new("."),
+        )
+        .unwrap();
+
+        assert_eq!(config.serial.port.as_deref(), Some("/dev/ttyUSB1"));
+        assert_eq!(config.serial.baud, 115200);
+        assert_eq!(config.serial.timeout_ms, 1000);
+        assert_eq!(config.limits.min_elevation, 15.0);
+        assert_eq!(config.site.unwrap().latitude, -33.9);
+        assert_eq!(
+            config.display.angles,
+            crate::units::AngleFormat::Sexagesimal
+        );
+    }
+
+    #[test]
+    fn limit_profiles() {
+        let table = toml::from_str(
+            "[limits]\nmin_elevation = 20.0\n[limits.profiles.wedge]\nmax_hour_angle = 90.0\ntracking_modes = [\"EQNorth\"]\n\
+             [[limits.profiles.wedge.keep_out]]\nmin_azimuth = 340.0\nmax_azimuth = 20.0\nmin_elevation = 30.0",
+        )
+        .unwrap();
+        let config = Config::parse(table, vars(&[]), Path::new(".")).unwrap();
+        let wedge = &config.limits.profiles["wedge"];
+        assert_eq!(wedge.min_elevation, 0.0);
+        assert_eq!(wedge.tracking_modes, [crate::mount::TrackingMode::EQNorth]);
+        assert!(wedge.keep_out[0].contains(0.0));
+        assert_eq!(wedge.max_slew_rate, None);
+
+        let site = Site {
+            latitude: 45.0,
+            longitude: 0.0,
+            elevation: 0.0,
+            horizon_file: None,
+        };
+        let mut mount = config
+            .limits
+            .limited_mount(crate::mount::SimMount::new(), &site)
+            .unwrap();
+        assert_eq!(mount.selected(), Some("default"));
+        assert!(mount.goto_az_el(crate::AzEl::new(0.0, 10.0)).is_err());
+    }
+
+    #[test]
+    fn saves_offsets() {
+        let table = toml::from_str(
+            "[optics]\nselected = \"guide\"\n[optics.offsets.guide]\nra_arcsec = 72.0\ndec_arcsec = -36.0",
+        )
+        .unwrap();
+        let mut config = Config::parse(table, vars(&[]), Path::new(".")).unwrap();
+        assert_eq!(config.optics.offsets["guide"].ra_arcsec, 72.0);
+        config.optics.offsets.insert(
+            "eyepiece".to_string(),
+            PointingOffset {
+                ra_arcsec: 0.0,
+                dec_arcsec: 12.5,
+            },
+        );
+
+        let path = env::temp_dir().join(format!("nexlib-config-{}.toml", std::process::id()));
+        config.save_to(&path).unwrap();
+        let saved = Config::load_from(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(saved.unwrap(), config);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let table = toml::from_str("[serial]\ntimeout_ms = \"slow\"").unwrap();
+        let e = Config::parse(table, vars(&[]), Path::new(".")).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,505 @@
+//! Daemon owning the mount connection and serving clients over a local socket.
+//!
+//! Only the daemon opens the serial port. The GUI, CLI, and capture software connect to it instead, over a Unix
+//! domain socket (abstract namespace on Linux) or a named pipe on Windows, and exchange newline-delimited JSON as
+//! described in [`crate::rpc`]. Requests from all clients are serialized onto the mount one at a time,
+//! through a [`CommandQueue`] so that aborts and stops run ahead of any backlog of polls and gotos.
+//!
+//! So that two programs cannot fight over the axes, commands which move the mount or change its pointing model require
+//! holding the mount's lease. A client takes the free lease implicitly with its first such command, or explicitly
+//! with [`DaemonClient::acquire_lease`], and keeps it until it releases it or disconnects. Reads are always allowed,
+//! as are aborts and stops, so any client can halt the mount in an emergency. A client may take a held lease with
+//! `force`, for when its holder has hung or been abandoned.
+
+use crate::mount::error::MountError;
+use crate::mount::limits::ProfileSelection;
+use crate::mount::queue::{CommandQueue, Priority};
+use crate::mount::{
+    Gps, Location, Model, Mount, NonGpsDevice, SlewAxis, SlewDir, SlewRate, TrackingMode,
+};
+use crate::rpc::{self, ErrorObject, Request, Response};
+use crate::{AzEl, RADec};
+use chrono::{DateTime, Utc};
+use interprocess::local_socket::prelude::*;
+use interprocess::local_socket::{
+    GenericFilePath, GenericNamespaced, ListenerOptions, Name, Stream,
+};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Socket name used when none is given.
+pub const DEFAULT_SOCKET_NAME: &str = "nexlib.sock";
+
+/// Resolves a socket name to a platform-appropriate local socket.
+///
+/// Namespaced sockets are used where supported (Linux, Windows); elsewhere the name is placed in the temporary
+/// directory.
+pub fn socket_name(name: &str) -> Result<Name<'static>, io::Error> {
+    if GenericNamespaced::is_supported() {
+        name.to_owned().to_ns_name::<GenericNamespaced>()
+    } else {
+        std::env::temp_dir()
+            .join(name)
+            .to_fs_name::<GenericFilePath>()
+            .map(|n| n.into_owned())
+    }
+}
+
+/// The client allowed to move the mount.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Holder {
+    client: u64,
+    name: String,
+}
+
+/// Arbitrates motion commands between clients; see the [module documentation](self).
+#[derive(Debug, Default)]
+struct Lease {
+    holder: Option<Holder>,
+}
+
+impl Lease {
+    /// Takes the lease for `client`, failing if another client holds it unless `force` is set.
+    fn acquire(&mut self, client: u64, name: String, force: bool) -> Result<(), ErrorObject> {
+        if let Some(holder) = self.holder.as_ref().filter(|h| h.client != client) {
+            if !force {
+                return Err(held(holder));
+            }
+            log::warn!(
+                "[{}:{}] Lease taken from {} by {}.",
+                file!(),
+                line!(),
+                holder.name,
+                name
+            );
+        }
+
+        self.holder = Some(Holder { client, name });
+        Ok(())
+    }
+
+    /// Gives up the lease if `client` holds it.
+    fn release(&mut self, client: u64) {
+        if self.holder.as_ref().is_some_and(|h| h.client == client) {
+            self.holder = None;
+        }
+    }
+
+    /// Checks whether `client` may call `method`, taking the lease implicitly if it is free.
+    fn authorize(
+        &mut self,
+        client: u64,
+        name: &str,
+        method: &str,
+        params: &Value,
+    ) -> Result<(), ErrorObject> {
+        if !requires_lease(method, params) {
+            return Ok(());
+        }
+        match &self.holder {
+            Some(holder) if holder.client != client => Err(held(holder)),
+            Some(_) => Ok(()),
+            None => self.acquire(client, name.to_owned(), false),
+        }
+    }
+}
+
+/// Whether `method` moves the mount or changes its pointing model or limits. Stops never require the lease.
+fn requires_lease(method: &str, params: &Value) -> bool {
+    matches!(
+        method,
+        "goto_ra_dec"
+            | "goto_az_el"
+            | "sync"
+            | "set_tracking_mode"
+            | "slew_variable"
+            | "slew_fixed"
+            | "select_limit_profile"
+    ) && rpc::priority(method, params) != Priority::Urgent
+}
+
+fn held(holder: &Holder) -> ErrorObject {
+    let mut e = ErrorObject::from(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("The mount is leased to {}.", holder.name),
+    ));
+    e.code = rpc::LEASE_HELD;
+    e
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AcquireLeaseParams {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    force: bool,
+}
+
+/// Answers the lease methods and refuses calls `client` may not make, or returns `None` to let `req` through to the
+/// mount.
+fn arbitrate(lease: &Mutex<Lease>, client: u64, req: &Request) -> Option<Response> {
+    let mut lease = lease.lock().unwrap();
+    let res = match req.method.as_str() {
+        "acquire_lease" => {
+            let p = match &req.params {
+                Value::Null => Ok(AcquireLeaseParams::default()),
+                p => serde_json::from_value::<AcquireLeaseParams>(p.clone()).map_err(|e| {
+                    ErrorObject::new(rpc::INVALID_PARAMS, format!("Invalid parameters: {e}"))
+                }),
+            };
+            p.and_then(|p| {
+                let name = p.name.unwrap_or_else(|| format!("client {client}"));
+                lease.acquire(client, name, p.force)
+            })
+            .map(|()| Value::Null)
+        }
+        "release_lease" => {
+            lease.release(client);
+            Ok(Value::Null)
+        }
+        "lease_holder" => Ok(json!(lease.holder.as_ref().map(|h| &h.name))),
+        method => {
+            let name = format!("client {client}");
+            match lease.authorize(client, &name, method, &req.params) {
+                Ok(()) => return None,
+                Err(e) => Err(e),
+            }
+        }
+    };
+
+    Some(match res {
+        Ok(result) => Response::ok(req.id.clone(), result),
+        Err(e) => Response::err(req.id.clone(), e),
+    })
+}
+
+fn serve_client<M: Mount + Send + 'static>(
+    mount: &CommandQueue<M>,
+    lease: &Mutex<Lease>,
+    client: u64,
+    conn: Stream,
+) -> Result<(), io::Error> {
+    let mut reader = BufReader::new(conn);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let line = line.trim().to_owned();
+        let req = serde_json::from_str::<Request>(&line).ok();
+        let res = match req.as_ref().and_then(|req| arbitrate(lease, client, req)) {
+            Some(res) => serde_json::to_string(&res).expect("Responses are always serializable."),
+            None => {
+                let priority = req
+                    .map(|req| rpc::priority(&req.method, &req.params))
+                    .unwrap_or(Priority::Normal);
+                mount.run(priority, move |mount| rpc::handle_line(mount, &line))
+            }
+        };
+
+        let conn = reader.get_mut();
+        conn.write_all(res.as_bytes())?;
+        conn.write_all(b"\n")?;
+    }
+}
+
+/// Serves `mount` on the local socket `name` until the listener fails.
+pub fn run<M: Mount + Send + 'static>(mount: M, name: &str) -> Result<(), io::Error> {
+    serve(Arc::new(CommandQueue::spawn(mount)), name)
+}
+
+/// Serves the mount owned by `mount` on the local socket `name` until the listener fails.
+///
+/// Other sources of commands, such as a hardware emergency stop input (the `estop` feature), can share the queue with
+/// the clients.
+pub fn serve<M: Mount + Send + 'static>(
+    mount: Arc<CommandQueue<M>>,
+    name: &str,
+) -> Result<(), io::Error> {
+    let listener = ListenerOptions::new()
+        .name(socket_name(name)?)
+        .create_sync()?;
+    let lease = Arc::new(Mutex::new(Lease::default()));
+    let next_client = AtomicU64::new(1);
+
+    for conn in listener.incoming() {
+        let conn = match conn {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::warn!("[{}:{}] Failed to accept client: {:?}", file!(), line!(), e);
+                continue;
+            }
+        };
+
+        let mount = Arc::clone(&mount);
+        let lease = Arc::clone(&lease);
+        let client = next_client.fetch_add(1, Ordering::Relaxed);
+        thread::spawn(move || {
+            if let Err(e) = serve_client(&mount, &lease, client, conn) {
+                log::warn!("[{}:{}] Client disconnected: {:?}", file!(), line!(), e);
+            }
+            lease.lock().unwrap().release(client);
+        });
+    }
+
+    Ok(())
+}
+
+/// A connection to a running daemon.
+///
+/// Implements [`Mount`], so applications can drive a shared mount exactly as they would a directly connected one.
+pub struct DaemonClient {
+    reader: BufReader<Stream>,
+    next_id: u64,
+}
+
+impl DaemonClient {
+    /// Connects to the daemon listening on the local socket `name`.
+    pub fn connect(name: &str) -> Result<DaemonClient, io::Error> {
+        Ok(DaemonClient {
+            reader: BufReader::new(Stream::connect(socket_name(name)?)?),
+            next_id: 0,
+        })
+    }
+
+    /// Calls `method` on the daemon and returns its raw JSON result.
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, io::Error> {
+        self.next_id += 1;
+        let req = Request {
+            id: json!(self.next_id),
+            method: method.to_owned(),
+            params,
+        };
+
+        let conn = self.reader.get_mut();
+        serde_json::to_writer(&mut *conn, &req)?;
+        conn.write_all(b"\n")?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Daemon closed the connection.",
+            ));
+        }
+
+        let res: Response = serde_json::from_str(&line)?;
+        match (res.result, res.error) {
+            (_, Some(e)) => Err(e.into_io_error()),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+
+    /// Takes the lease allowing this client to move the mount, under `name` as shown to other clients.
+    ///
+    /// Fails with `PermissionDenied` if another client holds the lease, unless `force` is set to take it from them.
+    pub fn acquire_lease(&mut self, name: &str, force: bool) -> Result<(), io::Error> {
+        Ok(self.call_unit("acquire_lease", json!({ "name": name, "force": force }))?)
+    }
+
+    /// Gives up the lease, if held, so that other clients may move the mount.
+    pub fn release_lease(&mut self) -> Result<(), io::Error> {
+        Ok(self.call_unit("release_lease", Value::Null)?)
+    }
+
+    /// Gets the name of the client holding the lease, if any.
+    pub fn lease_holder(&mut self) -> Result<Option<String>, io::Error> {
+        Ok(self.call_as("lease_holder", Value::Null)?)
+    }
+
+    fn call_as<T: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, MountError> {
+        serde_json::from_value(self.call(method, params)?)
+            .map_err(|e| MountError::other(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    fn call_unit(&mut self, method: &str, params: Value) -> Result<(), MountError> {
+        self.call(method, params)?;
+        Ok(())
+    }
+}
+
+impl Mount for DaemonClient {
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        self.call_as("get_position_ra_dec", Value::Null)
+    }
+
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        self.call_as("get_position_az_el", Value::Null)
+    }
+
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.call_unit("goto_ra_dec", json!(coord))
+    }
+
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        self.call_unit("goto_az_el", json!(coord))
+    }
+
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.call_unit("sync", json!(coord))
+    }
+
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        self.call_as("get_tracking_mode", Value::Null)
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        self.call_unit("set_tracking_mode", json!({ "mode": mode }))
+    }
+
+    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), MountError> {
+        self.call_unit(
+            "slew_variable",
+            json!({ "axis": axis, "dir": dir, "rate": rate }),
+        )
+    }
+
+    fn slew_fixed(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        self.call_unit(
+            "slew_fixed",
+            json!({ "axis": axis, "dir": dir, "rate": rate as u8 }),
+        )
+    }
+
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        self.call_as("get_location", Value::Null)
+    }
+
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        self.call_unit("set_location", json!(location))
+    }
+
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        let time: String = self.call_as("get_time", Value::Null)?;
+        DateTime::parse_from_rfc3339(&time)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| MountError::other(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        self.call_unit("set_time", json!({ "time": time.to_rfc3339() }))
+    }
+
+    fn get_version(&mut self) -> Result<String, MountError> {
+        self.call_as("get_version", Value::Null)
+    }
+
+    fn get_device_version(&mut self, device: NonGpsDevice) -> Result<String, MountError> {
+        self.call_as("get_device_version", json!({ "device": device }))
+    }
+
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        let res = self.call("get_model", Value::Null)?;
+        let id = res["id"].as_u64().and_then(|id| u8::try_from(id).ok());
+        match id {
+            Some(id) => Model::try_from(id),
+            None => Err(MountError::other(
+                io::ErrorKind::InvalidData,
+                format!("Invalid model {res}."),
+            )),
+        }
+    }
+
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        self.call_as("echo", json!({ "byte": byte }))
+    }
+
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        self.call_as("is_aligned", Value::Null)
+    }
+
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        self.call_as("goto_in_progress", Value::Null)
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.call_unit("cancel_goto", Value::Null)
+    }
+
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
+        self.call_unit("stop_slew", json!({ "axis": axis }))
+    }
+
+    /// GPS passthrough requires direct access to the serial port and is not forwarded by the daemon.
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
+        Err(MountError::unsupported(
+            "GPS access is not available through the daemon.",
+        ))
+    }
+
+    /// Selects the daemon's limit profile, for every client of the daemon.
+    fn select_limit_profile(&mut self, name: Option<&str>) -> Result<(), MountError> {
+        self.call_unit("select_limit_profile", json!({ "name": name }))
+    }
+
+    fn limit_profiles(&mut self) -> Result<ProfileSelection, MountError> {
+        self.call_as("limit_profiles", Value::Null)
+    }
+
+    /// Stops both axes and any goto in a single request, whoever holds the lease.
+    fn stop_all(&mut self) -> Result<(), MountError> {
+        self.call_unit("stop_all", Value::Null)
+    }
+
+    /// Stops the mount in a single request, whoever holds the lease.
+    fn emergency_stop(&mut self) -> Result<(), MountError> {
+        self.call_unit("emergency_stop", Value::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lease_arbitrates_motion() {
+        let mut lease = Lease::default();
+        let goto = json!({"ra": 83.8, "dec": -5.4});
+        let stop = json!({"axis": "RAAz", "dir": "Positive", "rate": 0});
+
+        // Reads are always allowed, and the first motion command takes the free lease.
+        assert!(lease
+            .authorize(2, "gui", "get_position_ra_dec", &Value::Null)
+            .is_ok());
+        assert!(lease.authorize(1, "cli", "goto_ra_dec", &goto).is_ok());
+        assert_eq!(lease.holder.as_ref().unwrap().name, "cli");
+
+        let e = lease.authorize(2, "gui", "goto_ra_dec", &goto).unwrap_err();
+        assert_eq!(e.code, rpc::LEASE_HELD);
+        assert_eq!(e.into_io_error().kind(), io::ErrorKind::PermissionDenied);
+        assert!(lease.authorize(2, "gui", "slew_fixed", &stop).is_ok());
+        assert!(lease
+            .authorize(2, "gui", "cancel_goto", &Value::Null)
+            .is_ok());
+
+        assert!(lease.acquire(2, "gui".into(), false).is_err());
+        assert!(lease.acquire(2, "gui".into(), true).is_ok());
+        assert!(lease.authorize(1, "cli", "goto_ra_dec", &goto).is_err());
+
+        lease.release(1);
+        assert!(lease.holder.is_some());
+        lease.release(2);
+        assert!(lease.holder.is_none());
+    }
+}
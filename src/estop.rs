@@ -0,0 +1,315 @@
+//! Hardware emergency-stop inputs for headless installations.
+//!
+//! On a pier with no keyboard at hand, a button wired to a Raspberry Pi GPIO pin, or any key of an input device such as
+//! a USB foot switch, can stop the mount. [`bind`] watches such a [`Trigger`] and, when it fires, runs
+//! [`Mount::emergency_stop`] through the daemon's command queue ahead of anything else waiting:
+//!
+//! ```no_run
+//! use nexlib::estop::{self, Trigger};
+//! use nexlib::mount::queue::CommandQueue;
+//! use nexlib::CelestronMount;
+//! use std::sync::Arc;
+//!
+//! let queue = Arc::new(CommandQueue::spawn(CelestronMount::new().unwrap()));
+//! // BCM pin 17 on a Raspberry Pi kernel whose GPIO chip starts at 512.
+//! estop::bind("gpio:529:active-low".parse::<Trigger>().unwrap(), Arc::clone(&queue)).unwrap();
+//! nexlib::daemon::serve(queue, nexlib::daemon::DEFAULT_SOCKET_NAME).unwrap();
+//! ```
+//!
+//! `nexctl daemon --estop <TRIGGER>` does the same. GPIO pins are read through the sysfs interface, which must be
+//! enabled in the kernel, and key presses from an evdev device under `/dev/input`. Both need read access for the
+//! daemon's user, usually through the `gpio` and `input` groups.
+//!
+//! Sysfs numbers GPIO pins globally, not by their number on the header or chip: a pin is the `base` of its chip, in
+//! `/sys/class/gpio/gpiochip*/base`, plus its line on that chip. On current Raspberry Pi kernels the main chip's base
+//! is 512 (571 on some), so BCM pin 17 is `gpio:529`; only older kernels numbered from 0, where it was `gpio:17`.
+//!
+//! An input which fails, such as an unplugged device, fires once more as it stops being watched, so losing the
+//! emergency stop stops the mount rather than going unnoticed.
+//!
+//! Only Linux is supported.
+
+use crate::mount::queue::{CommandQueue, Priority};
+use crate::mount::Mount;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Root of the sysfs GPIO interface.
+const GPIO_ROOT: &str = "/sys/class/gpio";
+
+/// Time between reads of a GPIO pin. Far shorter than anyone can press and release a button.
+const GPIO_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// `EV_KEY` event type of the Linux input subsystem.
+const EV_KEY: u16 = 1;
+
+/// Size of a Linux `struct input_event`: a `struct timeval` of two longs, then type, code, and value.
+const INPUT_EVENT_SIZE: usize = 2 * std::mem::size_of::<usize>() + 8;
+
+/// An input which triggers an emergency stop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// A GPIO pin, by its global sysfs number (see [`estop`](self)), which fires on becoming active.
+    Gpio {
+        pin: u32,
+        /// Whether the pin is active when low, as with a button pulling it to ground.
+        active_low: bool,
+    },
+    /// A key of an evdev input device, which fires on being pressed.
+    Key { device: PathBuf, code: u16 },
+}
+
+impl FromStr for Trigger {
+    type Err = io::Error;
+
+    /// Parses `gpio:<PIN>`, `gpio:<PIN>:active-low`, or `key:<DEVICE>:<CODE>`, e.g. `key:/dev/input/event0:28` for
+    /// the Enter key.
+    fn from_str(s: &str) -> Result<Trigger, io::Error> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid emergency stop trigger {s:?}; expected gpio:<PIN>[:active-low] or key:<DEVICE>:<CODE>."),
+            )
+        };
+
+        match s.split_once(':').ok_or_else(invalid)? {
+            ("gpio", rest) => {
+                let (pin, active_low) = match rest.split_once(':') {
+                    Some((pin, "active-low")) => (pin, true),
+                    Some(_) => return Err(invalid()),
+                    None => (rest, false),
+                };
+                Ok(Trigger::Gpio {
+                    pin: pin.parse().map_err(|_| invalid())?,
+                    active_low,
+                })
+            }
+            ("key", rest) => {
+                let (device, code) = rest.rsplit_once(':').ok_or_else(invalid)?;
+                Ok(Trigger::Key {
+                    device: device.into(),
+                    code: code.parse().map_err(|_| invalid())?,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Calls `on_fire` from a background thread each time `trigger` fires.
+///
+/// The thread runs until the input fails, such as when the device is unplugged, which is logged and calls `on_fire`
+/// a last time to fail safe.
+pub fn watch<F>(trigger: Trigger, on_fire: F) -> Result<JoinHandle<()>, io::Error>
+where
+    F: FnMut() + Send + 'static,
+{
+    match trigger {
+        Trigger::Gpio { pin, active_low } => {
+            let value = export_gpio(pin)?;
+            Ok(watch_level(value, active_low, on_fire))
+        }
+        Trigger::Key { device, code } => {
+            let file = File::open(&device)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", device.display())))?;
+            Ok(watch_key(file, code, on_fire))
+        }
+    }
+}
+
+/// Runs [`Mount::emergency_stop`] through `queue` at urgent priority each time `trigger` fires.
+pub fn bind<M: Mount + Send + 'static>(
+    trigger: Trigger,
+    queue: Arc<CommandQueue<M>>,
+) -> Result<JoinHandle<()>, io::Error> {
+    log::info!("Emergency stop bound to {trigger:?}.");
+    watch(trigger, move || {
+        log::warn!("Emergency stop input triggered.");
+        // Not waiting for the result, so a repeated press is not held up by a slow link.
+        let res = queue.submit(Priority::Urgent, |mount| mount.emergency_stop());
+        thread::spawn(move || {
+            if let Ok(Err(e)) = res.recv() {
+                log::error!("[{}:{}] Emergency stop failed: {}", file!(), line!(), e);
+            }
+        });
+    })
+}
+
+/// Makes `pin` available as an input through sysfs, returning the path of its value.
+fn export_gpio(pin: u32) -> Result<PathBuf, io::Error> {
+    let dir = Path::new(GPIO_ROOT).join(format!("gpio{pin}"));
+    if !dir.exists() {
+        fs::write(Path::new(GPIO_ROOT).join("export"), pin.to_string())?;
+    }
+    // Freshly exported pins may take a moment to become writable.
+    let mut res = fs::write(dir.join("direction"), "in");
+    for _ in 0..10 {
+        if res.is_ok() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+        res = fs::write(dir.join("direction"), "in");
+    }
+    res.map_err(|e| io::Error::new(e.kind(), format!("GPIO {pin}: {e}")))?;
+    Ok(dir.join("value"))
+}
+
+fn read_level(path: &Path) -> Result<bool, io::Error> {
+    Ok(fs::read_to_string(path)?.trim() == "1")
+}
+
+/// Polls the GPIO value file at `path`, firing when it becomes active.
+fn watch_level<F: FnMut() + Send + 'static>(
+    path: PathBuf,
+    active_low: bool,
+    mut on_fire: F,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut was_active = None;
+        loop {
+            let active = match read_level(&path) {
+                Ok(high) => high != active_low,
+                Err(e) => {
+                    log::error!(
+                        "[{}:{}] Emergency stop input {} failed, stopping the mount: {}",
+                        file!(),
+                        line!(),
+                        path.display(),
+                        e
+                    );
+                    on_fire();
+                    return;
+                }
+            };
+            // A latching switch already pressed at startup fires too, in case the mount is moving.
+            if active && was_active != Some(true) {
+                on_fire();
+            }
+            was_active = Some(active);
+            thread::sleep(GPIO_POLL_INTERVAL);
+        }
+    })
+}
+
+/// Whether an `input_event` record is a press of the key `code`. Repeats and releases are ignored.
+fn is_key_press(event: &[u8], code: u16) -> bool {
+    let at = INPUT_EVENT_SIZE - 8;
+    let field = |i: usize| [event[at + i], event[at + i + 1]];
+    let kind = u16::from_ne_bytes(field(0));
+    let key = u16::from_ne_bytes(field(2));
+    let value = i32::from_ne_bytes([event[at + 4], event[at + 5], event[at + 6], event[at + 7]]);
+    kind == EV_KEY && key == code && value == 1
+}
+
+/// Reads input events from `device`, firing on each press of the key `code`.
+fn watch_key<R, F>(mut device: R, code: u16, mut on_fire: F) -> JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    F: FnMut() + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut event = [0; INPUT_EVENT_SIZE];
+        loop {
+            if let Err(e) = device.read_exact(&mut event) {
+                log::error!(
+                    "[{}:{}] Emergency stop input failed, stopping the mount: {}",
+                    file!(),
+                    line!(),
+                    e
+                );
+                on_fire();
+                return;
+            }
+            if is_key_press(&event, code) {
+                on_fire();
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn parses_triggers() {
+        assert_eq!(
+            "gpio:17:active-low".parse::<Trigger>().unwrap(),
+            Trigger::Gpio {
+                pin: 17,
+                active_low: true
+            }
+        );
+        assert_eq!(
+            "key:/dev/input/event0:28".parse::<Trigger>().unwrap(),
+            Trigger::Key {
+                device: "/dev/input/event0".into(),
+                code: 28
+            }
+        );
+        assert!("gpio:17:high".parse::<Trigger>().is_err());
+        assert!("serial:/dev/ttyUSB0".parse::<Trigger>().is_err());
+    }
+
+    fn event(kind: u16, code: u16, value: i32) -> Vec<u8> {
+        let mut event = vec![0; INPUT_EVENT_SIZE - 8];
+        event.extend(kind.to_ne_bytes());
+        event.extend(code.to_ne_bytes());
+        event.extend(value.to_ne_bytes());
+        event
+    }
+
+    #[test]
+    fn fires_on_key_press() {
+        let mut events = Vec::new();
+        for (kind, code, value) in [
+            (EV_KEY, 28, 1),
+            (EV_KEY, 28, 2),
+            (EV_KEY, 28, 0),
+            (0, 0, 0),
+            (EV_KEY, 30, 1),
+        ] {
+            events.extend(event(kind, code, value));
+        }
+        events.extend(event(EV_KEY, 28, 1));
+
+        let (tx, rx) = mpsc::channel();
+        watch_key(io::Cursor::new(events), 28, move || tx.send(()).unwrap())
+            .join()
+            .unwrap();
+        // Two presses, then the end of the input fails safe.
+        assert_eq!(rx.try_iter().count(), 3);
+    }
+
+    #[test]
+    fn fires_on_gpio_edge() {
+        let path = std::env::temp_dir().join(format!("nexlib-estop-{}", std::process::id()));
+        fs::write(&path, "1\n").unwrap();
+        let (tx, rx) = mpsc::channel();
+        let _watcher = watch_level(path.clone(), true, move || {
+            let _ = tx.send(());
+        });
+
+        thread::sleep(GPIO_POLL_INTERVAL * 3);
+        assert!(rx.try_recv().is_err());
+        fs::write(&path, "0\n").unwrap();
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fires_when_gpio_fails() {
+        let path = std::env::temp_dir().join(format!("nexlib-estop-gone-{}", std::process::id()));
+        let (tx, rx) = mpsc::channel();
+        watch_level(path, true, move || tx.send(()).unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+}
@@ -0,0 +1,402 @@
+//! Exporters for session telemetry.
+//!
+//! Position samples and serial transactions are written with the same column names and types regardless of format,
+//! so a session exported as CSV, JSON Lines, or Parquet loads into the same dataframe schema.
+
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// The type of a single exported column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Int,
+    Float,
+    Bool,
+    Text,
+}
+
+/// Describes one column of an exported record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    pub kind: ColumnKind,
+    pub nullable: bool,
+}
+
+impl Column {
+    const fn new(name: &'static str, kind: ColumnKind) -> Column {
+        Column {
+            name,
+            kind,
+            nullable: false,
+        }
+    }
+
+    const fn nullable(name: &'static str, kind: ColumnKind) -> Column {
+        Column {
+            name,
+            kind,
+            nullable: true,
+        }
+    }
+}
+
+/// A single exported value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+    Null,
+}
+
+/// A row of telemetry which can be exported in any of the supported formats.
+///
+/// The serde field names must match [`Record::COLUMNS`], which is what keeps JSON Lines consistent with the
+/// column-oriented formats.
+pub trait Record: Serialize {
+    /// Name of the record type, used as the Parquet schema name.
+    const NAME: &'static str;
+    const COLUMNS: &'static [Column];
+
+    /// The values of this record, in the order of [`Record::COLUMNS`].
+    fn values(&self) -> Vec<Value>;
+}
+
+/// A timestamped pointing position.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PositionRecord {
+    pub unix_millis: i64,
+    pub ra: f64,
+    pub dec: f64,
+    pub az: f64,
+    pub el: f64,
+}
+
+impl Record for PositionRecord {
+    const NAME: &'static str = "position";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("unix_millis", ColumnKind::Int),
+        Column::new("ra", ColumnKind::Float),
+        Column::new("dec", ColumnKind::Float),
+        Column::new("az", ColumnKind::Float),
+        Column::new("el", ColumnKind::Float),
+    ];
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            Value::Int(self.unix_millis),
+            Value::Float(self.ra),
+            Value::Float(self.dec),
+            Value::Float(self.az),
+            Value::Float(self.el),
+        ]
+    }
+}
+
+/// A single command sent to the mount and the response received.
+///
+/// Raw bytes are exported as lowercase hex strings so that every format can represent them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransactionRecord {
+    pub unix_millis: i64,
+    #[serde(serialize_with = "serialize_hex")]
+    pub request: Vec<u8>,
+    #[serde(serialize_with = "serialize_hex")]
+    pub response: Vec<u8>,
+    pub latency_micros: i64,
+    pub error: Option<String>,
+}
+
+impl Record for TransactionRecord {
+    const NAME: &'static str = "transaction";
+    const COLUMNS: &'static [Column] = &[
+        Column::new("unix_millis", ColumnKind::Int),
+        Column::new("request", ColumnKind::Text),
+        Column::new("response", ColumnKind::Text),
+        Column::new("latency_micros", ColumnKind::Int),
+        Column::nullable("error", ColumnKind::Text),
+    ];
+
+    fn values(&self) -> Vec<Value> {
+        vec![
+            Value::Int(self.unix_millis),
+            Value::Text(hex(&self.request)),
+            Value::Text(hex(&self.response)),
+            Value::Int(self.latency_micros),
+            match &self.error {
+                Some(e) => Value::Text(e.clone()),
+                None => Value::Null,
+            },
+        ]
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn serialize_hex<S: serde::Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex(bytes))
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote, or line break.
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn csv_field(value: &Value) -> String {
+    match value {
+        Value::Int(v) => v.to_string(),
+        Value::Float(v) => v.to_string(),
+        Value::Bool(v) => v.to_string(),
+        Value::Text(v) => csv_escape(v),
+        Value::Null => String::new(),
+    }
+}
+
+/// Writes `records` as CSV with a header row.
+pub fn write_csv<W: Write, R: Record>(mut w: W, records: &[R]) -> Result<(), io::Error> {
+    let header: Vec<&str> = R::COLUMNS.iter().map(|c| c.name).collect();
+    writeln!(w, "{}", header.join(","))?;
+
+    for record in records {
+        let row: Vec<String> = record.values().iter().map(csv_field).collect();
+        writeln!(w, "{}", row.join(","))?;
+    }
+
+    w.flush()
+}
+
+/// Writes `records` as JSON Lines, one object per record.
+pub fn write_jsonl<W: Write, R: Record>(mut w: W, records: &[R]) -> Result<(), io::Error> {
+    for record in records {
+        serde_json::to_writer(&mut w, record)?;
+        w.write_all(b"\n")?;
+    }
+
+    w.flush()
+}
+
+/// Writes `records` as a single-row-group Parquet file.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: Write + Send, R: Record>(w: W, records: &[R]) -> Result<(), io::Error> {
+    use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    let fields: Vec<String> = R::COLUMNS
+        .iter()
+        .map(|c| {
+            let repetition = if c.nullable { "OPTIONAL" } else { "REQUIRED" };
+            let physical = match c.kind {
+                ColumnKind::Int => "INT64",
+                ColumnKind::Float => "DOUBLE",
+                ColumnKind::Bool => "BOOLEAN",
+                ColumnKind::Text => "BYTE_ARRAY",
+            };
+            let logical = if c.kind == ColumnKind::Text {
+                " (UTF8)"
+            } else {
+                ""
+            };
+            format!("{repetition} {physical} {}{logical};", c.name)
+        })
+        .collect();
+    let schema = parse_message_type(&format!("message {} {{ {} }}", R::NAME, fields.join(" ")))
+        .map_err(io::Error::other)?;
+
+    let rows: Vec<Vec<Value>> = records.iter().map(Record::values).collect();
+    let mut writer = SerializedFileWriter::new(
+        w,
+        Arc::new(schema),
+        Arc::new(WriterProperties::builder().build()),
+    )
+    .map_err(io::Error::other)?;
+    let mut row_group = writer.next_row_group().map_err(io::Error::other)?;
+
+    for (idx, column) in R::COLUMNS.iter().enumerate() {
+        let values: Vec<&Value> = rows.iter().map(|row| &row[idx]).collect();
+        let def_levels: Vec<i16> = values
+            .iter()
+            .map(|v| if **v == Value::Null { 0 } else { 1 })
+            .collect();
+        let def_levels = column.nullable.then_some(&def_levels[..]);
+
+        let mut writer = row_group
+            .next_column()
+            .map_err(io::Error::other)?
+            .expect("The schema has a column for every entry in COLUMNS.");
+
+        match column.kind {
+            ColumnKind::Int => write_column::<Int64Type, _>(
+                &mut writer,
+                column,
+                &values,
+                def_levels,
+                |v| match v {
+                    Value::Int(v) => Some(*v),
+                    _ => None,
+                },
+            ),
+            ColumnKind::Float => {
+                write_column::<DoubleType, _>(&mut writer, column, &values, def_levels, |v| match v
+                {
+                    Value::Float(v) => Some(*v),
+                    _ => None,
+                })
+            }
+            ColumnKind::Bool => {
+                write_column::<BoolType, _>(&mut writer, column, &values, def_levels, |v| match v {
+                    Value::Bool(v) => Some(*v),
+                    _ => None,
+                })
+            }
+            ColumnKind::Text => {
+                write_column::<ByteArrayType, _>(&mut writer, column, &values, def_levels, |v| {
+                    match v {
+                        Value::Text(v) => Some(ByteArray::from(v.as_str())),
+                        _ => None,
+                    }
+                })
+            }
+        }?;
+
+        writer.close().map_err(io::Error::other)?;
+    }
+
+    row_group.close().map_err(io::Error::other)?;
+    writer.close().map_err(io::Error::other)?;
+
+    Ok(())
+}
+
+/// Writes the non-null `values` of one column, extracting the physical value with `extract`.
+#[cfg(feature = "parquet")]
+fn write_column<T, F>(
+    writer: &mut parquet::file::writer::SerializedColumnWriter,
+    column: &Column,
+    values: &[&Value],
+    def_levels: Option<&[i16]>,
+    extract: F,
+) -> Result<(), io::Error>
+where
+    T: parquet::data_type::DataType,
+    F: Fn(&Value) -> Option<T::T>,
+{
+    let data = values
+        .iter()
+        .filter(|v| ***v != Value::Null)
+        .map(|v| {
+            extract(v).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Value {:?} does not match the type of column {}.",
+                        v, column.name
+                    ),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    writer
+        .typed::<T>()
+        .write_batch(&data, def_levels, None)
+        .map_err(io::Error::other)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(error: Option<&str>) -> TransactionRecord {
+        TransactionRecord {
+            unix_millis: 10,
+            request: vec![b'e'],
+            response: b"#".to_vec(),
+            latency_micros: 1500,
+            error: error.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn csv_position() {
+        let mut out = Vec::new();
+        let record = PositionRecord {
+            unix_millis: 1,
+            ra: 2.5,
+            dec: -3.0,
+            az: 4.0,
+            el: 5.0,
+        };
+        write_csv(&mut out, &[record]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "unix_millis,ra,dec,az,el\n1,2.5,-3,4,5\n"
+        );
+    }
+
+    #[test]
+    fn csv_escapes_errors() {
+        let mut out = Vec::new();
+        write_csv(
+            &mut out,
+            &[transaction(Some("bad, \"short\" frame")), transaction(None)],
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "unix_millis,request,response,latency_micros,error\n\
+             10,65,23,1500,\"bad, \"\"short\"\" frame\"\n\
+             10,65,23,1500,\n"
+        );
+    }
+
+    #[test]
+    fn jsonl_matches_columns() {
+        let mut out = Vec::new();
+        write_jsonl(&mut out, &[transaction(None)]).unwrap();
+        let line: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let keys: Vec<&str> = line
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let mut columns: Vec<&str> = TransactionRecord::COLUMNS.iter().map(|c| c.name).collect();
+        columns.sort();
+        assert_eq!(keys, columns);
+        assert_eq!(line["request"], "65");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_round_trip() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let path = std::env::temp_dir().join("nexlib_export_parquet_round_trip.parquet");
+        let file = std::fs::File::create(&path).unwrap();
+        write_parquet(file, &[transaction(Some("timeout")), transaction(None)]).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+        assert_eq!(
+            reader
+                .metadata()
+                .file_metadata()
+                .schema_descr()
+                .num_columns(),
+            TransactionRecord::COLUMNS.len()
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Stable C ABI for driving a mount from C and C++.
+//!
+//! The mount is exposed as an opaque `NexMount` handle created with `nex_mount_create` and released with
+//! `nex_mount_destroy`. Every other function returns a `NexStatus`, writing results through out-pointers only on
+//! success. The header in `include/nexlib.h` is generated from this file with `cbindgen --output include/nexlib.h`.
+
+use crate::mount::error::MountError;
+use crate::mount::Mount;
+use crate::{AzEl, CelestronMount, RADec};
+use std::ffi::c_char;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Result of every fallible FFI call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NexStatus {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// No mount was found.
+    NotFound = 2,
+    /// The mount sent a response that could not be understood.
+    InvalidData = 3,
+    /// The mount did not respond in time.
+    Timeout = 4,
+    /// The addressed device is unavailable or the command is not supported.
+    NotConnected = 5,
+    /// An argument was out of range.
+    InvalidInput = 6,
+    /// Any other I/O failure.
+    Io = 7,
+    /// The library panicked; the handle should be destroyed.
+    Panic = 8,
+}
+
+impl From<MountError> for NexStatus {
+    fn from(e: MountError) -> Self {
+        match e.kind() {
+            io::ErrorKind::NotFound => NexStatus::NotFound,
+            io::ErrorKind::InvalidData => NexStatus::InvalidData,
+            io::ErrorKind::TimedOut => NexStatus::Timeout,
+            io::ErrorKind::NotConnected => NexStatus::NotConnected,
+            io::ErrorKind::InvalidInput => NexStatus::InvalidInput,
+            _ => NexStatus::Io,
+        }
+    }
+}
+
+/// Opaque handle to a connected mount.
+pub struct NexMount {
+    mount: CelestronMount,
+}
+
+/// Runs `f`, converting errors and panics into a status code so that neither crosses the FFI boundary.
+fn guard<F: FnOnce() -> Result<(), MountError>>(f: F) -> NexStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => NexStatus::Ok,
+        Ok(Err(e)) => e.into(),
+        Err(_) => NexStatus::Panic,
+    }
+}
+
+/// Runs `f` against the mount behind `handle`.
+///
+/// # Safety
+///
+/// `handle` must be null or a pointer returned by `nex_mount_create` which has not been destroyed.
+unsafe fn with_mount<F>(handle: *mut NexMount, f: F) -> NexStatus
+where
+    F: FnOnce(&mut CelestronMount) -> Result<(), MountError>,
+{
+    match handle.as_mut() {
+        Some(handle) => guard(|| f(&mut handle.mount)),
+        None => NexStatus::NullPointer,
+    }
+}
+
+/// Returns a static, human-readable description of `status`.
+#[no_mangle]
+pub extern "C" fn nex_status_message(status: NexStatus) -> *const c_char {
+    let msg: &'static [u8] = match status {
+        NexStatus::Ok => b"Success.\0",
+        NexStatus::NullPointer => b"A required pointer argument was null.\0",
+        NexStatus::NotFound => b"No mount was found.\0",
+        NexStatus::InvalidData => b"The mount sent an invalid response.\0",
+        NexStatus::Timeout => b"The mount did not respond in time.\0",
+        NexStatus::NotConnected => b"The device is unavailable or the command is not supported.\0",
+        NexStatus::InvalidInput => b"An argument was out of range.\0",
+        NexStatus::Io => b"Serial communication failed.\0",
+        NexStatus::Panic => b"Internal library error.\0",
+    };
+    msg.as_ptr().cast()
+}
+
+/// Connects to the first detected mount, storing a new handle in `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nex_mount_create(out: *mut *mut NexMount) -> NexStatus {
+    if out.is_null() {
+        return NexStatus::NullPointer;
+    }
+
+    let mut handle = None;
+    let status = guard(|| {
+        handle = Some(Box::new(NexMount {
+            mount: CelestronMount::new()?,
+        }));
+        Ok(())
+    });
+
+    if let Some(handle) = handle {
+        *out = Box::into_raw(handle);
+    }
+
+    status
+}
+
+/// Closes the connection and frees the handle. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `handle` must be null or a pointer returned by `nex_mount_create` which has not already been destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn nex_mount_destroy(handle: *mut NexMount) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Gets the current position in right ascension and declination, in degrees.
+///
+/// # Safety
+///
+/// `handle` must be a live handle and `ra` and `dec` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nex_mount_get_position_ra_dec(
+    handle: *mut NexMount,
+    ra: *mut f64,
+    dec: *mut f64,
+) -> NexStatus {
+    if ra.is_null() || dec.is_null() {
+        return NexStatus::NullPointer;
+    }
+
+    with_mount(handle, |m| {
+        let pos = m.get_position_ra_dec()?;
+        *ra = pos.ra;
+        *dec = pos.dec;
+        Ok(())
+    })
+}
+
+/// Gets the current position in azimuth and elevation, in degrees.
+///
+/// # Safety
+///
+/// `handle` must be a live handle and `az` and `el` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nex_mount_get_position_az_el(
+    handle: *mut NexMount,
+    az: *mut f64,
+    el: *mut f64,
+) -> NexStatus {
+    if az.is_null() || el.is_null() {
+        return NexStatus::NullPointer;
+    }
+
+    with_mount(handle, |m| {
+        let pos = m.get_position_az_el()?;
+        *az = pos.az;
+        *el = pos.el;
+        Ok(())
+    })
+}
+
+/// Starts a goto to a right ascension and declination, in degrees.
+///
+/// # Safety
+///
+/// `handle` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn nex_mount_goto_ra_dec(
+    handle: *mut NexMount,
+    ra: f64,
+    dec: f64,
+) -> NexStatus {
+    with_mount(handle, |m| m.goto_ra_dec(RADec::new(ra, dec)))
+}
+
+/// Starts a goto to an azimuth and elevation, in degrees.
+///
+/// # Safety
+///
+/// `handle` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn nex_mount_goto_az_el(
+    handle: *mut NexMount,
+    az: f64,
+    el: f64,
+) -> NexStatus {
+    with_mount(handle, |m| m.goto_az_el(AzEl::new(az, el)))
+}
+
+/// Writes whether a goto is currently in progress to `in_progress`.
+///
+/// # Safety
+///
+/// `handle` must be a live handle and `in_progress` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nex_mount_goto_in_progress(
+    handle: *mut NexMount,
+    in_progress: *mut bool,
+) -> NexStatus {
+    if in_progress.is_null() {
+        return NexStatus::NullPointer;
+    }
+
+    with_mount(handle, |m| {
+        *in_progress = m.goto_in_progress()?;
+        Ok(())
+    })
+}
+
+/// Cancels the goto in progress.
+///
+/// # Safety
+///
+/// `handle` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn nex_mount_cancel_goto(handle: *mut NexMount) -> NexStatus {
+    with_mount(handle, |m| m.cancel_goto())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::ptr;
+
+    #[test]
+    fn null_handles_are_rejected() {
+        let (mut a, mut b) = (0.0, 0.0);
+        unsafe {
+            assert_eq!(
+                nex_mount_get_position_ra_dec(ptr::null_mut(), &mut a, &mut b),
+                NexStatus::NullPointer
+            );
+            assert_eq!(
+                nex_mount_goto_ra_dec(ptr::null_mut(), 1.0, 2.0),
+                NexStatus::NullPointer
+            );
+            assert_eq!(nex_mount_create(ptr::null_mut()), NexStatus::NullPointer);
+            nex_mount_destroy(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn status_messages_are_terminated() {
+        let msg = unsafe { CStr::from_ptr(nex_status_message(NexStatus::Timeout)) };
+        assert_eq!(msg.to_str().unwrap(), "The mount did not respond in time.");
+    }
+
+    #[test]
+    fn panics_do_not_unwind() {
+        assert_eq!(guard(|| panic!("boom")), NexStatus::Panic);
+    }
+}
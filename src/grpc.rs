@@ -0,0 +1,419 @@
+//! gRPC service exposing a [`Mount`] to remote clients.
+//!
+//! The service definition lives in `proto/nexlib.proto`, so typed clients can be generated for any language with
+//! gRPC support. Calls are forwarded to the mount on a blocking thread since all serial communication is synchronous.
+
+// `tonic::Status` is large, but it is the error type tonic requires of every handler.
+#![allow(clippy::result_large_err)]
+
+use crate::mount::error::MountError;
+use crate::mount::{Mount, SlewAxis, SlewDir, SlewRate, TrackingMode};
+use crate::{AzEl, RADec};
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status};
+
+/// Code generated from `proto/nexlib.proto`.
+pub mod proto {
+    tonic::include_proto!("nexlib");
+}
+
+use proto::mount_server::{Mount as MountRpc, MountServer};
+
+/// Shortest interval accepted by `StreamPosition`, to keep a single client from saturating the serial link.
+const MIN_STREAM_INTERVAL_MS: u32 = 100;
+
+/// Converts an error from the mount into the closest matching gRPC status.
+fn mount_status(e: MountError) -> Status {
+    let msg = e.to_string();
+    match e.kind() {
+        io::ErrorKind::NotFound => Status::not_found(msg),
+        io::ErrorKind::InvalidInput => Status::invalid_argument(msg),
+        io::ErrorKind::TimedOut => Status::deadline_exceeded(msg),
+        io::ErrorKind::NotConnected => Status::unavailable(msg),
+        io::ErrorKind::Unsupported => Status::unimplemented(msg),
+        _ => Status::internal(msg),
+    }
+}
+
+fn tracking_mode_from_proto(mode: i32) -> Result<TrackingMode, Status> {
+    match proto::TrackingMode::try_from(mode) {
+        Ok(proto::TrackingMode::Off) => Ok(TrackingMode::Off),
+        Ok(proto::TrackingMode::AzEl) => Ok(TrackingMode::AzEl),
+        Ok(proto::TrackingMode::EqNorth) => Ok(TrackingMode::EQNorth),
+        Ok(proto::TrackingMode::EqSouth) => Ok(TrackingMode::EQSouth),
+        Err(_) => Err(Status::invalid_argument(format!(
+            "Invalid tracking mode {mode}."
+        ))),
+    }
+}
+
+fn tracking_mode_to_proto(mode: TrackingMode) -> proto::TrackingMode {
+    match mode {
+        TrackingMode::Off => proto::TrackingMode::Off,
+        TrackingMode::AzEl => proto::TrackingMode::AzEl,
+        TrackingMode::EQNorth => proto::TrackingMode::EqNorth,
+        TrackingMode::EQSouth => proto::TrackingMode::EqSouth,
+    }
+}
+
+fn slew_axis_from_proto(axis: i32) -> Result<SlewAxis, Status> {
+    match proto::SlewAxis::try_from(axis) {
+        Ok(proto::SlewAxis::RaAz) => Ok(SlewAxis::RAAz),
+        Ok(proto::SlewAxis::DecEl) => Ok(SlewAxis::DecEl),
+        Err(_) => Err(Status::invalid_argument(format!(
+            "Invalid slew axis {axis}."
+        ))),
+    }
+}
+
+fn slew_dir_from_proto(dir: i32) -> Result<SlewDir, Status> {
+    match proto::SlewDir::try_from(dir) {
+        Ok(proto::SlewDir::Positive) => Ok(SlewDir::Positive),
+        Ok(proto::SlewDir::Negative) => Ok(SlewDir::Negative),
+        Err(_) => Err(Status::invalid_argument(format!(
+            "Invalid slew direction {dir}."
+        ))),
+    }
+}
+
+fn slew_rate_from_proto(rate: u32) -> Result<SlewRate, Status> {
+    match rate {
+        0 => Ok(SlewRate::Stop),
+        1 => Ok(SlewRate::Rate1),
+        2 => Ok(SlewRate::Rate2),
+        3 => Ok(SlewRate::Rate3),
+        4 => Ok(SlewRate::Rate4),
+        5 => Ok(SlewRate::Rate5),
+        6 => Ok(SlewRate::Rate6),
+        7 => Ok(SlewRate::Rate7),
+        8 => Ok(SlewRate::Rate8),
+        9 => Ok(SlewRate::Rate9),
+        _ => Err(Status::invalid_argument(format!(
+            "Invalid fixed slew rate {rate}."
+        ))),
+    }
+}
+
+fn ra_dec_to_proto(coord: RADec) -> proto::RaDec {
+    proto::RaDec {
+        ra: coord.ra,
+        dec: coord.dec,
+    }
+}
+
+fn az_el_to_proto(coord: AzEl) -> proto::AzEl {
+    proto::AzEl {
+        az: coord.az,
+        el: coord.el,
+    }
+}
+
+/// Runs `f` against the shared mount on a blocking thread.
+async fn with_mount<M, T, F>(mount: &Arc<Mutex<M>>, f: F) -> Result<T, Status>
+where
+    M: Mount + Send + 'static,
+    T: Send + 'static,
+    F: FnOnce(&mut M) -> Result<T, Status> + Send + 'static,
+{
+    let mount = Arc::clone(mount);
+    tokio::task::spawn_blocking(move || {
+        let mut mount = mount
+            .lock()
+            .map_err(|_| Status::internal("Mount lock poisoned."))?;
+        f(&mut mount)
+    })
+    .await
+    .map_err(|e| Status::internal(format!("Mount task failed: {e}")))?
+}
+
+/// Implements the `nexlib.Mount` gRPC service on top of any [`Mount`].
+pub struct MountService<M> {
+    mount: Arc<Mutex<M>>,
+}
+
+impl<M: Mount + Send + 'static> MountService<M> {
+    pub fn new(mount: M) -> MountService<M> {
+        Self::from_shared(Arc::new(Mutex::new(mount)))
+    }
+
+    /// Creates a service around a mount which is also used elsewhere in the application.
+    pub fn from_shared(mount: Arc<Mutex<M>>) -> MountService<M> {
+        MountService { mount }
+    }
+
+    /// Wraps the service in a tonic server ready to be added to a router.
+    pub fn into_server(self) -> MountServer<MountService<M>> {
+        MountServer::new(self)
+    }
+
+    async fn call<T, F>(&self, f: F) -> Result<Response<T>, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut M) -> Result<T, Status> + Send + 'static,
+    {
+        with_mount(&self.mount, f).await.map(Response::new)
+    }
+}
+
+#[tonic::async_trait]
+impl<M: Mount + Send + 'static> MountRpc for MountService<M> {
+    async fn get_position_ra_dec(
+        &self,
+        _: Request<proto::Empty>,
+    ) -> Result<Response<proto::RaDec>, Status> {
+        self.call(|m| {
+            m.get_position_ra_dec()
+                .map(ra_dec_to_proto)
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn get_position_az_el(
+        &self,
+        _: Request<proto::Empty>,
+    ) -> Result<Response<proto::AzEl>, Status> {
+        self.call(|m| {
+            m.get_position_az_el()
+                .map(az_el_to_proto)
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn goto_ra_dec(
+        &self,
+        request: Request<proto::RaDec>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let coord = request.into_inner();
+        self.call(move |m| {
+            m.goto_ra_dec(RADec::new(coord.ra, coord.dec))
+                .map(|_| proto::Empty {})
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn goto_az_el(
+        &self,
+        request: Request<proto::AzEl>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let coord = request.into_inner();
+        self.call(move |m| {
+            m.goto_az_el(AzEl::new(coord.az, coord.el))
+                .map(|_| proto::Empty {})
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn sync(&self, request: Request<proto::RaDec>) -> Result<Response<proto::Empty>, Status> {
+        let coord = request.into_inner();
+        self.call(move |m| {
+            m.sync(RADec::new(coord.ra, coord.dec))
+                .map(|_| proto::Empty {})
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn get_tracking_mode(
+        &self,
+        _: Request<proto::Empty>,
+    ) -> Result<Response<proto::TrackingModeMessage>, Status> {
+        self.call(|m| {
+            m.get_tracking_mode()
+                .map(|mode| proto::TrackingModeMessage {
+                    mode: tracking_mode_to_proto(mode).into(),
+                })
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn set_tracking_mode(
+        &self,
+        request: Request<proto::TrackingModeMessage>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let mode = tracking_mode_from_proto(request.into_inner().mode)?;
+        self.call(move |m| {
+            m.set_tracking_mode(mode)
+                .map(|_| proto::Empty {})
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn slew_variable(
+        &self,
+        request: Request<proto::SlewVariableRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let axis = slew_axis_from_proto(request.axis)?;
+        let dir = slew_dir_from_proto(request.dir)?;
+        let rate = u16::try_from(request.rate).map_err(|_| {
+            Status::invalid_argument(format!("Slew rate {} is too large.", request.rate))
+        })?;
+        self.call(move |m| {
+            m.slew_variable(axis, dir, rate)
+                .map(|_| proto::Empty {})
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn slew_fixed(
+        &self,
+        request: Request<proto::SlewFixedRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let axis = slew_axis_from_proto(request.axis)?;
+        let dir = slew_dir_from_proto(request.dir)?;
+        let rate = slew_rate_from_proto(request.rate)?;
+        self.call(move |m| {
+            m.slew_fixed(axis, dir, rate)
+                .map(|_| proto::Empty {})
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn stop_slew(
+        &self,
+        request: Request<proto::StopSlewRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let axis = slew_axis_from_proto(request.into_inner().axis)?;
+        self.call(move |m| {
+            m.stop_slew(axis)
+                .map(|_| proto::Empty {})
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn get_time(&self, _: Request<proto::Empty>) -> Result<Response<proto::Time>, Status> {
+        self.call(|m| {
+            m.get_time()
+                .map(|t| proto::Time {
+                    unix_seconds: t.timestamp(),
+                })
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn get_version(
+        &self,
+        _: Request<proto::Empty>,
+    ) -> Result<Response<proto::Version>, Status> {
+        self.call(|m| {
+            m.get_version()
+                .map(|version| proto::Version { version })
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn get_model(
+        &self,
+        _: Request<proto::Empty>,
+    ) -> Result<Response<proto::ModelMessage>, Status> {
+        self.call(|m| {
+            m.get_model()
+                .map(|model| proto::ModelMessage {
+                    name: model.to_string(),
+                    id: model as u32,
+                })
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn is_aligned(&self, _: Request<proto::Empty>) -> Result<Response<proto::Flag>, Status> {
+        self.call(|m| {
+            m.is_aligned()
+                .map(|value| proto::Flag { value })
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn goto_in_progress(
+        &self,
+        _: Request<proto::Empty>,
+    ) -> Result<Response<proto::Flag>, Status> {
+        self.call(|m| {
+            m.goto_in_progress()
+                .map(|value| proto::Flag { value })
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    async fn cancel_goto(
+        &self,
+        _: Request<proto::Empty>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        self.call(|m| {
+            m.cancel_goto()
+                .map(|_| proto::Empty {})
+                .map_err(mount_status)
+        })
+        .await
+    }
+
+    type StreamPositionStream =
+        Pin<Box<dyn Stream<Item = Result<proto::Position, Status>> + Send + 'static>>;
+
+    async fn stream_position(
+        &self,
+        request: Request<proto::StreamPositionRequest>,
+    ) -> Result<Response<Self::StreamPositionStream>, Status> {
+        let interval_ms = request.into_inner().interval_ms.max(MIN_STREAM_INTERVAL_MS);
+        let mount = Arc::clone(&self.mount);
+        let (tx, rx) = mpsc::channel(4);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(interval_ms.into()));
+            loop {
+                interval.tick().await;
+
+                let position = with_mount(&mount, |m| {
+                    let ra_dec = m.get_position_ra_dec().map_err(mount_status)?;
+                    let az_el = m.get_position_az_el().map_err(mount_status)?;
+                    Ok(proto::Position {
+                        unix_millis: chrono::Utc::now().timestamp_millis(),
+                        ra_dec: Some(ra_dec_to_proto(ra_dec)),
+                        az_el: Some(az_el_to_proto(az_el)),
+                    })
+                })
+                .await;
+
+                // Stop polling the mount once the client has gone away.
+                if tx.send(position).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+/// Serves `mount` over gRPC on `addr` until the server fails.
+pub async fn serve<M: Mount + Send + 'static>(
+    mount: M,
+    addr: SocketAddr,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(MountService::new(mount).into_server())
+        .serve(addr)
+        .await
+}
@@ -0,0 +1,269 @@
+//! A guide port on a TCP socket, for autoguiding programs such as PHD2 to guide through nexlib.
+//!
+//! A [`GuidePortServer`] stands in for the ST-4 port of a mount: guiding programs connect to it and send pulse guide
+//! commands, which it passes on to any [`Guider`], such as a [`CelestronMount`](crate::CelestronMount) guiding through
+//! its motor controllers. It speaks the pulse guide subset of the Meade LX200 command set that guiding programs
+//! already use for LX200 mounts on a network port, so in PHD2 choose an LX200 mount at `localhost` on
+//! [`DEFAULT_PORT`]:
+//!
+//! ```no_run
+//! use nexlib::guideport::GuidePortServer;
+//! use nexlib::CelestronMount;
+//! use std::sync::{Arc, Mutex};
+//!
+//! let mount = CelestronMount::new().unwrap();
+//! let server = GuidePortServer::bind(("127.0.0.1", nexlib::guideport::DEFAULT_PORT)).unwrap();
+//! server.run(Arc::new(Mutex::new(mount))).unwrap();
+//! ```
+//!
+//! Commands start with `:` and end with `#`:
+//!
+//! - `:Mgn1000#`, `:Mgs`, `:Mge`, and `:Mgw` pulse north, south, east, or west for a number of milliseconds. Pulses
+//!   longer than the mount takes at once are sent in parts, each after the last has finished.
+//! - `:Q#` stops all motion.
+//! - `:GVP#` answers `nexlib#`, and the acknowledge byte `0x06` answers `P` for a polar mount, so clients identifying
+//!   the mount accept it.
+//!
+//! Other commands are ignored, as LX200 mounts ignore commands they do not know. Each client is served on its own
+//! thread; they share the mount, one command at a time.
+
+use crate::mount::codec::MAX_GUIDE_PULSE;
+use crate::mount::{GuideDirection, Guider, Mount, SlewAxis};
+use std::io::{self, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Port LX200 mounts are conventionally served on over TCP.
+pub const DEFAULT_PORT: u16 = 4030;
+
+/// Longest command accepted, so a client cannot make a session buffer without bound.
+const MAX_COMMAND: usize = 32;
+
+/// Time between checks whether a pulse sent in parts has finished its current part.
+const PULSE_POLL: Duration = Duration::from_millis(20);
+
+/// A command from a guiding program.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum GuideCommand {
+    /// The acknowledge byte, asking for the alignment mode.
+    Ack,
+    Pulse(GuideDirection, u32),
+    Stop,
+    ProductName,
+    /// A command with no effect.
+    Ignored,
+}
+
+/// Splits a client's byte stream into commands.
+#[derive(Debug, Default)]
+struct Parser {
+    command: Option<Vec<u8>>,
+}
+
+impl Parser {
+    /// Adds a byte, returning the command it completes, if any.
+    fn push(&mut self, byte: u8) -> Option<GuideCommand> {
+        match (byte, &mut self.command) {
+            (0x06, None) => Some(GuideCommand::Ack),
+            (b':', _) => {
+                self.command = Some(Vec::new());
+                None
+            }
+            (b'#', Some(_)) => self.command.take().map(|c| parse(&c)),
+            (_, Some(command)) if command.len() < MAX_COMMAND => {
+                command.push(byte);
+                None
+            }
+            (_, Some(_)) => {
+                self.command = None;
+                None
+            }
+            (_, None) => None,
+        }
+    }
+}
+
+/// Parses a command between its `:` and `#`.
+fn parse(command: &[u8]) -> GuideCommand {
+    match command {
+        [b'M', b'g', dir, ms @ ..] => {
+            let direction = match dir.to_ascii_lowercase() {
+                b'n' => GuideDirection::North,
+                b's' => GuideDirection::South,
+                b'e' => GuideDirection::East,
+                b'w' => GuideDirection::West,
+                _ => return GuideCommand::Ignored,
+            };
+            match std::str::from_utf8(ms)
+                .ok()
+                .and_then(|ms| ms.trim().parse().ok())
+            {
+                Some(ms) => GuideCommand::Pulse(direction, ms),
+                None => GuideCommand::Ignored,
+            }
+        }
+        b"Q" => GuideCommand::Stop,
+        b"GVP" => GuideCommand::ProductName,
+        _ => GuideCommand::Ignored,
+    }
+}
+
+/// Carries out `command`, returning the reply to send, if any.
+fn execute<M: Mount + Guider>(
+    mount: &Mutex<M>,
+    command: GuideCommand,
+) -> Result<Option<&'static [u8]>, io::Error> {
+    let lock = || mount.lock().unwrap_or_else(|e| e.into_inner());
+    match command {
+        GuideCommand::Ack => return Ok(Some(b"P")),
+        GuideCommand::ProductName => return Ok(Some(b"nexlib#")),
+        GuideCommand::Pulse(direction, mut ms) => {
+            let max = MAX_GUIDE_PULSE.as_millis() as u32;
+            loop {
+                lock().pulse_guide(direction, ms.min(max))?;
+                if ms <= max {
+                    break;
+                }
+                ms -= max;
+                while lock().is_pulse_guiding()? {
+                    thread::sleep(PULSE_POLL);
+                }
+            }
+        }
+        GuideCommand::Stop => {
+            let mut mount = lock();
+            mount.cancel_goto()?;
+            mount.stop_slew(SlewAxis::RAAz)?;
+            mount.stop_slew(SlewAxis::DecEl)?;
+        }
+        GuideCommand::Ignored => (),
+    }
+    Ok(None)
+}
+
+/// Serves one client until it disconnects.
+fn serve_client<M: Mount + Guider>(stream: TcpStream, mount: &Mutex<M>) -> Result<(), io::Error> {
+    let mut out = stream.try_clone()?;
+    let mut parser = Parser::default();
+    for byte in BufReader::new(stream).bytes() {
+        let Some(command) = parser.push(byte?) else {
+            continue;
+        };
+        log::trace!("Guide port command: {command:?}");
+        match execute(mount, command) {
+            Ok(Some(reply)) => out.write_all(reply)?,
+            Ok(None) => (),
+            // The protocol has no way to report errors, so the client carries on.
+            Err(e) => log::warn!(
+                "[{}:{}] Guide port command {:?} failed: {}",
+                file!(),
+                line!(),
+                command,
+                e
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// A TCP server taking pulse guide commands; see [`guideport`](self).
+#[derive(Debug)]
+pub struct GuidePortServer {
+    listener: TcpListener,
+}
+
+impl GuidePortServer {
+    /// Listens on `addr`. Guiding programs usually run on the same machine, so bind to `127.0.0.1` unless they do
+    /// not: the port has no authentication.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<GuidePortServer, io::Error> {
+        Ok(GuidePortServer {
+            listener: TcpListener::bind(addr)?,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
+
+    /// Serves clients, each on its own thread, until the listener fails.
+    pub fn run<M: Mount + Guider + Send + 'static>(
+        self,
+        mount: Arc<Mutex<M>>,
+    ) -> Result<(), io::Error> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let mount = Arc::clone(&mount);
+            thread::spawn(move || {
+                if let Err(e) = serve_client(stream, &mount) {
+                    log::debug!(
+                        "[{}:{}] Guide port client dropped: {:?}",
+                        file!(),
+                        line!(),
+                        e
+                    );
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use crate::RADec;
+    use chrono::TimeZone;
+
+    #[test]
+    fn guides_sim_mount() {
+        let mut parser = Parser::default();
+        let commands: Vec<_> = b"\x06:GVP#:Mgw0500#:MgX100#:Mg#:Q#"
+            .iter()
+            .filter_map(|&b| parser.push(b))
+            .collect();
+        assert_eq!(
+            commands,
+            [
+                GuideCommand::Ack,
+                GuideCommand::ProductName,
+                GuideCommand::Pulse(GuideDirection::West, 500),
+                GuideCommand::Ignored,
+                GuideCommand::Ignored,
+                GuideCommand::Stop,
+            ]
+        );
+
+        let time = chrono::Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap();
+        let mut sim = SimMount::new().manual_clock(time);
+        sim.goto_ra_dec(RADec::new(100.0, 20.0)).unwrap();
+        while sim.goto_in_progress().unwrap() {
+            sim.step(Duration::from_secs(1));
+        }
+        let mount = Arc::new(Mutex::new(sim));
+        let server = GuidePortServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let shared = Arc::clone(&mount);
+        thread::spawn(move || server.run(shared));
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client.write_all(b":GVP#").unwrap();
+        let mut reply = [0; 7];
+        client.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"nexlib#");
+
+        let before = mount.lock().unwrap().get_position_ra_dec().unwrap();
+        // Two seconds north at half the sidereal rate, then an acknowledge to know it was handled.
+        client.write_all(b":Mgn2000#\x06").unwrap();
+        client.read_exact(&mut reply[..1]).unwrap();
+        assert_eq!(reply[0], b'P');
+        let mut sim = mount.lock().unwrap();
+        assert!(sim.is_pulse_guiding().unwrap());
+        sim.step(Duration::from_secs(3));
+        assert!(!sim.is_pulse_guiding().unwrap());
+        let moved = (sim.get_position_ra_dec().unwrap().dec - before.dec) * 3600.0;
+        assert!((moved - 15.04).abs() < 0.01, "{moved}");
+    }
+}
@@ -0,0 +1,325 @@
+//! Home Assistant MQTT discovery messages for a mount.
+//!
+//! Publishing the retained messages from [`Discovery::config_messages`] makes the mount appear in Home Assistant as a
+//! device with position, tracking, and goto sensors and Park and Stop buttons. State is published as a single JSON
+//! object to [`Discovery::state_topic`], and button presses arrive on [`Discovery::command_topic`] as `PARK` or
+//! `STOP`.
+//!
+//! This module only builds topics and payloads; sending them is left to whichever MQTT client the application uses:
+//!
+//! ```ignore
+//! let discovery = Discovery::new("observatory_mount", "Observatory Mount");
+//! for msg in discovery.config_messages() {
+//!     client.publish(msg.topic, msg.payload, msg.retain)?;
+//! }
+//! client.publish(discovery.availability_topic(), ONLINE, true)?;
+//! client.subscribe(discovery.command_topic())?;
+//! ```
+
+use crate::mount::{Mount, TrackingMode};
+use crate::AzEl;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io;
+
+/// Default topic prefix Home Assistant listens on for discovery messages.
+pub const DEFAULT_DISCOVERY_PREFIX: &str = "homeassistant";
+
+/// Availability payload published while the mount is connected.
+pub const ONLINE: &str = "online";
+/// Availability payload published when the mount disconnects, typically as the MQTT last will.
+pub const OFFLINE: &str = "offline";
+
+/// A message to publish.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Publish {
+    pub topic: String,
+    pub payload: String,
+    pub retain: bool,
+}
+
+/// Actions triggered by the Home Assistant buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Stop both axes and slew to the park position.
+    Park,
+    /// Stop both axes and cancel any goto.
+    Stop,
+}
+
+impl Command {
+    /// Parses a payload received on the command topic.
+    pub fn parse(payload: &str) -> Option<Command> {
+        match payload.trim() {
+            "PARK" => Some(Command::Park),
+            "STOP" => Some(Command::Stop),
+            _ => None,
+        }
+    }
+
+    /// Executes the command, using `park` as the park position.
+    pub fn execute<M: Mount>(self, mount: &mut M, park: AzEl) -> Result<(), io::Error> {
+        mount.stop_all()?;
+
+        if self == Command::Park {
+            mount.set_tracking_mode(TrackingMode::Off)?;
+            mount.goto_az_el(park)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Mount state published to the state topic.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct State {
+    pub ra: f64,
+    pub dec: f64,
+    pub az: f64,
+    pub el: f64,
+    pub tracking_mode: String,
+    /// `ON` or `OFF`, as expected by Home Assistant binary sensors.
+    pub goto_in_progress: &'static str,
+}
+
+impl State {
+    /// Queries the current state of `mount`.
+    pub fn sample<M: Mount>(mount: &mut M) -> Result<State, io::Error> {
+        let ra_dec = mount.get_position_ra_dec()?;
+        let az_el = mount.get_position_az_el()?;
+
+        Ok(State {
+            ra: ra_dec.ra,
+            dec: ra_dec.dec,
+            az: az_el.az,
+            el: az_el.el,
+            tracking_mode: format!("{:?}", mount.get_tracking_mode()?),
+            goto_in_progress: if mount.goto_in_progress()? {
+                "ON"
+            } else {
+                "OFF"
+            },
+        })
+    }
+}
+
+/// Discovery settings for one mount.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discovery {
+    /// Identifier unique to this mount within Home Assistant, e.g. `observatory_mount`.
+    pub node_id: String,
+    /// Device name shown in Home Assistant.
+    pub name: String,
+    pub discovery_prefix: String,
+    /// Prefix of the state, availability, and command topics.
+    pub base_topic: String,
+    /// Model reported in the device registry, e.g. from [`Mount::get_model`].
+    pub model: Option<String>,
+    /// Firmware version reported in the device registry.
+    pub sw_version: Option<String>,
+}
+
+impl Discovery {
+    pub fn new(node_id: &str, name: &str) -> Discovery {
+        Discovery {
+            node_id: node_id.to_owned(),
+            name: name.to_owned(),
+            discovery_prefix: DEFAULT_DISCOVERY_PREFIX.to_owned(),
+            base_topic: format!("nexlib/{node_id}"),
+            model: None,
+            sw_version: None,
+        }
+    }
+
+    pub fn state_topic(&self) -> String {
+        format!("{}/state", self.base_topic)
+    }
+
+    pub fn availability_topic(&self) -> String {
+        format!("{}/availability", self.base_topic)
+    }
+
+    pub fn command_topic(&self) -> String {
+        format!("{}/command", self.base_topic)
+    }
+
+    fn device(&self) -> Value {
+        let mut device = json!({
+            "identifiers": [self.node_id],
+            "name": self.name,
+            "manufacturer": "Celestron",
+        });
+        if let Some(model) = &self.model {
+            device["model"] = json!(model);
+        }
+        if let Some(sw_version) = &self.sw_version {
+            device["sw_version"] = json!(sw_version);
+        }
+        device
+    }
+
+    fn config(&self, component: &str, object_id: &str, name: &str, mut extra: Value) -> Publish {
+        let unique_id = format!("{}_{}", self.node_id, object_id);
+        let mut payload = json!({
+            "name": name,
+            "unique_id": unique_id,
+            "object_id": unique_id,
+            "availability_topic": self.availability_topic(),
+            "device": self.device(),
+        });
+        if let (Some(payload), Some(extra)) = (payload.as_object_mut(), extra.as_object_mut()) {
+            payload.append(extra);
+        }
+
+        Publish {
+            topic: format!(
+                "{}/{}/{}/{}/config",
+                self.discovery_prefix, component, self.node_id, object_id
+            ),
+            payload: payload.to_string(),
+            retain: true,
+        }
+    }
+
+    fn angle_sensor(&self, key: &str, name: &str) -> Publish {
+        self.config(
+            "sensor",
+            key,
+            name,
+            json!({
+                "state_topic": self.state_topic(),
+                "value_template": format!("{{{{ value_json.{key} }}}}"),
+                "unit_of_measurement": "°",
+                "state_class": "measurement",
+                "suggested_display_precision": 4,
+            }),
+        )
+    }
+
+    fn button(&self, key: &str, name: &str, payload: &str, icon: &str) -> Publish {
+        self.config(
+            "button",
+            key,
+            name,
+            json!({
+                "command_topic": self.command_topic(),
+                "payload_press": payload,
+                "icon": icon,
+            }),
+        )
+    }
+
+    /// Retained discovery messages announcing every entity of the mount.
+    pub fn config_messages(&self) -> Vec<Publish> {
+        vec![
+            self.angle_sensor("ra", "Right Ascension"),
+            self.angle_sensor("dec", "Declination"),
+            self.angle_sensor("az", "Azimuth"),
+            self.angle_sensor("el", "Elevation"),
+            self.config(
+                "sensor",
+                "tracking_mode",
+                "Tracking Mode",
+                json!({
+                    "state_topic": self.state_topic(),
+                    "value_template": "{{ value_json.tracking_mode }}",
+                    "icon": "mdi:telescope",
+                }),
+            ),
+            self.config(
+                "binary_sensor",
+                "goto_in_progress",
+                "Slewing",
+                json!({
+                    "state_topic": self.state_topic(),
+                    "value_template": "{{ value_json.goto_in_progress }}",
+                    "device_class": "moving",
+                }),
+            ),
+            self.button("park", "Park", "PARK", "mdi:parking"),
+            self.button("stop", "Stop", "STOP", "mdi:stop"),
+        ]
+    }
+
+    /// Empty retained messages which remove the mount from Home Assistant.
+    pub fn removal_messages(&self) -> Vec<Publish> {
+        self.config_messages()
+            .into_iter()
+            .map(|msg| Publish {
+                payload: String::new(),
+                ..msg
+            })
+            .collect()
+    }
+
+    /// State message for the sensors.
+    pub fn state_message(&self, state: &State) -> Publish {
+        Publish {
+            topic: self.state_topic(),
+            payload: serde_json::to_string(state).expect("State is always serializable."),
+            retain: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_topics_and_payloads() {
+        let mut discovery = Discovery::new("obs", "Observatory Mount");
+        discovery.model = Some("Advanced VX".to_owned());
+
+        let messages = discovery.config_messages();
+        assert_eq!(messages.len(), 8);
+        assert!(messages.iter().all(|m| m.retain));
+
+        let ra = &messages[0];
+        assert_eq!(ra.topic, "homeassistant/sensor/obs/ra/config");
+        let payload: Value = serde_json::from_str(&ra.payload).unwrap();
+        assert_eq!(payload["unique_id"], "obs_ra");
+        assert_eq!(payload["state_topic"], "nexlib/obs/state");
+        assert_eq!(payload["value_template"], "{{ value_json.ra }}");
+        assert_eq!(payload["device"]["model"], "Advanced VX");
+
+        let park = messages
+            .iter()
+            .find(|m| m.topic == "homeassistant/button/obs/park/config")
+            .unwrap();
+        let payload: Value = serde_json::from_str(&park.payload).unwrap();
+        assert_eq!(payload["command_topic"], "nexlib/obs/command");
+        assert_eq!(
+            Command::parse(payload["payload_press"].as_str().unwrap()),
+            Some(Command::Park)
+        );
+    }
+
+    #[test]
+    fn removal_clears_payloads() {
+        let discovery = Discovery::new("obs", "Observatory Mount");
+        assert!(discovery
+            .removal_messages()
+            .iter()
+            .all(|m| m.payload.is_empty() && m.retain));
+    }
+
+    #[test]
+    fn state_payload() {
+        let discovery = Discovery::new("obs", "Observatory Mount");
+        let msg = discovery.state_message(&State {
+            ra: 83.8,
+            dec: -5.4,
+            az: 120.0,
+            el: 35.0,
+            tracking_mode: "EQNorth".to_owned(),
+            goto_in_progress: "OFF",
+        });
+
+        let payload: Value = serde_json::from_str(&msg.payload).unwrap();
+        assert_eq!(msg.topic, "nexlib/obs/state");
+        assert_eq!(payload["dec"], -5.4);
+        assert_eq!(payload["goto_in_progress"], "OFF");
+        assert_eq!(Command::parse("launch"), None);
+    }
+}
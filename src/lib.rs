@@ -0,0 +1,78 @@
+//! Control of Celestron NexStar and compatible telescope mounts.
+//!
+//! [`mount`] holds the [`Mount`] trait, its implementations, and the protocol, with tools built on it such as
+//! status polling and pointing limits in its submodules. [`catalog`] finds targets and [`units`] formats angles and
+//! times for display. Optional features add the other top-level modules, such as servers and the configuration file.
+//!
+//! The mount types, traits, and coordinates are re-exported here, and [`prelude`] gathers the ones most programs need
+//! for a glob import.
+
+pub mod astro;
+pub mod catalog;
+pub mod mount;
+pub mod prelude;
+pub mod units;
+pub use mount::{
+    AzEl, CelestronGps, CelestronMount, CommsConfig, FineTracking, FixProgress, Gps, GpsFix, GpsSync, GuideDirection,
+    Guider, Location, Model, Mount, Mounting, NonGpsDevice, Pec, PecState, PrecisionMode, RADec, ResponseOverflow,
+    Rtc, SimMount, SlewAxis, SlewDir, SlewRate, TimeZoneSetting, TrackingMode, TrackingRate,
+};
+pub use mount::discovery::{discover, MountCandidate};
+pub use mount::error::MountError;
+
+#[cfg(feature = "alpaca")]
+pub mod alpaca;
+
+#[cfg(all(windows, feature = "ascom"))]
+pub mod ascom;
+
+#[cfg(feature = "config")]
+pub mod config;
+
+#[cfg(feature = "daemon")]
+pub mod daemon;
+
+#[cfg(all(target_os = "linux", feature = "estop"))]
+pub mod estop;
+
+#[cfg(feature = "export")]
+pub mod export;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+#[cfg(feature = "guideport")]
+pub mod guideport;
+
+#[cfg(feature = "homeassistant")]
+pub mod homeassistant;
+
+#[cfg(feature = "logbook")]
+pub mod logbook;
+
+#[cfg(feature = "node")]
+mod node;
+
+#[cfg(feature = "rpc")]
+pub mod rpc;
+
+#[cfg(feature = "script")]
+pub mod script;
+
+#[cfg(feature = "server")]
+pub mod server;
+
+#[cfg(feature = "sequence")]
+pub mod sequence;
+
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+
+#[cfg(feature = "websocket")]
+pub mod websocket;
@@ -0,0 +1,377 @@
+//! An observing log of a night's session.
+//!
+//! A [`Logbook`] records when the mount was connected and disconnected, each target visited with its coordinates,
+//! arrival and departure times, and any sync corrections made while on it, along with free-form notes on conditions.
+//! At the end of the night it is saved as JSON for other tools, or printed as a plain text report:
+//!
+//! ```
+//! use nexlib::logbook::Logbook;
+//! use nexlib::RADec;
+//!
+//! let mut log = Logbook::new();
+//! log.connected("/dev/ttyUSB0");
+//! log.visit("M42", RADec::new(83.82, -5.39));
+//! log.synced(RADec::new(83.82, -5.39), RADec::new(83.83, -5.40));
+//! log.note("Seeing 3/5, thin cirrus in the west.");
+//! log.disconnected(None);
+//! log.finish();
+//!
+//! println!("{}", log.report());
+//! let json = log.to_json().unwrap();
+//! ```
+//!
+//! Times are in UTC.
+
+use crate::mount::transform::angular_separation;
+use crate::RADec;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A correction applied by syncing the mount.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncCorrection {
+    pub time: DateTime<Utc>,
+    /// Where the mount thought it was pointing.
+    pub before: RADec,
+    /// Where it was actually pointing.
+    pub after: RADec,
+}
+
+impl SyncCorrection {
+    /// Size of the correction, in arcseconds.
+    pub fn offset_arcsec(&self) -> f64 {
+        angular_separation(
+            self.before.ra,
+            self.before.dec,
+            self.after.ra,
+            self.after.dec,
+        ) * 3600.0
+    }
+}
+
+/// Time spent on one target.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Visit {
+    pub name: String,
+    pub coord: RADec,
+    pub arrived: DateTime<Utc>,
+    /// When the next target was visited or the log finished; `None` while still on the target.
+    pub left: Option<DateTime<Utc>>,
+    pub syncs: Vec<SyncCorrection>,
+}
+
+/// One line of the log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+pub enum Entry {
+    Connected {
+        time: DateTime<Utc>,
+        /// The port or address of the mount.
+        device: String,
+    },
+    Disconnected {
+        time: DateTime<Utc>,
+        /// Why, if not on purpose.
+        reason: Option<String>,
+    },
+    Visit(Visit),
+    /// A sync made while not on a logged target.
+    Sync(SyncCorrection),
+    Note {
+        time: DateTime<Utc>,
+        text: String,
+    },
+}
+
+/// The log of one session; see [`logbook`](self).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Logbook {
+    pub started: DateTime<Utc>,
+    pub ended: Option<DateTime<Utc>>,
+    pub entries: Vec<Entry>,
+}
+
+impl Default for Logbook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Logbook {
+    /// A log starting now.
+    pub fn new() -> Logbook {
+        Logbook {
+            started: Utc::now(),
+            ended: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Reloads a log saved with [`Logbook::save_json`], e.g. to continue it after a restart.
+    pub fn load_json(path: impl AsRef<Path>) -> Result<Logbook, io::Error> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Adds an entry, for events not covered by the other methods or recorded elsewhere.
+    pub fn push(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
+    pub fn connected(&mut self, device: impl Into<String>) {
+        self.push(Entry::Connected {
+            time: Utc::now(),
+            device: device.into(),
+        });
+    }
+
+    /// Records a disconnection, with its `reason` if it was not on purpose. Leaves the current target.
+    pub fn disconnected(&mut self, reason: Option<&str>) {
+        let time = Utc::now();
+        self.leave(time);
+        self.push(Entry::Disconnected {
+            time,
+            reason: reason.map(str::to_string),
+        });
+    }
+
+    /// Records arriving at a target, leaving the previous one.
+    pub fn visit(&mut self, name: impl Into<String>, coord: RADec) {
+        let time = Utc::now();
+        self.leave(time);
+        self.push(Entry::Visit(Visit {
+            name: name.into(),
+            coord,
+            arrived: time,
+            left: None,
+            syncs: Vec::new(),
+        }));
+    }
+
+    /// Records a sync from the position the mount reported, `before`, to the position it was actually at, `after`.
+    pub fn synced(&mut self, before: RADec, after: RADec) {
+        let sync = SyncCorrection {
+            time: Utc::now(),
+            before,
+            after,
+        };
+        match self.current_visit() {
+            Some(visit) => visit.syncs.push(sync),
+            None => self.push(Entry::Sync(sync)),
+        }
+    }
+
+    /// Records a note, e.g. on the seeing or clouds.
+    pub fn note(&mut self, text: impl Into<String>) {
+        self.push(Entry::Note {
+            time: Utc::now(),
+            text: text.into(),
+        });
+    }
+
+    /// Ends the log, leaving the current target.
+    pub fn finish(&mut self) {
+        let time = Utc::now();
+        self.leave(time);
+        self.ended = Some(time);
+    }
+
+    /// The target still being visited, if any.
+    fn current_visit(&mut self) -> Option<&mut Visit> {
+        self.entries.iter_mut().rev().find_map(|entry| match entry {
+            Entry::Visit(visit) if visit.left.is_none() => Some(visit),
+            _ => None,
+        })
+    }
+
+    fn leave(&mut self, time: DateTime<Utc>) {
+        if let Some(visit) = self.current_visit() {
+            visit.left = Some(time);
+        }
+    }
+
+    /// Every target visited, in order.
+    pub fn visits(&self) -> impl Iterator<Item = &Visit> {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::Visit(visit) => Some(visit),
+            _ => None,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, io::Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn save_json(&self, path: impl AsRef<Path>) -> Result<(), io::Error> {
+        fs::write(path, self.to_json()?)
+    }
+
+    /// A plain text report of the session, one line per entry followed by a summary.
+    pub fn report(&self) -> String {
+        let time = |t: &DateTime<Utc>| t.format("%H:%M:%S").to_string();
+        let mut out = String::new();
+
+        let _ = write!(
+            out,
+            "Observing log, {}",
+            self.started.format("%Y-%m-%d %H:%M")
+        );
+        if let Some(ended) = &self.ended {
+            let _ = write!(out, " to {}", ended.format("%Y-%m-%d %H:%M"));
+        }
+        out.push_str(" UTC\n\n");
+
+        for entry in &self.entries {
+            let _ = match entry {
+                Entry::Connected { time: t, device } => {
+                    writeln!(out, "{}  Connected to {device}", time(t))
+                }
+                Entry::Disconnected { time: t, reason } => match reason {
+                    Some(reason) => writeln!(out, "{}  Disconnected: {reason}", time(t)),
+                    None => writeln!(out, "{}  Disconnected", time(t)),
+                },
+                Entry::Visit(visit) => {
+                    let _ = write!(
+                        out,
+                        "{}  {} {}",
+                        time(&visit.arrived),
+                        visit.name,
+                        visit.coord
+                    );
+                    if let Some(left) = &visit.left {
+                        let _ = write!(
+                            out,
+                            ", until {} ({})",
+                            time(left),
+                            duration(*left - visit.arrived)
+                        );
+                    }
+                    out.push('\n');
+                    for sync in &visit.syncs {
+                        let _ = writeln!(
+                            out,
+                            "          {}  Synced by {:.1}\"",
+                            time(&sync.time),
+                            sync.offset_arcsec()
+                        );
+                    }
+                    Ok(())
+                }
+                Entry::Sync(sync) => writeln!(
+                    out,
+                    "{}  Synced by {:.1}\"",
+                    time(&sync.time),
+                    sync.offset_arcsec()
+                ),
+                Entry::Note { time: t, text } => writeln!(out, "{}  Note: {text}", time(t)),
+            };
+        }
+
+        let targets = self.visits().count();
+        let syncs = self.visits().map(|v| v.syncs.len()).sum::<usize>()
+            + self
+                .entries
+                .iter()
+                .filter(|e| matches!(e, Entry::Sync(_)))
+                .count();
+        let on_target = self
+            .visits()
+            .filter_map(|v| Some(v.left? - v.arrived))
+            .sum::<chrono::TimeDelta>();
+        let _ = writeln!(
+            out,
+            "\n{targets} targets, {syncs} syncs, {} on target.",
+            duration(on_target)
+        );
+        out
+    }
+}
+
+/// Formats a duration as hours and minutes, or minutes and seconds if under an hour.
+fn duration(d: chrono::TimeDelta) -> String {
+    let secs = d.num_seconds().max(0);
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn records_a_session() {
+        let mut log = Logbook::new();
+        log.connected("/dev/ttyUSB0");
+        log.synced(RADec::new(10.0, 0.0), RADec::new(10.0, 0.01));
+        log.visit("M42", RADec::new(83.82, -5.39));
+        log.synced(RADec::new(83.82, -5.39), RADec::new(83.82, -5.38));
+        log.visit("M31", RADec::new(10.68, 41.27));
+        log.disconnected(Some("Cable pulled."));
+        log.finish();
+
+        let visits = log.visits().collect::<Vec<_>>();
+        assert_eq!(visits.len(), 2);
+        assert_eq!(visits[0].syncs.len(), 1);
+        assert!((visits[0].syncs[0].offset_arcsec() - 36.0).abs() < 0.01);
+        assert!(visits.iter().all(|v| v.left.is_some()));
+        assert!(matches!(log.entries[1], Entry::Sync(_)));
+
+        let json = log.to_json().unwrap();
+        assert_eq!(serde_json::from_str::<Logbook>(&json).unwrap(), log);
+    }
+
+    #[test]
+    fn writes_a_report() {
+        let at = |h, m, s| Utc.with_ymd_and_hms(2024, 3, 20, h, m, s).unwrap();
+        let log = Logbook {
+            started: at(21, 0, 0),
+            ended: Some(at(23, 30, 0)),
+            entries: vec![
+                Entry::Connected {
+                    time: at(21, 0, 5),
+                    device: "/dev/ttyUSB0".to_string(),
+                },
+                Entry::Visit(Visit {
+                    name: "M42".to_string(),
+                    coord: RADec::new(83.82, -5.39),
+                    arrived: at(21, 5, 0),
+                    left: Some(at(22, 20, 30)),
+                    syncs: vec![SyncCorrection {
+                        time: at(21, 6, 0),
+                        before: RADec::new(83.82, -5.39),
+                        after: RADec::new(83.82, -5.38),
+                    }],
+                }),
+                Entry::Note {
+                    time: at(22, 0, 0),
+                    text: "Clouds from the west.".to_string(),
+                },
+                Entry::Disconnected {
+                    time: at(23, 29, 0),
+                    reason: None,
+                },
+            ],
+        };
+
+        let report = log.report();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "Observing log, 2024-03-20 21:00 to 2024-03-20 23:30 UTC"
+        );
+        assert_eq!(lines[2], "21:00:05  Connected to /dev/ttyUSB0");
+        assert!(lines[3].starts_with("21:05:00  M42 ("));
+        assert!(lines[3].ends_with(", until 22:20:30 (1h 15m)"));
+        assert_eq!(lines[4], "          21:06:00  Synced by 36.0\"");
+        assert_eq!(lines[5], "22:00:00  Note: Clouds from the west.");
+        assert_eq!(lines[6], "23:29:00  Disconnected");
+        assert_eq!(lines[8], "1 targets, 1 syncs, 1h 15m on target.");
+    }
+}
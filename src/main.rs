@@ -3,14 +3,12 @@
 use eframe::egui;
 use eframe::egui::Visuals;
 use egui_dock::{DockArea, DockState, NodeIndex};
-use mount::Mount;
+use nexlib::config::{Config, ConfiguredMount};
+use nexlib::mount::Mount;
+use nexlib::units;
+use nexlib::RADec;
 use std::vec;
 
-pub mod mount;
-pub use mount::{AzEl, CelestronMount, NonGpsDevice, RADec};
-
-// TODO: Fix issue where the serial port always waits the 3.5 second timeout before returning the buffer, even when something has been read. Perhaps this has to do with the fact that the buffer hasn't been filled to capacity?
-
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
@@ -41,10 +39,12 @@ fn main() -> eframe::Result<()> {
 }
 
 struct GuiTabs {
-    mount: Option<CelestronMount>,
+    config: Config,
+    mount: Option<ConfiguredMount>,
     connected: bool,
 
     curr_ra_dec: RADec,
+    refreshed: Option<chrono::DateTime<chrono::Local>>,
     goto_ra_dec: RADec,
 }
 
@@ -86,7 +86,7 @@ impl GuiTabs {
                     ui.add(egui::Spinner::new().color(egui::Color32::WHITE));
                     ui.label("Connecting...");
 
-                    self.mount = match CelestronMount::new() {
+                    self.mount = match self.config.connect() {
                         Ok(m) => Some(m),
                         Err(e) => {
                             println!("Error: {:?}", e);
@@ -103,8 +103,9 @@ impl GuiTabs {
             // ui.label("Current RA/Dec:");
             // ui.end_row();
 
-            ui.add(egui::Label::new(format!("{}", self.curr_ra_dec.ra)));
-            ui.add(egui::Label::new(format!("{}", self.curr_ra_dec.dec)));
+            let units = units::current();
+            ui.add(egui::Label::new(units.ra(self.curr_ra_dec.ra).to_string()));
+            ui.add(egui::Label::new(units.dec(self.curr_ra_dec.dec).to_string()));
             if ui.button("Refresh").clicked() {
                 self.curr_ra_dec = self
                     .mount
@@ -112,14 +113,28 @@ impl GuiTabs {
                     .unwrap()
                     .get_position_ra_dec()
                     .expect("Failed to get position.");
+                self.refreshed = Some(chrono::Local::now());
+            }
+            if let Some(refreshed) = &self.refreshed {
+                ui.label(units.time(refreshed));
             }
             ui.end_row();
 
             // ui.label("Go to RA/Dec:");
             // ui.end_row();
 
-            ui.add(egui::DragValue::new(&mut self.goto_ra_dec.ra).speed(0.1));
-            ui.add(egui::DragValue::new(&mut self.goto_ra_dec.dec).speed(0.1));
+            ui.add(
+                egui::DragValue::new(&mut self.goto_ra_dec.ra)
+                    .speed(0.1)
+                    .custom_formatter(|v, _| units.ra(v).to_string())
+                    .custom_parser(|s| units.parse_ra(s)),
+            );
+            ui.add(
+                egui::DragValue::new(&mut self.goto_ra_dec.dec)
+                    .speed(0.1)
+                    .custom_formatter(|v, _| units.dec(v).to_string())
+                    .custom_parser(|s| units.parse_dec(s)),
+            );
             if ui.button("Go").clicked() {
                 self.mount
                     .as_mut()
@@ -163,10 +178,19 @@ impl Default for Gui {
             .main_surface_mut()
             .split_below(a, 0.8, vec!["Data Log".to_owned()]);
 
+        let config = Config::load().unwrap_or_else(|e| {
+            log::error!("Failed to load configuration, using defaults: {}", e);
+            Config::default()
+        });
+
+        units::set(config.display);
+
         let tabs = GuiTabs {
+            config,
             mount: None,
             connected: false,
             curr_ra_dec: RADec::new(0.0, 0.0),
+            refreshed: None,
             goto_ra_dec: RADec::new(0.0, 0.0),
         };
 
@@ -185,42 +209,48 @@ impl eframe::App for Gui {
     }
 }
 
-/// Tests prefixed with `nocon` require exclusive communication access to a mount and cannot be run concurrently. These tests should only be run using `cargo test nocon -- --test-threads=1`. If all tests are to be run, then `cargo test -- --test-threads=1` should be used since some will require exclusive access to the same hardware device.
+/// Tests prefixed with `nocon` exercise `CelestronMount` end to end. They run against the simulator behind a mock
+/// serial port, so they need no hardware and can run concurrently.
 #[cfg(test)]
 mod tests {
-    pub use crate::mount::CelestronMount;
-    use crate::{
-        mount::{Gps, Mount, RADec, Rtc, SlewAxis, SlewDir, TrackingMode}, // + SlewRate ?
+    pub use nexlib::mount::CelestronMount;
+    use nexlib::{
+        mount::{
+            sim::SimPort, Gps, Mount, RADec, Rtc, SimMount, SimulatedMount, SlewAxis, SlewDir, TrackingMode,
+        }, // + SlewRate ?
         AzEl,
         NonGpsDevice,
     };
     use chrono::Utc;
     use std::{thread::sleep, time::Duration};
 
-    const ERR_MSG_1: &str = "Failed to connect to mount. Are you in WSL?";
+    /// A `CelestronMount` connected to a simulated mount.
+    fn connect(sim: SimMount) -> CelestronMount {
+        CelestronMount::from_port(Box::new(SimPort::new(sim)))
+    }
 
     #[test]
     fn nocon_basic_build() {
-        let _mount = CelestronMount::new().expect(ERR_MSG_1);
+        let _mount = connect(SimMount::new());
     }
 
     #[test]
     fn nocon_get_gps_expect() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new().gps(Duration::ZERO));
         let _gps = mount.get_gps().expect("Failed to get GPS.");
     }
 
     #[test]
     #[should_panic]
     fn nocon_get_gps_panic() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         let _gps = mount.get_gps().expect("Failed to get GPS.");
     }
 
     #[test]
     #[should_panic]
     fn nocon_gps_is_linked() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         let mut gps = mount.get_gps().expect("Failed to get GPS.");
         gps.is_linked().expect("Failed to get GPS link status.");
     }
@@ -228,7 +258,7 @@ mod tests {
     #[test]
     #[should_panic]
     fn nocon_gps_get_location() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         let mut gps = mount.get_gps().expect("Failed to get GPS.");
         gps.get_location().expect("Failed to get GPS link status.");
     }
@@ -236,14 +266,25 @@ mod tests {
     #[test]
     #[should_panic]
     fn nocon_gps_get_datetime() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         let mut gps = mount.get_gps().expect("Failed to get GPS.");
         gps.get_datetime().expect("Failed to get GPS link status.");
     }
 
+    #[test]
+    fn nocon_gps_fix() {
+        let mut mount = connect(SimulatedMount::new().site(40.0, -75.0).gps(Duration::ZERO));
+        let mut gps = mount.get_gps().expect("Failed to get GPS.");
+        assert!(gps.is_linked().expect("Failed to get GPS link status."));
+        let (lat, lon) = gps.get_location().expect("Failed to get GPS location.");
+        assert!((lat - 40.0).abs() < 0.01 && (lon + 75.0).abs() < 0.01, "{lat}, {lon}");
+        let datetime = gps.get_datetime().expect("Failed to get GPS time.");
+        assert!((datetime - Utc::now()).num_seconds().abs() <= 2);
+    }
+
     #[test]
     fn nocon_get_ra_dec() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         let pos = mount
             .get_position_ra_dec()
             .expect("Failed to get position.");
@@ -253,7 +294,7 @@ mod tests {
 
     #[test]
     fn nocon_get_az_el() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         let _pos = mount.get_position_az_el().expect("Failed to get position.");
     }
 
@@ -262,23 +303,21 @@ mod tests {
         const DX: f64 = 1.0;
         const ACC: f64 = 0.5;
 
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
 
         let pos = mount
             .get_position_ra_dec()
             .expect("Failed to get position.");
 
+        // The simulator starts at the pole, so move away from it.
         mount
-            .goto_ra_dec(RADec::new(pos.ra + DX, pos.dec + DX))
+            .goto_ra_dec_blocking(
+                RADec::new(pos.ra + DX, pos.dec - DX),
+                Duration::from_secs(120),
+                |pos| println!("{pos}"),
+            )
             .expect("Failed to goto position.");
 
-        while mount
-            .goto_in_progress()
-            .expect("Failed to get goto in progress.")
-        {
-            sleep(Duration::from_secs(1));
-        }
-
         // Verify that we are within 1 degree of the target
         let new_pos = mount
             .get_position_ra_dec()
@@ -291,7 +330,7 @@ mod tests {
             new_pos.ra
         );
         assert!(
-            (new_pos.dec - (pos.dec + DX)).abs() < ACC,
+            (new_pos.dec - (pos.dec - DX)).abs() < ACC,
             "Dec: {} -> {}",
             pos.dec,
             new_pos.dec
@@ -300,7 +339,7 @@ mod tests {
 
     #[test]
     fn nocon_get_goto_in_progress() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         let _in_progress = mount
             .goto_in_progress()
             .expect("Failed to get goto in progress.");
@@ -308,28 +347,30 @@ mod tests {
 
     #[test]
     fn nocon_goto_az_el() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        // Near the pole a small move in azimuth is a long one in hour angle, so simulate a fast mount.
+        let mut mount = connect(SimMount::new().max_rate(40.0).acceleration(40.0));
         let pos = mount.get_position_az_el().expect("Failed to get position.");
+        let target = AzEl::new(pos.az + 5., pos.el - 5.);
         mount
-            .goto_az_el(AzEl::new(pos.az + 5., pos.el + 5.))
+            .goto_az_el(target)
             .expect("Failed to goto position.");
         while mount
             .goto_in_progress()
             .expect("Failed to get goto in progress.")
         {
-            sleep(Duration::from_secs(1));
+            sleep(Duration::from_millis(100));
         }
         // Verify that we are within 1 degree of the target
         let new_pos = mount.get_position_az_el().expect("Failed to get position.");
-        assert!((new_pos.az - 5.).abs() < 1.0);
-        assert!((new_pos.el - 5.).abs() < 1.0);
+        assert!((new_pos.az - target.az).abs() < 1.0, "Az: {} -> {}", pos.az, new_pos.az);
+        assert!((new_pos.el - target.el).abs() < 1.0, "El: {} -> {}", pos.el, new_pos.el);
     }
 
     // Sync
 
     #[test]
     fn nocon_get_tracking_mode() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
 
         let mode = mount.get_tracking_mode().unwrap();
 
@@ -341,7 +382,7 @@ mod tests {
     // Slew variable (and wait til done!)
     #[test]
     fn nocon_slew_variable_decel() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
 
         let pos = mount
             .get_position_ra_dec()
@@ -355,7 +396,7 @@ mod tests {
 
         for _ in 0..3 {
             println!("Sleeping...");
-            sleep(Duration::from_secs(1));
+            sleep(Duration::from_millis(200));
         }
 
         mount
@@ -364,11 +405,11 @@ mod tests {
 
         for _ in 0..3 {
             println!("Sleeping...");
-            sleep(Duration::from_secs(1));
+            sleep(Duration::from_millis(200));
         }
 
         mount
-            .stop_slew(SlewAxis::RAAz)
+            .stop_slew(SlewAxis::DecEl)
             .expect("Failed to stop slew.");
 
         let pos = mount
@@ -380,7 +421,7 @@ mod tests {
 
     #[test]
     fn nocon_slew_variable_raaz() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
 
         let pos = mount
             .get_position_ra_dec()
@@ -394,7 +435,7 @@ mod tests {
 
         for _ in 0..3 {
             println!("Sleeping...");
-            sleep(Duration::from_secs(1));
+            sleep(Duration::from_millis(200));
         }
 
         mount
@@ -403,7 +444,7 @@ mod tests {
 
         for _ in 0..3 {
             println!("Sleeping...");
-            sleep(Duration::from_secs(1));
+            sleep(Duration::from_millis(200));
         }
 
         mount
@@ -428,7 +469,7 @@ mod tests {
 
     #[test]
     fn nocon_rtc_get_datetime() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
 
         let datetime = mount.get_datetime();
 
@@ -444,19 +485,19 @@ mod tests {
 
     #[test]
     fn nocon_rtc_set_datetime_now() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         let datetime = Utc::now();
         mount.set_datetime_now().expect("Failed to set datetime.");
         sleep(Duration::from_secs(1));
         println!("Getting... ");
         let ndatetime = mount.get_datetime().expect("Failed to get datetime.");
         println!("Got: {}", ndatetime.format("%Y-%m-%d %H:%M:%S"));
-        assert!(ndatetime - datetime > chrono::Duration::seconds(1));
+        assert!((ndatetime - datetime - chrono::Duration::seconds(1)).num_seconds().abs() <= 1);
     }
 
     #[test]
     fn nocon_get_version() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
 
         let version = mount.get_version().unwrap();
 
@@ -465,7 +506,7 @@ mod tests {
 
     #[test]
     fn nocon_get_device_version() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new().gps(Duration::ZERO));
 
         // let version = mount.get_device_version(Device::AzRaMotor).unwrap();
 
@@ -494,20 +535,22 @@ mod tests {
 
     #[test]
     fn nocon_get_model() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         let _model = mount.get_model().unwrap();
     }
 
     #[test]
     fn nocon_set_tracking_mode_eqsouth() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
-        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        let mut mount = connect(SimMount::new());
+        mount.set_tracking_mode(TrackingMode::EQSouth).unwrap();
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::EQSouth);
     }
 
     #[test]
     fn nocon_set_tracking_mode_eqnorth() {
-        let mut mount = CelestronMount::new().expect(ERR_MSG_1);
+        let mut mount = connect(SimMount::new());
         mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::EQNorth);
     }
 
     // echo
@@ -1,27 +1,77 @@
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
 use chrono::{Datelike, Timelike};
-use serialport::{SerialPort, SerialPortType};
+use log::{debug, error, trace};
+use serialport::{ClearBuffer, SerialPort, SerialPortType};
 use std::error::Error;
 use std::fmt::Display;
 use std::io::Read;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use std::{fmt, io};
 
+use crate::catalog::Target;
+
+pub mod alignment;
+pub mod builder;
+pub use builder::MountBuilder;
+pub mod codec;
+use codec::Framing;
 mod coordinates;
 pub use coordinates::{AzEl, RADec};
 
-// const REV: i64 = 0x100000000;
+pub mod diagnose;
+pub mod discovery;
+pub mod error;
+use error::MountError;
+pub mod hand_control;
+pub use hand_control::{HandController, Key};
+pub mod health;
+pub mod history;
+pub mod latency;
+pub mod limits;
+pub mod lx200;
+pub mod meridian;
+use latency::{AdaptiveTimeout, Command, LatencyRecorder, LatencyStats};
+pub mod metrics;
+pub use metrics::Metrics;
+pub mod monitor;
+pub mod offsets;
+pub mod plan;
+pub mod queue;
+pub mod safety;
+pub mod self_test;
+pub mod session;
+pub mod sim;
+pub mod stability;
+pub mod state;
+pub mod status;
+pub mod stream;
+pub mod support;
+pub mod synscan;
+pub mod tracking;
+pub mod transform;
+pub mod transport;
+pub use sim::{SimMount, SimulatedMount};
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
+
+#[cfg(feature = "events")]
+pub mod events;
+
+#[cfg(feature = "indi")]
+pub mod indi;
+#[cfg(feature = "indi")]
+pub use indi::IndiClientMount;
+
+#[cfg(feature = "pointing")]
+pub mod pointing;
 
-/// Converts a slew rate in arcseconds/second to a mount-readable format.
-/// The rate is multiplied by four and separated into a high and low byte.
-fn slew_rate(rate: u16) -> (u8, u8) {
-    (
-        ((rate * 4) / 256).try_into().unwrap(),
-        ((rate * 4) % 256).try_into().unwrap(),
-    )
-}
+// const REV: i64 = 0x100000000;
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TrackingMode {
     Off = 0,
     AzEl = 1,
@@ -29,16 +79,97 @@ pub enum TrackingMode {
     EQSouth = 3,
 }
 
+/// How the mount's axes are arranged, for interpreting raw motor positions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mounting {
+    /// The axes turn in azimuth and elevation.
+    AltAz,
+    /// The axes turn in hour angle and declination.
+    Equatorial,
+}
+
+/// Geographic location of the mount's site.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Location {
+    /// Degrees, positive north.
+    pub latitude: f64,
+    /// Degrees, positive east.
+    pub longitude: f64,
+}
+
+/// Time zone the hand control displays and accepts local time in.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeZoneSetting {
+    /// Offset of standard time from UTC, in hours.
+    pub utc_offset: i8,
+    /// Whether daylight saving time is in effect, adding an hour to the offset.
+    pub dst: bool,
+}
+
+impl TimeZoneSetting {
+    /// The offset of local time from UTC, including daylight saving time.
+    pub fn offset(&self) -> FixedOffset {
+        let hours = self.utc_offset as i32 + self.dst as i32;
+        FixedOffset::east_opt(hours * 3600).expect("A whole number of hours under a day is a valid offset.")
+    }
+
+    /// The setting for local time at `offset` from UTC, of which `dst` says whether an hour is daylight saving time.
+    ///
+    /// Fails if the offset is not a whole number of hours, which the hand control cannot represent.
+    pub fn from_offset(offset: FixedOffset, dst: bool) -> Result<TimeZoneSetting, io::Error> {
+        let seconds = offset.local_minus_utc();
+        if seconds % 3600 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The hand control only supports whole-hour UTC offsets, not {offset}."),
+            ));
+        }
+        Ok(TimeZoneSetting {
+            utc_offset: (seconds / 3600 - dst as i32) as i8,
+            dst,
+        })
+    }
+
+    /// The setting matching `zone` at `time`, e.g. to set the hand control's clock from the site's time zone.
+    ///
+    /// Fails if the zone's standard offset is not a whole number of hours, which the hand control cannot represent.
+    #[cfg(feature = "tz")]
+    pub fn for_zone(zone: chrono_tz::Tz, time: DateTime<Utc>) -> Result<TimeZoneSetting, io::Error> {
+        use chrono_tz::OffsetComponents;
+
+        let offset = *time.with_timezone(&zone).offset();
+        let standard = offset.base_utc_offset().num_seconds();
+        if standard % 3600 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("The hand control only supports whole-hour UTC offsets, not that of {zone}."),
+            ));
+        }
+        Ok(TimeZoneSetting {
+            utc_offset: (standard / 3600) as i8,
+            dst: !offset.dst_offset().is_zero(),
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SlewAxis {
     RAAz = 0,
     DecEl = 1,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SlewDir {
     Positive = 0,
     Negative = 1,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SlewRate {
     Stop = 0,
     Rate1 = 1,
@@ -52,6 +183,30 @@ pub enum SlewRate {
     Rate9 = 9,
 }
 
+impl TryFrom<u8> for SlewRate {
+    type Error = io::Error;
+
+    fn try_from(rate: u8) -> Result<Self, Self::Error> {
+        match rate {
+            0 => Ok(SlewRate::Stop),
+            1 => Ok(SlewRate::Rate1),
+            2 => Ok(SlewRate::Rate2),
+            3 => Ok(SlewRate::Rate3),
+            4 => Ok(SlewRate::Rate4),
+            5 => Ok(SlewRate::Rate5),
+            6 => Ok(SlewRate::Rate6),
+            7 => Ok(SlewRate::Rate7),
+            8 => Ok(SlewRate::Rate8),
+            9 => Ok(SlewRate::Rate9),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid fixed slew rate {rate}."),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Model {
     GPSSeries = 1,
     ISeries = 3,
@@ -68,6 +223,42 @@ pub enum Model {
     Evolution = 22,
 }
 
+impl TryFrom<u8> for Model {
+    type Error = MountError;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        match id {
+            1 => Ok(Model::GPSSeries),
+            3 => Ok(Model::ISeries),
+            4 => Ok(Model::ISeriesSe),
+            5 => Ok(Model::Cge),
+            6 => Ok(Model::AdvancedGT),
+            7 => Ok(Model::Slt),
+            9 => Ok(Model::Cpc),
+            10 => Ok(Model::Gt),
+            11 => Ok(Model::FourFiveSE),
+            12 => Ok(Model::SixEightSE),
+            14 => Ok(Model::Cgem),
+            20 => Ok(Model::AdvancedVX),
+            22 => Ok(Model::Evolution),
+            _ => Err(MountError::InvalidResponse {
+                cmd: Some(Command::HandControl(b'm')),
+                bytes: vec![id],
+            }),
+        }
+    }
+}
+
+impl Model {
+    /// How the model's axes are arranged as sold; fork and single arm mounts may also be put on a wedge.
+    pub fn mounting(&self) -> Mounting {
+        match self {
+            Model::Cge | Model::AdvancedGT | Model::Cgem | Model::AdvancedVX => Mounting::Equatorial,
+            _ => Mounting::AltAz,
+        }
+    }
+}
+
 impl fmt::Display for Model {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -90,13 +281,15 @@ impl fmt::Display for Model {
 
 #[derive(Debug, Copy, Clone)]
 enum Device {
+    HandControl = 4,
     AzRaMotor = 16,
     ElDecMotor = 17,
     GpsUnit = 176,
     RtcUnit = 178,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NonGpsDevice {
     AzRaMotor = 16,
     ElDecMotor = 17,
@@ -116,6 +309,7 @@ impl NonGpsDevice {
 impl Display for Device {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            Device::HandControl => write!(f, "Hand Control"),
             Device::AzRaMotor => write!(f, "Azimuth/RA Motor"),
             Device::ElDecMotor => write!(f, "Elevation/Dec Motor"),
             Device::GpsUnit => write!(f, "GPS Unit"),
@@ -124,44 +318,563 @@ impl Display for Device {
     }
 }
 
+/// A telescope mount.
+///
+/// Backends implement the required methods; the higher level operations from [`Mount::wait_for_goto`] on are
+/// provided in terms of them, and may be overridden where the backend can do better.
 pub trait Mount {
-    fn get_position_ra_dec(&mut self) -> Result<RADec, io::Error>;
-    fn get_position_az_el(&mut self) -> Result<AzEl, io::Error>;
-    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), io::Error>;
-    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), io::Error>;
-    fn sync(&mut self, coord: RADec) -> Result<(), io::Error>;
-    fn get_tracking_mode(&mut self) -> Result<TrackingMode, io::Error>;
-    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), io::Error>;
-    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), io::Error>;
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError>;
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError>;
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError>;
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError>;
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError>;
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError>;
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError>;
+    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), MountError>;
     fn slew_fixed(&mut self, axis: SlewAxis, dir: SlewDir, rate: SlewRate)
-        -> Result<(), io::Error>;
-    fn get_location();
-    fn set_location();
-    fn get_time(&mut self) -> Result<DateTime<Utc>, io::Error>;
-    fn set_time();
-    fn get_version(&mut self) -> Result<String, Box<dyn std::error::Error>>;
-    fn get_device_version(&mut self, device: NonGpsDevice) -> Result<String, Box<dyn Error>>;
-    fn get_model(&mut self) -> Result<Model, io::Error>;
-    fn echo();
-    fn is_aligned(&mut self) -> Result<bool, io::Error>;
-    fn goto_in_progress(&mut self) -> Result<bool, io::Error>;
-    fn cancel_goto(&mut self) -> Result<(), io::Error>;
-    fn stop_slew(&mut self, slew: SlewAxis) -> Result<(), io::Error>;
+        -> Result<(), MountError>;
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError>;
+    /// Sets the mount's clock, keeping its time zone.
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError>;
+    fn get_version(&mut self) -> Result<String, MountError>;
+    fn get_device_version(&mut self, device: NonGpsDevice) -> Result<String, MountError>;
+    fn get_model(&mut self) -> Result<Model, MountError>;
+    /// Sends `byte` to the hand control, which repeats it back; a check that the link works which changes nothing.
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError>;
+    fn is_aligned(&mut self) -> Result<bool, MountError>;
+    fn goto_in_progress(&mut self) -> Result<bool, MountError>;
+    fn cancel_goto(&mut self) -> Result<(), MountError>;
+    fn stop_slew(&mut self, slew: SlewAxis) -> Result<(), MountError>;
+
+    /// Gets the GPS receiver, which fails with `NotFound` on mounts without one.
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError>;
+
+    /// Waits for the goto in progress, if any, to finish.
+    ///
+    /// With a `timeout`, the goto is cancelled and `TimedOut` returned if it has not finished in time.
+    fn wait_for_goto(&mut self, timeout: Option<Duration>) -> Result<(), MountError> {
+        let start = Instant::now();
+        while self.goto_in_progress()? {
+            if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+                self.cancel_goto()?;
+                return Err(MountError::other(
+                    io::ErrorKind::TimedOut,
+                    format!("Goto did not finish within {:?}.", timeout.unwrap()),
+                ));
+            }
+            std::thread::sleep(GOTO_POLL_INTERVAL);
+        }
+        Ok(())
+    }
 
-    /// Get GPS device
-    fn get_gps(&mut self) -> Result<CelestronGps, io::Error>;
+    /// Moves to `coord` and waits for the goto to finish; see [`Mount::wait_for_goto`].
+    fn goto_and_wait(&mut self, coord: RADec, timeout: Option<Duration>) -> Result<(), MountError> {
+        self.goto_ra_dec(coord)?;
+        self.wait_for_goto(timeout)
+    }
+
+    /// Moves to `coord` and waits for the goto to finish, calling `on_progress` with the position at each poll and
+    /// once more on arrival.
+    ///
+    /// The goto is cancelled and `TimedOut` returned if it has not finished within `timeout`, or if it stalls: the
+    /// mount still reports the goto in progress, but has moved less than [`GOTO_STALL_DEGREES`] in
+    /// [`GOTO_STALL_TIME`].
+    fn goto_ra_dec_blocking(
+        &mut self,
+        coord: RADec,
+        timeout: Duration,
+        mut on_progress: impl FnMut(RADec),
+    ) -> Result<(), MountError>
+    where
+        Self: Sized,
+    {
+        self.goto_ra_dec(coord)?;
+        let start = Instant::now();
+        let mut moved = (self.get_position_ra_dec()?, start);
+        loop {
+            let in_progress = self.goto_in_progress()?;
+            let pos = self.get_position_ra_dec()?;
+            on_progress(pos);
+            if !in_progress {
+                return Ok(());
+            }
+
+            let failure = if start.elapsed() >= timeout {
+                Some(format!("Goto to {coord} did not finish within {timeout:?}."))
+            } else if transform::angular_separation(pos.ra, pos.dec, moved.0.ra, moved.0.dec)
+                >= GOTO_STALL_DEGREES
+            {
+                moved = (pos, Instant::now());
+                None
+            } else if moved.1.elapsed() >= GOTO_STALL_TIME {
+                Some(format!("Goto to {coord} stalled at {pos}."))
+            } else {
+                None
+            };
+            if let Some(msg) = failure {
+                self.cancel_goto()?;
+                return Err(MountError::other(io::ErrorKind::TimedOut, msg));
+            }
+            std::thread::sleep(GOTO_POLL_INTERVAL);
+        }
+    }
+
+    /// Moves to a catalog target, which fails with `NotFound` if its coordinates are not known yet.
+    fn goto_object(&mut self, target: &Target) -> Result<(), MountError> {
+        let coord = target.coord.ok_or_else(|| {
+            MountError::other(
+                io::ErrorKind::NotFound,
+                format!("No coordinates for {}; resolve its name first.", target.name),
+            )
+        })?;
+        self.goto_ra_dec(coord)
+    }
+
+    /// Slews `axis` at `rate` for `duration`, e.g. to center an object by hand.
+    ///
+    /// The axis is stopped even if the slew fails part way.
+    fn nudge(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+        duration: Duration,
+    ) -> Result<(), MountError> {
+        let res = self.slew_fixed(axis, dir, rate);
+        if res.is_ok() {
+            std::thread::sleep(duration);
+        }
+        let stopped = self.stop_slew(axis);
+        res.and(stopped)
+    }
+
+    /// Cancels any goto and stops both axes, leaving tracking as it is.
+    ///
+    /// Every step is attempted even if an earlier one fails. Returns the first error.
+    fn stop_all(&mut self) -> Result<(), MountError> {
+        let results = [
+            self.cancel_goto(),
+            self.stop_slew(SlewAxis::RAAz),
+            self.stop_slew(SlewAxis::DecEl),
+        ];
+        results.into_iter().collect()
+    }
+
+    /// Stops all motion: cancels any goto, stops both axes, and turns tracking off.
+    ///
+    /// Every step is attempted even if an earlier one fails, since a partial stop is better than none. Returns the
+    /// first error.
+    fn emergency_stop(&mut self) -> Result<(), MountError> {
+        log::warn!("Emergency stop.");
+        let stopped = self.stop_all();
+        let tracking = self.set_tracking_mode(TrackingMode::Off);
+        stopped.and(tracking)
+    }
+
+    /// Sets the mount's clock to the computer's.
+    fn set_time_now(&mut self) -> Result<(), MountError> {
+        self.set_time(Utc::now())
+    }
+
+    /// Gets the site the mount computes positions and times for. Fails with `Unsupported` on mounts which do not keep
+    /// one.
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        Err(MountError::unsupported("This mount does not report its site."))
+    }
+
+    /// Sets the site the mount computes positions and times for. Fails with `Unsupported` on mounts which do not
+    /// keep one.
+    fn set_location(&mut self, _location: Location) -> Result<(), MountError> {
+        Err(MountError::unsupported("This mount does not keep a site."))
+    }
+
+    /// Selects the named limit profile, or none for no limits, on a mount which enforces limits, such as a
+    /// [`LimitedMount`](limits::LimitedMount). Fails with `Unsupported` on others.
+    fn select_limit_profile(&mut self, _name: Option<&str>) -> Result<(), MountError> {
+        Err(MountError::unsupported("This mount does not enforce limit profiles."))
+    }
+
+    /// The limit profiles of a mount which enforces limits, and the one selected. Fails with `Unsupported` on others.
+    fn limit_profiles(&mut self) -> Result<limits::ProfileSelection, MountError> {
+        Err(MountError::unsupported("This mount does not enforce limit profiles."))
+    }
+}
+
+/// Time between checks of whether a goto has finished.
+const GOTO_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Least movement, in degrees, for a goto not to count as stalled; well above the sidereal drift of a mount which
+/// stopped with tracking off.
+pub const GOTO_STALL_DEGREES: f64 = 0.1;
+
+/// Time a goto may go without moving [`GOTO_STALL_DEGREES`] before [`Mount::goto_ra_dec_blocking`] gives up on it.
+pub const GOTO_STALL_TIME: Duration = Duration::from_secs(10);
+
+/// First wait between polls of a GPS receiver for a fix, doubled after each poll.
+const FIX_POLL_INITIAL: Duration = Duration::from_millis(250);
+
+/// Longest wait between polls of a GPS receiver for a fix.
+const FIX_POLL_MAX: Duration = Duration::from_secs(4);
+
+/// A position and time reported by a GPS receiver with a fix.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GpsFix {
+    pub location: Location,
+    pub time: DateTime<Utc>,
+}
+
+/// What [`CelestronMount::sync_from_gps`] updated.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GpsSync {
+    /// The location and time set.
+    pub fix: GpsFix,
+    /// The site the hand control had before, if it could be read.
+    pub previous_site: Option<Location>,
+    /// How far the hand control's clock was ahead of GPS time, if it could be read.
+    pub clock_offset: Option<chrono::TimeDelta>,
+    /// Whether the real-time clock was set; mounts without one skip it.
+    pub rtc_updated: bool,
+}
+
+/// The state of a [`Gps::wait_for_fix_with`] poll.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FixProgress {
+    /// Time since waiting started.
+    pub elapsed: Duration,
+    /// Polls so far, including this one.
+    pub polls: u32,
+    /// Whether the receiver has linked to satellites, though its position or time may not be valid yet.
+    pub linked: bool,
 }
 
 pub trait Gps {
-    fn is_linked(&mut self) -> Result<bool, io::Error>;
-    fn get_location(&mut self) -> Result<(f32, f32), io::Error>;
-    fn get_datetime(&mut self) -> Result<DateTime<chrono::Utc>, io::Error>;
-    fn get_device_version(&mut self) -> Result<String, Box<dyn Error>>;
+    fn is_linked(&mut self) -> Result<bool, MountError>;
+    fn get_location(&mut self) -> Result<(f32, f32), MountError>;
+    fn get_datetime(&mut self) -> Result<DateTime<chrono::Utc>, MountError>;
+    fn get_device_version(&mut self) -> Result<String, MountError>;
+
+    /// Waits up to `timeout` for the receiver to lock, returning its location and time.
+    ///
+    /// A receiver can take minutes to acquire satellites after power on, while [`Gps::get_location`] fails at once
+    /// until it has. See [`Gps::wait_for_fix_with`] to report progress meanwhile.
+    fn wait_for_fix(&mut self, timeout: Duration) -> Result<GpsFix, MountError>
+    where
+        Self: Sized,
+    {
+        self.wait_for_fix_with(timeout, |_| ())
+    }
+
+    /// Waits up to `timeout` for the receiver to lock, calling `on_progress` after each poll.
+    ///
+    /// Polls start a quarter second apart and back off to every few seconds. A receiver which is linked but has no
+    /// valid position or time yet, or which does not answer a poll, is polled again; other errors end the wait. Fails
+    /// with `TimedOut` if there is no fix in time.
+    fn wait_for_fix_with<F>(&mut self, timeout: Duration, mut on_progress: F) -> Result<GpsFix, MountError>
+    where
+        Self: Sized,
+        F: FnMut(&FixProgress),
+    {
+        let start = Instant::now();
+        let mut interval = FIX_POLL_INITIAL;
+        let mut polls = 0;
+        loop {
+            polls += 1;
+            let res = self.is_linked().and_then(|linked| {
+                if !linked {
+                    return Ok((false, None));
+                }
+                let (latitude, longitude) = self.get_location()?;
+                let location = Location {
+                    latitude: latitude as f64,
+                    longitude: longitude as f64,
+                };
+                let time = self.get_datetime()?;
+                Ok((true, Some(GpsFix { location, time })))
+            });
+            let (linked, fix) = match res {
+                Ok(polled) => polled,
+                Err(e) if matches!(e.kind(), io::ErrorKind::InvalidData | io::ErrorKind::TimedOut) => {
+                    debug!("No GPS fix yet: {e}");
+                    (false, None)
+                }
+                Err(e) => return Err(e),
+            };
+
+            on_progress(&FixProgress {
+                elapsed: start.elapsed(),
+                polls,
+                linked,
+            });
+            if let Some(fix) = fix {
+                return Ok(fix);
+            }
+
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(MountError::other(
+                    io::ErrorKind::TimedOut,
+                    format!("No GPS fix within {timeout:?}."),
+                ));
+            }
+            std::thread::sleep(interval.min(remaining));
+            interval = (interval * 2).min(FIX_POLL_MAX);
+        }
+    }
+}
+
+/// A receiver returned by [`Mount::get_gps`].
+impl<G: Gps + ?Sized> Gps for Box<G> {
+    fn is_linked(&mut self) -> Result<bool, MountError> {
+        (**self).is_linked()
+    }
+
+    fn get_location(&mut self) -> Result<(f32, f32), MountError> {
+        (**self).get_location()
+    }
+
+    fn get_datetime(&mut self) -> Result<DateTime<chrono::Utc>, MountError> {
+        (**self).get_datetime()
+    }
+
+    fn get_device_version(&mut self) -> Result<String, MountError> {
+        (**self).get_device_version()
+    }
 }
 
 pub trait Rtc {
-    fn get_datetime(&mut self) -> Result<DateTime<chrono::Utc>, io::Error>;
-    fn set_datetime_now(&mut self) -> Result<(), io::Error>;
+    fn get_datetime(&mut self) -> Result<DateTime<chrono::Utc>, MountError>;
+    fn set_datetime_now(&mut self) -> Result<(), MountError>;
+}
+
+/// Periodic error correction state of the right ascension motor; see [`Pec`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PecState {
+    /// Whether the index mark of the worm gear has been found.
+    pub indexed: bool,
+    /// Whether a recording is in progress.
+    pub recording: bool,
+    /// Whether playback was last enabled or disabled through this connection. The motor controller cannot be asked,
+    /// so this is `None` until [`Pec::set_pec_playback`] is called.
+    pub playback: Option<bool>,
+}
+
+/// Periodic error correction (PEC) of the right ascension motor.
+///
+/// The motor controller records the corrections made while guiding over one turn of the worm gear, and plays them
+/// back while tracking. Both are relative to an index mark on the worm, so seek it with [`Pec::seek_pec_index`] and
+/// wait until [`PecState::indexed`] before recording or playing back.
+pub trait Pec {
+    /// Starts turning the worm to its index mark.
+    fn seek_pec_index(&mut self) -> Result<(), MountError>;
+    /// Starts recording guiding corrections over the next turn of the worm, after which recording stops by itself.
+    fn start_pec_recording(&mut self) -> Result<(), MountError>;
+    /// Stops a recording early.
+    fn stop_pec_recording(&mut self) -> Result<(), MountError>;
+    /// Enables or disables playback of the recorded corrections.
+    fn set_pec_playback(&mut self, enabled: bool) -> Result<(), MountError>;
+    fn get_pec_state(&mut self) -> Result<PecState, MountError>;
+}
+
+/// Direction of a guide pulse on the sky.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GuideDirection {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl GuideDirection {
+    /// The axis moved and the direction it moves in.
+    pub fn axis(&self) -> (SlewAxis, SlewDir) {
+        match self {
+            GuideDirection::North => (SlewAxis::DecEl, SlewDir::Positive),
+            GuideDirection::South => (SlewAxis::DecEl, SlewDir::Negative),
+            // Right ascension increases eastward.
+            GuideDirection::East => (SlewAxis::RAAz, SlewDir::Positive),
+            GuideDirection::West => (SlewAxis::RAAz, SlewDir::Negative),
+        }
+    }
+}
+
+/// Autoguiding through the motor controllers, as an autoguider's ST-4 port or a guiding program's pulse guide
+/// commands would.
+///
+/// Guide rates are fractions of the sidereal rate, e.g. `0.5` to move half a sidereal rate faster or slower than
+/// tracking while a pulse lasts.
+pub trait Guider {
+    /// Sets the guide rate of `axis`, from 0 up to just under 1.
+    fn set_guide_rate(&mut self, axis: SlewAxis, rate: f64) -> Result<(), MountError>;
+    fn get_guide_rate(&mut self, axis: SlewAxis) -> Result<f64, MountError>;
+    /// Starts a guide pulse of `duration_ms` milliseconds at the guide rate, without waiting for it to end. Fails with
+    /// `InvalidInput` for pulses longer than [`codec::MAX_GUIDE_PULSE`].
+    fn pulse_guide(&mut self, direction: GuideDirection, duration_ms: u32) -> Result<(), MountError>;
+    /// Whether a guide pulse is in progress on either axis.
+    fn is_pulse_guiding(&mut self) -> Result<bool, MountError>;
+}
+
+/// Sidereal rate in degrees per second.
+pub const SIDEREAL_RATE: f64 = 360.985_647_366_29 / 86_400.0;
+
+/// Preset tracking rates for [`FineTracking::set_tracking_preset`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrackingRate {
+    /// The rate of the stars.
+    Sidereal,
+    /// The mean rate of the Moon.
+    Lunar,
+    /// The rate of the Sun.
+    Solar,
+    /// King's rate, the sidereal rate slowed for the mean refraction of stars away from the pole.
+    King,
+}
+
+impl TrackingRate {
+    /// The right ascension tracking rate in arcseconds per second.
+    pub fn arcsec_per_sec(&self) -> f64 {
+        match self {
+            TrackingRate::Sidereal => SIDEREAL_RATE * 3600.0,
+            TrackingRate::Lunar => 14.685,
+            TrackingRate::Solar => 15.0,
+            TrackingRate::King => 15.0369,
+        }
+    }
+}
+
+/// Tracking at rates other than the sidereal rate of the [`TrackingMode`], e.g. to follow the Moon or a comet.
+///
+/// Rates are in arcseconds per second of axis motion, positive in the axis' positive direction, which for the right
+/// ascension axis is the direction it tracks in the northern hemisphere. Setting the tracking mode again returns to
+/// the sidereal rate.
+pub trait FineTracking {
+    /// Tracks `axis` at `arcsec_per_sec`, up to [`codec::MAX_TRACKING_RATE`] either way; 0 stops the axis.
+    fn set_tracking_rate(&mut self, axis: SlewAxis, arcsec_per_sec: f64) -> Result<(), MountError>;
+    /// Tracks at `rate` in right ascension, in the direction of the equatorial tracking mode, and not at all in
+    /// declination. Fails with `InvalidInput` unless tracking equatorially.
+    fn set_tracking_preset(&mut self, rate: TrackingRate) -> Result<(), MountError>;
+}
+
+/// Time to wait for a hand control response before giving up.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(3500);
+
+/// Baud rate of the hand control's serial port.
+pub const DEFAULT_BAUD: u32 = 9600;
+
+/// Time each port is given to answer while probing for a hand control.
+pub const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Longest response accepted by default, in bytes including the terminating '#'.
+pub const DEFAULT_MAX_RESPONSE: usize = 256;
+
+/// Size of each read from the port.
+const READ_CHUNK: usize = 64;
+
+/// Timing and retry settings for talking to the hand control; see [`CelestronMount::set_comms`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CommsConfig {
+    /// Time to wait for the whole response to a command.
+    pub read_timeout: Duration,
+    /// Time to wait for a command to be written to the port. The wait for its response is bounded by `read_timeout`.
+    pub write_timeout: Duration,
+    /// Times a query is sent again after it timed out or its response was malformed. Commands which change the
+    /// mount's state are never repeated.
+    pub retries: u32,
+    /// Least time between the end of one command and the start of the next, for adapters which drop bytes sent too
+    /// soon after a response.
+    pub inter_command_delay: Duration,
+}
+
+impl Default for CommsConfig {
+    fn default() -> Self {
+        CommsConfig {
+            read_timeout: DEFAULT_TIMEOUT,
+            write_timeout: DEFAULT_TIMEOUT,
+            retries: 0,
+            inter_command_delay: Duration::ZERO,
+        }
+    }
+}
+
+/// A response was longer than the maximum set with [`CelestronMount::set_max_response`].
+///
+/// Returned as [`MountError::ResponseOverflow`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResponseOverflow {
+    /// The maximum response length.
+    pub max: usize,
+    /// Bytes received before giving up.
+    pub received: usize,
+}
+
+impl Display for ResponseOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Response exceeded {} bytes ({} received).",
+            self.max, self.received
+        )
+    }
+}
+
+impl Error for ResponseOverflow {}
+
+/// Waits until `port` has bytes to read or `deadline` passes, returning whether any are waiting.
+fn wait_for_bytes(port: &mut dyn SerialPort, deadline: Instant) -> Result<bool, io::Error> {
+    loop {
+        if port.bytes_to_read()? > 0 {
+            return Ok(true);
+        }
+        if Instant::now() >= deadline {
+            return Ok(false);
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Reads from `port` into `buf` until `framing` says the response is complete. At `deadline`, fails with
+/// [`MountError::Timeout`] if nothing arrived, or [`MountError::InvalidResponse`] with the part that did.
+///
+/// For backends without [`CelestronMount`]'s handling of late and oversized responses.
+fn read_framed(
+    port: &mut dyn SerialPort,
+    buf: &mut Vec<u8>,
+    framing: Framing,
+    deadline: Instant,
+) -> Result<(), MountError> {
+    let mut chunk = [0; READ_CHUNK];
+    while !framing.is_complete(buf) {
+        if !wait_for_bytes(port, deadline)? {
+            return Err(if buf.is_empty() {
+                MountError::Timeout
+            } else {
+                MountError::InvalidResponse {
+                    cmd: None,
+                    bytes: buf.clone(),
+                }
+            });
+        }
+        let n = port.read(&mut chunk)?;
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(())
+}
+
+/// Which coordinate commands a [`CelestronMount`] uses.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PrecisionMode {
+    /// The precise commands, unless the hand control firmware version, once read with
+    /// [`get_version`](Mount::get_version), predates them.
+    #[default]
+    Auto,
+    /// Always the precise commands (`e`, `z`, `r`, `b`, `s`), resolving about 0.08 arcseconds.
+    Precise,
+    /// Always the low precision commands (`E`, `Z`, `R`, `B`, `S`), resolving about 20 arcseconds, for the original
+    /// NexStar 5 and 8 hand controls.
+    Low,
 }
 
 /// The device which we control.
@@ -171,7 +884,40 @@ pub trait Rtc {
 pub struct CelestronMount {
     /// `port` should ONLY be accessed in `read_port` and `write_port`.
     port: Arc<Mutex<Box<dyn SerialPort>>>,
-    recv: [u8; 32],
+    /// The last response; reused between commands.
+    recv: Vec<u8>,
+    max_response: usize,
+    latency: LatencyRecorder,
+    metrics: Metrics,
+    info: InfoCache,
+    adaptive: Option<AdaptiveTimeout>,
+    /// Response deadlines set per command, taking precedence over adaptive timeouts.
+    deadlines: HashMap<Command, Duration>,
+    comms: CommsConfig,
+    precision: PrecisionMode,
+    /// Periodic error playback as last set, which the motor controller cannot report.
+    pec_playback: Option<bool>,
+    /// Guide rate of each axis, as set or last read.
+    guide_rates: [Option<f64>; 2],
+    /// When the last transaction ended, to keep the inter-command delay.
+    last_transaction: Option<Instant>,
+    /// A response may still arrive for a command which timed out.
+    stale: bool,
+    /// Monotonic and wall-clock time of the last response. Only the wall clock advances while the host is suspended.
+    last_response: Option<(Instant, SystemTime)>,
+    /// Idle time after which the link is checked before the next command.
+    revalidate_after: Option<Duration>,
+    failover: Option<transport::Failover>,
+    #[cfg(feature = "telemetry")]
+    telemetry: Option<crate::telemetry::Telemetry>,
+}
+
+/// Responses that do not change while the mount is powered.
+#[derive(Debug, Default)]
+struct InfoCache {
+    model: Option<Model>,
+    version: Option<String>,
+    device_versions: HashMap<NonGpsDevice, String>,
 }
 
 pub struct CelestronGps<'a> {
@@ -180,7 +926,7 @@ pub struct CelestronGps<'a> {
 
 impl<'a> Gps for CelestronGps<'a> {
     // Not available on AVX.
-    fn is_linked(&mut self) -> Result<bool, io::Error> {
+    fn is_linked(&mut self) -> Result<bool, MountError> {
         use Device::*;
 
         let res = self.mount.read_passthrough(GpsUnit, 55, 1)?;
@@ -192,32 +938,64 @@ impl<'a> Gps for CelestronGps<'a> {
     }
 
     // Not available on AVX.
-    fn get_location(&mut self) -> Result<(f32, f32), io::Error> {
+    fn get_location(&mut self) -> Result<(f32, f32), MountError> {
         use Device::*;
 
         if !self.is_linked()? {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "GPS unit is not linked.",
-            ));
+            return Err(MountError::GpsNotLinked);
         }
 
-        let res = self.mount.read_passthrough(GpsUnit, 1, 3)?;
-        let lat = (f32::from_be_bytes([0, res[0], res[1], res[2]]) / (0x1000000 as f32)) * 360.;
-        let res = self.mount.read_passthrough(GpsUnit, 2, 3)?;
-        let lon = (f32::from_be_bytes([0, res[0], res[1], res[2]]) / (0x1000000 as f32)) * 360.;
+        // 24-bit fractions of a revolution, signed.
+        let mut angle = |cmd| -> Result<f32, MountError> {
+            let res = self.mount.read_passthrough(GpsUnit, cmd, 3)?;
+            let value = u32::from_be_bytes([res[0], res[1], res[2], 0]);
+            Ok(codec::decode_signed_angle(value) as f32)
+        };
+        let lat = angle(1)?;
+        let lon = angle(2)?;
 
         Ok((lat, lon))
     }
 
-    fn get_datetime(&mut self) -> Result<DateTime<chrono::Utc>, io::Error> {
-        todo!();
+    // Not available on AVX.
+    fn get_datetime(&mut self) -> Result<DateTime<chrono::Utc>, MountError> {
+        use Device::*;
+
+        if self.mount.read_passthrough(GpsUnit, 54, 1)?[0] == 0 {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidData,
+                "GPS time is not valid.",
+            ));
+        }
+
+        let res = self.mount.read_passthrough(GpsUnit, 3, 2)?;
+        let (month, day) = (res[0], res[1]);
+        let res = self.mount.read_passthrough(GpsUnit, 4, 2)?;
+        let year = u16::from_be_bytes([res[0], res[1]]);
+        let res = self.mount.read_passthrough(GpsUnit, 51, 3)?;
+        let (hour, minute, second) = (res[0], res[1], res[2]);
+
+        Utc.with_ymd_and_hms(
+            year as i32,
+            month as u32,
+            day as u32,
+            hour as u32,
+            minute as u32,
+            second as u32,
+        )
+        .single()
+        .ok_or_else(|| {
+            MountError::other(
+                io::ErrorKind::InvalidData,
+                format!("Invalid GPS time: {year}-{month}-{day} {hour}:{minute}:{second}"),
+            )
+        })
     }
     
     /// Gets the version of the mount's firmware.
-    fn get_device_version(&mut self) -> Result<String, Box<dyn Error>> {
-        let res = self.mount.read_passthrough(Device::GpsUnit, 254, 2)?;
-        Ok(format!("{}.{}", res[0], res[1]))
+    fn get_device_version(&mut self) -> Result<String, MountError> {
+        let res = self.mount.read_passthrough(Device::GpsUnit, codec::MC_GET_VERSION, 2)?;
+        codec::decode_version(res)
     }
 }
 
@@ -225,120 +1003,336 @@ impl<'a> Gps for CelestronGps<'a> {
 impl CelestronMount {
     /// Reads from a USB port and checks for the '#' character at the end of the message.
     ///
-    /// The NexStar Communication Protocol requires a '#' at the end of each message sent by the mount.
-    fn read_port(&mut self) -> Result<usize, io::Error> {
+    /// The NexStar Communication Protocol requires a '#' at the end of each message sent by the mount. Error replies
+    /// are returned as [`NexError`](codec::NexError).
+    ///
+    /// Responses may arrive split across several reads on slow adapters, so reading continues until the response is
+    /// complete according to `framing`, and then while more bytes are waiting. Only bytes already waiting are read,
+    /// so a complete response returns at once, and the rest of a partial one is waited for until `deadline`.
+    fn read_port(&mut self, command: Command, framing: Framing, deadline: Instant) -> Result<usize, io::Error> {
         let mut port = self.port.lock().unwrap();
-        
-        match port.read(&mut self.recv) {
-            Ok(n) => {
-                println!("RECEIVED (Ok): {:?}", &self.recv[..n]);
-                if self.recv[n - 1] != b'#' {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("[{}:{}] Invalid data received: {:?}", file!(), line!(), self.recv),
-                    ));
+        let mut chunk = [0; READ_CHUNK];
+        self.recv.clear();
+
+        loop {
+            let waiting = self.recv.is_empty() || wait_for_bytes(&mut **port, deadline)?;
+            // Past the deadline, not read at all: a read would block for the port's whole timeout.
+            let n = if !waiting {
+                0
+            } else {
+                match port.read(&mut chunk) {
+                    Ok(0) if !self.recv.is_empty() => 0,
+                    Ok(n) => n,
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut && !self.recv.is_empty() => 0,
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut => return Err(MountError::Timeout.into()),
+                    Err(e) => {
+                        trace!("RECEIVED (Err): {:?}", &self.recv);
+                        error!(
+                            "[{}:{}] Failed to read from port: {:?}",
+                            file!(),
+                            line!(),
+                            e
+                        );
+                        return Err(e);
+                    }
                 }
-                
-                Ok(n)
+            };
+            self.metrics.bytes_read += n as u64;
+
+            if self.recv.len() + n > self.max_response {
+                let received = self.recv.len() + n;
+                self.recv.clear();
+                port.clear(ClearBuffer::Input)?;
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    ResponseOverflow {
+                        max: self.max_response,
+                        received,
+                    },
+                ));
             }
-            Err(e) => {
-                println!("RECEIVED (Err): {:?}", &self.recv);
-                eprintln!(
-                    "[{}:{}] Failed to read from port: {:?}",
-                    file!(),
-                    line!(),
-                    e
-                );
-                Err(e)
+            self.recv.extend_from_slice(&chunk[..n]);
+
+            if n == 0 {
+                if let Some(e) = codec::decode_error(&self.recv, framing) {
+                    return Err(e.into());
+                }
+                // The rest of the response never arrived, but may yet.
+                self.stale = true;
+                debug!("Incomplete response to {command}: {:?}", self.recv);
+                return Err(self.invalid_response(command));
             }
+            if framing.is_complete(&self.recv) && port.bytes_to_read()? == 0 {
+                break;
+            }
+        }
+
+        let n = self.recv.len();
+        trace!("RECEIVED (Ok): {:?}", self.recv);
+        if self.recv.last() != Some(&b'#') {
+            return Err(self.invalid_response(command));
+        }
+        if let Some(e) = codec::decode_error(&self.recv, framing) {
+            debug!("Hand control reported an error: {e}");
+            return Err(e.into());
+        }
+
+        Ok(n)
+    }
+
+    /// A [`MountError::InvalidResponse`](error::MountError::InvalidResponse) for the bytes received for `command`.
+    fn invalid_response(&self, command: Command) -> io::Error {
+        error::MountError::InvalidResponse {
+            cmd: Some(command),
+            bytes: self.recv.clone(),
         }
+        .into()
     }
 
-    fn write_port(&mut self, buf: &[u8]) -> Result<(), io::Error> {
-        println!("TRANSMITTED: {:?}", buf);
+    /// Waits up to `timeout` for a response.
+    fn write_port(&mut self, buf: &[u8], timeout: Duration) -> Result<(), io::Error> {
+        trace!("TRANSMITTED: {:?}", buf);
 
-        self.port.lock().unwrap().write_all(buf)?;
+        if std::mem::take(&mut self.stale) {
+            // Discard the late response to an earlier command.
+            self.port.lock().unwrap().clear(ClearBuffer::Input)?;
+        }
+        {
+            // The port's timeout bounds the write, then the first read of the response.
+            let comms = self.comms;
+            let mut port = self.port.lock().unwrap();
+            if comms.write_timeout != comms.read_timeout {
+                port.set_timeout(comms.write_timeout)?;
+            }
+            let res = port.write_all(buf);
+            if comms.write_timeout != comms.read_timeout {
+                port.set_timeout(comms.read_timeout)?;
+            }
+            res?;
+        }
+        self.metrics.bytes_written += buf.len() as u64;
         
         // Ok, so.
         // This loop is necessary because when we send a command where we do not expect any data back, we do expect to receive a '#' back. Unfortunately, it doesn't seem to be sent immediately. So, we must wait here until we get some sort of response (and we should always get some response) before we can continue. Then, the calling function should always call self.read_port() to clear the buffer whether or not it actually wants to read the data. Typically, its 10 - 100 ms.
+        let start = Instant::now();
         while self.port.lock().unwrap().bytes_to_read()? == 0 {
-            println!("Waiting for there to be bytes to read...");
+            if start.elapsed() >= timeout {
+                debug!("No response to {:?} within {:?}.", buf, timeout);
+                return Err(MountError::Timeout.into());
+            }
+            trace!("Waiting for there to be bytes to read...");
             std::thread::sleep(Duration::from_millis(10));
         }
 
         Ok(())
     }
 
-    /// Communicates through the hand controller to a device internal to the mount.
+    /// Writes a command and reads its response, timing the transaction.
     ///
-    /// Expects a response with data.
-    fn read_passthrough(
-        &mut self,
-        dev: Device,
-        cmd: u8,
-        resp_len: usize,
-    ) -> Result<&[u8], io::Error> {
-        self.write_port(&[b'P', 1, dev as u8, cmd, 0, 0, 0, resp_len as u8])?;
+    /// With the `tracing` feature, the transaction runs in a `transaction` span recording the command, the device of a
+    /// passthrough command, the bytes written and read, the latency, and the outcome.
+    fn transact(&mut self, cmd: &[u8], framing: Framing) -> Result<usize, io::Error> {
+        let command = Command::of(cmd);
+        let version = self.info.version.as_deref().and_then(support::parse_version);
+        support::check(command, version, self.info.model)?;
+        self.revalidate()?;
+        let limit = self.deadlines.get(&command).copied().or_else(|| {
+            self.adaptive
+                .and_then(|adaptive| adaptive.timeout(self.latency.stats_for(command)))
+        });
+        let timeout = self.comms.read_timeout;
+        let timeout = limit.map_or(timeout, |limit| timeout.min(limit));
+        if let Some(last) = self.last_transaction {
+            let wait = self.comms.inter_command_delay.saturating_sub(last.elapsed());
+            if !wait.is_zero() {
+                std::thread::sleep(wait);
+            }
+        }
 
-        let len = self.read_port()?;
-        if self.recv[len - 1] != b'#' {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("[{}:{}] Invalid data received: {:?}", file!(), line!(), self.recv),
-            ));
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "transaction",
+            command = %command,
+            device = tracing::field::Empty,
+            bytes_written = cmd.len(),
+            bytes_read = tracing::field::Empty,
+            latency_us = tracing::field::Empty,
+            outcome = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        #[cfg(feature = "tracing")]
+        if let Command::Passthrough { device, .. } = command {
+            span.record("device", device);
         }
-        if len == resp_len + 1 {
-            Ok(&self.recv[..resp_len])
-        } else if len == resp_len + 2 {
-            Err(io::Error::new(
-                io::ErrorKind::NotConnected,
-                format!("Device {dev} is unavailable or command {cmd} is invalid."),
-            ))
-        } else {
-            Err(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid data length {len} on command {cmd:?} from device {dev}: expected {resp_len} bytes ({:?}).", self.recv)))
+
+        // Nothing is left over from the previous command if this one fails before reading.
+        self.recv.clear();
+        let start = Instant::now();
+        self.metrics.commands += 1;
+        let res = self
+            .write_port(cmd, timeout)
+            .and_then(|()| self.read_port(command, framing, start + timeout));
+        let latency = start.elapsed();
+        self.last_transaction = Some(Instant::now());
+
+        #[cfg(feature = "telemetry")]
+        if let Some(telemetry) = &self.telemetry {
+            let response = match res {
+                Ok(len) => &self.recv[..len],
+                Err(_) => &self.recv[..],
+            };
+            telemetry.command(cmd, response, latency, res.as_ref().err());
+        }
+        #[cfg(feature = "tracing")]
+        {
+            span.record("bytes_read", self.recv.len());
+            span.record("latency_us", latency.as_micros() as u64);
+            match &res {
+                Ok(_) => span.record("outcome", "ok"),
+                Err(e) => span.record("outcome", tracing::field::debug(e.kind())),
+            };
+        }
+
+        let len = match res {
+            Ok(len) => len,
+            Err(e) => {
+                if e.kind() == io::ErrorKind::TimedOut && limit.is_some() {
+                    self.stale = true;
+                    self.latency.forget(command);
+                }
+                let e = self.record_error(e);
+                if let Some(port) = self.failover.as_mut().and_then(|f| f.failed(&e)) {
+                    *self.port.lock().unwrap() = port;
+                    self.metrics.reconnects += 1;
+                    self.stale = false;
+                    // Now on the backup, which is not switched away from, so this cannot recurse again.
+                    return self.transact(cmd, framing);
+                }
+                return Err(e);
+            }
+        };
+        if let Some(failover) = &mut self.failover {
+            failover.succeeded();
         }
+        self.latency.record(command, latency);
+        self.last_response = Some((Instant::now(), SystemTime::now()));
+        Ok(len)
     }
 
-    /// Communicates through the hand controller to a device internal to the mount.
-    ///
-    /// Expects a response with no data.
-    fn write_passthrough(&mut self, dev: Device, cmd: u8, args: &[u8]) -> Result<(), io::Error> {
-        // let mut port = self.port.lock().unwrap();
+    /// Checks the link with an echo if the mount has been idle for longer than `revalidate_after`, so a link which
+    /// dropped while idle fails here rather than part way through the next command.
+    fn revalidate(&mut self) -> Result<(), io::Error> {
+        let Some(after) = self.revalidate_after else {
+            return Ok(());
+        };
+        let Some(idle) = self.since_last_response().filter(|idle| *idle >= after) else {
+            return Ok(());
+        };
 
-        if args.len() > 3 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!("Command arguments must be 3 bytes or less. {:?}", args),
-            ));
-        }
-        
-        let mut cmd = [b'P', args.len() as u8 + 1, dev as u8, cmd, 0, 0, 0, 0];
-        
-        for (idx, arg) in args.iter().enumerate() {
-            cmd[4 + idx] = *arg;
+        debug!("Mount idle for {idle:?}; checking the link.");
+        // Not again for the echo itself.
+        self.revalidate_after = None;
+        let res = self.ping();
+        self.revalidate_after = Some(after);
+        res.map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Mount stopped responding while idle for {idle:?}: {e}"),
+            )
+        })
+    }
+
+    /// Counts a failed command in the metrics.
+    fn record_error(&mut self, e: io::Error) -> io::Error {
+        self.metrics.record_error(&e);
+        e
+    }
+
+    /// Runs the query `f`, sending it again up to `comms.retries` times if it times out or its response is malformed.
+    fn retrying(
+        &mut self,
+        mut f: impl FnMut(&mut Self) -> Result<usize, io::Error>,
+    ) -> Result<usize, io::Error> {
+        let mut attempt = 0;
+        loop {
+            match f(self) {
+                Err(e) if attempt < self.comms.retries && Self::is_transient(&e) => {
+                    attempt += 1;
+                    debug!("Retrying after {e} ({attempt} of {}).", self.comms.retries);
+                    self.metrics.retries += 1;
+                    // Whatever arrives late belongs to the failed attempt.
+                    self.stale = true;
+                }
+                res => return res,
+            }
         }
-        
-        // port.write_all(&cmd)?;
-        self.write_port(&cmd)?;
-        self.read_port()?; // Necessary to clear the buffer - we expect to get back a #.
-        
+    }
+
+    /// Whether a failure may not recur if the command is sent again.
+    fn is_transient(e: &io::Error) -> bool {
+        matches!(
+            error::MountError::from(e),
+            error::MountError::Timeout | error::MountError::InvalidResponse { .. }
+        )
+    }
+
+    /// Communicates through the hand controller to a device internal to the mount.
+    ///
+    /// Expects a response with data.
+    fn read_passthrough(
+        &mut self,
+        dev: Device,
+        cmd: u8,
+        resp_len: usize,
+    ) -> Result<&[u8], MountError> {
+        self.query_passthrough(dev, cmd, &[], resp_len)
+    }
+
+    /// Communicates through the hand controller to a device internal to the mount, with arguments.
+    ///
+    /// Expects a response with data.
+    fn query_passthrough(
+        &mut self,
+        dev: Device,
+        cmd: u8,
+        args: &[u8],
+        resp_len: usize,
+    ) -> Result<&[u8], MountError> {
+        let msg = codec::passthrough(dev as u8, cmd, args, resp_len)?;
+        self.retrying(|mount| {
+            let len = mount.transact(&msg, Framing::Length(resp_len + 1))?;
+            match codec::decode_passthrough(dev as u8, cmd, &mount.recv[..len], resp_len) {
+                Ok(_) => Ok(resp_len),
+                Err(e) => Err(mount.record_error(e.into())),
+            }
+        })?;
+        Ok(&self.recv[..resp_len])
+    }
+
+    /// Communicates through the hand controller to a device internal to the mount.
+    ///
+    /// Expects a response with no data.
+    fn write_passthrough(&mut self, msg: [u8; 8]) -> Result<(), MountError> {
+        self.transact(&msg, Framing::Terminator)?; // Reading is necessary to clear the buffer - we expect to get back a #.
         Ok(())
     }
 
     /// Communicates directly with the hand controller.
     ///
     /// Expects a response with data.
-    fn read_handcontrol(&mut self, cmd: u8) -> Result<&[u8], io::Error> {
-        self.write_port(&[cmd])?;
-
-        let len = self.read_port()?;
+    fn read_handcontrol(&mut self, cmd: u8) -> Result<&[u8], MountError> {
+        let len = self.retrying(|mount| {
+            let len = mount.transact(&[cmd], Framing::of_handcontrol(cmd))?;
 
-        if self.recv[len - 1] != b'#' {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("[{}:{}] Invalid data received: {:?}", file!(), line!(), self.recv),
-            ));
-        }
+            if mount.recv[len - 1] != b'#' {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("[{}:{}] Invalid data received: {:?}", file!(), line!(), mount.recv),
+                ));
+            }
+            Ok(len)
+        })?;
 
         Ok(&self.recv[..len - 1])
     }
@@ -346,17 +1340,9 @@ impl CelestronMount {
     /// Communicates directly with the hand controller.
     ///
     /// Expects a response with no data.
-    fn write_handcontrol(&mut self, cmd: u8, args: &[u8]) -> Result<Vec<u8>, io::Error> {
-        let mut cmd = vec![cmd];
-
-        cmd.extend_from_slice(args);
-
-        self.write_port(&cmd)?;
-        self.read_port()?; // Necessary to clear the buffer - we expect to get back a #.
-
-        cmd.clear();
-
-        Ok(cmd)
+    fn write_handcontrol(&mut self, msg: codec::Message) -> Result<(), MountError> {
+        self.transact(msg.as_bytes(), Framing::Terminator)?; // Reading is necessary to clear the buffer - we expect to get back a #.
+        Ok(())
     }
 }
 
@@ -369,97 +1355,471 @@ impl Default for CelestronMount {
 /// Public functions for Mount.
 impl CelestronMount {
     pub fn new() -> Result<CelestronMount, io::Error> {
-        println!("Available ports:");
+        // "Software drivers should be prepared to wait up to 3.5s (worst case scenario) for a hand control response."
+        Self::open_with_timeout(&Self::detect_port()?, DEFAULT_TIMEOUT)
+    }
+
+    /// Finds the serial port of the first connected hand control.
+    ///
+    /// Ports of the Celestron USB adapter are recognized by their USB IDs. If there are none, every port is probed
+    /// with [`CelestronMount::probe_ports`], which finds hand controls behind generic USB serial adapters.
+    pub fn detect_port() -> Result<String, io::Error> {
+        debug!("Available ports:");
 
         let ports_info = serialport::available_ports()?;
 
-        println!("Found {} ports", ports_info.len());
+        debug!("Found {} ports", ports_info.len());
 
         let mut port_name: Option<String> = None; //String::new();
 
         for p_info in ports_info {
-            println!("Port name: {}", p_info.port_name);
+            debug!("Port name: {}", p_info.port_name);
             match p_info.port_type {
                 SerialPortType::UsbPort(info) => {
-                    println!("USB device: VID: {:04x} PID: {:04x}", info.vid, info.pid);
+                    debug!("USB device: VID: {:04x} PID: {:04x}", info.vid, info.pid);
 
                     if info.vid == 0x067b && info.pid == 0x23d3 {
                         port_name = Some(p_info.port_name);
                         break;
                     } else {
-                        println!("Not the device we are looking for.");
+                        debug!("Not the device we are looking for.");
                     }
                 }
                 _ => {
-                    println!("Unknown device");
+                    debug!("Unknown device");
                 }
             }
         }
 
-        match &port_name {
-            Some(p) => println!("Found device: {}", p),
-            None => {
-                return Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    "AVX device not found.",
-                ))
+        if let Some(p) = port_name {
+            debug!("Found device: {}", p);
+            return Ok(p);
+        }
+
+        debug!("No known adapter found; probing all ports.");
+        match Self::probe_ports(PROBE_TIMEOUT)?.into_iter().next() {
+            Some(p) => {
+                debug!("Found device by probing: {}", p);
+                Ok(p)
+            }
+            None => Err(diagnose::not_found()),
+        }
+    }
+
+    /// Finds every serial port with a hand control attached, whatever the adapter, by opening each port in turn and
+    /// sending the echo command.
+    ///
+    /// Each port which does not answer costs up to `timeout`. Other devices on the probed ports receive the two bytes
+    /// of the echo command, which most ignore.
+    pub fn probe_ports(timeout: Duration) -> Result<Vec<String>, io::Error> {
+        let mut found = Vec::new();
+        for p_info in serialport::available_ports()? {
+            match Self::open_port(&p_info.port_name, timeout).map(Self::probe) {
+                Ok(true) => found.push(p_info.port_name),
+                Ok(false) => debug!("No hand control on {}", p_info.port_name),
+                Err(e) => debug!("Could not open {}: {}", p_info.port_name, e),
             }
         }
+        Ok(found)
+    }
 
-        Ok(CelestronMount {
-            // "Software drivers should be prepared to wait up to 3.5s (worst case scenario) for a hand control response."
-            port: Arc::new(Mutex::new(
-                serialport::new(port_name.unwrap(), 9600)
-                    .timeout(Duration::from_millis(3500)) // should be 3500 ms
-                    .stop_bits(serialport::StopBits::One)
-                    .parity(serialport::Parity::None)
-                    .open()?,
-            )),
-            recv: [0; 32],
+    /// Whether a hand control answers the echo command on `port`.
+    pub fn probe(port: Box<dyn SerialPort>) -> bool {
+        Self::from_port(port).ping().is_ok()
+    }
+
+    /// Opens a serial port with the settings the hand control expects.
+    ///
+    /// Failures carry a [`diagnose::PortError`] with the likely cause, such as missing permissions or another program
+    /// holding the port.
+    pub fn open_port(port_name: &str, timeout: Duration) -> Result<Box<dyn SerialPort>, io::Error> {
+        Self::open_port_at(port_name, DEFAULT_BAUD, timeout)
+    }
+
+    /// Opens a serial port as [`CelestronMount::open_port`] does, at another baud rate, for adapters or bridges which
+    /// do not run at the hand control's 9600 baud.
+    pub fn open_port_at(port_name: &str, baud: u32, timeout: Duration) -> Result<Box<dyn SerialPort>, io::Error> {
+        serialport::new(port_name, baud)
+            .timeout(timeout)
+            .stop_bits(serialport::StopBits::One)
+            .parity(serialport::Parity::None)
+            .open()
+            .map_err(|e| diagnose::diagnose(port_name, e.into()))
+    }
+
+    /// Opens the mount on a specific serial port at `baud`, e.g. [`DEFAULT_BAUD`], instead of searching for it, or at
+    /// a `host:port` network address; see [`transport::open_at`].
+    pub fn open(port_name: &str, baud: u32) -> Result<CelestronMount, io::Error> {
+        Ok(Self::from_port(transport::open_at(port_name, baud, DEFAULT_TIMEOUT)?))
+    }
+
+    /// Opens the mount as [`CelestronMount::open`] does at [`DEFAULT_BAUD`], waiting `timeout` for responses.
+    pub fn open_with_timeout(port_name: &str, timeout: Duration) -> Result<CelestronMount, io::Error> {
+        Ok(Self::from_port(transport::open(port_name, timeout)?))
+    }
+
+    /// Connects to a hand control over TCP, such as through a SkyPortal or StarSense WiFi module at
+    /// [`transport::WIFI_ADDR`], or the built-in WiFi of an Evolution mount, which relay the same protocol as the
+    /// serial port.
+    pub fn connect_tcp(addr: &str) -> Result<CelestronMount, io::Error> {
+        Ok(Self::from_port(Box::new(transport::TcpPort::connect(addr, DEFAULT_TIMEOUT)?)))
+    }
+
+    /// Configures a connection to a specific port, baud rate, or timeout; see [`MountBuilder`].
+    pub fn builder() -> MountBuilder {
+        MountBuilder::default()
+    }
+
+    /// Uses an already open port, such as a [`sim::SimPort`].
+    pub fn from_port(port: Box<dyn SerialPort>) -> CelestronMount {
+        let timeout = port.timeout();
+        CelestronMount {
+            port: Arc::new(Mutex::new(port)),
+            recv: Vec::with_capacity(32),
+            max_response: DEFAULT_MAX_RESPONSE,
+            latency: LatencyRecorder::default(),
+            metrics: Metrics::default(),
+            info: InfoCache::default(),
+            adaptive: None,
+            deadlines: HashMap::new(),
+            comms: CommsConfig {
+                read_timeout: timeout,
+                write_timeout: timeout,
+                ..CommsConfig::default()
+            },
+            precision: PrecisionMode::Auto,
+            pec_playback: None,
+            guide_rates: [None; 2],
+            last_transaction: None,
+            stale: false,
+            last_response: None,
+            revalidate_after: None,
+            failover: None,
+            #[cfg(feature = "telemetry")]
+            telemetry: None,
+        }
+    }
+
+    /// Time since the mount last answered a command, or `None` if it never has.
+    ///
+    /// Includes any time the host spent suspended, which the monotonic clock does not count on every platform.
+    pub fn since_last_response(&self) -> Option<Duration> {
+        self.last_response.map(|(mono, wall)| {
+            let wall = wall.elapsed().unwrap_or_default();
+            mono.elapsed().max(wall)
+        })
+    }
+
+    /// Checks the link with an echo before the next command whenever the mount has been idle for `after`, including
+    /// after the host resumes from sleep. Adapters which drop after a long idle or a suspend then report it before
+    /// a command is sent, instead of failing half way through it. `None` disables the check.
+    pub fn set_revalidate_after(&mut self, after: Option<Duration>) {
+        self.revalidate_after = after;
+    }
+
+    /// Switches to a backup link when the primary fails persistently; see [`transport`]. `None` removes it.
+    pub fn set_failover(&mut self, failover: Option<transport::Failover>) {
+        self.failover = failover;
+    }
+
+    /// The link in use: the backup once a [`transport::Failover`] has switched to it.
+    pub fn transport(&self) -> transport::Transport {
+        self.failover
+            .as_ref()
+            .map_or(transport::Transport::Primary, |f| f.active())
+    }
+
+    /// Checks that the hand control is responding with the cheap echo command.
+    pub fn ping(&mut self) -> Result<(), MountError> {
+        let echoed = self.echo(b'x')?;
+        if echoed != b'x' {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidData,
+                format!("[{}:{}] Echo of {:?} received for 'x'.", file!(), line!(), echoed as char),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sets the timeouts, retries, and pacing of commands; see [`CommsConfig`].
+    ///
+    /// A mount opened on a port starts with both timeouts set to the port's timeout and no retries.
+    pub fn set_comms(&mut self, comms: CommsConfig) -> Result<(), MountError> {
+        self.port.lock().unwrap().set_timeout(comms.read_timeout)?;
+        self.comms = comms;
+        Ok(())
+    }
+
+    pub fn comms(&self) -> CommsConfig {
+        self.comms
+    }
+
+    /// Chooses between the precise and low precision coordinate commands.
+    pub fn set_precision(&mut self, precision: PrecisionMode) {
+        self.precision = precision;
+    }
+
+    pub fn precision(&self) -> PrecisionMode {
+        self.precision
+    }
+
+    /// Whether to send the precise coordinate command `precise` rather than its low precision counterpart `low`.
+    ///
+    /// In [`PrecisionMode::Auto`], falls back only if the firmware supports `low` but not `precise`.
+    fn precise(&self, precise: u8, low: u8) -> bool {
+        match self.precision {
+            PrecisionMode::Precise => true,
+            PrecisionMode::Low => false,
+            PrecisionMode::Auto => {
+                let Some(version) = self.info.version.as_deref().and_then(support::parse_version) else {
+                    return true;
+                };
+                let supports =
+                    |cmd| support::required_version(Command::HandControl(cmd)).is_none_or(|v| v <= version);
+                supports(precise) || !supports(low)
+            }
+        }
+    }
+
+    /// Sets the longest response to accept, in bytes including the terminating '#'. Longer responses fail with
+    /// [`ResponseOverflow`].
+    pub fn set_max_response(&mut self, max: usize) {
+        self.max_response = max;
+    }
+
+    /// Enables or disables adaptive response timeouts; see [`AdaptiveTimeout`].
+    pub fn set_adaptive_timeout(&mut self, adaptive: Option<AdaptiveTimeout>) {
+        self.adaptive = adaptive;
+    }
+
+    /// Sets how long to wait for the whole response to `command`, or `None` for the adaptive or read timeout.
+    ///
+    /// Never longer than the read timeout of the [`CommsConfig`]. Useful for commands known to answer quickly, so a lost response fails fast.
+    pub fn set_command_deadline(&mut self, command: Command, deadline: Option<Duration>) {
+        match deadline {
+            Some(deadline) => self.deadlines.insert(command, deadline),
+            None => self.deadlines.remove(&command),
+        };
+    }
+
+    /// Forgets the cached model and firmware versions, so the next requests read them from the mount again.
+    ///
+    /// Only needed if the hand control or mount was swapped or reflashed without reopening the port.
+    pub fn refresh_info(&mut self) {
+        self.info = InfoCache::default();
+    }
+
+    /// Gets the minimum, mean, and 95th percentile time of recent successful transactions, per command.
+    ///
+    /// See [`latency`] for interpreting them.
+    pub fn latency_stats(&self) -> BTreeMap<Command, LatencyStats> {
+        self.latency.stats()
+    }
+
+    /// Discards the transaction times collected so far.
+    pub fn reset_latency_stats(&mut self) {
+        self.latency.clear();
+    }
+
+    /// Logs every command to `telemetry`, or stops logging if `None`.
+    #[cfg(feature = "telemetry")]
+    pub fn set_telemetry(&mut self, telemetry: Option<crate::telemetry::Telemetry>) {
+        self.telemetry = telemetry;
+    }
+
+    /// Gets a snapshot of the serial traffic and failure counters.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics
+    }
+
+    /// Gets the site location set in the hand control.
+    pub fn get_site(&mut self) -> Result<Location, MountError> {
+        codec::decode_location(self.read_handcontrol(b'w')?)
+    }
+
+    /// Sets the site location in the hand control, to the nearest arcsecond.
+    pub fn set_site(&mut self, location: Location) -> Result<(), MountError> {
+        self.write_handcontrol(codec::set_location(location))
+    }
+
+    /// Gets the time zone set in the hand control.
+    pub fn get_time_zone(&mut self) -> Result<TimeZoneSetting, MountError> {
+        codec::decode_time_zone(self.read_handcontrol(b'h')?)
+    }
+
+    /// Sets the hand control's clock to `time`, and its time zone to `zone`.
+    pub fn set_clock(&mut self, time: DateTime<Utc>, zone: TimeZoneSetting) -> Result<(), MountError> {
+        self.write_handcontrol(codec::set_time(time, zone))
+    }
+
+    /// Sets the mount's real-time clock to `time`.
+    pub fn set_rtc_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        use Device::*;
+
+        self.write_passthrough(codec::passthrough(
+            RtcUnit as u8,
+            131,
+            &[time.month() as u8, time.day() as u8],
+            0,
+        )?)?;
+        self.write_passthrough(codec::passthrough(
+            RtcUnit as u8,
+            132,
+            &(time.year() as u16).to_be_bytes(),
+            0,
+        )?)?;
+        self.write_passthrough(codec::passthrough(
+            RtcUnit as u8,
+            179,
+            &[time.hour() as u8, time.minute() as u8, time.second() as u8],
+            0,
+        )?)
+    }
+
+    /// Reads the location and time from the GPS receiver and sets them as the hand control's site and clock, and on
+    /// the real-time clock if the mount has one.
+    ///
+    /// The clock keeps the hand control's time zone. Fails with [`MountError::GpsNotLinked`](error::MountError) if
+    /// the receiver has no fix; see [`Gps::wait_for_fix`] to wait for one first.
+    pub fn sync_from_gps(&mut self) -> Result<GpsSync, MountError> {
+        let fix = {
+            let mut gps = self.get_gps()?;
+            if !gps.is_linked()? {
+                return Err(MountError::GpsNotLinked);
+            }
+            let (latitude, longitude) = gps.get_location()?;
+            let location = Location {
+                latitude: latitude as f64,
+                longitude: longitude as f64,
+            };
+            GpsFix {
+                location,
+                time: gps.get_datetime()?,
+            }
+        };
+
+        let previous_site = self.get_site().ok();
+        let clock_offset = self.get_time().ok().map(|clock| clock - fix.time);
+        self.set_site(fix.location)?;
+        self.set_time(fix.time)?;
+        let rtc_updated = match self.set_rtc_time(fix.time) {
+            Ok(()) => true,
+            Err(e) if e.kind() == io::ErrorKind::NotConnected => {
+                debug!("No real-time clock to set: {e}");
+                false
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(GpsSync {
+            fix,
+            previous_site,
+            clock_offset,
+            rtc_updated,
         })
     }
+
+    /// Gets the time as the hand control displays it, in its own time zone. [`Mount::get_time`] gives the same instant
+    /// in UTC.
+    pub fn get_local_time(&mut self) -> Result<DateTime<FixedOffset>, MountError> {
+        codec::decode_local_time(self.read_handcontrol(b'h')?)
+    }
+
+    /// Sets the hand control's clock and time zone to the local time `time`, of whose UTC offset `dst` says whether an
+    /// hour is daylight saving time. [`CelestronMount::get_local_time`] reads the same time back.
+    ///
+    /// Fails with `InvalidInput` if the offset is not a whole number of hours.
+    pub fn set_local_time(&mut self, time: DateTime<FixedOffset>, dst: bool) -> Result<(), MountError> {
+        let zone = TimeZoneSetting::from_offset(*time.offset(), dst)?;
+        self.set_clock(time.with_timezone(&Utc), zone)
+    }
+
+    /// Sets the hand control's clock to `time` and its time zone to `zone` as observed at that time, with daylight
+    /// saving time applied if it is in effect.
+    #[cfg(feature = "tz")]
+    pub fn set_clock_in(&mut self, time: DateTime<Utc>, zone: chrono_tz::Tz) -> Result<(), MountError> {
+        self.set_clock(time, TimeZoneSetting::for_zone(zone, time)?)
+    }
+
+    /// Gets the raw positions of the azimuth/RA and elevation/dec motors in degrees, using MC_GET_POSITION, one
+    /// passthrough query per motor.
+    pub fn get_motor_positions(&mut self) -> Result<[f64; 2], MountError> {
+        let mut positions = [0.0; 2];
+        for (pos, dev) in positions.iter_mut().zip([Device::AzRaMotor, Device::ElDecMotor]) {
+            let res = self.read_passthrough(dev, codec::MC_GET_POSITION, 3)?;
+            *pos = codec::decode_motor_position(res)?;
+        }
+        Ok(positions)
+    }
+
+    /// Gets the pointing position in right ascension and declination, with an approximate azimuth and elevation.
+    ///
+    /// A fast path for polling loops which display both. Only the right ascension and declination are read from the
+    /// mount, as by [`Mount::get_position_ra_dec`]. The azimuth and elevation are computed from them for the site at
+    /// `latitude` and `longitude` (degrees, positive north and east) and the host's clock when the reply arrives. They
+    /// are wrong by as much as the host's clock or the given site are off from the hand control's. Limit checks and
+    /// anything else that must know where the mount points should use [`Mount::get_position_az_el`] instead.
+    pub fn get_positions(&mut self, latitude: f64, longitude: f64) -> Result<(RADec, AzEl), MountError> {
+        let ra_dec = self.get_position_ra_dec()?;
+        let lst = transform::local_sidereal_time(Utc::now(), longitude);
+        let ha = transform::wrap_180(lst - ra_dec.ra);
+        Ok((ra_dec, transform::ha_dec_to_az_el(ha, ra_dec.dec, latitude)))
+    }
+}
+
+/// Rejects NaN and infinite coordinates, which normalization cannot turn into a position to send.
+fn check_finite(finite: bool, coord: impl std::fmt::Debug) -> Result<(), MountError> {
+    if finite {
+        Ok(())
+    } else {
+        Err(MountError::other(io::ErrorKind::InvalidInput, format!("Invalid coordinates {coord:?}.")))
+    }
 }
 
 impl Mount for CelestronMount {
     /// Gets the current pointing position of the mount in right ascension and declination.
     ///
-    /// Uses the high precision 24-bit NexStar coordinates.
-    fn get_position_ra_dec(&mut self) -> Result<RADec, io::Error> {
-        self.read_handcontrol(b'e')?;
-        Ok(RADec::from_msg(&self.recv))
+    /// Uses the high precision 24-bit NexStar coordinates, or the 16-bit ones as chosen by the [`PrecisionMode`].
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        let cmd = if self.precise(b'e', b'E') { b'e' } else { b'E' };
+        Ok(codec::decode_ra_dec(self.read_handcontrol(cmd)?)?)
     }
 
     /// Gets the current pointing position of the mount in azimuth and elevation.
     ///
-    /// Uses the precise 24-bit NexStar Get Position command.
-    fn get_position_az_el(&mut self) -> Result<AzEl, io::Error> {
-        self.read_handcontrol(b'z')?;
-        Ok(AzEl::from_msg(&self.recv))
+    /// Uses the precise 24-bit NexStar Get Position command, or the 16-bit one as chosen by the [`PrecisionMode`].
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        let cmd = if self.precise(b'z', b'Z') { b'z' } else { b'Z' };
+        Ok(codec::decode_az_el(self.read_handcontrol(cmd)?)?)
     }
 
     /// Moves the mount to a specified right ascension and declination.
     ///
-    /// Uses the high precision 24-bit NexStar coordinates.
+    /// Sends `r` with the high precision 24-bit NexStar coordinates, or `R` as chosen by the [`PrecisionMode`].
     ///
     /// Will not work if the mount is not aligned.
-    fn goto_ra_dec(&mut self, mut coord: RADec) -> Result<(), io::Error> {
-        self.write_handcontrol(
-            b'r',
-            format!("{:X},{:X}", coord.ra_as_i64(), coord.dec_as_i64()).as_bytes(),
-        )?;
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        check_finite(coord.is_finite(), coord)?;
+        if self.precise(b'r', b'R') {
+            self.write_handcontrol(codec::goto_ra_dec(coord))?;
+        } else {
+            self.write_handcontrol(codec::goto_ra_dec_low(coord))?;
+        }
         Ok(())
     }
 
     /// Moves the mount to a specified azimuth and elevation.
     ///
-    /// Uses the high precision 24-bit NexStar coordinates.
+    /// Sends `b` with the high precision 24-bit NexStar coordinates, or `B` as chosen by the [`PrecisionMode`].
     ///
     /// Will be relative to where it was powered on if not aligned.
-    fn goto_az_el(&mut self, mut coord: AzEl) -> Result<(), io::Error> {
-        self.write_handcontrol(
-            b'r',
-            format!("{:X},{:X}", coord.az_as_i64(), coord.el_as_i64()).as_bytes(),
-        )?;
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        check_finite(coord.is_finite(), coord)?;
+        if self.precise(b'b', b'B') {
+            self.write_handcontrol(codec::goto_az_el(coord))?;
+        } else {
+            self.write_handcontrol(codec::goto_az_el_low(coord))?;
+        }
         Ok(())
     }
 
@@ -472,35 +1832,25 @@ impl Mount for CelestronMount {
     /// # Arguments
     ///
     /// * `coord` - The `RADec` coordinates to sync to; should be the expected coordinates of the object currently
-    /// pointed at.
-    fn sync(&mut self, mut coord: RADec) -> Result<(), io::Error> {
-        self.write_handcontrol(
-            b's',
-            format!("{:X},{:X}", coord.ra_as_i64(), coord.dec_as_i64()).as_bytes(),
-        )?;
+    ///   pointed at.
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        check_finite(coord.is_finite(), coord)?;
+        if self.precise(b's', b'S') {
+            self.write_handcontrol(codec::sync(coord))?;
+        } else {
+            self.write_handcontrol(codec::sync_low(coord))?;
+        }
         Ok(())
     }
 
     /// Gets the current tracking mode of the mount.
-    fn get_tracking_mode(&mut self) -> Result<TrackingMode, io::Error> {
-        self.read_handcontrol(b't')?;
-
-        println!("Data found: {:?}", self.recv);
-        match self.recv[0] {
-            0 => Ok(TrackingMode::Off),
-            1 => Ok(TrackingMode::AzEl),
-            2 => Ok(TrackingMode::EQNorth),
-            3 => Ok(TrackingMode::EQSouth),
-            _ => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid tracking mode.",
-            )),
-        }
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        codec::decode_tracking_mode(self.read_handcontrol(b't')?)
     }
 
     /// Sets the tracking mode of the mount.
-    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), io::Error> {
-        self.write_handcontrol(b'T', &[mode as u8])?;
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        self.write_handcontrol(codec::set_tracking_mode(mode))?;
         Ok(())
     }
 
@@ -511,22 +1861,8 @@ impl Mount for CelestronMount {
     /// * `axis` - The axis to slew.
     /// * `dir` - The direction to slew.
     /// * `rate` - The rate of movement in arcseconds/second.
-    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), io::Error> {
-        let device = match axis {
-            SlewAxis::RAAz => Device::AzRaMotor,
-            SlewAxis::DecEl => Device::ElDecMotor,
-        };
-
-        let dir_byte = match dir {
-            SlewDir::Positive => 6,
-            SlewDir::Negative => 7,
-        };
-
-        let rate_bytes = slew_rate(rate);
-
-        self.write_passthrough(device, dir_byte, &[rate_bytes.0, rate_bytes.1])?;
-
-        Ok(())
+    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), MountError> {
+        self.write_passthrough(codec::slew_variable(axis, dir, rate))
     }
 
     /// Begins a fixed (predefined speed) slew movement.
@@ -541,207 +1877,126 @@ impl Mount for CelestronMount {
         axis: SlewAxis,
         dir: SlewDir,
         rate: SlewRate,
-    ) -> Result<(), io::Error> {
-        let device = match axis {
-            SlewAxis::RAAz => Device::AzRaMotor,
-            SlewAxis::DecEl => Device::ElDecMotor,
-        };
-
-        let dir_byte = match dir {
-            SlewDir::Positive => 36,
-            SlewDir::Negative => 37,
-        };
-
-        self.write_passthrough(device, dir_byte, &[rate as u8])?;
-        Ok(())
+    ) -> Result<(), MountError> {
+        self.write_passthrough(codec::slew_fixed(axis, dir, rate))
     }
 
-    fn get_location() {
-        todo!();
+    /// Gets the site location set in the hand control; see [`CelestronMount::get_site`].
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        self.get_site()
     }
 
-    fn set_location() {
-        todo!();
+    /// Sets the site location in the hand control; see [`CelestronMount::set_site`].
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        self.set_site(location)
     }
 
     /// Gets the current time from the mount.
-    fn get_time(&mut self) -> Result<DateTime<Utc>, io::Error> {
-        let res = self.read_handcontrol(b'h')?;
-
-        if res.len() != 8 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Invalid time data received: {:?}", res),
-            ));
-        }
-
-        let hour = res[0];
-        let min = res[1];
-        let sec = res[2];
-        let mon = res[3];
-        let day = res[4];
-        let year = res[5] as i32 + 2000;
-        let ndst = res[7] == 1;
-        let ofst = (i8::from_be_bytes([res[6]]) + if ndst { 0 } else { 1 }) as i32 * 100;
-        let time = format!("{year}-{mon}-{day} {hour}:{min}:{sec} {ofst:+05}");
-        let date = DateTime::parse_from_str(&time, "%Y-%m-%d %H:%M:%S %z").map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Failed to parse time {time}: {e:?}"),
-            )
-        })?;
-
-        Ok(date.with_timezone(&chrono::Utc))
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        codec::decode_time(self.read_handcontrol(b'h')?)
     }
 
-    /// Sets the current time on the mount.
-    fn set_time() {
-        todo!();
+    /// Sets the current time on the mount, in the time zone it is already set to.
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        let zone = self.get_time_zone()?;
+        self.set_clock(time, zone)
     }
 
     /// Gets the version of the hand controller's firmware.
-    fn get_version(&mut self) -> Result<String, Box<dyn std::error::Error>> {
-        let res = self.read_handcontrol(b'V')?;
-
-        if res.len() != 2 {
-            return Err(Box::new(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("[{}:{}] Invalid data received: {:?}", file!(), line!(), res),
-            )));
+    ///
+    /// Cached after the first read; see [`CelestronMount::refresh_info`].
+    fn get_version(&mut self) -> Result<String, MountError> {
+        if let Some(version) = &self.info.version {
+            return Ok(version.clone());
         }
 
-        Ok(format!("{}.{}", res[0], res[1]))
+        let version = codec::decode_version(self.read_handcontrol(b'V')?)?;
+        self.info.version = Some(version.clone());
+        Ok(version)
     }
 
     /// Gets the version of the mount's firmware.
-    fn get_device_version(&mut self, device: NonGpsDevice) -> Result<String, Box<dyn Error>> {
-        let res = self.read_passthrough(device.as_device(), 254, 2)?;
-        Ok(format!("{}.{}", res[0], res[1]))
+    ///
+    /// Cached after the first read; see [`CelestronMount::refresh_info`].
+    fn get_device_version(&mut self, device: NonGpsDevice) -> Result<String, MountError> {
+        if let Some(version) = self.info.device_versions.get(&device) {
+            return Ok(version.clone());
+        }
+
+        let res = self.read_passthrough(device.as_device(), codec::MC_GET_VERSION, 2)?;
+        let version = codec::decode_version(res)?;
+        self.info.device_versions.insert(device, version.clone());
+        Ok(version)
     }
 
     /// Gets the model of the mount.
-    fn get_model(&mut self) -> Result<Model, io::Error> {
-        let res = self.read_handcontrol(b'm')?;
-        if res.len() != 1 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("[{}:{}] Invalid data received: {:?}", file!(), line!(), res),
-            ));
-        }
-
-        match res[0] {
-            1 => Ok(Model::GPSSeries),
-            3 => Ok(Model::ISeries),
-            4 => Ok(Model::ISeriesSe),
-            5 => Ok(Model::Cge),
-            6 => Ok(Model::AdvancedGT),
-            7 => Ok(Model::Slt),
-            9 => Ok(Model::Cpc),
-            10 => Ok(Model::Gt),
-            11 => Ok(Model::FourFiveSE),
-            12 => Ok(Model::SixEightSE),
-            14 => Ok(Model::Cgem),
-            20 => Ok(Model::AdvancedVX),
-            22 => Ok(Model::Evolution),
-            _ => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid model identifier.",
-            )),
+    ///
+    /// Cached after the first read; see [`CelestronMount::refresh_info`].
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        if let Some(model) = self.info.model {
+            return Ok(model);
         }
-    }
 
-    /// Repeats back the message that was sent to it.
-    fn echo() {
-        unimplemented!();
+        let model = codec::decode_model(self.read_handcontrol(b'm')?)?;
+        self.info.model = Some(model);
+        Ok(model)
     }
 
-    /// Gets the mount's current alignment status.
-    fn is_aligned(&mut self) -> Result<bool, io::Error> {
-        let res = self.read_handcontrol(b'J')?;
-        if res.len() != 1 {
-            return Err(io::Error::new(
+    /// Sends `byte` with the `K` command and returns the byte echoed back.
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        let len = self.transact(codec::echo(byte).as_bytes(), Framing::Length(2))?;
+        if len != 2 || self.recv[1] != b'#' {
+            return Err(MountError::other(
                 io::ErrorKind::InvalidData,
-                format!("[{}:{}] Invalid data received: {:?}", file!(), line!(), res),
+                format!("[{}:{}] Invalid echo received: {:?}", file!(), line!(), &self.recv[..len]),
             ));
         }
+        Ok(self.recv[0])
+    }
 
-        match res[0] {
-            0 => Ok(false),
-            1 => Ok(true),
-            _ => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid goto status.",
-            )),
-        }
+    /// Gets the mount's current alignment status.
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        codec::decode_aligned(self.read_handcontrol(b'J')?)
     }
 
     /// Determines if the mount is currently executing a goto command.
-    fn goto_in_progress(&mut self) -> Result<bool, io::Error> {
-        let res = self.read_handcontrol(b'L')?;
-        if res.len() != 1 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("[{}:{}] Invalid data received: {:?}", file!(), line!(), res),
-            ));
-        }
-
-        match res[0] {
-            48 => Ok(false),
-            49 => Ok(true),
-            _ => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Invalid goto status: {} from {:?}.", res[0], res),
-            )),
-        }
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        codec::decode_goto_in_progress(self.read_handcontrol(b'L')?)
     }
 
     /// Cancels the current goto in progress.
-    fn cancel_goto(&mut self) -> Result<(), io::Error> {
-        let res = self.read_handcontrol(b'Q')?;
-
-        if res.len() != 1 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("[{}:{}] Invalid data received: {:?}", file!(), line!(), res),
-            ));
-        }
-
-        match res[0] {
-            0 => Ok(()),
-            _ => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid goto status.",
-            )),
-        }
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.write_handcontrol(codec::cancel_goto())?;
+        Ok(())
     }
 
     /// Get GPS device
-    fn get_gps(&mut self) -> Result<CelestronGps, io::Error> {
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
         let model = self.get_model()?;
 
         match model {
             Model::GPSSeries => (),
             _ => {
-                return Err(io::Error::new(
+                return Err(MountError::other(
                     io::ErrorKind::NotFound,
                     format!("No GPS device on model {}.", model),
                 ))
             }
         }
 
-        Ok(CelestronGps {
+        Ok(Box::new(CelestronGps {
             mount: self,
-        })
+        }))
     }
     
-    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), io::Error> {
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
         self.slew_variable(axis, SlewDir::Positive, 0)
     }
 }
 
 impl Rtc for CelestronMount {
     /// Gets the current date and time from the mount's real-time clock.
-    fn get_datetime(&mut self) -> Result<DateTime<chrono::Utc>, io::Error> {
+    fn get_datetime(&mut self) -> Result<DateTime<chrono::Utc>, MountError> {
         use Device::*;
 
         let res = self.read_passthrough(RtcUnit, 3, 2)?;
@@ -768,25 +2023,676 @@ impl Rtc for CelestronMount {
     }
 
     /// Sets the current date and time on the mount's real-time clock.
-    fn set_datetime_now(&mut self) -> Result<(), io::Error> {
-        let now = chrono::Utc::now();
+    fn set_datetime_now(&mut self) -> Result<(), MountError> {
+        self.set_rtc_time(Utc::now())
+    }
+}
 
-        use Device::*;
+impl Guider for CelestronMount {
+    fn set_guide_rate(&mut self, axis: SlewAxis, rate: f64) -> Result<(), MountError> {
+        if !(0.0..1.0).contains(&rate) {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidInput,
+                format!("Guide rate {rate} is not between 0 and 1."),
+            ));
+        }
+        self.write_passthrough(codec::set_guide_rate(axis, rate))?;
+        self.guide_rates[axis as usize] = Some(codec::decode_guide_rate(codec::encode_guide_rate(rate)));
+        Ok(())
+    }
 
-        self.write_passthrough(RtcUnit, 131, &[now.month() as u8, now.day() as u8])?;
-        self.write_passthrough(RtcUnit, 132, &(now.year() as u16).to_be_bytes())?;
+    fn get_guide_rate(&mut self, axis: SlewAxis) -> Result<f64, MountError> {
+        let dev = match axis {
+            SlewAxis::RAAz => Device::AzRaMotor,
+            SlewAxis::DecEl => Device::ElDecMotor,
+        };
+        let rate = codec::decode_guide_rate(self.read_passthrough(dev, codec::MC_GET_AUTOGUIDE_RATE, 1)?[0]);
+        self.guide_rates[axis as usize] = Some(rate);
+        Ok(rate)
+    }
 
-        let now = chrono::Utc::now();
+    /// The pulse is sent at the guide rate, read from the motor controller the first time, and rounded to whole
+    /// percents of the sidereal rate and hundredths of a second.
+    fn pulse_guide(&mut self, direction: GuideDirection, duration_ms: u32) -> Result<(), MountError> {
+        if duration_ms as u128 > codec::MAX_GUIDE_PULSE.as_millis() {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidInput,
+                format!("Guide pulse of {duration_ms} ms is longer than {:?}.", codec::MAX_GUIDE_PULSE),
+            ));
+        }
 
-        self.write_passthrough(
-            RtcUnit,
-            179,
-            &[now.hour() as u8, now.minute() as u8, now.second() as u8],
-        )
+        let (axis, dir) = direction.axis();
+        let rate = match self.guide_rates[axis as usize] {
+            Some(rate) => rate,
+            None => self.get_guide_rate(axis)?,
+        };
+        let percent = (rate * 100.0).round().clamp(1.0, 100.0) as i8;
+        let percent = if dir == SlewDir::Positive { percent } else { -percent };
+        let centiseconds = ((duration_ms + 5) / 10) as u8;
+        self.write_passthrough(codec::aux_guide(axis, percent, centiseconds))
+    }
+
+    fn is_pulse_guiding(&mut self) -> Result<bool, MountError> {
+        for dev in [Device::AzRaMotor, Device::ElDecMotor] {
+            if self.read_passthrough(dev, codec::MC_IS_AUX_GUIDE_ACTIVE, 1)?[0] != 0 {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl FineTracking for CelestronMount {
+    fn set_tracking_rate(&mut self, axis: SlewAxis, arcsec_per_sec: f64) -> Result<(), MountError> {
+        if !(0.0..=codec::MAX_TRACKING_RATE).contains(&arcsec_per_sec.abs()) {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidInput,
+                format!("Tracking rate {arcsec_per_sec}\"/s is faster than {}\"/s.", codec::MAX_TRACKING_RATE),
+            ));
+        }
+        self.write_passthrough(codec::tracking_rate(axis, arcsec_per_sec))
+    }
+
+    /// The sidereal, lunar, and solar rates are the motor controller's own; King's rate is sent as a custom rate.
+    fn set_tracking_preset(&mut self, rate: TrackingRate) -> Result<(), MountError> {
+        let dir = match self.get_tracking_mode()? {
+            TrackingMode::EQNorth => SlewDir::Positive,
+            TrackingMode::EQSouth => SlewDir::Negative,
+            mode => {
+                return Err(MountError::other(
+                    io::ErrorKind::InvalidInput,
+                    format!("Tracking presets need an equatorial tracking mode, not {mode:?}."),
+                ))
+            }
+        };
+        match codec::tracking_preset(dir, rate) {
+            Some(msg) => self.write_passthrough(msg)?,
+            None => {
+                let sign = if dir == SlewDir::Positive { 1.0 } else { -1.0 };
+                self.set_tracking_rate(SlewAxis::RAAz, sign * rate.arcsec_per_sec())?
+            }
+        }
+        self.set_tracking_rate(SlewAxis::DecEl, 0.0)
+    }
+}
+
+impl Pec for CelestronMount {
+    fn seek_pec_index(&mut self) -> Result<(), MountError> {
+        self.write_passthrough(codec::passthrough(Device::AzRaMotor as u8, codec::MC_SEEK_INDEX, &[], 0)?)
+    }
+
+    fn start_pec_recording(&mut self) -> Result<(), MountError> {
+        self.write_passthrough(codec::passthrough(Device::AzRaMotor as u8, codec::MC_PEC_RECORD_START, &[], 0)?)
+    }
+
+    fn stop_pec_recording(&mut self) -> Result<(), MountError> {
+        self.write_passthrough(codec::passthrough(Device::AzRaMotor as u8, codec::MC_PEC_RECORD_STOP, &[], 0)?)
+    }
+
+    fn set_pec_playback(&mut self, enabled: bool) -> Result<(), MountError> {
+        let msg = codec::passthrough(Device::AzRaMotor as u8, codec::MC_PEC_PLAYBACK, &[enabled as u8], 0)?;
+        self.write_passthrough(msg)?;
+        self.pec_playback = Some(enabled);
+        Ok(())
+    }
+
+    fn get_pec_state(&mut self) -> Result<PecState, MountError> {
+        let indexed = self.read_passthrough(Device::AzRaMotor, codec::MC_AT_INDEX, 1)?[0] == 0xFF;
+        let recording = self.read_passthrough(Device::AzRaMotor, codec::MC_PEC_RECORD_DONE, 1)?[0] != 0xFF;
+        Ok(PecState {
+            indexed,
+            recording,
+            playback: self.pec_playback,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*; // Allows testing of private functions.
+    use super::*; // Allows testing of private functions.
+    use session::{EventKind, ReplayPort};
+    use sim::{Fault, SimPort};
+
+    #[test]
+    fn goto_commands_follow_firmware() {
+        let port = ReplayPort::scripted(vec![
+            EventKind::Write(b"V".to_vec()),
+            EventKind::Read(vec![1, 6, b'#']),
+            EventKind::Write(b"r40000000,071C71C7".to_vec()),
+            EventKind::Read(b"#".to_vec()),
+            // Precise Az/El gotos need version 2.2, so the 16-bit command is used instead.
+            EventKind::Write(b"B5555,2000".to_vec()),
+            EventKind::Read(b"#".to_vec()),
+            // Likewise the precise Get AZM-ALT, but the 16-bit one works from version 1.2.
+            EventKind::Write(b"Z".to_vec()),
+            EventKind::Read(b"4000,2000#".to_vec()),
+        ]);
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        assert_eq!(mount.get_version().unwrap(), "1.6");
+        mount.goto_ra_dec(RADec::new(90.0, 10.0)).unwrap();
+        mount.goto_az_el(AzEl::new(120.0, 45.0)).unwrap();
+        let pos = mount.get_position_az_el().unwrap();
+        assert!((pos.az - 90.0).abs() < 0.01 && (pos.el - 45.0).abs() < 0.01);
+
+        // Forcing the precise commands fails before sending `z` or `b`.
+        mount.set_precision(PrecisionMode::Precise);
+        assert_eq!(mount.get_position_az_el().unwrap_err().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(
+            mount.goto_az_el(AzEl::new(120.0, 45.0)).unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+        assert_eq!(port.remaining(), 0);
+    }
+
+    #[test]
+    fn pec_commands() {
+        let motor = |cmd, args: &[u8], resp_len| {
+            let mut msg = vec![b'P', 1 + args.len() as u8, 16, cmd, 0, 0, 0, resp_len];
+            msg[4..4 + args.len()].copy_from_slice(args);
+            EventKind::Write(msg)
+        };
+        let port = ReplayPort::scripted(vec![
+            motor(0x19, &[], 0),
+            EventKind::Read(b"#".to_vec()),
+            motor(0x18, &[], 1),
+            EventKind::Read(vec![0xFF, b'#']),
+            motor(0x15, &[], 1),
+            EventKind::Read(vec![0xFF, b'#']),
+            motor(0x0C, &[], 0),
+            EventKind::Read(b"#".to_vec()),
+            motor(0x0D, &[1], 0),
+            EventKind::Read(b"#".to_vec()),
+            motor(0x18, &[], 1),
+            EventKind::Read(vec![0xFF, b'#']),
+            motor(0x15, &[], 1),
+            EventKind::Read(vec![0x00, b'#']),
+            motor(0x16, &[], 0),
+            EventKind::Read(b"#".to_vec()),
+        ]);
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        mount.seek_pec_index().unwrap();
+        assert_eq!(
+            mount.get_pec_state().unwrap(),
+            PecState {
+                indexed: true,
+                recording: false,
+                playback: None,
+            }
+        );
+        mount.start_pec_recording().unwrap();
+        mount.set_pec_playback(true).unwrap();
+        assert_eq!(
+            mount.get_pec_state().unwrap(),
+            PecState {
+                indexed: true,
+                recording: true,
+                playback: Some(true),
+            }
+        );
+        mount.stop_pec_recording().unwrap();
+        assert_eq!(port.remaining(), 0);
+    }
+
+    #[test]
+    fn guide_commands() {
+        let motor = |dev, cmd, args: &[u8], resp_len| {
+            let mut msg = vec![b'P', 1 + args.len() as u8, dev, cmd, 0, 0, 0, resp_len];
+            msg[4..4 + args.len()].copy_from_slice(args);
+            EventKind::Write(msg)
+        };
+        let port = ReplayPort::scripted(vec![
+            motor(16, 0x46, &[128], 0),
+            EventKind::Read(b"#".to_vec()),
+            motor(16, 0x26, &[(-50i8) as u8, 150], 0),
+            EventKind::Read(b"#".to_vec()),
+            // The declination guide rate is not known yet.
+            motor(17, 0x47, &[], 1),
+            EventKind::Read(vec![64, b'#']),
+            motor(17, 0x26, &[25, 3], 0),
+            EventKind::Read(b"#".to_vec()),
+            motor(16, 0x27, &[], 1),
+            EventKind::Read(vec![0, b'#']),
+            motor(17, 0x27, &[], 1),
+            EventKind::Read(vec![1, b'#']),
+        ]);
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        mount.set_guide_rate(SlewAxis::RAAz, 0.5).unwrap();
+        assert!(mount.set_guide_rate(SlewAxis::RAAz, 1.5).is_err());
+        mount.pulse_guide(GuideDirection::West, 1500).unwrap();
+        mount.pulse_guide(GuideDirection::North, 25).unwrap();
+        assert!(mount.pulse_guide(GuideDirection::North, 3000).is_err());
+        assert!(mount.is_pulse_guiding().unwrap());
+        assert_eq!(port.remaining(), 0);
+    }
+
+    #[test]
+    fn tracking_rate_commands() {
+        let motor = |dev, cmd, args: &[u8]| {
+            let mut msg = vec![b'P', 1 + args.len() as u8, dev, cmd, 0, 0, 0, 0];
+            msg[4..4 + args.len()].copy_from_slice(args);
+            vec![EventKind::Write(msg), EventKind::Read(b"#".to_vec())]
+        };
+        let events = [
+            // 1.5"/s backwards is 1536/1024"/s.
+            motor(17, 0x07, &[0x00, 0x06, 0x00]),
+            vec![EventKind::Write(b"t".to_vec()), EventKind::Read(vec![3, b'#'])],
+            motor(16, 0x07, &[0xFF, 0xFD]),
+            motor(17, 0x06, &[0, 0, 0]),
+            vec![EventKind::Write(b"t".to_vec()), EventKind::Read(vec![2, b'#'])],
+            motor(16, 0x06, &[0x00, 0x3C, 0x26]),
+            motor(17, 0x06, &[0, 0, 0]),
+            vec![EventKind::Write(b"t".to_vec()), EventKind::Read(vec![1, b'#'])],
+        ];
+        let port = ReplayPort::scripted(events.concat());
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        mount.set_tracking_rate(SlewAxis::DecEl, -1.5).unwrap();
+        assert!(mount.set_tracking_rate(SlewAxis::RAAz, 20000.0).is_err());
+        mount.set_tracking_preset(TrackingRate::Lunar).unwrap();
+        mount.set_tracking_preset(TrackingRate::King).unwrap();
+        assert_eq!(
+            mount.set_tracking_preset(TrackingRate::Solar).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(port.remaining(), 0);
+    }
+
+    #[test]
+    fn caches_info() {
+        let port = ReplayPort::scripted(vec![
+            EventKind::Write(b"m".to_vec()),
+            EventKind::Read(vec![20, b'#']),
+            EventKind::Write(b"V".to_vec()),
+            EventKind::Read(vec![5, 35, b'#']),
+            EventKind::Write(vec![b'P', 1, 16, 254, 0, 0, 0, 2]),
+            EventKind::Read(vec![7, 11, b'#']),
+            EventKind::Write(b"m".to_vec()),
+            EventKind::Read(vec![7, b'#']),
+        ]);
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        for _ in 0..2 {
+            assert_eq!(mount.get_model().unwrap(), Model::AdvancedVX);
+            assert_eq!(mount.get_version().unwrap(), "5.35");
+            assert_eq!(
+                mount.get_device_version(NonGpsDevice::AzRaMotor).unwrap(),
+                "7.11"
+            );
+        }
+        assert_eq!(port.remaining(), 1);
+
+        mount.refresh_info();
+        assert_eq!(mount.get_model().unwrap(), Model::Slt);
+        assert_eq!(port.remaining(), 0);
+    }
+
+    #[test]
+    fn echo_checks_framing() {
+        let port = ReplayPort::scripted(vec![
+            EventKind::Write(b"Kx".to_vec()),
+            EventKind::Read(b"x#".to_vec()),
+            EventKind::Write(b"Kx".to_vec()),
+            EventKind::Read(b"xx".to_vec()),
+        ]);
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        mount.ping().unwrap();
+        assert_eq!(mount.echo(b'x').unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(port.remaining(), 0);
+    }
+
+    #[test]
+    fn adaptive_timeout_fails_fast() {
+        let mut events = Vec::new();
+        for _ in 0..10 {
+            events.push(EventKind::Write(b"J".to_vec()));
+            events.push(EventKind::Read(vec![1, b'#']));
+        }
+        events.push(EventKind::Write(b"J".to_vec()));
+        let port = ReplayPort::scripted(events).timeout(Duration::from_secs(5));
+        let mut mount = CelestronMount::from_port(Box::new(port));
+        mount.set_adaptive_timeout(Some(AdaptiveTimeout {
+            floor: Duration::from_millis(50),
+            ..AdaptiveTimeout::default()
+        }));
+
+        for _ in 0..10 {
+            assert!(mount.is_aligned().unwrap());
+        }
+        let start = Instant::now();
+        let e = mount.is_aligned().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn long_responses() {
+        let mut long = vec![b'1'; 40];
+        long.push(b'#');
+        let mut too_long = vec![b'1'; 300];
+        too_long.push(b'#');
+        let port = ReplayPort::scripted(vec![
+            EventKind::Write(b"K".to_vec()),
+            EventKind::Read(long[..20].to_vec()),
+            EventKind::Read(long[20..].to_vec()),
+            EventKind::Write(b"K".to_vec()),
+            EventKind::Read(too_long),
+        ]);
+        let mut mount = CelestronMount::from_port(Box::new(port));
+
+        assert_eq!(mount.read_handcontrol(b'K').unwrap(), &long[..40]);
+
+        let e = mount.read_handcontrol(b'K').unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            e,
+            MountError::ResponseOverflow(ResponseOverflow {
+                max: DEFAULT_MAX_RESPONSE,
+                received: 301,
+            })
+        );
+    }
+
+    #[test]
+    fn reads_with_the_read_timeout() {
+        let port = SimPort::new(SimMount::new());
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+        let read_timeout = Duration::from_millis(300);
+        mount
+            .set_comms(CommsConfig {
+                read_timeout,
+                write_timeout: Duration::from_millis(50),
+                ..CommsConfig::default()
+            })
+            .unwrap();
+        assert_eq!(SerialPort::timeout(&port), read_timeout);
+
+        mount.get_tracking_mode().unwrap();
+        assert_eq!(SerialPort::timeout(&port), read_timeout);
+    }
+
+    #[test]
+    fn reassembles_split_responses() {
+        let port = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        // Version 5.35 is [5, '#'], so only the length tells the '#' apart from the terminator.
+        port.inject(Fault::Split(2));
+        assert_eq!(mount.get_version().unwrap(), "5.35");
+        port.inject(Fault::Split(5));
+        assert!(mount.get_position_ra_dec().is_ok());
+
+        // A response which never completes fails instead of being misread.
+        mount.refresh_info();
+        port.inject(Fault::Truncate(2));
+        let e = mount.get_version().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn partial_responses_wait_only_until_the_deadline() {
+        let port = SimPort::new(SimMount::new()).timeout(Duration::from_secs(3));
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+        let start = Instant::now();
+        assert!(mount.get_position_ra_dec().is_ok());
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        mount.set_command_deadline(Command::HandControl(b'e'), Some(Duration::from_millis(50)));
+        port.inject(Fault::Truncate(5));
+        let start = Instant::now();
+        assert!(mount.get_position_ra_dec().is_err());
+        assert!(start.elapsed() < Duration::from_secs(1));
+        // The late bytes of the failed response are discarded before the next command.
+        assert!(mount.get_position_ra_dec().is_ok());
+    }
+
+    #[test]
+    fn stops_reading_at_the_deadline() {
+        // A real port's read blocks for its whole timeout when nothing arrives.
+        let port = SimPort::new(SimMount::new())
+            .timeout(Duration::from_secs(3))
+            .blocking_reads();
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+        mount.set_command_deadline(Command::HandControl(b'e'), Some(Duration::from_millis(50)));
+
+        port.inject(Fault::Truncate(5));
+        let start = Instant::now();
+        let e = mount.get_position_ra_dec().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(start.elapsed() < Duration::from_secs(1), "{:?}", start.elapsed());
+        assert!(mount.get_position_ra_dec().is_ok());
+    }
+
+    #[test]
+    fn probe_identifies_hand_control() {
+        let port = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        assert!(CelestronMount::probe(Box::new(port.clone())));
+
+        // A device which stays silent, or answers with something else.
+        port.inject(Fault::Timeout);
+        assert!(!CelestronMount::probe(Box::new(port.clone())));
+        port.inject(Fault::Garbage(b"OK\r\n".to_vec()));
+        assert!(!CelestronMount::probe(Box::new(port)));
+    }
+
+    #[test]
+    fn revalidates_after_idle() {
+        let port = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+        mount.set_revalidate_after(Some(Duration::from_millis(50)));
+        mount.get_tracking_mode().unwrap();
+        mount.get_tracking_mode().unwrap();
+        assert_eq!(mount.metrics().commands, 2);
+
+        // The link dropped while idle: the check fails and the command is never sent.
+        std::thread::sleep(Duration::from_millis(60));
+        port.inject(Fault::Timeout);
+        let e = mount.get_tracking_mode().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(mount.metrics().commands, 3);
+
+        mount.get_tracking_mode().unwrap();
+        assert_eq!(mount.metrics().commands, 5);
+    }
+
+    #[test]
+    fn positions_with_derived_az_el() {
+        let port = SimPort::new(SimMount::new().site(40.0, -75.0).manual_clock(Utc::now()));
+        port.mount().goto_az_el(AzEl::new(120.0, 30.0)).unwrap();
+        while port.mount().goto_in_progress().unwrap() {
+            port.mount().step(Duration::from_secs(1));
+        }
+        // The azimuth and elevation are derived with the host's clock, which the simulation's is set to.
+        port.mount().set_time(Utc::now()).unwrap();
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        let (ra_dec, az_el) = mount.get_positions(40.0, -75.0).unwrap();
+        assert_eq!(mount.metrics().commands, 1);
+        let expected = mount.get_position_az_el().unwrap();
+        assert!((az_el.az - expected.az).abs() < 1e-3, "{az_el} != {expected}");
+        assert!((az_el.el - expected.el).abs() < 1e-3, "{az_el} != {expected}");
+        assert!((ra_dec.dec - mount.get_position_ra_dec().unwrap().dec).abs() < 1e-3);
+    }
+
+    #[test]
+    fn waits_for_gps_fix() {
+        let gps = |cmd, resp_len| EventKind::Write(vec![b'P', 1, 176, cmd, 0, 0, 0, resp_len]);
+        let port = ReplayPort::scripted(vec![
+            EventKind::Write(b"m".to_vec()),
+            EventKind::Read(vec![1, b'#']),
+            // Not linked yet.
+            gps(55, 1),
+            EventKind::Read(vec![0, b'#']),
+            // Linked, but the time is not valid yet.
+            gps(55, 1),
+            EventKind::Read(vec![1, b'#']),
+            // Checked again by `get_location`.
+            gps(55, 1),
+            EventKind::Read(vec![1, b'#']),
+            gps(1, 3),
+            EventKind::Read(vec![0x20, 0x00, 0x00, b'#']),
+            gps(2, 3),
+            EventKind::Read(vec![0xE0, 0x00, 0x00, b'#']),
+            gps(54, 1),
+            EventKind::Read(vec![0, b'#']),
+            gps(55, 1),
+            EventKind::Read(vec![1, b'#']),
+            gps(55, 1),
+            EventKind::Read(vec![1, b'#']),
+            gps(1, 3),
+            EventKind::Read(vec![0x20, 0x00, 0x00, b'#']),
+            gps(2, 3),
+            EventKind::Read(vec![0xE0, 0x00, 0x00, b'#']),
+            gps(54, 1),
+            EventKind::Read(vec![1, b'#']),
+            gps(3, 2),
+            EventKind::Read(vec![3, 20, b'#']),
+            gps(4, 2),
+            EventKind::Read(vec![0x07, 0xE8, b'#']),
+            gps(51, 3),
+            EventKind::Read(vec![22, 5, 9, b'#']),
+        ]);
+        let mut mount = CelestronMount::from_port(Box::new(port));
+        let mut gps = mount.get_gps().unwrap();
+
+        let mut progress = Vec::new();
+        let fix = gps
+            .wait_for_fix_with(Duration::from_secs(2), |p| progress.push(p.linked))
+            .unwrap();
+        assert_eq!(progress, [false, false, true]);
+        assert_eq!(
+            fix.location,
+            Location {
+                latitude: 45.0,
+                longitude: -45.0
+            }
+        );
+        assert_eq!(
+            fix.time,
+            Utc.with_ymd_and_hms(2024, 3, 20, 22, 5, 9).unwrap()
+        );
+
+        // The replay has nothing more to say.
+        let e = gps.wait_for_fix(Duration::from_millis(100)).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn local_time() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap();
+        let port = SimPort::new(SimMount::new().manual_clock(start));
+        let mut mount = CelestronMount::from_port(Box::new(port));
+        let zone = TimeZoneSetting {
+            utc_offset: -5,
+            dst: true,
+        };
+        mount.set_clock(start, zone).unwrap();
+
+        let local = mount.get_local_time().unwrap();
+        assert_eq!(local.offset().local_minus_utc(), -4 * 3600);
+        assert_eq!(local.format("%H:%M").to_string(), "18:00");
+        assert_eq!(local, start);
+
+        // The zone is kept.
+        let later = start + chrono::TimeDelta::hours(3);
+        mount.set_time(later).unwrap();
+        assert_eq!(mount.get_time().unwrap(), later);
+        assert_eq!(mount.get_time_zone().unwrap(), zone);
+
+        let offset = FixedOffset::east_opt(2 * 3600).unwrap();
+        let summer = Utc.with_ymd_and_hms(2024, 7, 1, 21, 30, 15).unwrap().with_timezone(&offset);
+        mount.set_local_time(summer, true).unwrap();
+        assert_eq!(mount.get_local_time().unwrap(), summer);
+        assert_eq!(mount.get_local_time().unwrap().offset(), &offset);
+        assert_eq!(
+            mount.get_time_zone().unwrap(),
+            TimeZoneSetting {
+                utc_offset: 1,
+                dst: true
+            }
+        );
+        let india = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+        let e = mount.set_local_time(summer.with_timezone(&india), false).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(feature = "tz")]
+    #[test]
+    fn zone_settings() {
+        use chrono_tz::{America, Asia};
+
+        let summer = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+        let winter = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            TimeZoneSetting::for_zone(America::New_York, summer).unwrap(),
+            TimeZoneSetting {
+                utc_offset: -5,
+                dst: true
+            }
+        );
+        assert!(!TimeZoneSetting::for_zone(America::New_York, winter).unwrap().dst);
+        assert!(TimeZoneSetting::for_zone(Asia::Kolkata, summer).is_err());
+    }
+
+    #[test]
+    fn blocking_goto_reports_progress() {
+        let mut mount = SimMount::new().max_rate(100.0).acceleration(1000.0);
+        let start = mount.get_position_ra_dec().unwrap();
+        let target = RADec::new((start.ra + 30.0).rem_euclid(360.0), 20.0);
+
+        let mut positions = Vec::new();
+        mount
+            .goto_ra_dec_blocking(target, Duration::from_secs(30), |pos| positions.push(pos))
+            .unwrap();
+        assert!(positions.len() >= 2, "{positions:?}");
+        let last = positions.last().unwrap();
+        assert!(transform::angular_separation(last.ra, last.dec, target.ra, target.dec) < 0.01);
+    }
+
+    #[test]
+    fn derived_operations() {
+        let mut mount = SimMount::new().manual_clock(Utc::now());
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+
+        let target = Target {
+            name: "M 42".to_string(),
+            designations: Vec::new(),
+            coord: None,
+            magnitude: None,
+            kind: None,
+            notes: None,
+        };
+        assert_eq!(
+            mount.goto_object(&target).unwrap_err(),
+            MountError::other(
+                io::ErrorKind::NotFound,
+                "No coordinates for M 42; resolve its name first."
+            )
+        );
+
+        let e = mount.goto_and_wait(RADec::new(100.0, 20.0), Some(Duration::ZERO)).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert!(!mount.goto_in_progress().unwrap());
+        let e = mount
+            .goto_ra_dec_blocking(RADec::new(100.0, 20.0), Duration::ZERO, |_| ())
+            .unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert!(!mount.goto_in_progress().unwrap());
+
+        mount.set_tracking_mode(TrackingMode::Off).unwrap();
+        let before = mount.get_position_az_el().unwrap();
+        mount.nudge(SlewAxis::RAAz, SlewDir::Positive, SlewRate::Rate9, Duration::ZERO).unwrap();
+        mount.step(Duration::from_secs(10));
+        assert!((mount.get_position_az_el().unwrap().az - before.az).abs() < 0.1);
+
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        mount.slew_fixed(SlewAxis::DecEl, SlewDir::Negative, SlewRate::Rate5).unwrap();
+        mount.goto_az_el(AzEl::new(180.0, 45.0)).unwrap();
+        mount.emergency_stop().unwrap();
+        assert!(!mount.goto_in_progress().unwrap());
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::Off);
+    }
 }
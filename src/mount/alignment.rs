@@ -0,0 +1,255 @@
+//! Guided star alignment built on [`Mount::sync`].
+//!
+//! An [`AlignmentSession`] walks through aligning on one or more bright stars from
+//! [`BRIGHT_STARS`](crate::catalog::stars::BRIGHT_STARS):
+//!
+//! 1. [`candidates`](AlignmentSession::candidates) lists stars above the horizon, well apart from those already used.
+//! 2. [`slew_to`](AlignmentSession::slew_to) starts a goto to the chosen star. A mount which is not aligned yet
+//!    refuses gotos, so for the first star the user slews to it by hand instead.
+//! 3. [`poll`](AlignmentSession::poll) reports when the goto has finished, and the user centers the star.
+//! 4. [`confirm`](AlignmentSession::confirm) syncs on the star and records how far off the mount was.
+//!
+//! After the requested number of stars the session is [`Complete`](AlignmentState::Complete), and
+//! [`residuals`](AlignmentSession::residuals) tell how much each sync corrected.
+
+use super::error::MountError;
+use super::transform::{angular_separation, ha_dec_to_az_el, local_sidereal_time};
+use super::{Location, Mount, RADec};
+use crate::catalog::stars::{BrightStar, BRIGHT_STARS};
+use chrono::{DateTime, Utc};
+use std::io;
+
+/// Default lowest elevation of a candidate star, in degrees, clear of trees and thick air.
+pub const DEFAULT_MIN_ELEVATION: f64 = 20.0;
+
+/// Least angle in degrees between alignment stars; stars close together barely constrain the pointing.
+pub const MIN_SEPARATION: f64 = 30.0;
+
+/// Where an alignment is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlignmentState {
+    /// Waiting for the next star to be chosen.
+    ChooseStar,
+    /// A goto to the star is in progress.
+    Slewing(&'static BrightStar),
+    /// Waiting for the user to center the star and confirm.
+    Centering(&'static BrightStar),
+    /// All stars have been synced on.
+    Complete,
+}
+
+/// The correction made by syncing on one star.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Residual {
+    pub star: &'static BrightStar,
+    /// Position of date the mount reported once the star was centered.
+    pub reported: RADec,
+    /// Position of date of the star.
+    pub actual: RADec,
+}
+
+impl Residual {
+    /// Pointing error corrected by the sync, in arcseconds.
+    pub fn error_arcsec(&self) -> f64 {
+        angular_separation(
+            self.reported.ra,
+            self.reported.dec,
+            self.actual.ra,
+            self.actual.dec,
+        ) * 3600.0
+    }
+}
+
+/// A star alignment in progress; see [`alignment`](self).
+#[derive(Debug, Clone)]
+pub struct AlignmentSession {
+    site: Location,
+    stars: usize,
+    min_elevation: f64,
+    state: AlignmentState,
+    residuals: Vec<Residual>,
+}
+
+impl AlignmentSession {
+    /// Starts an alignment on `stars` stars as seen from `site`.
+    pub fn new(site: Location, stars: usize) -> AlignmentSession {
+        AlignmentSession {
+            site,
+            stars: stars.max(1),
+            min_elevation: DEFAULT_MIN_ELEVATION,
+            state: AlignmentState::ChooseStar,
+            residuals: Vec::new(),
+        }
+    }
+
+    /// Sets the lowest elevation of a candidate star, in degrees.
+    pub fn min_elevation(mut self, min_elevation: f64) -> AlignmentSession {
+        self.min_elevation = min_elevation;
+        self
+    }
+
+    pub fn state(&self) -> AlignmentState {
+        self.state
+    }
+
+    /// Corrections made so far, in the order the stars were synced on.
+    pub fn residuals(&self) -> &[Residual] {
+        &self.residuals
+    }
+
+    /// Root mean square of the corrections, in arcseconds, or `None` before the first sync.
+    pub fn rms_error_arcsec(&self) -> Option<f64> {
+        if self.residuals.is_empty() {
+            return None;
+        }
+        let sum: f64 = self
+            .residuals
+            .iter()
+            .map(|r| r.error_arcsec().powi(2))
+            .sum();
+        Some((sum / self.residuals.len() as f64).sqrt())
+    }
+
+    /// Stars usable as the next alignment star at `time`, brightest first: above the minimum elevation, not used
+    /// yet, and at least [`MIN_SEPARATION`] from the stars already used.
+    pub fn candidates(&self, time: DateTime<Utc>) -> Vec<&'static BrightStar> {
+        let lst = local_sidereal_time(time, self.site.longitude);
+        BRIGHT_STARS
+            .iter()
+            .filter(|star| {
+                let coord = star.coord().j2000_to_jnow(time);
+                ha_dec_to_az_el(lst - coord.ra, coord.dec, self.site.latitude).el
+                    >= self.min_elevation
+            })
+            .filter(|star| {
+                self.residuals.iter().all(|r| {
+                    angular_separation(r.star.ra, r.star.dec, star.ra, star.dec) >= MIN_SEPARATION
+                })
+            })
+            .collect()
+    }
+
+    /// Starts a goto to `star`, or, if the mount is not aligned yet and so refuses gotos, waits for the user to slew
+    /// to it by hand.
+    pub fn slew_to<M: Mount>(
+        &mut self,
+        mount: &mut M,
+        star: &'static BrightStar,
+    ) -> Result<(), MountError> {
+        if self.state == AlignmentState::Complete {
+            return Err(invalid_state("The alignment is already complete."));
+        }
+
+        if mount.is_aligned()? {
+            let coord = star.coord().j2000_to_jnow(mount.get_time()?);
+            mount.goto_ra_dec(coord)?;
+            self.state = AlignmentState::Slewing(star);
+        } else {
+            self.state = AlignmentState::Centering(star);
+        }
+        Ok(())
+    }
+
+    /// Checks whether the goto has finished, moving on to centering the star once it has.
+    pub fn poll<M: Mount>(&mut self, mount: &mut M) -> Result<AlignmentState, MountError> {
+        if let AlignmentState::Slewing(star) = self.state {
+            if !mount.goto_in_progress()? {
+                self.state = AlignmentState::Centering(star);
+            }
+        }
+        Ok(self.state)
+    }
+
+    /// Syncs on the star the user has centered, returning the correction made.
+    pub fn confirm<M: Mount>(&mut self, mount: &mut M) -> Result<Residual, MountError> {
+        let AlignmentState::Centering(star) = self.state else {
+            return Err(invalid_state("No star is waiting to be centered."));
+        };
+
+        let reported = mount.get_position_ra_dec()?;
+        let actual = star.coord().j2000_to_jnow(mount.get_time()?);
+        mount.sync(actual)?;
+
+        let residual = Residual {
+            star,
+            reported,
+            actual,
+        };
+        self.residuals.push(residual);
+        self.state = if self.residuals.len() >= self.stars {
+            AlignmentState::Complete
+        } else {
+            AlignmentState::ChooseStar
+        };
+        Ok(residual)
+    }
+
+    /// Gives up on the current star, stopping any goto to it, so another can be chosen.
+    pub fn skip<M: Mount>(&mut self, mount: &mut M) -> Result<(), MountError> {
+        match self.state {
+            AlignmentState::Slewing(_) => mount.cancel_goto()?,
+            AlignmentState::Centering(_) => (),
+            _ => return Ok(()),
+        }
+        self.state = AlignmentState::ChooseStar;
+        Ok(())
+    }
+}
+
+fn invalid_state(message: &str) -> MountError {
+    MountError::other(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use chrono::TimeZone;
+    use std::time::Duration;
+
+    #[test]
+    fn two_star_alignment() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 20, 3, 0, 0).unwrap();
+        let mut mount = SimMount::new()
+            .manual_clock(start)
+            .site(40.0, -75.0)
+            .aligned(false);
+        let site = Location {
+            latitude: 40.0,
+            longitude: -75.0,
+        };
+        let mut session = AlignmentSession::new(site, 2);
+
+        let first = session.candidates(start)[0];
+        assert_eq!(first.name, "Arcturus");
+        session.slew_to(&mut mount, first).unwrap();
+        assert_eq!(session.state(), AlignmentState::Centering(first));
+
+        // Point roughly at the star by hand, about a degree off.
+        let mut rough = first.coord().j2000_to_jnow(start).to_az_el(&site, start);
+        rough.el -= 1.0;
+        mount.goto_az_el(rough).unwrap();
+        while mount.goto_in_progress().unwrap() {
+            mount.step(Duration::from_secs(1));
+        }
+        let residual = session.confirm(&mut mount).unwrap();
+        assert!(residual.error_arcsec() > 1800.0, "{residual:?}");
+        assert!(mount.is_aligned().unwrap());
+
+        let second = session.candidates(mount.get_time().unwrap())[0];
+        assert!(angular_separation(first.ra, first.dec, second.ra, second.dec) >= MIN_SEPARATION);
+        session.slew_to(&mut mount, second).unwrap();
+        assert_eq!(
+            session.poll(&mut mount).unwrap(),
+            AlignmentState::Slewing(second)
+        );
+        while session.poll(&mut mount).unwrap() != AlignmentState::Centering(second) {
+            mount.step(Duration::from_secs(1));
+        }
+        let residual = session.confirm(&mut mount).unwrap();
+        assert!(residual.error_arcsec() < 60.0, "{residual:?}");
+        assert_eq!(session.state(), AlignmentState::Complete);
+        assert!(session.rms_error_arcsec().is_some());
+        assert!(session.confirm(&mut mount).is_err());
+    }
+}
@@ -0,0 +1,632 @@
+//! An async interface to the mount for GUIs and services.
+//!
+//! Every [`Mount`] method blocks until the hand control answers, which takes tens of milliseconds for a command and
+//! minutes for a goto. [`AsyncMount`] offers the same operations as futures, so a GUI or async service can await them
+//! without stalling its event loop. [`CelestronMountAsync`] talks to the hand control on an async serial port from
+//! `tokio-serial`:
+//!
+//! ```no_run
+//! use nexlib::mount::asynchronous::{AsyncMount, CelestronMountAsync};
+//! use nexlib::RADec;
+//! use std::time::Duration;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let mount = CelestronMountAsync::connect().await?;
+//! mount.goto_ra_dec(RADec::new(83.82, -5.39)).await?;
+//! mount.wait_goto_complete(Some(Duration::from_secs(180))).await?;
+//! println!("Arrived at {}.", mount.get_position_ra_dec().await?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`MountAsync`] implements [`AsyncMount`] for any other [`Mount`], such as a [`SimMount`](super::SimMount) or an
+//! LX200 mount, by running each call on Tokio's blocking thread pool, one at a time. Clones of a [`MountAsync`] share
+//! the mount. Either way a Tokio runtime must be running.
+
+use super::error::MountError;
+use super::latency::Command;
+use super::{
+    check_finite, codec, diagnose, support, AzEl, CelestronMount, Model, Mount, RADec, SlewAxis,
+    SlewDir, SlewRate, TrackingMode, DEFAULT_BAUD, DEFAULT_TIMEOUT,
+};
+use chrono::{DateTime, Utc};
+use codec::Framing;
+use std::future::Future;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::{sleep, timeout, timeout_at, Instant};
+use tokio_serial::{SerialPortBuilderExt, SerialStream};
+
+/// How often [`AsyncMount::wait_goto_complete`] checks the goto.
+const GOTO_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The operations of [`Mount`] as futures.
+pub trait AsyncMount: Sync {
+    fn get_position_ra_dec(&self) -> impl Future<Output = Result<RADec, MountError>> + Send;
+    fn get_position_az_el(&self) -> impl Future<Output = Result<AzEl, MountError>> + Send;
+    fn goto_ra_dec(&self, coord: RADec) -> impl Future<Output = Result<(), MountError>> + Send;
+    fn goto_az_el(&self, coord: AzEl) -> impl Future<Output = Result<(), MountError>> + Send;
+    fn sync(&self, coord: RADec) -> impl Future<Output = Result<(), MountError>> + Send;
+    fn get_tracking_mode(&self) -> impl Future<Output = Result<TrackingMode, MountError>> + Send;
+    fn set_tracking_mode(
+        &self,
+        mode: TrackingMode,
+    ) -> impl Future<Output = Result<(), MountError>> + Send;
+    fn slew_variable(
+        &self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: u16,
+    ) -> impl Future<Output = Result<(), MountError>> + Send;
+    fn slew_fixed(
+        &self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> impl Future<Output = Result<(), MountError>> + Send;
+    fn stop_slew(&self, axis: SlewAxis) -> impl Future<Output = Result<(), MountError>> + Send;
+    fn get_time(&self) -> impl Future<Output = Result<DateTime<Utc>, MountError>> + Send;
+    fn set_time(&self, time: DateTime<Utc>) -> impl Future<Output = Result<(), MountError>> + Send;
+    fn get_model(&self) -> impl Future<Output = Result<Model, MountError>> + Send;
+    fn is_aligned(&self) -> impl Future<Output = Result<bool, MountError>> + Send;
+    fn goto_in_progress(&self) -> impl Future<Output = Result<bool, MountError>> + Send;
+    fn cancel_goto(&self) -> impl Future<Output = Result<(), MountError>> + Send;
+    /// Stops both axes and turns tracking off, as [`Mount::emergency_stop`].
+    fn emergency_stop(&self) -> impl Future<Output = Result<(), MountError>> + Send;
+
+    /// Waits for the current goto to finish, checking every half second without blocking.
+    ///
+    /// After `timeout` the goto is cancelled and `TimedOut` returned.
+    fn wait_goto_complete(
+        &self,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<(), MountError>> + Send {
+        async move {
+            let deadline = timeout.map(|t| Instant::now() + t);
+            while self.goto_in_progress().await? {
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    self.cancel_goto().await?;
+                    return Err(MountError::other(
+                        io::ErrorKind::TimedOut,
+                        "The goto did not finish in time and was cancelled.",
+                    ));
+                }
+                sleep(GOTO_POLL_INTERVAL).await;
+            }
+            Ok(())
+        }
+    }
+
+    /// Starts a goto to `coord` and waits for it to finish; see [`wait_goto_complete`](Self::wait_goto_complete).
+    fn goto_and_wait(
+        &self,
+        coord: RADec,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<(), MountError>> + Send {
+        async move {
+            self.goto_ra_dec(coord).await?;
+            self.wait_goto_complete(timeout).await
+        }
+    }
+}
+
+/// Any [`Mount`] driven from async code; see [`asynchronous`](self).
+#[derive(Debug)]
+pub struct MountAsync<M> {
+    mount: Arc<Mutex<M>>,
+}
+
+impl<M> Clone for MountAsync<M> {
+    fn clone(&self) -> Self {
+        MountAsync {
+            mount: Arc::clone(&self.mount),
+        }
+    }
+}
+
+impl<M: Mount + Send + 'static> MountAsync<M> {
+    pub fn new(mount: M) -> MountAsync<M> {
+        MountAsync::from_shared(Arc::new(Mutex::new(mount)))
+    }
+
+    /// Wraps a mount also used elsewhere, such as by a [`StatusCache`](super::status::StatusCache).
+    pub fn from_shared(mount: Arc<Mutex<M>>) -> MountAsync<M> {
+        MountAsync { mount }
+    }
+
+    /// The wrapped mount.
+    pub fn shared(&self) -> &Arc<Mutex<M>> {
+        &self.mount
+    }
+
+    /// Runs `f` against the mount on a blocking worker thread.
+    pub async fn with_mount<T, F>(&self, f: F) -> Result<T, MountError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut M) -> Result<T, MountError> + Send + 'static,
+    {
+        let mount = Arc::clone(&self.mount);
+        spawn(move || {
+            let mut mount = mount
+                .lock()
+                .map_err(|_| MountError::other(io::ErrorKind::Other, "Mount lock poisoned."))?;
+            f(&mut mount)
+        })
+        .await
+    }
+}
+
+/// Runs `f` on the blocking thread pool.
+async fn spawn<T, E, F>(f: F) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<MountError> + Send + 'static,
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| MountError::other(io::ErrorKind::Other, format!("Mount task failed: {e}")))?
+}
+
+impl<M: Mount + Send + 'static> AsyncMount for MountAsync<M> {
+    async fn get_position_ra_dec(&self) -> Result<RADec, MountError> {
+        self.with_mount(|m| m.get_position_ra_dec()).await
+    }
+
+    async fn get_position_az_el(&self) -> Result<AzEl, MountError> {
+        self.with_mount(|m| m.get_position_az_el()).await
+    }
+
+    async fn goto_ra_dec(&self, coord: RADec) -> Result<(), MountError> {
+        self.with_mount(move |m| m.goto_ra_dec(coord)).await
+    }
+
+    async fn goto_az_el(&self, coord: AzEl) -> Result<(), MountError> {
+        self.with_mount(move |m| m.goto_az_el(coord)).await
+    }
+
+    async fn sync(&self, coord: RADec) -> Result<(), MountError> {
+        self.with_mount(move |m| m.sync(coord)).await
+    }
+
+    async fn get_tracking_mode(&self) -> Result<TrackingMode, MountError> {
+        self.with_mount(|m| m.get_tracking_mode()).await
+    }
+
+    async fn set_tracking_mode(&self, mode: TrackingMode) -> Result<(), MountError> {
+        self.with_mount(move |m| m.set_tracking_mode(mode)).await
+    }
+
+    async fn slew_variable(
+        &self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: u16,
+    ) -> Result<(), MountError> {
+        self.with_mount(move |m| m.slew_variable(axis, dir, rate))
+            .await
+    }
+
+    async fn slew_fixed(
+        &self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        self.with_mount(move |m| m.slew_fixed(axis, dir, rate))
+            .await
+    }
+
+    async fn stop_slew(&self, axis: SlewAxis) -> Result<(), MountError> {
+        self.with_mount(move |m| m.stop_slew(axis)).await
+    }
+
+    async fn get_time(&self) -> Result<DateTime<Utc>, MountError> {
+        self.with_mount(|m| m.get_time()).await
+    }
+
+    async fn set_time(&self, time: DateTime<Utc>) -> Result<(), MountError> {
+        self.with_mount(move |m| m.set_time(time)).await
+    }
+
+    async fn get_model(&self) -> Result<Model, MountError> {
+        self.with_mount(|m| m.get_model()).await
+    }
+
+    async fn is_aligned(&self) -> Result<bool, MountError> {
+        self.with_mount(|m| m.is_aligned()).await
+    }
+
+    async fn goto_in_progress(&self) -> Result<bool, MountError> {
+        self.with_mount(|m| m.goto_in_progress()).await
+    }
+
+    async fn cancel_goto(&self) -> Result<(), MountError> {
+        self.with_mount(|m| m.cancel_goto()).await
+    }
+
+    async fn emergency_stop(&self) -> Result<(), MountError> {
+        self.with_mount(|m| m.emergency_stop()).await
+    }
+}
+
+/// A Celestron hand control on an async serial port; see [`asynchronous`](self).
+///
+/// Commands are written and their responses awaited on the port without blocking a thread. Calls made at the same time
+/// take turns. Any other async stream, such as a TCP connection to a WiFi module, can stand in for the port; see
+/// [`CelestronMountAsync::from_port`].
+#[derive(Debug)]
+pub struct CelestronMountAsync<P = SerialStream> {
+    link: tokio::sync::Mutex<Link<P>>,
+    timeout: Duration,
+}
+
+/// The port and what has been learned over it, locked for a whole command.
+#[derive(Debug)]
+struct Link<P> {
+    port: P,
+    recv: Vec<u8>,
+    /// The hand control's firmware version, read before the first coordinate command.
+    version: Option<(u8, u8)>,
+    /// Whether the rest of a late response may still arrive.
+    stale: bool,
+}
+
+impl CelestronMountAsync {
+    /// Detects the hand control as [`CelestronMount::detect_port`] does, and opens it.
+    pub async fn connect() -> Result<CelestronMountAsync, io::Error> {
+        let port = spawn(CelestronMount::detect_port).await?;
+        CelestronMountAsync::open(&port, DEFAULT_TIMEOUT)
+    }
+
+    /// Opens the hand control on serial port `port`, waiting `timeout` for each response.
+    pub fn open(port: &str, timeout: Duration) -> Result<CelestronMountAsync, io::Error> {
+        let stream = tokio_serial::new(port, DEFAULT_BAUD)
+            .timeout(timeout)
+            .stop_bits(tokio_serial::StopBits::One)
+            .parity(tokio_serial::Parity::None)
+            .open_native_async()
+            .map_err(|e| diagnose::diagnose(port, e.into()))?;
+        Ok(CelestronMountAsync::from_port(stream, timeout))
+    }
+}
+
+impl<P: AsyncRead + AsyncWrite + Unpin + Send> CelestronMountAsync<P> {
+    /// Uses an already open stream to the hand control.
+    pub fn from_port(port: P, timeout: Duration) -> CelestronMountAsync<P> {
+        CelestronMountAsync {
+            link: tokio::sync::Mutex::new(Link {
+                port,
+                recv: Vec::with_capacity(32),
+                version: None,
+                stale: false,
+            }),
+            timeout,
+        }
+    }
+
+    /// Gets the version of the hand controller's firmware.
+    pub async fn get_version(&self) -> Result<String, MountError> {
+        let mut link = self.link.lock().await;
+        self.version(&mut link).await
+    }
+
+    async fn version(&self, link: &mut Link<P>) -> Result<String, MountError> {
+        let version = codec::decode_version(self.read_handcontrol(link, b'V').await?)?;
+        link.version = support::parse_version(&version);
+        Ok(version)
+    }
+
+    /// Whether to send the precise coordinate command `precise` rather than its low precision counterpart `low`, as
+    /// a [`CelestronMount`] in [`PrecisionMode::Auto`](super::PrecisionMode::Auto) decides.
+    async fn precise(&self, link: &mut Link<P>, precise: u8, low: u8) -> Result<bool, MountError> {
+        if link.version.is_none() {
+            self.version(link).await?;
+        }
+        let Some(version) = link.version else {
+            return Ok(true);
+        };
+        let supports =
+            |cmd| support::required_version(Command::HandControl(cmd)).is_none_or(|v| v <= version);
+        Ok(supports(precise) || !supports(low))
+    }
+
+    /// Sends `cmd` and returns its response without the terminating '#'.
+    async fn read_handcontrol<'a>(
+        &self,
+        link: &'a mut Link<P>,
+        cmd: u8,
+    ) -> Result<&'a [u8], MountError> {
+        let len = self
+            .transact(link, &[cmd], Framing::of_handcontrol(cmd))
+            .await?;
+        Ok(&link.recv[..len - 1])
+    }
+
+    /// Sends `cmd`, whose response is only the '#'.
+    async fn write(&self, cmd: &[u8]) -> Result<(), MountError> {
+        let mut link = self.link.lock().await;
+        self.transact(&mut link, cmd, Framing::Terminator).await?;
+        Ok(())
+    }
+
+    /// Writes a command and reads its response into `link.recv`, returning its length.
+    ///
+    /// Reading stops as soon as the response is complete according to `framing`, or fails once the timeout passes.
+    async fn transact(
+        &self,
+        link: &mut Link<P>,
+        cmd: &[u8],
+        framing: Framing,
+    ) -> Result<usize, MountError> {
+        let command = Command::of(cmd);
+        support::check(command, link.version, None)?;
+        let mut chunk = [0; 32];
+
+        if std::mem::take(&mut link.stale) {
+            // Discard the late response to an earlier command.
+            while let Ok(Ok(1..)) = timeout(Duration::ZERO, link.port.read(&mut chunk)).await {}
+        }
+        link.recv.clear();
+        let deadline = Instant::now() + self.timeout;
+        timeout_at(deadline, link.port.write_all(cmd))
+            .await
+            .map_err(|_| MountError::Timeout)??;
+
+        while !framing.is_complete(&link.recv) {
+            let n = match timeout_at(deadline, link.port.read(&mut chunk)).await {
+                Ok(res) => res?,
+                Err(_) => 0,
+            };
+            if n == 0 {
+                if let Some(e) = codec::decode_error(&link.recv, framing) {
+                    return Err(e.into());
+                }
+                if link.recv.is_empty() {
+                    return Err(MountError::Timeout);
+                }
+                link.stale = true;
+                return Err(invalid_response(command, &link.recv));
+            }
+            link.recv.extend_from_slice(&chunk[..n]);
+        }
+
+        if link.recv.last() != Some(&b'#') {
+            return Err(invalid_response(command, &link.recv));
+        }
+        if let Some(e) = codec::decode_error(&link.recv, framing) {
+            return Err(e.into());
+        }
+        Ok(link.recv.len())
+    }
+}
+
+fn invalid_response(command: Command, bytes: &[u8]) -> MountError {
+    MountError::InvalidResponse {
+        cmd: Some(command),
+        bytes: bytes.to_vec(),
+    }
+}
+
+impl<P: AsyncRead + AsyncWrite + Unpin + Send> AsyncMount for CelestronMountAsync<P> {
+    async fn get_position_ra_dec(&self) -> Result<RADec, MountError> {
+        let mut link = self.link.lock().await;
+        let cmd = if self.precise(&mut link, b'e', b'E').await? {
+            b'e'
+        } else {
+            b'E'
+        };
+        Ok(codec::decode_ra_dec(
+            self.read_handcontrol(&mut link, cmd).await?,
+        )?)
+    }
+
+    async fn get_position_az_el(&self) -> Result<AzEl, MountError> {
+        let mut link = self.link.lock().await;
+        let cmd = if self.precise(&mut link, b'z', b'Z').await? {
+            b'z'
+        } else {
+            b'Z'
+        };
+        Ok(codec::decode_az_el(
+            self.read_handcontrol(&mut link, cmd).await?,
+        )?)
+    }
+
+    async fn goto_ra_dec(&self, coord: RADec) -> Result<(), MountError> {
+        check_finite(coord.is_finite(), coord)?;
+        let mut link = self.link.lock().await;
+        let msg = if self.precise(&mut link, b'r', b'R').await? {
+            codec::goto_ra_dec(coord)
+        } else {
+            codec::goto_ra_dec_low(coord)
+        };
+        self.transact(&mut link, msg.as_bytes(), Framing::Terminator)
+            .await?;
+        Ok(())
+    }
+
+    async fn goto_az_el(&self, coord: AzEl) -> Result<(), MountError> {
+        check_finite(coord.is_finite(), coord)?;
+        let mut link = self.link.lock().await;
+        let msg = if self.precise(&mut link, b'b', b'B').await? {
+            codec::goto_az_el(coord)
+        } else {
+            codec::goto_az_el_low(coord)
+        };
+        self.transact(&mut link, msg.as_bytes(), Framing::Terminator)
+            .await?;
+        Ok(())
+    }
+
+    async fn sync(&self, coord: RADec) -> Result<(), MountError> {
+        check_finite(coord.is_finite(), coord)?;
+        let mut link = self.link.lock().await;
+        let msg = if self.precise(&mut link, b's', b'S').await? {
+            codec::sync(coord)
+        } else {
+            codec::sync_low(coord)
+        };
+        self.transact(&mut link, msg.as_bytes(), Framing::Terminator)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_tracking_mode(&self) -> Result<TrackingMode, MountError> {
+        let mut link = self.link.lock().await;
+        codec::decode_tracking_mode(self.read_handcontrol(&mut link, b't').await?)
+    }
+
+    async fn set_tracking_mode(&self, mode: TrackingMode) -> Result<(), MountError> {
+        self.write(codec::set_tracking_mode(mode).as_bytes()).await
+    }
+
+    async fn slew_variable(
+        &self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: u16,
+    ) -> Result<(), MountError> {
+        self.write(&codec::slew_variable(axis, dir, rate)).await
+    }
+
+    async fn slew_fixed(
+        &self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        self.write(&codec::slew_fixed(axis, dir, rate)).await
+    }
+
+    async fn stop_slew(&self, axis: SlewAxis) -> Result<(), MountError> {
+        self.slew_variable(axis, SlewDir::Positive, 0).await
+    }
+
+    async fn get_time(&self) -> Result<DateTime<Utc>, MountError> {
+        let mut link = self.link.lock().await;
+        codec::decode_time(self.read_handcontrol(&mut link, b'h').await?)
+    }
+
+    /// Sets the current time on the mount, in the time zone it is already set to.
+    async fn set_time(&self, time: DateTime<Utc>) -> Result<(), MountError> {
+        let mut link = self.link.lock().await;
+        let zone = codec::decode_time_zone(self.read_handcontrol(&mut link, b'h').await?)?;
+        self.transact(
+            &mut link,
+            codec::set_time(time, zone).as_bytes(),
+            Framing::Terminator,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn get_model(&self) -> Result<Model, MountError> {
+        let mut link = self.link.lock().await;
+        codec::decode_model(self.read_handcontrol(&mut link, b'm').await?)
+    }
+
+    async fn is_aligned(&self) -> Result<bool, MountError> {
+        let mut link = self.link.lock().await;
+        codec::decode_aligned(self.read_handcontrol(&mut link, b'J').await?)
+    }
+
+    async fn goto_in_progress(&self) -> Result<bool, MountError> {
+        let mut link = self.link.lock().await;
+        codec::decode_goto_in_progress(self.read_handcontrol(&mut link, b'L').await?)
+    }
+
+    async fn cancel_goto(&self) -> Result<(), MountError> {
+        self.write(codec::cancel_goto().as_bytes()).await
+    }
+
+    async fn emergency_stop(&self) -> Result<(), MountError> {
+        log::warn!("Emergency stop.");
+        let results = [
+            self.cancel_goto().await,
+            self.stop_slew(SlewAxis::RAAz).await,
+            self.stop_slew(SlewAxis::DecEl).await,
+            self.set_tracking_mode(TrackingMode::Off).await,
+        ];
+        results.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::SimPort;
+    use crate::mount::SimMount;
+    use std::io::{Read, Write};
+    use tokio::io::DuplexStream;
+
+    /// One end of an in-memory stream with the hand control emulated by `port` on the other.
+    fn sim_link(mut port: SimPort) -> DuplexStream {
+        let (client, mut server) = tokio::io::duplex(64);
+        tokio::spawn(async move {
+            let mut buf = [0; 64];
+            while let Ok(n @ 1..) = server.read(&mut buf).await {
+                port.write_all(&buf[..n]).unwrap();
+                while let Ok(n) = port.read(&mut buf) {
+                    server.write_all(&buf[..n]).await.unwrap();
+                }
+            }
+        });
+        client
+    }
+
+    #[tokio::test]
+    async fn goto_completes_without_blocking() {
+        let mount = MountAsync::new(SimMount::new().max_rate(500.0).acceleration(5000.0));
+        let target = RADec::new(30.0, 40.0);
+        mount.goto_ra_dec(target).await.unwrap();
+        assert!(mount.goto_in_progress().await.unwrap());
+
+        // Other tasks keep running while the goto is awaited.
+        let ticker = tokio::spawn(async {
+            sleep(Duration::from_millis(10)).await;
+        });
+        mount.wait_goto_complete(None).await.unwrap();
+        ticker.await.unwrap();
+
+        let pos = mount.get_position_ra_dec().await.unwrap();
+        assert!(
+            (pos.ra - target.ra).abs() < 0.1 && (pos.dec - target.dec).abs() < 0.1,
+            "{pos}"
+        );
+    }
+
+    #[tokio::test]
+    async fn celestron_over_async_port() {
+        let sim = SimMount::new().max_rate(500.0).acceleration(5000.0);
+        let mount =
+            CelestronMountAsync::from_port(sim_link(SimPort::new(sim)), Duration::from_secs(1));
+        assert!(mount.is_aligned().await.unwrap());
+        mount
+            .set_tracking_mode(TrackingMode::EQNorth)
+            .await
+            .unwrap();
+        assert_eq!(
+            mount.get_tracking_mode().await.unwrap(),
+            TrackingMode::EQNorth
+        );
+
+        let target = RADec::new(30.0, 40.0);
+        mount.goto_and_wait(target, None).await.unwrap();
+        let pos = mount.get_position_ra_dec().await.unwrap();
+        assert!(
+            (pos.ra - target.ra).abs() < 0.1 && (pos.dec - target.dec).abs() < 0.1,
+            "{pos}"
+        );
+    }
+
+    #[tokio::test]
+    async fn silent_port_times_out() {
+        let (client, _server) = tokio::io::duplex(64);
+        let mount = CelestronMountAsync::from_port(client, Duration::from_millis(20));
+        let e = mount.goto_in_progress().await.unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,151 @@
+//! Connecting to a mount on a chosen port.
+//!
+//! [`CelestronMount::new`] looks for the Celestron USB adapter by its USB IDs and otherwise probes every port, which
+//! picks the wrong device with several mounts attached and takes a while with many ports. [`MountBuilder`] opens a
+//! named port instead, with the baud rate and timeout to use:
+//!
+//! ```no_run
+//! use nexlib::CelestronMount;
+//! use std::time::Duration;
+//!
+//! let mount = CelestronMount::builder()
+//!     .port("/dev/ttyUSB0")
+//!     .baud(9600)
+//!     .timeout(Duration::from_secs(2))
+//!     .open()
+//!     .unwrap();
+//! ```
+//!
+//! A `host:port` address, such as a WiFi module's, connects over TCP instead, ignoring the baud rate. Without a port,
+//! the builder detects one as [`CelestronMount::new`] does.
+
+use super::latency::AdaptiveTimeout;
+use super::transport;
+use super::{
+    CelestronMount, CommsConfig, PrecisionMode, DEFAULT_BAUD, DEFAULT_MAX_RESPONSE, DEFAULT_TIMEOUT,
+};
+use log::debug;
+use std::io;
+use std::time::Duration;
+
+/// Settings for opening a [`CelestronMount`]; see [`builder`](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountBuilder {
+    port: Option<String>,
+    baud: u32,
+    timeout: Duration,
+    max_response: usize,
+    adaptive: Option<AdaptiveTimeout>,
+    comms: Option<CommsConfig>,
+    precision: PrecisionMode,
+    verify: bool,
+}
+
+impl Default for MountBuilder {
+    fn default() -> Self {
+        MountBuilder {
+            port: None,
+            baud: DEFAULT_BAUD,
+            timeout: DEFAULT_TIMEOUT,
+            max_response: DEFAULT_MAX_RESPONSE,
+            adaptive: None,
+            comms: None,
+            precision: PrecisionMode::Auto,
+            verify: false,
+        }
+    }
+}
+
+impl MountBuilder {
+    /// Sets the serial port, e.g. `/dev/ttyUSB0` or `COM3`, instead of detecting it, or the `host:port` address of a
+    /// WiFi module, e.g. `1.2.3.4:2000`.
+    pub fn port(mut self, port: impl Into<String>) -> MountBuilder {
+        self.port = Some(port.into());
+        self
+    }
+
+    /// Sets the baud rate, 9600 by default.
+    pub fn baud(mut self, baud: u32) -> MountBuilder {
+        self.baud = baud;
+        self
+    }
+
+    /// Sets the time to wait for a response, 3.5 s by default.
+    pub fn timeout(mut self, timeout: Duration) -> MountBuilder {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the longest response to accept; see [`CelestronMount::set_max_response`].
+    pub fn max_response(mut self, max: usize) -> MountBuilder {
+        self.max_response = max;
+        self
+    }
+
+    /// Enables adaptive response timeouts; see [`AdaptiveTimeout`].
+    pub fn adaptive_timeout(mut self, adaptive: AdaptiveTimeout) -> MountBuilder {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Sets the timeouts, retries, and pacing of commands, overriding [`timeout`](Self::timeout) once the port is
+    /// open; see [`CommsConfig`].
+    pub fn comms(mut self, comms: CommsConfig) -> MountBuilder {
+        self.comms = Some(comms);
+        self
+    }
+
+    /// Chooses between the precise and low precision coordinate commands; see [`PrecisionMode`].
+    pub fn precision(mut self, precision: PrecisionMode) -> MountBuilder {
+        self.precision = precision;
+        self
+    }
+
+    /// Checks that a hand control answers on the port before returning, failing otherwise.
+    pub fn verify(mut self, verify: bool) -> MountBuilder {
+        self.verify = verify;
+        self
+    }
+
+    /// Opens the port and connects to the mount.
+    ///
+    /// Failures to open the port carry a [`PortError`](super::diagnose::PortError) with the likely cause.
+    pub fn open(self) -> Result<CelestronMount, io::Error> {
+        let port = match self.port {
+            Some(port) => port,
+            None => CelestronMount::detect_port()?,
+        };
+        debug!("Opening {port} at {} baud.", self.baud);
+        let mut mount = CelestronMount::from_port(transport::open_at(&port, self.baud, self.timeout)?);
+        mount.set_max_response(self.max_response);
+        mount.set_adaptive_timeout(self.adaptive);
+        if let Some(comms) = self.comms {
+            mount.set_comms(comms)?;
+        }
+        mount.set_precision(self.precision);
+        if self.verify {
+            mount.ping()?;
+        }
+        Ok(mount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::diagnose::PortError;
+
+    #[test]
+    fn opens_the_named_port() {
+        let builder = CelestronMount::builder()
+            .port("/dev/nexlib-missing")
+            .baud(19200)
+            .timeout(Duration::from_millis(100));
+        assert_eq!(builder.baud, 19200);
+        assert_eq!(builder.max_response, DEFAULT_MAX_RESPONSE);
+
+        let e = builder.open().unwrap_err();
+        let port_error = e.get_ref().unwrap().downcast_ref::<PortError>().unwrap();
+        assert_eq!(port_error.port.as_deref(), Some("/dev/nexlib-missing"));
+    }
+}
@@ -0,0 +1,763 @@
+//! NexStar protocol encoding and decoding, free of I/O.
+//!
+//! Every function here turns values into the bytes of a command, or the bytes of a response into values, without
+//! touching a port. [`CelestronMount`](super::CelestronMount) pairs them with its serial transport; the simulator's
+//! [`SimPort`](super::sim::SimPort) uses the same functions from the other end of the link, and other transports or
+//! targets without a serial port can reuse them directly.
+//!
+//! Responses are passed to decoders without their terminating `#`, except where noted.
+//!
+//! ```
+//! use nexlib::mount::codec;
+//! use nexlib::RADec;
+//!
+//! assert_eq!(codec::goto_ra_dec(RADec::new(83.8, -5.4)).as_bytes(), b"r3B97530E,FC28F5C3");
+//! let pos = codec::decode_ra_dec(b"3B98C700,FC28F500").unwrap();
+//! assert!((pos.dec + 5.4).abs() < 1e-4);
+//! ```
+
+use super::latency::Command;
+use super::{
+    AzEl, Location, Model, RADec, SlewAxis, SlewDir, SlewRate, TimeZoneSetting, TrackingMode, TrackingRate,
+};
+use super::error::{MountError, ParseError};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Timelike, Utc};
+use std::error::Error;
+use std::{fmt, io};
+
+/// One revolution in the 32-bit angle format of the precise commands.
+const REV: f64 = 4_294_967_296.0;
+
+/// One revolution in the 24-bit angle format of the motor controllers.
+const MOTOR_REV: f64 = 16_777_216.0;
+
+/// Motor controller command reading the axis position.
+pub const MC_GET_POSITION: u8 = 0x01;
+
+/// Motor controller command reading the firmware version.
+pub const MC_GET_VERSION: u8 = 254;
+
+/// Motor controller command turning the axis at a rate in the positive direction, as variable slews and tracking do.
+pub const MC_SET_POS_GUIDERATE: u8 = 0x06;
+
+/// Motor controller command turning the axis at a rate in the negative direction.
+pub const MC_SET_NEG_GUIDERATE: u8 = 0x07;
+
+/// Motor controller command starting a periodic error recording.
+pub const MC_PEC_RECORD_START: u8 = 0x0C;
+
+/// Motor controller command starting (argument 1) or stopping (argument 0) periodic error playback.
+pub const MC_PEC_PLAYBACK: u8 = 0x0D;
+
+/// Motor controller command reading 0xFF once a periodic error recording has finished.
+pub const MC_PEC_RECORD_DONE: u8 = 0x15;
+
+/// Motor controller command stopping a periodic error recording.
+pub const MC_PEC_RECORD_STOP: u8 = 0x16;
+
+/// Motor controller command reading 0xFF once the worm gear's index mark has been found.
+pub const MC_AT_INDEX: u8 = 0x18;
+
+/// Motor controller command seeking the worm gear's index mark.
+pub const MC_SEEK_INDEX: u8 = 0x19;
+
+/// Motor controller command starting a guide pulse.
+pub const MC_AUX_GUIDE: u8 = 0x26;
+
+/// Motor controller command reading whether a guide pulse is in progress.
+pub const MC_IS_AUX_GUIDE_ACTIVE: u8 = 0x27;
+
+/// Motor controller command setting the autoguide rate.
+pub const MC_SET_AUTOGUIDE_RATE: u8 = 0x46;
+
+/// Motor controller command reading the autoguide rate.
+pub const MC_GET_AUTOGUIDE_RATE: u8 = 0x47;
+
+/// Fastest tracking rate the motors take, in arcseconds/second.
+pub const MAX_TRACKING_RATE: f64 = 0xFF_FFFF as f64 / 1024.0;
+
+/// Longest guide pulse one command can give.
+pub const MAX_GUIDE_PULSE: std::time::Duration = std::time::Duration::from_millis(2550);
+
+fn invalid(res: &[u8]) -> MountError {
+    MountError::InvalidResponse {
+        cmd: None,
+        bytes: res.to_vec(),
+    }
+}
+
+/// Converts degrees to the 32-bit fraction of a revolution used by the precise commands.
+pub fn encode_angle(deg: f64) -> u32 {
+    ((deg / 360.0) * REV) as i64 as u32
+}
+
+/// Converts degrees to the 16-bit fraction of a revolution used by the low precision commands.
+pub fn encode_low_angle(deg: f64) -> u16 {
+    ((deg / 360.0) * 65_536.0).round() as i64 as u16
+}
+
+/// Converts a 32-bit fraction of a revolution to degrees in [0, 360).
+pub fn decode_angle(value: u32) -> f64 {
+    value as f64 / REV * 360.0
+}
+
+/// Converts a 32-bit fraction of a revolution to degrees in [-180, 180), for declination and elevation.
+pub fn decode_signed_angle(value: u32) -> f64 {
+    value as i32 as f64 / REV * 360.0
+}
+
+/// Converts a 24-bit motor position to degrees in [0, 360).
+pub fn decode_motor_angle(value: u32) -> f64 {
+    (value & 0xFF_FFFF) as f64 / MOTOR_REV * 360.0
+}
+
+/// Converts degrees to a 24-bit motor position.
+pub fn encode_motor_angle(deg: f64) -> u32 {
+    (deg.rem_euclid(360.0) / 360.0 * MOTOR_REV) as u32 & 0xFF_FFFF
+}
+
+/// Converts a variable slew rate in arcseconds/second to the high and low bytes the motors expect, which count
+/// quarter arcseconds per second.
+pub fn encode_slew_rate(rate: u16) -> [u8; 2] {
+    ((rate as u32 * 4).min(u16::MAX as u32) as u16).to_be_bytes()
+}
+
+/// How the end of a response is recognized.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Framing {
+    /// The response ends at a '#'. Only for responses whose data never contains '#'.
+    Terminator,
+    /// The response is at least this many bytes, including the '#'. For binary data, which may itself contain '#'.
+    Length(usize),
+}
+
+impl Framing {
+    /// Framing of the response to a hand control command.
+    pub fn of_handcontrol(cmd: u8) -> Framing {
+        match cmd {
+            b'V' => Framing::Length(3),
+            b'h' | b'w' => Framing::Length(9),
+            b'm' | b'J' | b't' | b'L' | b'K' => Framing::Length(2),
+            _ => Framing::Terminator,
+        }
+    }
+
+    /// Whether `buf` holds a complete response.
+    pub fn is_complete(&self, buf: &[u8]) -> bool {
+        match self {
+            Framing::Terminator => buf.last() == Some(&b'#'),
+            Framing::Length(len) => buf.len() >= *len,
+        }
+    }
+}
+
+/// An error reported by the hand control in place of a response.
+///
+/// Hand controls which report failures answer `!` and a code digit before the `#`. Mount methods return it as a
+/// [`MountError`](super::error::MountError).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NexError {
+    /// Code 0: the command is not known to the firmware.
+    UnknownCommand,
+    /// Codes 1 and 3: the command was the wrong length or had an invalid argument.
+    InvalidArgument,
+    /// Code 2: a motor is still moving.
+    DeviceBusy,
+    /// Code 4: the mount has not been aligned.
+    NotAligned,
+    /// Any other code.
+    Other(u8),
+}
+
+impl NexError {
+    pub fn from_code(code: u8) -> NexError {
+        match code {
+            0 => NexError::UnknownCommand,
+            1 | 3 => NexError::InvalidArgument,
+            2 => NexError::DeviceBusy,
+            4 => NexError::NotAligned,
+            code => NexError::Other(code),
+        }
+    }
+
+    /// The kind of `io::Error` the error is returned as.
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            NexError::UnknownCommand => io::ErrorKind::Unsupported,
+            NexError::InvalidArgument => io::ErrorKind::InvalidInput,
+            NexError::DeviceBusy => io::ErrorKind::ResourceBusy,
+            NexError::NotAligned | NexError::Other(_) => io::ErrorKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for NexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NexError::UnknownCommand => write!(
+                f,
+                "The hand control does not know the command; its firmware may be too old."
+            ),
+            NexError::InvalidArgument => {
+                write!(f, "The hand control rejected the command's arguments.")
+            }
+            NexError::DeviceBusy => write!(
+                f,
+                "The mount is busy; wait for it to stop moving and try again."
+            ),
+            NexError::NotAligned => write!(
+                f,
+                "The mount is not aligned; align it from the hand control first."
+            ),
+            NexError::Other(code) => write!(f, "The hand control reported error {code}."),
+        }
+    }
+}
+
+impl Error for NexError {}
+
+impl From<NexError> for io::Error {
+    fn from(e: NexError) -> io::Error {
+        io::Error::new(e.kind(), e)
+    }
+}
+
+/// Decodes an error reply including its `#`, if `res` is one rather than a response framed by `framing`.
+pub fn decode_error(res: &[u8], framing: Framing) -> Option<NexError> {
+    if framing == Framing::Length(res.len()) {
+        return None;
+    }
+    match res {
+        [b'!', code @ b'0'..=b'9', b'#'] => Some(NexError::from_code(code - b'0')),
+        _ => None,
+    }
+}
+
+/// A hand control command encoded on the stack, so commands sent in polling and guiding loops do not allocate.
+///
+/// Sized for the longest command, a goto or sync with two 8-digit hex positions.
+#[derive(Debug, Copy, Clone)]
+pub struct Message {
+    buf: [u8; 18],
+    len: usize,
+}
+
+impl Message {
+    /// Starts a command with its command character.
+    pub fn new(cmd: u8) -> Message {
+        Message {
+            buf: [0; 18],
+            len: 0,
+        }
+        .byte(cmd)
+    }
+
+    /// Appends a byte.
+    ///
+    /// # Panics
+    ///
+    /// If the message is full.
+    pub fn byte(mut self, byte: u8) -> Message {
+        self.buf[self.len] = byte;
+        self.len += 1;
+        self
+    }
+
+    /// Appends `value` as 8 uppercase hex digits.
+    pub fn hex(mut self, value: u32) -> Message {
+        const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+        for shift in (0..32).step_by(4).rev() {
+            self = self.byte(DIGITS[(value >> shift) as usize & 0xF]);
+        }
+        self
+    }
+
+    /// Appends `value` as 4 uppercase hex digits.
+    pub fn hex16(mut self, value: u16) -> Message {
+        const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+        for shift in (0..16).step_by(4).rev() {
+            self = self.byte(DIGITS[(value >> shift) as usize & 0xF]);
+        }
+        self
+    }
+
+    /// Appends a pair of angles as `XXXXXXXX,XXXXXXXX`.
+    pub fn position_pair(self, a: f64, b: f64) -> Message {
+        self.hex(encode_angle(a)).byte(b',').hex(encode_angle(b))
+    }
+
+    /// Appends a pair of low precision angles as `XXXX,XXXX`.
+    pub fn low_position_pair(self, a: f64, b: f64) -> Message {
+        self.hex16(encode_low_angle(a)).byte(b',').hex16(encode_low_angle(b))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+/// Goto right ascension and declination (precise). The coordinates are normalized first.
+pub fn goto_ra_dec(coord: RADec) -> Message {
+    let coord = coord.normalized();
+    Message::new(b'r').position_pair(coord.ra, coord.dec)
+}
+
+/// Goto azimuth and elevation (precise). The coordinates are normalized first.
+pub fn goto_az_el(coord: AzEl) -> Message {
+    let coord = coord.normalized();
+    Message::new(b'b').position_pair(coord.az, coord.el)
+}
+
+/// Sync to right ascension and declination (precise). The coordinates are normalized first.
+pub fn sync(coord: RADec) -> Message {
+    let coord = coord.normalized();
+    Message::new(b's').position_pair(coord.ra, coord.dec)
+}
+
+/// Goto right ascension and declination (low precision, for firmware before 1.6).
+pub fn goto_ra_dec_low(coord: RADec) -> Message {
+    let coord = coord.normalized();
+    Message::new(b'R').low_position_pair(coord.ra, coord.dec)
+}
+
+/// Goto azimuth and elevation (low precision, for firmware before 2.2).
+pub fn goto_az_el_low(coord: AzEl) -> Message {
+    let coord = coord.normalized();
+    Message::new(b'B').low_position_pair(coord.az, coord.el)
+}
+
+/// Sync to right ascension and declination (low precision).
+pub fn sync_low(coord: RADec) -> Message {
+    let coord = coord.normalized();
+    Message::new(b'S').low_position_pair(coord.ra, coord.dec)
+}
+
+pub fn set_tracking_mode(mode: TrackingMode) -> Message {
+    Message::new(b'T').byte(mode as u8)
+}
+
+pub fn cancel_goto() -> Message {
+    Message::new(b'M')
+}
+
+/// Set location.
+pub fn set_location(location: Location) -> Message {
+    encode_location(location)
+        .into_iter()
+        .fold(Message::new(b'W'), Message::byte)
+}
+
+/// Set time, from `time` in UTC and the hand control's time zone.
+pub fn set_time(time: DateTime<Utc>, zone: TimeZoneSetting) -> Message {
+    encode_time(time, zone)
+        .into_iter()
+        .fold(Message::new(b'H'), Message::byte)
+}
+
+/// Echo, which the hand control answers with `byte`.
+pub fn echo(byte: u8) -> Message {
+    Message::new(b'K').byte(byte)
+}
+
+/// A passthrough command to device `dev`, with up to 3 argument bytes, expecting `resp_len` bytes of data back.
+pub fn passthrough(dev: u8, cmd: u8, args: &[u8], resp_len: usize) -> Result<[u8; 8], io::Error> {
+    if args.len() > 3 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Command arguments must be 3 bytes or less. {:?}", args),
+        ));
+    }
+
+    let mut msg = [
+        b'P',
+        args.len() as u8 + 1,
+        dev,
+        cmd,
+        0,
+        0,
+        0,
+        resp_len as u8,
+    ];
+    msg[4..4 + args.len()].copy_from_slice(args);
+    Ok(msg)
+}
+
+/// Device address of the motor turning `axis`.
+pub fn motor(axis: SlewAxis) -> u8 {
+    match axis {
+        SlewAxis::RAAz => 16,
+        SlewAxis::DecEl => 17,
+    }
+}
+
+/// Variable rate slew, in arcseconds/second.
+pub fn slew_variable(axis: SlewAxis, dir: SlewDir, rate: u16) -> [u8; 8] {
+    let cmd = match dir {
+        SlewDir::Positive => 6,
+        SlewDir::Negative => 7,
+    };
+    passthrough(motor(axis), cmd, &encode_slew_rate(rate), 0).unwrap()
+}
+
+/// Fixed rate slew.
+pub fn slew_fixed(axis: SlewAxis, dir: SlewDir, rate: SlewRate) -> [u8; 8] {
+    let cmd = match dir {
+        SlewDir::Positive => 36,
+        SlewDir::Negative => 37,
+    };
+    passthrough(motor(axis), cmd, &[rate as u8], 0).unwrap()
+}
+
+/// Encodes a guide rate, as a fraction of the sidereal rate, in 256ths up to 255.
+pub fn encode_guide_rate(rate: f64) -> u8 {
+    (rate * 256.0).round().clamp(0.0, 255.0) as u8
+}
+
+pub fn decode_guide_rate(byte: u8) -> f64 {
+    byte as f64 / 256.0
+}
+
+/// Set the autoguide rate of `axis`, as a fraction of the sidereal rate.
+pub fn set_guide_rate(axis: SlewAxis, rate: f64) -> [u8; 8] {
+    passthrough(motor(axis), MC_SET_AUTOGUIDE_RATE, &[encode_guide_rate(rate)], 0).unwrap()
+}
+
+/// Guide pulse on `axis` at `percent` of the sidereal rate, negative for the negative direction, lasting
+/// `centiseconds`.
+pub fn aux_guide(axis: SlewAxis, percent: i8, centiseconds: u8) -> [u8; 8] {
+    passthrough(motor(axis), MC_AUX_GUIDE, &[percent as u8, centiseconds], 0).unwrap()
+}
+
+/// Tracking rate of `axis` in arcseconds/second, negative for the negative direction, in the 24-bit form the motors
+/// take, which counts 1/1024 arcseconds per second.
+pub fn tracking_rate(axis: SlewAxis, arcsec_per_sec: f64) -> [u8; 8] {
+    let cmd = if arcsec_per_sec < 0.0 {
+        MC_SET_NEG_GUIDERATE
+    } else {
+        MC_SET_POS_GUIDERATE
+    };
+    let rate = ((arcsec_per_sec.abs() * 1024.0).round() as u32).min(0xFF_FFFF);
+    passthrough(motor(axis), cmd, &rate.to_be_bytes()[1..], 0).unwrap()
+}
+
+/// Right ascension tracking at a rate the motor controller knows by a special 16-bit value, or `None` for rates it
+/// does not know.
+pub fn tracking_preset(dir: SlewDir, rate: TrackingRate) -> Option<[u8; 8]> {
+    let code = match rate {
+        TrackingRate::Sidereal => 0xFF,
+        TrackingRate::Solar => 0xFE,
+        TrackingRate::Lunar => 0xFD,
+        TrackingRate::King => return None,
+    };
+    let cmd = match dir {
+        SlewDir::Positive => MC_SET_POS_GUIDERATE,
+        SlewDir::Negative => MC_SET_NEG_GUIDERATE,
+    };
+    Some(passthrough(motor(SlewAxis::RAAz), cmd, &[0xFF, code], 0).unwrap())
+}
+
+/// Checks a passthrough response including its `#`, returning its data.
+///
+/// Fails with `NotConnected` if the device did not answer, which the hand control signals with an extra byte.
+pub fn decode_passthrough(
+    dev: u8,
+    cmd: u8,
+    res: &[u8],
+    resp_len: usize,
+) -> Result<&[u8], MountError> {
+    if res.last() != Some(&b'#') {
+        return Err(invalid(res));
+    }
+
+    if res.len() == resp_len + 1 {
+        Ok(&res[..resp_len])
+    } else if res.len() == resp_len + 2 {
+        Err(MountError::DeviceUnavailable(dev))
+    } else {
+        Err(MountError::InvalidResponse {
+            cmd: Some(Command::Passthrough { device: dev, command: cmd }),
+            bytes: res.to_vec(),
+        })
+    }
+}
+
+/// Decodes a `XXXXXXXX,XXXXXXXX` pair of precise angles, or a `XXXX,XXXX` pair of low precision angles scaled to
+/// 32 bits.
+pub fn decode_position_pair(res: &[u8]) -> Result<(u32, u32), ParseError> {
+    let (digits, shift) = match res.len() {
+        17 => (8, 0),
+        9 => (4, 16),
+        _ => return Err(ParseError::Length(res.to_vec())),
+    };
+    let Ok(text) = std::str::from_utf8(res) else {
+        return Err(ParseError::NotUtf8(res.to_vec()));
+    };
+    let hex = |s: &str| {
+        if s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            u32::from_str_radix(s, 16).ok()
+        } else {
+            None
+        }
+    };
+    match text.split_once(',') {
+        Some((a, b)) if a.len() == digits => match (hex(a), hex(b)) {
+            (Some(a), Some(b)) => Ok((a << shift, b << shift)),
+            _ => Err(ParseError::NotHex(res.to_vec())),
+        },
+        _ => Err(ParseError::NotHex(res.to_vec())),
+    }
+}
+
+/// Encodes a pair of precise angles as `XXXXXXXX,XXXXXXXX`.
+pub fn encode_position_pair(a: u32, b: u32) -> [u8; 17] {
+    let msg = Message::new(0).hex(a).byte(b',').hex(b);
+    msg.as_bytes()[1..].try_into().unwrap()
+}
+
+/// Encodes a pair of low precision angles as `XXXX,XXXX`.
+pub fn encode_low_position_pair(a: u16, b: u16) -> [u8; 9] {
+    let msg = Message::new(0).hex16(a).byte(b',').hex16(b);
+    msg.as_bytes()[1..].try_into().unwrap()
+}
+
+/// Decodes the response to `e` or `E`.
+pub fn decode_ra_dec(res: &[u8]) -> Result<RADec, ParseError> {
+    let (ra, dec) = decode_position_pair(res)?;
+    Ok(RADec::new(decode_angle(ra), decode_signed_angle(dec)))
+}
+
+/// Decodes the response to `z` or `Z`.
+pub fn decode_az_el(res: &[u8]) -> Result<AzEl, ParseError> {
+    let (az, el) = decode_position_pair(res)?;
+    Ok(AzEl::new(decode_angle(az), decode_signed_angle(el)))
+}
+
+/// Decodes a two byte major.minor firmware version.
+pub fn decode_version(res: &[u8]) -> Result<String, MountError> {
+    match res {
+        [major, minor] => Ok(format!("{major}.{minor}")),
+        _ => Err(invalid(res)),
+    }
+}
+
+pub fn decode_model(res: &[u8]) -> Result<Model, MountError> {
+    match res {
+        [id] => Model::try_from(*id),
+        _ => Err(invalid(res)),
+    }
+}
+
+pub fn decode_tracking_mode(res: &[u8]) -> Result<TrackingMode, MountError> {
+    match res {
+        [0] => Ok(TrackingMode::Off),
+        [1] => Ok(TrackingMode::AzEl),
+        [2] => Ok(TrackingMode::EQNorth),
+        [3] => Ok(TrackingMode::EQSouth),
+        _ => Err(invalid(res)),
+    }
+}
+
+/// Decodes the response to `J`.
+pub fn decode_aligned(res: &[u8]) -> Result<bool, MountError> {
+    match res {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(invalid(res)),
+    }
+}
+
+/// Decodes the response to `L`.
+pub fn decode_goto_in_progress(res: &[u8]) -> Result<bool, MountError> {
+    match res {
+        [b'0'] => Ok(false),
+        [b'1'] => Ok(true),
+        _ => Err(invalid(res)),
+    }
+}
+
+/// Decodes the response to `h`: local time, the standard time offset from UTC in hours, and whether daylight saving
+/// time is in effect.
+pub fn decode_time(res: &[u8]) -> Result<DateTime<Utc>, MountError> {
+    decode_local_time(res).map(|time| time.with_timezone(&Utc))
+}
+
+/// Decodes the response to `h` as the local time the hand control displays, with its UTC offset.
+pub fn decode_local_time(res: &[u8]) -> Result<DateTime<FixedOffset>, MountError> {
+    let [hour, min, sec, mon, day, year, ..] = *res else {
+        return Err(invalid(res));
+    };
+    let offset = decode_time_zone(res)?.offset();
+
+    NaiveDate::from_ymd_opt(year as i32 + 2000, mon as u32, day as u32)
+        .and_then(|date| date.and_hms_opt(hour as u32, min as u32, sec as u32))
+        .and_then(|local| local.and_local_timezone(offset).single())
+        .ok_or_else(|| invalid(res))
+}
+
+/// Decodes the time zone from the response to `h`.
+pub fn decode_time_zone(res: &[u8]) -> Result<TimeZoneSetting, MountError> {
+    match res {
+        [_, _, _, _, _, _, offset, dst] => Ok(TimeZoneSetting {
+            utc_offset: *offset as i8,
+            dst: *dst == 1,
+        }),
+        _ => Err(invalid(res)),
+    }
+}
+
+/// Encodes `time` as local time in `zone`, as in the response to `h` and the argument of `H`.
+pub fn encode_time(time: DateTime<Utc>, zone: TimeZoneSetting) -> [u8; 8] {
+    let time = time.with_timezone(&zone.offset());
+    [
+        time.hour() as u8,
+        time.minute() as u8,
+        time.second() as u8,
+        time.month() as u8,
+        time.day() as u8,
+        (time.year() - 2000) as u8,
+        zone.utc_offset as u8,
+        zone.dst as u8,
+    ]
+}
+
+/// Decodes the response to `w`: degrees, minutes, seconds, and hemisphere of the latitude, then of the longitude.
+pub fn decode_location(res: &[u8]) -> Result<Location, MountError> {
+    let [lat_d, lat_m, lat_s, south, lon_d, lon_m, lon_s, west] = res else {
+        return Err(invalid(res));
+    };
+
+    let angle = |d: u8, m: u8, s: u8, negative: u8| {
+        let deg = d as f64 + m as f64 / 60.0 + s as f64 / 3600.0;
+        if negative == 1 {
+            -deg
+        } else {
+            deg
+        }
+    };
+    Ok(Location {
+        latitude: angle(*lat_d, *lat_m, *lat_s, *south),
+        longitude: angle(*lon_d, *lon_m, *lon_s, *west),
+    })
+}
+
+/// Encodes a location as in the response to `w` and the argument of `W`, to the nearest arcsecond.
+pub fn encode_location(location: Location) -> [u8; 8] {
+    let angle = |deg: f64| {
+        let secs = (deg.abs() * 3600.0).round() as u32;
+        [
+            (secs / 3600) as u8,
+            (secs / 60 % 60) as u8,
+            (secs % 60) as u8,
+            (deg < 0.0) as u8,
+        ]
+    };
+    let [a, b, c, d] = angle(location.latitude);
+    let [e, f, g, h] = angle(location.longitude);
+    [a, b, c, d, e, f, g, h]
+}
+
+/// Decodes the response to MC_GET_POSITION in degrees.
+pub fn decode_motor_position(res: &[u8]) -> Result<f64, MountError> {
+    match res {
+        [a, b, c] => Ok(decode_motor_angle(u32::from_be_bytes([0, *a, *b, *c]))),
+        _ => Err(invalid(res)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            goto_az_el(AzEl::new(120.0, 45.0)).as_bytes(),
+            b"b55555555,20000000"
+        );
+        assert_eq!(
+            sync(RADec::new(279.2347, 38.7837)).as_bytes(),
+            b"sC6912036,1B945B6C"
+        );
+        assert_eq!(
+            goto_ra_dec_low(RADec::new(279.2347, 38.7837)).as_bytes(),
+            b"RC691,1B94"
+        );
+        assert_eq!(
+            goto_az_el_low(AzEl::new(120.0, -45.0)).as_bytes(),
+            b"B5555,E000"
+        );
+        assert_eq!(sync_low(RADec::new(-90.0, 0.0)).as_bytes(), b"SC000,0000");
+        assert_eq!(
+            set_tracking_mode(TrackingMode::EQNorth).as_bytes(),
+            b"T\x02"
+        );
+        assert_eq!(
+            slew_variable(SlewAxis::RAAz, SlewDir::Positive, 300),
+            [b'P', 3, 16, 6, 0x04, 0xB0, 0, 0]
+        );
+        assert_eq!(
+            slew_fixed(SlewAxis::DecEl, SlewDir::Negative, SlewRate::Rate9),
+            [b'P', 2, 17, 37, 9, 0, 0, 0]
+        );
+        assert!(passthrough(16, 1, &[0; 4], 0).is_err());
+    }
+
+    #[test]
+    fn responses() {
+        let pos = decode_az_el(b"8F258B00,F8E38E00").unwrap();
+        assert!((pos.az - 201.29998).abs() < 1e-4 && (pos.el + 10.0).abs() < 1e-4);
+        assert!(decode_ra_dec(b"3B98C700,FC28F5").is_err());
+        assert!(decode_ra_dec(b"3B98C700;FC28F500").is_err());
+        assert_eq!(
+            &encode_position_pair(0x3B98C700, 0xFC28F500),
+            b"3B98C700,FC28F500"
+        );
+
+        assert_eq!(decode_version(&[5, 35]).unwrap(), "5.35");
+        assert_eq!(decode_model(&[20]).unwrap(), Model::AdvancedVX);
+        assert!(decode_goto_in_progress(b"1").unwrap());
+
+        let t = decode_time(&[22, 30, 15, 3, 20, 24, 0xFB, 1]).unwrap();
+        assert_eq!(t, Utc.with_ymd_and_hms(2024, 3, 21, 2, 30, 15).unwrap());
+        let zone = decode_time_zone(&[22, 30, 15, 3, 20, 24, 0xFB, 1]).unwrap();
+        assert_eq!((zone.utc_offset, zone.dst), (-5, true));
+        assert_eq!(encode_time(t, zone), [22, 30, 15, 3, 20, 24, 0xFB, 1]);
+        assert_eq!(decode_time(&encode_time(t, zone)).unwrap(), t);
+
+        let site = Location {
+            latitude: 40.446,
+            longitude: -79.982,
+        };
+        assert_eq!(encode_location(site), [40, 26, 46, 0, 79, 58, 55, 1]);
+        let decoded = decode_location(&encode_location(site)).unwrap();
+        assert!(
+            (decoded.latitude - 40.446).abs() < 1e-3 && (decoded.longitude + 79.982).abs() < 1e-3
+        );
+        assert_eq!(
+            set_location(site).as_bytes(),
+            b"W\x28\x1a\x2e\x00\x4f\x3a\x37\x01"
+        );
+
+        assert_eq!(
+            decode_error(b"!2#", Framing::Terminator),
+            Some(NexError::DeviceBusy)
+        );
+        // A valid version response.
+        assert_eq!(decode_error(b"!2#", Framing::Length(3)), None);
+        assert_eq!(decode_error(b"!#", Framing::Terminator), None);
+
+        assert_eq!(
+            decode_passthrough(16, 254, &[7, 11, b'#'], 2).unwrap(),
+            &[7, 11]
+        );
+        assert_eq!(
+            decode_passthrough(178, 254, &[0, 0, 0, b'#'], 2)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotConnected
+        );
+        let pos = decode_motor_position(&encode_motor_angle(90.0).to_be_bytes()[1..]).unwrap();
+        assert!((pos - 90.0).abs() < 1e-4);
+    }
+}
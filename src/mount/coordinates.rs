@@ -1,33 +1,30 @@
-use std::str;
+use super::error::ParseError;
+use super::{codec, transform};
+use crate::astro::GeoLocation;
+use chrono::{DateTime, Utc};
 
 const REV: i64 = 0x100000000;
 
-/// Unpacks the data from a message into a u8 array.
-fn from_msg_to_i64(bytes: &[u8]) -> i64 {
-    let as_str = str::from_utf8(bytes).unwrap();
-    println!("String: {:?}", as_str);
-    i64::from_str_radix(as_str, 16).unwrap()
-}
-
-/// Converts Celestron integer angle format (from a message) to floating point degrees.
-fn from_i64_to_deg(pos: i64) -> f64 {
-    (pos as f64 / REV as f64) * 360.0
-}
-
 /// Converts floating point degrees to transmittable Celestron integer angle format.
 fn from_deg_to_i64(deg: f64) -> i64 {
     ((deg / 360.0) * REV as f64) as i64
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RADec {
     pub ra: f64,
     pub dec: f64,
 }
 
+/// Follows the process-wide [`units`](crate::units) preference. A precision applies to both coordinates.
 impl std::fmt::Display for RADec {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "({}, {})", self.ra, self.dec)
+        let units = crate::units::current();
+        match f.precision() {
+            Some(p) => write!(f, "({:.p$}, {:.p$})", units.ra(self.ra), units.dec(self.dec)),
+            None => write!(f, "({}, {})", units.ra(self.ra), units.dec(self.dec)),
+        }
     }
 }
 
@@ -36,30 +33,67 @@ impl RADec {
         RADec {ra, dec}
     }
 
-    pub fn from_msg(msg: &[u8]) -> RADec {
-        RADec::new(
-            from_i64_to_deg(from_msg_to_i64(&msg[0..8])),
-            from_i64_to_deg(from_msg_to_i64(&msg[9..=16])),
-        )
+    /// Decodes a precise position response, with or without its terminating `#`.
+    ///
+    /// Fails if the message is truncated, not UTF-8, or not a pair of hexadecimal angles.
+    pub fn from_msg(msg: &[u8]) -> Result<RADec, ParseError> {
+        codec::decode_ra_dec(msg.strip_suffix(b"#").unwrap_or(msg))
     }
 
-    pub fn ra_as_i64(&mut self) -> i64 {
+    pub fn ra_as_i64(&self) -> i64 {
         from_deg_to_i64(self.ra)
     }
 
-    pub fn dec_as_i64(&mut self) -> i64 {
+    pub fn dec_as_i64(&self) -> i64 {
         from_deg_to_i64(self.dec)
     }
+
+    /// Wraps right ascension into [0, 360) and clamps declination to [-90, 90].
+    pub fn normalized(&self) -> RADec {
+        RADec::new(self.ra.rem_euclid(360.0), self.dec.clamp(-90.0, 90.0))
+    }
+
+    /// Whether both coordinates are finite numbers, which [`normalized`](Self::normalized) cannot make of NaN.
+    pub fn is_finite(&self) -> bool {
+        self.ra.is_finite() && self.dec.is_finite()
+    }
+
+    /// Precesses and nutates J2000 catalog coordinates to coordinates of date at `time`, which the mount expects.
+    ///
+    /// See [`transform::j2000_to_jnow`].
+    pub fn j2000_to_jnow(&self, time: DateTime<Utc>) -> RADec {
+        transform::j2000_to_jnow(*self, time)
+    }
+
+    /// Converts coordinates of date at `time`, as reported by the mount, back to J2000.
+    pub fn jnow_to_j2000(&self, time: DateTime<Utc>) -> RADec {
+        transform::jnow_to_j2000(*self, time)
+    }
+
+    /// Azimuth and elevation of these coordinates of date as seen from `location` at `time`.
+    ///
+    /// Refraction, precession, and nutation are ignored; see [`transform`].
+    pub fn to_az_el(&self, location: &GeoLocation, time: DateTime<Utc>) -> AzEl {
+        let ha = transform::local_sidereal_time(time, location.longitude) - self.ra;
+        transform::ha_dec_to_az_el(ha, self.dec, location.latitude)
+    }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AzEl {
     pub az: f64,
     pub el: f64,
 }
 
+/// Follows the process-wide [`units`](crate::units) preference. A precision applies to both coordinates.
 impl std::fmt::Display for AzEl {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "({}, {})", self.az, self.el)
+        let units = crate::units::current();
+        match f.precision() {
+            Some(p) => write!(f, "({:.p$}, {:.p$})", units.az(self.az), units.dec(self.el)),
+            None => write!(f, "({}, {})", units.az(self.az), units.dec(self.el)),
+        }
     }
 }
 
@@ -68,24 +102,44 @@ impl AzEl {
         AzEl {az, el}
     }
 
-    pub fn from_msg(msg: &[u8]) -> AzEl {
-        AzEl::new(
-            from_i64_to_deg(from_msg_to_i64(&msg[0..8])),
-            from_i64_to_deg(from_msg_to_i64(&msg[9..=16])),
-        )
+    /// Decodes a precise position response, with or without its terminating `#`.
+    ///
+    /// Fails if the message is truncated, not UTF-8, or not a pair of hexadecimal angles.
+    pub fn from_msg(msg: &[u8]) -> Result<AzEl, ParseError> {
+        codec::decode_az_el(msg.strip_suffix(b"#").unwrap_or(msg))
     }
 
-    pub fn az_as_i64(&mut self) -> i64 {
+    pub fn az_as_i64(&self) -> i64 {
         from_deg_to_i64(self.az)
     }
 
-    pub fn el_as_i64(&mut self) -> i64 {
+    pub fn el_as_i64(&self) -> i64 {
         from_deg_to_i64(self.el)
     }
+
+    /// Wraps azimuth into [0, 360) and clamps elevation to [-90, 90].
+    pub fn normalized(&self) -> AzEl {
+        AzEl::new(self.az.rem_euclid(360.0), self.el.clamp(-90.0, 90.0))
+    }
+
+    /// Whether both coordinates are finite numbers.
+    pub fn is_finite(&self) -> bool {
+        self.az.is_finite() && self.el.is_finite()
+    }
+
+    /// Right ascension and declination of date of this direction as seen from `location` at `time`.
+    ///
+    /// The inverse of [`RADec::to_az_el`].
+    pub fn to_ra_dec(&self, location: &GeoLocation, time: DateTime<Utc>) -> RADec {
+        let (ha, dec) = transform::az_el_to_ha_dec(*self, location.latitude);
+        RADec::new((transform::local_sidereal_time(time, location.longitude) - ha).rem_euclid(360.0), dec)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::ParseError;
+
     #[test]
     fn basic_build_az_el() {
         let az_el = super::AzEl::new(0.0, 0.0);
@@ -99,4 +153,52 @@ mod tests {
         assert_eq!(ra_dec.ra, 0.0);
         assert_eq!(ra_dec.dec, 0.0);
     }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let coord = super::RADec::from_msg(b"34AB0500,12CE0500#").unwrap();
+        assert!((coord.ra - 74.064).abs() < 1e-3 && (coord.dec - 26.444).abs() < 1e-3, "{coord:?}");
+        assert!(super::AzEl::from_msg(b"34AB0500,12CE0500").is_ok());
+
+        // Truncated, empty, non-hex, non-UTF-8, and missing separator.
+        type Variant = fn(Vec<u8>) -> ParseError;
+        let malformed: [(&[u8], Variant); 5] = [
+            (b"34AB0500,12CE05#", ParseError::Length),
+            (b"", ParseError::Length),
+            (b"34AB0500,12CE05ZZ#", ParseError::NotHex),
+            (b"34AB\xff500,12CE0500#", ParseError::NotUtf8),
+            (b"34AB0500;12CE0500#", ParseError::NotHex),
+        ];
+        for (msg, error) in malformed {
+            let bytes = msg.strip_suffix(b"#").unwrap_or(msg).to_vec();
+            assert_eq!(super::RADec::from_msg(msg), Err(error(bytes.clone())));
+            assert_eq!(super::AzEl::from_msg(msg), Err(error(bytes)));
+        }
+    }
+
+    #[test]
+    fn normalizes_coordinates() {
+        let coord = super::RADec::new(-15.0, 95.0).normalized();
+        assert_eq!(coord, super::RADec::new(345.0, 90.0));
+        assert_eq!(coord.dec_as_i64(), 0x40000000);
+        assert_eq!(super::AzEl::new(720.5, -100.0).normalized(), super::AzEl::new(0.5, -90.0));
+        assert!(!super::AzEl::new(f64::NAN, 0.0).is_finite());
+    }
+
+    #[test]
+    fn horizontal_round_trip() {
+        use chrono::TimeZone;
+
+        // Meeus, Astronomical Algorithms, example 12.a: sidereal time at Greenwich is 13h10m46.3668s.
+        let time = chrono::Utc.with_ymd_and_hms(1987, 4, 10, 0, 0, 0).unwrap();
+        let greenwich = super::GeoLocation { latitude: 45.0, longitude: 0.0 };
+        let transit = super::RADec::new(197.693195, 0.0).to_az_el(&greenwich, time);
+        assert!((transit.az - 180.0).abs() < 1e-3 && (transit.el - 45.0).abs() < 1e-3, "{transit:?}");
+
+        let boston = super::GeoLocation { latitude: 42.36, longitude: -71.06 };
+        let coord = super::RADec::new(83.82, -5.39);
+        let az_el = coord.to_az_el(&boston, time);
+        let back = az_el.to_ra_dec(&boston, time);
+        assert!((back.ra - coord.ra).abs() < 1e-9 && (back.dec - coord.dec).abs() < 1e-9, "{back:?}");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,273 @@
+//! Finding out why a serial port could not be opened.
+//!
+//! A port which fails to open usually does so for one of a few reasons, each with its own fix, while the operating
+//! system only reports "permission denied" or "not found". When opening a port fails,
+//! [`CelestronMount::open_port`](crate::CelestronMount::open_port) looks for the likely cause and returns a
+//! [`PortError`] naming it, with a hint on what to do:
+//!
+//! - On Linux, the user is not in the group owning the device, usually `dialout` or `uucp`.
+//! - Under WSL, no USB serial device exists because the adapter has not been attached from Windows with `usbipd`.
+//! - Another process, such as a planetarium program or a second copy of the GUI, has the port open. On Linux it is
+//!   named.
+//!
+//! Causes are detected on a best-effort basis and are [`Cause::Unknown`] where nothing is recognized.
+
+use super::error::MountError;
+use std::error::Error;
+use std::{fmt, io};
+
+/// Why a port could not be opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cause {
+    /// The port does not exist; the adapter may be unplugged.
+    NotFound,
+    /// The user may not open the port, which belongs to `group` if known.
+    NoPermission { group: Option<String> },
+    /// Running under WSL without any USB serial device attached.
+    WslWithoutUsb,
+    /// Another process has the port open; on Linux, its name and process ID.
+    InUse { process: Option<(String, u32)> },
+    /// Nothing recognized.
+    Unknown,
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cause::NotFound => write!(f, "The port does not exist; check that the adapter is plugged in."),
+            Cause::NoPermission { group: Some(group) } => write!(
+                f,
+                "You are not in the {group} group owning the port; run `sudo usermod -aG {group} $USER` and log in \
+                 again."
+            ),
+            Cause::NoPermission { group: None } => {
+                write!(f, "You do not have permission to open the port.")
+            }
+            Cause::WslWithoutUsb => write!(
+                f,
+                "WSL has no USB serial devices; attach the adapter from Windows with `usbipd attach --wsl`."
+            ),
+            Cause::InUse {
+                process: Some((name, pid)),
+            } => write!(f, "The port is in use by {name} (process {pid}); close it first."),
+            Cause::InUse { process: None } => write!(
+                f,
+                "The port is in use by another program; close it first."
+            ),
+            Cause::Unknown => write!(f, "The cause is unknown."),
+        }
+    }
+}
+
+/// A port could not be opened, with the likely [`Cause`].
+///
+/// Returned inside an `io::Error` of the original kind; use `get_ref` and `downcast_ref` to detect it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortError {
+    /// The port, or `None` if no port was found at all.
+    pub port: Option<String>,
+    pub cause: Cause,
+    /// The error reported by the operating system.
+    pub detail: String,
+}
+
+impl fmt::Display for PortError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.port {
+            Some(port) => write!(f, "Could not open {port}: {}. {}", self.detail, self.cause),
+            None => write!(f, "{} {}", self.detail, self.cause),
+        }
+    }
+}
+
+impl Error for PortError {}
+
+/// Wraps the error from opening `port` in a [`PortError`] with the likely cause.
+pub fn diagnose(port: &str, e: io::Error) -> io::Error {
+    let cause = cause(port, e.kind());
+    io::Error::new(
+        e.kind(),
+        PortError {
+            port: Some(port.to_string()),
+            cause,
+            detail: e.to_string(),
+        },
+    )
+}
+
+/// The likely reason opening `port` failed with an error of `kind`.
+pub fn cause(port: &str, kind: io::ErrorKind) -> Cause {
+    #[cfg(target_os = "linux")]
+    {
+        linux::cause(port, kind)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = port;
+        match kind {
+            io::ErrorKind::NotFound => Cause::NotFound,
+            // Windows denies access to a port another program has open.
+            io::ErrorKind::PermissionDenied if cfg!(windows) => Cause::InUse { process: None },
+            io::ErrorKind::PermissionDenied => Cause::NoPermission { group: None },
+            io::ErrorKind::ResourceBusy => Cause::InUse { process: None },
+            _ => Cause::Unknown,
+        }
+    }
+}
+
+/// Whether running under the Windows Subsystem for Linux.
+pub fn is_wsl() -> bool {
+    cfg!(target_os = "linux")
+        && std::fs::read_to_string("/proc/sys/kernel/osrelease")
+            .is_ok_and(|release| release.to_lowercase().contains("microsoft"))
+}
+
+/// The error returned when no hand control was found on any port, with a cause if one is apparent.
+pub(crate) fn not_found() -> io::Error {
+    if !is_wsl() || serialport::available_ports().is_ok_and(|ports| !ports.is_empty()) {
+        return MountError::PortNotFound.into();
+    }
+    let detail = "No hand control found.".to_string();
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        PortError {
+            port: None,
+            cause: Cause::WslWithoutUsb,
+            detail,
+        },
+    )
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{is_wsl, Cause};
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+
+    pub fn cause(port: &str, kind: io::ErrorKind) -> Cause {
+        match kind {
+            io::ErrorKind::NotFound if is_wsl() && !has_usb_serial() => Cause::WslWithoutUsb,
+            io::ErrorKind::NotFound => Cause::NotFound,
+            io::ErrorKind::PermissionDenied => {
+                let Ok(gid) = fs::metadata(port).map(|m| m.gid()) else {
+                    return Cause::NoPermission { group: None };
+                };
+                let member = fs::read_to_string("/proc/self/status")
+                    .is_ok_and(|status| groups(&status).contains(&gid));
+                if member {
+                    // Allowed to open it, so something else must be in the way.
+                    match holder(port) {
+                        Some(process) => Cause::InUse {
+                            process: Some(process),
+                        },
+                        None => Cause::NoPermission { group: None },
+                    }
+                } else {
+                    let group = fs::read_to_string("/etc/group")
+                        .ok()
+                        .and_then(|groups| group_name(&groups, gid));
+                    Cause::NoPermission { group }
+                }
+            }
+            io::ErrorKind::ResourceBusy => Cause::InUse {
+                process: holder(port),
+            },
+            _ => Cause::Unknown,
+        }
+    }
+
+    /// Whether any USB serial device exists.
+    fn has_usb_serial() -> bool {
+        fs::read_dir("/dev").is_ok_and(|entries| {
+            entries.flatten().any(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.starts_with("ttyUSB") || name.starts_with("ttyACM")
+            })
+        })
+    }
+
+    /// The name and ID of another process with `port` open, if visible.
+    fn holder(port: &str) -> Option<(String, u32)> {
+        let port = fs::canonicalize(port).ok()?;
+        let me = std::process::id();
+        fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            if pid == me {
+                return None;
+            }
+            let open = fs::read_dir(entry.path().join("fd"))
+                .ok()?
+                .flatten()
+                .any(|fd| fs::read_link(fd.path()).is_ok_and(|target| target == port));
+            open.then(|| {
+                let name =
+                    fs::read_to_string(Path::new("/proc").join(pid.to_string()).join("comm"))
+                        .map(|name| name.trim().to_string())
+                        .unwrap_or_default();
+                (name, pid)
+            })
+        })
+    }
+
+    /// The group IDs of the `Groups:` line of a `/proc/<pid>/status` file.
+    pub(super) fn groups(status: &str) -> Vec<u32> {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("Groups:"))
+            .map(|ids| {
+                ids.split_whitespace()
+                    .filter_map(|id| id.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The name of group `gid` in an `/etc/group` file.
+    pub(super) fn group_name(groups: &str, gid: u32) -> Option<String> {
+        groups.lines().find_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse::<u32>().ok()?;
+            (id == gid).then(|| name.to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnoses_missing_port() {
+        let e = diagnose(
+            "/dev/nexlib-missing",
+            io::Error::new(io::ErrorKind::NotFound, "No such file or directory"),
+        );
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+        let port_error = e.get_ref().unwrap().downcast_ref::<PortError>().unwrap();
+        if !is_wsl() {
+            assert_eq!(port_error.cause, Cause::NotFound);
+        }
+        assert!(e
+            .to_string()
+            .starts_with("Could not open /dev/nexlib-missing: No such file"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_groups() {
+        let status = "Name:\tnexctl\nUmask:\t0022\nGroups:\t4 20 27 \nNgid:\t0\n";
+        assert_eq!(linux::groups(status), [4, 20, 27]);
+        let groups = "root:x:0:\ndialout:x:20:alice,bob\nuucp:x:14:\n";
+        assert_eq!(linux::group_name(groups, 20).as_deref(), Some("dialout"));
+        assert_eq!(linux::group_name(groups, 99), None);
+
+        let cause = Cause::NoPermission {
+            group: Some("dialout".to_string()),
+        };
+        assert!(cause.to_string().contains("usermod -aG dialout"));
+    }
+}
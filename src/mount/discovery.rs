@@ -0,0 +1,125 @@
+//! Listing every mount attached to the computer.
+//!
+//! [`discover`] opens each serial port in turn, sends the echo command, and describes every port with a hand control
+//! answering as a [`MountCandidate`], so a program can show what is attached, or drive several mounts, before
+//! opening one with [`CelestronMount::open`]:
+//!
+//! ```no_run
+//! for candidate in nexlib::discover().unwrap() {
+//!     println!("{candidate}");
+//! }
+//! ```
+//!
+//! Each port which does not answer costs up to [`PROBE_TIMEOUT`], and other devices on the probed ports receive the
+//! two bytes of the echo command, which most ignore.
+
+use super::{CelestronMount, Model, Mount, PROBE_TIMEOUT};
+use log::debug;
+use serialport::{SerialPort, SerialPortType};
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// A serial port with a hand control answering on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountCandidate {
+    /// The port, e.g. `/dev/ttyUSB0` or `COM3`.
+    pub port: String,
+    /// USB vendor and product ID of the adapter, if it is a USB device.
+    pub usb_id: Option<(u16, u16)>,
+    /// The model, if the hand control reported it.
+    pub model: Option<Model>,
+    /// The hand control firmware version, if it reported one.
+    pub version: Option<String>,
+}
+
+impl MountCandidate {
+    /// Opens this mount.
+    pub fn open(&self) -> Result<CelestronMount, io::Error> {
+        CelestronMount::open(&self.port, super::DEFAULT_BAUD)
+    }
+}
+
+impl fmt::Display for MountCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.port)?;
+        match self.model {
+            Some(model) => write!(f, ": {model:?}")?,
+            None => write!(f, ": unknown model")?,
+        }
+        if let Some(version) = &self.version {
+            write!(f, ", version {version}")?;
+        }
+        if let Some((vid, pid)) = self.usb_id {
+            write!(f, " (USB {vid:04x}:{pid:04x})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists every serial port with a hand control answering the echo command; see [`discovery`](self).
+pub fn discover() -> Result<Vec<MountCandidate>, io::Error> {
+    discover_with(PROBE_TIMEOUT)
+}
+
+/// As [`discover`], giving each port `timeout` to answer.
+pub fn discover_with(timeout: Duration) -> Result<Vec<MountCandidate>, io::Error> {
+    let mut found = Vec::new();
+    for info in serialport::available_ports()? {
+        let usb_id = match info.port_type {
+            SerialPortType::UsbPort(usb) => Some((usb.vid, usb.pid)),
+            _ => None,
+        };
+        match CelestronMount::open_port(&info.port_name, timeout) {
+            Ok(port) => found.extend(identify(&info.port_name, port, usb_id)),
+            Err(e) => debug!("Could not open {}: {}", info.port_name, e),
+        }
+    }
+    Ok(found)
+}
+
+/// Describes the hand control on an open `port`, or `None` if nothing answers the echo command.
+fn identify(
+    name: &str,
+    port: Box<dyn SerialPort>,
+    usb_id: Option<(u16, u16)>,
+) -> Option<MountCandidate> {
+    let mut mount = CelestronMount::from_port(port);
+    if let Err(e) = mount.ping() {
+        debug!("No hand control on {name}: {e}");
+        return None;
+    }
+    Some(MountCandidate {
+        port: name.to_string(),
+        usb_id,
+        model: mount.get_model().ok(),
+        version: mount.get_version().ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{Fault, SimPort};
+    use crate::mount::SimMount;
+
+    #[test]
+    fn identifies_answering_ports() {
+        let port = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        let candidate = identify(
+            "/dev/ttyUSB0",
+            Box::new(port.clone()),
+            Some((0x067b, 0x23d3)),
+        )
+        .unwrap();
+        assert_eq!(candidate.model, Some(Model::AdvancedVX));
+        assert_eq!(candidate.version.as_deref(), Some("5.35"));
+        assert_eq!(
+            candidate.to_string(),
+            "/dev/ttyUSB0: AdvancedVX, version 5.35 (USB 067b:23d3)"
+        );
+
+        port.inject(Fault::Timeout);
+        assert!(identify("/dev/ttyUSB1", Box::new(port), None).is_none());
+    }
+}
@@ -0,0 +1,286 @@
+//! The errors mount operations return.
+//!
+//! Every method of [`Mount`](super::Mount) and the other mount traits returns a [`MountError`], so callers match on
+//! what went wrong instead of checking error kinds and downcasting payloads by hand:
+//!
+//! ```no_run
+//! use nexlib::mount::error::MountError;
+//! use nexlib::{CelestronMount, Mount, RADec};
+//!
+//! let mut mount = CelestronMount::new().unwrap();
+//! if let Err(e) = mount.goto_ra_dec(RADec::new(83.82, -5.39)) {
+//!     match e {
+//!         MountError::NotAligned => eprintln!("Align the mount from the hand control first."),
+//!         MountError::Timeout => eprintln!("The mount did not answer; check the cable."),
+//!         other => eprintln!("Goto failed: {other}"),
+//!     }
+//! }
+//! ```
+//!
+//! A `MountError` converts into an `io::Error` of the matching kind carrying it, so `?` still works in I/O code, and
+//! back again with [`MountError::from`].
+
+use super::codec::NexError;
+use super::diagnose::{Cause, PortError};
+use super::latency::Command;
+use super::ResponseOverflow;
+use std::io;
+use thiserror::Error;
+
+/// A failure of a mount operation; see [`error`](self).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum MountError {
+    /// No hand control was found, or the named port does not exist.
+    #[error("No hand control found.")]
+    PortNotFound,
+    /// The port could not be opened for another reason.
+    #[error(transparent)]
+    Port(PortError),
+    /// The mount did not answer in time.
+    #[error("The mount did not answer in time.")]
+    Timeout,
+    /// The response could not be understood, with the command if known and the bytes received.
+    #[error("Invalid response{}: {bytes:?}", .cmd.map(|cmd| format!(" to {cmd}")).unwrap_or_default())]
+    InvalidResponse {
+        cmd: Option<Command>,
+        bytes: Vec<u8>,
+    },
+    /// A response was longer than the maximum set with
+    /// [`CelestronMount::set_max_response`](super::CelestronMount::set_max_response).
+    #[error(transparent)]
+    ResponseOverflow(ResponseOverflow),
+    /// A device inside the mount, identified by its passthrough address, did not answer.
+    #[error("Device {0} is unavailable or does not support the command.")]
+    DeviceUnavailable(u8),
+    /// The mount must be aligned first.
+    #[error("{}", NexError::NotAligned)]
+    NotAligned,
+    /// The GPS receiver has no fix.
+    #[error("GPS unit is not linked.")]
+    GpsNotLinked,
+    /// The hand control reported another error.
+    #[error(transparent)]
+    HandControl(NexError),
+    /// The mount, its firmware, or its model does not support the operation.
+    ///
+    /// Raised before sending a NexStar `command` the connected hand control is known not to support, which needs
+    /// firmware `required_version` or later, or is not supported by the model at all if `None`; see
+    /// [`support`](super::support).
+    #[error("{reason}")]
+    Unsupported {
+        command: Option<Command>,
+        required_version: Option<(u8, u8)>,
+        reason: String,
+    },
+    /// Any other failure.
+    #[error("{1}")]
+    Io(io::ErrorKind, String),
+}
+
+impl MountError {
+    /// An [`Unsupported`](MountError::Unsupported) error for an operation other than a NexStar command.
+    pub fn unsupported(reason: impl Into<String>) -> MountError {
+        MountError::Unsupported {
+            command: None,
+            required_version: None,
+            reason: reason.into(),
+        }
+    }
+
+    /// An [`Unsupported`](MountError::Unsupported) error for `command`.
+    pub fn unsupported_command(command: Command, required_version: Option<(u8, u8)>) -> MountError {
+        let reason = match required_version {
+            Some((major, minor)) => {
+                format!("Command {command} requires hand control version {major}.{minor} or later.")
+            }
+            None => format!("Command {command} is not supported by this model."),
+        };
+        MountError::Unsupported {
+            command: Some(command),
+            required_version,
+            reason,
+        }
+    }
+
+    /// Any other failure of `kind`, described by `msg`.
+    pub fn other(kind: io::ErrorKind, msg: impl Into<String>) -> MountError {
+        MountError::Io(kind, msg.into())
+    }
+
+    /// The kind of `io::Error` the error is returned as.
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            MountError::PortNotFound => io::ErrorKind::NotFound,
+            MountError::Port(port) => match port.cause {
+                Cause::NotFound | Cause::WslWithoutUsb => io::ErrorKind::NotFound,
+                Cause::NoPermission { .. } => io::ErrorKind::PermissionDenied,
+                Cause::InUse { .. } => io::ErrorKind::ResourceBusy,
+                Cause::Unknown => io::ErrorKind::Other,
+            },
+            MountError::Timeout => io::ErrorKind::TimedOut,
+            MountError::InvalidResponse { .. }
+            | MountError::ResponseOverflow(_)
+            | MountError::GpsNotLinked => io::ErrorKind::InvalidData,
+            MountError::DeviceUnavailable(_) => io::ErrorKind::NotConnected,
+            MountError::NotAligned => NexError::NotAligned.kind(),
+            MountError::HandControl(e) => e.kind(),
+            MountError::Unsupported { .. } => io::ErrorKind::Unsupported,
+            MountError::Io(kind, _) => *kind,
+        }
+    }
+}
+
+impl From<MountError> for io::Error {
+    fn from(e: MountError) -> io::Error {
+        io::Error::new(e.kind(), e)
+    }
+}
+
+/// Classifies an `io::Error` by its payload, or else keeps its kind and message.
+impl From<&io::Error> for MountError {
+    fn from(e: &io::Error) -> MountError {
+        if let Some(inner) = e.get_ref() {
+            if let Some(e) = inner.downcast_ref::<MountError>() {
+                return e.clone();
+            }
+            if let Some(e) = inner.downcast_ref::<NexError>() {
+                return MountError::from(*e);
+            }
+            if let Some(e) = inner.downcast_ref::<ResponseOverflow>() {
+                return MountError::ResponseOverflow(*e);
+            }
+            if let Some(e) = inner.downcast_ref::<PortError>() {
+                return match e.cause {
+                    Cause::NotFound => MountError::PortNotFound,
+                    _ => MountError::Port(e.clone()),
+                };
+            }
+        }
+        match e.kind() {
+            io::ErrorKind::Unsupported => MountError::unsupported(e.to_string()),
+            kind => MountError::Io(kind, e.to_string()),
+        }
+    }
+}
+
+impl From<io::Error> for MountError {
+    fn from(e: io::Error) -> MountError {
+        MountError::from(&e)
+    }
+}
+
+impl From<serialport::Error> for MountError {
+    fn from(e: serialport::Error) -> MountError {
+        MountError::from(io::Error::from(e))
+    }
+}
+
+impl From<NexError> for MountError {
+    fn from(e: NexError) -> MountError {
+        match e {
+            NexError::NotAligned => MountError::NotAligned,
+            e => MountError::HandControl(e),
+        }
+    }
+}
+
+/// A coordinate message which is not a pair of hexadecimal angles; see [`RADec::from_msg`](super::RADec::from_msg).
+///
+/// Each variant holds the bytes of the message. Through `?` in a mount method it becomes a
+/// [`MountError::InvalidResponse`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The message is truncated, or too long, to be a pair of 4 or 8 digit angles.
+    #[error("Coordinate message of {} bytes is not a pair of 4 or 8 digit angles.", .0.len())]
+    Length(Vec<u8>),
+    /// The message is not UTF-8.
+    #[error("Coordinate message {0:?} is not UTF-8.")]
+    NotUtf8(Vec<u8>),
+    /// The angles are not hexadecimal, or not separated by a comma.
+    #[error("Coordinate message {0:?} is not a pair of hexadecimal angles.")]
+    NotHex(Vec<u8>),
+}
+
+impl ParseError {
+    /// The message which failed to parse.
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            ParseError::Length(bytes) | ParseError::NotUtf8(bytes) | ParseError::NotHex(bytes) => {
+                bytes
+            }
+        }
+    }
+}
+
+impl From<ParseError> for MountError {
+    fn from(e: ParseError) -> MountError {
+        MountError::InvalidResponse {
+            cmd: None,
+            bytes: e.bytes().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{Fault, SimPort};
+    use crate::mount::{CelestronMount, Gps, Mount, NonGpsDevice, SimMount};
+    use std::time::Duration;
+
+    #[test]
+    fn classifies_mount_failures() {
+        let port = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        port.inject(Fault::Timeout);
+        let e = mount.get_tracking_mode().unwrap_err();
+        assert_eq!(e, MountError::Timeout);
+
+        port.inject(Fault::Truncate(3));
+        let e = mount.get_position_ra_dec().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        let MountError::InvalidResponse { cmd, bytes } = e else {
+            panic!("Not an invalid response.");
+        };
+        assert_eq!(cmd, Some(Command::HandControl(b'e')));
+        assert_eq!(bytes.len(), 3);
+
+        port.inject(Fault::Error(4));
+        let e = mount.get_tracking_mode().unwrap_err();
+        assert_eq!(e, MountError::NotAligned);
+
+        port.inject(Fault::DeviceUnavailable);
+        let e = mount.get_device_version(NonGpsDevice::RtcUnit).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotConnected);
+        assert_eq!(e, MountError::DeviceUnavailable(178));
+
+        let port = SimPort::new(SimMount::new().gps(Duration::from_secs(3600)));
+        let mut mount = CelestronMount::from_port(Box::new(port));
+        let e = mount.get_gps().unwrap().get_location().unwrap_err();
+        assert_eq!(e, MountError::GpsNotLinked);
+    }
+
+    #[test]
+    fn round_trips_through_io_error() {
+        let e = MountError::unsupported_command(Command::HandControl(b's'), Some((4, 10)));
+        let io = io::Error::from(e.clone());
+        assert_eq!(io.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(MountError::from(&io), e);
+        assert_eq!(
+            e.to_string(),
+            "Command 's' requires hand control version 4.10 or later."
+        );
+
+        let io = io::Error::from(MountError::Timeout);
+        assert_eq!(MountError::from(io), MountError::Timeout);
+
+        let io = io::Error::new(io::ErrorKind::NotFound, "No such target.");
+        assert_eq!(
+            MountError::from(io),
+            MountError::other(io::ErrorKind::NotFound, "No such target.")
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! A broadcast channel of changes in the pointing state of a mount.
+//!
+//! [`MountEvents::spawn`] starts a background poller on a [`StatusCache`] which compares each status with the last and
+//! publishes a typed [`MountEvent`] for every change. Any number of consumers call [`MountEvents::subscribe`] for a
+//! Tokio broadcast receiver, which async code can `select!` over alongside its other work, and synchronous code can
+//! read with `blocking_recv`:
+//!
+//! ```no_run
+//! use nexlib::mount::events::{MountEvent, MountEvents};
+//! use nexlib::mount::status::StatusCache;
+//! use nexlib::CelestronMount;
+//! use std::sync::{Arc, Mutex};
+//! use std::time::Duration;
+//!
+//! # async fn run() {
+//! let mount = Arc::new(Mutex::new(CelestronMount::new().unwrap()));
+//! let cache = StatusCache::new(mount, Duration::from_millis(500));
+//! let events = MountEvents::spawn(cache, Duration::from_secs(1)).elevation_limits(10.0, 88.0);
+//!
+//! let mut rx = events.subscribe();
+//! while let Ok(event) = rx.recv().await {
+//!     match event {
+//!         MountEvent::GotoFinished => println!("Arrived."),
+//!         MountEvent::LimitHit { az_el, .. } => eprintln!("Outside the limits at {az_el}!"),
+//!         _ => (),
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! A receiver which falls more than [`CAPACITY`] events behind misses the oldest, and its next `recv` reports how
+//! many were skipped.
+
+use super::status::{MountStatus, StatusCache};
+use super::{AzEl, Mount, TrackingMode};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Events held for each receiver before the oldest are dropped.
+pub const CAPACITY: usize = 256;
+
+/// A change in the state of the mount.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MountEvent {
+    /// A new status was read. Sent on every poll, before any other events it causes.
+    PositionUpdated(MountStatus),
+    GotoStarted,
+    GotoFinished,
+    TrackingChanged {
+        from: TrackingMode,
+        to: TrackingMode,
+    },
+    /// The mount moved outside the elevation limits, sent once each time it leaves them.
+    LimitHit {
+        az_el: AzEl,
+        min: f64,
+        max: f64,
+    },
+    /// The mount stopped answering, sent once until it answers again.
+    Disconnected {
+        kind: io::ErrorKind,
+        message: String,
+    },
+    /// The mount answered again after being disconnected.
+    Reconnected,
+}
+
+/// What is remembered of the previous poll.
+#[derive(Debug, Default)]
+struct Previous {
+    status: Option<MountStatus>,
+    disconnected: bool,
+    outside_limits: bool,
+}
+
+/// Determines the events caused by the outcome of a poll.
+fn detect(
+    prev: &mut Previous,
+    res: Result<MountStatus, io::Error>,
+    limits: Option<(f64, f64)>,
+) -> Vec<MountEvent> {
+    let status = match res {
+        Ok(status) => status,
+        Err(e) => {
+            if std::mem::replace(&mut prev.disconnected, true) {
+                return Vec::new();
+            }
+            return vec![MountEvent::Disconnected {
+                kind: e.kind(),
+                message: e.to_string(),
+            }];
+        }
+    };
+
+    let mut events = vec![MountEvent::PositionUpdated(status)];
+    if std::mem::take(&mut prev.disconnected) {
+        events.push(MountEvent::Reconnected);
+    }
+    if let Some(last) = prev.status {
+        if !last.goto_in_progress && status.goto_in_progress {
+            events.push(MountEvent::GotoStarted);
+        }
+        if last.goto_in_progress && !status.goto_in_progress {
+            events.push(MountEvent::GotoFinished);
+        }
+        if last.tracking_mode != status.tracking_mode {
+            events.push(MountEvent::TrackingChanged {
+                from: last.tracking_mode,
+                to: status.tracking_mode,
+            });
+        }
+    }
+    if let Some((min, max)) = limits {
+        let el = status.az_el.el;
+        let outside = el < min || el > max;
+        if outside && !prev.outside_limits {
+            events.push(MountEvent::LimitHit {
+                az_el: status.az_el,
+                min,
+                max,
+            });
+        }
+        prev.outside_limits = outside;
+    }
+
+    prev.status = Some(status);
+    events
+}
+
+/// A background poller publishing [`MountEvent`]s; see [`events`](self).
+///
+/// Polling stops when this is dropped.
+#[derive(Debug)]
+pub struct MountEvents {
+    tx: broadcast::Sender<MountEvent>,
+    limits: Arc<Mutex<Option<(f64, f64)>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl MountEvents {
+    /// Polls the status from `cache` every `interval`.
+    pub fn spawn<M: Mount + Send + 'static>(
+        cache: StatusCache<M>,
+        interval: Duration,
+    ) -> MountEvents {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        let events = MountEvents {
+            tx: tx.clone(),
+            limits: Arc::default(),
+            running: Arc::new(AtomicBool::new(true)),
+        };
+
+        let limits = Arc::clone(&events.limits);
+        let running = Arc::clone(&events.running);
+        thread::spawn(move || {
+            let mut prev = Previous::default();
+            while running.load(Ordering::Relaxed) {
+                let limits = *limits.lock().unwrap();
+                for event in detect(&mut prev, cache.get(), limits) {
+                    // Fails only while nobody is subscribed.
+                    let _ = tx.send(event);
+                }
+                thread::sleep(interval);
+            }
+        });
+
+        events
+    }
+
+    /// Reports [`MountEvent::LimitHit`] when the elevation leaves `min` to `max` degrees.
+    pub fn elevation_limits(self, min: f64, max: f64) -> MountEvents {
+        *self.limits.lock().unwrap() = Some((min, max));
+        self
+    }
+
+    /// A receiver of every event published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<MountEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Drop for MountEvents {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{Fault, SimMount, SimPort};
+    use crate::CelestronMount;
+
+    #[test]
+    fn publishes_changes() {
+        let port = SimPort::new(SimMount::new().max_rate(50.0).acceleration(100.0));
+        let mount = Arc::new(Mutex::new(CelestronMount::from_port(Box::new(
+            port.clone(),
+        ))));
+        let cache = StatusCache::new(Arc::clone(&mount), Duration::ZERO);
+        let events = MountEvents::spawn(cache, Duration::from_millis(10));
+        let mut rx = events.subscribe();
+
+        let mut next = |wanted: fn(&MountEvent) -> bool| loop {
+            let event = rx.blocking_recv().unwrap();
+            if wanted(&event) {
+                return event;
+            }
+        };
+        next(|e| matches!(e, MountEvent::PositionUpdated(_)));
+
+        mount
+            .lock()
+            .unwrap()
+            .goto_az_el(AzEl::new(0.0, 50.0))
+            .unwrap();
+        mount
+            .lock()
+            .unwrap()
+            .set_tracking_mode(TrackingMode::EQNorth)
+            .unwrap();
+        next(|e| matches!(e, MountEvent::GotoStarted));
+        assert_eq!(
+            next(|e| matches!(e, MountEvent::TrackingChanged { .. })),
+            MountEvent::TrackingChanged {
+                from: TrackingMode::Off,
+                to: TrackingMode::EQNorth
+            }
+        );
+        next(|e| matches!(e, MountEvent::GotoFinished));
+
+        port.inject(Fault::Timeout);
+        let MountEvent::Disconnected { kind, .. } =
+            next(|e| matches!(e, MountEvent::Disconnected { .. }))
+        else {
+            unreachable!();
+        };
+        assert_eq!(kind, io::ErrorKind::TimedOut);
+        next(|e| matches!(e, MountEvent::Reconnected));
+    }
+
+    #[test]
+    fn reports_limits_once() {
+        let status = |el| MountStatus {
+            time: chrono::Utc::now(),
+            ra_dec: crate::RADec::new(0.0, 0.0),
+            az_el: AzEl::new(180.0, el),
+            tracking_mode: TrackingMode::Off,
+            goto_in_progress: false,
+        };
+        let limits = Some((10.0, 80.0));
+        let mut prev = Previous::default();
+        let hits = |events: Vec<MountEvent>| {
+            events
+                .iter()
+                .filter(|e| matches!(e, MountEvent::LimitHit { .. }))
+                .count()
+        };
+
+        assert_eq!(hits(detect(&mut prev, Ok(status(45.0)), limits)), 0);
+        assert_eq!(hits(detect(&mut prev, Ok(status(5.0)), limits)), 1);
+        assert_eq!(hits(detect(&mut prev, Ok(status(4.0)), limits)), 0);
+        assert_eq!(hits(detect(&mut prev, Ok(status(45.0)), limits)), 0);
+        assert_eq!(hits(detect(&mut prev, Ok(status(85.0)), limits)), 1);
+    }
+}
@@ -0,0 +1,197 @@
+//! Remote operation of the hand control's keypad and display.
+//!
+//! Some hand control firmware accepts keypresses and reports the contents of its LCD through passthrough commands
+//! addressed to the hand control itself. With these, features only reachable through its menus, such as the factory
+//! alignment options, can be operated remotely:
+//!
+//! ```no_run
+//! use nexlib::mount::{HandController, Key};
+//! use nexlib::CelestronMount;
+//!
+//! let mut mount = CelestronMount::new().unwrap();
+//! let mut hc = mount.hand_controller();
+//! hc.press_keys(&[Key::Menu, Key::ScrollDown, Key::Enter]).unwrap();
+//! println!("{}", hc.read_lcd().unwrap());
+//! ```
+//!
+//! This is not part of the published protocol. Firmware without it answers as an unavailable device, an error of
+//! kind `NotConnected`.
+
+use super::{CelestronMount, Device};
+use std::{fmt, io};
+
+/// Passthrough command pressing the key given as the argument.
+const HC_PRESS_KEY: u8 = 0x21;
+
+/// Passthrough command reading the line of the LCD given as the argument.
+const HC_GET_LCD_LINE: u8 = 0x22;
+
+/// Lines on the hand control's LCD.
+pub const LCD_LINES: usize = 2;
+
+/// Characters on each line of the hand control's LCD.
+pub const LCD_WIDTH: usize = 16;
+
+/// A key on the hand control's keypad.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Key {
+    /// A number key, 0 to 9.
+    Digit(u8),
+    Enter,
+    Back,
+    Menu,
+    Info,
+    Align,
+    Tour,
+    Rate,
+    /// The scroll keys, for moving through menus.
+    ScrollUp,
+    ScrollDown,
+    /// The direction keys, which also slew the mount.
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Key {
+    /// The code of the key sent to the hand control.
+    fn code(&self) -> Result<u8, io::Error> {
+        Ok(match self {
+            Key::Digit(n @ 0..=9) => b'0' + n,
+            Key::Digit(n) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("No key for digit {n}."),
+                ))
+            }
+            Key::Enter => 0x0D,
+            Key::Back => 0x08,
+            Key::Menu => b'M',
+            Key::Info => b'I',
+            Key::Align => b'A',
+            Key::Tour => b'T',
+            Key::Rate => b'R',
+            Key::ScrollUp => b'+',
+            Key::ScrollDown => b'-',
+            Key::Up => b'U',
+            Key::Down => b'D',
+            Key::Left => b'L',
+            Key::Right => b'G',
+        })
+    }
+}
+
+/// The text shown on the hand control's LCD.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lcd {
+    /// Each line, with trailing spaces removed.
+    pub lines: [String; LCD_LINES],
+}
+
+impl fmt::Display for Lcd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\n{}", self.lines[0], self.lines[1])
+    }
+}
+
+/// Remote control of a hand control's keypad and display.
+pub trait HandController {
+    /// Presses and releases `key`.
+    fn press_key(&mut self, key: Key) -> Result<(), io::Error>;
+
+    /// Reads what the LCD currently shows.
+    fn read_lcd(&mut self) -> Result<Lcd, io::Error>;
+
+    /// Presses each of `keys` in turn, stopping at the first error.
+    fn press_keys(&mut self, keys: &[Key]) -> Result<(), io::Error> {
+        keys.iter().try_for_each(|&key| self.press_key(key))
+    }
+}
+
+/// The keypad and display of the hand control of a [`CelestronMount`].
+#[derive(Debug)]
+pub struct CelestronHandController<'a> {
+    mount: &'a mut CelestronMount,
+}
+
+impl CelestronMount {
+    /// The hand control's keypad and display, on firmware supporting remote operation.
+    pub fn hand_controller(&mut self) -> CelestronHandController<'_> {
+        CelestronHandController { mount: self }
+    }
+}
+
+impl HandController for CelestronHandController<'_> {
+    fn press_key(&mut self, key: Key) -> Result<(), io::Error> {
+        self.mount
+            .query_passthrough(Device::HandControl, HC_PRESS_KEY, &[key.code()?], 0)?;
+        Ok(())
+    }
+
+    fn read_lcd(&mut self) -> Result<Lcd, io::Error> {
+        let mut lcd = Lcd::default();
+        for (i, line) in lcd.lines.iter_mut().enumerate() {
+            let res = self.mount.query_passthrough(
+                Device::HandControl,
+                HC_GET_LCD_LINE,
+                &[i as u8],
+                LCD_WIDTH,
+            )?;
+            *line = res
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() { b as char } else { ' ' })
+                .collect::<String>()
+                .trim_end()
+                .to_string();
+        }
+        Ok(lcd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::session::{EventKind, ReplayPort};
+
+    #[test]
+    fn operates_keypad_and_display() {
+        let hc = |cmd, arg, resp_len| EventKind::Write(vec![b'P', 2, 4, cmd, arg, 0, 0, resp_len]);
+        let line = |text: &str| {
+            let mut res = format!("{text:16}").into_bytes();
+            res.push(b'#');
+            EventKind::Read(res)
+        };
+        let port = ReplayPort::scripted([
+            hc(HC_PRESS_KEY, b'M', 0),
+            EventKind::Read(b"#".to_vec()),
+            hc(HC_PRESS_KEY, 0x0D, 0),
+            EventKind::Read(b"#".to_vec()),
+            hc(HC_GET_LCD_LINE, 0, 16),
+            line("Scope Setup"),
+            hc(HC_GET_LCD_LINE, 1, 16),
+            line("Setup Time-Site"),
+            // Firmware without remote operation.
+            hc(HC_PRESS_KEY, b'1', 0),
+            EventKind::Read(vec![0, b'#']),
+        ]);
+        let mut mount = CelestronMount::from_port(Box::new(port));
+        let mut hc = mount.hand_controller();
+
+        hc.press_keys(&[Key::Menu, Key::Enter]).unwrap();
+        assert_eq!(
+            hc.read_lcd().unwrap().to_string(),
+            "Scope Setup\nSetup Time-Site"
+        );
+        assert_eq!(
+            hc.press_key(Key::Digit(10)).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            hc.press_key(Key::Digit(1)).unwrap_err().kind(),
+            io::ErrorKind::NotConnected
+        );
+    }
+}
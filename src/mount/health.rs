@@ -0,0 +1,174 @@
+//! Background link health monitoring.
+//!
+//! A [`HealthMonitor`] watches a shared [`CelestronMount`] from its own thread. Whenever the mount has not answered
+//! a command within the monitor's interval, it sends a cheap echo to check the link, and calls back as soon as the
+//! mount stops (or resumes) responding. Operators then learn about a dead link before the next goto fails, while an
+//! application that is busy talking to the mount costs no extra serial traffic. The echo also serves as an idle
+//! keep-alive for adapters which drop the link after a period of silence.
+//!
+//! The monitor notices when the host resumes from sleep, after which USB serial adapters are often gone or wedged, and
+//! checks the link straight away rather than waiting for the next interval.
+//!
+//! ```no_run
+//! use nexlib::mount::health::{HealthEvent, HealthMonitor};
+//! use nexlib::CelestronMount;
+//! use std::sync::{Arc, Mutex};
+//! use std::time::Duration;
+//!
+//! let mount = Arc::new(Mutex::new(CelestronMount::new().unwrap()));
+//! let _monitor = HealthMonitor::spawn(Arc::clone(&mount), Duration::from_secs(5), |event| match event {
+//!     HealthEvent::Lost(e) => eprintln!("Mount stopped responding: {e}"),
+//!     HealthEvent::Restored => eprintln!("Mount is responding again."),
+//!     HealthEvent::Resumed(slept) => eprintln!("Woke after {slept:?}; checking the mount."),
+//! });
+//! ```
+
+use super::error::MountError;
+use super::CelestronMount;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A change in whether the mount is responding.
+#[derive(Debug)]
+pub enum HealthEvent {
+    /// The mount stopped responding; holds the error of the failed check.
+    Lost(MountError),
+    /// The mount is responding again after being lost.
+    Restored,
+    /// The host resumed after sleeping for about this long. The link is checked next.
+    Resumed(Duration),
+}
+
+/// Detects host sleep by comparing the wall clock, which keeps running during a suspend, to the monotonic clock,
+/// which does not on every platform.
+#[derive(Debug)]
+pub struct SleepDetector {
+    mono: Instant,
+    wall: SystemTime,
+    tolerance: Duration,
+}
+
+impl SleepDetector {
+    /// Reports gaps between the clocks longer than `tolerance`, which should be well above any expected clock
+    /// adjustment.
+    pub fn new(tolerance: Duration) -> SleepDetector {
+        SleepDetector {
+            mono: Instant::now(),
+            wall: SystemTime::now(),
+            tolerance,
+        }
+    }
+
+    /// Returns roughly how long the host slept since the last call, if it did.
+    pub fn check(&mut self) -> Option<Duration> {
+        let mono = self.mono.elapsed();
+        let wall = self.wall.elapsed().unwrap_or_default();
+        self.mono = Instant::now();
+        self.wall = SystemTime::now();
+
+        let slept = wall.saturating_sub(mono);
+        (slept > self.tolerance).then_some(slept)
+    }
+}
+
+/// Checks the link to a mount in the background until dropped.
+#[derive(Debug)]
+pub struct HealthMonitor {
+    stop: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HealthMonitor {
+    /// Checks the link every `interval`, calling `on_event` when the mount stops or resumes responding.
+    pub fn spawn<F>(
+        mount: Arc<Mutex<CelestronMount>>,
+        interval: Duration,
+        mut on_event: F,
+    ) -> HealthMonitor
+    where
+        F: FnMut(HealthEvent) + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel();
+
+        let thread = thread::spawn(move || {
+            let mut healthy = true;
+            let mut sleep = SleepDetector::new(interval.max(Duration::from_secs(5)));
+
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                if let Some(slept) = sleep.check() {
+                    log::info!("Host resumed after {slept:?}; checking the mount link.");
+                    on_event(HealthEvent::Resumed(slept));
+                }
+                let res = check(&mut mount.lock().unwrap(), interval);
+
+                match res {
+                    Ok(()) if !healthy => {
+                        healthy = true;
+                        on_event(HealthEvent::Restored);
+                    }
+                    Err(e) if healthy => {
+                        log::warn!("[{}:{}] Mount stopped responding: {}", file!(), line!(), e);
+                        healthy = false;
+                        on_event(HealthEvent::Lost(e));
+                    }
+                    _ => (),
+                }
+            }
+        });
+
+        HealthMonitor {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Pings the mount unless it answered a command within `interval`.
+fn check(mount: &mut CelestronMount, interval: Duration) -> Result<(), MountError> {
+    match mount.since_last_response() {
+        Some(elapsed) if elapsed < interval => Ok(()),
+        _ => mount.ping(),
+    }
+}
+
+impl Drop for HealthMonitor {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{Fault, SimMount, SimPort};
+
+    #[test]
+    fn reports_lost_and_restored() {
+        let port = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        let mount = Arc::new(Mutex::new(CelestronMount::from_port(Box::new(
+            port.clone(),
+        ))));
+        let (tx, rx) = mpsc::channel();
+
+        port.inject(Fault::Timeout);
+        let monitor = HealthMonitor::spawn(mount, Duration::from_millis(30), move |event| {
+            let _ = tx.send(event);
+        });
+
+        let timeout = Duration::from_secs(5);
+        assert!(matches!(
+            rx.recv_timeout(timeout).unwrap(),
+            HealthEvent::Lost(MountError::Timeout)
+        ));
+        assert!(matches!(
+            rx.recv_timeout(timeout).unwrap(),
+            HealthEvent::Restored
+        ));
+        drop(monitor);
+    }
+}
@@ -0,0 +1,187 @@
+//! A bounded in-memory history of positions.
+//!
+//! A [`PositionHistory`] keeps the most recent samples of a [`PositionStream`](super::stream::PositionStream), or any
+//! other source of [`PositionSample`]s, and answers questions about the past: the positions over a drift measurement,
+//! the points of a plot, or where the mount was pointing at the middle of an exposure for its FITS header.
+//!
+//! ```no_run
+//! use nexlib::mount::history::PositionHistory;
+//! use nexlib::mount::stream::position_stream;
+//! use nexlib::CelestronMount;
+//! use std::sync::{Arc, Mutex};
+//!
+//! let mount = Arc::new(Mutex::new(CelestronMount::new().unwrap()));
+//! let mut history = PositionHistory::new(3600);
+//! let start = chrono::Utc::now();
+//! history.extend(position_stream(mount, 1.0).take(60).flatten());
+//!
+//! let mid_exposure = start + chrono::TimeDelta::seconds(30);
+//! println!("{:?}", history.position_at(mid_exposure));
+//! ```
+
+use super::stream::PositionSample;
+use super::transform::wrap_180;
+use super::{AzEl, RADec};
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+
+/// The most recent positions, in time order, up to a fixed number of samples.
+#[derive(Debug, Clone)]
+pub struct PositionHistory {
+    samples: VecDeque<PositionSample>,
+    capacity: usize,
+}
+
+impl PositionHistory {
+    /// Keeps at most `capacity` samples, discarding the oldest first.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn new(capacity: usize) -> PositionHistory {
+        assert!(
+            capacity > 0,
+            "A position history must hold at least one sample."
+        );
+        PositionHistory {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Adds a sample. A sample older than the latest is inserted in time order.
+    pub fn push(&mut self, sample: PositionSample) {
+        let i = self.samples.partition_point(|s| s.time <= sample.time);
+        if i == 0 && self.samples.len() == self.capacity {
+            // Older than everything kept.
+            return;
+        }
+        self.samples.insert(i, sample);
+        if self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn latest(&self) -> Option<&PositionSample> {
+        self.samples.back()
+    }
+
+    /// Every sample, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &PositionSample> {
+        self.samples.iter()
+    }
+
+    /// The samples taken at or after `time`, oldest first.
+    pub fn positions_since(&self, time: DateTime<Utc>) -> impl Iterator<Item = &PositionSample> {
+        let i = self.samples.partition_point(|s| s.time < time);
+        self.samples.range(i..)
+    }
+
+    /// The position at `time`, interpolated linearly between the samples either side of it.
+    ///
+    /// Returns `None` if `time` is outside the period covered by the history.
+    pub fn position_at(&self, time: DateTime<Utc>) -> Option<PositionSample> {
+        let i = self.samples.partition_point(|s| s.time < time);
+        let after = self.samples.get(i)?;
+        if after.time == time {
+            return Some(*after);
+        }
+        let before = self.samples.get(i.checked_sub(1)?)?;
+
+        let span = (after.time - before.time).num_microseconds()? as f64;
+        let t = (time - before.time).num_microseconds()? as f64 / span;
+        Some(PositionSample {
+            time,
+            ra_dec: RADec::new(
+                lerp_wrapped(before.ra_dec.ra, after.ra_dec.ra, t),
+                lerp(before.ra_dec.dec, after.ra_dec.dec, t),
+            ),
+            az_el: AzEl::new(
+                lerp_wrapped(before.az_el.az, after.az_el.az, t),
+                lerp(before.az_el.el, after.az_el.el, t),
+            ),
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+impl Extend<PositionSample> for PositionHistory {
+    fn extend<I: IntoIterator<Item = PositionSample>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|sample| self.push(sample));
+    }
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Interpolates an angle in [0, 360) the short way round, across 0° if need be.
+fn lerp_wrapped(a: f64, b: f64, t: f64) -> f64 {
+    (a + wrap_180(b - a) * t).rem_euclid(360.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeDelta, TimeZone};
+
+    fn sample(secs: i64, ra: f64, dec: f64) -> PositionSample {
+        PositionSample {
+            time: Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap() + TimeDelta::seconds(secs),
+            ra_dec: RADec::new(ra, dec),
+            az_el: AzEl::new(ra, dec),
+        }
+    }
+
+    #[test]
+    fn queries_history() {
+        let mut history = PositionHistory::new(3);
+        history.extend([
+            sample(0, 10.0, 0.0),
+            sample(10, 359.0, 10.0),
+            sample(30, 1.0, 30.0),
+        ]);
+        // Out of order, then pushing out the oldest.
+        history.push(sample(20, 0.0, 20.0));
+        assert_eq!(history.len(), 3);
+        assert_eq!(
+            history.iter().next().unwrap().time,
+            sample(10, 0.0, 0.0).time
+        );
+
+        let since: Vec<_> = history
+            .positions_since(sample(15, 0.0, 0.0).time)
+            .map(|s| s.ra_dec.dec)
+            .collect();
+        assert_eq!(since, [20.0, 30.0]);
+
+        // Across 0° right ascension.
+        let mid = history.position_at(sample(15, 0.0, 0.0).time).unwrap();
+        assert!((mid.ra_dec.ra - 359.5).abs() < 1e-9, "{}", mid.ra_dec);
+        assert!((mid.ra_dec.dec - 15.0).abs() < 1e-9);
+        assert_eq!(
+            history
+                .position_at(sample(30, 0.0, 0.0).time)
+                .unwrap()
+                .ra_dec,
+            RADec::new(1.0, 30.0)
+        );
+        assert!(history.position_at(sample(5, 0.0, 0.0).time).is_none());
+        assert!(history.position_at(sample(31, 0.0, 0.0).time).is_none());
+    }
+}
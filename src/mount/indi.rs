@@ -0,0 +1,771 @@
+//! [`Mount`] backend driving a telescope device on a remote INDI server.
+//!
+//! INDI clients and servers exchange a stream of XML property vectors over TCP (port 7624 by default). A background
+//! thread keeps a copy of every property of the chosen device up to date, so queries read the latest values the
+//! server has sent and commands only need to send a `new*Vector` element. The standard telescope properties are used:
+//!
+//! - `EQUATORIAL_EOD_COORD` and `HORIZONTAL_COORD` for position, gotos (`ON_COORD_SET` = `TRACK`), and syncs.
+//! - `TELESCOPE_TRACK_STATE`, `TELESCOPE_MOTION_NS`/`_WE`, `TELESCOPE_SLEW_RATE`, and `TELESCOPE_ABORT_MOTION`.
+//! - `TIME_UTC` and `DRIVER_INFO`.
+//!
+//! The [`driver`] module goes the other way, serving any [`Mount`] as an INDI device under `indiserver`.
+
+use super::error::MountError;
+use super::{Gps, Location, Model, Mount, NonGpsDevice, SlewAxis, SlewDir, SlewRate, TrackingMode};
+use crate::{AzEl, RADec};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub mod driver;
+
+/// Port INDI servers listen on by default.
+pub const DEFAULT_PORT: u16 = 7624;
+
+/// Time to wait for the server to define the telescope properties after connecting.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+const EQUATORIAL: &str = "EQUATORIAL_EOD_COORD";
+const HORIZONTAL: &str = "HORIZONTAL_COORD";
+const GEOGRAPHIC: &str = "GEOGRAPHIC_COORD";
+
+/// Property state as reported by the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyState {
+    Idle,
+    Ok,
+    Busy,
+    Alert,
+}
+
+impl PropertyState {
+    fn parse(s: &str) -> Option<PropertyState> {
+        match s {
+            "Idle" => Some(PropertyState::Idle),
+            "Ok" => Some(PropertyState::Ok),
+            "Busy" => Some(PropertyState::Busy),
+            "Alert" => Some(PropertyState::Alert),
+            _ => None,
+        }
+    }
+}
+
+/// Latest known value of one property vector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub state: PropertyState,
+    /// Element names and values, in the order the server defined them.
+    pub elements: Vec<(String, String)>,
+}
+
+impl Property {
+    pub fn get(&self, element: &str) -> Option<&str> {
+        self.elements
+            .iter()
+            .find(|(name, _)| name == element)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parses a number element, accepting INDI's sexagesimal notation (`5:35:17.3`).
+    pub fn number(&self, element: &str) -> Option<f64> {
+        parse_number(self.get(element)?)
+    }
+
+    /// Whether a switch element is `On`.
+    pub fn is_on(&self, element: &str) -> bool {
+        self.get(element) == Some("On")
+    }
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if let Ok(v) = s.parse() {
+        return Some(v);
+    }
+
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, s),
+    };
+    let mut value = 0.0;
+    for (part, div) in s.split([':', ' ']).zip([1.0, 60.0, 3600.0]) {
+        value += part.parse::<f64>().ok()? / div;
+    }
+    Some(sign * value)
+}
+
+#[derive(Debug, Default)]
+struct State {
+    properties: HashMap<String, Property>,
+    /// Set once the server closes the connection.
+    closed: bool,
+}
+
+type Shared = Arc<(Mutex<State>, Condvar)>;
+
+/// A property vector being read.
+struct Vector {
+    name: String,
+    state: Option<PropertyState>,
+    elements: Vec<(String, String)>,
+    /// Whether this is a definition (`def*Vector`) rather than an update (`set*Vector`).
+    define: bool,
+}
+
+/// Incrementally applies a stream of INDI XML to the properties of one device.
+struct Parser {
+    device: String,
+    /// The vector being read, if it belongs to `device`.
+    vector: Option<Vector>,
+    element: Option<String>,
+    text: String,
+}
+
+impl Parser {
+    fn new(device: &str) -> Parser {
+        Parser {
+            device: device.to_owned(),
+            vector: None,
+            element: None,
+            text: String::new(),
+        }
+    }
+
+    fn attr(e: &BytesStart, name: &str) -> Option<String> {
+        e.try_get_attribute(name)
+            .ok()
+            .flatten()
+            .and_then(|a| a.unescape_value().ok())
+            .map(|v| v.into_owned())
+    }
+
+    fn start(&mut self, e: &BytesStart, state: &mut State) {
+        let tag = e.name();
+        let tag = String::from_utf8_lossy(tag.as_ref());
+
+        if Self::attr(e, "device").is_some_and(|d| d != self.device) {
+            return;
+        }
+
+        let Some(name) = Self::attr(e, "name") else {
+            return;
+        };
+
+        if tag == "delProperty" {
+            state.properties.remove(&name);
+            return;
+        }
+
+        let define = tag.starts_with("def");
+        if (define || tag.starts_with("set")) && tag.ends_with("Vector") {
+            self.vector = Some(Vector {
+                name,
+                state: Self::attr(e, "state").and_then(|s| PropertyState::parse(&s)),
+                elements: Vec::new(),
+                define,
+            });
+        } else if self.vector.is_some() && (tag.starts_with("def") || tag.starts_with("one")) {
+            self.element = Some(name);
+            self.text.clear();
+        }
+    }
+
+    fn end(&mut self, tag: &[u8], state: &mut State) {
+        if let Some(element) = self.element.take() {
+            if let Some(vector) = &mut self.vector {
+                vector.elements.push((element, self.text.trim().to_owned()));
+            }
+            return;
+        }
+
+        if !tag.ends_with(b"Vector") {
+            return;
+        }
+
+        let Some(vector) = self.vector.take() else {
+            return;
+        };
+
+        if vector.define {
+            state.properties.insert(
+                vector.name,
+                Property {
+                    state: vector.state.unwrap_or(PropertyState::Idle),
+                    elements: vector.elements,
+                },
+            );
+        } else if let Some(property) = state.properties.get_mut(&vector.name) {
+            if let Some(s) = vector.state {
+                property.state = s;
+            }
+            for (element, value) in vector.elements {
+                match property.elements.iter_mut().find(|(n, _)| *n == element) {
+                    Some((_, v)) => *v = value,
+                    None => property.elements.push((element, value)),
+                }
+            }
+        }
+    }
+
+    /// Reads events until `reader` is exhausted, publishing every completed vector to `shared`.
+    fn run<R: BufRead>(mut self, reader: R, shared: &Shared) {
+        let mut reader = Reader::from_reader(reader);
+        reader.config_mut().check_end_names = false;
+        let mut buf = Vec::new();
+
+        loop {
+            let event = reader.read_event_into(&mut buf);
+            let (lock, cvar) = &**shared;
+            let mut state = lock.lock().unwrap();
+
+            match event {
+                Ok(Event::Start(e)) => self.start(&e, &mut state),
+                Ok(Event::Empty(e)) => {
+                    self.start(&e, &mut state);
+                    let tag = e.name().as_ref().to_owned();
+                    self.end(&tag, &mut state);
+                }
+                Ok(Event::Text(t)) => {
+                    if self.element.is_some() {
+                        self.text.push_str(&t.unescape().unwrap_or_default());
+                    }
+                }
+                Ok(Event::End(e)) => {
+                    self.end(e.name().as_ref(), &mut state);
+                    cvar.notify_all();
+                }
+                Ok(Event::Eof) | Err(_) => {
+                    if let Err(e) = event {
+                        log::warn!("[{}:{}] INDI stream error: {:?}", file!(), line!(), e);
+                    }
+                    state.closed = true;
+                    cvar.notify_all();
+                    return;
+                }
+                Ok(_) => (),
+            }
+
+            buf.clear();
+        }
+    }
+}
+
+/// A telescope device on a remote INDI server.
+#[derive(Debug)]
+pub struct IndiClientMount {
+    stream: TcpStream,
+    device: String,
+    shared: Shared,
+}
+
+impl Drop for IndiClientMount {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+impl IndiClientMount {
+    /// Connects to the INDI server at `addr` and attaches to its telescope `device`, e.g. `"Telescope Simulator"`.
+    ///
+    /// The device is connected to its hardware if the server reports it disconnected.
+    pub fn connect<A: ToSocketAddrs>(addr: A, device: &str) -> Result<IndiClientMount, io::Error> {
+        let stream = TcpStream::connect(addr)?;
+        let shared: Shared = Arc::new((Mutex::new(State::default()), Condvar::new()));
+
+        {
+            let reader = BufReader::new(stream.try_clone()?);
+            let shared = Arc::clone(&shared);
+            let parser = Parser::new(device);
+            thread::spawn(move || parser.run(reader, &shared));
+        }
+
+        let mut mount = IndiClientMount {
+            stream,
+            device: device.to_owned(),
+            shared,
+        };
+
+        mount.send(&format!(
+            "<getProperties version=\"1.7\" device=\"{}\"/>",
+            escape(device)
+        ))?;
+
+        let connection = mount.wait("CONNECTION", CONNECT_TIMEOUT, |_| true)?;
+        if !connection.is_on("CONNECT") {
+            mount.set_switch("CONNECTION", &["CONNECT"], &["DISCONNECT"])?;
+        }
+        mount.wait(EQUATORIAL, CONNECT_TIMEOUT, |_| true)?;
+
+        Ok(mount)
+    }
+
+    pub fn device(&self) -> &str {
+        &self.device
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.shared.0.lock().unwrap()
+    }
+
+    /// Latest value of `name`, if the server has defined it.
+    pub fn property(&self, name: &str) -> Option<Property> {
+        self.state().properties.get(name).cloned()
+    }
+
+    fn require(&self, name: &str) -> Result<Property, MountError> {
+        let state = self.state();
+        if state.closed {
+            return Err(MountError::other(
+                io::ErrorKind::NotConnected,
+                "INDI server closed the connection.",
+            ));
+        }
+
+        state.properties.get(name).cloned().ok_or_else(|| {
+            MountError::unsupported(format!("{} does not define {}.", self.device, name))
+        })
+    }
+
+    /// Waits until `name` is defined and satisfies `ready`.
+    fn wait<F: Fn(&Property) -> bool>(
+        &self,
+        name: &str,
+        timeout: Duration,
+        ready: F,
+    ) -> Result<Property, MountError> {
+        let deadline = Instant::now() + timeout;
+        let (lock, cvar) = &*self.shared;
+        let mut state = lock.lock().unwrap();
+
+        loop {
+            if let Some(property) = state.properties.get(name).filter(|p| ready(p)) {
+                return Ok(property.clone());
+            }
+            if state.closed {
+                return Err(MountError::other(
+                    io::ErrorKind::NotConnected,
+                    "INDI server closed the connection.",
+                ));
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(MountError::other(
+                    io::ErrorKind::TimedOut,
+                    format!("Timed out waiting for {} on {}.", name, self.device),
+                ));
+            }
+            state = cvar.wait_timeout(state, remaining).unwrap().0;
+        }
+    }
+
+    fn send(&mut self, xml: &str) -> Result<(), MountError> {
+        log::trace!("INDI send: {}", xml);
+        self.stream.write_all(xml.as_bytes())?;
+        self.stream.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn new_vector(
+        &mut self,
+        kind: &str,
+        name: &str,
+        elements: &[(&str, String)],
+    ) -> Result<(), MountError> {
+        let mut xml = format!(
+            "<new{kind}Vector device=\"{}\" name=\"{}\">",
+            escape(self.device.as_str()),
+            escape(name)
+        );
+        for (element, value) in elements {
+            xml.push_str(&format!(
+                "<one{kind} name=\"{}\">{}</one{kind}>",
+                escape(*element),
+                escape(value.as_str())
+            ));
+        }
+        xml.push_str(&format!("</new{kind}Vector>"));
+
+        self.send(&xml)
+    }
+
+    fn set_numbers(&mut self, name: &str, values: &[(&str, f64)]) -> Result<(), MountError> {
+        let values: Vec<(&str, String)> = values.iter().map(|(e, v)| (*e, v.to_string())).collect();
+        self.new_vector("Number", name, &values)
+    }
+
+    fn set_switch(&mut self, name: &str, on: &[&str], off: &[&str]) -> Result<(), MountError> {
+        let values: Vec<(&str, String)> = on
+            .iter()
+            .map(|e| (*e, "On".to_owned()))
+            .chain(off.iter().map(|e| (*e, "Off".to_owned())))
+            .collect();
+        self.new_vector("Switch", name, &values)
+    }
+
+    fn goto_equatorial(&mut self, action: &str, coord: RADec) -> Result<(), MountError> {
+        self.require(EQUATORIAL)?;
+        self.set_switch("ON_COORD_SET", &[action], &[])?;
+        self.set_numbers(EQUATORIAL, &[("RA", coord.ra / 15.0), ("DEC", coord.dec)])
+    }
+
+    fn motion(axis: SlewAxis) -> (&'static str, [&'static str; 2]) {
+        match axis {
+            SlewAxis::RAAz => ("TELESCOPE_MOTION_WE", ["MOTION_EAST", "MOTION_WEST"]),
+            SlewAxis::DecEl => ("TELESCOPE_MOTION_NS", ["MOTION_NORTH", "MOTION_SOUTH"]),
+        }
+    }
+
+    fn unsupported(what: &str) -> MountError {
+        MountError::unsupported(format!("{what} is not available through INDI."))
+    }
+}
+
+impl Mount for IndiClientMount {
+    /// Right ascension in degrees and declination of date, as reported by `EQUATORIAL_EOD_COORD`.
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        let p = self.require(EQUATORIAL)?;
+        match (p.number("RA"), p.number("DEC")) {
+            (Some(ra), Some(dec)) => Ok(RADec::new(ra * 15.0, dec)),
+            _ => Err(MountError::other(
+                io::ErrorKind::InvalidData,
+                format!("Invalid {EQUATORIAL}: {:?}", p.elements),
+            )),
+        }
+    }
+
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        let p = self.require(HORIZONTAL)?;
+        match (p.number("AZ"), p.number("ALT")) {
+            (Some(az), Some(alt)) => Ok(AzEl::new(az, alt)),
+            _ => Err(MountError::other(
+                io::ErrorKind::InvalidData,
+                format!("Invalid {HORIZONTAL}: {:?}", p.elements),
+            )),
+        }
+    }
+
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.goto_equatorial("TRACK", coord)
+    }
+
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        self.require(HORIZONTAL)?;
+        self.set_numbers(HORIZONTAL, &[("AZ", coord.az), ("ALT", coord.el)])
+    }
+
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.goto_equatorial("SYNC", coord)
+    }
+
+    /// INDI only reports whether tracking is on; the hemisphere is taken from the site latitude.
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        if !self.require("TELESCOPE_TRACK_STATE")?.is_on("TRACK_ON") {
+            return Ok(TrackingMode::Off);
+        }
+
+        let south = self
+            .property(GEOGRAPHIC)
+            .and_then(|p| p.number("LAT"))
+            .is_some_and(|lat| lat < 0.0);
+        Ok(if south {
+            TrackingMode::EQSouth
+        } else {
+            TrackingMode::EQNorth
+        })
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        self.require("TELESCOPE_TRACK_STATE")?;
+        match mode {
+            TrackingMode::Off => {
+                self.set_switch("TELESCOPE_TRACK_STATE", &["TRACK_OFF"], &["TRACK_ON"])
+            }
+            _ => self.set_switch("TELESCOPE_TRACK_STATE", &["TRACK_ON"], &["TRACK_OFF"]),
+        }
+    }
+
+    /// INDI has no standard property for arbitrary slew rates.
+    fn slew_variable(
+        &mut self,
+        _axis: SlewAxis,
+        _dir: SlewDir,
+        _rate: u16,
+    ) -> Result<(), MountError> {
+        Err(Self::unsupported("Variable rate slewing"))
+    }
+
+    /// Maps rates 1-9 proportionally onto the driver's `TELESCOPE_SLEW_RATE` switches.
+    fn slew_fixed(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        if rate == SlewRate::Stop {
+            return self.stop_slew(axis);
+        }
+
+        if let Some(rates) = self.property("TELESCOPE_SLEW_RATE") {
+            let n = rates.elements.len();
+            if n > 0 {
+                let index = ((rate as usize - 1) * n / 9).min(n - 1);
+                let on = rates.elements[index].0.clone();
+                self.set_switch("TELESCOPE_SLEW_RATE", &[&on], &[])?;
+            }
+        }
+
+        let (name, [positive, negative]) = Self::motion(axis);
+        self.require(name)?;
+        match dir {
+            SlewDir::Positive => self.set_switch(name, &[positive], &[negative]),
+            SlewDir::Negative => self.set_switch(name, &[negative], &[positive]),
+        }
+    }
+
+    /// The site from `GEOGRAPHIC_COORD`, whose longitudes run from 0 to 360 degrees east.
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        let p = self.require(GEOGRAPHIC)?;
+        match (p.number("LAT"), p.number("LONG")) {
+            (Some(latitude), Some(longitude)) => Ok(Location {
+                latitude,
+                longitude: (longitude + 180.0).rem_euclid(360.0) - 180.0,
+            }),
+            _ => Err(MountError::other(
+                io::ErrorKind::InvalidData,
+                format!("Invalid {GEOGRAPHIC}: {:?}", p.elements),
+            )),
+        }
+    }
+
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        // Drivers expect the whole vector, so the elevation is resent unchanged.
+        let elevation = self.require(GEOGRAPHIC)?.number("ELEV").unwrap_or(0.0);
+        self.set_numbers(
+            GEOGRAPHIC,
+            &[
+                ("LAT", location.latitude),
+                ("LONG", location.longitude.rem_euclid(360.0)),
+                ("ELEV", elevation),
+            ],
+        )
+    }
+
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        let p = self.require("TIME_UTC")?;
+        let utc = p.get("UTC").unwrap_or_default();
+        NaiveDateTime::parse_from_str(utc, "%Y-%m-%dT%H:%M:%S%.f")
+            .map(|t| t.and_utc())
+            .map_err(|e| {
+                MountError::other(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid UTC {utc:?}: {e}"),
+                )
+            })
+    }
+
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        // Drivers expect the whole vector, so the offset is resent unchanged.
+        let offset = self.require("TIME_UTC")?.get("OFFSET").unwrap_or("0").to_owned();
+        let utc = time.format("%Y-%m-%dT%H:%M:%S").to_string();
+        self.new_vector("Text", "TIME_UTC", &[("UTC", utc), ("OFFSET", offset)])
+    }
+
+    /// Version of the INDI driver.
+    fn get_version(&mut self) -> Result<String, MountError> {
+        Ok(self
+            .require("DRIVER_INFO")?
+            .get("DRIVER_VERSION")
+            .unwrap_or_default()
+            .to_owned())
+    }
+
+    fn get_device_version(&mut self, _device: NonGpsDevice) -> Result<String, MountError> {
+        Err(Self::unsupported("Device versions"))
+    }
+
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        Err(Self::unsupported("The Celestron model"))
+    }
+
+    /// INDI has no echo, so the byte is returned as long as the device is connected.
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        self.require(EQUATORIAL).map(|_| byte)
+    }
+
+    /// INDI drivers manage their own alignment, so a connected device is always considered aligned.
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        self.require(EQUATORIAL).map(|_| true)
+    }
+
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        let busy = |name| {
+            self.property(name)
+                .is_some_and(|p| p.state == PropertyState::Busy)
+        };
+        self.require(EQUATORIAL)?;
+        Ok(busy(EQUATORIAL) || busy(HORIZONTAL))
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.require("TELESCOPE_ABORT_MOTION")?;
+        self.set_switch("TELESCOPE_ABORT_MOTION", &["ABORT"], &[])
+    }
+
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
+        let (name, elements) = Self::motion(axis);
+        self.require(name)?;
+        self.set_switch(name, &[], &elements)
+    }
+
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
+        Err(Self::unsupported("GPS passthrough"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    const DEFS: &str = r#"<defSwitchVector device="Scope" name="CONNECTION" state="Ok" perm="rw" rule="OneOfMany">
+  <defSwitch name="CONNECT">On</defSwitch>
+  <defSwitch name="DISCONNECT">Off</defSwitch>
+</defSwitchVector>
+<defNumberVector device="Other" name="EQUATORIAL_EOD_COORD" state="Idle" perm="rw">
+  <defNumber name="RA" format="%010.6m" min="0" max="24" step="0">1</defNumber>
+</defNumberVector>
+<defNumberVector device="Scope" name="EQUATORIAL_EOD_COORD" state="Idle" perm="rw">
+  <defNumber name="RA" format="%010.6m" min="0" max="24" step="0">5:35:17.3</defNumber>
+  <defNumber name="DEC" format="%010.6m" min="-90" max="90" step="0">-5.5</defNumber>
+</defNumberVector>
+<defSwitchVector device="Scope" name="TELESCOPE_SLEW_RATE" state="Idle" perm="rw" rule="OneOfMany">
+  <defSwitch name="SLEW_GUIDE">On</defSwitch>
+  <defSwitch name="SLEW_CENTERING">Off</defSwitch>
+  <defSwitch name="SLEW_FIND">Off</defSwitch>
+  <defSwitch name="SLEW_MAX">Off</defSwitch>
+</defSwitchVector>
+<defSwitchVector device="Scope" name="TELESCOPE_MOTION_WE" state="Idle" perm="rw" rule="AtMostOne">
+  <defSwitch name="MOTION_WEST">Off</defSwitch>
+  <defSwitch name="MOTION_EAST">Off</defSwitch>
+</defSwitchVector>
+<defNumberVector device="Scope" name="GEOGRAPHIC_COORD" state="Idle" perm="rw">
+  <defNumber name="LAT" format="%010.6m" min="-90" max="90" step="0">-33.5</defNumber>
+  <defNumber name="LONG" format="%010.6m" min="0" max="360" step="0">341.75</defNumber>
+  <defNumber name="ELEV" format="%g" min="-200" max="10000" step="0">120</defNumber>
+</defNumberVector>
+<defTextVector device="Scope" name="TIME_UTC" state="Idle" perm="rw">
+  <defText name="UTC">2024-03-01T21:30:00</defText>
+  <defText name="OFFSET">-5</defText>
+</defTextVector>
+"#;
+
+    fn parse(xml: &str) -> State {
+        let shared: Shared = Arc::new((Mutex::new(State::default()), Condvar::new()));
+        Parser::new("Scope").run(xml.as_bytes(), &shared);
+        Arc::try_unwrap(shared)
+            .ok()
+            .unwrap()
+            .0
+            .into_inner()
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_definitions_and_updates() {
+        let xml = format!(
+            "{DEFS}<setNumberVector device=\"Scope\" name=\"EQUATORIAL_EOD_COORD\" state=\"Busy\">\
+             <oneNumber name=\"DEC\">10.25</oneNumber></setNumberVector>\
+             <delProperty device=\"Scope\" name=\"TIME_UTC\"/>"
+        );
+        let state = parse(&xml);
+        assert!(state.closed);
+
+        let eq = &state.properties[EQUATORIAL];
+        assert_eq!(eq.state, PropertyState::Busy);
+        assert!((eq.number("RA").unwrap() - 5.588139).abs() < 1e-6);
+        assert_eq!(eq.number("DEC"), Some(10.25));
+        assert!(state.properties["CONNECTION"].is_on("CONNECT"));
+        assert!(!state.properties.contains_key("TIME_UTC"));
+    }
+
+    #[test]
+    fn sexagesimal_numbers() {
+        assert_eq!(parse_number("-10:30"), Some(-10.5));
+        assert_eq!(parse_number("1.5e1"), Some(15.0));
+        assert_eq!(parse_number("north"), None);
+    }
+
+    #[test]
+    fn drives_fake_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            conn.write_all(DEFS.as_bytes()).unwrap();
+            let mut received = String::new();
+            conn.read_to_string(&mut received).unwrap();
+            received
+        });
+
+        let mut mount = IndiClientMount::connect(addr, "Scope").unwrap();
+        // Properties defined after the coordinates may still be in flight.
+        mount.wait("TIME_UTC", CONNECT_TIMEOUT, |_| true).unwrap();
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!((pos.ra - 83.822083).abs() < 1e-5);
+        assert_eq!(pos.dec, -5.5);
+        assert_eq!(
+            mount.get_time().unwrap().to_rfc3339(),
+            "2024-03-01T21:30:00+00:00"
+        );
+        assert!(!mount.goto_in_progress().unwrap());
+        assert_eq!(
+            mount.get_location().unwrap(),
+            Location {
+                latitude: -33.5,
+                longitude: -18.25,
+            }
+        );
+
+        mount
+            .set_location(Location {
+                latitude: 40.0,
+                longitude: -75.0,
+            })
+            .unwrap();
+        mount.goto_ra_dec(RADec::new(90.0, 10.0)).unwrap();
+        mount
+            .slew_fixed(SlewAxis::RAAz, SlewDir::Negative, SlewRate::Rate9)
+            .unwrap();
+        assert_eq!(
+            mount.cancel_goto().unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+        drop(mount);
+
+        let received = server.join().unwrap();
+        assert!(received.contains("<getProperties version=\"1.7\" device=\"Scope\"/>"));
+        assert!(received.contains(
+            "<newNumberVector device=\"Scope\" name=\"EQUATORIAL_EOD_COORD\">\
+             <oneNumber name=\"RA\">6</oneNumber><oneNumber name=\"DEC\">10</oneNumber></newNumberVector>"
+        ));
+        assert!(received.contains(
+            "<newNumberVector device=\"Scope\" name=\"GEOGRAPHIC_COORD\">\
+             <oneNumber name=\"LAT\">40</oneNumber><oneNumber name=\"LONG\">285</oneNumber>\
+             <oneNumber name=\"ELEV\">120</oneNumber></newNumberVector>"
+        ));
+        assert!(received.contains("<oneSwitch name=\"SLEW_MAX\">On</oneSwitch>"));
+        assert!(received.contains(
+            "<oneSwitch name=\"MOTION_WEST\">On</oneSwitch><oneSwitch name=\"MOTION_EAST\">Off</oneSwitch>"
+        ));
+    }
+}
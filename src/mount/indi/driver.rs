@@ -0,0 +1,753 @@
+//! INDI driver serving any [`Mount`] to INDI clients such as KStars/Ekos.
+//!
+//! `indiserver` starts drivers as child processes and relays the INDI XML protocol over their stdin and stdout, so
+//! [`IndiDriver::run`] reads client commands from one stream and writes property definitions and updates to the
+//! other. The device offers `CONNECTION` and `DRIVER_INFO` until a client connects, which opens the mount, and then
+//! the same standard telescope properties [`IndiClientMount`](super::IndiClientMount) drives:
+//!
+//! - `EQUATORIAL_EOD_COORD` and `HORIZONTAL_COORD`, polled for position and `Busy` while a goto is in progress.
+//! - `ON_COORD_SET` (`TRACK`, `SLEW`, or `SYNC`) choosing what setting `EQUATORIAL_EOD_COORD` does.
+//! - `TELESCOPE_TRACK_STATE`, `TELESCOPE_MOTION_NS`/`_WE`, `TELESCOPE_SLEW_RATE`, `TELESCOPE_ABORT_MOTION`, and
+//!   `TIME_UTC`.
+
+use super::{parse_number, Parser};
+use crate::mount::{Mount, SlewAxis, SlewDir, SlewRate, TrackingMode};
+use crate::{AzEl, RADec};
+use chrono::NaiveDateTime;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default time between position updates sent to clients.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `DRIVER_INTERFACE` bit of a telescope.
+const TELESCOPE_INTERFACE: u32 = 1;
+
+const GROUP: &str = "Main Control";
+
+/// Telescope properties, deleted again when the client disconnects.
+const TELESCOPE_PROPERTIES: [&str; 9] = [
+    "EQUATORIAL_EOD_COORD",
+    "HORIZONTAL_COORD",
+    "ON_COORD_SET",
+    "TELESCOPE_TRACK_STATE",
+    "TELESCOPE_ABORT_MOTION",
+    "TELESCOPE_MOTION_NS",
+    "TELESCOPE_MOTION_WE",
+    "TELESCOPE_SLEW_RATE",
+    "TIME_UTC",
+];
+
+/// `TELESCOPE_SLEW_RATE` switches and the hand control rates they select.
+const SLEW_RATES: [(&str, SlewRate); 4] = [
+    ("SLEW_GUIDE", SlewRate::Rate2),
+    ("SLEW_CENTERING", SlewRate::Rate5),
+    ("SLEW_FIND", SlewRate::Rate7),
+    ("SLEW_MAX", SlewRate::Rate9),
+];
+
+/// Element names and values of a property vector.
+type Elements = Vec<(String, String)>;
+
+/// A command read from a client.
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    /// `getProperties`, optionally for one device.
+    GetProperties { device: Option<String> },
+    /// A `new*Vector` with its element names and values.
+    New {
+        device: String,
+        name: String,
+        elements: Elements,
+    },
+}
+
+/// Reads client commands from `input`, sending each to `tx` until the stream ends or the driver stops listening.
+fn read_commands<R: BufRead>(input: R, tx: mpsc::Sender<Command>) {
+    let mut reader = Reader::from_reader(input);
+    reader.config_mut().check_end_names = false;
+    let mut buf = Vec::new();
+    let mut vector: Option<(String, String, Elements)> = None;
+    let mut element: Option<String> = None;
+    let mut text = String::new();
+
+    loop {
+        let event = reader.read_event_into(&mut buf);
+        let command = match event {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                let device = Parser::attr(e, "device");
+                let name = Parser::attr(e, "name");
+                let empty = matches!(event, Ok(Event::Empty(_)));
+
+                if tag == "getProperties" {
+                    Some(Command::GetProperties { device })
+                } else if tag.starts_with("new") && tag.ends_with("Vector") {
+                    let new = (
+                        device.unwrap_or_default(),
+                        name.unwrap_or_default(),
+                        Vec::new(),
+                    );
+                    if empty {
+                        Some(Command::New {
+                            device: new.0,
+                            name: new.1,
+                            elements: new.2,
+                        })
+                    } else {
+                        vector = Some(new);
+                        None
+                    }
+                } else {
+                    if vector.is_some() && tag.starts_with("one") {
+                        if empty {
+                            if let (Some((_, _, elements)), Some(name)) = (&mut vector, name) {
+                                elements.push((name, String::new()));
+                            }
+                        } else {
+                            element = name;
+                            text.clear();
+                        }
+                    }
+                    None
+                }
+            }
+            Ok(Event::Text(t)) => {
+                if element.is_some() {
+                    text.push_str(&t.unescape().unwrap_or_default());
+                }
+                None
+            }
+            Ok(Event::End(e)) => {
+                if let Some(name) = element.take() {
+                    if let Some((_, _, elements)) = &mut vector {
+                        elements.push((name, text.trim().to_owned()));
+                    }
+                    None
+                } else if e.name().as_ref().ends_with(b"Vector") {
+                    vector.take().map(|(device, name, elements)| Command::New {
+                        device,
+                        name,
+                        elements,
+                    })
+                } else {
+                    None
+                }
+            }
+            Ok(Event::Eof) | Err(_) => {
+                if let Err(e) = event {
+                    log::warn!("[{}:{}] INDI stream error: {:?}", file!(), line!(), e);
+                }
+                return;
+            }
+            Ok(_) => None,
+        };
+
+        if let Some(command) = command {
+            if tx.send(command).is_err() {
+                return;
+            }
+        }
+        buf.clear();
+    }
+}
+
+/// Whether switch `element` of a client command is `On`.
+fn is_on(elements: &[(String, String)], element: &str) -> bool {
+    elements
+        .iter()
+        .any(|(name, value)| name == element && value == "On")
+}
+
+fn number(elements: &[(String, String)], element: &str) -> Option<f64> {
+    elements
+        .iter()
+        .find(|(name, _)| name == element)
+        .and_then(|(_, value)| parse_number(value))
+}
+
+/// Serves a mount as an INDI telescope device.
+pub struct IndiDriver<M, F> {
+    device: String,
+    connect: F,
+    mount: Option<M>,
+    poll_interval: Duration,
+    /// Action taken when a client sets `EQUATORIAL_EOD_COORD`.
+    coord_set: &'static str,
+    slew_rate: SlewRate,
+    /// Mode to resume when a client turns tracking back on.
+    tracking: TrackingMode,
+}
+
+impl<M: Mount, F: FnMut() -> Result<M, io::Error>> IndiDriver<M, F> {
+    /// Creates a driver for the INDI device `device`, which opens the mount with `connect` when a client connects.
+    pub fn new(device: &str, connect: F) -> IndiDriver<M, F> {
+        IndiDriver {
+            device: device.to_owned(),
+            connect,
+            mount: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            coord_set: "TRACK",
+            slew_rate: SlewRate::Rate9,
+            tracking: TrackingMode::EQNorth,
+        }
+    }
+
+    /// Sets the time between position updates.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> IndiDriver<M, F> {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// The mount, while a client has it connected.
+    pub fn mount(&mut self) -> Option<&mut M> {
+        self.mount.as_mut()
+    }
+
+    /// Serves client commands from `input`, writing to `output`, until `input` ends.
+    pub fn run<R, W>(&mut self, input: R, mut output: W) -> Result<(), io::Error>
+    where
+        R: Read + Send + 'static,
+        W: Write,
+    {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || read_commands(BufReader::new(input), tx));
+
+        let mut next_poll = Instant::now();
+        loop {
+            let timeout = next_poll.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(timeout) {
+                Ok(command) => self.handle(command, &mut output)?,
+                Err(RecvTimeoutError::Timeout) => {
+                    self.poll(&mut output)?;
+                    next_poll = Instant::now() + self.poll_interval;
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+            output.flush()?;
+        }
+    }
+
+    fn handle<W: Write>(&mut self, command: Command, out: &mut W) -> Result<(), io::Error> {
+        match command {
+            Command::GetProperties { device } => {
+                if device.is_none_or(|d| d == self.device) {
+                    self.define(out)?;
+                }
+                Ok(())
+            }
+            Command::New {
+                device,
+                name,
+                elements,
+            } => {
+                if device != self.device {
+                    return Ok(());
+                }
+                match self.apply(&name, &elements, out) {
+                    Ok(()) => Ok(()),
+                    // Failures of the mount are reported to the client rather than ending the driver.
+                    Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                        log::warn!("[{}:{}] {} failed: {:?}", file!(), line!(), name, e);
+                        self.set(out, &name, "Alert", Some(&e.to_string()), "", &[])
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+
+    fn apply<W: Write>(
+        &mut self,
+        name: &str,
+        elements: &[(String, String)],
+        out: &mut W,
+    ) -> Result<(), io::Error> {
+        if name == "CONNECTION" {
+            return self.set_connected(is_on(elements, "CONNECT"), out);
+        }
+
+        let Some(mount) = self.mount.as_mut() else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "The mount is not connected.",
+            ));
+        };
+
+        match name {
+            "EQUATORIAL_EOD_COORD" => {
+                let current = mount.get_position_ra_dec()?;
+                let coord = RADec::new(
+                    number(elements, "RA").map_or(current.ra, |ra| ra * 15.0),
+                    number(elements, "DEC").unwrap_or(current.dec),
+                );
+                if self.coord_set == "SYNC" {
+                    mount.sync(coord)?;
+                    self.poll(out)
+                } else {
+                    mount.goto_ra_dec(coord)?;
+                    let values = [("RA", coord.ra / 15.0), ("DEC", coord.dec)];
+                    self.set_numbers(out, name, "Busy", &values)
+                }
+            }
+            "HORIZONTAL_COORD" => {
+                let current = mount.get_position_az_el()?;
+                let coord = AzEl::new(
+                    number(elements, "AZ").unwrap_or(current.az),
+                    number(elements, "ALT").unwrap_or(current.el),
+                );
+                mount.goto_az_el(coord)?;
+                self.set_numbers(out, name, "Busy", &[("AZ", coord.az), ("ALT", coord.el)])
+            }
+            "ON_COORD_SET" => {
+                self.coord_set = ["TRACK", "SLEW", "SYNC"]
+                    .into_iter()
+                    .find(|action| is_on(elements, action))
+                    .unwrap_or(self.coord_set);
+                self.set_switches(out, name, "Ok", &["TRACK", "SLEW", "SYNC"], self.coord_set)
+            }
+            "TELESCOPE_TRACK_STATE" => {
+                let on = is_on(elements, "TRACK_ON");
+                mount.set_tracking_mode(if on { self.tracking } else { TrackingMode::Off })?;
+                let selected = if on { "TRACK_ON" } else { "TRACK_OFF" };
+                self.set_switches(out, name, "Ok", &["TRACK_ON", "TRACK_OFF"], selected)
+            }
+            "TELESCOPE_ABORT_MOTION" => {
+                mount.cancel_goto()?;
+                mount.stop_slew(SlewAxis::RAAz)?;
+                mount.stop_slew(SlewAxis::DecEl)?;
+                self.set_switches(out, name, "Ok", &["ABORT"], "")
+            }
+            "TELESCOPE_MOTION_NS" | "TELESCOPE_MOTION_WE" => {
+                let (axis, positive, negative) = if name == "TELESCOPE_MOTION_NS" {
+                    (SlewAxis::DecEl, "MOTION_NORTH", "MOTION_SOUTH")
+                } else {
+                    (SlewAxis::RAAz, "MOTION_EAST", "MOTION_WEST")
+                };
+                let (state, selected) = if is_on(elements, positive) {
+                    mount.slew_fixed(axis, SlewDir::Positive, self.slew_rate)?;
+                    ("Busy", positive)
+                } else if is_on(elements, negative) {
+                    mount.slew_fixed(axis, SlewDir::Negative, self.slew_rate)?;
+                    ("Busy", negative)
+                } else {
+                    mount.stop_slew(axis)?;
+                    ("Idle", "")
+                };
+                self.set_switches(out, name, state, &[positive, negative], selected)
+            }
+            "TELESCOPE_SLEW_RATE" => {
+                if let Some((_, rate)) = SLEW_RATES
+                    .iter()
+                    .find(|(switch, _)| is_on(elements, switch))
+                {
+                    self.slew_rate = *rate;
+                }
+                let selected = SLEW_RATES
+                    .iter()
+                    .find(|(_, rate)| *rate == self.slew_rate)
+                    .map_or("", |(switch, _)| *switch);
+                let switches: Vec<&str> = SLEW_RATES.iter().map(|(switch, _)| *switch).collect();
+                self.set_switches(out, name, "Ok", &switches, selected)
+            }
+            "TIME_UTC" => {
+                let utc = elements
+                    .iter()
+                    .find(|(name, _)| name == "UTC")
+                    .map_or("", |(_, value)| value.as_str());
+                let time =
+                    NaiveDateTime::parse_from_str(utc, "%Y-%m-%dT%H:%M:%S%.f").map_err(|e| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Invalid UTC {utc:?}: {e}"),
+                        )
+                    })?;
+                mount.set_time(time.and_utc())?;
+                self.set(out, name, "Ok", None, "Text", &[("UTC", utc.to_owned())])
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Unknown property {name}."),
+            )),
+        }
+    }
+
+    fn set_connected<W: Write>(&mut self, connect: bool, out: &mut W) -> Result<(), io::Error> {
+        if connect && self.mount.is_none() {
+            self.mount = Some((self.connect)()?);
+            self.define_telescope(out)?;
+            self.poll(out)?;
+        } else if !connect && self.mount.take().is_some() {
+            for name in TELESCOPE_PROPERTIES {
+                writeln!(
+                    out,
+                    "<delProperty device=\"{}\" name=\"{name}\"/>",
+                    escape(self.device.as_str())
+                )?;
+            }
+        }
+        let selected = if connect { "CONNECT" } else { "DISCONNECT" };
+        self.set_switches(
+            out,
+            "CONNECTION",
+            "Ok",
+            &["CONNECT", "DISCONNECT"],
+            selected,
+        )
+    }
+
+    /// Sends the current position, goto, and tracking state to clients.
+    fn poll<W: Write>(&mut self, out: &mut W) -> Result<(), io::Error> {
+        let Some(mount) = self.mount.as_mut() else {
+            return Ok(());
+        };
+
+        let status = (|| {
+            let ra_dec = mount.get_position_ra_dec()?;
+            let az_el = mount.get_position_az_el()?;
+            let busy = mount.goto_in_progress()?;
+            let tracking = mount.get_tracking_mode()?;
+            Ok::<_, io::Error>((ra_dec, az_el, busy, tracking))
+        })();
+
+        let (ra_dec, az_el, busy, tracking) = match status {
+            Ok(status) => status,
+            Err(e) => {
+                return self.set(
+                    out,
+                    "EQUATORIAL_EOD_COORD",
+                    "Alert",
+                    Some(&e.to_string()),
+                    "",
+                    &[],
+                );
+            }
+        };
+        if tracking != TrackingMode::Off {
+            self.tracking = tracking;
+        }
+
+        let state = if busy { "Busy" } else { "Ok" };
+        self.set_numbers(
+            out,
+            "EQUATORIAL_EOD_COORD",
+            state,
+            &[("RA", ra_dec.ra / 15.0), ("DEC", ra_dec.dec)],
+        )?;
+        self.set_numbers(
+            out,
+            "HORIZONTAL_COORD",
+            state,
+            &[("AZ", az_el.az), ("ALT", az_el.el)],
+        )?;
+        let selected = if tracking == TrackingMode::Off {
+            "TRACK_OFF"
+        } else {
+            "TRACK_ON"
+        };
+        self.set_switches(
+            out,
+            "TELESCOPE_TRACK_STATE",
+            "Ok",
+            &["TRACK_ON", "TRACK_OFF"],
+            selected,
+        )
+    }
+
+    /// Defines the properties available in the current connection state.
+    fn define<W: Write>(&mut self, out: &mut W) -> Result<(), io::Error> {
+        let device = escape(self.device.as_str()).into_owned();
+        let connected = self.mount.is_some();
+        writeln!(
+            out,
+            "<defSwitchVector device=\"{device}\" name=\"CONNECTION\" label=\"Connection\" group=\"{GROUP}\" \
+             state=\"Ok\" perm=\"rw\" rule=\"OneOfMany\" timeout=\"60\">\
+             <defSwitch name=\"CONNECT\">{}</defSwitch><defSwitch name=\"DISCONNECT\">{}</defSwitch>\
+             </defSwitchVector>",
+            on_off(connected),
+            on_off(!connected),
+        )?;
+        writeln!(
+            out,
+            "<defTextVector device=\"{device}\" name=\"DRIVER_INFO\" label=\"Driver Info\" group=\"General Info\" \
+             state=\"Idle\" perm=\"ro\">\
+             <defText name=\"DRIVER_NAME\">nexlib</defText><defText name=\"DRIVER_EXEC\">indi_nexlib</defText>\
+             <defText name=\"DRIVER_VERSION\">{}</defText>\
+             <defText name=\"DRIVER_INTERFACE\">{TELESCOPE_INTERFACE}</defText></defTextVector>",
+            env!("CARGO_PKG_VERSION"),
+        )?;
+
+        if connected {
+            self.define_telescope(out)?;
+            self.poll(out)?;
+        }
+        Ok(())
+    }
+
+    fn define_telescope<W: Write>(&self, out: &mut W) -> Result<(), io::Error> {
+        let device = escape(self.device.as_str()).into_owned();
+        let numbers = |name: &str, label: &str, elements: &[(&str, &str, f64, f64)]| {
+            let mut xml = format!(
+                "<defNumberVector device=\"{device}\" name=\"{name}\" label=\"{label}\" group=\"{GROUP}\" \
+                 state=\"Idle\" perm=\"rw\" timeout=\"60\">"
+            );
+            for (element, format, min, max) in elements {
+                xml.push_str(&format!(
+                    "<defNumber name=\"{element}\" format=\"{format}\" min=\"{min}\" max=\"{max}\" step=\"0\">0</defNumber>"
+                ));
+            }
+            xml + "</defNumberVector>\n"
+        };
+        let switches = |name: &str, label: &str, rule: &str, elements: &[&str], selected: &str| {
+            let mut xml = format!(
+                "<defSwitchVector device=\"{device}\" name=\"{name}\" label=\"{label}\" group=\"{GROUP}\" \
+                 state=\"Idle\" perm=\"rw\" rule=\"{rule}\" timeout=\"60\">"
+            );
+            for element in elements {
+                xml.push_str(&format!(
+                    "<defSwitch name=\"{element}\">{}</defSwitch>",
+                    on_off(*element == selected)
+                ));
+            }
+            xml + "</defSwitchVector>\n"
+        };
+        let slew_rates: Vec<&str> = SLEW_RATES.iter().map(|(switch, _)| *switch).collect();
+        let slew_rate = SLEW_RATES
+            .iter()
+            .find(|(_, rate)| *rate == self.slew_rate)
+            .map_or("", |(switch, _)| *switch);
+
+        out.write_all(
+            numbers(
+                "EQUATORIAL_EOD_COORD",
+                "Eq. Coordinates",
+                &[
+                    ("RA", "%010.6m", 0.0, 24.0),
+                    ("DEC", "%010.6m", -90.0, 90.0),
+                ],
+            )
+            .as_bytes(),
+        )?;
+        out.write_all(
+            numbers(
+                "HORIZONTAL_COORD",
+                "Horizontal Coordinates",
+                &[
+                    ("AZ", "%010.6m", 0.0, 360.0),
+                    ("ALT", "%010.6m", -90.0, 90.0),
+                ],
+            )
+            .as_bytes(),
+        )?;
+        out.write_all(
+            switches(
+                "ON_COORD_SET",
+                "On Set",
+                "OneOfMany",
+                &["TRACK", "SLEW", "SYNC"],
+                self.coord_set,
+            )
+            .as_bytes(),
+        )?;
+        out.write_all(
+            switches(
+                "TELESCOPE_TRACK_STATE",
+                "Tracking",
+                "OneOfMany",
+                &["TRACK_ON", "TRACK_OFF"],
+                "TRACK_OFF",
+            )
+            .as_bytes(),
+        )?;
+        out.write_all(
+            switches(
+                "TELESCOPE_ABORT_MOTION",
+                "Abort Motion",
+                "AtMostOne",
+                &["ABORT"],
+                "",
+            )
+            .as_bytes(),
+        )?;
+        out.write_all(
+            switches(
+                "TELESCOPE_MOTION_NS",
+                "Motion N/S",
+                "AtMostOne",
+                &["MOTION_NORTH", "MOTION_SOUTH"],
+                "",
+            )
+            .as_bytes(),
+        )?;
+        out.write_all(
+            switches(
+                "TELESCOPE_MOTION_WE",
+                "Motion W/E",
+                "AtMostOne",
+                &["MOTION_WEST", "MOTION_EAST"],
+                "",
+            )
+            .as_bytes(),
+        )?;
+        out.write_all(
+            switches(
+                "TELESCOPE_SLEW_RATE",
+                "Slew Rate",
+                "OneOfMany",
+                &slew_rates,
+                slew_rate,
+            )
+            .as_bytes(),
+        )?;
+        writeln!(
+            out,
+            "<defTextVector device=\"{device}\" name=\"TIME_UTC\" label=\"UTC\" group=\"Site Management\" \
+             state=\"Idle\" perm=\"rw\" timeout=\"60\">\
+             <defText name=\"UTC\"></defText><defText name=\"OFFSET\">0</defText></defTextVector>"
+        )
+    }
+
+    /// Sends a `set*Vector` of `kind` with the given elements, or just the state if `kind` is empty.
+    fn set<W: Write>(
+        &self,
+        out: &mut W,
+        name: &str,
+        state: &str,
+        message: Option<&str>,
+        kind: &str,
+        elements: &[(&str, String)],
+    ) -> Result<(), io::Error> {
+        // A state change alone still needs a vector type; any works for clients keyed on the name.
+        let kind = if kind.is_empty() {
+            vector_kind(name)
+        } else {
+            kind
+        };
+        let mut xml = format!(
+            "<set{kind}Vector device=\"{}\" name=\"{name}\" state=\"{state}\"",
+            escape(self.device.as_str())
+        );
+        if let Some(message) = message {
+            xml.push_str(&format!(" message=\"{}\"", escape(message)));
+        }
+        xml.push('>');
+        for (element, value) in elements {
+            xml.push_str(&format!(
+                "<one{kind} name=\"{element}\">{}</one{kind}>",
+                escape(value.as_str())
+            ));
+        }
+        xml.push_str(&format!("</set{kind}Vector>"));
+        writeln!(out, "{xml}")
+    }
+
+    fn set_numbers<W: Write>(
+        &self,
+        out: &mut W,
+        name: &str,
+        state: &str,
+        values: &[(&str, f64)],
+    ) -> Result<(), io::Error> {
+        let values: Vec<(&str, String)> = values.iter().map(|(e, v)| (*e, v.to_string())).collect();
+        self.set(out, name, state, None, "Number", &values)
+    }
+
+    /// Sends switch vector `name` with `selected` on and the other `elements` off.
+    fn set_switches<W: Write>(
+        &self,
+        out: &mut W,
+        name: &str,
+        state: &str,
+        elements: &[&str],
+        selected: &str,
+    ) -> Result<(), io::Error> {
+        let values: Vec<(&str, String)> = elements
+            .iter()
+            .map(|e| (*e, on_off(*e == selected).to_owned()))
+            .collect();
+        self.set(out, name, state, None, "Switch", &values)
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+/// Type of one of the driver's properties.
+fn vector_kind(name: &str) -> &'static str {
+    match name {
+        "EQUATORIAL_EOD_COORD" | "HORIZONTAL_COORD" => "Number",
+        "TIME_UTC" | "DRIVER_INFO" => "Text",
+        _ => "Switch",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::SimMount;
+    use chrono::{TimeZone, Utc};
+    use std::sync::{Arc, Mutex};
+
+    /// Output buffer shared with the test, since `run` takes ownership of its writer.
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn serves_sim_mount() {
+        let input = "<getProperties version=\"1.7\"/>\
+            <newSwitchVector device=\"nexlib\" name=\"CONNECTION\"><oneSwitch name=\"CONNECT\">On</oneSwitch>\
+            </newSwitchVector>\
+            <newSwitchVector device=\"nexlib\" name=\"ON_COORD_SET\"><oneSwitch name=\"SYNC\">On</oneSwitch>\
+            </newSwitchVector>\
+            <newNumberVector device=\"nexlib\" name=\"EQUATORIAL_EOD_COORD\">\
+            <oneNumber name=\"RA\">5:30</oneNumber><oneNumber name=\"DEC\">-20.25</oneNumber></newNumberVector>\
+            <newSwitchVector device=\"nexlib\" name=\"TELESCOPE_TRACK_STATE\">\
+            <oneSwitch name=\"TRACK_ON\">On</oneSwitch></newSwitchVector>\
+            <newSwitchVector device=\"Other\" name=\"TELESCOPE_TRACK_STATE\">\
+            <oneSwitch name=\"TRACK_OFF\">On</oneSwitch></newSwitchVector>\
+            <newTextVector device=\"nexlib\" name=\"TIME_UTC\"><oneText name=\"UTC\">nonsense</oneText></newTextVector>";
+
+        let mut driver = IndiDriver::new("nexlib", || {
+            Ok(SimMount::new().manual_clock(Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap()))
+        })
+        .poll_interval(Duration::from_secs(3600));
+        let output = Output::default();
+        driver.run(input.as_bytes(), output.clone()).unwrap();
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+
+        assert!(output.contains("<defSwitchVector device=\"nexlib\" name=\"CONNECTION\""));
+        assert!(output.contains("<defNumberVector device=\"nexlib\" name=\"EQUATORIAL_EOD_COORD\""));
+        assert!(output.contains(
+            "<oneSwitch name=\"TRACK_ON\">On</oneSwitch><oneSwitch name=\"TRACK_OFF\">Off"
+        ));
+        assert!(
+            output.contains("<setTextVector device=\"nexlib\" name=\"TIME_UTC\" state=\"Alert\"")
+        );
+
+        let mount = driver.mount().unwrap();
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!((pos.ra - 82.5).abs() < 0.01 && (pos.dec + 20.25).abs() < 0.01);
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::EQNorth);
+    }
+}
@@ -0,0 +1,223 @@
+//! Per-command transaction timing.
+//!
+//! [`CelestronMount`](super::CelestronMount) times every transaction, from writing the command until the complete
+//! response has been read. Comparing commands helps place the blame for a slow session: hand control commands the
+//! controller answers itself ('V', 'm', 'J') measure the link and adapter, while passthrough commands also include
+//! the hand control relaying to a motor controller and back. Uniformly slow commands point at the adapter or the
+//! host, rather than the mount.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::time::Duration;
+
+/// Samples kept per command; older samples are discarded.
+const MAX_SAMPLES: usize = 1024;
+
+/// A command type, as timed by [`CelestronMount::latency_stats`](super::CelestronMount::latency_stats).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Command {
+    /// A command answered by the hand control, identified by its command character.
+    HandControl(u8),
+    /// A command relayed to a device inside the mount.
+    Passthrough { device: u8, command: u8 },
+}
+
+impl Command {
+    /// Identifies the command of an encoded message.
+    pub fn of(msg: &[u8]) -> Command {
+        match msg {
+            [b'P', _, device, command, _, _, _, _] => Command::Passthrough {
+                device: *device,
+                command: *command,
+            },
+            _ => Command::HandControl(msg.first().copied().unwrap_or(0)),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::HandControl(cmd) => write!(f, "'{}'", cmd.escape_ascii()),
+            Command::Passthrough { device, command } => write!(f, "P {device}/{command}"),
+        }
+    }
+}
+
+/// Latency of one command type over the recent successful transactions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// Number of transactions the statistics cover.
+    pub count: usize,
+    pub min: Duration,
+    pub mean: Duration,
+    /// 95th percentile.
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+impl fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "n={} min={:?} mean={:?} p95={:?} max={:?}",
+            self.count, self.min, self.mean, self.p95, self.max
+        )
+    }
+}
+
+impl LatencyStats {
+    /// Summarizes `samples`, which must not be empty.
+    fn from_samples<'a>(samples: impl IntoIterator<Item = &'a Duration>) -> LatencyStats {
+        let mut sorted: Vec<Duration> = samples.into_iter().copied().collect();
+        sorted.sort();
+
+        let count = sorted.len();
+        let total: Duration = sorted.iter().sum();
+        // Nearest-rank percentile.
+        let rank = (count * 95).div_ceil(100).max(1);
+
+        LatencyStats {
+            count,
+            min: sorted[0],
+            mean: total / count as u32,
+            p95: sorted[rank - 1],
+            max: sorted[count - 1],
+        }
+    }
+}
+
+/// Shrinks each command's response timeout to a multiple of its observed latency, so a healthy link fails fast when
+/// a command goes unanswered instead of waiting out the full port timeout.
+///
+/// Commands keep the port timeout until `min_samples` transactions have been timed, and never wait less than
+/// `floor`. When a command times out, its samples are discarded so its next attempt waits the full port timeout
+/// again; a command which has become slower relearns its latency instead of failing repeatedly.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AdaptiveTimeout {
+    /// Shortest timeout to use.
+    pub floor: Duration,
+    /// Timeout as a multiple of the 95th percentile latency.
+    pub factor: f64,
+    /// Transactions to time before shrinking a command's timeout.
+    pub min_samples: usize,
+}
+
+impl Default for AdaptiveTimeout {
+    fn default() -> Self {
+        AdaptiveTimeout {
+            floor: Duration::from_millis(250),
+            factor: 3.0,
+            min_samples: 10,
+        }
+    }
+}
+
+impl AdaptiveTimeout {
+    /// Timeout for a command with `stats`, or `None` to use the port timeout.
+    pub fn timeout(&self, stats: Option<LatencyStats>) -> Option<Duration> {
+        let stats = stats.filter(|s| s.count >= self.min_samples)?;
+        Some(stats.p95.mul_f64(self.factor).max(self.floor))
+    }
+}
+
+/// Recent transaction times per command.
+#[derive(Debug, Default)]
+pub(crate) struct LatencyRecorder {
+    samples: HashMap<Command, VecDeque<Duration>>,
+}
+
+impl LatencyRecorder {
+    pub(crate) fn record(&mut self, command: Command, elapsed: Duration) {
+        let samples = self.samples.entry(command).or_default();
+        if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(elapsed);
+    }
+
+    pub(crate) fn stats(&self) -> BTreeMap<Command, LatencyStats> {
+        self.samples
+            .iter()
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|(command, samples)| (*command, LatencyStats::from_samples(samples)))
+            .collect()
+    }
+
+    pub(crate) fn stats_for(&self, command: Command) -> Option<LatencyStats> {
+        self.samples
+            .get(&command)
+            .filter(|samples| !samples.is_empty())
+            .map(LatencyStats::from_samples)
+    }
+
+    pub(crate) fn forget(&mut self, command: Command) {
+        self.samples.remove(&command);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let mut recorder = LatencyRecorder::default();
+        for ms in 1..=100 {
+            recorder.record(Command::HandControl(b'e'), Duration::from_millis(ms));
+        }
+        recorder.record(
+            Command::of(&[b'P', 1, 16, 254, 0, 0, 0, 2]),
+            Duration::from_millis(40),
+        );
+
+        let stats = recorder.stats();
+        let e = stats[&Command::HandControl(b'e')];
+        assert_eq!(e.count, 100);
+        assert_eq!(e.min, Duration::from_millis(1));
+        assert_eq!(e.mean, Duration::from_micros(50_500));
+        assert_eq!(e.p95, Duration::from_millis(95));
+        assert_eq!(e.max, Duration::from_millis(100));
+
+        let version = Command::Passthrough {
+            device: 16,
+            command: 254,
+        };
+        assert_eq!(stats[&version].p95, Duration::from_millis(40));
+        assert_eq!(version.to_string(), "P 16/254");
+        assert_eq!(Command::HandControl(b'e').to_string(), "'e'");
+    }
+
+    #[test]
+    fn adaptive_timeout() {
+        let adaptive = AdaptiveTimeout::default();
+        let mut recorder = LatencyRecorder::default();
+        let e = Command::HandControl(b'e');
+
+        for _ in 0..adaptive.min_samples - 1 {
+            recorder.record(e, Duration::from_millis(200));
+        }
+        assert_eq!(adaptive.timeout(recorder.stats_for(e)), None);
+
+        recorder.record(e, Duration::from_millis(200));
+        assert_eq!(
+            adaptive.timeout(recorder.stats_for(e)),
+            Some(Duration::from_millis(600))
+        );
+
+        recorder.forget(e);
+        recorder.record(e, Duration::from_millis(10));
+        assert_eq!(adaptive.timeout(recorder.stats_for(e)), None);
+        for _ in 0..adaptive.min_samples {
+            recorder.record(e, Duration::from_millis(10));
+        }
+        assert_eq!(
+            adaptive.timeout(recorder.stats_for(e)),
+            Some(adaptive.floor)
+        );
+    }
+}
@@ -0,0 +1,572 @@
+//! Named pointing limit profiles for different ways of setting up a mount.
+//!
+//! The safe envelope of a mount depends on how it is set up: on an alt-az tripod only the elevation matters, on an
+//! equatorial wedge the tube may hit the base past some hour angle, and on a pier the limits differ again. A
+//! [`LimitProfile`] describes one envelope and the tracking modes which make sense with it, and a [`LimitedMount`]
+//! checks every goto against the selected profile, refusing targets outside it with `InvalidInput` before the mount
+//! moves. Switching profiles at runtime is checked against the current tracking mode, so an equatorial profile cannot
+//! be selected while tracking in alt-az.
+//!
+//! [`Config::connect`](crate::config::Config::connect) wraps every configured mount in one. Through any [`Mount`],
+//! [`Mount::select_limit_profile`] switches the profile and [`Mount::limit_profiles`] lists them, so the daemon and
+//! the servers offer them as [`rpc`](crate::rpc) methods too, and `nexctl limits` switches the daemon's profile.
+//!
+//! Profiles are kept in the `[limits.profiles]` section of the [configuration](crate::config):
+//!
+//! ```toml
+//! [limits]
+//! profile = "wedge"
+//!
+//! [limits.profiles.wedge]
+//! min_elevation = 15.0
+//! min_hour_angle = -95.0
+//! max_hour_angle = 95.0
+//! tracking_modes = ["EQNorth"]
+//! max_slew_rate = 2.0
+//!
+//! # The pier is in the north.
+//! [[limits.profiles.wedge.keep_out]]
+//! min_azimuth = 340.0
+//! max_azimuth = 20.0
+//! min_elevation = 30.0
+//! ```
+//!
+//! Manual slews faster than the profile's maximum slew rate are refused. A manual slew has no end point to check, so
+//! call [`LimitedMount::poll`] periodically while slewing, e.g. alongside status polling, to stop it where it leaves
+//! the envelope.
+
+use super::SIDEREAL_RATE;
+use super::error::MountError;
+use super::transform::{az_el_to_ha_dec, ha_dec_to_az_el, local_sidereal_time, wrap_180};
+use super::{
+    AzEl, Gps, GuideDirection, Guider, Location, Model, Mount, NonGpsDevice, RADec, SlewAxis,
+    SlewDir, SlewRate, TrackingMode,
+};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::io;
+
+/// The safe envelope of one setup.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LimitProfile {
+    /// Lowest elevation the mount may point to, in degrees.
+    pub min_elevation: f64,
+    /// Highest elevation the mount may point to, in degrees.
+    pub max_elevation: f64,
+    /// Lowest hour angle the mount may point to, in degrees, east negative.
+    pub min_hour_angle: Option<f64>,
+    /// Highest hour angle the mount may point to, in degrees, west positive.
+    pub max_hour_angle: Option<f64>,
+    /// Tracking modes which may be used; any if empty. Tracking can always be turned off.
+    pub tracking_modes: Vec<TrackingMode>,
+    /// Azimuth ranges where the mount must point higher than elsewhere.
+    pub keep_out: Vec<KeepOut>,
+    /// Fastest manual slew allowed, in degrees per second.
+    pub max_slew_rate: Option<f64>,
+}
+
+/// A range of azimuth where the mount must point higher than [`LimitProfile::min_elevation`], e.g. over a pier, a
+/// tripod leg, or a wall.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeepOut {
+    /// Azimuth where the range starts, in degrees.
+    pub min_azimuth: f64,
+    /// Azimuth where the range ends, in degrees, clockwise from `min_azimuth` and so less than it if the range
+    /// crosses north.
+    pub max_azimuth: f64,
+    /// Lowest elevation the mount may point to within the range, in degrees; 90 keeps out of it entirely.
+    pub min_elevation: f64,
+}
+
+impl KeepOut {
+    /// Whether the range includes azimuth `az`.
+    pub fn contains(&self, az: f64) -> bool {
+        let az = az.rem_euclid(360.0);
+        let (min, max) = (
+            self.min_azimuth.rem_euclid(360.0),
+            self.max_azimuth.rem_euclid(360.0),
+        );
+        if min <= max {
+            (min..=max).contains(&az)
+        } else {
+            az >= min || az <= max
+        }
+    }
+}
+
+/// Nominal speed of a hand control slew rate, in degrees per second.
+fn nominal_rate(rate: SlewRate) -> f64 {
+    match rate {
+        SlewRate::Stop => 0.0,
+        SlewRate::Rate1 => 2.0 * SIDEREAL_RATE,
+        SlewRate::Rate2 => 4.0 * SIDEREAL_RATE,
+        SlewRate::Rate3 => 8.0 * SIDEREAL_RATE,
+        SlewRate::Rate4 => 16.0 * SIDEREAL_RATE,
+        SlewRate::Rate5 => 32.0 * SIDEREAL_RATE,
+        SlewRate::Rate6 => 0.3,
+        SlewRate::Rate7 => 1.0,
+        SlewRate::Rate8 => 2.0,
+        SlewRate::Rate9 => 4.0,
+    }
+}
+
+impl Default for LimitProfile {
+    fn default() -> Self {
+        LimitProfile {
+            min_elevation: 0.0,
+            max_elevation: 90.0,
+            min_hour_angle: None,
+            max_hour_angle: None,
+            tracking_modes: Vec::new(),
+            keep_out: Vec::new(),
+            max_slew_rate: None,
+        }
+    }
+}
+
+fn outside(what: String) -> MountError {
+    MountError::other(io::ErrorKind::InvalidInput, what)
+}
+
+impl LimitProfile {
+    /// Checks a position given in both horizontal coordinates and hour angle, in degrees.
+    pub fn check(&self, az_el: AzEl, hour_angle: f64) -> Result<(), MountError> {
+        if az_el.el < self.min_elevation || az_el.el > self.max_elevation {
+            return Err(outside(format!(
+                "Elevation {:.1}° is outside the limits of {}° to {}°.",
+                az_el.el, self.min_elevation, self.max_elevation
+            )));
+        }
+        let ha = wrap_180(hour_angle);
+        if self.min_hour_angle.is_some_and(|min| ha < min)
+            || self.max_hour_angle.is_some_and(|max| ha > max)
+        {
+            return Err(outside(format!(
+                "Hour angle {ha:.1}° is outside the limits of {}° to {}°.",
+                self.min_hour_angle.unwrap_or(-180.0),
+                self.max_hour_angle.unwrap_or(180.0)
+            )));
+        }
+        if let Some(zone) = self
+            .keep_out
+            .iter()
+            .find(|z| z.contains(az_el.az) && az_el.el < z.min_elevation)
+        {
+            return Err(outside(format!(
+                "Elevation {:.1}° at azimuth {:.1}° is below the limit of {}° from {}° to {}°.",
+                az_el.el, az_el.az, zone.min_elevation, zone.min_azimuth, zone.max_azimuth
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks a manual slew at `rate` degrees per second against the maximum slew rate.
+    pub fn check_slew_rate(&self, rate: f64) -> Result<(), MountError> {
+        match self.max_slew_rate {
+            Some(max) if rate > max => Err(outside(format!(
+                "Slew rate {rate:.2}°/s is above the limit of {max}°/s."
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the profile may be used while tracking in `mode`.
+    pub fn allows_tracking(&self, mode: TrackingMode) -> bool {
+        mode == TrackingMode::Off
+            || self.tracking_modes.is_empty()
+            || self.tracking_modes.contains(&mode)
+    }
+}
+
+/// The limit profiles of a mount and the one selected; see [`Mount::limit_profiles`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfileSelection {
+    pub selected: Option<String>,
+    pub profiles: Vec<String>,
+}
+
+/// A mount whose gotos are checked against the selected [`LimitProfile`]; see [`limits`](self).
+#[derive(Debug)]
+pub struct LimitedMount<M> {
+    mount: M,
+    profiles: BTreeMap<String, LimitProfile>,
+    selected: Option<String>,
+    latitude: f64,
+    longitude: f64,
+    /// Whether each axis is slewing manually.
+    slewing: [bool; 2],
+}
+
+impl<M: Mount> LimitedMount<M> {
+    /// Wraps `mount` at the site at `latitude` and `longitude` with the named `profiles`, none selected.
+    pub fn new(
+        mount: M,
+        profiles: BTreeMap<String, LimitProfile>,
+        latitude: f64,
+        longitude: f64,
+    ) -> LimitedMount<M> {
+        LimitedMount {
+            mount,
+            profiles,
+            selected: None,
+            latitude,
+            longitude,
+            slewing: [false; 2],
+        }
+    }
+
+    /// Selects the profile in use, or none for no limits.
+    ///
+    /// Fails with `NotFound` for an unknown name, and with `InvalidInput` if the profile does not allow the current
+    /// tracking mode, in which case the previous profile stays selected.
+    pub fn select(&mut self, name: Option<&str>) -> Result<(), MountError> {
+        if let Some(name) = name {
+            let profile = self.profiles.get(name).ok_or_else(|| {
+                MountError::other(
+                    io::ErrorKind::NotFound,
+                    format!("No limit profile {name:?}."),
+                )
+            })?;
+            let mode = self.mount.get_tracking_mode()?;
+            if !profile.allows_tracking(mode) {
+                return Err(MountError::other(
+                    io::ErrorKind::InvalidInput,
+                    format!("Limit profile {name:?} does not allow tracking mode {mode:?}."),
+                ));
+            }
+        }
+        self.selected = name.map(str::to_string);
+        Ok(())
+    }
+
+    /// The profile in use.
+    pub fn selected(&self) -> Option<&str> {
+        self.selected.as_deref()
+    }
+
+    pub fn profiles(&self) -> &BTreeMap<String, LimitProfile> {
+        &self.profiles
+    }
+
+    /// The wrapped mount.
+    pub fn inner(&mut self) -> &mut M {
+        &mut self.mount
+    }
+
+    /// Checks that `coord` is within the selected profile now, as a goto to it would. Fails with `InvalidInput` if
+    /// it is not.
+    pub fn validate_target(&self, coord: RADec) -> Result<(), MountError> {
+        self.validate_target_at(coord, Utc::now())
+    }
+
+    /// Checks that `coord` is within the selected profile at `time`, e.g. to plan when a target may be visited.
+    pub fn validate_target_at(&self, coord: RADec, time: DateTime<Utc>) -> Result<(), MountError> {
+        match self.profile() {
+            Some(profile) => {
+                let ha = local_sidereal_time(time, self.longitude) - coord.ra;
+                profile.check(ha_dec_to_az_el(ha, coord.dec, self.latitude), ha)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Stops a manual slew which has left the selected profile's envelope, returning whether it did.
+    pub fn poll(&mut self) -> Result<bool, MountError> {
+        if self.profile().is_none() || !self.slewing.contains(&true) {
+            return Ok(false);
+        }
+
+        let az_el = self.mount.get_position_az_el()?;
+        let (ha, _) = az_el_to_ha_dec(az_el, self.latitude);
+        let checked = self
+            .profile()
+            .map_or(Ok(()), |profile| profile.check(az_el, ha));
+        if let Err(e) = checked {
+            log::warn!("Stopping manual slew: {e}");
+            self.stop_slew(SlewAxis::RAAz)?;
+            self.stop_slew(SlewAxis::DecEl)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn check_slew_rate(&self, rate: f64) -> Result<(), MountError> {
+        self.profile()
+            .map_or(Ok(()), |profile| profile.check_slew_rate(rate))
+    }
+
+    fn profile(&self) -> Option<&LimitProfile> {
+        self.selected
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+    }
+}
+
+impl<M: Mount> Mount for LimitedMount<M> {
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        self.mount.get_position_ra_dec()
+    }
+
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        self.mount.get_position_az_el()
+    }
+
+    /// Fails with `InvalidInput` if the target is currently outside the limits.
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.validate_target(coord)?;
+        self.mount.goto_ra_dec(coord)
+    }
+
+    /// Fails with `InvalidInput` if the target is outside the limits.
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        if let Some(profile) = self.profile() {
+            let (ha, _) = az_el_to_ha_dec(coord, self.latitude);
+            profile.check(coord, ha)?;
+        }
+        self.mount.goto_az_el(coord)
+    }
+
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.mount.sync(coord)
+    }
+
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        self.mount.get_tracking_mode()
+    }
+
+    /// Fails with `InvalidInput` if the selected profile does not allow `mode`.
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        if let Some(name) = &self.selected {
+            if self.profile().is_some_and(|p| !p.allows_tracking(mode)) {
+                return Err(MountError::other(
+                    io::ErrorKind::InvalidInput,
+                    format!("Limit profile {name:?} does not allow tracking mode {mode:?}."),
+                ));
+            }
+        }
+        self.mount.set_tracking_mode(mode)
+    }
+
+    /// Fails with `InvalidInput` if `rate` is above the maximum slew rate.
+    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), MountError> {
+        self.check_slew_rate(rate as f64 / 3600.0)?;
+        self.mount.slew_variable(axis, dir, rate)?;
+        self.slewing[axis as usize] = rate != 0;
+        Ok(())
+    }
+
+    /// Fails with `InvalidInput` if the nominal speed of `rate` is above the maximum slew rate.
+    fn slew_fixed(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        self.check_slew_rate(nominal_rate(rate))?;
+        self.mount.slew_fixed(axis, dir, rate)?;
+        self.slewing[axis as usize] = rate != SlewRate::Stop;
+        Ok(())
+    }
+
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        self.mount.get_location()
+    }
+
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        self.mount.set_location(location)
+    }
+
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        self.mount.get_time()
+    }
+
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        self.mount.set_time(time)
+    }
+
+    fn get_version(&mut self) -> Result<String, MountError> {
+        self.mount.get_version()
+    }
+
+    fn get_device_version(&mut self, device: NonGpsDevice) -> Result<String, MountError> {
+        self.mount.get_device_version(device)
+    }
+
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        self.mount.get_model()
+    }
+
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        self.mount.echo(byte)
+    }
+
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        self.mount.is_aligned()
+    }
+
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        self.mount.goto_in_progress()
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.mount.cancel_goto()
+    }
+
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
+        self.mount.stop_slew(axis)?;
+        self.slewing[axis as usize] = false;
+        Ok(())
+    }
+
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
+        self.mount.get_gps()
+    }
+
+    fn select_limit_profile(&mut self, name: Option<&str>) -> Result<(), MountError> {
+        self.select(name)
+    }
+
+    fn limit_profiles(&mut self) -> Result<ProfileSelection, MountError> {
+        Ok(ProfileSelection {
+            selected: self.selected.clone(),
+            profiles: self.profiles.keys().cloned().collect(),
+        })
+    }
+}
+
+
+impl<M: Guider> Guider for LimitedMount<M> {
+    fn set_guide_rate(&mut self, axis: SlewAxis, rate: f64) -> Result<(), MountError> {
+        self.mount.set_guide_rate(axis, rate)
+    }
+
+    fn get_guide_rate(&mut self, axis: SlewAxis) -> Result<f64, MountError> {
+        self.mount.get_guide_rate(axis)
+    }
+
+    fn pulse_guide(&mut self, direction: GuideDirection, duration_ms: u32) -> Result<(), MountError> {
+        self.mount.pulse_guide(direction, duration_ms)
+    }
+
+    fn is_pulse_guiding(&mut self) -> Result<bool, MountError> {
+        self.mount.is_pulse_guiding()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use chrono::TimeZone;
+    use std::time::Duration;
+
+    #[test]
+    fn enforces_selected_profile() {
+        let profiles = BTreeMap::from([
+            (
+                "tripod".to_string(),
+                LimitProfile {
+                    min_elevation: 10.0,
+                    max_elevation: 80.0,
+                    tracking_modes: vec![TrackingMode::AzEl],
+                    ..LimitProfile::default()
+                },
+            ),
+            (
+                "wedge".to_string(),
+                LimitProfile {
+                    min_hour_angle: Some(-90.0),
+                    max_hour_angle: Some(90.0),
+                    tracking_modes: vec![TrackingMode::EQNorth],
+                    ..LimitProfile::default()
+                },
+            ),
+        ]);
+        let mut mount = LimitedMount::new(SimMount::new(), profiles, 45.0, 0.0);
+        let below = AzEl::new(180.0, 5.0);
+        mount.goto_az_el(below).unwrap();
+
+        mount.select(Some("tripod")).unwrap();
+        mount.set_tracking_mode(TrackingMode::AzEl).unwrap();
+        let e = mount.goto_az_el(below).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        mount.goto_az_el(AzEl::new(180.0, 45.0)).unwrap();
+
+        // Not while tracking in alt-az.
+        assert!(mount.select_limit_profile(Some("wedge")).is_err());
+        assert_eq!(mount.selected(), Some("tripod"));
+        assert!(mount.select(Some("pier")).is_err());
+
+        mount.set_tracking_mode(TrackingMode::Off).unwrap();
+        mount.select_limit_profile(Some("wedge")).unwrap();
+        assert_eq!(
+            mount.limit_profiles().unwrap(),
+            ProfileSelection {
+                selected: Some("wedge".to_string()),
+                profiles: vec!["tripod".to_string(), "wedge".to_string()],
+            }
+        );
+        assert!(mount.set_tracking_mode(TrackingMode::AzEl).is_err());
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        // Low but in the east.
+        mount.goto_az_el(ha_dec_to_az_el(-60.0, 0.0, 45.0)).unwrap();
+        // High but far past the meridian.
+        let e = mount
+            .goto_az_el(ha_dec_to_az_el(120.0, 70.0, 45.0))
+            .unwrap_err();
+        assert!(e.to_string().contains("Hour angle"), "{e}");
+    }
+
+    #[test]
+    fn keeps_out_and_limits_slews() {
+        let pier = LimitProfile {
+            keep_out: vec![KeepOut {
+                min_azimuth: 340.0,
+                max_azimuth: 20.0,
+                min_elevation: 30.0,
+            }],
+            max_slew_rate: Some(2.0),
+            ..LimitProfile::default()
+        };
+        let profiles = BTreeMap::from([("pier".to_string(), pier)]);
+        let time = Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap();
+        let mut mount = LimitedMount::new(SimMount::new().manual_clock(time), profiles, 45.0, 0.0);
+        mount.select(Some("pier")).unwrap();
+
+        assert!(mount.goto_az_el(AzEl::new(10.0, 20.0)).is_err());
+        mount.goto_az_el(AzEl::new(10.0, 40.0)).unwrap();
+        mount.goto_az_el(AzEl::new(90.0, 20.0)).unwrap();
+        // Low in the north, and high in the south.
+        let lst = local_sidereal_time(time, 0.0);
+        assert!(mount
+            .validate_target_at(RADec::new(lst - 180.0, 60.0), time)
+            .is_err());
+        mount
+            .validate_target_at(RADec::new(lst, 0.0), time)
+            .unwrap();
+
+        assert!(mount
+            .slew_fixed(SlewAxis::DecEl, SlewDir::Negative, SlewRate::Rate9)
+            .is_err());
+        assert!(mount
+            .slew_variable(SlewAxis::DecEl, SlewDir::Negative, 9000)
+            .is_err());
+        while mount.goto_in_progress().unwrap() {
+            mount.inner().step(Duration::from_secs(1));
+        }
+        assert!(!mount.poll().unwrap());
+        mount
+            .slew_fixed(SlewAxis::DecEl, SlewDir::Negative, SlewRate::Rate7)
+            .unwrap();
+        mount.inner().step(Duration::from_secs(1));
+        assert!(!mount.poll().unwrap());
+        // Until it turns the tube below the horizon.
+        mount.inner().step(Duration::from_secs(60));
+        assert!(mount.poll().unwrap());
+        assert!(!mount.poll().unwrap());
+    }
+}
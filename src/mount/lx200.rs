@@ -0,0 +1,514 @@
+//! [`Mount`] backend for mounts speaking the Meade LX200 serial command set.
+//!
+//! Besides Meade's own LX200 and Autostar mounts, the command set is understood by OnStep, 10Micron, Losmandy Gemini,
+//! and many other controllers. Commands are ASCII, start with `:` and end with `#`; replies are either one character,
+//! such as `1` for an accepted value, or text ending with `#`. A goto sets the target with `:Sr` and `:Sd`, then
+//! starts the slew with `:MS#`:
+//!
+//! ```no_run
+//! use nexlib::mount::lx200::Lx200Mount;
+//! use nexlib::{Mount, RADec};
+//! use std::time::Duration;
+//!
+//! let mut mount = Lx200Mount::open("/dev/ttyUSB0", Duration::from_secs(2)).unwrap();
+//! println!("{} {}", mount.get_product_name().unwrap(), mount.get_version().unwrap());
+//! mount.goto_ra_dec(RADec::new(83.82, -5.39)).unwrap();
+//! ```
+//!
+//! The protocol has no equivalent of a few NexStar commands:
+//!
+//! - The alignment mode stands in for the tracking mode: polar tracks equatorially, alt-az tracks in alt-az, and land
+//!   does not track. The hemisphere is taken from the site latitude.
+//! - Targets for alt-az gotos, and the site, are set to the nearest arcminute.
+//! - Fixed rate slews map rates 1-9 onto the four LX200 rates, guide, centering, find, and slew; variable rate slews,
+//!   device versions, and GPS passthrough are not available.
+
+use super::codec::Framing;
+use super::error::MountError;
+use super::{
+    read_framed, CelestronMount, Gps, Location, Model, Mount, NonGpsDevice, SlewAxis, SlewDir,
+    SlewRate, TrackingMode,
+};
+use crate::{AzEl, RADec};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+use log::trace;
+use serialport::{ClearBuffer, SerialPort};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// The acknowledge byte, answered with the alignment mode: `A` alt-az, `P` polar, or `L` land.
+const ACK: &str = "\x06";
+
+/// Parses an angle or time such as `05:35:17`, `-05*23'28`, or the low precision `05:35.3`, in degrees or hours.
+///
+/// Any non-digit separates fields, including the degree sign LX200 mounts send as byte `0xDF`.
+pub fn parse_sexagesimal(res: &[u8]) -> Option<f64> {
+    let text: String = res.iter().map(|&b| b as char).collect();
+    let text = text.trim();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let fields = text
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .filter(|f| !f.is_empty())
+        .map(|f| f.parse::<f64>().ok())
+        .collect::<Option<Vec<f64>>>()?;
+    let value = match fields[..] {
+        [d] => d,
+        [d, m] => d + m / 60.0,
+        [d, m, s] => d + m / 60.0 + s / 3600.0,
+        _ => return None,
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// Splits `value` into whole units, minutes, and seconds, rounded to the nearest second.
+fn sexagesimal(value: f64) -> (u32, u32, u32) {
+    let secs = (value.abs() * 3600.0).round() as u32;
+    (secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Formats hours of right ascension as `HH:MM:SS`.
+fn format_hours(hours: f64) -> String {
+    let (h, m, s) = sexagesimal(hours.rem_euclid(24.0));
+    format!("{:02}:{m:02}:{s:02}", h % 24)
+}
+
+/// Formats degrees as `sDD*MM:SS`.
+fn format_signed(deg: f64) -> String {
+    let (d, m, s) = sexagesimal(deg);
+    let sign = if deg < 0.0 { '-' } else { '+' };
+    format!("{sign}{d:02}*{m:02}:{s:02}")
+}
+
+/// Formats degrees to the nearest arcminute, as `sDD*MM` if `signed`, otherwise `DDD*MM`.
+fn format_arcmin(deg: f64, signed: bool) -> String {
+    let minutes = (deg.abs() * 60.0).round() as u32;
+    let (d, m) = (minutes / 60, minutes % 60);
+    if signed {
+        let sign = if deg < 0.0 { '-' } else { '+' };
+        format!("{sign}{d:02}*{m:02}")
+    } else {
+        format!("{:03}*{m:02}", d % 360)
+    }
+}
+
+/// A mount speaking the LX200 protocol; see [`lx200`](self).
+#[derive(Debug)]
+pub struct Lx200Mount {
+    port: Box<dyn SerialPort>,
+    /// The last response; reused between commands.
+    recv: Vec<u8>,
+    /// Whether positions have been switched to high precision, or already were.
+    precise: bool,
+}
+
+impl Lx200Mount {
+    /// Opens the mount on `port`, e.g. `/dev/ttyUSB0` or `COM3`, at 9600 baud.
+    pub fn open(port: &str, timeout: Duration) -> Result<Lx200Mount, io::Error> {
+        Ok(Self::from_port(CelestronMount::open_port(port, timeout)?))
+    }
+
+    /// Uses an already open port.
+    pub fn from_port(port: Box<dyn SerialPort>) -> Lx200Mount {
+        Lx200Mount {
+            port,
+            recv: Vec::with_capacity(32),
+            precise: false,
+        }
+    }
+
+    /// The product name, e.g. `LX200 GPS` or `Autostar`.
+    pub fn get_product_name(&mut self) -> Result<String, MountError> {
+        self.text(":GVP#")
+    }
+
+    /// Gets the site location, to the nearest arcminute.
+    pub fn get_site(&mut self) -> Result<Location, MountError> {
+        let latitude = self.angle(":Gt#")?;
+        // LX200 longitudes are positive west.
+        let longitude = -self.angle(":Gg#")?;
+        Ok(Location {
+            latitude,
+            longitude: (longitude + 180.0).rem_euclid(360.0) - 180.0,
+        })
+    }
+
+    /// Sets the site location, to the nearest arcminute.
+    pub fn set_site(&mut self, location: Location) -> Result<(), MountError> {
+        self.set(&format!(":St{}#", format_arcmin(location.latitude, true)))?;
+        let west = (-location.longitude).rem_euclid(360.0);
+        self.set(&format!(":Sg{}#", format_arcmin(west, false)))
+    }
+
+    /// Writes `cmd` and reads a response of `framing`, if any.
+    fn transact(&mut self, cmd: &str, framing: Option<Framing>) -> Result<&[u8], MountError> {
+        trace!("TRANSMITTED: {:?}", cmd);
+        self.port.clear(ClearBuffer::Input)?;
+        self.port.write_all(cmd.as_bytes())?;
+
+        self.recv.clear();
+        if let Some(framing) = framing {
+            let deadline = Instant::now() + self.port.timeout();
+            read_framed(&mut *self.port, &mut self.recv, framing, deadline)?;
+            trace!("RECEIVED: {:?}", self.recv);
+        }
+        Ok(&self.recv)
+    }
+
+    /// Sends a command without a reply.
+    fn send(&mut self, cmd: &str) -> Result<(), MountError> {
+        self.transact(cmd, None).map(|_| ())
+    }
+
+    /// Sends a command answered with one character.
+    fn char(&mut self, cmd: &str) -> Result<u8, MountError> {
+        Ok(self.transact(cmd, Some(Framing::Length(1)))?[0])
+    }
+
+    /// Sends a command answered with text ending in `#`, which is not returned.
+    fn reply(&mut self, cmd: &str) -> Result<&[u8], MountError> {
+        let res = self.transact(cmd, Some(Framing::Terminator))?;
+        Ok(&res[..res.len() - 1])
+    }
+
+    fn text(&mut self, cmd: &str) -> Result<String, MountError> {
+        Ok(String::from_utf8_lossy(self.reply(cmd)?).trim().to_owned())
+    }
+
+    fn angle(&mut self, cmd: &str) -> Result<f64, MountError> {
+        let res = self.reply(cmd)?;
+        parse_sexagesimal(res).ok_or_else(|| MountError::InvalidResponse {
+            cmd: None,
+            bytes: res.to_vec(),
+        })
+    }
+
+    /// Sends a command setting a value, which the mount answers `1` if accepted.
+    fn set(&mut self, cmd: &str) -> Result<(), MountError> {
+        match self.char(cmd)? {
+            b'1' => Ok(()),
+            _ => Err(MountError::other(
+                io::ErrorKind::InvalidInput,
+                format!("The mount rejected {cmd}"),
+            )),
+        }
+    }
+
+    /// Starts a slew to the target set, with `:MS#` or `:MA#`, which the mount answers `0` or an error message.
+    fn slew(&mut self, cmd: &str) -> Result<(), MountError> {
+        match self.char(cmd)? {
+            b'0' => Ok(()),
+            code => {
+                let deadline = Instant::now() + self.port.timeout();
+                // The message is informative only, so a missing one is not an error of its own.
+                let _ = read_framed(
+                    &mut *self.port,
+                    &mut self.recv,
+                    Framing::Terminator,
+                    deadline,
+                );
+                let msg = String::from_utf8_lossy(&self.recv[1..]);
+                Err(MountError::other(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "The mount refused to slew ({}): {}",
+                        code as char,
+                        msg.trim_end_matches('#').trim()
+                    ),
+                ))
+            }
+        }
+    }
+
+    fn set_target(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.set(&format!(":Sr{}#", format_hours(coord.ra / 15.0)))?;
+        self.set(&format!(":Sd{}#", format_signed(coord.dec)))
+    }
+
+    /// Enables high precision positions if the mount reports low precision ones.
+    fn ensure_precise(&mut self) -> Result<(), MountError> {
+        if !self.precise {
+            if self.reply(":GR#")?.contains(&b'.') {
+                self.send(":U#")?;
+            }
+            self.precise = true;
+        }
+        Ok(())
+    }
+
+    fn alignment_mode(&mut self) -> Result<u8, MountError> {
+        self.char(ACK)
+    }
+
+    /// Hours to add to local time to get UTC.
+    fn utc_offset(&mut self) -> Result<f64, MountError> {
+        self.angle(":GG#")
+    }
+
+    fn motion(axis: SlewAxis) -> [&'static str; 2] {
+        match axis {
+            SlewAxis::RAAz => ["e", "w"],
+            SlewAxis::DecEl => ["n", "s"],
+        }
+    }
+
+    fn unsupported(what: &str) -> MountError {
+        MountError::unsupported(format!(
+            "{what} is not available through the LX200 protocol."
+        ))
+    }
+}
+
+impl Mount for Lx200Mount {
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        self.ensure_precise()?;
+        let ra = self.angle(":GR#")?;
+        let dec = self.angle(":GD#")?;
+        Ok(RADec::new(ra * 15.0, dec))
+    }
+
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        self.ensure_precise()?;
+        let az = self.angle(":GZ#")?;
+        let el = self.angle(":GA#")?;
+        Ok(AzEl::new(az, el))
+    }
+
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.set_target(coord)?;
+        self.slew(":MS#")
+    }
+
+    /// The target is set to the nearest arcminute.
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        self.set(&format!(
+            ":Sz{}#",
+            format_arcmin(coord.az.rem_euclid(360.0), false)
+        ))?;
+        self.set(&format!(":Sa{}#", format_arcmin(coord.el, true)))?;
+        self.slew(":MA#")
+    }
+
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.set_target(coord)?;
+        self.reply(":CM#").map(|_| ())
+    }
+
+    /// The alignment mode; see [`lx200`](self).
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        match self.alignment_mode()? {
+            b'L' => Ok(TrackingMode::Off),
+            b'A' => Ok(TrackingMode::AzEl),
+            b'P' if self.get_site()?.latitude < 0.0 => Ok(TrackingMode::EQSouth),
+            b'P' => Ok(TrackingMode::EQNorth),
+            mode => Err(MountError::InvalidResponse {
+                cmd: None,
+                bytes: vec![mode],
+            }),
+        }
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        match mode {
+            TrackingMode::Off => self.send(":AL#"),
+            TrackingMode::AzEl => self.send(":AA#"),
+            TrackingMode::EQNorth | TrackingMode::EQSouth => self.send(":AP#"),
+        }
+    }
+
+    fn slew_variable(
+        &mut self,
+        _axis: SlewAxis,
+        _dir: SlewDir,
+        _rate: u16,
+    ) -> Result<(), MountError> {
+        Err(Self::unsupported("Variable rate slewing"))
+    }
+
+    /// Maps rates 1-9 proportionally onto the guide, centering, find, and slew rates.
+    fn slew_fixed(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        if rate == SlewRate::Stop {
+            return self.stop_slew(axis);
+        }
+
+        let rates = ["G", "C", "M", "S"];
+        self.send(&format!(
+            ":R{}#",
+            rates[(rate as usize - 1) * rates.len() / 9]
+        ))?;
+        let [positive, negative] = Self::motion(axis);
+        match dir {
+            SlewDir::Positive => self.send(&format!(":M{positive}#")),
+            SlewDir::Negative => self.send(&format!(":M{negative}#")),
+        }
+    }
+
+    /// Gets the site location; see [`Lx200Mount::get_site`].
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        self.get_site()
+    }
+
+    /// Sets the site location; see [`Lx200Mount::set_site`].
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        self.set_site(location)
+    }
+
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        let offset = self.utc_offset()?;
+        let time = self.text(":GL#")?;
+        let date = self.text(":GC#")?;
+        let invalid = |_| MountError::InvalidResponse {
+            cmd: None,
+            bytes: format!("{date} {time}").into_bytes(),
+        };
+        let local = NaiveDate::parse_from_str(&date, "%m/%d/%y")
+            .map_err(invalid)?
+            .and_time(NaiveTime::parse_from_str(&time, "%H:%M:%S").map_err(invalid)?);
+        Ok(local.and_utc() + TimeDelta::seconds((offset * 3600.0).round() as i64))
+    }
+
+    /// Sets the current time on the mount, in the time zone it is already set to.
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        let offset = self.utc_offset()?;
+        let local = time - TimeDelta::seconds((offset * 3600.0).round() as i64);
+        self.set(&format!(":SL{}#", local.format("%H:%M:%S")))?;
+        // Meade mounts follow the date with messages while they update planetary data, discarded before the next
+        // command.
+        self.set(&format!(":SC{}#", local.format("%m/%d/%y")))
+    }
+
+    /// The firmware version, e.g. `4.2g`.
+    fn get_version(&mut self) -> Result<String, MountError> {
+        self.text(":GVN#")
+    }
+
+    fn get_device_version(&mut self, _device: NonGpsDevice) -> Result<String, MountError> {
+        Err(Self::unsupported("Device versions"))
+    }
+
+    /// LX200 mounts have no Celestron model; see [`Lx200Mount::get_product_name`].
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        Err(Self::unsupported("The Celestron model"))
+    }
+
+    /// The LX200 protocol has no echo, so the byte is returned once the mount answers the acknowledge byte.
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        self.alignment_mode().map(|_| byte)
+    }
+
+    /// LX200 mounts manage their own alignment, so a responding mount is always considered aligned.
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        self.alignment_mode().map(|_| true)
+    }
+
+    /// The distance bars of `:D#` are shown while slewing.
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        Ok(!self.reply(":D#")?.is_empty())
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.send(":Q#")
+    }
+
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
+        let [positive, negative] = Self::motion(axis);
+        self.send(&format!(":Q{positive}#"))?;
+        self.send(&format!(":Q{negative}#"))
+    }
+
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
+        Err(Self::unsupported("GPS passthrough"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::session::{EventKind, ReplayPort};
+
+    #[test]
+    fn speaks_lx200() {
+        assert_eq!(
+            parse_sexagesimal(b"05:35:17"),
+            Some(5.0 + 35.0 / 60.0 + 17.0 / 3600.0)
+        );
+        let dec = parse_sexagesimal(b"-05\xDF23'30").unwrap();
+        assert!((dec + 5.0 + 23.5 / 60.0).abs() < 1e-9);
+        assert_eq!(parse_sexagesimal(b"05:35.3"), Some(5.0 + 35.3 / 60.0));
+        assert_eq!(parse_sexagesimal(b"+12.0"), Some(12.0));
+        assert_eq!(parse_sexagesimal(b"x"), None);
+        assert_eq!(format_hours(23.999_999), "00:00:00");
+        assert_eq!(format_signed(-5.3911), "-05*23:28");
+        assert_eq!(format_arcmin(284.75, false), "284*45");
+
+        let exchange = |cmd: &str, res: &[u8]| {
+            let mut events = vec![EventKind::Write(cmd.as_bytes().to_vec())];
+            if !res.is_empty() {
+                events.push(EventKind::Read(res.to_vec()));
+            }
+            events
+        };
+        let events = [
+            // Low precision at first.
+            exchange(":GR#", b"05:35.3#"),
+            exchange(":U#", b""),
+            exchange(":GR#", b"05:35:17#"),
+            exchange(":GD#", b"-05\xDF23:28#"),
+            exchange(":Sr05:35:17#", b"1"),
+            exchange(":Sd-05*23:28#", b"1"),
+            exchange(":MS#", b"1Object Below Horizon#"),
+            exchange(":D#", b"\x7F\x7F#"),
+            exchange(":D#", b"#"),
+            exchange("\x06", b"P"),
+            exchange(":Gt#", b"-33\xDF52#"),
+            exchange(":Gg#", b"208\xDF47#"),
+            exchange(":GG#", b"-10#"),
+            exchange(":GL#", b"20:30:00#"),
+            exchange(":GC#", b"03/21/24#"),
+            exchange(":Gt#", b"-33\xDF52#"),
+            exchange(":Gg#", b"341\xDF45#"),
+            exchange(":St-33*30#", b"1"),
+            exchange(":Sg341*45#", b"1"),
+        ]
+        .concat();
+        let port = ReplayPort::scripted(events);
+        let mut mount = Lx200Mount::from_port(Box::new(port.clone()));
+
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!((pos.ra - 83.820_833).abs() < 1e-5, "{pos}");
+        assert!((pos.dec + 5.391_111).abs() < 1e-5, "{pos}");
+
+        let e = mount.goto_ra_dec(pos).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert!(e.to_string().ends_with("Object Below Horizon"), "{e}");
+        assert!(mount.goto_in_progress().unwrap());
+        assert!(!mount.goto_in_progress().unwrap());
+
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::EQSouth);
+        assert_eq!(
+            mount.get_time().unwrap(),
+            "2024-03-21T10:30:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        let site = mount.get_location().unwrap();
+        assert!(
+            (site.latitude + 33.0 + 52.0 / 60.0).abs() < 1e-9,
+            "{site:?}"
+        );
+        assert!((site.longitude - 18.25).abs() < 1e-9, "{site:?}");
+        mount
+            .set_location(Location {
+                latitude: -33.5,
+                longitude: 18.25,
+            })
+            .unwrap();
+        assert_eq!(port.remaining(), 0);
+    }
+}
@@ -0,0 +1,270 @@
+//! Meridian flips for German equatorial mounts.
+//!
+//! A German equatorial mount points at any target from one of two sides of the pier. After a goto to a target east of
+//! the meridian the tube is on the west side, and as the target is tracked across the meridian the tube swings
+//! towards the pier. Some way past the meridian it must be flipped to the other side, by a goto to the same target,
+//! which the firmware then approaches from the east side.
+//!
+//! A [`MeridianFlipper`] watches a tracking mount with [`MeridianFlipper::poll`], and once the target is past the
+//! meridian by [`MeridianPolicy::flip_after`] flips it, calling back before the flip, e.g. to stop guiding and
+//! exposures, and after, e.g. to re-center and resume them:
+//!
+//! ```no_run
+//! use nexlib::mount::meridian::{FlipState, MeridianFlipper, MeridianPolicy};
+//! use nexlib::{CelestronMount, Location};
+//! use std::time::Duration;
+//!
+//! let mut mount = CelestronMount::new().unwrap();
+//! let site = Location { latitude: 40.0, longitude: -75.0 };
+//! let mut flipper = MeridianFlipper::new(site, MeridianPolicy::default())
+//!     .before_flip(|target| println!("Flipping to {target}"))
+//!     .after_flip(|target| println!("Flipped to {target}"));
+//! loop {
+//!     if flipper.poll(&mut mount).unwrap() == FlipState::Flipped {
+//!         break;
+//!     }
+//!     std::thread::sleep(Duration::from_secs(10));
+//! }
+//! ```
+
+use super::error::MountError;
+use super::transform::{local_sidereal_time, wrap_180};
+use super::{CelestronMount, Location, Mount, RADec, TrackingMode};
+use crate::mount::SIDEREAL_RATE;
+
+/// Which side of the pier the tube is on, in the ASCOM sense of the mount's pointing state.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PierSide {
+    /// The normal pointing state, with the declination axis within 90° of the pole: the tube is east of the pier,
+    /// pointing at targets west of the meridian.
+    East,
+    /// Pointing through the pole: the tube is west of the pier, pointing at targets east of the meridian.
+    West,
+}
+
+impl PierSide {
+    /// The side of a declination axis `angle` degrees from the equator, as the motor controller reports it.
+    pub fn from_dec_axis(angle: f64) -> PierSide {
+        if wrap_180(angle).abs() <= 90.0 {
+            PierSide::East
+        } else {
+            PierSide::West
+        }
+    }
+
+    /// The side a goto to hour angle `ha`, in degrees, approaches from.
+    pub fn for_hour_angle(ha: f64) -> PierSide {
+        if wrap_180(ha) < 0.0 {
+            PierSide::West
+        } else {
+            PierSide::East
+        }
+    }
+}
+
+/// A mount which can tell which side of the pier it is on.
+pub trait SideOfPier {
+    fn get_pier_side(&mut self) -> Result<PierSide, MountError>;
+}
+
+impl SideOfPier for CelestronMount {
+    /// From the position of the declination motor.
+    fn get_pier_side(&mut self) -> Result<PierSide, MountError> {
+        Ok(PierSide::from_dec_axis(self.get_motor_positions()?[1]))
+    }
+}
+
+/// When to flip; see [`meridian`](self).
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct MeridianPolicy {
+    /// Hour angle past the meridian at which to flip, in degrees; 2.5° is ten minutes of tracking.
+    pub flip_after: f64,
+    /// Whether to flip when it is due, or only report it.
+    pub auto_flip: bool,
+}
+
+impl Default for MeridianPolicy {
+    fn default() -> Self {
+        MeridianPolicy {
+            flip_after: 2.5,
+            auto_flip: true,
+        }
+    }
+}
+
+/// What [`MeridianFlipper::poll`] found.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlipState {
+    /// Not tracking, or not past the meridian yet.
+    NotDue,
+    /// A flip is due but automatic flips are off.
+    Due,
+    /// A flip is in progress.
+    Flipping,
+    /// A flip has just finished.
+    Flipped,
+}
+
+type Callback<'a> = Box<dyn FnMut(RADec) + Send + 'a>;
+
+/// Flips a tracking mount when [`MeridianPolicy`] says to; see [`meridian`](self).
+pub struct MeridianFlipper<'a> {
+    site: Location,
+    policy: MeridianPolicy,
+    before: Option<Callback<'a>>,
+    after: Option<Callback<'a>>,
+    /// Target of the flip in progress.
+    flipping: Option<RADec>,
+}
+
+impl std::fmt::Debug for MeridianFlipper<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MeridianFlipper")
+            .field("site", &self.site)
+            .field("policy", &self.policy)
+            .field("flipping", &self.flipping)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> MeridianFlipper<'a> {
+    /// A flipper for a mount at `site`.
+    pub fn new(site: Location, policy: MeridianPolicy) -> MeridianFlipper<'a> {
+        MeridianFlipper {
+            site,
+            policy,
+            before: None,
+            after: None,
+            flipping: None,
+        }
+    }
+
+    /// Calls `f` with the target before starting a flip.
+    pub fn before_flip(mut self, f: impl FnMut(RADec) + Send + 'a) -> MeridianFlipper<'a> {
+        self.before = Some(Box::new(f));
+        self
+    }
+
+    /// Calls `f` with the target once a flip has finished.
+    pub fn after_flip(mut self, f: impl FnMut(RADec) + Send + 'a) -> MeridianFlipper<'a> {
+        self.after = Some(Box::new(f));
+        self
+    }
+
+    pub fn policy(&self) -> MeridianPolicy {
+        self.policy
+    }
+
+    /// Tracking time until a flip is due, zero if it is already, or `None` if none will be because the mount is not
+    /// tracking equatorially or is already on the east side of the pier.
+    pub fn time_to_flip<M: Mount + SideOfPier>(
+        &self,
+        mount: &mut M,
+    ) -> Result<Option<chrono::Duration>, MountError> {
+        if !matches!(
+            mount.get_tracking_mode()?,
+            TrackingMode::EQNorth | TrackingMode::EQSouth
+        ) || mount.get_pier_side()? == PierSide::East
+        {
+            return Ok(None);
+        }
+        let time = mount.get_time()?;
+        let ha = wrap_180(
+            local_sidereal_time(time, self.site.longitude) - mount.get_position_ra_dec()?.ra,
+        );
+        let degrees = (self.policy.flip_after - ha).max(0.0);
+        Ok(Some(chrono::Duration::milliseconds(
+            (degrees / SIDEREAL_RATE * 1000.0) as i64,
+        )))
+    }
+
+    /// Checks whether a flip is due, starting it if automatic flips are on, and whether one in progress has finished.
+    pub fn poll<M: Mount + SideOfPier>(&mut self, mount: &mut M) -> Result<FlipState, MountError> {
+        if let Some(target) = self.flipping {
+            if mount.goto_in_progress()? {
+                return Ok(FlipState::Flipping);
+            }
+            self.flipping = None;
+            log::info!("Meridian flip to {target} finished.");
+            if let Some(after) = &mut self.after {
+                after(target);
+            }
+            return Ok(FlipState::Flipped);
+        }
+
+        if self.time_to_flip(mount)? != Some(chrono::Duration::zero()) {
+            return Ok(FlipState::NotDue);
+        }
+        if !self.policy.auto_flip {
+            return Ok(FlipState::Due);
+        }
+        self.flip(mount)?;
+        Ok(FlipState::Flipping)
+    }
+
+    /// Flips now, by a goto to the current position.
+    pub fn flip<M: Mount>(&mut self, mount: &mut M) -> Result<(), MountError> {
+        let target = mount.get_position_ra_dec()?;
+        if let Some(before) = &mut self.before {
+            before(target);
+        }
+        log::info!("Meridian flip to {target}.");
+        mount.goto_ra_dec(target)?;
+        self.flipping = Some(target);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use chrono::{TimeZone, Utc};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn flips_past_meridian() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 20, 3, 0, 0).unwrap();
+        let mut mount = SimMount::new().manual_clock(time).site(40.0, -75.0);
+        let site = Location {
+            latitude: 40.0,
+            longitude: -75.0,
+        };
+        let lst = local_sidereal_time(time, site.longitude);
+        // An hour east of the meridian.
+        mount
+            .goto_ra_dec(RADec::new((lst + 15.0).rem_euclid(360.0), 30.0))
+            .unwrap();
+        while mount.goto_in_progress().unwrap() {
+            mount.step(Duration::from_secs(1));
+        }
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let (before, after) = (Arc::clone(&calls), Arc::clone(&calls));
+        let mut flipper = MeridianFlipper::new(site, MeridianPolicy::default())
+            .before_flip(move |_| before.lock().unwrap().push("before"))
+            .after_flip(move |_| after.lock().unwrap().push("after"));
+
+        assert_eq!(mount.get_pier_side().unwrap(), PierSide::West);
+        // Not tracking.
+        assert_eq!(flipper.time_to_flip(&mut mount).unwrap(), None);
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        let wait = flipper.time_to_flip(&mut mount).unwrap().unwrap();
+        assert!((wait.num_seconds() - 4190).abs() < 30, "{wait}");
+        assert_eq!(flipper.poll(&mut mount).unwrap(), FlipState::NotDue);
+
+        mount.step(wait.to_std().unwrap() + Duration::from_secs(60));
+        assert_eq!(flipper.poll(&mut mount).unwrap(), FlipState::Flipping);
+        assert_eq!(*calls.lock().unwrap(), ["before"]);
+        while flipper.poll(&mut mount).unwrap() == FlipState::Flipping {
+            mount.step(Duration::from_secs(1));
+        }
+        assert_eq!(*calls.lock().unwrap(), ["before", "after"]);
+        assert_eq!(mount.get_pier_side().unwrap(), PierSide::East);
+        assert_eq!(flipper.poll(&mut mount).unwrap(), FlipState::NotDue);
+    }
+}
@@ -0,0 +1,128 @@
+//! Operational counters.
+//!
+//! [`CelestronMount::metrics`](super::CelestronMount::metrics) returns a snapshot of counters that only ever
+//! increase over the life of the mount. [`Metrics::to_prometheus`] renders a snapshot in the Prometheus text
+//! exposition format, for serving from any HTTP endpoint a Prometheus server scrapes.
+
+use std::fmt::Write;
+use std::io;
+
+/// Counters of serial traffic and failures.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metrics {
+    /// Commands sent to the hand control.
+    pub commands: u64,
+    pub bytes_written: u64,
+    pub bytes_read: u64,
+    /// Commands the hand control did not answer in time.
+    pub timeouts: u64,
+    /// Malformed or unexpected responses.
+    pub protocol_errors: u64,
+    /// Commands a device inside the mount did not answer.
+    pub device_errors: u64,
+    /// Other serial port failures.
+    pub io_errors: u64,
+    /// Commands sent again after a failure.
+    pub retries: u64,
+    /// Times the serial port was reopened.
+    pub reconnects: u64,
+}
+
+impl Metrics {
+    /// Counts a failure by its category.
+    pub(crate) fn record_error(&mut self, e: &io::Error) {
+        match e.kind() {
+            io::ErrorKind::TimedOut => self.timeouts += 1,
+            io::ErrorKind::InvalidData => self.protocol_errors += 1,
+            io::ErrorKind::NotConnected => self.device_errors += 1,
+            _ => self.io_errors += 1,
+        }
+    }
+
+    /// Total failures in all categories.
+    pub fn errors(&self) -> u64 {
+        self.timeouts + self.protocol_errors + self.device_errors + self.io_errors
+    }
+
+    /// Renders the counters in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut counter = |name: &str, help: &str, samples: &[(&str, u64)]| {
+            let _ = writeln!(out, "# HELP nexlib_{name} {help}");
+            let _ = writeln!(out, "# TYPE nexlib_{name} counter");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "nexlib_{name}{labels} {value}");
+            }
+        };
+
+        counter(
+            "commands_total",
+            "Commands sent to the hand control.",
+            &[("", self.commands)],
+        );
+        counter(
+            "bytes_total",
+            "Bytes transferred over the serial port.",
+            &[
+                ("{direction=\"write\"}", self.bytes_written),
+                ("{direction=\"read\"}", self.bytes_read),
+            ],
+        );
+        counter(
+            "errors_total",
+            "Failed commands by category.",
+            &[
+                ("{category=\"timeout\"}", self.timeouts),
+                ("{category=\"protocol\"}", self.protocol_errors),
+                ("{category=\"device\"}", self.device_errors),
+                ("{category=\"io\"}", self.io_errors),
+            ],
+        );
+        counter(
+            "retries_total",
+            "Commands sent again after a failure.",
+            &[("", self.retries)],
+        );
+        counter(
+            "reconnects_total",
+            "Times the serial port was reopened.",
+            &[("", self.reconnects)],
+        );
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::session::{EventKind, ReplayPort};
+    use crate::mount::{CelestronMount, Mount};
+
+    #[test]
+    fn counts_traffic_and_errors() {
+        let events = [
+            EventKind::Write(b"m".to_vec()),
+            EventKind::Read(vec![20, b'#']),
+            EventKind::Write(b"J".to_vec()),
+            EventKind::Error(io::ErrorKind::TimedOut),
+        ];
+        let port = ReplayPort::scripted(events);
+        let mut mount = CelestronMount::from_port(Box::new(port));
+
+        mount.get_model().unwrap();
+        mount.is_aligned().unwrap_err();
+
+        let metrics = mount.metrics();
+        assert_eq!(metrics.commands, 2);
+        assert_eq!(metrics.bytes_written, 2);
+        assert_eq!(metrics.bytes_read, 2);
+        assert_eq!(metrics.timeouts, 1);
+        assert_eq!(metrics.errors(), 1);
+
+        let text = metrics.to_prometheus();
+        assert!(text.contains("nexlib_commands_total 2\n"));
+        assert!(text.contains("nexlib_errors_total{category=\"timeout\"} 1\n"));
+    }
+}
@@ -0,0 +1,220 @@
+//! A shared background poller of the mount's status.
+//!
+//! GUIs and loggers each need the position, goto state, and tracking mode at a steady rate. Rather than each running
+//! its own polling loop, [`MountMonitor::spawn`] starts one background thread reading a [`MountStatus`] from a
+//! [`StatusCache`] at a fixed interval, and any number of consumers call [`MountMonitor::subscribe`] for a
+//! [`Subscription`] receiving every update over a channel. Sharing the cache with the servers and other frontends
+//! keeps the serial traffic bounded across all of them:
+//!
+//! ```no_run
+//! use nexlib::mount::monitor::MountMonitor;
+//! use nexlib::mount::status::StatusCache;
+//! use nexlib::CelestronMount;
+//! use std::sync::{Arc, Mutex};
+//! use std::time::Duration;
+//!
+//! let mount = Arc::new(Mutex::new(CelestronMount::new().unwrap()));
+//! let cache = StatusCache::new(mount, Duration::from_millis(500));
+//! let monitor = MountMonitor::spawn(cache, Duration::from_millis(500));
+//!
+//! for update in monitor.subscribe().take(10) {
+//!     match update {
+//!         Ok(s) => println!("{} goto: {} tracking: {:?}", s.ra_dec, s.goto_in_progress, s.tracking_mode),
+//!         Err(e) => eprintln!("Failed to read the status: {e}"),
+//!     }
+//! }
+//! ```
+//!
+//! Unlike a [`position_stream`](super::stream::position_stream), which runs while any stream is left, the monitor
+//! runs until it is stopped or dropped, and subscriptions end with it. Use [`MountMonitor::latest`] to read the last
+//! status without subscribing.
+
+use super::status::{MountStatus, StatusCache};
+use super::Mount;
+use std::io;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Updates held for each subscriber before newer ones are dropped, so a stalled consumer cannot grow memory.
+const BUFFER: usize = 64;
+
+type Update = io::Result<MountStatus>;
+
+#[derive(Debug, Default)]
+struct Shared {
+    subscribers: Mutex<Vec<SyncSender<Update>>>,
+    latest: Mutex<Option<MountStatus>>,
+}
+
+/// Polls a shared mount in the background and publishes each status; see [`monitor`](self).
+#[derive(Debug)]
+pub struct MountMonitor {
+    shared: Arc<Shared>,
+    interval: Duration,
+    /// Dropped to stop the poller, which waits on it between polls.
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MountMonitor {
+    /// Polls the status from `cache` every `interval`.
+    ///
+    /// The mount stays usable by others between polls, which only hold its lock while reading one status.
+    pub fn spawn<M>(cache: StatusCache<M>, interval: Duration) -> MountMonitor
+    where
+        M: Mount + Send + 'static,
+    {
+        let shared = Arc::new(Shared::default());
+        let (stop, stopped) = mpsc::channel();
+        let thread = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || poll(&cache, &shared, interval, &stopped))
+        };
+        MountMonitor {
+            shared,
+            interval,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Starts receiving updates, beginning with the next one.
+    pub fn subscribe(&self) -> Subscription {
+        let (tx, updates) = mpsc::sync_channel(BUFFER);
+        self.shared.subscribers.lock().unwrap().push(tx);
+        Subscription { updates }
+    }
+
+    /// The last status read successfully, if any.
+    pub fn latest(&self) -> Option<MountStatus> {
+        *self.shared.latest.lock().unwrap()
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Number of subscriptions still open.
+    pub fn subscribers(&self) -> usize {
+        self.shared.subscribers.lock().unwrap().len()
+    }
+
+    /// Stops polling and waits for the poller to finish, ending every subscription.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        self.shared.subscribers.lock().unwrap().clear();
+    }
+}
+
+impl Drop for MountMonitor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn poll<M: Mount>(cache: &StatusCache<M>, shared: &Shared, interval: Duration, stopped: &Receiver<()>) {
+    let mut next = Instant::now();
+    loop {
+        let update = cache.get();
+        if let Ok(status) = &update {
+            *shared.latest.lock().unwrap() = Some(*status);
+        }
+
+        shared.subscribers.lock().unwrap().retain(|tx| {
+            let update = match &update {
+                Ok(s) => Ok(*s),
+                Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+            };
+            match tx.try_send(update) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    log::trace!("Mount monitor subscriber is behind; dropping an update.");
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+
+        // Keep to the interval regardless of how long the reads take, without bursting to catch up after a stall.
+        next = (next + interval).max(Instant::now());
+        match stopped.recv_timeout(next.saturating_duration_since(Instant::now())) {
+            Err(RecvTimeoutError::Timeout) => (),
+            _ => break,
+        }
+    }
+}
+
+/// Updates from a [`MountMonitor`], iterated until the monitor stops.
+///
+/// A failed read yields an error and polling carries on.
+#[derive(Debug)]
+pub struct Subscription {
+    updates: Receiver<Update>,
+}
+
+impl Subscription {
+    /// Waits at most `timeout` for the next update.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<Update> {
+        self.updates.recv_timeout(timeout).ok()
+    }
+
+    /// Gets the next update if one has already arrived.
+    pub fn try_next(&mut self) -> Option<Update> {
+        self.updates.try_recv().ok()
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = Update;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.updates.recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::{SimMount, TrackingMode};
+
+    #[test]
+    fn publishes_until_stopped() {
+        let mount = Arc::new(Mutex::new(SimMount::new()));
+        let cache = StatusCache::new(Arc::clone(&mount), Duration::from_millis(20));
+        let monitor = MountMonitor::spawn(cache.clone(), Duration::from_millis(20));
+        let mut gui = monitor.subscribe();
+        let logger = monitor.subscribe();
+
+        let timeout = Duration::from_secs(5);
+        let first = gui.next_timeout(timeout).unwrap().unwrap();
+        assert_eq!(first.tracking_mode, TrackingMode::Off);
+        mount
+            .lock()
+            .unwrap()
+            .set_tracking_mode(TrackingMode::EQNorth)
+            .unwrap();
+        let tracking = gui
+            .by_ref()
+            .map(Result::unwrap)
+            .find(|s| s.tracking_mode == TrackingMode::EQNorth)
+            .unwrap();
+        assert!(tracking.time > first.time);
+        assert!(monitor.latest().is_some());
+
+        drop(logger);
+        monitor.stop();
+        // The subscription ends once any buffered updates are read.
+        assert!(gui.count() < BUFFER + 1);
+        drop(cache);
+        assert_eq!(Arc::strong_count(&mount), 1);
+    }
+}
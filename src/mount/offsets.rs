@@ -0,0 +1,296 @@
+//! Pointing offsets between optical configurations.
+//!
+//! A main camera, an eyepiece, and a guide scope on the same mount never point at exactly the same spot, so a mount
+//! synced through one is off by a small fixed amount through the others. An [`OffsetMount`] keeps a named
+//! [`PointingOffset`] per configuration and applies the selected one to every equatorial goto, sync, and position,
+//! so switching from imaging to visual is a call to [`OffsetMount::select`] instead of another sync.
+//!
+//! To calibrate a configuration, center a known object through it and call [`OffsetMount::calibrate`]. The offsets
+//! are kept in the `[optics.offsets]` section of the [configuration](crate::config), with the one in use named by
+//! `optics.selected`:
+//!
+//! ```no_run
+//! use nexlib::mount::offsets::OffsetMount;
+//! use nexlib::mount::Mount;
+//! use nexlib::{CelestronMount, RADec};
+//! use std::collections::BTreeMap;
+//!
+//! let mut mount = OffsetMount::new(CelestronMount::new().unwrap(), BTreeMap::new());
+//! // With Betelgeuse centered in the eyepiece:
+//! mount.calibrate("eyepiece", RADec::new(88.79, 7.41)).unwrap();
+//! mount.select(Some("eyepiece")).unwrap();
+//! mount.goto_ra_dec(RADec::new(83.82, -5.39)).unwrap();
+//! ```
+//!
+//! Offsets are differences of right ascension and declination, which are accurate for the small offsets between
+//! optics on one mount. Gotos in azimuth and elevation are not offset.
+
+use super::error::MountError;
+use super::limits::ProfileSelection;
+use super::{
+    AzEl, Gps, GuideDirection, Guider, Location, Model, Mount, NonGpsDevice, RADec, SlewAxis,
+    SlewDir, SlewRate, TrackingMode,
+};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::io;
+
+/// Where an optical configuration points relative to the mount's synced pointing.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointingOffset {
+    /// Right ascension offset, in arcseconds.
+    pub ra_arcsec: f64,
+    /// Declination offset, in arcseconds.
+    pub dec_arcsec: f64,
+}
+
+impl PointingOffset {
+    /// Where the optics point when the mount points at `coord`.
+    pub fn apply(&self, coord: RADec) -> RADec {
+        RADec::new(
+            (coord.ra + self.ra_arcsec / 3600.0).rem_euclid(360.0),
+            coord.dec + self.dec_arcsec / 3600.0,
+        )
+    }
+
+    /// Where the mount points when the optics point at `coord`.
+    pub fn remove(&self, coord: RADec) -> RADec {
+        RADec::new(
+            (coord.ra - self.ra_arcsec / 3600.0).rem_euclid(360.0),
+            coord.dec - self.dec_arcsec / 3600.0,
+        )
+    }
+}
+
+/// A mount applying the offset of the selected optical configuration; see [`offsets`](self).
+#[derive(Debug)]
+pub struct OffsetMount<M> {
+    mount: M,
+    offsets: BTreeMap<String, PointingOffset>,
+    selected: Option<String>,
+}
+
+impl<M: Mount> OffsetMount<M> {
+    /// Wraps `mount` with the named `offsets`, none selected.
+    pub fn new(mount: M, offsets: BTreeMap<String, PointingOffset>) -> OffsetMount<M> {
+        OffsetMount {
+            mount,
+            offsets,
+            selected: None,
+        }
+    }
+
+    /// Selects the configuration in use, or none for the mount's own pointing. Fails with `NotFound` for an unknown
+    /// name.
+    pub fn select(&mut self, name: Option<&str>) -> Result<(), io::Error> {
+        if let Some(name) = name {
+            if !self.offsets.contains_key(name) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No pointing offset for optics {name:?}."),
+                ));
+            }
+        }
+        self.selected = name.map(str::to_string);
+        Ok(())
+    }
+
+    /// The configuration in use.
+    pub fn selected(&self) -> Option<&str> {
+        self.selected.as_deref()
+    }
+
+    /// The offset in use, zero if none is selected.
+    pub fn offset(&self) -> PointingOffset {
+        self.selected
+            .as_ref()
+            .and_then(|name| self.offsets.get(name))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Every configuration's offset, e.g. to save to the configuration file.
+    pub fn offsets(&self) -> &BTreeMap<String, PointingOffset> {
+        &self.offsets
+    }
+
+    /// Stores the offset of configuration `name` from the mount's current pointing, with `actual` centered through
+    /// it. The mount should already be synced.
+    pub fn calibrate(&mut self, name: &str, actual: RADec) -> Result<PointingOffset, io::Error> {
+        let pointing = self.mount.get_position_ra_dec()?;
+        let offset = PointingOffset {
+            ra_arcsec: ((actual.ra - pointing.ra + 180.0).rem_euclid(360.0) - 180.0) * 3600.0,
+            dec_arcsec: (actual.dec - pointing.dec) * 3600.0,
+        };
+        self.offsets.insert(name.to_string(), offset);
+        Ok(offset)
+    }
+
+    /// The wrapped mount.
+    pub fn inner(&mut self) -> &mut M {
+        &mut self.mount
+    }
+}
+
+impl<M: Mount> Mount for OffsetMount<M> {
+    /// Where the selected optics point.
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        Ok(self.offset().apply(self.mount.get_position_ra_dec()?))
+    }
+
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        self.mount.get_position_az_el()
+    }
+
+    /// Centers `coord` in the selected optics.
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        let coord = self.offset().remove(coord);
+        self.mount.goto_ra_dec(coord)
+    }
+
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        self.mount.goto_az_el(coord)
+    }
+
+    /// Syncs on `coord` centered in the selected optics.
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        let coord = self.offset().remove(coord);
+        self.mount.sync(coord)
+    }
+
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        self.mount.get_tracking_mode()
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        self.mount.set_tracking_mode(mode)
+    }
+
+    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), MountError> {
+        self.mount.slew_variable(axis, dir, rate)
+    }
+
+    fn slew_fixed(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        self.mount.slew_fixed(axis, dir, rate)
+    }
+
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        self.mount.get_location()
+    }
+
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        self.mount.set_location(location)
+    }
+
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        self.mount.get_time()
+    }
+
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        self.mount.set_time(time)
+    }
+
+    fn get_version(&mut self) -> Result<String, MountError> {
+        self.mount.get_version()
+    }
+
+    fn get_device_version(&mut self, device: NonGpsDevice) -> Result<String, MountError> {
+        self.mount.get_device_version(device)
+    }
+
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        self.mount.get_model()
+    }
+
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        self.mount.echo(byte)
+    }
+
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        self.mount.is_aligned()
+    }
+
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        self.mount.goto_in_progress()
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.mount.cancel_goto()
+    }
+
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
+        self.mount.stop_slew(axis)
+    }
+
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
+        self.mount.get_gps()
+    }
+
+    fn select_limit_profile(&mut self, name: Option<&str>) -> Result<(), MountError> {
+        self.mount.select_limit_profile(name)
+    }
+
+    fn limit_profiles(&mut self) -> Result<ProfileSelection, MountError> {
+        self.mount.limit_profiles()
+    }
+}
+
+
+impl<M: Guider> Guider for OffsetMount<M> {
+    fn set_guide_rate(&mut self, axis: SlewAxis, rate: f64) -> Result<(), MountError> {
+        self.mount.set_guide_rate(axis, rate)
+    }
+
+    fn get_guide_rate(&mut self, axis: SlewAxis) -> Result<f64, MountError> {
+        self.mount.get_guide_rate(axis)
+    }
+
+    fn pulse_guide(&mut self, direction: GuideDirection, duration_ms: u32) -> Result<(), MountError> {
+        self.mount.pulse_guide(direction, duration_ms)
+    }
+
+    fn is_pulse_guiding(&mut self) -> Result<bool, MountError> {
+        self.mount.is_pulse_guiding()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use std::time::Duration;
+
+    #[test]
+    fn applies_selected_offset() {
+        let mut mount = OffsetMount::new(SimMount::new().manual_clock(Utc::now()), BTreeMap::new());
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        mount.goto_ra_dec(RADec::new(100.0, 20.0)).unwrap();
+        mount.inner().step(Duration::from_secs(600));
+        let pointing = mount.get_position_ra_dec().unwrap();
+        let offset = mount
+            .calibrate("guide", RADec::new(pointing.ra + 0.02, pointing.dec - 0.01))
+            .unwrap();
+        assert!((offset.ra_arcsec - 72.0).abs() < 1.0);
+        assert!((offset.dec_arcsec + 36.0).abs() < 1.0);
+        assert!(mount.select(Some("eyepiece")).is_err());
+
+        mount.select(Some("guide")).unwrap();
+        let target = RADec::new(120.0, 30.0);
+        mount.goto_ra_dec(target).unwrap();
+        mount.inner().step(Duration::from_secs(600));
+
+        let seen = mount.get_position_ra_dec().unwrap();
+        assert!((seen.ra - target.ra).abs() < 0.001 && (seen.dec - target.dec).abs() < 0.001);
+        let pointing = mount.inner().get_position_ra_dec().unwrap();
+        assert!((pointing.ra - (target.ra - 0.02)).abs() < 0.001);
+
+        mount.select(None).unwrap();
+        assert_eq!(mount.offset(), PointingOffset::default());
+    }
+}
@@ -0,0 +1,449 @@
+//! Observation plans: a list of targets, each visited in turn for a while.
+//!
+//! A [`Plan`] lists [`PlanEntry`]s, each a target with how long to stay on it and how to tell the mount has settled
+//! there. A [`PlanRunner`] works through it against any [`Mount`]: it starts the goto to each target, waits for the
+//! goto to finish and the mount to settle, calls back on arrival, dwells, calls back on departure, and moves on.
+//!
+//! Times are measured by the mount's clock, so [`PlanRunner::poll`] can be driven from an event loop, or by
+//! stepping a [`SimMount`](super::SimMount) in tests. [`PlanRunner::run`] polls until the plan is finished or aborted
+//! through a [`PlanAbort`] handle from another thread:
+//!
+//! ```no_run
+//! use nexlib::mount::plan::{Plan, PlanEntry, PlanRunner, PlanTarget};
+//! use nexlib::mount::TrackingMode;
+//! use nexlib::{CelestronMount, Location};
+//!
+//! let plan = Plan {
+//!     tracking: Some(TrackingMode::EQNorth),
+//!     entries: vec![
+//!         PlanEntry::new(PlanTarget::Named("M 13".to_owned()), 300.0),
+//!         PlanEntry::new(PlanTarget::Named("Ring Nebula".to_owned()), 300.0),
+//!     ],
+//! };
+//! let site = Location { latitude: 40.0, longitude: -75.0 };
+//! let mut runner = PlanRunner::new(plan, site).on_arrival(|i, entry| println!("{i}: at {:?}", entry.target));
+//! let mut mount = CelestronMount::new().unwrap();
+//! runner.run(&mut mount).unwrap();
+//! ```
+
+use super::transform::angular_separation;
+use super::{AzEl, Location, Mount, RADec, TrackingMode};
+use crate::catalog::{self, ephemeris::Body};
+use chrono::{DateTime, Utc};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Time between polls in [`PlanRunner::run`].
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Where to point for a [`PlanEntry`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PlanTarget {
+    /// A position of date, as the mount takes it.
+    RaDec(RADec),
+    AzEl(AzEl),
+    /// An object built into the [`catalog`], looked up with [`catalog::lookup`] and precessed to date.
+    Named(String),
+    /// A solar system body, at its position when the goto starts. The Sun is refused; see
+    /// [`goto_body`](crate::catalog::ephemeris::goto_body).
+    Body(Body),
+}
+
+/// How to tell the mount has settled on a target after a goto.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Settle {
+    /// Time to wait after the goto finishes, for vibrations to die down.
+    pub seconds: f64,
+    /// Distance from the target the mount must report before arriving, in arcseconds, or `None` not to check.
+    /// Azimuth and elevation targets are compared in azimuth and elevation, so leave tracking off for those.
+    pub tolerance_arcsec: Option<f64>,
+    /// Time after the goto finishes to wait for the mount to come within the tolerance before giving up.
+    pub timeout_seconds: f64,
+}
+
+impl Default for Settle {
+    fn default() -> Self {
+        Settle {
+            seconds: 2.0,
+            tolerance_arcsec: None,
+            timeout_seconds: 60.0,
+        }
+    }
+}
+
+/// A target and how to visit it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlanEntry {
+    pub target: PlanTarget,
+    /// Time to stay on the target once settled.
+    pub dwell_seconds: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub settle: Settle,
+}
+
+impl PlanEntry {
+    /// An entry dwelling `dwell_seconds` on `target`, settling by default.
+    pub fn new(target: PlanTarget, dwell_seconds: f64) -> PlanEntry {
+        PlanEntry {
+            target,
+            dwell_seconds,
+            settle: Settle::default(),
+        }
+    }
+}
+
+/// Targets to visit in order; see [`plan`](self).
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plan {
+    /// Tracking mode set before the first goto, or `None` to leave tracking as it is.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tracking: Option<TrackingMode>,
+    pub entries: Vec<PlanEntry>,
+}
+
+/// What a [`PlanRunner`] is doing, with the index of the entry it is on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlanState {
+    Slewing(usize),
+    Settling(usize),
+    Dwelling(usize),
+    /// Every entry has been visited.
+    Finished,
+    /// Stopped through [`PlanAbort::abort`].
+    Aborted,
+}
+
+/// Stops a [`PlanRunner`] from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct PlanAbort(Arc<AtomicBool>);
+
+impl PlanAbort {
+    /// Makes the runner cancel any goto and stop at its next poll.
+    pub fn abort(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Where the runner is within the current entry.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Phase {
+    Start,
+    Slewing,
+    Settling { since: DateTime<Utc> },
+    Dwelling { until: DateTime<Utc> },
+    Finished,
+    Aborted,
+}
+
+/// Where the current entry's goto went.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Goal {
+    RaDec(RADec),
+    AzEl(AzEl),
+}
+
+type Callback<'a> = Box<dyn FnMut(usize, &PlanEntry) + Send + 'a>;
+
+/// Carries out a [`Plan`]; see [`plan`](self).
+pub struct PlanRunner<'a> {
+    plan: Plan,
+    site: Location,
+    index: usize,
+    phase: Phase,
+    goal: Option<Goal>,
+    abort: PlanAbort,
+    arrival: Option<Callback<'a>>,
+    departure: Option<Callback<'a>>,
+}
+
+impl std::fmt::Debug for PlanRunner<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlanRunner")
+            .field("plan", &self.plan)
+            .field("site", &self.site)
+            .field("index", &self.index)
+            .field("phase", &self.phase)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> PlanRunner<'a> {
+    /// A runner for `plan` at `site`, which places solar system bodies.
+    pub fn new(plan: Plan, site: Location) -> PlanRunner<'a> {
+        PlanRunner {
+            plan,
+            site,
+            index: 0,
+            phase: Phase::Start,
+            goal: None,
+            abort: PlanAbort::default(),
+            arrival: None,
+            departure: None,
+        }
+    }
+
+    /// Calls `f` with the index and entry once the mount has settled on a target, e.g. to start exposures.
+    pub fn on_arrival(mut self, f: impl FnMut(usize, &PlanEntry) + Send + 'a) -> PlanRunner<'a> {
+        self.arrival = Some(Box::new(f));
+        self
+    }
+
+    /// Calls `f` with the index and entry once the dwell on a target is over, before the goto to the next.
+    pub fn on_departure(mut self, f: impl FnMut(usize, &PlanEntry) + Send + 'a) -> PlanRunner<'a> {
+        self.departure = Some(Box::new(f));
+        self
+    }
+
+    pub fn plan(&self) -> &Plan {
+        &self.plan
+    }
+
+    /// A handle aborting this runner.
+    pub fn abort_handle(&self) -> PlanAbort {
+        self.abort.clone()
+    }
+
+    pub fn state(&self) -> PlanState {
+        match self.phase {
+            Phase::Start | Phase::Slewing => PlanState::Slewing(self.index),
+            Phase::Settling { .. } => PlanState::Settling(self.index),
+            Phase::Dwelling { .. } => PlanState::Dwelling(self.index),
+            Phase::Finished => PlanState::Finished,
+            Phase::Aborted => PlanState::Aborted,
+        }
+    }
+
+    /// Moves the plan along as far as it can go without waiting.
+    ///
+    /// Fails, leaving the runner on the same entry to be polled again, if a goto cannot be started or the mount does
+    /// not settle within [`Settle::timeout_seconds`].
+    pub fn poll<M: Mount>(&mut self, mount: &mut M) -> Result<PlanState, io::Error> {
+        if self.abort.0.load(Ordering::Relaxed)
+            && !matches!(self.phase, Phase::Finished | Phase::Aborted)
+        {
+            if matches!(self.phase, Phase::Slewing) {
+                mount.cancel_goto()?;
+            }
+            log::info!("Plan aborted at entry {}.", self.index);
+            self.phase = Phase::Aborted;
+        }
+
+        loop {
+            match self.phase {
+                Phase::Start => {
+                    let Some(entry) = self.plan.entries.get(self.index) else {
+                        self.phase = Phase::Finished;
+                        continue;
+                    };
+                    if self.index == 0 {
+                        if let Some(mode) = self.plan.tracking {
+                            mount.set_tracking_mode(mode)?;
+                        }
+                    }
+                    let goal = self.start_goto(mount, &entry.target)?;
+                    log::info!("Plan entry {}: goto to {:?}.", self.index, entry.target);
+                    self.goal = Some(goal);
+                    self.phase = Phase::Slewing;
+                }
+                Phase::Slewing => {
+                    if mount.goto_in_progress()? {
+                        break;
+                    }
+                    self.phase = Phase::Settling {
+                        since: mount.get_time()?,
+                    };
+                }
+                Phase::Settling { since } => {
+                    let entry = &self.plan.entries[self.index];
+                    let now = mount.get_time()?;
+                    let elapsed = (now - since).num_milliseconds() as f64 / 1000.0;
+                    if elapsed < entry.settle.seconds {
+                        break;
+                    }
+                    if let (Some(tolerance), Some(goal)) =
+                        (entry.settle.tolerance_arcsec, self.goal)
+                    {
+                        let error = distance_arcsec(mount, goal)?;
+                        if error > tolerance {
+                            if elapsed > entry.settle.timeout_seconds {
+                                return Err(io::Error::new(
+                                    io::ErrorKind::TimedOut,
+                                    format!(
+                                        "Plan entry {} did not settle: {error:.0}\" from the target.",
+                                        self.index
+                                    ),
+                                ));
+                            }
+                            break;
+                        }
+                    }
+                    if let Some(arrival) = &mut self.arrival {
+                        arrival(self.index, entry);
+                    }
+                    let dwell = chrono::Duration::milliseconds(
+                        (entry.dwell_seconds.max(0.0) * 1000.0) as i64,
+                    );
+                    self.phase = Phase::Dwelling { until: now + dwell };
+                }
+                Phase::Dwelling { until } => {
+                    if mount.get_time()? < until {
+                        break;
+                    }
+                    if let Some(departure) = &mut self.departure {
+                        departure(self.index, &self.plan.entries[self.index]);
+                    }
+                    self.index += 1;
+                    self.phase = Phase::Start;
+                }
+                Phase::Finished | Phase::Aborted => break,
+            }
+        }
+        Ok(self.state())
+    }
+
+    /// Polls until the plan is finished or aborted.
+    pub fn run<M: Mount>(&mut self, mount: &mut M) -> Result<PlanState, io::Error> {
+        loop {
+            let state = self.poll(mount)?;
+            if matches!(state, PlanState::Finished | PlanState::Aborted) {
+                return Ok(state);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn start_goto<M: Mount>(&self, mount: &mut M, target: &PlanTarget) -> Result<Goal, io::Error> {
+        let coord = match target {
+            PlanTarget::AzEl(coord) => {
+                mount.goto_az_el(*coord)?;
+                return Ok(Goal::AzEl(*coord));
+            }
+            PlanTarget::RaDec(coord) => *coord,
+            PlanTarget::Named(name) => catalog::lookup(name)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("No object named {name} in the catalog."),
+                    )
+                })?
+                .j2000_to_jnow(mount.get_time()?),
+            PlanTarget::Body(body) => {
+                let time = mount.get_time()?;
+                catalog::ephemeris::goto_body(mount, *body, &self.site, Default::default())?;
+                return Ok(Goal::RaDec(body.position(&self.site, time)));
+            }
+        };
+        mount.goto_ra_dec(coord)?;
+        Ok(Goal::RaDec(coord))
+    }
+}
+
+/// How far the mount reports it is from `goal`, in arcseconds.
+fn distance_arcsec<M: Mount>(mount: &mut M, goal: Goal) -> Result<f64, io::Error> {
+    let degrees = match goal {
+        Goal::RaDec(goal) => {
+            let pos = mount.get_position_ra_dec()?;
+            angular_separation(pos.ra, pos.dec, goal.ra, goal.dec)
+        }
+        Goal::AzEl(goal) => {
+            let pos = mount.get_position_az_el()?;
+            angular_separation(pos.az, pos.el, goal.az, goal.el)
+        }
+    };
+    Ok(degrees * 3600.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    #[test]
+    fn visits_targets_in_order() {
+        let time = Utc.with_ymd_and_hms(2024, 7, 15, 4, 0, 0).unwrap();
+        let mut mount = SimMount::new().manual_clock(time).site(40.0, -75.0);
+        let site = Location {
+            latitude: 40.0,
+            longitude: -75.0,
+        };
+        let settle = Settle {
+            tolerance_arcsec: Some(5.0),
+            ..Settle::default()
+        };
+        let plan = Plan {
+            tracking: Some(TrackingMode::EQNorth),
+            entries: vec![
+                PlanEntry {
+                    settle,
+                    ..PlanEntry::new(PlanTarget::Named("M13".to_owned()), 60.0)
+                },
+                PlanEntry::new(PlanTarget::Body(Body::Jupiter), 30.0),
+                PlanEntry::new(PlanTarget::AzEl(AzEl::new(180.0, 30.0)), 10.0),
+            ],
+        };
+
+        let visits = Arc::new(Mutex::new(Vec::new()));
+        let (arrivals, departures) = (Arc::clone(&visits), Arc::clone(&visits));
+        let mut runner = PlanRunner::new(plan, site)
+            .on_arrival(move |i, _| arrivals.lock().unwrap().push(("arrive", i)))
+            .on_departure(move |i, _| departures.lock().unwrap().push(("depart", i)));
+
+        assert_eq!(runner.poll(&mut mount).unwrap(), PlanState::Slewing(0));
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::EQNorth);
+        let mut states = vec![PlanState::Slewing(0)];
+        while states.last() != Some(&PlanState::Finished) {
+            mount.step(Duration::from_secs(1));
+            let state = runner.poll(&mut mount).unwrap();
+            if states.last() != Some(&state) {
+                states.push(state);
+            }
+        }
+        assert_eq!(
+            states,
+            [0, 1, 2]
+                .into_iter()
+                .flat_map(|i| [
+                    PlanState::Slewing(i),
+                    PlanState::Settling(i),
+                    PlanState::Dwelling(i)
+                ])
+                .chain([PlanState::Finished])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            *visits.lock().unwrap(),
+            [
+                ("arrive", 0),
+                ("depart", 0),
+                ("arrive", 1),
+                ("depart", 1),
+                ("arrive", 2),
+                ("depart", 2)
+            ]
+        );
+
+        // Aborting cancels the goto in progress.
+        let plan = Plan {
+            tracking: None,
+            entries: vec![PlanEntry::new(
+                PlanTarget::RaDec(RADec::new(10.0, 10.0)),
+                10.0,
+            )],
+        };
+        let mut runner = PlanRunner::new(plan, site);
+        assert_eq!(runner.poll(&mut mount).unwrap(), PlanState::Slewing(0));
+        runner.abort_handle().abort();
+        assert_eq!(runner.poll(&mut mount).unwrap(), PlanState::Aborted);
+        assert!(!mount.goto_in_progress().unwrap());
+    }
+}
@@ -0,0 +1,473 @@
+//! A pointing model fitted to several syncs.
+//!
+//! A single sync only shifts the mount's idea of where it points, so it corrects gotos near the sync star and less
+//! the further away they go. A mount whose polar axis is off points worse in some parts of the sky than in others.
+//! A [`PointingModel`] keeps the mount's reported position and the true position of every star synced on, and fits
+//! by least squares the index errors of both axes and the azimuth and elevation misalignment of the polar axis.
+//! A [`ModelMount`] records its syncs in the model and corrects every equatorial goto and position with it:
+//!
+//! ```no_run
+//! use nexlib::mount::pointing::{ModelMount, PointingModel};
+//! use nexlib::mount::{Location, Mount};
+//! use nexlib::{CelestronMount, RADec};
+//!
+//! let site = Location { latitude: 40.0, longitude: -75.0 };
+//! let mut mount = ModelMount::new(CelestronMount::new().unwrap(), PointingModel::new(site));
+//! // Center each star in turn and sync on it.
+//! mount.sync(RADec::new(213.92, 19.18)).unwrap();
+//! mount.sync(RADec::new(279.23, 38.78)).unwrap();
+//! mount.sync(RADec::new(297.70, 8.87)).unwrap();
+//! mount.model().save_json("pointing.json").unwrap();
+//! ```
+//!
+//! With one sample the model is a plain offset; the misalignment terms need samples at two or more hour angles.
+//! Positions are of date, as sent to the mount. Gotos in azimuth and elevation are not corrected.
+
+use super::error::MountError;
+use super::limits::ProfileSelection;
+use super::transform::{local_sidereal_time, wrap_180};
+use super::{
+    AzEl, Gps, Location, Model, Mount, NonGpsDevice, RADec, SlewAxis, SlewDir, SlewRate,
+    TrackingMode,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A star synced on.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointingSample {
+    pub time: DateTime<Utc>,
+    /// Where the mount reported it was pointing.
+    pub commanded: RADec,
+    /// Where it was actually pointing.
+    pub actual: RADec,
+}
+
+/// Fitted pointing errors, in arcseconds.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PointingTerms {
+    /// Index error of the hour angle axis.
+    pub ih: f64,
+    /// Index error of the declination axis.
+    pub id: f64,
+    /// Polar axis misalignment in azimuth, positive east of the pole.
+    pub ma: f64,
+    /// Polar axis misalignment in elevation, positive above the pole.
+    pub me: f64,
+}
+
+impl PointingTerms {
+    /// Errors of hour angle and declination in degrees at an hour angle and declination.
+    fn error(&self, ha: f64, dec: f64) -> (f64, f64) {
+        let (sin_h, cos_h) = ha.to_radians().sin_cos();
+        let tan_d = dec.clamp(-89.0, 89.0).to_radians().tan();
+        (
+            (self.ih - self.ma * cos_h * tan_d + self.me * sin_h * tan_d) / 3600.0,
+            (self.id + self.ma * sin_h + self.me * cos_h) / 3600.0,
+        )
+    }
+}
+
+/// Samples from syncs and the terms fitted to them; see [`pointing`](self).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointingModel {
+    site: Location,
+    samples: Vec<PointingSample>,
+    #[serde(skip)]
+    terms: PointingTerms,
+}
+
+impl PointingModel {
+    /// An empty model for a mount at `site`, which corrects nothing.
+    pub fn new(site: Location) -> PointingModel {
+        PointingModel {
+            site,
+            samples: Vec::new(),
+            terms: PointingTerms::default(),
+        }
+    }
+
+    /// Reloads a model saved with [`PointingModel::save_json`].
+    pub fn load_json(path: impl AsRef<Path>) -> Result<PointingModel, io::Error> {
+        let mut model: PointingModel = serde_json::from_str(&fs::read_to_string(path)?)?;
+        model.fit();
+        Ok(model)
+    }
+
+    pub fn to_json(&self) -> Result<String, io::Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn save_json(&self, path: impl AsRef<Path>) -> Result<(), io::Error> {
+        fs::write(path, self.to_json()?)
+    }
+
+    pub fn site(&self) -> Location {
+        self.site
+    }
+
+    pub fn samples(&self) -> &[PointingSample] {
+        &self.samples
+    }
+
+    pub fn terms(&self) -> PointingTerms {
+        self.terms
+    }
+
+    /// Adds a sample and refits the terms.
+    pub fn add(&mut self, sample: PointingSample) {
+        self.samples.push(sample);
+        self.fit();
+    }
+
+    /// Removes every sample, e.g. after the mount has been moved.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.terms = PointingTerms::default();
+    }
+
+    /// Where to send the mount at `time` so it points at `coord`.
+    pub fn to_mount(&self, coord: RADec, time: DateTime<Utc>) -> RADec {
+        let ha = local_sidereal_time(time, self.site.longitude) - coord.ra;
+        let (dh, dd) = self.terms.error(ha, coord.dec);
+        RADec::new((coord.ra - dh).rem_euclid(360.0), coord.dec + dd)
+    }
+
+    /// Where the mount points at `time` when it reports `coord`.
+    pub fn to_sky(&self, coord: RADec, time: DateTime<Utc>) -> RADec {
+        let lst = local_sidereal_time(time, self.site.longitude);
+        let mut sky = coord;
+        // The errors change slowly across the sky, so a few iterations converge.
+        for _ in 0..3 {
+            let (dh, dd) = self.terms.error(lst - sky.ra, sky.dec);
+            sky = RADec::new((coord.ra + dh).rem_euclid(360.0), coord.dec - dd);
+        }
+        sky
+    }
+
+    /// Root mean square of the errors left at the samples after the fit, in arcseconds, or `None` without samples.
+    pub fn rms_arcsec(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let sum: f64 = self
+            .samples
+            .iter()
+            .map(|s| {
+                let (h, d) = self.residual(s);
+                (h * s.actual.dec.to_radians().cos()).powi(2) + d.powi(2)
+            })
+            .sum();
+        Some((sum / self.samples.len() as f64).sqrt() * 3600.0)
+    }
+
+    /// Measured minus modelled errors of hour angle and declination at a sample, in degrees.
+    fn residual(&self, sample: &PointingSample) -> (f64, f64) {
+        let (dh, dd) = measured(sample);
+        let ha = local_sidereal_time(sample.time, self.site.longitude) - sample.actual.ra;
+        let (mh, md) = self.terms.error(ha, sample.actual.dec);
+        (dh - mh, dd - md)
+    }
+
+    fn fit(&mut self) {
+        self.terms = self.fit_misalignment().unwrap_or_else(|| self.fit_offset());
+    }
+
+    /// The mean error of each axis.
+    fn fit_offset(&self) -> PointingTerms {
+        if self.samples.is_empty() {
+            return PointingTerms::default();
+        }
+        let n = self.samples.len() as f64;
+        let (dh, dd) = self
+            .samples
+            .iter()
+            .map(measured)
+            .fold((0.0, 0.0), |a, e| (a.0 + e.0, a.1 + e.1));
+        PointingTerms {
+            ih: dh / n * 3600.0,
+            id: dd / n * 3600.0,
+            ..PointingTerms::default()
+        }
+    }
+
+    /// Least squares fit of all terms, or `None` if the samples cannot tell them apart.
+    fn fit_misalignment(&self) -> Option<PointingTerms> {
+        if self.samples.len() < 2 {
+            return None;
+        }
+
+        // Normal equations of the errors in arcseconds, the hour angle errors weighted to lengths on the sky.
+        let mut ata = [[0.0; 4]; 4];
+        let mut atb = [0.0; 4];
+        for sample in &self.samples {
+            let (dh, dd) = measured(sample);
+            let ha = local_sidereal_time(sample.time, self.site.longitude) - sample.actual.ra;
+            let (sin_h, cos_h) = ha.to_radians().sin_cos();
+            let dec = sample.actual.dec.clamp(-89.0, 89.0).to_radians();
+            let (sin_d, cos_d) = dec.sin_cos();
+            let rows = [
+                (
+                    [cos_d, 0.0, -cos_h * sin_d, sin_h * sin_d],
+                    dh * cos_d * 3600.0,
+                ),
+                ([0.0, 1.0, sin_h, cos_h], dd * 3600.0),
+            ];
+            for (row, b) in rows {
+                for i in 0..4 {
+                    for j in 0..4 {
+                        ata[i][j] += row[i] * row[j];
+                    }
+                    atb[i] += row[i] * b;
+                }
+            }
+        }
+
+        let [ih, id, ma, me] = solve(ata, atb)?;
+        Some(PointingTerms { ih, id, ma, me })
+    }
+}
+
+/// Reported minus actual hour angle and declination at a sample, in degrees.
+fn measured(sample: &PointingSample) -> (f64, f64) {
+    (
+        wrap_180(sample.actual.ra - sample.commanded.ra),
+        sample.commanded.dec - sample.actual.dec,
+    )
+}
+
+/// Solves `a x = b` by Gaussian elimination, or `None` if `a` is close to singular.
+fn solve(mut a: [[f64; 4]; 4], mut b: [f64; 4]) -> Option<[f64; 4]> {
+    let scale = (0..4).map(|i| a[i][i]).fold(0.0, f64::max);
+    for col in 0..4 {
+        let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() <= scale * 1e-9 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..4 {
+            let f = a[row][col] / a[col][col];
+            let pivot_row = a[col];
+            for (x, p) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= f * p;
+            }
+            b[row] -= f * b[col];
+        }
+    }
+
+    let mut x = [0.0; 4];
+    for row in (0..4).rev() {
+        let sum: f64 = (row + 1..4).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// A mount correcting its pointing with a [`PointingModel`]; see [`pointing`](self).
+#[derive(Debug)]
+pub struct ModelMount<M> {
+    mount: M,
+    model: PointingModel,
+}
+
+impl<M: Mount> ModelMount<M> {
+    pub fn new(mount: M, model: PointingModel) -> ModelMount<M> {
+        ModelMount { mount, model }
+    }
+
+    pub fn model(&self) -> &PointingModel {
+        &self.model
+    }
+
+    /// The model, e.g. to clear it.
+    pub fn model_mut(&mut self) -> &mut PointingModel {
+        &mut self.model
+    }
+
+    /// The wrapped mount.
+    pub fn inner(&mut self) -> &mut M {
+        &mut self.mount
+    }
+}
+
+impl<M: Mount> Mount for ModelMount<M> {
+    /// Where the mount points, corrected by the model.
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        let time = self.mount.get_time()?;
+        Ok(self.model.to_sky(self.mount.get_position_ra_dec()?, time))
+    }
+
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        self.mount.get_position_az_el()
+    }
+
+    /// Starts a goto to where the model says the mount must go to point at `coord`.
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        let time = self.mount.get_time()?;
+        self.mount.goto_ra_dec(self.model.to_mount(coord, time))
+    }
+
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        self.mount.goto_az_el(coord)
+    }
+
+    /// Adds a sample of the mount pointing at `coord` to the model. A mount which is not aligned yet is synced
+    /// instead, so it accepts gotos, and the sample has no error.
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        if !self.mount.is_aligned()? {
+            self.mount.sync(coord)?;
+        }
+        let time = self.mount.get_time()?;
+        let commanded = self.mount.get_position_ra_dec()?;
+        self.model.add(PointingSample {
+            time,
+            commanded,
+            actual: coord,
+        });
+        Ok(())
+    }
+
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        self.mount.get_tracking_mode()
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        self.mount.set_tracking_mode(mode)
+    }
+
+    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), MountError> {
+        self.mount.slew_variable(axis, dir, rate)
+    }
+
+    fn slew_fixed(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        self.mount.slew_fixed(axis, dir, rate)
+    }
+
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        self.mount.get_location()
+    }
+
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        self.mount.set_location(location)
+    }
+
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        self.mount.get_time()
+    }
+
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        self.mount.set_time(time)
+    }
+
+    fn get_version(&mut self) -> Result<String, MountError> {
+        self.mount.get_version()
+    }
+
+    fn get_device_version(&mut self, device: NonGpsDevice) -> Result<String, MountError> {
+        self.mount.get_device_version(device)
+    }
+
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        self.mount.get_model()
+    }
+
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        self.mount.echo(byte)
+    }
+
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        self.mount.is_aligned()
+    }
+
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        self.mount.goto_in_progress()
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.mount.cancel_goto()
+    }
+
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
+        self.mount.stop_slew(axis)
+    }
+
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
+        self.mount.get_gps()
+    }
+
+    fn select_limit_profile(&mut self, name: Option<&str>) -> Result<(), MountError> {
+        self.mount.select_limit_profile(name)
+    }
+
+    fn limit_profiles(&mut self) -> Result<ProfileSelection, MountError> {
+        self.mount.limit_profiles()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn fits_polar_misalignment() {
+        let site = Location {
+            latitude: 40.0,
+            longitude: -75.0,
+        };
+        let time = Utc.with_ymd_and_hms(2024, 3, 20, 3, 0, 0).unwrap();
+        let truth = PointingModel {
+            terms: PointingTerms {
+                ih: 300.0,
+                id: -120.0,
+                ma: 600.0,
+                me: -400.0,
+            },
+            ..PointingModel::new(site)
+        };
+
+        let mut model = PointingModel::new(site);
+        assert!(model.rms_arcsec().is_none());
+        let stars = [(213.9, 19.2), (279.2, 38.8), (152.1, 12.0), (116.3, 28.0)];
+        for (i, &(ra, dec)) in stars.iter().enumerate() {
+            let actual = RADec::new(ra, dec);
+            model.add(PointingSample {
+                time,
+                commanded: truth.to_mount(actual, time),
+                actual,
+            });
+            if i == 0 {
+                // One sample only gives an offset.
+                assert_eq!(model.terms().ma, 0.0);
+            }
+        }
+
+        let terms = model.terms();
+        assert!((terms.ih - 300.0).abs() < 1.0, "{terms:?}");
+        assert!((terms.id + 120.0).abs() < 1.0, "{terms:?}");
+        assert!((terms.ma - 600.0).abs() < 1.0, "{terms:?}");
+        assert!((terms.me + 400.0).abs() < 1.0, "{terms:?}");
+        assert!(model.rms_arcsec().unwrap() < 1.0);
+
+        let target = RADec::new(190.0, -10.0);
+        let sent = model.to_mount(target, time);
+        assert!(wrap_180(sent.ra - target.ra).abs() > 0.05);
+        let seen = model.to_sky(sent, time);
+        assert!(wrap_180(seen.ra - target.ra).abs() < 1e-5 && (seen.dec - target.dec).abs() < 1e-5);
+
+        let reloaded: PointingModel = serde_json::from_str(&model.to_json().unwrap()).unwrap();
+        assert_eq!(reloaded.samples().len(), 4);
+        assert!(
+            (reloaded.samples()[3].commanded.ra - model.samples()[3].commanded.ra).abs() < 1e-9
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! A worker thread owning a mount, running commands by priority.
+//!
+//! With a plain `Mutex`, a stop request from one client waits its turn behind every status poll and goto other
+//! clients queued before it, which on a slow link can take seconds. A [`CommandQueue`] instead hands the mount to a
+//! single worker thread and always runs the most urgent command waiting next: aborts and stops before gotos and other
+//! commands, and those before background status polls. Commands of the same priority run in the order submitted.
+//!
+//! A command already running on the serial port is never interrupted; an urgent command runs as soon as it finishes.
+//!
+//! ```
+//! use nexlib::mount::queue::{CommandQueue, Priority};
+//! use nexlib::mount::{Mount, SimMount};
+//!
+//! let queue = CommandQueue::spawn(SimMount::new());
+//! let pos = queue.run(Priority::Background, |m| m.get_position_ra_dec()).unwrap();
+//! queue.run(Priority::Urgent, |m| m.cancel_goto()).unwrap();
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// How soon a command runs relative to others waiting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Status and position polls, which are repeated anyway if delayed.
+    Background = 0,
+    /// Gotos, configuration, and everything else.
+    Normal = 1,
+    /// Aborts and stops, which must never wait behind a backlog.
+    Urgent = 2,
+}
+
+type Job<M> = Box<dyn FnOnce(&mut M) + Send>;
+
+struct State<M> {
+    /// Waiting jobs, indexed by priority.
+    jobs: [VecDeque<Job<M>>; 3],
+    shutdown: bool,
+}
+
+struct Shared<M> {
+    state: Mutex<State<M>>,
+    ready: Condvar,
+}
+
+/// Runs commands against a mount on a worker thread, most urgent first.
+///
+/// The worker finishes the commands already queued and stops when the queue is dropped.
+pub struct CommandQueue<M> {
+    shared: Arc<Shared<M>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<M: Send + 'static> CommandQueue<M> {
+    /// Moves `mount` onto a new worker thread.
+    pub fn spawn(mut mount: M) -> CommandQueue<M> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                jobs: Default::default(),
+                shutdown: false,
+            }),
+            ready: Condvar::new(),
+        });
+
+        let worker = Arc::clone(&shared);
+        let thread = thread::spawn(move || loop {
+            let job = {
+                let mut state = worker.state.lock().unwrap();
+                loop {
+                    if let Some(job) = state.jobs.iter_mut().rev().find_map(|q| q.pop_front()) {
+                        break job;
+                    }
+                    if state.shutdown {
+                        return;
+                    }
+                    state = worker.ready.wait(state).unwrap();
+                }
+            };
+            job(&mut mount);
+        });
+
+        CommandQueue {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Queues `f` to run against the mount, returning a receiver for its result.
+    pub fn submit<T, F>(&self, priority: Priority, f: F) -> mpsc::Receiver<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut M) -> T + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let job: Job<M> = Box::new(move |mount| {
+            let _ = tx.send(f(mount));
+        });
+
+        self.shared.state.lock().unwrap().jobs[priority as usize].push_back(job);
+        self.shared.ready.notify_one();
+        rx
+    }
+
+    /// Runs `f` against the mount and waits for its result.
+    ///
+    /// # Panics
+    ///
+    /// If `f` or an earlier command panicked, stopping the worker.
+    pub fn run<T, F>(&self, priority: Priority, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut M) -> T + Send + 'static,
+    {
+        self.submit(priority, f)
+            .recv()
+            .expect("Command queue worker stopped.")
+    }
+
+    /// Number of commands waiting to run, not counting one running.
+    pub fn pending(&self) -> usize {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .jobs
+            .iter()
+            .map(VecDeque::len)
+            .sum()
+    }
+}
+
+impl<M> Drop for CommandQueue<M> {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.ready.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl<M> std::fmt::Debug for CommandQueue<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandQueue").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::{Mount, SimMount};
+
+    #[test]
+    fn urgent_commands_skip_the_backlog() {
+        let queue = CommandQueue::spawn(SimMount::new());
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // Hold the worker busy until the backlog is queued.
+        let (release, hold) = mpsc::channel::<()>();
+        let busy = queue.submit(Priority::Normal, move |_| hold.recv().unwrap());
+
+        let mut results = Vec::new();
+        for (name, priority) in [
+            ("poll 1", Priority::Background),
+            ("goto", Priority::Normal),
+            ("poll 2", Priority::Background),
+            ("stop", Priority::Urgent),
+        ] {
+            let order = Arc::clone(&order);
+            results.push(queue.submit(priority, move |m: &mut SimMount| {
+                m.get_position_ra_dec().unwrap();
+                order.lock().unwrap().push(name);
+            }));
+        }
+
+        assert!(queue.pending() >= 4);
+        release.send(()).unwrap();
+        busy.recv().unwrap();
+        for res in results {
+            res.recv().unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), ["stop", "goto", "poll 1", "poll 2"]);
+    }
+}
@@ -0,0 +1,293 @@
+//! Suspending motion on an external safety signal.
+//!
+//! Observatory weather stations, rain sensors, and roof controllers report whether it is safe to operate through a
+//! [`SafetyMonitor`]. A [`SafeMount`] wraps any [`Mount`] and checks its monitor before every motion command. When the
+//! monitor reports unsafe, the mount is stopped with [`Mount::emergency_stop`] and, if a park position was set, sent there.
+//! New gotos, slews, and tracking are then refused with `PermissionDenied` until the monitor reports safe again.
+//! Stopping is always allowed, and tracking is not resumed on its own.
+//!
+//! A mount left tracking issues no commands, so call [`SafeMount::poll`] periodically, e.g. alongside status polling,
+//! to react to conditions changing in between:
+//!
+//! ```
+//! use nexlib::mount::safety::SafeMount;
+//! use nexlib::mount::{Mount, SimMount, TrackingMode};
+//! use nexlib::AzEl;
+//! use std::sync::atomic::{AtomicBool, Ordering};
+//! use std::sync::Arc;
+//!
+//! let roof_open = Arc::new(AtomicBool::new(true));
+//! let monitor = {
+//!     let roof_open = Arc::clone(&roof_open);
+//!     move || Ok(roof_open.load(Ordering::Relaxed))
+//! };
+//! let mut mount = SafeMount::new(SimMount::new(), monitor).park(AzEl::new(0.0, 0.0));
+//! mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+//!
+//! roof_open.store(false, Ordering::Relaxed);
+//! mount.poll().unwrap();
+//! assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::Off);
+//! assert!(mount.set_tracking_mode(TrackingMode::EQNorth).is_err());
+//! ```
+
+use super::error::MountError;
+use super::limits::ProfileSelection;
+use super::{
+    AzEl, Gps, Location, Model, Mount, NonGpsDevice, RADec, SlewAxis, SlewDir,
+    SlewRate, TrackingMode,
+};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use std::io;
+
+/// A source of whether it is safe to operate the mount.
+///
+/// Implemented for closures returning `Result<bool, io::Error>`.
+pub trait SafetyMonitor {
+    /// Whether conditions are safe. An error is treated as unsafe.
+    fn is_safe(&mut self) -> Result<bool, io::Error>;
+}
+
+impl<F: FnMut() -> Result<bool, io::Error>> SafetyMonitor for F {
+    fn is_safe(&mut self) -> Result<bool, io::Error> {
+        self()
+    }
+}
+
+/// A mount whose motion is suspended while a [`SafetyMonitor`] reports unsafe; see [`safety`](self).
+#[derive(Debug)]
+pub struct SafeMount<M, S> {
+    mount: M,
+    monitor: S,
+    park: Option<AzEl>,
+    safe: bool,
+}
+
+impl<M: Mount, S: SafetyMonitor> SafeMount<M, S> {
+    pub fn new(mount: M, monitor: S) -> SafeMount<M, S> {
+        SafeMount {
+            mount,
+            monitor,
+            park: None,
+            safe: true,
+        }
+    }
+
+    /// Sends the mount to `position` after stopping it when conditions become unsafe.
+    pub fn park(mut self, position: AzEl) -> SafeMount<M, S> {
+        self.park = Some(position);
+        self
+    }
+
+    /// Whether conditions were safe at the last check.
+    pub fn is_safe(&self) -> bool {
+        self.safe
+    }
+
+    /// The wrapped mount.
+    pub fn inner(&mut self) -> &mut M {
+        &mut self.mount
+    }
+
+    /// Checks the monitor, stopping and parking the mount if conditions have become unsafe. Returns whether they are
+    /// safe.
+    pub fn poll(&mut self) -> Result<bool, MountError> {
+        let safe = match self.monitor.is_safe() {
+            Ok(safe) => safe,
+            Err(e) => {
+                warn!("Safety monitor failed, assuming unsafe: {e}");
+                false
+            }
+        };
+
+        match (self.safe, safe) {
+            (true, false) => {
+                warn!("Conditions are unsafe; stopping the mount.");
+                self.safe = false;
+                self.mount.emergency_stop()?;
+                if let Some(park) = self.park {
+                    self.mount.goto_az_el(park)?;
+                }
+            }
+            (false, true) => {
+                info!("Conditions are safe again.");
+                self.safe = true;
+            }
+            _ => (),
+        }
+        Ok(safe)
+    }
+
+    /// Fails unless conditions are safe for new motion.
+    fn guard(&mut self) -> Result<(), MountError> {
+        if self.poll()? {
+            Ok(())
+        } else {
+            Err(MountError::other(
+                io::ErrorKind::PermissionDenied,
+                "Conditions are unsafe; motion is blocked until they clear.",
+            ))
+        }
+    }
+}
+
+impl<M: Mount, S: SafetyMonitor> Mount for SafeMount<M, S> {
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        self.mount.get_position_ra_dec()
+    }
+
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        self.mount.get_position_az_el()
+    }
+
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.guard()?;
+        self.mount.goto_ra_dec(coord)
+    }
+
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        self.guard()?;
+        self.mount.goto_az_el(coord)
+    }
+
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.mount.sync(coord)
+    }
+
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        self.mount.get_tracking_mode()
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        if mode != TrackingMode::Off {
+            self.guard()?;
+        }
+        self.mount.set_tracking_mode(mode)
+    }
+
+    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), MountError> {
+        if rate != 0 {
+            self.guard()?;
+        }
+        self.mount.slew_variable(axis, dir, rate)
+    }
+
+    fn slew_fixed(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        if rate != SlewRate::Stop {
+            self.guard()?;
+        }
+        self.mount.slew_fixed(axis, dir, rate)
+    }
+
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        self.mount.get_location()
+    }
+
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        self.mount.set_location(location)
+    }
+
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        self.mount.get_time()
+    }
+
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        self.mount.set_time(time)
+    }
+
+    fn get_version(&mut self) -> Result<String, MountError> {
+        self.mount.get_version()
+    }
+
+    fn get_device_version(&mut self, device: NonGpsDevice) -> Result<String, MountError> {
+        self.mount.get_device_version(device)
+    }
+
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        self.mount.get_model()
+    }
+
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        self.mount.echo(byte)
+    }
+
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        self.mount.is_aligned()
+    }
+
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        self.mount.goto_in_progress()
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.mount.cancel_goto()
+    }
+
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
+        self.mount.stop_slew(axis)
+    }
+
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
+        self.mount.get_gps()
+    }
+
+    fn select_limit_profile(&mut self, name: Option<&str>) -> Result<(), MountError> {
+        self.mount.select_limit_profile(name)
+    }
+
+    fn limit_profiles(&mut self) -> Result<ProfileSelection, MountError> {
+        self.mount.limit_profiles()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn suspends_motion_while_unsafe() {
+        let safe = Rc::new(Cell::new(true));
+        let monitor = {
+            let safe = Rc::clone(&safe);
+            move || match safe.get() {
+                true => Ok(true),
+                false => Err(io::Error::other("Weather station offline.")),
+            }
+        };
+        let park = AzEl::new(90.0, 10.0);
+        let mut mount =
+            SafeMount::new(SimMount::new().manual_clock(Utc::now()), monitor).park(park);
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        mount
+            .slew_fixed(SlewAxis::RAAz, SlewDir::Positive, SlewRate::Rate5)
+            .unwrap();
+
+        safe.set(false);
+        let e = mount.goto_az_el(AzEl::new(180.0, 45.0)).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::PermissionDenied);
+        assert!(!mount.is_safe());
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::Off);
+        assert!(mount
+            .slew_variable(SlewAxis::DecEl, SlewDir::Negative, 100)
+            .is_err());
+
+        assert!(mount.goto_in_progress().unwrap());
+        mount.inner().step(std::time::Duration::from_secs(600));
+        let az_el = mount.get_position_az_el().unwrap();
+        assert!((az_el.az - park.az).abs() < 0.01 && (az_el.el - park.el).abs() < 0.01);
+        // Stopping is always allowed.
+        mount.stop_slew(SlewAxis::RAAz).unwrap();
+
+        safe.set(true);
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        assert!(mount.is_safe());
+    }
+}
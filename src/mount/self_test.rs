@@ -0,0 +1,255 @@
+//! A routine exercising the whole command set, for validating a new cable, adapter, or firmware.
+//!
+//! [`CelestronMount::self_test`] runs every supported query, then, unless disabled, nudges each axis a little in both
+//! directions at a slow rate and checks the motors report the movement. Each check is timed and recorded in a
+//! [`SelfTestReport`], which prints as a table:
+//!
+//! ```no_run
+//! use nexlib::mount::self_test::SelfTestOptions;
+//! use nexlib::CelestronMount;
+//!
+//! let mut mount = CelestronMount::new().unwrap();
+//! let report = mount.self_test(SelfTestOptions::default());
+//! println!("{report}");
+//! assert!(report.passed());
+//! ```
+//!
+//! The motion pattern is skipped while a goto is in progress, and both axes are stopped afterwards even if a check
+//! fails. The mount should still be free to move a few degrees in any direction.
+
+use super::error::MountError;
+use super::{CelestronMount, Mount, NonGpsDevice, SlewAxis, SlewDir, SlewRate};
+use std::fmt;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Which parts of the self-test to run.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SelfTestOptions {
+    /// Whether to move the axes.
+    pub motion: bool,
+    /// Rate of the test movements.
+    pub rate: SlewRate,
+    /// How long to move each axis in each direction.
+    pub duration: Duration,
+}
+
+impl Default for SelfTestOptions {
+    /// Moves each axis for one second each way at rate 3, a few arcminutes.
+    fn default() -> Self {
+        SelfTestOptions {
+            motion: true,
+            rate: SlewRate::Rate3,
+            duration: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Passed, with what the mount reported.
+    Pass(String),
+    Fail(String),
+    /// Not applicable to this mount or this run.
+    Skipped(String),
+}
+
+/// The result of a single check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub outcome: Outcome,
+    /// Time taken by the check, including any deliberate movement.
+    pub latency: Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub checks: Vec<Check>,
+}
+
+impl SelfTestReport {
+    /// Whether no check failed.
+    pub fn passed(&self) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|c| matches!(c.outcome, Outcome::Fail(_)))
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks
+            .iter()
+            .filter(|c| matches!(c.outcome, Outcome::Fail(_)))
+    }
+
+    /// Runs `f` as the check `name`. A device which is not present is recorded as skipped.
+    fn check<T: fmt::Debug>(
+        &mut self,
+        name: impl Into<String>,
+        f: impl FnOnce() -> Result<T, MountError>,
+    ) -> Option<T> {
+        let start = Instant::now();
+        let res = f();
+        let latency = start.elapsed();
+
+        let (outcome, value) = match res {
+            Ok(value) => (Outcome::Pass(format!("{value:?}")), Some(value)),
+            Err(e) if e.kind() == io::ErrorKind::NotConnected => {
+                (Outcome::Skipped(format!("Not present: {e}")), None)
+            }
+            Err(e) => (Outcome::Fail(e.to_string()), None),
+        };
+        self.checks.push(Check {
+            name: name.into(),
+            outcome,
+            latency,
+        });
+        value
+    }
+
+    fn skip(&mut self, name: impl Into<String>, reason: &str) {
+        self.checks.push(Check {
+            name: name.into(),
+            outcome: Outcome::Skipped(reason.to_owned()),
+            latency: Duration::ZERO,
+        });
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let (status, detail) = match &check.outcome {
+                Outcome::Pass(detail) => ("PASS", detail),
+                Outcome::Fail(detail) => ("FAIL", detail),
+                Outcome::Skipped(detail) => ("SKIP", detail),
+            };
+            writeln!(
+                f,
+                "{status} {:<28} {:>9.1?} {detail}",
+                check.name, check.latency
+            )?;
+        }
+        let failed = self.failures().count();
+        write!(f, "{} checks, {failed} failed", self.checks.len())
+    }
+}
+
+impl CelestronMount {
+    /// Runs every supported query and, if enabled in `options`, a small motion pattern; see [`self_test`](self).
+    pub fn self_test(&mut self, options: SelfTestOptions) -> SelfTestReport {
+        let mut report = SelfTestReport::default();
+        self.refresh_info();
+
+        report.check("echo", || self.ping());
+        report.check("get_model", || self.get_model());
+        report.check("get_version", || self.get_version());
+        for device in [
+            NonGpsDevice::AzRaMotor,
+            NonGpsDevice::ElDecMotor,
+            NonGpsDevice::RtcUnit,
+        ] {
+            report.check(format!("get_device_version {device:?}"), || {
+                self.get_device_version(device)
+            });
+        }
+        report.check("is_aligned", || self.is_aligned());
+        report.check("get_tracking_mode", || self.get_tracking_mode());
+        let busy = report.check("goto_in_progress", || self.goto_in_progress());
+        report.check("get_position_ra_dec", || self.get_position_ra_dec());
+        report.check("get_position_az_el", || self.get_position_az_el());
+        report.check("get_motor_positions", || self.get_motor_positions());
+        report.check("get_time", || self.get_time());
+        report.check("get_site", || self.get_site());
+
+        if !options.motion {
+            report.skip("motion", "Disabled.");
+        } else if busy != Some(false) {
+            report.skip("motion", "A goto may be in progress.");
+        } else {
+            for axis in [SlewAxis::RAAz, SlewAxis::DecEl] {
+                let first = report.check(format!("slew_fixed {axis:?} Positive"), || {
+                    self.test_slew(axis, SlewDir::Positive, options, None)
+                });
+                report.check(format!("slew_fixed {axis:?} Negative"), || {
+                    self.test_slew(axis, SlewDir::Negative, options, first)
+                });
+            }
+            for axis in [SlewAxis::RAAz, SlewAxis::DecEl] {
+                report.check(format!("stop_slew {axis:?}"), || self.stop_slew(axis));
+            }
+        }
+
+        report
+    }
+
+    /// Moves `axis` for the test duration and checks its motor moved, returning how far in degrees.
+    ///
+    /// Which way the motor position counts depends on the mount, so only the second movement's direction is checked:
+    /// it must be opposite to `first`.
+    fn test_slew(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        options: SelfTestOptions,
+        first: Option<f64>,
+    ) -> Result<f64, MountError> {
+        let i = axis as usize;
+        let before = self.get_motor_positions()?[i];
+        self.slew_fixed(axis, dir, options.rate)?;
+        thread::sleep(options.duration);
+        let stopped = self.stop_slew(axis);
+        let after = self.get_motor_positions()?[i];
+        stopped?;
+
+        let moved = super::transform::wrap_180(after - before);
+        if moved == 0.0 || first.is_some_and(|first| first.signum() == moved.signum()) {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidData,
+                format!("Motor moved {moved:.4}° while slewing {dir:?}."),
+            ));
+        }
+        Ok(moved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{Fault, SimMount, SimPort};
+    use crate::AzEl;
+
+    #[test]
+    fn reports_every_check() {
+        // Away from the pole, where the simulated declination axis stops.
+        let port = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        port.mount().goto_az_el(AzEl::new(180.0, 30.0)).unwrap();
+        while port.mount().goto_in_progress().unwrap() {
+            port.mount().step(Duration::from_secs(1));
+        }
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+        let options = SelfTestOptions {
+            duration: Duration::from_millis(100),
+            ..SelfTestOptions::default()
+        };
+
+        let report = mount.self_test(options);
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.checks.len(), 20);
+
+        port.inject(Fault::Timeout);
+        port.inject(Fault::Timeout);
+        let report = mount.self_test(SelfTestOptions {
+            motion: false,
+            ..options
+        });
+        let failed: Vec<_> = report.failures().map(|c| c.name.as_str()).collect();
+        assert_eq!(failed, ["echo", "get_model"]);
+        assert_eq!(
+            report.checks.last().unwrap().outcome,
+            Outcome::Skipped("Disabled.".into())
+        );
+    }
+}
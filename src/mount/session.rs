@@ -0,0 +1,851 @@
+//! Recording and replaying serial sessions.
+//!
+//! A [`Recorder`] wraps a serial port and logs every write, read, and read error with the time since the session
+//! started. Set `record` in the `[serial]` configuration section (or `NEXLIB_SERIAL_RECORD`) to record any nexlib
+//! application. A [`ReplayPort`] answers a [`CelestronMount`](crate::CelestronMount) from such a recording, so a
+//! user's session can be reproduced exactly without their mount:
+//!
+//! ```ignore
+//! let port = ReplayPort::open("session.txt")?;
+//! let mut mount = CelestronMount::from_port(Box::new(port));
+//! // Issue the same calls as the recorded application.
+//! ```
+//!
+//! Sessions are text files with one event per line, bytes written in hex:
+//!
+//! ```text
+//! # nexlib session
+//! # started 2024-03-20T22:00:00+00:00
+//! # timeout_ms 3500
+//! 0.000012 > 65
+//! 0.061000 < 34313030303030302C32303030303030302023
+//! 0.061500 > 74
+//! 3.561700 ! TimedOut
+//! ```
+//!
+//! [`diff`] compares two sessions command by command, ignoring timing, to find where the behaviour of two setups
+//! diverges, such as the same application run against two firmware versions.
+
+use super::DEFAULT_TIMEOUT;
+use chrono::{DateTime, Utc};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const HEADER: &str = "# nexlib session";
+
+/// What happened on the port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    /// Bytes written to the mount.
+    Write(Vec<u8>),
+    /// Bytes read from the mount.
+    Read(Vec<u8>),
+    /// A read failed.
+    Error(io::ErrorKind),
+}
+
+/// One recorded event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// Time since the session started.
+    pub time: Duration,
+    pub kind: EventKind,
+}
+
+/// A recorded session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub started: Option<DateTime<Utc>>,
+    /// Read timeout of the recorded port.
+    pub timeout: Option<Duration>,
+    pub events: Vec<Event>,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+pub(crate) fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parses the `Debug` name of the error kinds serial ports report.
+pub(crate) fn parse_error_kind(s: &str) -> io::ErrorKind {
+    use io::ErrorKind::*;
+
+    [
+        TimedOut,
+        WouldBlock,
+        Interrupted,
+        BrokenPipe,
+        NotFound,
+        PermissionDenied,
+        NotConnected,
+        ConnectionReset,
+        InvalidInput,
+        InvalidData,
+        UnexpectedEof,
+    ]
+    .into_iter()
+    .find(|kind| format!("{kind:?}") == s)
+    .unwrap_or(Other)
+}
+
+impl Session {
+    /// Parses a session file.
+    pub fn parse(text: &str) -> Result<Session, io::Error> {
+        let mut session = Session {
+            started: None,
+            timeout: None,
+            events: Vec::new(),
+        };
+
+        for (i, line) in text.lines().enumerate() {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Line {}: Invalid session event {:?}.", i + 1, line),
+                )
+            };
+
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                let comment = comment.trim();
+                if let Some(started) = comment.strip_prefix("started ") {
+                    session.started = DateTime::parse_from_rfc3339(started.trim())
+                        .ok()
+                        .map(|t| t.with_timezone(&Utc));
+                } else if let Some(ms) = comment.strip_prefix("timeout_ms ") {
+                    session.timeout = ms.trim().parse().ok().map(Duration::from_millis);
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let (Some(time), Some(dir), data) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid());
+            };
+            let time = time
+                .parse::<f64>()
+                .ok()
+                .filter(|t| t.is_finite() && *t >= 0.0)
+                .map(Duration::from_secs_f64)
+                .ok_or_else(invalid)?;
+            let data = data.unwrap_or("");
+
+            let kind = match dir {
+                ">" => EventKind::Write(from_hex(data).ok_or_else(invalid)?),
+                "<" => EventKind::Read(from_hex(data).ok_or_else(invalid)?),
+                "!" => EventKind::Error(parse_error_kind(data)),
+                _ => return Err(invalid()),
+            };
+            session.events.push(Event { time, kind });
+        }
+
+        Ok(session)
+    }
+
+    /// Loads a session file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Session, io::Error> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        Self::parse(&text)
+    }
+}
+
+/// A command of a session and what came back before the next command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    pub request: Vec<u8>,
+    /// Every byte read, concatenated.
+    pub response: Vec<u8>,
+    /// The read error which ended the exchange, if any.
+    pub error: Option<io::ErrorKind>,
+}
+
+impl fmt::Display for Exchange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "> {} < {}",
+            to_hex(&self.request),
+            to_hex(&self.response)
+        )?;
+        if let Some(kind) = self.error {
+            write!(f, " ! {kind:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Session {
+    /// Groups the events into commands and their responses, ignoring timing. Reads before the first write are
+    /// dropped.
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        let mut exchanges: Vec<Exchange> = Vec::new();
+
+        for event in &self.events {
+            match (&event.kind, exchanges.last_mut()) {
+                (EventKind::Write(data), _) => exchanges.push(Exchange {
+                    request: data.clone(),
+                    response: Vec::new(),
+                    error: None,
+                }),
+                (EventKind::Read(data), Some(exchange)) => exchange.response.extend(data),
+                (EventKind::Error(kind), Some(exchange)) => exchange.error = Some(*kind),
+                (_, None) => (),
+            }
+        }
+
+        exchanges
+    }
+}
+
+/// A difference between two sessions found by [`diff`]. Positions count commands from 1, as in the errors of a
+/// [`ReplayPort`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// Both sessions sent the same command, but it was answered differently.
+    Response {
+        left: (usize, Exchange),
+        right: (usize, Exchange),
+    },
+    /// The sessions sent different commands at corresponding points.
+    Request {
+        left: (usize, Exchange),
+        right: (usize, Exchange),
+    },
+    /// A command sent only in the left session.
+    OnlyLeft(usize, Exchange),
+    /// A command sent only in the right session.
+    OnlyRight(usize, Exchange),
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::Response { left, right } => write!(
+                f,
+                "Command {}/{} answered differently:\n  - {}\n  + {}",
+                left.0, right.0, left.1, right.1
+            ),
+            Divergence::Request { left, right } => write!(
+                f,
+                "Command {}/{} differs:\n  - {}\n  + {}",
+                left.0, right.0, left.1, right.1
+            ),
+            Divergence::OnlyLeft(i, exchange) => {
+                write!(f, "Command {i} only in left:\n  - {exchange}")
+            }
+            Divergence::OnlyRight(i, exchange) => {
+                write!(f, "Command {i} only in right:\n  + {exchange}")
+            }
+        }
+    }
+}
+
+/// Commands searched ahead in each session for a common command to resynchronize on after the sessions differ.
+const RESYNC_WINDOW: usize = 32;
+
+/// Compares two sessions command by command, ignoring timing, and lists where they diverge.
+///
+/// Where the sessions sent different commands, such as an application retrying after an error in one of them, the
+/// comparison looks a little ahead for the next command both sent and carries on from there, so one extra command
+/// does not make every later one a divergence.
+pub fn diff(left: &Session, right: &Session) -> Vec<Divergence> {
+    let (left, right) = (left.exchanges(), right.exchanges());
+    let (mut i, mut j) = (0, 0);
+    let mut divergences = Vec::new();
+
+    while i < left.len() && j < right.len() {
+        if left[i].request == right[j].request {
+            if left[i] != right[j] {
+                divergences.push(Divergence::Response {
+                    left: (i + 1, left[i].clone()),
+                    right: (j + 1, right[j].clone()),
+                });
+            }
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        // The nearest pair of matching commands, by the total number skipped.
+        let resync = (1..=2 * RESYNC_WINDOW).find_map(|skipped| {
+            (0..=skipped.min(RESYNC_WINDOW))
+                .map(|di| (di, skipped - di))
+                .filter(|&(_, dj)| dj <= RESYNC_WINDOW)
+                .find(|&(di, dj)| {
+                    matches!(
+                        (left.get(i + di), right.get(j + dj)),
+                        (Some(l), Some(r)) if l.request == r.request
+                    )
+                })
+        });
+
+        match resync {
+            Some((di, dj)) => {
+                divergences
+                    .extend((i..i + di).map(|k| Divergence::OnlyLeft(k + 1, left[k].clone())));
+                divergences
+                    .extend((j..j + dj).map(|k| Divergence::OnlyRight(k + 1, right[k].clone())));
+                i += di;
+                j += dj;
+            }
+            None => {
+                divergences.push(Divergence::Request {
+                    left: (i + 1, left[i].clone()),
+                    right: (j + 1, right[j].clone()),
+                });
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    divergences.extend((i..left.len()).map(|k| Divergence::OnlyLeft(k + 1, left[k].clone())));
+    divergences.extend((j..right.len()).map(|k| Divergence::OnlyRight(k + 1, right[k].clone())));
+    divergences
+}
+
+#[derive(Debug)]
+struct Log {
+    out: BufWriter<File>,
+    start: Instant,
+}
+
+impl Log {
+    fn event(&mut self, dir: char, data: &str) {
+        let time = self.start.elapsed().as_secs_f64();
+        // A failure to record must not disturb the session itself.
+        let res = writeln!(self.out, "{time:.6} {dir} {data}").and_then(|_| self.out.flush());
+        if let Err(e) = res {
+            log::warn!(
+                "[{}:{}] Failed to record session: {:?}",
+                file!(),
+                line!(),
+                e
+            );
+        }
+    }
+}
+
+/// A serial port which records its traffic to a session file.
+#[derive(Debug)]
+pub struct Recorder {
+    port: Box<dyn SerialPort>,
+    log: Arc<Mutex<Log>>,
+}
+
+impl Recorder {
+    /// Records the traffic on `port` to a new file at `path`.
+    pub fn create<P: AsRef<Path>>(
+        port: Box<dyn SerialPort>,
+        path: P,
+    ) -> Result<Recorder, io::Error> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{HEADER}")?;
+        writeln!(out, "# started {}", Utc::now().to_rfc3339())?;
+        writeln!(out, "# timeout_ms {}", port.timeout().as_millis())?;
+        out.flush()?;
+
+        Ok(Recorder {
+            port,
+            log: Arc::new(Mutex::new(Log {
+                out,
+                start: Instant::now(),
+            })),
+        })
+    }
+
+    fn record(&self, dir: char, data: &str) {
+        self.log.lock().unwrap().event(dir, data);
+    }
+}
+
+impl Read for Recorder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.port.read(buf) {
+            Ok(n) => {
+                self.record('<', &to_hex(&buf[..n]));
+                Ok(n)
+            }
+            Err(e) => {
+                self.record('!', &format!("{:?}", e.kind()));
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.port.write(buf)?;
+        self.record('>', &to_hex(&buf[..n]));
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl SerialPort for Recorder {
+    fn name(&self) -> Option<String> {
+        self.port.name()
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        self.port.baud_rate()
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        self.port.data_bits()
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        self.port.flow_control()
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        self.port.parity()
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        self.port.stop_bits()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.port.timeout()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.port.set_baud_rate(baud_rate)
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> serialport::Result<()> {
+        self.port.set_data_bits(data_bits)
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> serialport::Result<()> {
+        self.port.set_flow_control(flow_control)
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> serialport::Result<()> {
+        self.port.set_parity(parity)
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> serialport::Result<()> {
+        self.port.set_stop_bits(stop_bits)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.port.set_timeout(timeout)
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        self.port.write_request_to_send(level)
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        self.port.write_data_terminal_ready(level)
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        self.port.read_clear_to_send()
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        self.port.read_data_set_ready()
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        self.port.read_ring_indicator()
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        self.port.read_carrier_detect()
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        self.port.bytes_to_read()
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        self.port.bytes_to_write()
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        self.port.clear(buffer_to_clear)
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(Recorder {
+            port: self.port.try_clone()?,
+            log: Arc::clone(&self.log),
+        }))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        self.port.set_break()
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        self.port.clear_break()
+    }
+}
+
+#[derive(Debug)]
+struct Replay {
+    events: VecDeque<Event>,
+    /// Events answering the last write.
+    pending: VecDeque<EventKind>,
+    written: usize,
+    timeout: Duration,
+}
+
+/// A serial port answering from a recorded [`Session`].
+///
+/// Each write must match the next recorded write, otherwise it fails with `InvalidData` describing the divergence.
+/// Reads then return the recorded responses and errors in order.
+#[derive(Debug, Clone)]
+pub struct ReplayPort {
+    replay: Arc<Mutex<Replay>>,
+}
+
+impl ReplayPort {
+    pub fn new(session: Session) -> ReplayPort {
+        ReplayPort {
+            replay: Arc::new(Mutex::new(Replay {
+                timeout: session.timeout.unwrap_or(DEFAULT_TIMEOUT),
+                events: session.events.into(),
+                pending: VecDeque::new(),
+                written: 0,
+            })),
+        }
+    }
+
+    /// Answers with the reads in `events` as if recorded without timing, and times out after a millisecond once they
+    /// run out, e.g. to script a hand control's responses in tests.
+    pub fn scripted(events: impl IntoIterator<Item = EventKind>) -> ReplayPort {
+        Self::new(Session {
+            started: None,
+            timeout: None,
+            events: events
+                .into_iter()
+                .map(|kind| Event {
+                    time: Duration::ZERO,
+                    kind,
+                })
+                .collect(),
+        })
+        .timeout(Duration::from_millis(1))
+    }
+
+    /// Replays the session file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ReplayPort, io::Error> {
+        Ok(Self::new(Session::load(path)?))
+    }
+
+    /// Overrides the recorded timeout, e.g. to replay sessions with timeouts quickly.
+    pub fn timeout(self, timeout: Duration) -> ReplayPort {
+        self.replay.lock().unwrap().timeout = timeout;
+        self
+    }
+
+    /// Number of recorded writes not yet replayed.
+    pub fn remaining(&self) -> usize {
+        let replay = self.replay.lock().unwrap();
+        replay
+            .events
+            .iter()
+            .filter(|e| matches!(e.kind, EventKind::Write(_)))
+            .count()
+    }
+}
+
+impl Read for ReplayPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut replay = self.replay.lock().unwrap();
+        match replay.pending.pop_front() {
+            Some(EventKind::Read(mut data)) => {
+                let n = buf.len().min(data.len());
+                buf[..n].copy_from_slice(&data[..n]);
+                if n < data.len() {
+                    data.drain(..n);
+                    replay.pending.push_front(EventKind::Read(data));
+                }
+                Ok(n)
+            }
+            Some(EventKind::Error(kind)) => Err(io::Error::new(kind, "Recorded read error")),
+            Some(EventKind::Write(_)) | None => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Operation timed out",
+            )),
+        }
+    }
+}
+
+impl Write for ReplayPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut replay = self.replay.lock().unwrap();
+        replay.written += 1;
+
+        // Responses never read before this write are discarded, as the recorded application did not see them.
+        replay.pending.clear();
+        let expected = loop {
+            match replay.events.pop_front().map(|e| e.kind) {
+                Some(EventKind::Write(data)) => break Some(data),
+                Some(_) => continue,
+                None => break None,
+            }
+        };
+
+        if expected.as_deref() != Some(buf) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Session diverged at write {}: expected {:?}, got {:?}.",
+                    replay.written,
+                    expected.map(|e| to_hex(&e)),
+                    to_hex(buf)
+                ),
+            ));
+        }
+
+        while let Some(event) = replay.events.front() {
+            if matches!(event.kind, EventKind::Write(_)) {
+                break;
+            }
+            let kind = replay.events.pop_front().unwrap().kind;
+            replay.pending.push_back(kind);
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for ReplayPort {
+    fn name(&self) -> Option<String> {
+        Some("replay".to_owned())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(9600)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.replay.lock().unwrap().timeout
+    }
+
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.replay.lock().unwrap().timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    /// Bytes of the recorded responses up to the next recorded error.
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        let replay = self.replay.lock().unwrap();
+        let n: usize = replay
+            .pending
+            .iter()
+            .map_while(|e| match e {
+                EventKind::Read(data) => Some(data.len()),
+                _ => None,
+            })
+            .sum();
+        Ok(n as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        if !matches!(buffer_to_clear, ClearBuffer::Output) {
+            self.replay.lock().unwrap().pending.clear();
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.clone()))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{Fault, SimPort};
+    use crate::mount::{Mount, SimMount, TrackingMode};
+    use crate::{CelestronMount, RADec};
+
+    #[test]
+    fn record_and_replay() {
+        let path = std::env::temp_dir().join(format!("nexlib-session-{}.txt", std::process::id()));
+
+        let sim = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        let recorder = Recorder::create(Box::new(sim.clone()), &path).unwrap();
+        let mut mount = CelestronMount::from_port(Box::new(recorder));
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        mount.goto_ra_dec(RADec::new(83.8, -5.4)).unwrap();
+        let recorded = mount.get_position_ra_dec().unwrap();
+        sim.inject(Fault::Timeout);
+        assert!(mount.is_aligned().is_err());
+        drop(mount);
+
+        let session = Session::load(&path).unwrap();
+        assert!(session.started.is_some());
+        assert_eq!(session.timeout, Some(Duration::from_millis(20)));
+        assert_eq!(session.events[0].kind, EventKind::Write(vec![b'T', 2]));
+        fs::remove_file(&path).unwrap();
+
+        let replay = ReplayPort::new(session);
+        let mut mount = CelestronMount::from_port(Box::new(replay.clone()));
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        mount.goto_ra_dec(RADec::new(83.8, -5.4)).unwrap();
+        let replayed = mount.get_position_ra_dec().unwrap();
+        assert_eq!((recorded.ra, recorded.dec), (replayed.ra, replayed.dec));
+        let e = mount.is_aligned().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(replay.remaining(), 0);
+    }
+
+    #[test]
+    fn divergence() {
+        let session = Session::parse(
+            "# nexlib session\n0.001 > 74\n0.050 < 0223\n0.060 > 4A\n0.110 ! TimedOut\n",
+        )
+        .unwrap();
+        assert_eq!(
+            session.events[3].kind,
+            EventKind::Error(io::ErrorKind::TimedOut)
+        );
+
+        let mut mount = CelestronMount::from_port(Box::new(
+            ReplayPort::new(session).timeout(Duration::from_millis(1)),
+        ));
+        let e = mount.get_model().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(e.to_string().contains("diverged at write 1"));
+
+        assert!(Session::parse("0.1 > 7").is_err());
+        assert!(Session::parse("0.1 ? 74").is_err());
+    }
+
+    #[test]
+    fn diff_sessions() {
+        let old = Session::parse(
+            "0.0 > 56\n0.1 < 051C23\n0.2 > 74\n0.3 < 0223\n0.4 > 4A\n0.5 < 0123\n0.6 > 4C\n0.7 < 3023\n",
+        )
+        .unwrap();
+        // Newer firmware answers 'V' differently, times out once on 't', and the application retries it.
+        let new = Session::parse(
+            "0.0 > 56\n0.1 < 052123\n0.2 > 74\n3.7 ! TimedOut\n3.8 > 74\n3.9 < 0223\n4.0 > 4A\n4.1 < 0123\n\
+             4.2 > 4C\n4.3 < 3023\n",
+        )
+        .unwrap();
+
+        let divergences = diff(&old, &new);
+        assert_eq!(divergences.len(), 3, "{divergences:#?}");
+        assert!(matches!(
+            &divergences[0],
+            Divergence::Response { left: (1, _), right: (1, r) } if r.response == [5, 0x21, b'#']
+        ));
+        assert!(matches!(
+            &divergences[1],
+            Divergence::Response { left: (2, _), right: (2, r) } if r.error == Some(io::ErrorKind::TimedOut)
+        ));
+        assert!(matches!(&divergences[2], Divergence::OnlyRight(3, e) if e.request == b"t"));
+        assert!(diff(&old, &old).is_empty());
+    }
+}
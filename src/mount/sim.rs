@@ -0,0 +1,868 @@
+//! Software mount simulator.
+//!
+//! [`SimMount`] implements [`Mount`] without any hardware, so applications, the GUI, and tests can run anywhere. It
+//! models an equatorial mount with a right ascension (hour angle) and a declination axis:
+//!
+//! - Gotos and manual slews accelerate and decelerate at a configurable rate up to a configurable maximum speed.
+//! - While tracking, the hour angle axis turns at the sidereal rate, or another rate set through [`FineTracking`], plus
+//!   an optional drift to mimic periodic error or polar misalignment. The axes turn the same way in either
+//!   hemisphere.
+//! - Like a German equatorial mount, a goto approaches targets east of the meridian from the west side of the pier and
+//!   others from the east side, and the mount stays on that side while tracking until the next goto.
+//! - Guide pulses move the axes at the guide rate, half sidereal unless set otherwise, for the length of the pulse.
+//! - The mount starts aligned unless configured otherwise. Gotos to right ascension and declination are refused until
+//!   it is aligned, and a sync both aligns it and corrects its pointing.
+//!
+//! By default the simulation follows the system clock. Tests can use [`SimMount::manual_clock`] and advance time
+//! explicitly with [`SimMount::step`] for deterministic results:
+//!
+//! ```
+//! use nexlib::mount::{Mount, SimMount};
+//! use nexlib::RADec;
+//! use std::time::Duration;
+//!
+//! let mut mount = SimMount::new().manual_clock(chrono::Utc::now());
+//! mount.goto_ra_dec(RADec::new(83.8, -5.4)).unwrap();
+//! while mount.goto_in_progress().unwrap() {
+//!     mount.step(Duration::from_secs(1));
+//! }
+//! ```
+//!
+//! [`SimPort`] puts the simulator behind a mock serial port speaking the NexStar protocol, with optional fault
+//! injection, to exercise [`CelestronMount`](crate::CelestronMount) itself.
+//!
+//! The real-time clock keeps its own time, which can be made to drift with [`SimMount::rtc_drift`] to exercise time
+//! synchronization. A GPS receiver added with [`SimMount::gps`] gets a fix some time after the simulation starts and
+//! then reports the site and the simulation time, as the receiver of a GPS series mount does. It is reached with
+//! [`Mount::get_gps`], on the simulator itself or through [`SimPort`] and a [`CelestronMount`](crate::CelestronMount),
+//! so [`Gps::wait_for_fix`](super::Gps::wait_for_fix) and anything built on it can be tested without the hardware:
+//!
+//! ```
+//! use nexlib::mount::{Gps, Mount, SimMount};
+//! use std::time::Duration;
+//!
+//! let mut mount = SimMount::new().site(40.0, -75.0).gps(Duration::from_millis(300));
+//! let fix = mount.get_gps().unwrap().wait_for_fix(Duration::from_secs(5)).unwrap();
+//! assert!((fix.location.latitude - 40.0).abs() < 0.001);
+//! ```
+
+mod port;
+pub use port::{Fault, SimPort};
+
+use super::error::MountError;
+use super::meridian::{PierSide, SideOfPier};
+use super::transform::{az_el_to_ha_dec, ha_dec_to_az_el, local_sidereal_time, wrap_180};
+use super::{
+    FineTracking, Gps, GuideDirection, Guider, Location, Model, Mount, NonGpsDevice, Rtc, SlewAxis, SlewDir,
+    SlewRate, TimeZoneSetting, TrackingMode, TrackingRate, SIDEREAL_RATE,
+};
+use crate::{AzEl, RADec};
+use chrono::{DateTime, Utc};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Default maximum slew speed in degrees per second.
+pub const DEFAULT_MAX_RATE: f64 = 4.0;
+
+/// Default axis acceleration in degrees per second squared.
+pub const DEFAULT_ACCELERATION: f64 = 2.0;
+
+/// Integration step while an axis is moving, in seconds.
+const MAX_STEP: f64 = 0.05;
+
+/// Distance in degrees at which a goto is considered complete.
+const GOTO_TOLERANCE: f64 = 1.0 / 3600.0;
+
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    RADec(RADec),
+    AzEl(AzEl),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Clock {
+    System(Instant),
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Axis {
+    /// Mechanical position in degrees.
+    pos: f64,
+    /// Velocity in degrees per second, excluding tracking.
+    vel: f64,
+    /// Commanded manual slew rate in degrees per second.
+    manual: f64,
+}
+
+impl Axis {
+    /// Changes the velocity towards `desired`, limited by `accel`.
+    fn accelerate(&mut self, desired: f64, accel: f64, dt: f64) {
+        let dv = (desired - self.vel).clamp(-accel * dt, accel * dt);
+        self.vel += dv;
+    }
+
+    /// Velocity which still allows stopping at a point `err` degrees away without overshooting it within `dt`.
+    fn approach(err: f64, max_rate: f64, accel: f64, dt: f64) -> f64 {
+        let speed = max_rate
+            .min((2.0 * accel * err.abs()).sqrt())
+            .min(err.abs() / dt);
+        err.signum() * speed
+    }
+}
+
+/// Another name for [`SimMount`].
+pub type SimulatedMount = SimMount;
+
+/// A simulated mount.
+#[derive(Debug, Clone)]
+pub struct SimMount {
+    latitude: f64,
+    longitude: f64,
+    max_rate: f64,
+    acceleration: f64,
+    /// Extra hour angle and declination motion while tracking, in degrees per second.
+    drift: (f64, f64),
+    model: Model,
+    aligned: bool,
+    tracking: TrackingMode,
+    /// Hour angle and declination tracking rates in degrees per second.
+    tracking_rates: [f64; 2],
+    /// Hour angle and declination axes.
+    axes: [Axis; 2],
+    /// Difference between the sky and mechanical positions, as set by syncs.
+    offset: [f64; 2],
+    /// Guide rate of the right ascension and declination axes, as a fraction of the sidereal rate.
+    guide_rates: [f64; 2],
+    /// Side of the pier chosen by the last goto.
+    pier_side: PierSide,
+    /// Time left of the guide pulse on each axis in seconds, negative for the negative direction.
+    pulses: [f64; 2],
+    target: Option<Target>,
+    time: DateTime<Utc>,
+    clock: Clock,
+    /// Time zone set through the hand control, which only changes how it reports local time.
+    zone: TimeZoneSetting,
+    /// Simulated time since the simulation started.
+    uptime: chrono::Duration,
+    /// Uptime at which the GPS receiver gets a fix, if there is one.
+    gps_fix_delay: Option<chrono::Duration>,
+    /// Time the real-time clock was last set to, and the uptime when it was set.
+    rtc_set: (DateTime<Utc>, chrono::Duration),
+    /// Real-time clock error in seconds per day, positive fast.
+    rtc_drift: f64,
+}
+
+impl Default for SimMount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimMount {
+    /// An aligned mount at latitude 45° and longitude 0°, parked pointing at the celestial pole, with tracking off.
+    pub fn new() -> SimMount {
+        let now = Utc::now();
+        SimMount {
+            latitude: 45.0,
+            longitude: 0.0,
+            max_rate: DEFAULT_MAX_RATE,
+            acceleration: DEFAULT_ACCELERATION,
+            drift: (0.0, 0.0),
+            model: Model::AdvancedVX,
+            aligned: true,
+            tracking: TrackingMode::Off,
+            tracking_rates: [SIDEREAL_RATE, 0.0],
+            axes: [
+                Axis::default(),
+                Axis {
+                    pos: 90.0,
+                    ..Axis::default()
+                },
+            ],
+            offset: [0.0, 0.0],
+            guide_rates: [0.5; 2],
+            pier_side: PierSide::East,
+            pulses: [0.0; 2],
+            target: None,
+            time: now,
+            clock: Clock::System(Instant::now()),
+            zone: TimeZoneSetting::default(),
+            uptime: chrono::Duration::zero(),
+            gps_fix_delay: None,
+            rtc_set: (now, chrono::Duration::zero()),
+            rtc_drift: 0.0,
+        }
+    }
+
+    /// Sets the site location in degrees, longitude positive east.
+    pub fn site(mut self, latitude: f64, longitude: f64) -> SimMount {
+        self.latitude = latitude;
+        self.longitude = longitude;
+        self
+    }
+
+    /// Sets the fastest slew speed in degrees per second, used for gotos and fixed rate 9.
+    pub fn max_rate(mut self, rate: f64) -> SimMount {
+        self.max_rate = rate;
+        self
+    }
+
+    /// Sets the axis acceleration in degrees per second squared.
+    pub fn acceleration(mut self, accel: f64) -> SimMount {
+        self.acceleration = accel;
+        self
+    }
+
+    /// Adds drift while tracking, in arcseconds per second on the right ascension and declination axes.
+    pub fn tracking_drift(mut self, ra: f64, dec: f64) -> SimMount {
+        self.drift = (ra / 3600.0, dec / 3600.0);
+        self
+    }
+
+    /// Sets whether the mount starts aligned.
+    pub fn aligned(mut self, aligned: bool) -> SimMount {
+        self.aligned = aligned;
+        self
+    }
+
+    /// Sets the model the mount reports.
+    pub fn model(mut self, model: Model) -> SimMount {
+        self.model = model;
+        self
+    }
+
+    /// Adds a GPS receiver, which gets a fix `fix_delay` after the simulation starts, and makes the mount a GPS series
+    /// one.
+    pub fn gps(mut self, fix_delay: Duration) -> SimMount {
+        self.gps_fix_delay =
+            Some(chrono::Duration::from_std(fix_delay).unwrap_or(chrono::Duration::max_value()));
+        self.model = Model::GPSSeries;
+        self
+    }
+
+    /// Makes the real-time clock gain `seconds_per_day`, or lose it if negative.
+    pub fn rtc_drift(mut self, seconds_per_day: f64) -> SimMount {
+        self.rtc_drift = seconds_per_day;
+        self
+    }
+
+    /// Starts the simulation clock at `start` and only advances it through [`SimMount::step`].
+    ///
+    /// The real-time clock starts at `start` too.
+    pub fn manual_clock(mut self, start: DateTime<Utc>) -> SimMount {
+        self.time = start;
+        self.rtc_set = (start, self.uptime);
+        self.clock = Clock::Manual;
+        self
+    }
+
+    /// Current simulation time.
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    /// Whether the GPS receiver has a fix, or `None` without a receiver.
+    pub fn gps_fixed(&self) -> Option<bool> {
+        self.gps_fix_delay.map(|delay| self.uptime >= delay)
+    }
+
+    /// Current time of the real-time clock, including its drift.
+    pub fn rtc_time(&self) -> DateTime<Utc> {
+        let (set, at) = self.rtc_set;
+        let elapsed = self.uptime - at;
+        let error =
+            elapsed.num_microseconds().unwrap_or(i64::MAX) as f64 * self.rtc_drift / 86_400.0;
+        set + elapsed + chrono::Duration::microseconds(error as i64)
+    }
+
+    /// Sets the real-time clock.
+    fn set_rtc(&mut self, time: DateTime<Utc>) {
+        self.rtc_set = (time, self.uptime);
+    }
+
+    /// Advances the simulation by `dt`.
+    pub fn step(&mut self, dt: Duration) {
+        let mut remaining = dt.as_secs_f64();
+
+        while remaining > 0.0 {
+            let idle =
+                self.target.is_none() && self.axes.iter().all(|a| a.vel == 0.0 && a.manual == 0.0);
+            let h = if idle {
+                remaining
+            } else {
+                remaining.min(MAX_STEP)
+            };
+            self.integrate(h);
+            remaining -= h;
+        }
+    }
+
+    /// Advances the simulation to the current time when following the system clock.
+    fn update(&mut self) {
+        if let Clock::System(last) = self.clock {
+            let now = Instant::now();
+            self.clock = Clock::System(now);
+            self.step(now - last);
+        }
+    }
+
+    fn integrate(&mut self, h: f64) {
+        let dt = chrono::Duration::microseconds((h * 1e6) as i64);
+        self.time += dt;
+        self.uptime += dt;
+
+        let goal = self.target.map(|t| self.mechanical(t));
+        for (i, axis) in self.axes.iter_mut().enumerate() {
+            let desired = match goal {
+                Some(goal) => {
+                    let err = goal[i] - axis.pos;
+                    let err = if i == 0 { wrap_180(err) } else { err };
+                    Axis::approach(err, self.max_rate, self.acceleration, h)
+                }
+                None => axis.manual,
+            };
+            axis.accelerate(desired, self.acceleration, h);
+            axis.pos += axis.vel * h;
+        }
+
+        for (i, pulse) in self.pulses.iter_mut().enumerate() {
+            let t = pulse.abs().min(h);
+            let moved = pulse.signum() * t * self.guide_rates[i] * SIDEREAL_RATE;
+            // Right ascension increases as the hour angle decreases.
+            self.axes[i].pos += if i == 0 { -moved } else { moved };
+            *pulse -= pulse.signum() * t;
+        }
+
+        if self.tracking != TrackingMode::Off {
+            self.axes[0].pos += (self.tracking_rates[0] + self.drift.0) * h;
+            self.axes[1].pos += (self.tracking_rates[1] + self.drift.1) * h;
+        }
+
+        self.axes[0].pos = wrap_180(self.axes[0].pos);
+        if self.axes[1].pos.abs() > 90.0 {
+            self.axes[1].pos = self.axes[1].pos.clamp(-90.0, 90.0);
+            self.axes[1].vel = 0.0;
+        }
+
+        if let Some(goal) = self.target.map(|t| self.mechanical(t)) {
+            let arrived = wrap_180(goal[0] - self.axes[0].pos).abs() < GOTO_TOLERANCE
+                && (goal[1] - self.axes[1].pos).abs() < GOTO_TOLERANCE;
+            if arrived {
+                self.axes[0].pos = goal[0];
+                self.axes[1].pos = goal[1];
+                self.axes.iter_mut().for_each(|a| a.vel = 0.0);
+                self.target = None;
+            }
+        }
+    }
+
+    /// Mechanical axis positions which point at `target`.
+    fn mechanical(&self, target: Target) -> [f64; 2] {
+        let (ha, dec) = self.ha_dec_of(target);
+        [
+            wrap_180(ha - self.offset[0]),
+            (dec - self.offset[1]).clamp(-90.0, 90.0),
+        ]
+    }
+
+    /// Sky hour angle and declination of `target` now.
+    fn ha_dec_of(&self, target: Target) -> (f64, f64) {
+        match target {
+            Target::RADec(coord) => (
+                local_sidereal_time(self.time, self.longitude) - coord.ra,
+                coord.dec,
+            ),
+            Target::AzEl(coord) => az_el_to_ha_dec(coord, self.latitude),
+        }
+    }
+
+    /// Sky hour angle and declination.
+    fn ha_dec(&self) -> (f64, f64) {
+        (
+            wrap_180(self.axes[0].pos + self.offset[0]),
+            self.axes[1].pos + self.offset[1],
+        )
+    }
+
+    fn goto(&mut self, target: Target) {
+        self.pier_side = PierSide::for_hour_angle(self.ha_dec_of(target).0);
+        self.axes.iter_mut().for_each(|a| a.manual = 0.0);
+        self.target = Some(target);
+    }
+
+    /// Sets the manual rate of `axis`, in degrees per second in the direction of increasing right ascension and
+    /// declination.
+    fn slew(&mut self, axis: SlewAxis, dir: SlewDir, rate: f64) {
+        self.target = None;
+        let rate = match dir {
+            SlewDir::Positive => rate,
+            SlewDir::Negative => -rate,
+        };
+        match axis {
+            // Right ascension increases as the hour angle decreases.
+            SlewAxis::RAAz => self.axes[0].manual = -rate,
+            SlewAxis::DecEl => self.axes[1].manual = rate,
+        }
+    }
+}
+
+impl Mount for SimMount {
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        self.update();
+        let (ha, dec) = self.ha_dec();
+        Ok(RADec::new(
+            (local_sidereal_time(self.time, self.longitude) - ha).rem_euclid(360.0),
+            dec,
+        ))
+    }
+
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        self.update();
+        let (ha, dec) = self.ha_dec();
+        Ok(ha_dec_to_az_el(ha, dec, self.latitude))
+    }
+
+    /// Starts a goto, which fails if the mount is not aligned.
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.update();
+        if !self.aligned {
+            return Err(MountError::NotAligned);
+        }
+        self.goto(Target::RADec(coord));
+        Ok(())
+    }
+
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        self.update();
+        self.goto(Target::AzEl(coord));
+        Ok(())
+    }
+
+    /// Corrects the pointing so the current position reads as `coord`, and marks the mount aligned.
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.update();
+        let ha = local_sidereal_time(self.time, self.longitude) - coord.ra;
+        self.offset = [
+            wrap_180(ha - self.axes[0].pos),
+            coord.dec - self.axes[1].pos,
+        ];
+        self.aligned = true;
+        Ok(())
+    }
+
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        self.update();
+        Ok(self.tracking)
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        self.update();
+        self.tracking = mode;
+        self.tracking_rates = [SIDEREAL_RATE, 0.0];
+        Ok(())
+    }
+
+    /// Slews at `rate` arcseconds per second.
+    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), MountError> {
+        self.update();
+        self.slew(axis, dir, (rate as f64 / 3600.0).min(self.max_rate));
+        Ok(())
+    }
+
+    /// Slews at the hand control rates: 2x to 32x sidereal for rates 1 to 5, then 0.3, 1, and 2 degrees per second,
+    /// and the maximum rate for rate 9.
+    fn slew_fixed(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        self.update();
+        let deg = match rate {
+            SlewRate::Stop => 0.0,
+            SlewRate::Rate1 => 2.0 * SIDEREAL_RATE,
+            SlewRate::Rate2 => 4.0 * SIDEREAL_RATE,
+            SlewRate::Rate3 => 8.0 * SIDEREAL_RATE,
+            SlewRate::Rate4 => 16.0 * SIDEREAL_RATE,
+            SlewRate::Rate5 => 32.0 * SIDEREAL_RATE,
+            SlewRate::Rate6 => 0.3,
+            SlewRate::Rate7 => 1.0,
+            SlewRate::Rate8 => 2.0,
+            SlewRate::Rate9 => self.max_rate,
+        };
+        self.slew(axis, dir, deg.min(self.max_rate));
+        Ok(())
+    }
+
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        Ok(Location {
+            latitude: self.latitude,
+            longitude: self.longitude,
+        })
+    }
+
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        self.update();
+        self.latitude = location.latitude;
+        self.longitude = location.longitude;
+        Ok(())
+    }
+
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        self.update();
+        Ok(self.time)
+    }
+
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        self.update();
+        self.time = time;
+        Ok(())
+    }
+
+    fn get_version(&mut self) -> Result<String, MountError> {
+        Ok(format!("sim-{}", env!("CARGO_PKG_VERSION")))
+    }
+
+    fn get_device_version(&mut self, _device: NonGpsDevice) -> Result<String, MountError> {
+        Ok(format!("sim-{}", env!("CARGO_PKG_VERSION")))
+    }
+
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        Ok(self.model)
+    }
+
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        Ok(byte)
+    }
+
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        Ok(self.aligned)
+    }
+
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        self.update();
+        Ok(self.target.is_some())
+    }
+
+    /// Cancels the goto; the axes decelerate to a stop.
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.update();
+        self.target = None;
+        Ok(())
+    }
+
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
+        self.update();
+        self.slew(axis, SlewDir::Positive, 0.0);
+        Ok(())
+    }
+
+    /// The GPS receiver added with [`SimMount::gps`]; see [`SimMount::gps_unit`].
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
+        Ok(Box::new(self.gps_unit()?))
+    }
+}
+
+/// The simulated GPS receiver of a [`SimMount`]; see [`SimMount::gps_unit`].
+#[derive(Debug)]
+pub struct SimGps<'a> {
+    mount: &'a mut SimMount,
+}
+
+impl SimMount {
+    /// The GPS receiver added with [`SimMount::gps`], which fails with `NotFound` without one, as
+    /// [`Mount::get_gps`] does.
+    pub fn gps_unit(&mut self) -> Result<SimGps<'_>, MountError> {
+        if self.gps_fix_delay.is_none() {
+            return Err(MountError::other(
+                io::ErrorKind::NotFound,
+                "The simulated mount has no GPS receiver.",
+            ));
+        }
+        Ok(SimGps { mount: self })
+    }
+}
+
+impl SimGps<'_> {
+    fn fixed(&mut self) -> Result<(), MountError> {
+        if self.is_linked()? {
+            Ok(())
+        } else {
+            Err(MountError::GpsNotLinked)
+        }
+    }
+}
+
+impl Gps for SimGps<'_> {
+    fn is_linked(&mut self) -> Result<bool, MountError> {
+        self.mount.update();
+        Ok(self.mount.gps_fixed() == Some(true))
+    }
+
+    /// The site of the simulated mount, once linked.
+    fn get_location(&mut self) -> Result<(f32, f32), MountError> {
+        self.fixed()?;
+        Ok((self.mount.latitude as f32, self.mount.longitude as f32))
+    }
+
+    /// The simulation time, once linked.
+    fn get_datetime(&mut self) -> Result<DateTime<Utc>, MountError> {
+        self.fixed()?;
+        Ok(self.mount.time)
+    }
+
+    fn get_device_version(&mut self) -> Result<String, MountError> {
+        Ok(format!("sim-{}", env!("CARGO_PKG_VERSION")))
+    }
+}
+
+impl Rtc for SimMount {
+    fn get_datetime(&mut self) -> Result<DateTime<Utc>, MountError> {
+        self.update();
+        Ok(self.rtc_time())
+    }
+
+    /// Sets the real-time clock to the simulation time.
+    fn set_datetime_now(&mut self) -> Result<(), MountError> {
+        self.update();
+        self.set_rtc(self.time);
+        Ok(())
+    }
+}
+
+impl SideOfPier for SimMount {
+    fn get_pier_side(&mut self) -> Result<PierSide, MountError> {
+        Ok(self.pier_side)
+    }
+}
+
+impl FineTracking for SimMount {
+    fn set_tracking_rate(&mut self, axis: SlewAxis, arcsec_per_sec: f64) -> Result<(), MountError> {
+        self.update();
+        if !(0.0..=super::codec::MAX_TRACKING_RATE).contains(&arcsec_per_sec.abs()) {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Tracking rate {arcsec_per_sec}\"/s is faster than {}\"/s.",
+                    super::codec::MAX_TRACKING_RATE
+                ),
+            ));
+        }
+        self.tracking_rates[axis as usize] = arcsec_per_sec / 3600.0;
+        Ok(())
+    }
+
+    fn set_tracking_preset(&mut self, rate: TrackingRate) -> Result<(), MountError> {
+        self.update();
+        if !matches!(self.tracking, TrackingMode::EQNorth | TrackingMode::EQSouth) {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidInput,
+                format!("Tracking presets need an equatorial tracking mode, not {:?}.", self.tracking),
+            ));
+        }
+        self.tracking_rates = [rate.arcsec_per_sec() / 3600.0, 0.0];
+        Ok(())
+    }
+}
+
+impl Guider for SimMount {
+    fn set_guide_rate(&mut self, axis: SlewAxis, rate: f64) -> Result<(), MountError> {
+        if !(0.0..1.0).contains(&rate) {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidInput,
+                format!("Guide rate {rate} is not between 0 and 1."),
+            ));
+        }
+        self.guide_rates[axis as usize] = rate;
+        Ok(())
+    }
+
+    fn get_guide_rate(&mut self, axis: SlewAxis) -> Result<f64, MountError> {
+        Ok(self.guide_rates[axis as usize])
+    }
+
+    fn pulse_guide(&mut self, direction: GuideDirection, duration_ms: u32) -> Result<(), MountError> {
+        self.update();
+        if duration_ms as u128 > super::codec::MAX_GUIDE_PULSE.as_millis() {
+            return Err(MountError::other(
+                io::ErrorKind::InvalidInput,
+                format!("Guide pulse of {duration_ms} ms is longer than {:?}.", super::codec::MAX_GUIDE_PULSE),
+            ));
+        }
+        let (axis, dir) = direction.axis();
+        let secs = duration_ms as f64 / 1000.0;
+        self.pulses[axis as usize] = match dir {
+            SlewDir::Positive => secs,
+            SlewDir::Negative => -secs,
+        };
+        Ok(())
+    }
+
+    fn is_pulse_guiding(&mut self) -> Result<bool, MountError> {
+        self.update();
+        Ok(self.pulses.iter().any(|&p| p != 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sim() -> SimMount {
+        SimMount::new().manual_clock(Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap())
+    }
+
+    /// Steps until the goto finishes, returning the time taken in seconds.
+    fn finish_goto(mount: &mut SimMount) -> u64 {
+        let mut secs = 0;
+        while mount.goto_in_progress().unwrap() {
+            mount.step(Duration::from_secs(1));
+            secs += 1;
+            assert!(secs < 600, "Goto did not finish.");
+        }
+        secs
+    }
+
+    #[test]
+    fn goto_accelerates_and_arrives() {
+        let mut mount = sim();
+        let target = RADec::new(
+            (local_sidereal_time(mount.time(), 0.0) + 30.0).rem_euclid(360.0),
+            10.0,
+        );
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        mount.goto_ra_dec(target).unwrap();
+
+        mount.step(Duration::from_secs(1));
+        assert!(mount.axes[1].vel.abs() <= DEFAULT_ACCELERATION + 1e-9);
+
+        // 80° of declination at 4°/s, plus ramps.
+        let secs = finish_goto(&mut mount);
+        assert!((20..40).contains(&secs), "Goto took {secs} s.");
+
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!(wrap_180(pos.ra - target.ra).abs() < GOTO_TOLERANCE);
+        assert!((pos.dec - target.dec).abs() < GOTO_TOLERANCE);
+
+        // Tracking holds the target.
+        mount.step(Duration::from_secs(3600));
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!(wrap_180(pos.ra - target.ra).abs() < 1e-6);
+    }
+
+    #[test]
+    fn drift_when_not_tracking() {
+        let mut mount = sim().tracking_drift(1.0, 0.0);
+        mount.goto_az_el(AzEl::new(180.0, 45.0)).unwrap();
+        finish_goto(&mut mount);
+
+        let before = mount.get_position_ra_dec().unwrap();
+        mount.step(Duration::from_secs(3600));
+        let after = mount.get_position_ra_dec().unwrap();
+        assert!((wrap_180(after.ra - before.ra) - 15.041).abs() < 0.01);
+        let az_el = mount.get_position_az_el().unwrap();
+        assert!((az_el.az - 180.0).abs() < 1e-6 && (az_el.el - 45.0).abs() < 1e-6);
+
+        // With tracking on, only the configured drift of 1"/s remains.
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        mount.step(Duration::from_secs(3600));
+        let tracked = mount.get_position_ra_dec().unwrap();
+        assert!((wrap_180(after.ra - tracked.ra) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tracks_at_preset_rates() {
+        let mut mount = sim();
+        mount.goto_az_el(AzEl::new(180.0, 45.0)).unwrap();
+        finish_goto(&mut mount);
+        assert!(mount.set_tracking_preset(TrackingRate::Lunar).is_err());
+
+        // The Moon falls behind the stars by about half a degree an hour.
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        mount.set_tracking_preset(TrackingRate::Lunar).unwrap();
+        let before = mount.get_position_ra_dec().unwrap();
+        mount.step(Duration::from_secs(3600));
+        let after = mount.get_position_ra_dec().unwrap();
+        assert!((wrap_180(after.ra - before.ra) - 0.3559).abs() < 1e-3);
+
+        mount.set_tracking_rate(SlewAxis::DecEl, 36.0).unwrap();
+        mount.step(Duration::from_secs(100));
+        assert!((mount.get_position_ra_dec().unwrap().dec - after.dec - 1.0).abs() < 1e-6);
+
+        // Setting the tracking mode returns to the sidereal rate.
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        let before = mount.get_position_ra_dec().unwrap();
+        mount.step(Duration::from_secs(3600));
+        assert!(wrap_180(mount.get_position_ra_dec().unwrap().ra - before.ra).abs() < 1e-6);
+    }
+
+    #[test]
+    fn alignment_and_sync() {
+        let mut mount = sim().aligned(false);
+        assert!(!mount.is_aligned().unwrap());
+        assert!(mount.goto_ra_dec(RADec::new(10.0, 10.0)).is_err());
+
+        let coord = RADec::new(100.0, 20.0);
+        mount.sync(coord).unwrap();
+        assert!(mount.is_aligned().unwrap());
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!((pos.ra - coord.ra).abs() < 1e-9 && (pos.dec - coord.dec).abs() < 1e-9);
+    }
+
+    #[test]
+    fn gps_unit() {
+        let mut mount = sim().site(40.0, -75.0);
+        assert_eq!(
+            mount.gps_unit().unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        let mut mount = mount.gps(Duration::from_secs(90));
+        assert_eq!(mount.get_model().unwrap(), Model::GPSSeries);
+        let mut gps = mount.gps_unit().unwrap();
+        assert!(!gps.is_linked().unwrap());
+        assert!(gps.get_datetime().is_err());
+        gps.mount.step(Duration::from_secs(90));
+        let fix = gps.wait_for_fix(Duration::ZERO).unwrap();
+        assert_eq!(
+            (fix.location.latitude, fix.location.longitude),
+            (40.0, -75.0)
+        );
+        assert_eq!(fix.time, mount.time());
+    }
+
+    #[test]
+    fn gps_through_mount_trait() {
+        let mut mount = sim().site(40.0, -75.0);
+        assert!(matches!(mount.get_gps(), Err(e) if e.kind() == io::ErrorKind::NotFound));
+
+        let mut mount = mount.gps(Duration::from_secs(90));
+        assert!(!mount.get_gps().unwrap().is_linked().unwrap());
+        mount.step(Duration::from_secs(90));
+        let mut gps = mount.get_gps().unwrap();
+        assert_eq!(gps.get_location().unwrap(), (40.0, -75.0));
+        let fix = gps.wait_for_fix(Duration::ZERO).unwrap();
+        assert_eq!(fix.location.longitude, -75.0);
+        drop(gps);
+        assert_eq!(fix.time, mount.time());
+    }
+
+    #[test]
+    fn manual_slew_ramps() {
+        let mut mount = sim();
+        mount
+            .slew_fixed(SlewAxis::DecEl, SlewDir::Negative, SlewRate::Rate8)
+            .unwrap();
+        mount.step(Duration::from_millis(500));
+        assert!((mount.axes[1].vel + 1.0).abs() < 1e-9);
+        mount.step(Duration::from_secs(2));
+        assert!((mount.axes[1].vel + 2.0).abs() < 1e-9);
+
+        mount.stop_slew(SlewAxis::DecEl).unwrap();
+        mount.step(Duration::from_secs(2));
+        assert_eq!(mount.axes[1].vel, 0.0);
+        assert!(mount.get_position_ra_dec().unwrap().dec < 90.0 - 4.0);
+    }
+}
@@ -0,0 +1,717 @@
+//! Mock serial transport speaking the NexStar protocol on behalf of a [`SimMount`].
+//!
+//! [`SimPort`] implements `serialport::SerialPort`, so a [`CelestronMount`](crate::CelestronMount) created with
+//! [`CelestronMount::from_port`](crate::CelestronMount::from_port) runs its real encoding and decoding against the
+//! simulator. Faults queued with [`SimPort::inject`] corrupt the responses to the following commands, one fault per
+//! command, so error handling can be exercised deterministically:
+//!
+//! ```
+//! use nexlib::mount::sim::{Fault, SimPort};
+//! use nexlib::mount::{Mount, SimMount};
+//! use nexlib::CelestronMount;
+//!
+//! let port = SimPort::new(SimMount::new());
+//! let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+//!
+//! port.inject(Fault::Truncate(1));
+//! assert!(mount.get_tracking_mode().is_err());
+//! assert!(mount.get_tracking_mode().is_ok());
+//! ```
+//!
+//! Reads never block: with nothing to read they fail with `TimedOut` immediately, unless [`SimPort::blocking_reads`]
+//! makes them wait for the port's timeout first, as a real port does.
+
+use super::SimMount;
+use crate::mount::{
+    codec, Location, Mount, SlewAxis, SlewDir, SlewRate, TrackingMode, DEFAULT_TIMEOUT,
+};
+use crate::{AzEl, RADec};
+use chrono::{Datelike, Timelike};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A corruption applied to the response to one command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// The command is not answered at all.
+    Timeout,
+    /// Only the first `n` bytes of the response arrive.
+    Truncate(usize),
+    /// The bytes arrive ahead of the response, as after line noise or a previous partial response.
+    Garbage(Vec<u8>),
+    /// The terminating `#` arrives [`LATE`] after the rest of the response.
+    DelayTerminator,
+    /// Only the first bytes of the response are ready; the rest arrive [`LATE`] later, as on a slow adapter.
+    Split(usize),
+    /// The addressed device does not answer: passthrough commands get an extra byte, others only `#`.
+    DeviceUnavailable,
+    /// The hand control answers with an error reply carrying the code, as a [`NexError`](codec::NexError).
+    Error(u8),
+}
+
+/// How long the late bytes of a [`Fault::DelayTerminator`] or [`Fault::Split`] response take to arrive.
+pub const LATE: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Default)]
+struct Link {
+    /// Bytes ready to be read.
+    out: VecDeque<u8>,
+    /// Bytes which arrive at `late_at`, or on the next blocking read.
+    late: Vec<u8>,
+    late_at: Option<Instant>,
+    faults: VecDeque<Fault>,
+    timeout: Duration,
+    /// Whether reads with nothing to read wait for `timeout`.
+    blocking: bool,
+}
+
+/// A serial port answered by a simulated hand controller.
+#[derive(Debug, Clone)]
+pub struct SimPort {
+    mount: Arc<Mutex<SimMount>>,
+    link: Arc<Mutex<Link>>,
+}
+
+/// Encodes a pair of angles as the precise commands do, with the 24-bit resolution of the hand controller.
+fn encode_pair(a: f64, b: f64) -> Vec<u8> {
+    let encode = |deg| codec::encode_angle(deg) & 0xFFFF_FF00;
+    codec::encode_position_pair(encode(a), encode(b)).to_vec()
+}
+
+/// Encodes a pair of angles as the low precision commands do.
+fn encode_low_pair(a: f64, b: f64) -> Vec<u8> {
+    codec::encode_low_position_pair(codec::encode_low_angle(a), codec::encode_low_angle(b)).to_vec()
+}
+
+/// Decodes a `XXXXXXXX,XXXXXXXX` or `XXXX,XXXX` argument pair into degrees, the second in [-180, 180).
+fn decode_pair(args: &[u8]) -> Option<(f64, f64)> {
+    let (a, b) = codec::decode_position_pair(args).ok()?;
+    Some((codec::decode_angle(a), codec::decode_signed_angle(b)))
+}
+
+impl SimPort {
+    pub fn new(mount: SimMount) -> SimPort {
+        SimPort {
+            mount: Arc::new(Mutex::new(mount)),
+            link: Arc::new(Mutex::new(Link {
+                timeout: DEFAULT_TIMEOUT,
+                ..Link::default()
+            })),
+        }
+    }
+
+    /// Sets the time the client waits for responses, reported by `SerialPort::timeout`.
+    pub fn timeout(self, timeout: Duration) -> SimPort {
+        self.link().timeout = timeout;
+        self
+    }
+
+    /// Makes reads with nothing to read wait for the timeout before failing, as a real serial port does.
+    pub fn blocking_reads(self) -> SimPort {
+        self.link().blocking = true;
+        self
+    }
+
+    /// The simulated mount, e.g. to advance its clock.
+    pub fn mount(&self) -> MutexGuard<'_, SimMount> {
+        self.mount.lock().unwrap()
+    }
+
+    /// Queues `fault` for the response to the next command without one.
+    pub fn inject(&self, fault: Fault) {
+        self.link().faults.push_back(fault);
+    }
+
+    /// Number of queued faults not yet applied.
+    pub fn pending_faults(&self) -> usize {
+        self.link().faults.len()
+    }
+
+    fn link(&self) -> MutexGuard<'_, Link> {
+        self.link.lock().unwrap()
+    }
+
+    /// Executes one command, returning the response including the `#` terminator.
+    fn respond(&self, cmd: &[u8], unavailable: bool) -> Vec<u8> {
+        let mut mount = self.mount();
+        let mut res = match cmd {
+            [b'P', _, dev, op, a1, a2, a3, len] => {
+                if unavailable {
+                    vec![0; *len as usize + 1]
+                } else {
+                    match passthrough(&mut mount, *dev, *op, [*a1, *a2, *a3]) {
+                        Some(data) => data,
+                        None => vec![0; *len as usize + 1],
+                    }
+                }
+            }
+            _ if unavailable => Vec::new(),
+            [b'e'] => {
+                let pos = mount.get_position_ra_dec().unwrap();
+                encode_pair(pos.ra, pos.dec)
+            }
+            [b'z'] => {
+                let pos = mount.get_position_az_el().unwrap();
+                encode_pair(pos.az, pos.el)
+            }
+            [b'E'] => {
+                let pos = mount.get_position_ra_dec().unwrap();
+                encode_low_pair(pos.ra, pos.dec)
+            }
+            [b'Z'] => {
+                let pos = mount.get_position_az_el().unwrap();
+                encode_low_pair(pos.az, pos.el)
+            }
+            [b'r', args @ ..] | [b's', args @ ..] | [b'R', args @ ..] | [b'S', args @ ..] => {
+                if let Some((ra, dec)) = decode_pair(args) {
+                    let coord = RADec::new(ra, dec);
+                    // A goto which the mount refuses is silently ignored, as by the hand controller.
+                    let _ = match cmd[0] {
+                        b'r' | b'R' => mount.goto_ra_dec(coord),
+                        _ => mount.sync(coord),
+                    };
+                }
+                Vec::new()
+            }
+            [b'b', args @ ..] | [b'B', args @ ..] => {
+                if let Some((az, el)) = decode_pair(args) {
+                    mount.goto_az_el(AzEl::new(az, el)).unwrap();
+                }
+                Vec::new()
+            }
+            [b't'] => vec![mount.get_tracking_mode().unwrap() as u8],
+            [b'T', mode] => {
+                let mode = match mode {
+                    1 => TrackingMode::AzEl,
+                    2 => TrackingMode::EQNorth,
+                    3 => TrackingMode::EQSouth,
+                    _ => TrackingMode::Off,
+                };
+                mount.set_tracking_mode(mode).unwrap();
+                Vec::new()
+            }
+            [b'h'] => codec::encode_time(mount.get_time().unwrap(), mount.zone).to_vec(),
+            [b'H', args @ ..] => {
+                if let (Ok(time), Ok(zone)) =
+                    (codec::decode_time(args), codec::decode_time_zone(args))
+                {
+                    mount.set_time(time).unwrap();
+                    mount.zone = zone;
+                }
+                Vec::new()
+            }
+            [b'w'] => codec::encode_location(Location {
+                latitude: mount.latitude,
+                longitude: mount.longitude,
+            })
+            .to_vec(),
+            [b'W', args @ ..] => {
+                if let Ok(site) = codec::decode_location(args) {
+                    mount.latitude = site.latitude;
+                    mount.longitude = site.longitude;
+                }
+                Vec::new()
+            }
+            [b'V'] => vec![5, 35],
+            [b'm'] => vec![mount.get_model().unwrap() as u8],
+            [b'J'] => vec![mount.is_aligned().unwrap() as u8],
+            [b'L'] => vec![b'0' + mount.goto_in_progress().unwrap() as u8],
+            [b'M'] => {
+                mount.cancel_goto().unwrap();
+                Vec::new()
+            }
+            [b'K', c] => vec![*c],
+            _ => Vec::new(),
+        };
+
+        res.push(b'#');
+        res
+    }
+}
+
+/// Answers a passthrough command, or `None` if the device does not exist.
+fn passthrough(mount: &mut SimMount, dev: u8, op: u8, args: [u8; 3]) -> Option<Vec<u8>> {
+    let axis = match dev {
+        16 => Some(SlewAxis::RAAz),
+        17 => Some(SlewAxis::DecEl),
+        _ => None,
+    };
+
+    match (dev, op, axis) {
+        (_, 6 | 7, Some(axis)) => {
+            let dir = if op == 6 {
+                SlewDir::Positive
+            } else {
+                SlewDir::Negative
+            };
+            let rate = u16::from_be_bytes([args[0], args[1]]) / 4;
+            mount.slew_variable(axis, dir, rate).unwrap();
+            Some(Vec::new())
+        }
+        (_, 36 | 37, Some(axis)) => {
+            let dir = if op == 36 {
+                SlewDir::Positive
+            } else {
+                SlewDir::Negative
+            };
+            let rate = SlewRate::try_from(args[0]).unwrap_or(SlewRate::Stop);
+            mount.slew_fixed(axis, dir, rate).unwrap();
+            Some(Vec::new())
+        }
+        // MC_GET_POSITION, as a 24-bit fraction of a revolution of the hour angle or declination axis.
+        (16 | 17, codec::MC_GET_POSITION, _) => {
+            mount.update();
+            let value = codec::encode_motor_angle(mount.axes[(dev - 16) as usize].pos);
+            Some(value.to_be_bytes()[1..].to_vec())
+        }
+        (176, _, _) if mount.gps_fixed().is_none() => None,
+        (16 | 17 | 176 | 178, codec::MC_GET_VERSION, _) => Some(vec![7, 11]),
+        // Whether the GPS is linked, and whether its time is valid.
+        (176, 55 | 54, _) => {
+            mount.update();
+            Some(vec![(mount.gps_fixed() == Some(true)) as u8])
+        }
+        // Latitude and longitude, as signed 24-bit fractions of a revolution.
+        (176, 1 | 2, _) => {
+            let deg = if op == 1 {
+                mount.latitude
+            } else {
+                mount.longitude
+            };
+            Some(codec::encode_angle(deg).to_be_bytes()[..3].to_vec())
+        }
+        (176 | 178, 3 | 4 | 51, _) => {
+            mount.update();
+            // The GPS reports the true time, the real-time clock its own.
+            let t = if dev == 176 {
+                mount.time()
+            } else {
+                mount.rtc_time()
+            };
+            Some(match op {
+                3 => vec![t.month() as u8, t.day() as u8],
+                4 => (t.year() as u16).to_be_bytes().to_vec(),
+                _ => vec![t.hour() as u8, t.minute() as u8, t.second() as u8],
+            })
+        }
+        // Setting the month and day, the year, or the time of the real-time clock.
+        (178, 131 | 132 | 179, _) => {
+            mount.update();
+            let t = mount.rtc_time();
+            let set = match op {
+                131 => t
+                    .with_day(1)
+                    .and_then(|t| t.with_month(args[0] as u32)?.with_day(args[1] as u32)),
+                132 => t.with_year(u16::from_be_bytes([args[0], args[1]]) as i32),
+                _ => t
+                    .with_hour(args[0] as u32)
+                    .and_then(|t| t.with_minute(args[1] as u32))
+                    .and_then(|t| t.with_second(args[2] as u32)),
+            };
+            if let Some(set) = set {
+                mount.set_rtc(set);
+            }
+            Some(Vec::new())
+        }
+        _ => None,
+    }
+}
+
+impl Read for SimPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut link = self.link();
+        if link.out.is_empty() && link.blocking {
+            let timeout = link.timeout;
+            drop(link);
+            std::thread::sleep(timeout);
+            link = self.link();
+        }
+        if link.out.is_empty() {
+            let late = std::mem::take(&mut link.late);
+            link.out.extend(late);
+        }
+        if link.out.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Operation timed out",
+            ));
+        }
+
+        let n = buf.len().min(link.out.len());
+        for (dst, src) in buf.iter_mut().zip(link.out.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for SimPort {
+    /// Treats each write as one complete command.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let fault = {
+            let mut link = self.link();
+            // Late bytes arrive before the response to the new command.
+            let late = std::mem::take(&mut link.late);
+            link.out.extend(late);
+            link.faults.pop_front()
+        };
+
+        let mut res = self.respond(buf, fault == Some(Fault::DeviceUnavailable));
+        let mut late = Vec::new();
+        match fault {
+            Some(Fault::Timeout) => res.clear(),
+            Some(Fault::Truncate(n)) => res.truncate(n),
+            Some(Fault::Garbage(bytes)) => {
+                res.splice(0..0, bytes);
+            }
+            Some(Fault::DelayTerminator) => late.extend(res.pop()),
+            Some(Fault::Split(n)) => late.extend(res.drain(n.min(res.len())..)),
+            Some(Fault::Error(code)) => res = vec![b'!', b'0' + code, b'#'],
+            Some(Fault::DeviceUnavailable) | None => (),
+        }
+
+        let mut link = self.link();
+        link.out.extend(res);
+        link.late = late;
+        link.late_at = Some(Instant::now() + LATE);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for SimPort {
+    fn name(&self) -> Option<String> {
+        Some("sim".to_owned())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(9600)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.link().timeout
+    }
+
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.link().timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        let mut link = self.link();
+        if link.late_at.is_some_and(|at| Instant::now() >= at) {
+            let late = std::mem::take(&mut link.late);
+            link.out.extend(late);
+        }
+        Ok(link.out.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        if !matches!(buffer_to_clear, ClearBuffer::Output) {
+            let mut link = self.link();
+            link.out.clear();
+            link.late.clear();
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.clone()))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::error::MountError;
+    use crate::mount::{Gps, NonGpsDevice, Rtc};
+    use crate::CelestronMount;
+    use chrono::{TimeZone, Utc};
+
+    fn connect() -> (SimPort, CelestronMount) {
+        let start = Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap();
+        let port =
+            SimPort::new(SimMount::new().manual_clock(start)).timeout(Duration::from_millis(20));
+        let mount = CelestronMount::from_port(Box::new(port.clone()));
+        (port, mount)
+    }
+
+    #[test]
+    fn angle_codec() {
+        assert_eq!(encode_pair(180.0, -90.0), b"80000000,C0000000");
+        assert_eq!(encode_pair(83.8, 0.0), b"3B975300,00000000");
+        assert_eq!(decode_pair(b"40000000,C0000000").unwrap(), (90.0, -90.0));
+        assert!(decode_pair(b"40000000,FFFFFFFFC0000000").is_none());
+    }
+
+    #[test]
+    fn celestron_mount_over_sim() {
+        let (port, mut mount) = connect();
+        assert_eq!(mount.get_model().unwrap(), crate::mount::Model::AdvancedVX);
+        assert!(mount.is_aligned().unwrap());
+
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::EQNorth);
+
+        let target = RADec::new(100.0, 20.0);
+        mount.goto_ra_dec(target).unwrap();
+        assert!(mount.goto_in_progress().unwrap());
+        port.mount().step(Duration::from_secs(120));
+        assert!(!mount.goto_in_progress().unwrap());
+
+        // The hand controller reports 24-bit positions.
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!((pos.ra - target.ra).abs() < 1e-3);
+        assert!((pos.dec - target.dec).abs() < 1e-3);
+
+        mount
+            .slew_variable(SlewAxis::DecEl, SlewDir::Positive, 3600)
+            .unwrap();
+        port.mount().step(Duration::from_secs(10));
+        assert!(mount.get_position_ra_dec().unwrap().dec > 25.0);
+
+        let site = Location {
+            latitude: -33.5,
+            longitude: 18.25,
+        };
+        mount.set_location(site).unwrap();
+        assert_eq!(port.mount().get_location().unwrap(), site);
+        assert_eq!(mount.get_location().unwrap(), site);
+
+        assert_eq!(
+            mount.get_device_version(NonGpsDevice::AzRaMotor).unwrap(),
+            "7.11"
+        );
+        assert_eq!(
+            mount.get_datetime().unwrap(),
+            port.mount().time().with_nanosecond(0).unwrap()
+        );
+    }
+
+    #[test]
+    fn faults() {
+        let (port, mut mount) = connect();
+        assert_eq!(mount.echo(b'Q').unwrap(), b'Q');
+
+        port.inject(Fault::Timeout);
+        assert!(mount.ping().is_err());
+        assert!(mount.ping().is_ok());
+        port.inject(Fault::Timeout);
+        let e = mount.get_tracking_mode().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert!(mount.get_tracking_mode().is_ok());
+
+        port.inject(Fault::Truncate(1));
+        let e = mount.get_position_ra_dec().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+
+        port.inject(Fault::Garbage(vec![0x7f]));
+        assert!(mount.get_tracking_mode().is_err());
+
+        port.inject(Fault::Error(4));
+        let e = mount.goto_ra_dec(RADec::new(100.0, 20.0)).unwrap_err();
+        assert_eq!(e, MountError::NotAligned);
+        port.inject(Fault::Error(2));
+        let e = mount.get_tracking_mode().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::ResourceBusy);
+
+        port.inject(Fault::DeviceUnavailable);
+        let e = mount.get_device_version(NonGpsDevice::RtcUnit).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotConnected);
+
+        // Reading continues while the late terminator is waiting.
+        port.inject(Fault::DelayTerminator);
+        assert!(mount.is_aligned().unwrap());
+        assert_eq!(port.pending_faults(), 0);
+        assert!(mount.is_aligned().unwrap());
+    }
+
+    #[test]
+    fn retries_queries() {
+        let (port, mut mount) = connect();
+        let comms = crate::mount::CommsConfig {
+            retries: 1,
+            ..mount.comms()
+        };
+        mount.set_comms(comms).unwrap();
+
+        port.inject(Fault::Timeout);
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::Off);
+        port.inject(Fault::Truncate(1));
+        assert!(mount.get_device_version(NonGpsDevice::AzRaMotor).is_ok());
+        assert_eq!(mount.metrics().retries, 2);
+
+        // Hand control errors are not retried, nor are commands changing the mount's state.
+        port.inject(Fault::Error(2));
+        assert!(mount.get_tracking_mode().is_err());
+        port.inject(Fault::Timeout);
+        assert!(mount.set_tracking_mode(TrackingMode::EQNorth).is_err());
+        assert_eq!(mount.metrics().retries, 2);
+    }
+
+    #[test]
+    fn low_precision_commands() {
+        let (_port, mut mount) = connect();
+        mount.set_precision(crate::mount::PrecisionMode::Low);
+
+        mount.sync(RADec::new(83.82, -5.39)).unwrap();
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!(
+            (pos.ra - 83.82).abs() < 0.01 && (pos.dec + 5.39).abs() < 0.01,
+            "{pos:?}"
+        );
+        // Only the 16-bit commands give positions on whole steps of 1/65536 revolution.
+        let step = 360.0 / 65_536.0;
+        assert!((pos.dec / step - (pos.dec / step).round()).abs() < 1e-6, "{pos:?}");
+
+        mount.set_precision(crate::mount::PrecisionMode::Auto);
+        mount.sync(RADec::new(83.82, -5.39)).unwrap();
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!((pos.dec / step - (pos.dec / step).round()).abs() > 1e-6, "{pos:?}");
+    }
+
+    #[test]
+    fn gps_and_rtc() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap();
+        let sim = SimMount::new()
+            .site(40.5, -75.25)
+            .gps(Duration::from_secs(60))
+            .rtc_drift(2.0)
+            .manual_clock(start);
+        let port = SimPort::new(sim).timeout(Duration::from_millis(20));
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        let mut gps = mount.get_gps().unwrap();
+        assert!(!gps.is_linked().unwrap());
+        assert_eq!(
+            gps.get_location().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        port.mount().step(Duration::from_secs(60));
+        let fix = gps.wait_for_fix(Duration::ZERO).unwrap();
+        assert!((fix.location.latitude - 40.5).abs() < 1e-4);
+        assert!((fix.location.longitude + 75.25).abs() < 1e-4);
+        assert_eq!(fix.time, start + chrono::TimeDelta::seconds(60));
+        drop(gps);
+
+        // The real-time clock gains 2 seconds a day until set.
+        port.mount().step(Duration::from_secs(86_400 - 60));
+        let time = port.mount().time();
+        assert_eq!(
+            mount.get_datetime().unwrap(),
+            time + chrono::TimeDelta::seconds(2)
+        );
+        mount.set_datetime_now().unwrap();
+        let now = Utc::now();
+        assert!((mount.get_datetime().unwrap() - now).num_seconds().abs() <= 1);
+
+        let mut sim = SimMount::new().manual_clock(start).rtc_drift(-86.4);
+        sim.step(Duration::from_secs(1000));
+        assert_eq!(
+            sim.get_datetime().unwrap(),
+            start + chrono::TimeDelta::seconds(999)
+        );
+        sim.set_datetime_now().unwrap();
+        assert_eq!(sim.get_datetime().unwrap(), sim.time());
+    }
+
+    #[test]
+    fn gps_sync() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap();
+        let sim = SimMount::new()
+            .site(40.5, -75.25)
+            .gps(Duration::from_secs(60))
+            .manual_clock(start);
+        let port = SimPort::new(sim).timeout(Duration::from_millis(20));
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        let e = mount.sync_from_gps().unwrap_err();
+        assert_eq!(e, MountError::GpsNotLinked);
+
+        port.mount().step(Duration::from_secs(60));
+        let sync = mount.sync_from_gps().unwrap();
+        assert!((sync.fix.location.latitude - 40.5).abs() < 1e-4);
+        assert!(sync.rtc_updated);
+        assert_eq!(sync.clock_offset, Some(chrono::TimeDelta::zero()));
+        assert!((mount.get_site().unwrap().latitude - 40.5).abs() < 1e-3);
+        assert_eq!(mount.get_datetime().unwrap(), sync.fix.time);
+    }
+}
@@ -0,0 +1,300 @@
+//! Long-duration tracking stability measurement.
+//!
+//! Before committing to a night of long exposures on a new mount or pier, it is worth knowing how well the mount holds
+//! a target. [`stability_monitor`] reads the position of a tracking mount at a fixed interval for hours, compares each
+//! sample to the starting position, which sidereal tracking should hold, and summarizes the result in a
+//! [`StabilityReport`]: the steady drift in each axis, the RMS error and the jitter left after removing the drift, the
+//! worst excursions, and any gaps in the record.
+//!
+//! ```no_run
+//! use nexlib::mount::stability::stability_monitor;
+//! use nexlib::mount::{Mount, TrackingMode};
+//! use nexlib::{CelestronMount, RADec};
+//! use std::time::Duration;
+//!
+//! let mut mount = CelestronMount::new().unwrap();
+//! mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+//! mount.goto_and_wait(RADec::new(83.82, -5.39), None).unwrap();
+//! let report = stability_monitor(&mut mount, Duration::from_secs(4 * 3600), Duration::from_secs(10)).unwrap();
+//! println!("{report}");
+//! ```
+//!
+//! The mount reports where its encoders say it points, so the report covers the drive and its tracking rate, not
+//! flexure or polar misalignment, which need guiding or plate solving to measure. Failed reads are counted and left as
+//! gaps rather than ending the run.
+
+use super::error::MountError;
+use super::transform::wrap_180;
+use super::{Mount, RADec, TrackingMode};
+use chrono::{DateTime, Utc};
+use log::warn;
+use std::fmt;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Number of worst excursions kept in a report.
+const WORST: usize = 5;
+
+/// Error from the starting position at one sample.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Excursion {
+    pub time: DateTime<Utc>,
+    /// Error in right ascension, on the sky, in arcseconds.
+    pub ra_arcsec: f64,
+    /// Error in declination, in arcseconds.
+    pub dec_arcsec: f64,
+}
+
+impl Excursion {
+    /// Total error, in arcseconds.
+    pub fn arcsec(&self) -> f64 {
+        self.ra_arcsec.hypot(self.dec_arcsec)
+    }
+}
+
+/// A stretch without samples.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Gap {
+    /// Time of the last sample before the gap.
+    pub start: DateTime<Utc>,
+    pub duration: Duration,
+}
+
+/// The result of a [`stability_monitor`] run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StabilityReport {
+    pub started: DateTime<Utc>,
+    pub ended: DateTime<Utc>,
+    /// Positions read.
+    pub samples: usize,
+    /// Reads which failed.
+    pub failed: usize,
+    /// Steady drift in right ascension, on the sky, in arcseconds per minute.
+    pub drift_ra: f64,
+    /// Steady drift in declination, in arcseconds per minute.
+    pub drift_dec: f64,
+    /// RMS error from the starting position, in arcseconds.
+    pub rms_arcsec: f64,
+    /// RMS error left after removing the steady drift, in arcseconds.
+    pub jitter_arcsec: f64,
+    /// The samples furthest from the starting position, worst first.
+    pub worst: Vec<Excursion>,
+    /// Stretches of more than twice the sampling interval without a sample.
+    pub gaps: Vec<Gap>,
+}
+
+impl StabilityReport {
+    /// Analyzes positions read every `interval` while tracking, in time order.
+    ///
+    /// Returns `None` for fewer than two samples.
+    pub fn from_samples(
+        samples: &[(DateTime<Utc>, RADec)],
+        interval: Duration,
+        failed: usize,
+    ) -> Option<StabilityReport> {
+        let [(started, start), .., (ended, _)] = *samples else {
+            return None;
+        };
+        let cos_dec = start.dec.to_radians().cos();
+        let errors = samples
+            .iter()
+            .map(|(time, pos)| Excursion {
+                time: *time,
+                ra_arcsec: wrap_180(pos.ra - start.ra) * cos_dec * 3600.0,
+                dec_arcsec: (pos.dec - start.dec) * 3600.0,
+            })
+            .collect::<Vec<_>>();
+        let minutes = errors
+            .iter()
+            .map(|e| (e.time - started).num_milliseconds() as f64 / 60_000.0)
+            .collect::<Vec<_>>();
+
+        let (ra_fit, ra_slope) = fit(&minutes, errors.iter().map(|e| e.ra_arcsec));
+        let (dec_fit, dec_slope) = fit(&minutes, errors.iter().map(|e| e.dec_arcsec));
+        let n = errors.len() as f64;
+        let rms_arcsec = (errors.iter().map(|e| e.arcsec().powi(2)).sum::<f64>() / n).sqrt();
+        let jitter_arcsec = (errors
+            .iter()
+            .zip(&minutes)
+            .map(|(e, t)| {
+                (e.ra_arcsec - ra_fit - ra_slope * t).powi(2)
+                    + (e.dec_arcsec - dec_fit - dec_slope * t).powi(2)
+            })
+            .sum::<f64>()
+            / n)
+            .sqrt();
+
+        let gaps = samples
+            .windows(2)
+            .filter_map(|pair| {
+                let duration = (pair[1].0 - pair[0].0).to_std().ok()?;
+                (duration > interval * 2).then_some(Gap {
+                    start: pair[0].0,
+                    duration,
+                })
+            })
+            .collect();
+
+        let mut worst = errors;
+        worst.sort_by(|a, b| b.arcsec().total_cmp(&a.arcsec()));
+        worst.truncate(WORST);
+
+        Some(StabilityReport {
+            started,
+            ended,
+            samples: samples.len(),
+            failed,
+            drift_ra: ra_slope,
+            drift_dec: dec_slope,
+            rms_arcsec,
+            jitter_arcsec,
+            worst,
+            gaps,
+        })
+    }
+}
+
+/// Least-squares line through `ys` at times `xs`, as (intercept, slope).
+fn fit(xs: &[f64], ys: impl Iterator<Item = f64>) -> (f64, f64) {
+    let ys = ys.collect::<Vec<_>>();
+    let n = xs.len() as f64;
+    let (mx, my) = (xs.iter().sum::<f64>() / n, ys.iter().sum::<f64>() / n);
+    let sxx = xs.iter().map(|x| (x - mx).powi(2)).sum::<f64>();
+    let sxy = xs
+        .iter()
+        .zip(&ys)
+        .map(|(x, y)| (x - mx) * (y - my))
+        .sum::<f64>();
+    let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+    (my - slope * mx, slope)
+}
+
+impl fmt::Display for StabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time = |t: &DateTime<Utc>| t.format("%H:%M:%S");
+        writeln!(
+            f,
+            "Tracking stability, {} to {} UTC",
+            self.started.format("%Y-%m-%d %H:%M:%S"),
+            time(&self.ended)
+        )?;
+        writeln!(f, "Samples: {} ({} failed)", self.samples, self.failed)?;
+        writeln!(
+            f,
+            "Drift:   {:+.2}\"/min RA, {:+.2}\"/min Dec",
+            self.drift_ra, self.drift_dec
+        )?;
+        writeln!(f, "RMS:     {:.2}\"", self.rms_arcsec)?;
+        writeln!(f, "Jitter:  {:.2}\" RMS after drift", self.jitter_arcsec)?;
+        writeln!(f, "Worst:")?;
+        for e in &self.worst {
+            writeln!(
+                f,
+                "  {}  {:.2}\" ({:+.2}\" RA, {:+.2}\" Dec)",
+                time(&e.time),
+                e.arcsec(),
+                e.ra_arcsec,
+                e.dec_arcsec
+            )?;
+        }
+        match self.gaps.len() {
+            0 => writeln!(f, "Gaps:    none"),
+            n => {
+                writeln!(f, "Gaps:    {n}")?;
+                for gap in &self.gaps {
+                    writeln!(f, "  {}  {:.0?}", time(&gap.start), gap.duration)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads the position of a tracking mount every `interval` for `duration`, and reports how well it held.
+///
+/// Fails with `InvalidInput` if tracking is off, and with the last error if fewer than two positions could be read.
+pub fn stability_monitor<M: Mount>(
+    mount: &mut M,
+    duration: Duration,
+    interval: Duration,
+) -> Result<StabilityReport, MountError> {
+    if mount.get_tracking_mode()? == TrackingMode::Off {
+        return Err(MountError::other(
+            io::ErrorKind::InvalidInput,
+            "Tracking is off; start tracking before measuring its stability.",
+        ));
+    }
+
+    let start = Instant::now();
+    let mut samples = Vec::new();
+    let mut failed = 0;
+    let mut last_error = None;
+    let mut next = start;
+    while next - start <= duration {
+        match mount.get_position_ra_dec() {
+            Ok(pos) => samples.push((Utc::now(), pos)),
+            Err(e) => {
+                warn!("Failed to read the position: {e}");
+                failed += 1;
+                last_error = Some(e);
+            }
+        }
+        next += interval;
+        thread::sleep(next.saturating_duration_since(Instant::now()));
+    }
+
+    StabilityReport::from_samples(&samples, interval, failed).ok_or_else(|| {
+        last_error.unwrap_or_else(|| {
+            MountError::other(
+                io::ErrorKind::InvalidInput,
+                "The duration is too short for two samples.",
+            )
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn reports_drift_jitter_and_gaps() {
+        let start = Utc::now();
+        let samples = (0..60)
+            .filter(|i| !(20..25).contains(i))
+            .map(|i| {
+                let jitter = if i % 2 == 0 { 0.5 } else { -0.5 };
+                let pos = RADec::new(100.0 + i as f64 / 3600.0 / 0.5, 60.0 + jitter / 3600.0);
+                (start + TimeDelta::seconds(i * 60), pos)
+            })
+            .collect::<Vec<_>>();
+        let report = StabilityReport::from_samples(&samples, Duration::from_secs(60), 2).unwrap();
+
+        // 2"/min of right ascension is 1"/min on the sky at 60°.
+        assert!((report.drift_ra - 1.0).abs() < 1e-3, "{report}");
+        assert!(report.drift_dec.abs() < 0.01);
+        assert!((report.jitter_arcsec - 0.5).abs() < 0.01);
+        assert_eq!(report.gaps.len(), 1);
+        assert_eq!(report.gaps[0].duration, Duration::from_secs(360));
+        assert_eq!(report.worst[0].time, start + TimeDelta::minutes(59));
+        assert!(report.to_string().contains("Samples: 55 (2 failed)"));
+        assert!(StabilityReport::from_samples(&samples[..1], Duration::from_secs(60), 0).is_none());
+    }
+
+    #[test]
+    fn measures_a_drifting_mount() {
+        let mut mount = SimMount::new().tracking_drift(60.0, 0.0);
+        let interval = Duration::from_millis(20);
+        assert!(stability_monitor(&mut mount, interval, interval).is_err());
+
+        mount.sync(RADec::new(100.0, 0.0)).unwrap();
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        let report = stability_monitor(&mut mount, Duration::from_millis(300), interval).unwrap();
+        // 60"/s of hour angle, so right ascension falls 3600"/min.
+        assert!((report.drift_ra + 3600.0).abs() < 360.0, "{report}");
+        assert!(report.samples > 10);
+    }
+}
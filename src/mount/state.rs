@@ -0,0 +1,204 @@
+//! Saving and restoring the mount's configuration.
+//!
+//! After a power cycle or a hand control reset the mount comes back with tracking off, and possibly with the wrong
+//! site or clock. [`CelestronMount::snapshot_state`] records the settings that matter for pointing, and
+//! [`CelestronMount::restore_state`] puts them back in one call. With the `serde` feature a snapshot can be saved
+//! alongside the session, to restore it after the application itself restarts.
+//!
+//! The hand control protocol offers no way to read back custom tracking rates or slew limits, so a snapshot does not
+//! cover them; they are kept by the hand control across power cycles. The pointing limits of the `[limits]`
+//! configuration section are enforced by nexlib itself, so they need no restoring either.
+//!
+//! [`CelestronMount::reconnect`] takes a snapshot further: after the link drops, it reopens the mount on a new port,
+//! works out whether the mount was reset in the meantime, and if so restores the snapshot, returning a
+//! [`RestoreReport`] of what it could and could not put back. Alignment is lost with a power cycle and can only be
+//! redone at the sky.
+//!
+//! ```no_run
+//! use nexlib::CelestronMount;
+//!
+//! let mut mount = CelestronMount::new().unwrap();
+//! let state = mount.snapshot_state().unwrap();
+//! // ... the mount is power cycled ...
+//! mount.restore_state(&state).unwrap();
+//! ```
+
+use super::error::MountError;
+use super::{CelestronMount, Location, Mount, TimeZoneSetting, TrackingMode};
+use chrono::Utc;
+use serialport::SerialPort;
+
+/// Largest difference between the mount's clock and the system clock not taken as a sign of a reset.
+const CLOCK_TOLERANCE: chrono::TimeDelta = chrono::TimeDelta::minutes(5);
+
+/// Largest difference in site latitude or longitude, in degrees, not taken as a sign of a reset.
+const SITE_TOLERANCE: f64 = 0.01;
+
+/// The configuration of a mount, as recorded by [`CelestronMount::snapshot_state`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MountState {
+    pub tracking_mode: TrackingMode,
+    pub location: Location,
+    pub time_zone: TimeZoneSetting,
+    /// Whether the mount was aligned. Recorded to detect a reset; alignment cannot be restored.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub aligned: bool,
+}
+
+/// A setting which [`CelestronMount::reconnect`] checks after a reset.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Setting {
+    Location,
+    Time,
+    TrackingMode,
+    Alignment,
+}
+
+/// What [`CelestronMount::reconnect`] found and did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreReport {
+    /// Whether the mount appeared to have been reset. Nothing is restored otherwise.
+    pub reset: bool,
+    pub restored: Vec<Setting>,
+    /// Settings left as the reset left them, with the reason.
+    pub unrestored: Vec<(Setting, String)>,
+}
+
+impl CelestronMount {
+    /// Records the tracking mode, site location, and time zone.
+    pub fn snapshot_state(&mut self) -> Result<MountState, MountError> {
+        Ok(MountState {
+            tracking_mode: self.get_tracking_mode()?,
+            location: self.get_site()?,
+            time_zone: self.get_time_zone()?,
+            aligned: self.is_aligned()?,
+        })
+    }
+
+    /// Restores the settings recorded in `state`, and sets the clock from the system clock.
+    ///
+    /// The site and time are set before tracking resumes, so that tracking starts from the right sky position.
+    pub fn restore_state(&mut self, state: &MountState) -> Result<(), MountError> {
+        self.set_site(state.location)?;
+        self.set_clock(chrono::Utc::now(), state.time_zone)?;
+        self.set_tracking_mode(state.tracking_mode)
+    }
+
+    /// Switches to `port` after the link was lost, and restores `state` if the mount was reset meanwhile.
+    ///
+    /// A reset is recognised by the alignment, site, or tracking differing from `state`, or the mount's clock being
+    /// far from the system clock. Each setting is restored independently, so one failing does not stop the rest.
+    pub fn reconnect(
+        &mut self,
+        port: Box<dyn SerialPort>,
+        state: &MountState,
+    ) -> Result<RestoreReport, MountError> {
+        *self.port.lock().unwrap() = port;
+        self.metrics.reconnects += 1;
+        self.stale = false;
+        // A reflashed or swapped hand control may be on the other end.
+        self.refresh_info();
+        self.ping()?;
+
+        let mut report = RestoreReport {
+            reset: self.detect_reset(state)?,
+            ..RestoreReport::default()
+        };
+        if !report.reset {
+            return Ok(report);
+        }
+        log::warn!("Mount was reset while disconnected; restoring its configuration.");
+
+        let mut restore = |setting, res: Result<(), MountError>| match res {
+            Ok(()) => report.restored.push(setting),
+            Err(e) => report.unrestored.push((setting, e.to_string())),
+        };
+        restore(Setting::Location, self.set_site(state.location));
+        restore(Setting::Time, self.set_clock(Utc::now(), state.time_zone));
+        restore(
+            Setting::TrackingMode,
+            self.set_tracking_mode(state.tracking_mode),
+        );
+
+        if state.aligned && !self.is_aligned()? {
+            report.unrestored.push((
+                Setting::Alignment,
+                "Alignment is lost with a reset; align the mount again.".to_owned(),
+            ));
+        }
+        Ok(report)
+    }
+
+    fn detect_reset(&mut self, state: &MountState) -> Result<bool, MountError> {
+        let location = self.get_site()?;
+        let moved = (location.latitude - state.location.latitude).abs() > SITE_TOLERANCE
+            || (location.longitude - state.location.longitude).abs() > SITE_TOLERANCE;
+        let clock_off = (self.get_time()? - Utc::now()).abs() > CLOCK_TOLERANCE;
+
+        Ok(moved
+            || clock_off
+            || (state.aligned && !self.is_aligned()?)
+            || self.get_tracking_mode()? != state.tracking_mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{SimMount, SimPort};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn restores_after_power_cycle() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 20, 22, 0, 0).unwrap();
+        let port = SimPort::new(SimMount::new().site(40.446, -79.982).manual_clock(start));
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        let zone = TimeZoneSetting {
+            utc_offset: -5,
+            dst: true,
+        };
+        mount.set_clock(start, zone).unwrap();
+        let state = mount.snapshot_state().unwrap();
+
+        // A power cycle loses tracking, the site, and the clock.
+        *port.mount() =
+            SimMount::new().manual_clock(Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap());
+        mount.restore_state(&state).unwrap();
+
+        assert_eq!(mount.snapshot_state().unwrap(), state);
+        assert_eq!(state.time_zone, zone);
+        assert!((state.location.longitude + 79.982).abs() < 1e-3);
+        assert!((port.mount().time() - Utc::now()).num_seconds().abs() < 5);
+    }
+
+    #[test]
+    fn reconnect_restores_after_reset() {
+        let port = SimPort::new(SimMount::new().site(40.446, -79.982));
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        mount
+            .set_clock(Utc::now(), TimeZoneSetting::default())
+            .unwrap();
+        let state = mount.snapshot_state().unwrap();
+
+        // Reconnecting to a mount which kept its settings changes nothing.
+        let report = mount.reconnect(Box::new(port.clone()), &state).unwrap();
+        assert_eq!(report, RestoreReport::default());
+
+        *port.mount() = SimMount::new()
+            .aligned(false)
+            .manual_clock(Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap());
+        let report = mount.reconnect(Box::new(port.clone()), &state).unwrap();
+        assert!(report.reset);
+        assert_eq!(
+            report.restored,
+            [Setting::Location, Setting::Time, Setting::TrackingMode]
+        );
+        assert_eq!(report.unrestored.len(), 1);
+        assert_eq!(report.unrestored[0].0, Setting::Alignment);
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::EQNorth);
+        assert_eq!(mount.metrics().reconnects, 2);
+    }
+}
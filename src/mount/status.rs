@@ -0,0 +1,151 @@
+//! A status cache shared by every consumer of a mount.
+//!
+//! A GUI, a web server, and a logger each polling a mount for its status multiply the serial traffic by the number
+//! of frontends, and the hand control answers only one command at a time. A [`StatusCache`] coalesces their polls:
+//! the first request in each interval queries the mount, and every other request in the same interval is served the
+//! cached result. Requests arriving while the query is underway wait for it rather than starting their own, so
+//! serial traffic stays bounded however many frontends are attached.
+//!
+//! ```no_run
+//! use nexlib::mount::status::StatusCache;
+//! use nexlib::CelestronMount;
+//! use std::sync::{Arc, Mutex};
+//! use std::time::Duration;
+//!
+//! let mount = Arc::new(Mutex::new(CelestronMount::new().unwrap()));
+//! let cache = StatusCache::new(mount, Duration::from_millis(500));
+//!
+//! // Give each frontend a clone.
+//! let gui = cache.clone();
+//! println!("{:?}", gui.get().unwrap().tracking_mode);
+//! ```
+
+use super::{AzEl, Mount, RADec, TrackingMode};
+use chrono::{DateTime, Utc};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The state of a mount at one instant.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MountStatus {
+    /// When the status was read.
+    pub time: DateTime<Utc>,
+    pub ra_dec: RADec,
+    pub az_el: AzEl,
+    pub tracking_mode: TrackingMode,
+    pub goto_in_progress: bool,
+}
+
+impl MountStatus {
+    /// Reads the status of `mount`, four commands.
+    pub fn read<M: Mount>(mount: &mut M) -> Result<MountStatus, io::Error> {
+        Ok(MountStatus {
+            ra_dec: mount.get_position_ra_dec()?,
+            az_el: mount.get_position_az_el()?,
+            tracking_mode: mount.get_tracking_mode()?,
+            goto_in_progress: mount.goto_in_progress()?,
+            time: Utc::now(),
+        })
+    }
+}
+
+/// The outcome of the last query. Failures are cached too, so an unresponsive mount is not queried more often.
+type Sample = (Instant, Result<MountStatus, (io::ErrorKind, String)>);
+
+/// Serves the status of a shared mount, querying it at most once per interval; see [`status`](self).
+///
+/// Clones share the same cache.
+#[derive(Debug)]
+pub struct StatusCache<M> {
+    mount: Arc<Mutex<M>>,
+    max_age: Duration,
+    last: Arc<Mutex<Option<Sample>>>,
+}
+
+impl<M> Clone for StatusCache<M> {
+    fn clone(&self) -> Self {
+        StatusCache {
+            mount: Arc::clone(&self.mount),
+            max_age: self.max_age,
+            last: Arc::clone(&self.last),
+        }
+    }
+}
+
+impl<M: Mount> StatusCache<M> {
+    /// Caches the status of `mount` for `max_age` after each query.
+    pub fn new(mount: Arc<Mutex<M>>, max_age: Duration) -> StatusCache<M> {
+        StatusCache {
+            mount,
+            max_age,
+            last: Arc::default(),
+        }
+    }
+
+    /// The mount the status is read from, for sending it commands.
+    pub fn mount(&self) -> &Arc<Mutex<M>> {
+        &self.mount
+    }
+
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Gets the cached status, first querying the mount if it is older than the interval.
+    pub fn get(&self) -> Result<MountStatus, io::Error> {
+        // Held during the query, so concurrent requests wait for its result.
+        let mut last = self.last.lock().unwrap();
+
+        let fresh = last
+            .as_ref()
+            .is_some_and(|(at, _)| at.elapsed() < self.max_age);
+        if !fresh {
+            let res = MountStatus::read(&mut *self.mount.lock().unwrap());
+            *last = Some((Instant::now(), res.map_err(|e| (e.kind(), e.to_string()))));
+        }
+
+        match &last.as_ref().expect("The status was just read.").1 {
+            Ok(status) => Ok(*status),
+            Err((kind, message)) => Err(io::Error::new(*kind, message.clone())),
+        }
+    }
+
+    /// Discards the cached status, so the next request queries the mount. Call after a command changes the state.
+    pub fn invalidate(&self) {
+        *self.last.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{SimMount, SimPort};
+    use crate::CelestronMount;
+    use std::thread;
+
+    #[test]
+    fn coalesces_polls() {
+        let port = SimPort::new(SimMount::new());
+        let mount = Arc::new(Mutex::new(CelestronMount::from_port(Box::new(port))));
+        let cache = StatusCache::new(Arc::clone(&mount), Duration::from_secs(60));
+
+        let frontends: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    for _ in 0..10 {
+                        cache.get().unwrap();
+                    }
+                })
+            })
+            .collect();
+        frontends.into_iter().for_each(|t| t.join().unwrap());
+        assert_eq!(mount.lock().unwrap().metrics().commands, 4);
+
+        cache.invalidate();
+        cache.get().unwrap();
+        assert_eq!(mount.lock().unwrap().metrics().commands, 8);
+    }
+}
@@ -0,0 +1,242 @@
+//! Continuous streams of timestamped positions.
+//!
+//! [`position_stream`] starts a background poller reading a shared mount's position at a fixed rate, and returns a
+//! [`PositionStream`] iterating over the samples. Further consumers call [`PositionStream::subscribe`] to receive the
+//! same samples from the same poller, so plots, loggers, and guiding analysis share one stream of serial traffic
+//! instead of each running their own poll loop. The poller stops once every stream is dropped.
+//!
+//! ```no_run
+//! use nexlib::mount::stream::position_stream;
+//! use nexlib::CelestronMount;
+//! use std::sync::{Arc, Mutex};
+//!
+//! let mount = Arc::new(Mutex::new(CelestronMount::new().unwrap()));
+//! for sample in position_stream(mount, 2.0).take(10) {
+//!     match sample {
+//!         Ok(s) => println!("{} {} {}", s.time, s.ra_dec, s.az_el),
+//!         Err(e) => eprintln!("Failed to read position: {e}"),
+//!     }
+//! }
+//! ```
+
+use super::transform::angular_separation;
+use super::{AzEl, Mount, RADec};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Samples held for each consumer before newer ones are dropped, so a stalled consumer cannot grow memory.
+const BUFFER: usize = 64;
+
+/// The mount's position at one instant.
+#[derive(Debug, Copy, Clone)]
+pub struct PositionSample {
+    /// When the position was read.
+    pub time: DateTime<Utc>,
+    pub ra_dec: RADec,
+    pub az_el: AzEl,
+}
+
+type Subscribers = Arc<Mutex<Vec<SyncSender<io::Result<PositionSample>>>>>;
+
+/// An iterator over the samples of a background position poller; see [`position_stream`].
+///
+/// Blocks until the next sample arrives. A failed read yields an error and polling carries on.
+#[derive(Debug)]
+pub struct PositionStream {
+    samples: Receiver<io::Result<PositionSample>>,
+    subscribers: Subscribers,
+}
+
+impl PositionStream {
+    fn new(subscribers: Subscribers) -> PositionStream {
+        let (tx, samples) = mpsc::sync_channel(BUFFER);
+        subscribers.lock().unwrap().push(tx);
+        PositionStream {
+            samples,
+            subscribers,
+        }
+    }
+
+    /// Starts another stream of the samples from the same poller, beginning with the next sample.
+    pub fn subscribe(&self) -> PositionStream {
+        PositionStream::new(Arc::clone(&self.subscribers))
+    }
+
+    /// Waits at most `timeout` for the next sample.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<io::Result<PositionSample>> {
+        self.samples.recv_timeout(timeout).ok()
+    }
+
+    /// Gets the next sample if one has already arrived.
+    pub fn try_next(&mut self) -> Option<io::Result<PositionSample>> {
+        self.samples.try_recv().ok()
+    }
+
+    /// Waits until the mount has stayed within `epsilon_arcsec` of the same position for `window`, returning the
+    /// last sample.
+    ///
+    /// The mount counts as still if it holds either its right ascension and declination, as when tracking, or its
+    /// azimuth and elevation, as when not. This is more dependable before an exposure than `goto_in_progress`, which
+    /// some firmware clears before the mount has stopped oscillating. Fails with the first failed read.
+    pub fn wait_until_settled(
+        &mut self,
+        epsilon_arcsec: f64,
+        window: Duration,
+    ) -> io::Result<PositionSample> {
+        let epsilon = epsilon_arcsec / 3600.0;
+        let window = TimeDelta::from_std(window).unwrap_or(TimeDelta::max_value());
+        // Samples since the mount last moved by more than epsilon.
+        let mut still = VecDeque::<PositionSample>::new();
+
+        loop {
+            let sample = self.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::BrokenPipe, "Position stream ended.")
+            })??;
+
+            if let Some(moved) = still.iter().rposition(|s| !s.near(&sample, epsilon)) {
+                still.drain(..=moved);
+            }
+            let start = still.front().map_or(sample.time, |s| s.time);
+            still.push_back(sample);
+
+            if sample.time - start >= window {
+                return Ok(sample);
+            }
+        }
+    }
+}
+
+impl PositionSample {
+    /// Whether `other` is within `epsilon` degrees in either coordinate system.
+    fn near(&self, other: &PositionSample, epsilon: f64) -> bool {
+        let (a, b) = (self.ra_dec, other.ra_dec);
+        let (c, d) = (self.az_el, other.az_el);
+        angular_separation(a.ra, a.dec, b.ra, b.dec) <= epsilon
+            || angular_separation(c.az, c.el, d.az, d.el) <= epsilon
+    }
+}
+
+impl Iterator for PositionStream {
+    type Item = io::Result<PositionSample>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.samples.recv().ok()
+    }
+}
+
+/// Polls `mount` for its position `rate_hz` times per second in the background, returning a stream of the samples.
+///
+/// The mount stays usable by others between polls, which only hold its lock for the duration of one read.
+///
+/// # Panics
+///
+/// If `rate_hz` is not a positive number.
+pub fn position_stream<M>(mount: Arc<Mutex<M>>, rate_hz: f64) -> PositionStream
+where
+    M: Mount + Send + 'static,
+{
+    assert!(
+        rate_hz > 0.0 && rate_hz.is_finite(),
+        "Invalid position stream rate: {rate_hz} Hz"
+    );
+    let interval = Duration::from_secs_f64(1.0 / rate_hz);
+
+    let subscribers = Subscribers::default();
+    let stream = PositionStream::new(Arc::clone(&subscribers));
+
+    thread::spawn(move || {
+        let mut next = Instant::now();
+
+        loop {
+            let sample = read(&mut *mount.lock().unwrap());
+
+            let mut subscribers = subscribers.lock().unwrap();
+            subscribers.retain(|tx| {
+                let sample = match &sample {
+                    Ok(s) => Ok(*s),
+                    Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+                };
+                match tx.try_send(sample) {
+                    Ok(()) => true,
+                    Err(TrySendError::Full(_)) => {
+                        log::trace!("Position stream consumer is behind; dropping a sample.");
+                        true
+                    }
+                    Err(TrySendError::Disconnected(_)) => false,
+                }
+            });
+            if subscribers.is_empty() {
+                break;
+            }
+            drop(subscribers);
+
+            // Keep to the rate regardless of how long the reads take, without bursting to catch up after a stall.
+            next = (next + interval).max(Instant::now());
+            thread::sleep(next.saturating_duration_since(Instant::now()));
+        }
+    });
+
+    stream
+}
+
+fn read<M: Mount>(mount: &mut M) -> io::Result<PositionSample> {
+    let ra_dec = mount.get_position_ra_dec()?;
+    let az_el = mount.get_position_az_el()?;
+    Ok(PositionSample {
+        time: Utc::now(),
+        ra_dec,
+        az_el,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::SimMount;
+
+    #[test]
+    fn streams_to_every_subscriber() {
+        let mount = Arc::new(Mutex::new(SimMount::new()));
+        let mut stream = position_stream(Arc::clone(&mount), 50.0);
+        let mut other = stream.subscribe();
+
+        let timeout = Duration::from_secs(5);
+        let first = stream.next_timeout(timeout).unwrap().unwrap();
+        let second = stream.next_timeout(timeout).unwrap().unwrap();
+        assert!(second.time > first.time);
+        assert!(other.next_timeout(timeout).unwrap().is_ok());
+
+        // The poller stops once the last stream is gone, releasing its reference to the mount.
+        drop(stream);
+        drop(other);
+        let start = Instant::now();
+        while Arc::strong_count(&mount) > 1 {
+            assert!(start.elapsed() < timeout);
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn settles_after_goto() {
+        let mount = Arc::new(Mutex::new(
+            SimMount::new().max_rate(20.0).acceleration(40.0),
+        ));
+        mount
+            .lock()
+            .unwrap()
+            .goto_az_el(AzEl::new(0.0, 50.0))
+            .unwrap();
+        let mut stream = position_stream(Arc::clone(&mount), 50.0);
+
+        let settled = stream
+            .wait_until_settled(2.0, Duration::from_millis(200))
+            .unwrap();
+        assert!(!mount.lock().unwrap().goto_in_progress().unwrap());
+        assert!((settled.az_el.el - 50.0).abs() < 1e-3, "{}", settled.az_el);
+    }
+}
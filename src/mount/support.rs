@@ -0,0 +1,131 @@
+//! Which commands each hand control firmware version and mount model supports.
+//!
+//! Once the firmware version has been read with [`Mount::get_version`](super::Mount::get_version), and the model
+//! with [`Mount::get_model`](super::Mount::get_model), a [`CelestronMount`](crate::CelestronMount) checks every
+//! command against this table and fails with [`MountError::Unsupported`] before sending anything, instead of letting an
+//! old hand control time out or answer with a confusing response. Commands are not checked while the version is
+//! unknown.
+//!
+//! The versions are those given for each command by the NexStar communication protocol.
+
+use super::error::MountError;
+use super::latency::Command;
+use super::Model;
+
+/// The first hand control firmware version supporting `command`, if known.
+pub fn required_version(command: Command) -> Option<(u8, u8)> {
+    match command {
+        Command::HandControl(cmd) => match cmd {
+            b'E' | b'R' | b'Z' | b'B' | b'V' | b'K' | b'J' | b'L' | b'M' => Some((1, 2)),
+            b'e' | b'r' | b'T' => Some((1, 6)),
+            b'z' | b'b' | b'm' => Some((2, 2)),
+            b't' | b'w' | b'W' | b'h' | b'H' => Some((2, 3)),
+            b's' | b'S' => Some((4, 10)),
+            _ => None,
+        },
+        // Setting the date, year, and time of the real-time clock.
+        Command::Passthrough {
+            device: 178,
+            command: 131 | 132 | 179,
+        } => Some((3, 1)),
+        Command::Passthrough { .. } => Some((1, 6)),
+    }
+}
+
+/// Whether `model` can support `command` at all.
+pub fn supported_by(command: Command, model: Model) -> bool {
+    match command {
+        // The Advanced VX has no GPS port.
+        Command::Passthrough { device: 176, .. } => model != Model::AdvancedVX,
+        _ => true,
+    }
+}
+
+/// Parses a version reported by [`Mount::get_version`](super::Mount::get_version), e.g. `4.10`.
+pub fn parse_version(version: &str) -> Option<(u8, u8)> {
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Checks `command` against what is known of the hand control and mount.
+pub fn check(
+    command: Command,
+    version: Option<(u8, u8)>,
+    model: Option<Model>,
+) -> Result<(), MountError> {
+    if model.is_some_and(|model| !supported_by(command, model)) {
+        return Err(MountError::unsupported_command(command, None));
+    }
+    match (required_version(command), version) {
+        (Some(required), Some(version)) if version < required => {
+            Err(MountError::unsupported_command(command, Some(required)))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::session::{EventKind, ReplayPort};
+    use crate::mount::{Mount, TrackingMode};
+    use crate::{CelestronMount, RADec};
+
+    #[test]
+    fn rejects_unsupported_commands() {
+        let port = ReplayPort::scripted([
+            EventKind::Write(b"V".to_vec()),
+            EventKind::Read(vec![2, 2, b'#']),
+            EventKind::Write(b"m".to_vec()),
+            EventKind::Read(vec![20, b'#']),
+            EventKind::Write(b"T\x02".to_vec()),
+            EventKind::Read(b"#".to_vec()),
+        ]);
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+        // Nothing is checked before the version is known.
+        assert_eq!(mount.get_version().unwrap(), "2.2");
+        assert_eq!(mount.get_model().unwrap(), Model::AdvancedVX);
+
+        let e = mount.sync(RADec::new(10.0, 20.0)).unwrap_err();
+        assert_eq!(
+            e,
+            MountError::unsupported_command(Command::HandControl(b's'), Some((4, 10)))
+        );
+        assert!(matches!(
+            mount.get_tracking_mode(),
+            Err(MountError::Unsupported { .. })
+        ));
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        assert_eq!(port.remaining(), 0);
+    }
+
+    #[test]
+    fn checks_models_and_versions() {
+        let gps = Command::Passthrough {
+            device: 176,
+            command: 1,
+        };
+        assert!(check(gps, Some((5, 35)), Some(Model::Cpc)).is_ok());
+        assert!(check(gps, Some((5, 35)), Some(Model::AdvancedVX)).is_err());
+        assert!(check(gps, None, None).is_ok());
+        assert!(check(Command::HandControl(b'S'), Some((4, 9)), None).is_err());
+        assert!(check(Command::HandControl(b'S'), Some((4, 10)), None).is_ok());
+        assert!(check(Command::HandControl(b'Z'), Some((1, 2)), None).is_ok());
+        assert!(check(Command::HandControl(b'z'), Some((2, 1)), None).is_err());
+
+        let get_rtc_time = Command::Passthrough {
+            device: 178,
+            command: 51,
+        };
+        let set_rtc_time = Command::Passthrough {
+            device: 178,
+            command: 179,
+        };
+        assert!(check(get_rtc_time, Some((1, 6)), None).is_ok());
+        assert!(check(set_rtc_time, Some((2, 3)), None).is_err());
+        assert!(check(set_rtc_time, parse_version("3.01"), None).is_ok());
+        assert_eq!(parse_version("4.10"), Some((4, 10)));
+        assert_eq!(parse_version("bad"), None);
+    }
+}
@@ -0,0 +1,366 @@
+//! [`Mount`] backend for SkyWatcher and Orion mounts with a SynScan hand control.
+//!
+//! The SynScan hand control's serial protocol grew out of NexStar's and shares most of its commands: positions,
+//! gotos, and syncs in the same hex formats, the location and time blocks, and motor slews through the `P`
+//! passthrough command, so [`codec`] encodes and decodes them for both. It differs in a few places, handled here:
+//!
+//! - `t` and `T` number the tracking modes off, alt-az, equatorial, and PEC, with no hemisphere; the hemisphere is
+//!   taken from the site latitude.
+//! - `V` answers six hex digits, e.g. `042507` for version 4.37.07.
+//! - `m` answers SkyWatcher's own model numbers; see [`SynScanMount::get_synscan_model`].
+//! - There is no GPS or motor version passthrough.
+//!
+//! ```no_run
+//! use nexlib::mount::synscan::SynScanMount;
+//! use nexlib::{Mount, RADec};
+//! use std::time::Duration;
+//!
+//! let mut mount = SynScanMount::open("/dev/ttyUSB0", Duration::from_secs(2)).unwrap();
+//! println!("{} running {}", mount.get_synscan_model().unwrap(), mount.get_version().unwrap());
+//! mount.goto_ra_dec(RADec::new(83.82, -5.39)).unwrap();
+//! ```
+
+use super::codec::{self, Framing};
+use super::error::MountError;
+use super::{
+    read_framed, CelestronMount, Gps, Location, Model, Mount, NonGpsDevice, SlewAxis, SlewDir,
+    SlewRate, TrackingMode,
+};
+use crate::{AzEl, RADec};
+use chrono::{DateTime, Utc};
+use log::trace;
+use serialport::{ClearBuffer, SerialPort};
+use std::fmt;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// A SkyWatcher mount model, as numbered by the SynScan hand control.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SynScanModel {
+    Eq6,
+    Heq5,
+    Eq5,
+    Eq3,
+    Eq8,
+    AzEq6,
+    AzEq5,
+    /// The AZ GOTO series, numbers 128 to 143.
+    AzGoto(u8),
+    /// The Dobsonian GOTO series, numbers 144 to 159.
+    DobGoto(u8),
+    AllView,
+    Other(u8),
+}
+
+impl From<u8> for SynScanModel {
+    fn from(id: u8) -> Self {
+        match id {
+            0 => SynScanModel::Eq6,
+            1 => SynScanModel::Heq5,
+            2 => SynScanModel::Eq5,
+            3 => SynScanModel::Eq3,
+            4 => SynScanModel::Eq8,
+            5 => SynScanModel::AzEq6,
+            6 => SynScanModel::AzEq5,
+            128..=143 => SynScanModel::AzGoto(id),
+            144..=159 => SynScanModel::DobGoto(id),
+            160 => SynScanModel::AllView,
+            _ => SynScanModel::Other(id),
+        }
+    }
+}
+
+impl fmt::Display for SynScanModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SynScanModel::Eq6 => write!(f, "EQ6"),
+            SynScanModel::Heq5 => write!(f, "HEQ5"),
+            SynScanModel::Eq5 => write!(f, "EQ5"),
+            SynScanModel::Eq3 => write!(f, "EQ3"),
+            SynScanModel::Eq8 => write!(f, "EQ8"),
+            SynScanModel::AzEq6 => write!(f, "AZ-EQ6"),
+            SynScanModel::AzEq5 => write!(f, "AZ-EQ5"),
+            SynScanModel::AzGoto(_) => write!(f, "AZ GOTO"),
+            SynScanModel::DobGoto(_) => write!(f, "Dob GOTO"),
+            SynScanModel::AllView => write!(f, "AllView"),
+            SynScanModel::Other(id) => write!(f, "Unknown model {id}"),
+        }
+    }
+}
+
+/// Decodes the six hex digit response to `V`, e.g. `042507`, as `4.37.7`.
+pub fn decode_version(res: &[u8]) -> Result<String, MountError> {
+    let parts = std::str::from_utf8(res)
+        .ok()
+        .filter(|s| s.len() == 6)
+        .and_then(|s| {
+            (0..6)
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+                .collect::<Option<Vec<u8>>>()
+        });
+    match parts.as_deref() {
+        Some([major, minor, patch]) => Ok(format!("{major}.{minor}.{patch}")),
+        _ => Err(MountError::InvalidResponse {
+            cmd: None,
+            bytes: res.to_vec(),
+        }),
+    }
+}
+
+/// A mount with a SynScan hand control; see [`synscan`](self).
+#[derive(Debug)]
+pub struct SynScanMount {
+    port: Box<dyn SerialPort>,
+    /// The last response; reused between commands.
+    recv: Vec<u8>,
+}
+
+impl SynScanMount {
+    /// Opens the hand control on `port`, e.g. `/dev/ttyUSB0` or `COM3`, which uses the same 9600 baud as NexStar.
+    pub fn open(port: &str, timeout: Duration) -> Result<SynScanMount, io::Error> {
+        Ok(Self::from_port(CelestronMount::open_port(port, timeout)?))
+    }
+
+    /// Uses an already open port.
+    pub fn from_port(port: Box<dyn SerialPort>) -> SynScanMount {
+        SynScanMount {
+            port,
+            recv: Vec::with_capacity(32),
+        }
+    }
+
+    pub fn get_synscan_model(&mut self) -> Result<SynScanModel, MountError> {
+        match self.query(b'm')? {
+            [id] => Ok(SynScanModel::from(*id)),
+            res => Err(MountError::InvalidResponse {
+                cmd: None,
+                bytes: res.to_vec(),
+            }),
+        }
+    }
+
+    pub fn get_site(&mut self) -> Result<Location, MountError> {
+        codec::decode_location(self.query(b'w')?)
+    }
+
+    /// Sets the site location in the hand control, to the nearest arcsecond.
+    pub fn set_site(&mut self, location: Location) -> Result<(), MountError> {
+        self.command(codec::set_location(location).as_bytes())
+    }
+
+    /// Writes `cmd` and reads the response up to its `#`, which is not returned.
+    fn transact(&mut self, cmd: &[u8], framing: Framing) -> Result<&[u8], MountError> {
+        trace!("TRANSMITTED: {:?}", cmd);
+        self.port.clear(ClearBuffer::Input)?;
+        self.port.write_all(cmd)?;
+
+        self.recv.clear();
+        let deadline = Instant::now() + self.port.timeout();
+        read_framed(&mut *self.port, &mut self.recv, framing, deadline)?;
+
+        trace!("RECEIVED: {:?}", self.recv);
+        match self.recv.split_last() {
+            Some((b'#', res)) => Ok(res),
+            _ => Err(MountError::InvalidResponse {
+                cmd: None,
+                bytes: self.recv.clone(),
+            }),
+        }
+    }
+
+    fn query(&mut self, cmd: u8) -> Result<&[u8], MountError> {
+        let framing = match cmd {
+            b'V' => Framing::Length(7),
+            cmd => Framing::of_handcontrol(cmd),
+        };
+        self.transact(&[cmd], framing)
+    }
+
+    /// Sends a command answered with a bare `#`.
+    fn command(&mut self, cmd: &[u8]) -> Result<(), MountError> {
+        self.transact(cmd, Framing::Terminator).map(|_| ())
+    }
+
+    fn unsupported(what: &str) -> MountError {
+        MountError::unsupported(format!("{what} is not available on SynScan hand controls."))
+    }
+}
+
+impl Mount for SynScanMount {
+    fn get_position_ra_dec(&mut self) -> Result<RADec, MountError> {
+        Ok(codec::decode_ra_dec(self.query(b'e')?)?)
+    }
+
+    fn get_position_az_el(&mut self) -> Result<AzEl, MountError> {
+        Ok(codec::decode_az_el(self.query(b'z')?)?)
+    }
+
+    fn goto_ra_dec(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.command(codec::goto_ra_dec(coord).as_bytes())
+    }
+
+    fn goto_az_el(&mut self, coord: AzEl) -> Result<(), MountError> {
+        self.command(codec::goto_az_el(coord).as_bytes())
+    }
+
+    /// Needs hand control firmware 3.37 or later.
+    fn sync(&mut self, coord: RADec) -> Result<(), MountError> {
+        self.command(codec::sync(coord).as_bytes())
+    }
+
+    /// SynScan reports equatorial tracking without a hemisphere; it is taken from the site latitude. PEC tracking is
+    /// reported as equatorial.
+    fn get_tracking_mode(&mut self) -> Result<TrackingMode, MountError> {
+        let mode = match self.query(b't')? {
+            [mode] => *mode,
+            res => {
+                return Err(MountError::InvalidResponse {
+                    cmd: None,
+                    bytes: res.to_vec(),
+                })
+            }
+        };
+        match mode {
+            0 => Ok(TrackingMode::Off),
+            1 => Ok(TrackingMode::AzEl),
+            2 | 3 if self.get_site()?.latitude < 0.0 => Ok(TrackingMode::EQSouth),
+            2 | 3 => Ok(TrackingMode::EQNorth),
+            _ => Err(MountError::InvalidResponse {
+                cmd: None,
+                bytes: vec![mode],
+            }),
+        }
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<(), MountError> {
+        let mode = match mode {
+            TrackingMode::Off => 0,
+            TrackingMode::AzEl => 1,
+            TrackingMode::EQNorth | TrackingMode::EQSouth => 2,
+        };
+        self.command(&[b'T', mode])
+    }
+
+    fn slew_variable(&mut self, axis: SlewAxis, dir: SlewDir, rate: u16) -> Result<(), MountError> {
+        self.command(&codec::slew_variable(axis, dir, rate))
+    }
+
+    fn slew_fixed(
+        &mut self,
+        axis: SlewAxis,
+        dir: SlewDir,
+        rate: SlewRate,
+    ) -> Result<(), MountError> {
+        self.command(&codec::slew_fixed(axis, dir, rate))
+    }
+
+    /// Gets the site location set in the hand control; see [`SynScanMount::get_site`].
+    fn get_location(&mut self) -> Result<Location, MountError> {
+        self.get_site()
+    }
+
+    /// Sets the site location in the hand control; see [`SynScanMount::set_site`].
+    fn set_location(&mut self, location: Location) -> Result<(), MountError> {
+        self.set_site(location)
+    }
+
+    fn get_time(&mut self) -> Result<DateTime<Utc>, MountError> {
+        codec::decode_time(self.query(b'h')?)
+    }
+
+    /// Sets the current time on the mount, in the time zone it is already set to.
+    fn set_time(&mut self, time: DateTime<Utc>) -> Result<(), MountError> {
+        let zone = codec::decode_time_zone(self.query(b'h')?)?;
+        self.command(codec::set_time(time, zone).as_bytes())
+    }
+
+    /// Version of the hand control firmware, e.g. `4.39.5`.
+    fn get_version(&mut self) -> Result<String, MountError> {
+        decode_version(self.query(b'V')?)
+    }
+
+    fn get_device_version(&mut self, _device: NonGpsDevice) -> Result<String, MountError> {
+        Err(Self::unsupported("Device versions"))
+    }
+
+    /// SkyWatcher models have no Celestron equivalent; see [`SynScanMount::get_synscan_model`].
+    fn get_model(&mut self) -> Result<Model, MountError> {
+        Err(Self::unsupported("The Celestron model"))
+    }
+
+    fn echo(&mut self, byte: u8) -> Result<u8, MountError> {
+        match self.transact(codec::echo(byte).as_bytes(), Framing::Length(2))? {
+            [echoed] => Ok(*echoed),
+            res => Err(MountError::InvalidResponse {
+                cmd: None,
+                bytes: res.to_vec(),
+            }),
+        }
+    }
+
+    fn is_aligned(&mut self) -> Result<bool, MountError> {
+        codec::decode_aligned(self.query(b'J')?)
+    }
+
+    fn goto_in_progress(&mut self) -> Result<bool, MountError> {
+        codec::decode_goto_in_progress(self.query(b'L')?)
+    }
+
+    fn cancel_goto(&mut self) -> Result<(), MountError> {
+        self.command(codec::cancel_goto().as_bytes())
+    }
+
+    fn stop_slew(&mut self, axis: SlewAxis) -> Result<(), MountError> {
+        self.slew_fixed(axis, SlewDir::Positive, SlewRate::Stop)
+    }
+
+    fn get_gps(&mut self) -> Result<Box<dyn Gps + '_>, MountError> {
+        Err(Self::unsupported("GPS passthrough"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::SimPort;
+    use crate::mount::SimMount;
+
+    #[test]
+    fn shared_commands_and_differences() {
+        assert_eq!(decode_version(b"042507").unwrap(), "4.37.7");
+        assert!(decode_version(b"0425").is_err());
+        assert_eq!(SynScanModel::from(1).to_string(), "HEQ5");
+        assert_eq!(SynScanModel::from(130), SynScanModel::AzGoto(130));
+
+        // The commands SynScan shares with NexStar work against the NexStar simulator.
+        let port = SimPort::new(SimMount::new().manual_clock(Utc::now()))
+            .timeout(Duration::from_millis(20));
+        let mut mount = SynScanMount::from_port(Box::new(port.clone()));
+        assert_eq!(mount.echo(b'x').unwrap(), b'x');
+        assert!(mount.is_aligned().unwrap());
+
+        mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::EQNorth);
+
+        let target = RADec::new(100.0, 20.0);
+        mount.goto_ra_dec(target).unwrap();
+        assert!(mount.goto_in_progress().unwrap());
+        port.mount().step(Duration::from_secs(120));
+        assert!(!mount.goto_in_progress().unwrap());
+        let pos = mount.get_position_ra_dec().unwrap();
+        assert!((pos.ra - target.ra).abs() < 1e-3 && (pos.dec - target.dec).abs() < 1e-3);
+
+        let site = Location {
+            latitude: 52.5,
+            longitude: -1.25,
+        };
+        mount.set_location(site).unwrap();
+        assert_eq!(mount.get_location().unwrap(), site);
+
+        assert_eq!(
+            mount.get_model().unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+    }
+}
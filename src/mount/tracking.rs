@@ -0,0 +1,105 @@
+//! Which tracking mode to turn tracking on in.
+//!
+//! ASCOM and Alpaca only switch tracking on and off, leaving the driver to choose the mode. A [`TrackingMemory`]
+//! remembers the mode the mount last tracked in, so tracking switched off and on again resumes as it was. Before any
+//! mode has been seen, [`site_tracking_mode`] picks one from the mount's model and site: alt-az for a fork or single
+//! arm mount, and equatorial for the hemisphere otherwise.
+//!
+//! ```no_run
+//! use nexlib::mount::tracking::TrackingMemory;
+//! use nexlib::mount::{Mount, TrackingMode};
+//! use nexlib::CelestronMount;
+//!
+//! let mut mount = CelestronMount::new().unwrap();
+//! let mut memory = TrackingMemory::default();
+//! memory.set_tracking(&mut mount, false).unwrap();
+//! memory.set_tracking(&mut mount, true).unwrap();
+//! ```
+
+use super::error::MountError;
+use super::{Mount, Mounting, TrackingMode};
+
+/// The tracking mode suited to the mount's model and site.
+///
+/// A mount which does not report its model is taken to be equatorial, and one which does not report its site to be in
+/// the northern hemisphere.
+pub fn site_tracking_mode<M: Mount>(mount: &mut M) -> Result<TrackingMode, MountError> {
+    let mounting = match mount.get_model() {
+        Ok(model) => model.mounting(),
+        Err(MountError::Unsupported { .. }) => Mounting::Equatorial,
+        Err(e) => return Err(e),
+    };
+    if mounting == Mounting::AltAz {
+        return Ok(TrackingMode::AzEl);
+    }
+    match mount.get_location() {
+        Ok(location) if location.latitude < 0.0 => Ok(TrackingMode::EQSouth),
+        Ok(_) | Err(MountError::Unsupported { .. }) => Ok(TrackingMode::EQNorth),
+        Err(e) => Err(e),
+    }
+}
+
+/// The tracking mode a mount last tracked in; see [`tracking`](self).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct TrackingMemory {
+    last: Option<TrackingMode>,
+}
+
+impl TrackingMemory {
+    /// Records a mode read from the mount.
+    pub fn observe(&mut self, mode: TrackingMode) {
+        if mode != TrackingMode::Off {
+            self.last = Some(mode);
+        }
+    }
+
+    /// The mode to turn tracking on in: the last one seen, or else the one suited to the mount's model and site.
+    pub fn resume_mode<M: Mount>(&self, mount: &mut M) -> Result<TrackingMode, MountError> {
+        match self.last {
+            Some(mode) => Ok(mode),
+            None => site_tracking_mode(mount),
+        }
+    }
+
+    /// Turns tracking on in the mode [`resume_mode`](Self::resume_mode) picks, or off after noting the mode it was in.
+    pub fn set_tracking<M: Mount>(&mut self, mount: &mut M, on: bool) -> Result<(), MountError> {
+        let current = mount.get_tracking_mode()?;
+        self.observe(current);
+        match (on, current) {
+            (true, TrackingMode::Off) => {
+                let mode = self.resume_mode(mount)?;
+                mount.set_tracking_mode(mode)
+            }
+            (false, TrackingMode::Off) | (true, _) => Ok(()),
+            (false, _) => mount.set_tracking_mode(TrackingMode::Off),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::{Model, SimMount};
+
+    #[test]
+    fn resumes_the_last_mode() {
+        let mut mount = SimMount::new();
+        let mut memory = TrackingMemory::default();
+        mount.set_tracking_mode(TrackingMode::AzEl).unwrap();
+        memory.set_tracking(&mut mount, false).unwrap();
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::Off);
+        memory.set_tracking(&mut mount, true).unwrap();
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::AzEl);
+    }
+
+    #[test]
+    fn picks_a_mode_for_the_site() {
+        let mut south = SimMount::new().site(-33.9, 18.4);
+        TrackingMemory::default().set_tracking(&mut south, true).unwrap();
+        assert_eq!(south.get_tracking_mode().unwrap(), TrackingMode::EQSouth);
+
+        let mut fork = SimMount::new().model(Model::SixEightSE);
+        TrackingMemory::default().set_tracking(&mut fork, true).unwrap();
+        assert_eq!(fork.get_tracking_mode().unwrap(), TrackingMode::AzEl);
+    }
+}
@@ -0,0 +1,160 @@
+//! Conversions between celestial and horizontal coordinates.
+//!
+//! Azimuth is measured from north through east, longitude is positive east, and all angles are in degrees. These
+//! are geometric conversions only: refraction, precession, and nutation are ignored, except by the epoch conversions
+//! [`j2000_to_jnow`] and [`jnow_to_j2000`].
+
+use super::{AzEl, RADec};
+use chrono::{DateTime, Utc};
+
+/// Wraps an angle to [-180, 180).
+pub fn wrap_180(deg: f64) -> f64 {
+    (deg + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Angle in degrees between two directions given as longitude and latitude, such as right ascension and
+/// declination, or azimuth and elevation.
+pub fn angular_separation(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (lon2 - lon1).to_radians();
+    // Haversine, accurate for the tiny separations of a settling mount.
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * h.sqrt().min(1.0).asin().to_degrees()
+}
+
+/// Local apparent sidereal time in degrees, ignoring nutation.
+pub fn local_sidereal_time(time: DateTime<Utc>, longitude: f64) -> f64 {
+    let jd = time.timestamp_millis() as f64 / 86_400_000.0 + 2_440_587.5;
+    (280.460_618_37 + 360.985_647_366_29 * (jd - 2_451_545.0) + longitude).rem_euclid(360.0)
+}
+
+/// Converts hour angle and declination to azimuth and elevation.
+pub fn ha_dec_to_az_el(ha: f64, dec: f64, latitude: f64) -> AzEl {
+    let (ha, dec, lat) = (ha.to_radians(), dec.to_radians(), latitude.to_radians());
+    let el = (dec.sin() * lat.sin() + dec.cos() * lat.cos() * ha.cos()).asin();
+    let az =
+        (-dec.cos() * ha.sin()).atan2(dec.sin() * lat.cos() - dec.cos() * lat.sin() * ha.cos());
+    AzEl::new(az.to_degrees().rem_euclid(360.0), el.to_degrees())
+}
+
+/// Converts azimuth and elevation to hour angle and declination.
+pub fn az_el_to_ha_dec(coord: AzEl, latitude: f64) -> (f64, f64) {
+    // The transformation is its own inverse.
+    let res = ha_dec_to_az_el(coord.az, coord.el, latitude);
+    (wrap_180(res.az), res.el)
+}
+
+/// Julian centuries since J2000.0. The difference between UTC and terrestrial time is well below the accuracy needed.
+fn julian_centuries(time: DateTime<Utc>) -> f64 {
+    let jd = time.timestamp_millis() as f64 / 86_400_000.0 + 2_440_587.5;
+    (jd - 2_451_545.0) / 36_525.0
+}
+
+/// IAU 1976 precession angles ζ, z, and θ from J2000 to `t` centuries later, in radians (Meeus, eq. 21.3).
+fn precession_angles(t: f64) -> (f64, f64, f64) {
+    let arcsec = |x: f64| (x / 3600.0).to_radians();
+    let zeta = arcsec(2306.2181 * t + 0.30188 * t * t + 0.017998 * t * t * t);
+    let z = arcsec(2306.2181 * t + 1.09468 * t * t + 0.018203 * t * t * t);
+    let theta = arcsec(2004.3109 * t - 0.42665 * t * t - 0.041833 * t * t * t);
+    (zeta, z, theta)
+}
+
+/// Nutation in right ascension and declination, in degrees, of a position of date (Meeus, eq. 23.1, with the
+/// nutation series truncated to its four largest terms, good to half an arcsecond).
+fn nutation(coord: RADec, t: f64) -> (f64, f64) {
+    let omega = (125.04452 - 1934.136261 * t).to_radians();
+    let l = (280.4665 + 36000.7698 * t).to_radians();
+    let l_moon = (218.3165 + 481267.8813 * t).to_radians();
+    let d_psi = -17.20 * omega.sin() - 1.32 * (2.0 * l).sin() - 0.23 * (2.0 * l_moon).sin()
+        + 0.21 * (2.0 * omega).sin();
+    let d_eps = 9.20 * omega.cos() + 0.57 * (2.0 * l).cos() + 0.10 * (2.0 * l_moon).cos()
+        - 0.09 * (2.0 * omega).cos();
+    let eps = (23.439_291 - 0.013_004_2 * t + d_eps / 3600.0).to_radians();
+
+    let (ra, dec) = (coord.ra.to_radians(), coord.dec.to_radians());
+    let d_ra =
+        (eps.cos() + eps.sin() * ra.sin() * dec.tan()) * d_psi - ra.cos() * dec.tan() * d_eps;
+    let d_dec = eps.sin() * ra.cos() * d_psi + ra.sin() * d_eps;
+    (d_ra / 3600.0, d_dec / 3600.0)
+}
+
+/// Converts J2000 coordinates, as given by catalogs, to coordinates of date at `time`, as used by the mount.
+///
+/// Applies precession and nutation; aberration and proper motion are ignored.
+pub fn j2000_to_jnow(coord: RADec, time: DateTime<Utc>) -> RADec {
+    let t = julian_centuries(time);
+    let (zeta, z, theta) = precession_angles(t);
+    let (ra, dec) = (coord.ra.to_radians() + zeta, coord.dec.to_radians());
+
+    let a = dec.cos() * ra.sin();
+    let b = theta.cos() * dec.cos() * ra.cos() - theta.sin() * dec.sin();
+    let c = theta.sin() * dec.cos() * ra.cos() + theta.cos() * dec.sin();
+    let mean = RADec::new(
+        (a.atan2(b) + z).to_degrees().rem_euclid(360.0),
+        c.clamp(-1.0, 1.0).asin().to_degrees(),
+    );
+
+    let (d_ra, d_dec) = nutation(mean, t);
+    RADec::new((mean.ra + d_ra).rem_euclid(360.0), mean.dec + d_dec)
+}
+
+/// Converts coordinates of date at `time` to J2000; the inverse of [`j2000_to_jnow`].
+pub fn jnow_to_j2000(coord: RADec, time: DateTime<Utc>) -> RADec {
+    let t = julian_centuries(time);
+    // Nutation depends on the mean position it is applied to, which converges in a few iterations even near the pole.
+    let mut mean = coord;
+    for _ in 0..3 {
+        let (d_ra, d_dec) = nutation(mean, t);
+        mean = RADec::new(coord.ra - d_ra, coord.dec - d_dec);
+    }
+
+    let (zeta, z, theta) = precession_angles(t);
+    let (ra, dec) = (mean.ra.to_radians() - z, mean.dec.to_radians());
+
+    let a = dec.cos() * ra.sin();
+    let b = theta.cos() * dec.cos() * ra.cos() + theta.sin() * dec.sin();
+    let c = -theta.sin() * dec.cos() * ra.cos() + theta.cos() * dec.sin();
+    RADec::new(
+        (a.atan2(b) - zeta).to_degrees().rem_euclid(360.0),
+        c.clamp(-1.0, 1.0).asin().to_degrees(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinate_transforms() {
+        // On the meridian, a star at the latitude is at the zenith.
+        let zenith = ha_dec_to_az_el(0.0, 45.0, 45.0);
+        assert!((zenith.el - 90.0).abs() < 1e-9);
+
+        let coord = ha_dec_to_az_el(-30.0, 20.0, 45.0);
+        assert!(
+            coord.az > 90.0 && coord.az < 180.0,
+            "Rising stars are in the east: {coord}"
+        );
+        let (ha, dec) = az_el_to_ha_dec(coord, 45.0);
+        assert!((ha + 30.0).abs() < 1e-9 && (dec - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn epoch_conversions() {
+        use chrono::TimeZone;
+
+        // Annual precession at the equinox is 3.075 s of right ascension and 20.04" of declination.
+        let time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let jnow = j2000_to_jnow(RADec::new(0.0, 0.0), time);
+        assert!((jnow.ra - 24.0 * 3.075 / 240.0).abs() < 0.01, "{jnow:?}");
+        assert!((jnow.dec - 24.0 * 20.04 / 3600.0).abs() < 0.01, "{jnow:?}");
+
+        let polaris = RADec::new(37.954561, 89.264109);
+        let back = jnow_to_j2000(j2000_to_jnow(polaris, time), time);
+        assert!(
+            angular_separation(back.ra, back.dec, polaris.ra, polaris.dec) < 1e-6,
+            "{back:?}"
+        );
+    }
+}
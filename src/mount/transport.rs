@@ -0,0 +1,436 @@
+//! Network transports and failover between two links to the same mount.
+//!
+//! Besides a serial port, a hand control can be reached over WiFi through a SkyPortal or StarSense WiFi module, which
+//! relays the serial protocol over TCP. [`open`] opens either from a name: a `host:port` address, such as
+//! [`WIFI_ADDR`], opens a [`TcpPort`], and anything else a serial port.
+//!
+//! For long unattended sessions, a mount can be given a [`Failover`] to a backup transport, typically WiFi behind a
+//! USB cable. After a number of consecutive commands fail on the primary link, the mount opens the backup, resends the
+//! failed command over it, and reports a [`FailoverEvent`]:
+//!
+//! ```no_run
+//! use nexlib::mount::transport::{self, Failover, FailoverEvent, WIFI_ADDR};
+//! use nexlib::mount::DEFAULT_TIMEOUT;
+//! use nexlib::CelestronMount;
+//!
+//! let mut mount = CelestronMount::from_port(transport::open("/dev/ttyUSB0", DEFAULT_TIMEOUT).unwrap());
+//! let backup = Failover::new(|| transport::open(WIFI_ADDR, DEFAULT_TIMEOUT), 3).on_event(|event| match event {
+//!     FailoverEvent::FailedOver(e) => eprintln!("Switched to WiFi after: {e}"),
+//!     FailoverEvent::BackupUnavailable(e) => eprintln!("WiFi is unavailable too: {e}"),
+//! });
+//! mount.set_failover(Some(backup));
+//! ```
+//!
+//! The mount stays on the backup once it has switched; reconnect the primary with
+//! [`CelestronMount::reconnect`](crate::CelestronMount::reconnect).
+
+use super::{CelestronMount, DEFAULT_BAUD};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Address of a SkyPortal or StarSense WiFi module in direct connect mode, where it hosts its own access point.
+pub const WIFI_ADDR: &str = "1.2.3.4:2000";
+
+/// Consecutive failed commands after which a [`Failover`] switches by default.
+pub const DEFAULT_FAILOVER_AFTER: u32 = 3;
+
+/// Whether `name` is a `host:port` network address rather than a serial port.
+pub(super) fn is_network_addr(name: &str) -> bool {
+    name.rsplit_once(':').is_some_and(|(host, port)| {
+        !host.is_empty() && !host.contains(['/', '\\']) && port.parse::<u16>().is_ok()
+    })
+}
+
+/// Opens the hand control at `name`: a `host:port` address over TCP, or else a serial port.
+pub fn open(name: &str, timeout: Duration) -> Result<Box<dyn SerialPort>, io::Error> {
+    open_at(name, DEFAULT_BAUD, timeout)
+}
+
+/// Opens the hand control at `name` as [`open`] does, running a serial port at `baud`. TCP ignores the baud rate.
+pub fn open_at(name: &str, baud: u32, timeout: Duration) -> Result<Box<dyn SerialPort>, io::Error> {
+    if is_network_addr(name) {
+        Ok(Box::new(TcpPort::connect(name, timeout)?))
+    } else {
+        CelestronMount::open_port_at(name, baud, timeout)
+    }
+}
+
+/// A hand control reached over TCP, as through a WiFi module, behaving like a serial port.
+///
+/// Line settings such as the baud rate are accepted and ignored.
+#[derive(Debug)]
+pub struct TcpPort {
+    stream: TcpStream,
+    name: String,
+    timeout: Duration,
+}
+
+impl TcpPort {
+    /// Connects to `addr`, waiting up to `timeout` for the connection and later for each response.
+    pub fn connect(addr: &str, timeout: Duration) -> Result<TcpPort, io::Error> {
+        let resolved = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("No address for {addr}."))
+        })?;
+        let stream = TcpStream::connect_timeout(&resolved, timeout)
+            .map_err(|e| io::Error::new(e.kind(), format!("{addr}: {e}")))?;
+        stream.set_nodelay(true)?;
+
+        let mut port = TcpPort {
+            stream,
+            name: addr.to_owned(),
+            timeout,
+        };
+        port.set_timeout(timeout)?;
+        Ok(port)
+    }
+}
+
+fn closed() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        "The connection was closed.",
+    )
+}
+
+impl Read for TcpPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.stream.read(buf) {
+            Ok(0) => Err(closed()),
+            // The read timeout is reported as `WouldBlock` on Unix.
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Operation timed out",
+            )),
+            res => res,
+        }
+    }
+}
+
+impl Write for TcpPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl SerialPort for TcpPort {
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(9600)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        // A zero read timeout is rejected by the socket.
+        self.stream
+            .set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        self.stream.set_nonblocking(true)?;
+        let mut chunk = [0; 256];
+        let res = match self.stream.peek(&mut chunk) {
+            Ok(0) => Err(closed()),
+            Ok(n) => Ok(n as u32),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(0),
+            Err(e) => Err(e),
+        };
+        self.stream.set_nonblocking(false)?;
+        Ok(res?)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        if !matches!(buffer_to_clear, ClearBuffer::Output) {
+            // Through a clone, as discarding the received bytes needs `&mut self`.
+            let mut stream = self.stream.try_clone()?;
+            stream.set_nonblocking(true)?;
+            let mut chunk = [0; 256];
+            let res = loop {
+                match stream.read(&mut chunk) {
+                    Ok(0) => break Err(closed()),
+                    Ok(_) => (),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break Ok(()),
+                    Err(e) => break Err(e),
+                }
+            };
+            stream.set_nonblocking(false)?;
+            res?;
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(TcpPort {
+            stream: self.stream.try_clone()?,
+            name: self.name.clone(),
+            timeout: self.timeout,
+        }))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+/// The link a mount is using.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Transport {
+    Primary,
+    Backup,
+}
+
+/// A change of link, reported by a [`Failover`].
+#[derive(Debug)]
+pub enum FailoverEvent {
+    /// The primary link failed persistently, with this last error, and the mount switched to the backup.
+    FailedOver(io::Error),
+    /// The primary link failed persistently but the backup could not be opened either. Switching is tried again
+    /// after as many further failures.
+    BackupUnavailable(io::Error),
+}
+
+type Opener = Box<dyn FnMut() -> Result<Box<dyn SerialPort>, io::Error> + Send>;
+type Listener = Box<dyn FnMut(FailoverEvent) + Send>;
+
+/// A backup link for a [`CelestronMount`]; see [`transport`](self).
+pub struct Failover {
+    open_backup: Opener,
+    after: u32,
+    failures: u32,
+    active: Transport,
+    on_event: Option<Listener>,
+}
+
+impl fmt::Debug for Failover {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Failover")
+            .field("after", &self.after)
+            .field("failures", &self.failures)
+            .field("active", &self.active)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Failover {
+    /// Switches to the port returned by `open_backup` after `after` consecutive commands fail on the primary link.
+    ///
+    /// A command fails on the link if it times out or the port reports an error. Malformed responses do not count,
+    /// since the link is working.
+    pub fn new<F>(open_backup: F, after: u32) -> Failover
+    where
+        F: FnMut() -> Result<Box<dyn SerialPort>, io::Error> + Send + 'static,
+    {
+        Failover {
+            open_backup: Box::new(open_backup),
+            after: after.max(1),
+            failures: 0,
+            active: Transport::Primary,
+            on_event: None,
+        }
+    }
+
+    /// Calls `on_event` from the thread of the failing command whenever the link changes or cannot be changed.
+    pub fn on_event<F: FnMut(FailoverEvent) + Send + 'static>(mut self, on_event: F) -> Failover {
+        self.on_event = Some(Box::new(on_event));
+        self
+    }
+
+    pub fn active(&self) -> Transport {
+        self.active
+    }
+
+    pub(super) fn succeeded(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Records a failed command, returning the backup port if it is time to switch to it.
+    pub(super) fn failed(&mut self, e: &io::Error) -> Option<Box<dyn SerialPort>> {
+        if self.active == Transport::Backup || e.kind() == io::ErrorKind::InvalidData {
+            return None;
+        }
+        self.failures += 1;
+        if self.failures < self.after {
+            return None;
+        }
+        self.failures = 0;
+
+        let reason = io::Error::new(e.kind(), e.to_string());
+        match (self.open_backup)() {
+            Ok(port) => {
+                log::warn!("Primary link failed ({reason}); switching to the backup.");
+                self.active = Transport::Backup;
+                self.emit(FailoverEvent::FailedOver(reason));
+                Some(port)
+            }
+            Err(backup) => {
+                log::error!(
+                    "Primary link failed ({reason}) and the backup is unavailable: {backup}"
+                );
+                self.emit(FailoverEvent::BackupUnavailable(backup));
+                None
+            }
+        }
+    }
+
+    fn emit(&mut self, event: FailoverEvent) {
+        if let Some(on_event) = &mut self.on_event {
+            on_event(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{Fault, SimMount, SimPort};
+    use crate::mount::Mount;
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn recognizes_addresses() {
+        assert!(is_network_addr(WIFI_ADDR));
+        assert!(is_network_addr("mount.local:2000"));
+        assert!(!is_network_addr("/dev/ttyUSB0"));
+        assert!(!is_network_addr("COM3"));
+        assert!(!is_network_addr("/dev/serial/by-path/pci-0:1"));
+    }
+
+    #[test]
+    fn talks_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        // Echoes the argument of each echo command, as a hand control behind a WiFi module does.
+        let server = thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut cmd = [0; 2];
+            while socket.read_exact(&mut cmd).is_ok() {
+                socket.write_all(&[cmd[1], b'#']).unwrap();
+            }
+        });
+
+        let mut mount = CelestronMount::connect_tcp(&addr).unwrap();
+        mount.ping().unwrap();
+        mount.ping().unwrap();
+        drop(mount);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn fails_over_to_backup() {
+        let primary = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        let mut mount = CelestronMount::from_port(Box::new(primary.clone()));
+        let (tx, rx) = mpsc::channel();
+        mount.set_failover(Some(
+            Failover::new(|| Ok(Box::new(SimPort::new(SimMount::new()))), 2).on_event(
+                move |event| {
+                    let _ = tx.send(event);
+                },
+            ),
+        ));
+
+        // A malformed response does not count, and a success resets the count.
+        primary.inject(Fault::Timeout);
+        primary.inject(Fault::Garbage(b"zz".to_vec()));
+        assert!(mount.get_tracking_mode().is_err());
+        assert!(mount.get_tracking_mode().is_err());
+        mount.get_tracking_mode().unwrap();
+        primary.inject(Fault::Timeout);
+        assert!(mount.get_tracking_mode().is_err());
+        assert_eq!(mount.transport(), Transport::Primary);
+
+        // The second consecutive failure is retried on the backup.
+        primary.inject(Fault::Timeout);
+        mount.get_tracking_mode().unwrap();
+        assert_eq!(mount.transport(), Transport::Backup);
+        assert!(matches!(rx.try_recv(), Ok(FailoverEvent::FailedOver(_))));
+        assert_eq!(mount.metrics().reconnects, 1);
+        // The primary is no longer used.
+        primary.inject(Fault::Timeout);
+        mount.get_tracking_mode().unwrap();
+        assert_eq!(primary.pending_faults(), 1);
+    }
+}
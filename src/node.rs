@@ -0,0 +1,177 @@
+//! Node.js bindings built with napi-rs.
+//!
+//! Every method returns a `Promise` and runs its serial communication on a worker thread, so Electron and Node
+//! applications never block their event loop waiting on the mount:
+//!
+//! ```js
+//! const { connect } = require('nexlib');
+//! const mount = await connect();
+//! await mount.gotoRaDec(83.8, -5.4);
+//! console.log(await mount.getPositionRaDec());
+//! ```
+
+use crate::mount::error::MountError;
+use crate::mount::{Mount, TrackingMode};
+use crate::CelestronMount;
+use chrono::{DateTime, Utc};
+use napi::{Error, Result, Status};
+use napi_derive::napi;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+fn to_js_error(e: impl fmt::Display) -> Error {
+    Error::new(Status::GenericFailure, e.to_string())
+}
+
+/// Runs `f` against the mount on a blocking worker thread.
+async fn with_mount<T, F>(mount: &Arc<Mutex<CelestronMount>>, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut CelestronMount) -> std::result::Result<T, MountError> + Send + 'static,
+{
+    let mount = Arc::clone(mount);
+    tokio::task::spawn_blocking(move || {
+        let mut mount = mount
+            .lock()
+            .map_err(|_| Error::from_reason("Mount lock poisoned."))?;
+        f(&mut mount).map_err(to_js_error)
+    })
+    .await
+    .map_err(|e| Error::from_reason(format!("Mount task failed: {e}")))?
+}
+
+#[napi(object)]
+pub struct RaDec {
+    pub ra: f64,
+    pub dec: f64,
+}
+
+#[napi(object)]
+pub struct AzEl {
+    pub az: f64,
+    pub el: f64,
+}
+
+fn tracking_mode_from_js(mode: u32) -> Result<TrackingMode> {
+    match mode {
+        0 => Ok(TrackingMode::Off),
+        1 => Ok(TrackingMode::AzEl),
+        2 => Ok(TrackingMode::EQNorth),
+        3 => Ok(TrackingMode::EQSouth),
+        _ => Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid tracking mode {mode}."),
+        )),
+    }
+}
+
+/// Connects to the first detected mount.
+// Only JavaScript calls it, so the test build sees it as unused.
+#[cfg_attr(test, allow(dead_code))]
+#[napi]
+pub async fn connect() -> Result<NexMount> {
+    let mount = tokio::task::spawn_blocking(CelestronMount::new)
+        .await
+        .map_err(|e| Error::from_reason(format!("Connect task failed: {e}")))?
+        .map_err(to_js_error)?;
+
+    Ok(NexMount {
+        mount: Arc::new(Mutex::new(mount)),
+    })
+}
+
+/// Handle to a connected mount.
+#[napi]
+pub struct NexMount {
+    mount: Arc<Mutex<CelestronMount>>,
+}
+
+#[napi]
+impl NexMount {
+    /// Current position in right ascension and declination, in degrees.
+    #[napi]
+    pub async fn get_position_ra_dec(&self) -> Result<RaDec> {
+        let pos = with_mount(&self.mount, |m| m.get_position_ra_dec()).await?;
+        Ok(RaDec {
+            ra: pos.ra,
+            dec: pos.dec,
+        })
+    }
+
+    /// Current position in azimuth and elevation, in degrees.
+    #[napi]
+    pub async fn get_position_az_el(&self) -> Result<AzEl> {
+        let pos = with_mount(&self.mount, |m| m.get_position_az_el()).await?;
+        Ok(AzEl {
+            az: pos.az,
+            el: pos.el,
+        })
+    }
+
+    #[napi]
+    pub async fn goto_ra_dec(&self, ra: f64, dec: f64) -> Result<()> {
+        with_mount(&self.mount, move |m| {
+            m.goto_ra_dec(crate::RADec::new(ra, dec))
+        })
+        .await
+    }
+
+    #[napi]
+    pub async fn goto_az_el(&self, az: f64, el: f64) -> Result<()> {
+        with_mount(&self.mount, move |m| m.goto_az_el(crate::AzEl::new(az, el))).await
+    }
+
+    #[napi]
+    pub async fn sync(&self, ra: f64, dec: f64) -> Result<()> {
+        with_mount(&self.mount, move |m| m.sync(crate::RADec::new(ra, dec))).await
+    }
+
+    #[napi]
+    pub async fn goto_in_progress(&self) -> Result<bool> {
+        with_mount(&self.mount, |m| m.goto_in_progress()).await
+    }
+
+    #[napi]
+    pub async fn cancel_goto(&self) -> Result<()> {
+        with_mount(&self.mount, |m| m.cancel_goto()).await
+    }
+
+    #[napi]
+    pub async fn is_aligned(&self) -> Result<bool> {
+        with_mount(&self.mount, |m| m.is_aligned()).await
+    }
+
+    /// Tracking mode: 0 = off, 1 = alt-az, 2 = EQ north, 3 = EQ south.
+    #[napi]
+    pub async fn get_tracking_mode(&self) -> Result<u32> {
+        with_mount(&self.mount, |m| {
+            m.get_tracking_mode().map(|mode| mode as u32)
+        })
+        .await
+    }
+
+    /// Sets the tracking mode, using the same numbering as `getTrackingMode`.
+    #[napi]
+    pub async fn set_tracking_mode(&self, mode: u32) -> Result<()> {
+        let mode = tracking_mode_from_js(mode)?;
+        with_mount(&self.mount, move |m| m.set_tracking_mode(mode)).await
+    }
+
+    #[napi]
+    pub async fn get_time(&self) -> Result<DateTime<Utc>> {
+        with_mount(&self.mount, |m| m.get_time()).await
+    }
+
+    #[napi]
+    pub async fn get_model(&self) -> Result<String> {
+        with_mount(&self.mount, |m| {
+            m.get_model().map(|model| model.to_string())
+        })
+        .await
+    }
+
+    #[napi]
+    pub async fn get_version(&self) -> Result<String> {
+        with_mount(&self.mount, |m| m.get_version()).await
+    }
+}
@@ -0,0 +1,16 @@
+//! The traits and types most programs need, for a glob import:
+//!
+//! ```
+//! use nexlib::prelude::*;
+//!
+//! let mut mount = SimMount::new();
+//! mount.set_tracking_mode(TrackingMode::EQNorth).unwrap();
+//! mount.goto_ra_dec(RADec::new(83.82, -5.39)).unwrap();
+//! ```
+//!
+//! Bringing the traits into scope is what makes their methods callable on [`CelestronMount`] and the other mounts.
+
+pub use crate::mount::{
+    AzEl, CelestronMount, FineTracking, Gps, Guider, HandController, Location, Model, Mount, Pec, RADec, Rtc,
+    SimMount, SlewAxis, SlewDir, SlewRate, TrackingMode, TrackingRate,
+};
@@ -0,0 +1,368 @@
+//! JSON request dispatch onto the [`Mount`] trait.
+//!
+//! Requests and responses follow the shape of JSON-RPC 2.0, one JSON object per line:
+//!
+//! ```json
+//! {"id": 1, "method": "goto_ra_dec", "params": {"ra": 83.8, "dec": -5.4}}
+//! {"id": 1, "result": null}
+//! {"id": 2, "method": "get_position_ra_dec"}
+//! {"id": 2, "result": {"ra": 83.8, "dec": -5.4}}
+//! ```
+//!
+//! Method names and parameters mirror the `Mount` trait. Errors reported by the mount carry the `io::ErrorKind` in
+//! `error.data.kind` so clients can reconstruct them.
+//!
+//! [`serve_stdio`] speaks this protocol over stdin and stdout, letting other programs embed mount control as a
+//! subprocess (`nexctl stdio`) without networking or FFI.
+
+use chrono::{DateTime, Utc};
+use crate::mount::error::MountError;
+use crate::mount::queue::Priority;
+use crate::mount::{Location, Mount, NonGpsDevice, SlewAxis, SlewDir, SlewRate, TrackingMode};
+use crate::{AzEl, RADec};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// The mount reported an error while executing the method.
+pub const MOUNT_ERROR: i64 = -32000;
+/// Another client of the daemon holds the lease required to move the mount.
+pub const LEASE_HELD: i64 = -32001;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub id: Value,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorObject {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl ErrorObject {
+    pub fn new(code: i64, message: impl Into<String>) -> ErrorObject {
+        ErrorObject {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Converts the error back into an `io::Error`, restoring its kind when the server reported one.
+    pub fn into_io_error(self) -> io::Error {
+        let kind = match self.data.as_ref().and_then(|d| d["kind"].as_str()) {
+            Some("NotFound") => io::ErrorKind::NotFound,
+            Some("InvalidData") => io::ErrorKind::InvalidData,
+            Some("InvalidInput") => io::ErrorKind::InvalidInput,
+            Some("TimedOut") => io::ErrorKind::TimedOut,
+            Some("NotConnected") => io::ErrorKind::NotConnected,
+            Some("Unsupported") => io::ErrorKind::Unsupported,
+            Some("PermissionDenied") => io::ErrorKind::PermissionDenied,
+            _ if self.code == INVALID_PARAMS => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, self.message)
+    }
+}
+
+impl From<io::Error> for ErrorObject {
+    fn from(e: io::Error) -> Self {
+        ErrorObject {
+            code: MOUNT_ERROR,
+            message: e.to_string(),
+            data: Some(json!({ "kind": format!("{:?}", e.kind()) })),
+        }
+    }
+}
+
+impl From<MountError> for ErrorObject {
+    fn from(e: MountError) -> Self {
+        io::Error::from(e).into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorObject>,
+}
+
+impl Response {
+    pub fn ok(id: Value, result: Value) -> Response {
+        Response {
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn err(id: Value, error: ErrorObject) -> Response {
+        Response {
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SlewVariableParams {
+    axis: SlewAxis,
+    dir: SlewDir,
+    rate: u16,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlewFixedParams {
+    axis: SlewAxis,
+    dir: SlewDir,
+    rate: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct EchoParams {
+    byte: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct LimitProfileParams {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AxisParams {
+    axis: SlewAxis,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrackingModeParams {
+    mode: TrackingMode,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimeParams {
+    /// RFC 3339.
+    time: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceParams {
+    device: NonGpsDevice,
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, ErrorObject> {
+    serde_json::from_value(params)
+        .map_err(|e| ErrorObject::new(INVALID_PARAMS, format!("Invalid parameters: {e}")))
+}
+
+fn to_value<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).expect("Mount types are always serializable.")
+}
+
+/// Executes a single method call against `mount`.
+pub fn dispatch<M: Mount>(mount: &mut M, method: &str, p: Value) -> Result<Value, ErrorObject> {
+    let res = match method {
+        "get_position_ra_dec" => to_value(mount.get_position_ra_dec()?),
+        "get_position_az_el" => to_value(mount.get_position_az_el()?),
+        "goto_ra_dec" => to_value(mount.goto_ra_dec(params::<RADec>(p)?)?),
+        "goto_az_el" => to_value(mount.goto_az_el(params::<AzEl>(p)?)?),
+        "sync" => to_value(mount.sync(params::<RADec>(p)?)?),
+        "get_tracking_mode" => to_value(mount.get_tracking_mode()?),
+        "set_tracking_mode" => {
+            to_value(mount.set_tracking_mode(params::<TrackingModeParams>(p)?.mode)?)
+        }
+        "slew_variable" => {
+            let p = params::<SlewVariableParams>(p)?;
+            to_value(mount.slew_variable(p.axis, p.dir, p.rate)?)
+        }
+        "slew_fixed" => {
+            let p = params::<SlewFixedParams>(p)?;
+            let rate = SlewRate::try_from(p.rate)
+                .map_err(|e| ErrorObject::new(INVALID_PARAMS, e.to_string()))?;
+            to_value(mount.slew_fixed(p.axis, p.dir, rate)?)
+        }
+        "stop_slew" => to_value(mount.stop_slew(params::<AxisParams>(p)?.axis)?),
+        "get_location" => to_value(mount.get_location()?),
+        "set_location" => to_value(mount.set_location(params::<Location>(p)?)?),
+        "get_time" => to_value(mount.get_time()?.to_rfc3339()),
+        "set_time" => {
+            let time = params::<TimeParams>(p)?.time;
+            let time = DateTime::parse_from_rfc3339(&time).map_err(|e| {
+                ErrorObject::new(INVALID_PARAMS, format!("Invalid time {time:?}: {e}"))
+            })?;
+            to_value(mount.set_time(time.with_timezone(&Utc))?)
+        }
+        "get_version" => to_value(mount.get_version()?),
+        "get_device_version" => {
+            to_value(mount.get_device_version(params::<DeviceParams>(p)?.device)?)
+        }
+        "get_model" => {
+            let model = mount.get_model()?;
+            json!({ "id": model as u8, "name": model.to_string() })
+        }
+        "echo" => to_value(mount.echo(params::<EchoParams>(p)?.byte)?),
+        "is_aligned" => to_value(mount.is_aligned()?),
+        "goto_in_progress" => to_value(mount.goto_in_progress()?),
+        "cancel_goto" => to_value(mount.cancel_goto()?),
+        "stop_all" => to_value(mount.stop_all()?),
+        "select_limit_profile" => {
+            let name = params::<LimitProfileParams>(p)?.name;
+            to_value(mount.select_limit_profile(name.as_deref())?)
+        }
+        "limit_profiles" => to_value(mount.limit_profiles()?),
+        "emergency_stop" => to_value(mount.emergency_stop()?),
+        _ => {
+            return Err(ErrorObject::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method {method}."),
+            ))
+        }
+    };
+
+    Ok(res)
+}
+
+/// The priority to run a call at when requests from several clients are queued onto one mount.
+///
+/// Aborts and stops are urgent, and status reads run in the background behind everything else.
+pub fn priority(method: &str, params: &Value) -> Priority {
+    match method {
+        "cancel_goto" | "stop_slew" | "stop_all" | "emergency_stop" => Priority::Urgent,
+        "slew_variable" | "slew_fixed" if params["rate"] == 0 => Priority::Urgent,
+        "echo" | "is_aligned" | "goto_in_progress" => Priority::Background,
+        "limit_profiles" => Priority::Background,
+        m if m.starts_with("get_") => Priority::Background,
+        _ => Priority::Normal,
+    }
+}
+
+/// Parses and executes one line of input, returning the serialized response.
+pub fn handle_line<M: Mount>(mount: &mut M, line: &str) -> String {
+    let res = match serde_json::from_str::<Value>(line) {
+        Err(e) => Response::err(
+            Value::Null,
+            ErrorObject::new(PARSE_ERROR, format!("Parse error: {e}")),
+        ),
+        Ok(value) => match serde_json::from_value::<Request>(value) {
+            Err(e) => Response::err(
+                Value::Null,
+                ErrorObject::new(INVALID_REQUEST, format!("Invalid request: {e}")),
+            ),
+            Ok(req) => match dispatch(mount, &req.method, req.params) {
+                Ok(result) => Response::ok(req.id, result),
+                Err(e) => Response::err(req.id, e),
+            },
+        },
+    };
+
+    serde_json::to_string(&res).expect("Responses are always serializable.")
+}
+
+/// Answers requests read from `input`, one per line, until end of input. Blank lines are ignored.
+pub fn serve<M: Mount, R: BufRead, W: Write>(
+    mount: &mut M,
+    input: R,
+    mut output: W,
+) -> Result<(), io::Error> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        writeln!(output, "{}", handle_line(mount, line.trim()))?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Serves requests from stdin, writing responses to stdout, until stdin is closed.
+pub fn serve_stdio<M: Mount>(mount: &mut M) -> Result<(), io::Error> {
+    serve(mount, io::stdin().lock(), io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_without_params() {
+        let req: Request = serde_json::from_str(r#"{"id":3,"method":"is_aligned"}"#).unwrap();
+        assert_eq!(req.id, json!(3));
+        assert_eq!(req.params, Value::Null);
+    }
+
+    #[test]
+    fn slew_params() {
+        let p: SlewFixedParams =
+            params(json!({"axis": "DecEl", "dir": "Negative", "rate": 4})).unwrap();
+        assert_eq!(p.axis, SlewAxis::DecEl);
+        assert_eq!(p.dir, SlewDir::Negative);
+        assert_eq!(p.rate, 4);
+
+        let e = params::<AxisParams>(json!({"axis": "Sideways"})).unwrap_err();
+        assert_eq!(e.code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn stops_are_urgent() {
+        let stop = json!({"axis": "RAAz", "dir": "Positive", "rate": 0});
+        assert_eq!(priority("slew_fixed", &stop), Priority::Urgent);
+        assert_eq!(priority("cancel_goto", &Value::Null), Priority::Urgent);
+        assert_eq!(priority("stop_all", &Value::Null), Priority::Urgent);
+        assert_eq!(priority("emergency_stop", &Value::Null), Priority::Urgent);
+        let slew = json!({"axis": "RAAz", "dir": "Positive", "rate": 4});
+        assert_eq!(priority("slew_fixed", &slew), Priority::Normal);
+        assert_eq!(priority("goto_ra_dec", &Value::Null), Priority::Normal);
+        assert_eq!(priority("get_position_az_el", &Value::Null), Priority::Background);
+    }
+
+    #[test]
+    fn error_kind_round_trip() {
+        let e = ErrorObject::from(io::Error::new(io::ErrorKind::TimedOut, "late"));
+        assert_eq!(e.code, MOUNT_ERROR);
+        let e = e.into_io_error();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(e.to_string(), "late");
+    }
+
+    #[test]
+    fn serves_one_response_per_line() {
+        let mut mount = crate::mount::SimMount::new();
+        let input = concat!(
+            "{\"id\":1,\"method\":\"echo\",\"params\":{\"byte\":7}}\n",
+            "\n",
+            "   \n",
+            "{\"id\":2,\"method\":\"is_aligned\"}\n",
+            "not json\n",
+        );
+        let mut output = Vec::new();
+        serve(&mut mount, io::Cursor::new(input), &mut output).unwrap();
+
+        let responses: Vec<Response> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0], Response::ok(json!(1), json!(7)));
+        assert_eq!(responses[1], Response::ok(json!(2), json!(true)));
+        assert_eq!(responses[2].error.as_ref().unwrap().code, PARSE_ERROR);
+    }
+}
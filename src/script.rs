@@ -0,0 +1,317 @@
+//! Observing sessions described in a TOML or JSON file, for automating a night without writing Rust.
+//!
+//! A [`Script`] names the site, the tracking mode, optional pointing limits, and the targets to visit in order, each
+//! with a dwell time, settle criteria, and an optional time not to start before. [`Script::validate`] checks the whole
+//! session before anything moves: every name resolves, the Sun is not among the targets, start times are in order,
+//! and every target is within the limits when it is due. [`run`] loads, validates, and carries out a file:
+//!
+//! ```toml
+//! tracking = "EQNorth"
+//!
+//! [site]
+//! latitude = 40.0
+//! longitude = -75.0
+//!
+//! [limits]
+//! min_elevation = 20.0
+//!
+//! [[targets]]
+//! target = { named = "M 13" }
+//! dwell_seconds = 600
+//! settle = { seconds = 5, tolerance_arcsec = 60 }
+//!
+//! [[targets]]
+//! target = { body = "Jupiter" }
+//! dwell_seconds = 300
+//! start_at = "2024-07-15T09:30:00Z"
+//!
+//! [[targets]]
+//! target = { ra_dec = { ra = 283.4, dec = 33.0 } }
+//! dwell_seconds = 300
+//! ```
+//!
+//! Files ending in `.json` are read as JSON with the same structure, and anything else as TOML. From the command line,
+//! `nexctl script --check tonight.toml` validates a file and `nexctl script tonight.toml` runs it.
+
+use crate::catalog::{self, ephemeris::Body};
+use crate::mount::limits::LimitProfile;
+use crate::mount::plan::{Plan, PlanEntry, PlanRunner, PlanTarget};
+use crate::mount::transform::{az_el_to_ha_dec, ha_dec_to_az_el, local_sidereal_time};
+use crate::mount::{Location, Mount, TrackingMode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Longest sleep while waiting for a target's start time, so a mount clock set meanwhile is noticed.
+const START_POLL: Duration = Duration::from_secs(1);
+
+/// A target of a [`Script`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptTarget {
+    #[serde(flatten)]
+    pub entry: PlanEntry,
+    /// Time not to start the goto before, by the mount's clock.
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
+}
+
+/// An observing session; see [`script`](self).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Script {
+    pub site: Location,
+    /// Tracking mode set before the first goto, or `None` to leave tracking as it is.
+    #[serde(default)]
+    pub tracking: Option<TrackingMode>,
+    /// Limits every target must be within when it is due, or `None` for no limits.
+    #[serde(default)]
+    pub limits: Option<LimitProfile>,
+    #[serde(default)]
+    pub targets: Vec<ScriptTarget>,
+}
+
+fn invalid(path: &Path, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid script {}: {}", path.display(), e),
+    )
+}
+
+fn invalid_target(index: usize, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("Target {index}: {e}"))
+}
+
+impl Script {
+    /// Reads a script, as JSON if the file name ends in `.json` and as TOML otherwise.
+    pub fn load(path: &Path) -> Result<Script, io::Error> {
+        let text = fs::read_to_string(path)?;
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            serde_json::from_str(&text).map_err(|e| invalid(path, e))
+        } else {
+            toml::from_str(&text).map_err(|e| invalid(path, e))
+        }
+    }
+
+    /// Checks the session as it would run from `time`, failing with `InvalidInput`, or `NotFound` for a name not in
+    /// the catalog, on the first problem found.
+    ///
+    /// Targets without a start time are checked at the start time of the target before them, or `time` for the
+    /// first; how long gotos take is not known in advance.
+    pub fn validate(&self, time: DateTime<Utc>) -> Result<(), io::Error> {
+        let Location {
+            latitude,
+            longitude,
+        } = self.site;
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Site at latitude {latitude}, longitude {longitude} is not on Earth."),
+            ));
+        }
+        if let (Some(limits), Some(mode)) = (&self.limits, self.tracking) {
+            if !limits.allows_tracking(mode) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("The limits do not allow tracking mode {mode:?}."),
+                ));
+            }
+        }
+
+        let mut due = time;
+        for (i, target) in self.targets.iter().enumerate() {
+            if let Some(start) = target.start_at {
+                if start < due && i > 0 {
+                    return Err(invalid_target(i, "starts before the target before it."));
+                }
+                due = start;
+            }
+            let entry = &target.entry;
+            if !(entry.dwell_seconds >= 0.0 && entry.dwell_seconds.is_finite()) {
+                return Err(invalid_target(
+                    i,
+                    "the dwell time must be zero or more seconds.",
+                ));
+            }
+            if entry.target == PlanTarget::Body(Body::Sun) {
+                return Err(invalid_target(i, "scripts may not point at the Sun."));
+            }
+            self.check_limits(i, &entry.target, due)?;
+        }
+        Ok(())
+    }
+
+    /// Checks `target` against the limits at `time`, failing with `NotFound` for a name not in the catalog.
+    fn check_limits(
+        &self,
+        index: usize,
+        target: &PlanTarget,
+        time: DateTime<Utc>,
+    ) -> Result<(), io::Error> {
+        let coord = match target {
+            PlanTarget::AzEl(coord) => {
+                let Some(limits) = &self.limits else {
+                    return Ok(());
+                };
+                let (ha, _) = az_el_to_ha_dec(*coord, self.site.latitude);
+                return limits
+                    .check(*coord, ha)
+                    .map_err(|e| invalid_target(index, e));
+            }
+            PlanTarget::RaDec(coord) => *coord,
+            PlanTarget::Named(name) => catalog::lookup(name)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("Target {index}: no object named {name} in the catalog."),
+                    )
+                })?
+                .j2000_to_jnow(time),
+            PlanTarget::Body(body) => body.position(&self.site, time),
+        };
+        let Some(limits) = &self.limits else {
+            return Ok(());
+        };
+        let ha = local_sidereal_time(time, self.site.longitude) - coord.ra;
+        limits
+            .check(ha_dec_to_az_el(ha, coord.dec, self.site.latitude), ha)
+            .map_err(|e| invalid_target(index, e))
+    }
+
+    /// Validates the session at the mount's time and carries it out, waiting for each target's start time, checking
+    /// it against the limits again when it is due, and visiting it as a [`Plan`] entry.
+    pub fn run<M: Mount>(&self, mount: &mut M) -> Result<(), io::Error> {
+        self.validate(mount.get_time()?)?;
+        if let Some(mode) = self.tracking {
+            mount.set_tracking_mode(mode)?;
+        }
+
+        for (i, target) in self.targets.iter().enumerate() {
+            if let Some(start) = target.start_at {
+                loop {
+                    let wait = (start - mount.get_time()?).to_std().unwrap_or_default();
+                    if wait.is_zero() {
+                        break;
+                    }
+                    log::info!("Target {i} starts in {} s.", wait.as_secs());
+                    std::thread::sleep(wait.min(START_POLL));
+                }
+            }
+            self.check_limits(i, &target.entry.target, mount.get_time()?)?;
+
+            let plan = Plan {
+                tracking: None,
+                entries: vec![target.entry.clone()],
+            };
+            PlanRunner::new(plan, self.site)
+                .on_arrival(|_, entry| log::info!("Target {i}: at {:?}.", entry.target))
+                .run(mount)?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads the script at `path` and runs it on `mount`; see [`Script::run`].
+pub fn run<M: Mount>(mount: &mut M, path: &Path) -> Result<(), io::Error> {
+    Script::load(path)?.run(mount)
+}
+
+/// Loads the script at `path` and checks it as it would run from now; see [`Script::validate`].
+pub fn validate(path: &Path) -> Result<Script, io::Error> {
+    let script = Script::load(path)?;
+    script.validate(Utc::now())?;
+    Ok(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::plan::Settle;
+    use crate::mount::SimMount;
+    use crate::AzEl;
+    use chrono::TimeZone;
+
+    const SCRIPT: &str = r#"
+        tracking = "EQNorth"
+
+        [site]
+        latitude = 40.0
+        longitude = -75.0
+
+        [limits]
+        min_elevation = 20.0
+
+        [[targets]]
+        target = { named = "M 13" }
+        dwell_seconds = 600
+        settle = { seconds = 5, tolerance_arcsec = 60 }
+
+        [[targets]]
+        target = { body = "Jupiter" }
+        dwell_seconds = 300
+        start_at = "2024-07-15T09:30:00Z"
+    "#;
+
+    #[test]
+    fn validates_and_runs() {
+        let mut script: Script = toml::from_str(SCRIPT).unwrap();
+        assert_eq!(script.targets[0].entry.settle.tolerance_arcsec, Some(60.0));
+        let json = serde_json::to_string(&script).unwrap();
+        assert_eq!(serde_json::from_str::<Script>(&json).unwrap(), script);
+
+        // Hercules is high at midnight in July, and Jupiter has risen by dawn.
+        let midnight = Utc.with_ymd_and_hms(2024, 7, 15, 4, 0, 0).unwrap();
+        script.validate(midnight).unwrap();
+        // At noon Hercules is below the horizon.
+        let noon = Utc.with_ymd_and_hms(2024, 7, 15, 16, 0, 0).unwrap();
+        assert!(script
+            .validate(noon)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Target 0"));
+
+        script.targets[1].start_at = Some(midnight - chrono::Duration::hours(1));
+        assert!(script
+            .validate(midnight)
+            .unwrap_err()
+            .to_string()
+            .contains("starts before"));
+        script.targets[1].entry.target = PlanTarget::Named("Nowhere Nebula".to_owned());
+        script.targets[1].start_at = None;
+        assert_eq!(
+            script.validate(midnight).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        // The simulator's own site and the pole it is parked at, so the goto is short.
+        let script = Script {
+            site: Location {
+                latitude: 45.0,
+                longitude: 0.0,
+            },
+            tracking: None,
+            limits: None,
+            targets: vec![ScriptTarget {
+                entry: PlanEntry {
+                    settle: Settle {
+                        seconds: 0.0,
+                        ..Settle::default()
+                    },
+                    ..PlanEntry::new(PlanTarget::AzEl(AzEl::new(0.0, 45.0)), 0.0)
+                },
+                start_at: Some(Utc::now() - chrono::Duration::minutes(1)),
+            }],
+        };
+        let mut mount = SimMount::new();
+        script.run(&mut mount).unwrap();
+        let pos = mount.get_position_az_el().unwrap();
+        assert!(
+            pos.az.min(360.0 - pos.az) < 0.01 && (pos.el - 45.0).abs() < 0.01,
+            "{pos:?}"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Sequences of mount operations which survive restarts.
+//!
+//! A [`Sequence`] runs a list of [`Step`]s in order. Created with [`Sequence::persistent`], it saves its progress to a
+//! JSON file after every completed step, and [`Sequence::resume`] reloads the file after an application crash or a
+//! reboot to carry on with the first step not yet completed. An unattended all-night run then picks up where it left
+//! off instead of starting over or giving up.
+//!
+//! A step interrupted part way through runs again from its start on resume, so every step must be safe to repeat.
+//! All of the provided steps are.
+//!
+//! ```no_run
+//! use nexlib::sequence::{Sequence, Step};
+//! use nexlib::mount::TrackingMode;
+//! use nexlib::{CelestronMount, RADec};
+//! use std::path::Path;
+//!
+//! let path = Path::new("tonight.json");
+//! let mut sequence = if path.exists() {
+//!     Sequence::resume(path).unwrap()
+//! } else {
+//!     Sequence::persistent(
+//!         path,
+//!         vec![
+//!             Step::GotoRaDec(RADec::new(83.8, -5.4)),
+//!             Step::SetTracking { mode: TrackingMode::EQNorth },
+//!             Step::Wait { seconds: 600.0 },
+//!             Step::GotoRaDec(RADec::new(279.2, 38.8)),
+//!         ],
+//!     )
+//!     .unwrap()
+//! };
+//!
+//! let mut mount = CelestronMount::new().unwrap();
+//! sequence.run(&mut mount).unwrap();
+//! ```
+
+use crate::mount::error::MountError;
+use crate::mount::{Mount, TrackingMode};
+use crate::{AzEl, RADec};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// A single operation of a sequence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum Step {
+    /// Moves to a right ascension and declination, and waits for the goto to finish.
+    GotoRaDec(RADec),
+    /// Moves to an azimuth and elevation, and waits for the goto to finish.
+    GotoAzEl(AzEl),
+    SetTracking {
+        mode: TrackingMode,
+    },
+    /// Waits, for example while a camera exposes.
+    Wait {
+        seconds: f64,
+    },
+}
+
+impl Step {
+    fn run<M: Mount>(&self, mount: &mut M) -> Result<(), MountError> {
+        match self {
+            Step::GotoRaDec(coord) => mount.goto_and_wait(*coord, None),
+            Step::GotoAzEl(coord) => {
+                mount.goto_az_el(*coord)?;
+                mount.wait_for_goto(None)
+            }
+            Step::SetTracking { mode } => mount.set_tracking_mode(*mode),
+            Step::Wait { seconds } => {
+                thread::sleep(Duration::from_secs_f64(seconds.max(0.0)));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The saved form of a sequence.
+#[derive(Debug, Serialize, Deserialize)]
+struct Progress {
+    steps: Vec<Step>,
+    completed: usize,
+}
+
+/// An ordered list of steps and how many have completed.
+#[derive(Debug)]
+pub struct Sequence {
+    progress: Progress,
+    path: Option<PathBuf>,
+}
+
+impl Sequence {
+    /// A sequence kept only in memory.
+    pub fn new(steps: Vec<Step>) -> Sequence {
+        Sequence {
+            progress: Progress {
+                steps,
+                completed: 0,
+            },
+            path: None,
+        }
+    }
+
+    /// A sequence saved to `path`, replacing any sequence saved there.
+    pub fn persistent(path: impl AsRef<Path>, steps: Vec<Step>) -> Result<Sequence, io::Error> {
+        let sequence = Sequence {
+            path: Some(path.as_ref().to_owned()),
+            ..Sequence::new(steps)
+        };
+        sequence.save()?;
+        Ok(sequence)
+    }
+
+    /// Reloads a sequence saved to `path`, to continue with its first step not yet completed.
+    pub fn resume(path: impl AsRef<Path>) -> Result<Sequence, io::Error> {
+        let progress: Progress = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        if progress.completed > progress.steps.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Sequence has {} steps but {} completed.",
+                    progress.steps.len(),
+                    progress.completed
+                ),
+            ));
+        }
+
+        Ok(Sequence {
+            progress,
+            path: Some(path.as_ref().to_owned()),
+        })
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.progress.steps
+    }
+
+    /// Number of steps completed, which are skipped when running.
+    pub fn completed(&self) -> usize {
+        self.progress.completed
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress.completed == self.progress.steps.len()
+    }
+
+    /// Runs the next step not yet completed, returning `false` if there was none.
+    ///
+    /// A failed step is not marked completed, so it is attempted again by the next call.
+    pub fn step<M: Mount>(&mut self, mount: &mut M) -> Result<bool, io::Error> {
+        let Some(step) = self.progress.steps.get(self.progress.completed) else {
+            return Ok(false);
+        };
+
+        step.run(mount)?;
+        self.progress.completed += 1;
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Runs the remaining steps in order, stopping at the first failure.
+    pub fn run<M: Mount>(&mut self, mount: &mut M) -> Result<(), io::Error> {
+        while self.step(mount)? {}
+        Ok(())
+    }
+
+    /// Writes the progress to the file, if persistent. The file is replaced atomically, so a crash while saving leaves
+    /// the previous progress intact.
+    fn save(&self) -> Result<(), io::Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&self.progress)?)?;
+        fs::rename(&tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+
+    #[test]
+    fn resumes_after_restart() {
+        let path =
+            std::env::temp_dir().join(format!("nexlib-sequence-{}.json", std::process::id()));
+        let steps = vec![
+            Step::SetTracking {
+                mode: TrackingMode::EQNorth,
+            },
+            Step::Wait { seconds: 0.0 },
+            Step::SetTracking {
+                mode: TrackingMode::Off,
+            },
+        ];
+        let mut mount = SimMount::new();
+
+        let mut sequence = Sequence::persistent(&path, steps.clone()).unwrap();
+        assert!(sequence.step(&mut mount).unwrap());
+        drop(sequence);
+
+        let mut sequence = Sequence::resume(&path).unwrap();
+        assert_eq!(sequence.steps(), steps);
+        assert_eq!(sequence.completed(), 1);
+        sequence.run(&mut mount).unwrap();
+        assert!(sequence.is_finished());
+        assert_eq!(mount.get_tracking_mode().unwrap(), TrackingMode::Off);
+
+        let sequence = Sequence::resume(&path).unwrap();
+        assert!(sequence.is_finished());
+        fs::remove_file(&path).unwrap();
+    }
+}
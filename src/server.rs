@@ -0,0 +1,284 @@
+//! Remote control of a mount over WebSocket, for driving a mount at the pier from a machine indoors.
+//!
+//! Clients send the requests of [`rpc`](crate::rpc) as text frames, one request per frame, and get each response back
+//! as a text frame with the same `id`. Every client is also pushed the position, status, and event frames of
+//! [`websocket`](crate::websocket) at the server's interval; these have a `type` field instead of an `id`:
+//!
+//! ```json
+//! {"id": 1, "method": "goto_ra_dec", "params": {"ra": 83.8, "dec": -5.4}}
+//! {"id": 1, "result": null}
+//! {"type":"position","unix_millis":1700000000000,"ra":83.8,"dec":-5.4,"az":120.1,"el":35.2}
+//! {"id": 2, "method": "stop_all"}
+//! {"id": 2, "result": null}
+//! ```
+//!
+//! `stop_all` aborts any goto and slew, and `emergency_stop` also turns tracking off.
+//!
+//! With a [`RemoteServer::token`], a client must present the token to connect, either in an `Authorization: Bearer`
+//! header or, since browsers cannot set headers on a WebSocket, as a `token` query parameter
+//! (`ws://pier.local:11880/?token=...`). Connections without it are refused with HTTP 401. The token is not
+//! encrypted in transit; on untrusted networks, reach the server through an SSH tunnel or a VPN.
+
+use crate::mount::status::StatusCache;
+use crate::mount::Mount;
+use crate::rpc;
+use crate::websocket::{self, Clients};
+use std::io;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::{Message, WebSocket};
+
+/// Port served by `nexctl server` when no address is given.
+pub const DEFAULT_PORT: u16 = 11880;
+
+/// Longest wait for a request from a client before forwarding the frames pushed to it meanwhile.
+const READ_TIMEOUT: Duration = Duration::from_millis(20);
+
+/// The token a client presented, from its `Authorization` header or else its `token` query parameter.
+fn presented_token(req: &Request) -> Option<&str> {
+    let header = req
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    header.map(str::trim).or_else(|| {
+        req.uri()
+            .query()?
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+    })
+}
+
+/// Compares tokens in a time independent of where they first differ, so it does not reveal a prefix of the token.
+fn token_matches(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Refuses the handshake of a client which does not present the token, if there is one.
+struct Authorize<'a> {
+    token: Option<&'a str>,
+}
+
+impl Callback for Authorize<'_> {
+    fn on_request(self, req: &Request, res: Response) -> Result<Response, ErrorResponse> {
+        match self.token {
+            Some(token) if !presented_token(req).is_some_and(|t| token_matches(t, token)) => {
+                let mut res = ErrorResponse::new(Some("A valid token is required.".to_owned()));
+                *res.status_mut() = StatusCode::UNAUTHORIZED;
+                Err(res)
+            }
+            _ => Ok(res),
+        }
+    }
+}
+
+/// Answers one request, discarding the cached status so the next frames pushed show the effect of a command.
+fn handle<M: Mount>(cache: &StatusCache<M>, text: &str) -> String {
+    let response = rpc::handle_line(&mut *cache.mount().lock().unwrap(), text.trim());
+    cache.invalidate();
+    response
+}
+
+/// Answers a client's requests and forwards the frames pushed to it until either side disconnects.
+fn serve_client<M: Mount>(
+    mut socket: WebSocket<TcpStream>,
+    cache: StatusCache<M>,
+    rx: Receiver<Arc<str>>,
+) {
+    if let Err(e) = socket.get_ref().set_read_timeout(Some(READ_TIMEOUT)) {
+        log::warn!(
+            "[{}:{}] Failed to configure client socket: {:?}",
+            file!(),
+            line!(),
+            e
+        );
+        return;
+    }
+
+    loop {
+        loop {
+            match rx.try_recv() {
+                Ok(json) => {
+                    if socket.send(Message::text(&*json)).is_err() {
+                        return;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                if socket.send(Message::text(handle(&cache, &text))).is_err() {
+                    return;
+                }
+            }
+            Ok(Message::Close(_)) => return,
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => return,
+        }
+    }
+}
+
+/// Serves the [`Mount`] API and a position stream to WebSocket clients; see [`server`](self).
+pub struct RemoteServer {
+    listener: TcpListener,
+    interval: Duration,
+    token: Option<String>,
+}
+
+impl RemoteServer {
+    /// Listens for WebSocket connections on `addr`, from any client until a [`RemoteServer::token`] is set.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<RemoteServer, io::Error> {
+        Ok(RemoteServer {
+            listener: TcpListener::bind(addr)?,
+            interval: websocket::DEFAULT_INTERVAL,
+            token: None,
+        })
+    }
+
+    /// Sets the time between pushed samples. Values below [`websocket::MIN_INTERVAL`] are raised to it.
+    pub fn interval(mut self, interval: Duration) -> RemoteServer {
+        self.interval = interval.max(websocket::MIN_INTERVAL);
+        self
+    }
+
+    /// Requires clients to present `token` to connect.
+    pub fn token(mut self, token: impl Into<String>) -> RemoteServer {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
+
+    /// Serves `mount` to clients until the listener fails.
+    pub fn run<M: Mount + Send + 'static>(self, mount: Arc<Mutex<M>>) -> Result<(), io::Error> {
+        let cache = StatusCache::new(mount, self.interval);
+        self.serve(cache)
+    }
+
+    /// Serves the mount of `cache` to clients until the listener fails, pushing its status as often as the cache
+    /// allows.
+    pub fn serve<M: Mount + Send + 'static>(self, cache: StatusCache<M>) -> Result<(), io::Error> {
+        if self.token.is_none() {
+            log::warn!(
+                "[{}:{}] Serving the mount without a token; anyone who can reach {:?} can move it.",
+                file!(),
+                line!(),
+                self.listener.local_addr()
+            );
+        }
+
+        let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+        {
+            let (cache, clients) = (cache.clone(), Arc::clone(&clients));
+            let interval = self.interval;
+            thread::spawn(move || websocket::poll_mount(cache, clients, interval));
+        }
+
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("[{}:{}] Failed to accept client: {:?}", file!(), line!(), e);
+                    continue;
+                }
+            };
+
+            let (cache, clients, token) = (cache.clone(), Arc::clone(&clients), self.token.clone());
+            // The handshake runs on the client's own thread, so a client which never sends its upgrade request
+            // cannot hold up the others.
+            thread::spawn(move || {
+                let peer = stream.peer_addr();
+                if let Err(e) = stream.set_read_timeout(Some(websocket::HANDSHAKE_TIMEOUT)) {
+                    log::warn!("[{}:{}] Failed to set timeout: {:?}", file!(), line!(), e);
+                    return;
+                }
+                let socket = match tungstenite::accept_hdr(
+                    stream,
+                    Authorize {
+                        token: token.as_deref(),
+                    },
+                ) {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        log::warn!(
+                            "[{}:{}] Refused connection from {:?}: {:?}",
+                            file!(),
+                            line!(),
+                            peer,
+                            e
+                        );
+                        return;
+                    }
+                };
+
+                let (tx, rx) = mpsc::channel();
+                clients.lock().unwrap().push(tx);
+                serve_client(socket, cache, rx);
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::SimMount;
+    use serde_json::Value;
+
+    #[test]
+    fn serves_authorized_clients() {
+        let server = RemoteServer::bind("127.0.0.1:0")
+            .unwrap()
+            .interval(websocket::MIN_INTERVAL)
+            .token("s3cret");
+        let addr = server.local_addr().unwrap();
+        let mount = Arc::new(Mutex::new(SimMount::new()));
+        thread::spawn(move || server.run(mount));
+        // A client which never sends its upgrade request must not hold up the others.
+        let _silent = std::net::TcpStream::connect(addr).unwrap();
+
+        match tungstenite::connect(format!("ws://{addr}/?token=wrong")) {
+            Err(tungstenite::Error::Http(res)) => {
+                assert_eq!(res.status(), StatusCode::UNAUTHORIZED)
+            }
+            res => panic!("{:?}", res.map(|_| ())),
+        }
+
+        let (mut socket, _) = tungstenite::connect(format!("ws://{addr}/?token=s3cret")).unwrap();
+        socket
+            .send(Message::text(r#"{"id": 7, "method": "get_tracking_mode"}"#))
+            .unwrap();
+        let (mut answered, mut pushed) = (false, false);
+        while !(answered && pushed) {
+            let frame: Value =
+                serde_json::from_str(socket.read().unwrap().to_text().unwrap()).unwrap();
+            if frame["id"] == 7 {
+                assert_eq!(frame["result"], "Off");
+                answered = true;
+            }
+            pushed |= frame["type"] == "position";
+        }
+    }
+}
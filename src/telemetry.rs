@@ -0,0 +1,282 @@
+//! Session telemetry logged as JSON Lines.
+//!
+//! A [`Telemetry`] logger appends one JSON object per line to a file per session: every status sample, command,
+//! event, and error, each stamped with both the wall-clock time and a monotonic time since the session started. The
+//! monotonic time keeps intervals exact across clock adjustments (the hand control's own clock is often being set
+//! during a session), while the wall-clock time lines the log up with images and other logs.
+//!
+//! Logging is opt-in. Attach a logger to a mount with
+//! [`CelestronMount::set_telemetry`](crate::CelestronMount::set_telemetry) to log every command, and share clones of
+//! it with whatever else should log to the same session:
+//!
+//! ```no_run
+//! use nexlib::telemetry::Telemetry;
+//! use nexlib::CelestronMount;
+//!
+//! let telemetry = Telemetry::create("logs").unwrap();
+//! let mut mount = CelestronMount::new().unwrap();
+//! mount.set_telemetry(Some(telemetry.clone()));
+//!
+//! telemetry.event("session_start", None);
+//! mount.ping().unwrap();
+//! ```
+//!
+//! A line looks like:
+//!
+//! ```text
+//! {"unix_millis":1710972000061,"mono_micros":61000,"type":"command","request":"65","response":"23","latency_micros":60988,"error":null}
+//! ```
+//!
+//! [`read`] parses a log back into [`Line`]s for analysis.
+
+use crate::mount::stream::PositionSample;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// One line of a telemetry log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Line {
+    /// Wall-clock time of the entry.
+    pub unix_millis: i64,
+    /// Time since the session started, unaffected by changes to the system clock.
+    pub mono_micros: u64,
+    #[serde(flatten)]
+    pub entry: Entry,
+}
+
+/// What a line records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Entry {
+    /// The mount's position, in degrees.
+    Status { ra: f64, dec: f64, az: f64, el: f64 },
+    /// A command sent to the mount and the response received. Raw bytes are logged as lowercase hex.
+    Command {
+        #[serde(with = "hex")]
+        request: Vec<u8>,
+        #[serde(with = "hex")]
+        response: Vec<u8>,
+        latency_micros: u64,
+        error: Option<String>,
+    },
+    /// Something the application did, such as starting an exposure.
+    Event {
+        name: String,
+        detail: Option<String>,
+    },
+    /// A failure outside of a single command.
+    Error { context: String, message: String },
+}
+
+struct Log {
+    out: Box<dyn Write + Send>,
+    start: Instant,
+    path: Option<PathBuf>,
+}
+
+/// A JSON Lines telemetry logger; see [`telemetry`](self).
+///
+/// Clones log to the same session. Every line is flushed as it is written, so a crash loses at most the line being
+/// written. A failure to write is reported through the `log` crate rather than to the caller, so telemetry can never
+/// interrupt the mount.
+#[derive(Clone)]
+pub struct Telemetry {
+    log: Arc<Mutex<Log>>,
+}
+
+impl Telemetry {
+    /// Starts a session logging to a new file in `dir`, named after the time the session started.
+    pub fn create(dir: impl AsRef<Path>) -> Result<Telemetry, io::Error> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "nexlib-{}.jsonl",
+            Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+        ));
+        let file = File::options().create_new(true).append(true).open(&path)?;
+
+        let telemetry = Telemetry::to_writer(BufWriter::new(file));
+        telemetry.log.lock().unwrap().path = Some(path);
+        Ok(telemetry)
+    }
+
+    /// Starts a session logging to `out`.
+    pub fn to_writer(out: impl Write + Send + 'static) -> Telemetry {
+        Telemetry {
+            log: Arc::new(Mutex::new(Log {
+                out: Box::new(out),
+                start: Instant::now(),
+                path: None,
+            })),
+        }
+    }
+
+    /// The file being logged to, if created with [`Telemetry::create`].
+    pub fn path(&self) -> Option<PathBuf> {
+        self.log.lock().unwrap().path.clone()
+    }
+
+    pub fn status(&self, sample: &PositionSample) {
+        self.write(
+            sample.time.timestamp_millis(),
+            Entry::Status {
+                ra: sample.ra_dec.ra,
+                dec: sample.ra_dec.dec,
+                az: sample.az_el.az,
+                el: sample.az_el.el,
+            },
+        );
+    }
+
+    /// Logs a command and its outcome. On failure `response` holds whatever was received before the error.
+    pub fn command(
+        &self,
+        request: &[u8],
+        response: &[u8],
+        latency: Duration,
+        error: Option<&io::Error>,
+    ) {
+        self.write(
+            Utc::now().timestamp_millis(),
+            Entry::Command {
+                request: request.to_vec(),
+                response: response.to_vec(),
+                latency_micros: latency.as_micros() as u64,
+                error: error.map(ToString::to_string),
+            },
+        );
+    }
+
+    pub fn event(&self, name: &str, detail: Option<&str>) {
+        self.write(
+            Utc::now().timestamp_millis(),
+            Entry::Event {
+                name: name.to_owned(),
+                detail: detail.map(str::to_owned),
+            },
+        );
+    }
+
+    /// Logs an error, with `context` saying what was being attempted.
+    pub fn error(&self, context: &str, error: &dyn std::error::Error) {
+        self.write(
+            Utc::now().timestamp_millis(),
+            Entry::Error {
+                context: context.to_owned(),
+                message: error.to_string(),
+            },
+        );
+    }
+
+    fn write(&self, unix_millis: i64, entry: Entry) {
+        let mut log = self.log.lock().unwrap();
+        let line = Line {
+            unix_millis,
+            mono_micros: log.start.elapsed().as_micros() as u64,
+            entry,
+        };
+
+        let res = serde_json::to_writer(&mut log.out, &line)
+            .map_err(io::Error::from)
+            .and_then(|()| log.out.write_all(b"\n"))
+            .and_then(|()| log.out.flush());
+        if let Err(e) = res {
+            log::warn!("Failed to write telemetry: {e}");
+        }
+    }
+}
+
+impl std::fmt::Debug for Telemetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Telemetry")
+            .field("path", &self.path())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads the lines of a telemetry log.
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<Line>, io::Error> {
+    BufReader::new(File::open(path)?)
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|l| l.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+mod hex {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&bytes.iter().map(|b| format!("{b:02x}")).collect::<String>())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        if s.len() % 2 != 0 {
+            return Err(D::Error::custom("Hex string has an odd length."));
+        }
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mount::sim::{Fault, SimMount, SimPort};
+    use crate::CelestronMount;
+
+    #[test]
+    fn logs_commands_and_events() {
+        let dir = std::env::temp_dir().join(format!("nexlib-telemetry-{}", std::process::id()));
+        let telemetry = Telemetry::create(&dir).unwrap();
+        let port = SimPort::new(SimMount::new()).timeout(Duration::from_millis(20));
+        let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+        mount.set_telemetry(Some(telemetry.clone()));
+
+        telemetry.event("start", Some("test"));
+        mount.ping().unwrap();
+        port.inject(Fault::Timeout);
+        assert!(mount.ping().is_err());
+
+        let lines = read(telemetry.path().unwrap()).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0].entry,
+            Entry::Event {
+                name: "start".into(),
+                detail: Some("test".into())
+            }
+        );
+        match &lines[1].entry {
+            Entry::Command {
+                request,
+                response,
+                error,
+                ..
+            } => {
+                assert_eq!(request, b"Kx");
+                assert_eq!(response, b"x#");
+                assert_eq!(*error, None);
+            }
+            entry => panic!("Expected a command, got {entry:?}"),
+        }
+        assert!(matches!(
+            &lines[2].entry,
+            Entry::Command { error: Some(_), .. }
+        ));
+        assert!(lines
+            .windows(2)
+            .all(|w| w[0].mono_micros <= w[1].mono_micros));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
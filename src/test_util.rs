@@ -0,0 +1,342 @@
+//! Protocol conformance harness.
+//!
+//! A [`Transcript`] scripts [`Mount`] calls on a [`CelestronMount`] together with the exact bytes a hand control
+//! exchanged for each of them and the result the call must return. Running it replays the responses through a
+//! [`ReplayPort`], so any change in how a command is encoded, or how a response is decoded, fails the transcript.
+//! [`golden`] returns the transcripts captured from real hand controls, which `cargo test` runs.
+//!
+//! Transcripts are text files; `#` starts a comment and bytes are written in hex:
+//!
+//! ```text
+//! # NexStar+ 5.35 on an Advanced VX.
+//! call goto_az_el 120 45
+//! > 6235353535353535352C3230303030303030
+//! < 23
+//! =
+//!
+//! call get_position_ra_dec
+//! > 65
+//! ! TimedOut
+//! = ! TimedOut
+//! ```
+//!
+//! Each `call` names a method and its arguments; enum arguments use their `Debug` names. It is followed by the
+//! bytes written (`>`), read (`<`), and read errors (`!`) in order, then the expected result after `=`: the
+//! result's fields separated by spaces, or `!` and the `io::ErrorKind` of the expected error. Numbers match within
+//! `1e-5`.
+
+use crate::mount::error::MountError;
+use crate::mount::session::{from_hex, parse_error_kind, EventKind, ReplayPort};
+use crate::mount::{
+    AzEl, CelestronMount, Model, Mount, NonGpsDevice, RADec, SlewAxis, SlewDir, SlewRate,
+    TrackingMode,
+};
+use std::fmt::Debug;
+use std::io;
+
+const TOLERANCE: f64 = 1e-5;
+
+/// Expected outcome of a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expect {
+    /// The call succeeds, returning these fields.
+    Ok(Vec<String>),
+    /// The call fails with this kind of error.
+    Err(io::ErrorKind),
+}
+
+/// One scripted call.
+#[derive(Debug, Clone)]
+pub struct Call {
+    /// Line of the `call` in the transcript.
+    pub line: usize,
+    pub method: String,
+    pub args: Vec<String>,
+    /// Bytes exchanged with the hand control.
+    pub events: Vec<EventKind>,
+    pub expect: Expect,
+}
+
+/// A scripted conversation with a hand control.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub name: String,
+    pub calls: Vec<Call>,
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl Transcript {
+    /// Parses a transcript, naming it `name` in errors.
+    pub fn parse(name: &str, text: &str) -> Result<Transcript, io::Error> {
+        let mut calls: Vec<Call> = Vec::new();
+        // Whether the last call still lacks its expected result.
+        let mut open = false;
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let lineno = idx + 1;
+            let bad = |what: &str| invalid(format!("{name}:{lineno}: {what}: {line:?}"));
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (tag, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let rest = rest.trim();
+
+            if tag == "call" {
+                if open {
+                    return Err(bad("Previous call has no expected result"));
+                }
+                let mut words = rest.split_whitespace().map(str::to_string);
+                let method = words.next().ok_or_else(|| bad("Missing method"))?;
+                calls.push(Call {
+                    line: lineno,
+                    method,
+                    args: words.collect(),
+                    events: Vec::new(),
+                    expect: Expect::Ok(Vec::new()),
+                });
+                open = true;
+                continue;
+            }
+
+            let call = match calls.last_mut() {
+                Some(call) if open => call,
+                _ => return Err(bad("Expected a call")),
+            };
+
+            match tag {
+                ">" => call.events.push(EventKind::Write(
+                    from_hex(rest).ok_or_else(|| bad("Invalid hex"))?,
+                )),
+                "<" => call.events.push(EventKind::Read(
+                    from_hex(rest).ok_or_else(|| bad("Invalid hex"))?,
+                )),
+                "!" => call.events.push(EventKind::Error(parse_error_kind(rest))),
+                "=" => {
+                    call.expect = match rest.strip_prefix('!') {
+                        Some(kind) => Expect::Err(parse_error_kind(kind.trim())),
+                        None => Expect::Ok(rest.split_whitespace().map(str::to_string).collect()),
+                    };
+                    open = false;
+                }
+                _ => return Err(bad("Unknown line")),
+            }
+        }
+
+        if open {
+            return Err(invalid(format!("{name}: Last call has no expected result")));
+        }
+
+        Ok(Transcript {
+            name: name.to_string(),
+            calls,
+        })
+    }
+
+    /// Runs every call against a fresh [`CelestronMount`], failing on the first call whose bytes or result differ
+    /// from the transcript.
+    pub fn run(&self) -> Result<(), io::Error> {
+        for call in &self.calls {
+            let fail = |what: String| {
+                invalid(format!(
+                    "{}:{}: {}: {what}",
+                    self.name, call.line, call.method
+                ))
+            };
+
+            let port = ReplayPort::scripted(call.events.iter().cloned());
+            let mut mount = CelestronMount::from_port(Box::new(port.clone()));
+
+            let got = dispatch(&mut mount, &call.method, &call.args).map_err(fail)?;
+            let matches = match (&call.expect, &got) {
+                (Expect::Ok(expected), Ok(fields)) => {
+                    expected.len() == fields.len()
+                        && expected.iter().zip(fields).all(|(e, f)| field_eq(e, f))
+                }
+                (Expect::Err(kind), Err(e)) => *kind == e.kind(),
+                _ => false,
+            };
+            if !matches {
+                return Err(fail(format!(
+                    "expected {}, got {}",
+                    describe_expect(&call.expect),
+                    describe_result(&got)
+                )));
+            }
+
+            if port.remaining() != 0 {
+                return Err(fail(format!(
+                    "{} scripted writes were never sent",
+                    port.remaining()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The transcripts captured from real hand controls.
+pub fn golden() -> Vec<Transcript> {
+    [
+        (
+            "nexstar_plus_5_35.transcript",
+            include_str!("test_util/nexstar_plus_5_35.transcript"),
+        ),
+        (
+            "nexstar_4_21.transcript",
+            include_str!("test_util/nexstar_4_21.transcript"),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, text)| Transcript::parse(name, text).expect("Invalid golden transcript"))
+    .collect()
+}
+
+fn field_eq(expected: &str, got: &str) -> bool {
+    match (expected.parse::<f64>(), got.parse::<f64>()) {
+        (Ok(e), Ok(g)) => (e - g).abs() <= TOLERANCE,
+        _ => expected == got,
+    }
+}
+
+fn describe_expect(expect: &Expect) -> String {
+    match expect {
+        Expect::Ok(fields) => format!("{fields:?}"),
+        Expect::Err(kind) => format!("! {kind:?}"),
+    }
+}
+
+fn describe_result(result: &Result<Vec<String>, MountError>) -> String {
+    match result {
+        Ok(fields) => format!("{fields:?}"),
+        Err(e) => format!("! {:?} ({e})", e.kind()),
+    }
+}
+
+/// Parses an argument by the `Debug` name of one of `values`.
+fn parse_enum<T: Debug + Copy>(values: &[T], arg: Option<&String>) -> Result<T, String> {
+    let arg = arg.ok_or("Missing argument")?;
+    values
+        .iter()
+        .find(|v| format!("{v:?}") == *arg)
+        .copied()
+        .ok_or_else(|| format!("Invalid argument {arg:?}"))
+}
+
+fn parse_num<T: std::str::FromStr>(arg: Option<&String>) -> Result<T, String> {
+    let arg = arg.ok_or("Missing argument")?;
+    arg.parse().map_err(|_| format!("Invalid argument {arg:?}"))
+}
+
+/// Calls `method` on `mount`. The outer error reports a malformed call rather than a failed one.
+fn dispatch(
+    mount: &mut CelestronMount,
+    method: &str,
+    args: &[String],
+) -> Result<Result<Vec<String>, MountError>, String> {
+    const AXES: [SlewAxis; 2] = [SlewAxis::RAAz, SlewAxis::DecEl];
+    const DIRS: [SlewDir; 2] = [SlewDir::Positive, SlewDir::Negative];
+    const MODES: [TrackingMode; 4] = [
+        TrackingMode::Off,
+        TrackingMode::AzEl,
+        TrackingMode::EQNorth,
+        TrackingMode::EQSouth,
+    ];
+    const DEVICES: [NonGpsDevice; 3] = [
+        NonGpsDevice::AzRaMotor,
+        NonGpsDevice::ElDecMotor,
+        NonGpsDevice::RtcUnit,
+    ];
+
+    let mut args = args.iter();
+    let mut arg = || args.next();
+    let unit = |r: Result<(), MountError>| r.map(|()| Vec::new());
+    let one = |s: String| vec![s];
+
+    let result = match method {
+        "get_position_ra_dec" => mount
+            .get_position_ra_dec()
+            .map(|p| vec![p.ra.to_string(), p.dec.to_string()]),
+        "get_position_az_el" => mount
+            .get_position_az_el()
+            .map(|p| vec![p.az.to_string(), p.el.to_string()]),
+        "goto_ra_dec" => {
+            let coord = RADec::new(parse_num(arg())?, parse_num(arg())?);
+            unit(mount.goto_ra_dec(coord))
+        }
+        "goto_az_el" => {
+            let coord = AzEl::new(parse_num(arg())?, parse_num(arg())?);
+            unit(mount.goto_az_el(coord))
+        }
+        "sync" => {
+            let coord = RADec::new(parse_num(arg())?, parse_num(arg())?);
+            unit(mount.sync(coord))
+        }
+        "get_tracking_mode" => mount.get_tracking_mode().map(|m| one(format!("{m:?}"))),
+        "set_tracking_mode" => unit(mount.set_tracking_mode(parse_enum(&MODES, arg())?)),
+        "slew_variable" => {
+            let (axis, dir) = (parse_enum(&AXES, arg())?, parse_enum(&DIRS, arg())?);
+            unit(mount.slew_variable(axis, dir, parse_num(arg())?))
+        }
+        "slew_fixed" => {
+            let (axis, dir) = (parse_enum(&AXES, arg())?, parse_enum(&DIRS, arg())?);
+            let rate = SlewRate::try_from(parse_num::<u8>(arg())?).map_err(|e| e.to_string())?;
+            unit(mount.slew_fixed(axis, dir, rate))
+        }
+        "stop_slew" => unit(mount.stop_slew(parse_enum(&AXES, arg())?)),
+        "get_time" => mount.get_time().map(|t| one(t.to_rfc3339())),
+        "get_version" => mount.get_version().map(one),
+        "get_device_version" => mount
+            .get_device_version(parse_enum(&DEVICES, arg())?)
+            .map(one),
+        "get_model" => mount.get_model().map(|m: Model| one(format!("{m:?}"))),
+        "is_aligned" => mount.is_aligned().map(|b| one(b.to_string())),
+        "goto_in_progress" => mount.goto_in_progress().map(|b| one(b.to_string())),
+        "cancel_goto" => unit(mount.cancel_goto()),
+        _ => return Err(format!("Unknown method {method:?}")),
+    };
+
+    match arg() {
+        Some(extra) => Err(format!("Unexpected argument {extra:?}")),
+        None => Ok(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn golden_transcripts() {
+        let transcripts = golden();
+        assert!(transcripts.len() >= 2);
+        for transcript in transcripts {
+            transcript.run().unwrap();
+        }
+    }
+
+    #[test]
+    fn reports_divergence() {
+        // The az/el goto opcode is 'b'; 'r' is the RA/dec goto.
+        let transcript = Transcript::parse(
+            "regression",
+            "# Wrong opcode.\ncall goto_az_el 120 45\n> 7235353535353535352C3230303030303030\n< 23\n=\n",
+        )
+        .unwrap();
+        let e = transcript.run().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(
+            e.to_string()
+                .starts_with("regression:2: goto_az_el: expected"),
+            "{e}"
+        );
+
+        assert!(Transcript::parse("unterminated", "call get_model\n> 6D\n").is_err());
+    }
+}
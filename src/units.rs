@@ -0,0 +1,302 @@
+//! How angles, rates, and times are shown to the user.
+//!
+//! The preference is process-wide: each application reads it from the `[display]` section of the
+//! [configuration](crate::config) and calls [`set`] once at startup, after which the `Display` impls of [`RADec`] and
+//! [`AzEl`], the GUI, and `nexctl` all follow it. Values are always stored in degrees and UTC; only their text
+//! changes.
+//!
+//! ```
+//! use nexlib::units::{AngleFormat, Units};
+//! use nexlib::RADec;
+//!
+//! let units = Units {
+//!     angles: AngleFormat::Sexagesimal,
+//!     ..Units::default()
+//! };
+//! assert_eq!(format!("{}", units.ra(187.5)), "12h30m00.0s");
+//! assert_eq!(format!("{:.1}", units.dec(-45.2583)), "-45°15'29.9\"");
+//!
+//! nexlib::units::set(units);
+//! assert_eq!(RADec::new(187.5, 2.0).to_string(), "(12h30m00.0s, +02°00'00\")");
+//! ```
+//!
+//! [`RADec`]: crate::RADec
+//! [`AzEl`]: crate::AzEl
+
+use chrono::{DateTime, TimeZone};
+use std::fmt;
+use std::sync::RwLock;
+
+/// How angles are written.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum AngleFormat {
+    /// Decimal degrees, e.g. `187.5°`.
+    #[default]
+    Decimal,
+    /// Right ascension in hours, minutes, and seconds, and other angles in degrees, arcminutes, and arcseconds, e.g.
+    /// `12h30m00.0s` and `+45°15'30"`.
+    Sexagesimal,
+}
+
+/// The unit of angular rates.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum RateUnit {
+    /// Arcseconds per second, as taken by [`Mount::slew_variable`](crate::mount::Mount::slew_variable).
+    #[default]
+    Arcsec,
+    /// Degrees per second.
+    Degrees,
+}
+
+/// How times of day are written.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClockFormat {
+    /// `21:05:09`.
+    #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "24h"))]
+    TwentyFourHour,
+    /// `09:05:09 PM`.
+    #[cfg_attr(feature = "serde", serde(rename = "12h"))]
+    TwelveHour,
+}
+
+/// A units preference.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Units {
+    pub angles: AngleFormat,
+    pub rates: RateUnit,
+    pub clock: ClockFormat,
+}
+
+static CURRENT: RwLock<Units> = RwLock::new(Units {
+    angles: AngleFormat::Decimal,
+    rates: RateUnit::Arcsec,
+    clock: ClockFormat::TwentyFourHour,
+});
+
+/// Sets the preference used by every `Display` impl in the process.
+pub fn set(units: Units) {
+    *CURRENT.write().unwrap() = units;
+}
+
+/// The preference set with [`set`], or the default.
+pub fn current() -> Units {
+    *CURRENT.read().unwrap()
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Kind {
+    /// Right ascension, written in hours when sexagesimal.
+    Hours,
+    /// Declination or elevation.
+    Signed,
+    /// Azimuth.
+    Unsigned,
+    /// Arcseconds per second.
+    Rate,
+}
+
+/// A value written according to a [`Units`] preference.
+///
+/// A precision, as in `{:.2}`, sets the number of decimals of the smallest unit. By default angles are written to
+/// about an arcsecond: four decimals of a degree, or one of a second of time.
+#[derive(Debug, Copy, Clone)]
+pub struct Formatted {
+    units: Units,
+    kind: Kind,
+    value: f64,
+}
+
+impl Units {
+    /// Formats a right ascension in degrees.
+    pub fn ra(&self, deg: f64) -> Formatted {
+        self.formatted(Kind::Hours, deg)
+    }
+
+    /// Formats a signed angle in degrees, such as a declination or an elevation.
+    pub fn dec(&self, deg: f64) -> Formatted {
+        self.formatted(Kind::Signed, deg)
+    }
+
+    /// Formats an azimuth in degrees.
+    pub fn az(&self, deg: f64) -> Formatted {
+        self.formatted(Kind::Unsigned, deg)
+    }
+
+    /// Formats an angular rate in arcseconds per second.
+    pub fn rate(&self, arcsec_per_sec: f64) -> Formatted {
+        self.formatted(Kind::Rate, arcsec_per_sec)
+    }
+
+    fn formatted(&self, kind: Kind, value: f64) -> Formatted {
+        Formatted {
+            units: *self,
+            kind,
+            value,
+        }
+    }
+
+    /// Formats a time of day in its own time zone.
+    pub fn time<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        match self.clock {
+            ClockFormat::TwentyFourHour => time.format("%H:%M:%S").to_string(),
+            ClockFormat::TwelveHour => time.format("%I:%M:%S %p").to_string(),
+        }
+    }
+
+    /// Parses a right ascension typed by the user, into degrees.
+    ///
+    /// Accepts decimal degrees, or hours, minutes, and seconds separated by `h`, `m`, `s`, colons, or spaces, whatever
+    /// the preference.
+    pub fn parse_ra(&self, s: &str) -> Option<f64> {
+        let s = s.trim();
+        if s.contains(['h', ':', ' ']) {
+            parse_sexagesimal(s).map(|hours| hours * 15.0)
+        } else {
+            s.trim_end_matches('°').parse().ok()
+        }
+    }
+
+    /// Parses an angle typed by the user, into degrees.
+    ///
+    /// Accepts decimal degrees, or degrees, arcminutes, and arcseconds separated by `°`, `'`, `"`, colons, or spaces,
+    /// whatever the preference.
+    pub fn parse_dec(&self, s: &str) -> Option<f64> {
+        parse_sexagesimal(s)
+    }
+}
+
+/// Parses up to three fields, e.g. `-45:15:30` or `12h30m`, into a value in units of the first.
+fn parse_sexagesimal(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let fields = s
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter(|f| !f.is_empty())
+        .map(str::parse::<f64>)
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    if fields.is_empty() || fields.len() > 3 {
+        return None;
+    }
+
+    let value = fields
+        .iter()
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(v, scale)| v / scale)
+        .sum::<f64>();
+    Some(sign * value)
+}
+
+/// Writes `value` as three fields, the last with `decimals` decimals, rounding before splitting so `59.96` seconds
+/// carries into the minutes. The first field wraps at `wrap`, if given, so rounding never gives `24h`.
+fn write_sexagesimal(
+    f: &mut fmt::Formatter,
+    value: f64,
+    decimals: usize,
+    wrap: Option<f64>,
+    separators: [&str; 3],
+) -> fmt::Result {
+    let scale = 10f64.powi(decimals as i32);
+    let ticks = (value.abs() * 3600.0 * scale).round();
+    let whole = (ticks / (3600.0 * scale)).floor();
+    let minutes = ((ticks - whole * 3600.0 * scale) / (60.0 * scale)).floor();
+    let seconds = (ticks - whole * 3600.0 * scale - minutes * 60.0 * scale) / scale;
+    let whole = wrap.map_or(whole, |wrap| whole % wrap);
+
+    let width = if decimals > 0 { decimals + 3 } else { 2 };
+    write!(
+        f,
+        "{:02}{}{:02}{}{:0width$.decimals$}{}",
+        whole, separators[0], minutes, separators[1], seconds, separators[2]
+    )
+}
+
+impl fmt::Display for Formatted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.value;
+        match (self.kind, self.units.angles, self.units.rates) {
+            (Kind::Rate, _, RateUnit::Arcsec) => {
+                write!(f, "{:.*}\"/s", f.precision().unwrap_or(1), value)
+            }
+            (Kind::Rate, _, RateUnit::Degrees) => {
+                write!(f, "{:.*}°/s", f.precision().unwrap_or(4), value / 3600.0)
+            }
+            (_, AngleFormat::Decimal, _) => write!(f, "{:.*}°", f.precision().unwrap_or(4), value),
+            (Kind::Hours, AngleFormat::Sexagesimal, _) => write_sexagesimal(
+                f,
+                value.rem_euclid(360.0) / 15.0,
+                f.precision().unwrap_or(1),
+                Some(24.0),
+                ["h", "m", "s"],
+            ),
+            (Kind::Unsigned, AngleFormat::Sexagesimal, _) => write_sexagesimal(
+                f,
+                value.rem_euclid(360.0),
+                f.precision().unwrap_or(0),
+                Some(360.0),
+                ["°", "'", "\""],
+            ),
+            (_, AngleFormat::Sexagesimal, _) => {
+                f.write_str(if value < 0.0 { "-" } else { "+" })?;
+                write_sexagesimal(f, value, f.precision().unwrap_or(0), None, ["°", "'", "\""])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn formats_values() {
+        let decimal = Units::default();
+        assert_eq!(format!("{:.2}", decimal.ra(187.5)), "187.50°");
+        assert_eq!(decimal.dec(-1.0).to_string(), "-1.0000°");
+        assert_eq!(decimal.rate(15.0).to_string(), "15.0\"/s");
+
+        let sexagesimal = Units {
+            angles: AngleFormat::Sexagesimal,
+            rates: RateUnit::Degrees,
+            clock: ClockFormat::TwelveHour,
+        };
+        // Rounds up into the next day.
+        assert_eq!(sexagesimal.ra(359.99999).to_string(), "00h00m00.0s");
+        assert_eq!(sexagesimal.ra(-15.0).to_string(), "23h00m00.0s");
+        assert_eq!(sexagesimal.dec(-0.5).to_string(), "-00°30'00\"");
+        assert_eq!(format!("{:.1}", sexagesimal.az(270.25)), "270°15'00.0\"");
+        assert_eq!(format!("{:.3}", sexagesimal.rate(36.0)), "0.010°/s");
+
+        let time = Utc.with_ymd_and_hms(2024, 3, 20, 21, 5, 9).unwrap();
+        assert_eq!(decimal.time(&time), "21:05:09");
+        assert_eq!(sexagesimal.time(&time), "09:05:09 PM");
+    }
+
+    #[test]
+    fn parses_input() {
+        let units = Units::default();
+        assert_eq!(units.parse_ra("187.5"), Some(187.5));
+        assert_eq!(units.parse_ra("12h30m"), Some(187.5));
+        assert_eq!(units.parse_ra("12:30:00"), Some(187.5));
+        assert_eq!(units.parse_dec("-45°15'36\""), Some(-45.26));
+        assert_eq!(units.parse_dec("+10 30"), Some(10.5));
+        assert_eq!(units.parse_dec("north"), None);
+        assert_eq!(units.parse_dec("1:2:3:4"), None);
+    }
+}
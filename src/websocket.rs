@@ -0,0 +1,344 @@
+//! WebSocket endpoint streaming mount position, status, and events as JSON.
+//!
+//! A single poller thread queries the mount at the configured interval and broadcasts every sample to all connected
+//! clients, so the serial traffic does not grow with the number of browser dashboards attached. Use
+//! [`WebSocketServer::serve`] with a [`StatusCache`] shared with other frontends to bound it across all of them.
+//!
+//! Every frame is a JSON object with a `type` field of `position`, `status`, or `event`:
+//!
+//! ```json
+//! {"type":"position","unix_millis":1700000000000,"ra":83.8,"dec":-5.4,"az":120.1,"el":35.2}
+//! {"type":"status","unix_millis":1700000000000,"tracking_mode":"EQNorth","goto_in_progress":false}
+//! {"type":"event","unix_millis":1700000000000,"event":"goto_finished","message":null}
+//! ```
+
+use crate::mount::status::{MountStatus, StatusCache};
+use crate::mount::{Mount, TrackingMode};
+use serde::Serialize;
+use std::io;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+/// Default time between samples.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest interval accepted, to keep the serial link from being saturated by status polling.
+pub const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest wait for a client's upgrade request, so a client which connects and says nothing only ties up its own
+/// thread.
+pub(crate) const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Changes in mount state detected between two samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    GotoStarted,
+    GotoFinished,
+    TrackingChanged,
+    Error,
+}
+
+/// A single frame sent to clients.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage {
+    Position {
+        unix_millis: i64,
+        ra: f64,
+        dec: f64,
+        az: f64,
+        el: f64,
+    },
+    Status {
+        unix_millis: i64,
+        tracking_mode: String,
+        goto_in_progress: bool,
+    },
+    Event {
+        unix_millis: i64,
+        event: EventKind,
+        message: Option<String>,
+    },
+}
+
+impl StreamMessage {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Stream messages are always serializable.")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Status {
+    tracking_mode: TrackingMode,
+    goto_in_progress: bool,
+}
+
+/// Determines which events occurred between the previous and current status samples.
+fn status_events(prev: Option<Status>, curr: Status) -> Vec<EventKind> {
+    let mut events = Vec::new();
+
+    let Some(prev) = prev else {
+        return events;
+    };
+
+    if !prev.goto_in_progress && curr.goto_in_progress {
+        events.push(EventKind::GotoStarted);
+    }
+    if prev.goto_in_progress && !curr.goto_in_progress {
+        events.push(EventKind::GotoFinished);
+    }
+    if prev.tracking_mode != curr.tracking_mode {
+        events.push(EventKind::TrackingChanged);
+    }
+
+    events
+}
+
+/// Converts a status sample into the messages to broadcast.
+fn sample(status: MountStatus, prev: &mut Option<Status>) -> Vec<StreamMessage> {
+    let MountStatus { ra_dec, az_el, .. } = status;
+    let curr = Status {
+        tracking_mode: status.tracking_mode,
+        goto_in_progress: status.goto_in_progress,
+    };
+    let unix_millis = status.time.timestamp_millis();
+
+    let mut messages = vec![
+        StreamMessage::Position {
+            unix_millis,
+            ra: ra_dec.ra,
+            dec: ra_dec.dec,
+            az: az_el.az,
+            el: az_el.el,
+        },
+        StreamMessage::Status {
+            unix_millis,
+            tracking_mode: format!("{:?}", curr.tracking_mode),
+            goto_in_progress: curr.goto_in_progress,
+        },
+    ];
+
+    messages.extend(
+        status_events(*prev, curr)
+            .into_iter()
+            .map(|event| StreamMessage::Event {
+                unix_millis,
+                event,
+                message: None,
+            }),
+    );
+
+    *prev = Some(curr);
+
+    messages
+}
+
+pub(crate) type Clients = Arc<Mutex<Vec<Sender<Arc<str>>>>>;
+
+/// Sends `msg` to every client, forgetting clients whose connection has closed.
+fn broadcast(clients: &Clients, msg: &StreamMessage) {
+    let json: Arc<str> = msg.to_json().into();
+    clients
+        .lock()
+        .unwrap()
+        .retain(|client| client.send(Arc::clone(&json)).is_ok());
+}
+
+pub(crate) fn poll_mount<M: Mount>(cache: StatusCache<M>, clients: Clients, interval: Duration) {
+    let mut prev = None;
+
+    loop {
+        match cache.get() {
+            Ok(status) => sample(status, &mut prev)
+                .iter()
+                .for_each(|msg| broadcast(&clients, msg)),
+            Err(e) => broadcast(
+                &clients,
+                &StreamMessage::Event {
+                    unix_millis: chrono::Utc::now().timestamp_millis(),
+                    event: EventKind::Error,
+                    message: Some(e.to_string()),
+                },
+            ),
+        }
+
+        thread::sleep(interval);
+    }
+}
+
+/// Forwards broadcast frames to a single client until either side disconnects.
+fn serve_client(mut socket: WebSocket<TcpStream>, rx: Receiver<Arc<str>>) {
+    // Reads only need to pick up close and ping frames between broadcasts, so they should never block for long.
+    if let Err(e) = socket
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(1)))
+    {
+        log::warn!(
+            "[{}:{}] Failed to configure client socket: {:?}",
+            file!(),
+            line!(),
+            e
+        );
+        return;
+    }
+
+    for json in rx {
+        if socket.send(Message::text(&*json)).is_err() {
+            return;
+        }
+
+        match socket.read() {
+            Ok(Message::Close(_)) => return,
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => return,
+        }
+    }
+}
+
+/// Streams the state of a mount to any number of WebSocket clients.
+pub struct WebSocketServer {
+    listener: TcpListener,
+    interval: Duration,
+}
+
+impl WebSocketServer {
+    /// Listens for WebSocket connections on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<WebSocketServer, io::Error> {
+        Ok(WebSocketServer {
+            listener: TcpListener::bind(addr)?,
+            interval: DEFAULT_INTERVAL,
+        })
+    }
+
+    /// Sets the time between samples. Values below [`MIN_INTERVAL`] are raised to it.
+    pub fn interval(mut self, interval: Duration) -> WebSocketServer {
+        self.interval = interval.max(MIN_INTERVAL);
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr, io::Error> {
+        self.listener.local_addr()
+    }
+
+    /// Polls `mount` and serves clients until the listener fails.
+    pub fn run<M: Mount + Send + 'static>(self, mount: Arc<Mutex<M>>) -> Result<(), io::Error> {
+        let cache = StatusCache::new(mount, self.interval);
+        self.serve(cache)
+    }
+
+    /// Polls the mount through `cache` and serves clients until the listener fails.
+    ///
+    /// Samples are sent at the server's interval, but the mount is only queried as often as the cache allows.
+    pub fn serve<M: Mount + Send + 'static>(self, cache: StatusCache<M>) -> Result<(), io::Error> {
+        let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+
+        {
+            let clients = Arc::clone(&clients);
+            let interval = self.interval;
+            thread::spawn(move || poll_mount(cache, clients, interval));
+        }
+
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("[{}:{}] Failed to accept client: {:?}", file!(), line!(), e);
+                    continue;
+                }
+            };
+
+            let clients = Arc::clone(&clients);
+            thread::spawn(move || {
+                if let Err(e) = stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)) {
+                    log::warn!("[{}:{}] Failed to set timeout: {:?}", file!(), line!(), e);
+                    return;
+                }
+                let socket = match tungstenite::accept(stream) {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        log::warn!(
+                            "[{}:{}] WebSocket handshake failed: {:?}",
+                            file!(),
+                            line!(),
+                            e
+                        );
+                        return;
+                    }
+                };
+
+                let (tx, rx) = mpsc::channel();
+                clients.lock().unwrap().push(tx);
+                serve_client(socket, rx);
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_json() {
+        let msg = StreamMessage::Position {
+            unix_millis: 1,
+            ra: 2.0,
+            dec: -3.5,
+            az: 4.0,
+            el: 5.0,
+        };
+        assert_eq!(
+            msg.to_json(),
+            r#"{"type":"position","unix_millis":1,"ra":2.0,"dec":-3.5,"az":4.0,"el":5.0}"#
+        );
+    }
+
+    #[test]
+    fn event_json() {
+        let msg = StreamMessage::Event {
+            unix_millis: 1,
+            event: EventKind::GotoFinished,
+            message: None,
+        };
+        assert_eq!(
+            msg.to_json(),
+            r#"{"type":"event","unix_millis":1,"event":"goto_finished","message":null}"#
+        );
+    }
+
+    #[test]
+    fn goto_and_tracking_events() {
+        let idle = Status {
+            tracking_mode: TrackingMode::EQNorth,
+            goto_in_progress: false,
+        };
+        let slewing = Status {
+            goto_in_progress: true,
+            ..idle
+        };
+        let stopped = Status {
+            tracking_mode: TrackingMode::Off,
+            ..idle
+        };
+
+        assert!(status_events(None, slewing).is_empty());
+        assert_eq!(status_events(Some(idle), slewing), [EventKind::GotoStarted]);
+        assert_eq!(
+            status_events(Some(slewing), stopped),
+            [EventKind::GotoFinished, EventKind::TrackingChanged]
+        );
+        assert!(status_events(Some(idle), idle).is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+//! Runs `nexctl` against a simulated mount served over TCP, as a hand control behind a WiFi module is.
+
+use nexlib::mount::sim::SimPort;
+use nexlib::mount::{Mount, SimMount};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::thread;
+
+/// Serves `port` on a local TCP port, returning its address.
+fn serve(port: SimPort) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for socket in listener.incoming() {
+            let (mut socket, mut port) = (socket.unwrap(), port.clone());
+            thread::spawn(move || {
+                // Commands are written whole and answered before the next, so each read is one command.
+                let mut buf = [0; 64];
+                while let Ok(n @ 1..) = socket.read(&mut buf) {
+                    port.write_all(&buf[..n]).unwrap();
+                    while let Ok(n @ 1..) = port.read(&mut buf) {
+                        socket.write_all(&buf[..n]).unwrap();
+                    }
+                }
+            });
+        }
+    });
+    addr
+}
+
+/// Runs `nexctl` with `args` and a configuration file reaching the mount at `addr`.
+fn nexctl(name: &str, addr: &str, args: &[&str]) -> Output {
+    let config = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.toml"));
+    std::fs::write(
+        &config,
+        format!(
+            "[site]\nlatitude = 45.0\nlongitude = 0.0\n\n[limits]\nmin_elevation = 20.0\n\n\
+             [serial]\nport = \"{addr}\"\n"
+        ),
+    )
+    .unwrap();
+    Command::new(env!("CARGO_BIN_EXE_nexctl"))
+        .args(args)
+        .env("NEXLIB_CONFIG", &config)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn goto_respects_limits() {
+    let port = SimPort::new(SimMount::new());
+    let addr = serve(port.clone());
+
+    // Never more than 35° below the horizon from latitude 45°.
+    let res = nexctl("goto_below", &addr, &["goto", "--ra", "0", "--dec", "-80"]);
+    let stderr = String::from_utf8_lossy(&res.stderr);
+    assert!(!res.status.success());
+    assert!(stderr.contains("Elevation"), "{stderr}");
+    assert!(!port.mount().goto_in_progress().unwrap());
+
+    // Always 44° up or more.
+    let res = nexctl("goto_pole", &addr, &["goto", "--ra", "0", "--dec", "89"]);
+    assert!(
+        res.status.success(),
+        "{}",
+        String::from_utf8_lossy(&res.stderr)
+    );
+    assert!(port.mount().goto_in_progress().unwrap());
+}